@@ -30,4 +30,15 @@ contract XnetMessagingFacet is GatewayActorModifiers {
         LibGateway.applyTopDownMessages(s.networkName.getParentSubnet(), crossMsgs);
         LibGateway.propagateAllPostboxMessages();
     }
+
+    /// @notice Skips a top-down cross-net message that reverted when applied with `applyCrossMessages`, consuming
+    ///         its nonce and sending a `SystemErr` receipt back to the sender. This is invoked by IPC nodes so that
+    ///         a single failing message does not block the top-down messages queued after it.
+    /// @dev It requires the caller to be the system actor.
+    /// @param crossMsg The cross-network message that failed to apply.
+    /// @param reason The revert data of the failed application.
+    function rejectCrossMessage(IpcEnvelope calldata crossMsg, bytes calldata reason) external systemActorOnly {
+        LibGateway.rejectTopDownMessage(crossMsg, reason);
+        LibGateway.propagateAllPostboxMessages();
+    }
 }
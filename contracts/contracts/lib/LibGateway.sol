@@ -287,6 +287,25 @@ library LibGateway {
         }
     }
 
+    /// @notice skips a top-down message whose application reverted, so that it does not block the ones after it.
+    /// The nonce of the message is consumed and, if the message genuinely travelled top-down, a `SystemErr`
+    /// receipt carrying the revert data is sent back to the sender, returning the value of the message.
+    /// @param crossMsg - the top-down message that failed to apply
+    /// @param reason - the revert data of the failed application
+    function rejectTopDownMessage(IpcEnvelope memory crossMsg, bytes memory reason) internal {
+        GatewayActorStorage storage s = LibGatewayActorStorage.appStorage();
+
+        if (s.appliedTopDownNonce != crossMsg.localNonce) {
+            revert InvalidXnetMessage(InvalidXnetMessageReason.Nonce);
+        }
+        s.appliedTopDownNonce += 1;
+
+        // A message that is not top-down from our point of view has no route back to its sender.
+        if (crossMsg.applyType(s.networkName) == IPCMsgType.TopDown) {
+            sendReceipt(crossMsg, OutcomeType.SystemErr, reason);
+        }
+    }
+
     /// @notice executes a cross message if its destination is the current network, otherwise adds it to the postbox to be propagated further
     /// This function assumes that the relevant funds have been already minted or burnt
     /// when the top-down or bottom-up messages have been queued for execution.
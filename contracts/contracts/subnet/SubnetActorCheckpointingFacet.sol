@@ -3,7 +3,7 @@ pragma solidity ^0.8.23;
 
 import {LightHeader, CanonicalVote} from "tendermint-sol/proto/TendermintLight.sol";
 
-import {BottomUpCheckpointAlreadySubmitted, InvalidCheckpointEpoch} from "../errors/IPCErrors.sol";
+import {BottomUpCheckpointAlreadySubmitted, InvalidCheckpointEpoch, NotValidator, SignatureAddressesNotSorted} from "../errors/IPCErrors.sol";
 import {IGateway} from "../interfaces/IGateway.sol";
 import {BottomUpMsgBatch, BottomUpMsgBatchInfo} from "../structs/CrossNet.sol";
 import {Validator, ValidatorSet, SubnetID} from "../structs/Subnet.sol";
@@ -17,6 +17,7 @@ import {Pausable} from "../lib/LibPausable.sol";
 import {LibGateway} from "../lib/LibGateway.sol";
 import {LibActivity} from "../lib/LibActivity.sol";
 import {LibBottomUpBatch} from "../lib/LibBottomUpBatch.sol";
+import {MultisignatureChecker} from "../lib/LibMultisignatureChecker.sol";
 import {BottomUpBatch} from "../structs/BottomUpBatch.sol";
 import {IpcEnvelope} from "../structs/CrossNet.sol";
 import {CompressedActivityRollup} from "../structs/Activity.sol";
@@ -28,6 +29,7 @@ import {CometbftLightClient, ValidatorSignPayload, ValidatorCertificate, AppHash
 ///
 /// This facet is responsible for:
 /// - Verifying and storing CometBFT signed headers with BFT consensus validation
+/// - Alternatively, verifying app hashes certified by a quorum of validator signatures gossiped in the subnet
 /// - Recording activity rollups from subnet validators
 /// - Confirming validator set changes
 /// - Executing bottom-up message batches with Merkle proof verification
@@ -37,6 +39,7 @@ contract SubnetActorCheckpointingFacet is ISubnetActorCheckpointing, ReentrancyG
     using LibValidatorSet for ValidatorSet;
 
     error AppHashNotEqual();
+    error InvalidCheckpointSignatures(MultisignatureChecker.Error reason);
 
     /// @inheritdoc ISubnetActorCheckpointing
     function lastBottomUpCheckpointHeight() external view returns (uint256) {
@@ -81,6 +84,68 @@ contract SubnetActorCheckpointingFacet is ISubnetActorCheckpointing, ReentrancyG
         checkpointStorage.lastBottomUpCheckpointHeight = height;
     }
 
+    /// @notice Submits a bottom-up checkpoint certified by the signatures validators gossiped
+    /// among themselves, instead of a CometBFT signed header.
+    /// @dev Each signature is an ECDSA signature over `checkpointDigest(height, appHash)` in
+    /// `({bytes32 r}{bytes32 s}{uint8 v})` format. The signatories must be active validators,
+    /// sorted in ascending order, and their current power must add up to more than 2/3 of the total.
+    /// The app hash breakdown is recorded separately with `recordAppHashBreakdown`, as usual.
+    function submitBottomUpCheckpointSignatures(
+        uint64 height,
+        bytes32 appHash,
+        address[] calldata signatories,
+        bytes[] calldata signatures
+    ) external whenNotPaused {
+        SubnetActorCheckpointingStorage storage checkpointStorage = LibCheckpointingStorage.getStorage();
+
+        ensureValidHeight(height, checkpointStorage.lastBottomUpCheckpointHeight);
+        verifySignatureQuorum(checkpointDigest(height, appHash), signatories, signatures);
+
+        checkpointStorage.appHash[height] = abi.encodePacked(appHash);
+        checkpointStorage.lastBottomUpCheckpointHeight = height;
+    }
+
+    /// @notice The digest validators sign to certify the app hash of the subnet at a checkpoint height.
+    function checkpointDigest(uint64 height, bytes32 appHash) public view returns (bytes32) {
+        return keccak256(abi.encode(address(this), height, appHash));
+    }
+
+    function verifySignatureQuorum(
+        bytes32 digest,
+        address[] calldata signatories,
+        bytes[] calldata signatures
+    ) internal view {
+        uint256 len = signatories.length;
+        uint256[] memory weights = new uint256[](len);
+
+        for (uint256 i = 0; i < len; ) {
+            // sorting rules out counting the same validator twice
+            if (i > 0 && signatories[i] <= signatories[i - 1]) {
+                revert SignatureAddressesNotSorted();
+            }
+            if (!LibPower.isActiveValidator(signatories[i])) {
+                revert NotValidator(signatories[i]);
+            }
+            weights[i] = LibPower.getCurrentPower(signatories[i]);
+            unchecked {
+                i++;
+            }
+        }
+
+        uint256 threshold = (LibPower.getTotalCurrentPower() * 2) / 3 + 1;
+
+        (bool valid, MultisignatureChecker.Error err) = MultisignatureChecker.isValidWeightedMultiSignature({
+            signatories: signatories,
+            weights: weights,
+            threshold: threshold,
+            hash: digest,
+            signatures: signatures
+        });
+        if (!valid) {
+            revert InvalidCheckpointSignatures(err);
+        }
+    }
+
     /// @dev The app hash is the hash of AppHashBreakdown.
     /// The app hash break down is the aggregate of commitments for configuration number or message batch root.
     /// It's not submitted together with `submitBottomUpCheckpoint` for gas considerations.
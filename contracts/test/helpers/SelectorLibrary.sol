@@ -62,7 +62,7 @@ library SelectorLibrary {
         if (keccak256(abi.encodePacked(facetName)) == keccak256(abi.encodePacked("XnetMessagingFacet"))) {
             return
                 abi.decode(
-                    hex"00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000001007cf1ec00000000000000000000000000000000000000000000000000000000",
+                    hex"00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000002007cf1ec00000000000000000000000000000000000000000000000000000000b5fbe58200000000000000000000000000000000000000000000000000000000",
                     (bytes4[])
                 );
         }
@@ -97,7 +97,7 @@ library SelectorLibrary {
         if (keccak256(abi.encodePacked(facetName)) == keccak256(abi.encodePacked("SubnetActorCheckpointingFacet"))) {
             return
                 abi.decode(
-                    hex"0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000000738930dba00000000000000000000000000000000000000000000000000000000e6da1bd00000000000000000000000000000000000000000000000000000000072d0a0e000000000000000000000000000000000000000000000000000000000941f561300000000000000000000000000000000000000000000000000000000fe305eac00000000000000000000000000000000000000000000000000000000577f3af900000000000000000000000000000000000000000000000000000000042cd10300000000000000000000000000000000000000000000000000000000",
+                    hex"00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000009fe9a87e10000000000000000000000000000000000000000000000000000000038930dba00000000000000000000000000000000000000000000000000000000e6da1bd00000000000000000000000000000000000000000000000000000000072d0a0e000000000000000000000000000000000000000000000000000000000941f561300000000000000000000000000000000000000000000000000000000fe305eac00000000000000000000000000000000000000000000000000000000577f3af900000000000000000000000000000000000000000000000000000000c2cffbf900000000000000000000000000000000000000000000000000000000042cd10300000000000000000000000000000000000000000000000000000000",
                     (bytes4[])
                 );
         }
@@ -132,7 +132,7 @@ library SelectorLibrary {
         if (keccak256(abi.encodePacked(facetName)) == keccak256(abi.encodePacked("SubnetActorCheckpointFacetMock"))) {
             return
                 abi.decode(
-                    hex"00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000009933c6a6d0000000000000000000000000000000000000000000000000000000038930dba00000000000000000000000000000000000000000000000000000000c95e892400000000000000000000000000000000000000000000000000000000e6da1bd00000000000000000000000000000000000000000000000000000000072d0a0e000000000000000000000000000000000000000000000000000000000941f561300000000000000000000000000000000000000000000000000000000fe305eac00000000000000000000000000000000000000000000000000000000577f3af900000000000000000000000000000000000000000000000000000000042cd10300000000000000000000000000000000000000000000000000000000",
+                    hex"0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000000bfe9a87e100000000000000000000000000000000000000000000000000000000933c6a6d0000000000000000000000000000000000000000000000000000000038930dba00000000000000000000000000000000000000000000000000000000c95e892400000000000000000000000000000000000000000000000000000000e6da1bd00000000000000000000000000000000000000000000000000000000072d0a0e000000000000000000000000000000000000000000000000000000000941f561300000000000000000000000000000000000000000000000000000000fe305eac00000000000000000000000000000000000000000000000000000000577f3af900000000000000000000000000000000000000000000000000000000c2cffbf900000000000000000000000000000000000000000000000000000000042cd10300000000000000000000000000000000000000000000000000000000",
                     (bytes4[])
                 );
         }
@@ -67,6 +67,33 @@ contract L2GatewayActorDiamondTest is Test, L2GatewayActorDiamond {
         vm.stopPrank();
     }
 
+    function testGatewayDiamond_RejectCrossMessage_ConsumesNonceAndSendsReceipt() public {
+        address sender = vm.addr(102);
+        SubnetID memory id = gatewayDiamond.getter().getNetworkName();
+
+        IpcEnvelope memory crossMsg = TestUtils.newXnetCallMsg(
+            IPCAddress({subnetId: id.getParentSubnet(), rawAddress: FvmAddressHelper.from(sender)}),
+            IPCAddress({subnetId: id, rawAddress: FvmAddressHelper.from(address(this))}),
+            0,
+            0
+        );
+
+        vm.startPrank(FilAddress.SYSTEM_ACTOR);
+
+        // Only the next nonce can be rejected.
+        crossMsg.localNonce = 1;
+        vm.expectRevert(abi.encodeWithSelector(InvalidXnetMessage.selector, InvalidXnetMessageReason.Nonce));
+        gatewayDiamond.xnetMessenger().rejectCrossMessage(crossMsg, EMPTY_BYTES);
+
+        crossMsg.localNonce = 0;
+        gatewayDiamond.xnetMessenger().rejectCrossMessage(crossMsg, EMPTY_BYTES);
+
+        vm.stopPrank();
+
+        require(gatewayDiamond.getter().appliedTopDownNonce() == 1, "nonce not consumed");
+        require(gatewayDiamond.getter().bottomUpNonce() == 1, "receipt not sent");
+    }
+
     function callback() public view {}
 
     function collateralSource() external pure returns (Asset memory supply) {
@@ -28,6 +28,7 @@ import {OwnershipFacet} from "../../contracts/OwnershipFacet.sol";
 import {SubnetActorGetterFacet} from "../../contracts/subnet/SubnetActorGetterFacet.sol";
 import {SubnetActorPauseFacet} from "../../contracts/subnet/SubnetActorPauseFacet.sol";
 import {SubnetActorCheckpointFacetMock} from "../mocks/SubnetActorCheckpointFacetMock.sol";
+import {SubnetActorCheckpointingFacet} from "../../contracts/subnet/SubnetActorCheckpointingFacet.sol";
 import {SubnetActorRewardFacet} from "../../contracts/subnet/SubnetActorRewardFacet.sol";
 import {DiamondCutFacet} from "../../contracts/diamond/DiamondCutFacet.sol";
 import {FilAddress} from "fevmate/contracts/utils/FilAddress.sol";
@@ -157,6 +158,68 @@ contract SubnetBottomUpCheckpointTest is Test, IntegrationTestBase {
         saDiamond.checkpointer().submitBottomUpCheckpoint(abi.encode(lightHeader, certificate, voteTemplate));
     }
 
+    function testSubnetActorDiamond_submitCheckpointSignatures() public {
+        SubnetActorDiamond.ConstructorParams memory params = defaultSubnetActorParamsWith(address(gatewayDiamond));
+        params.bottomUpCheckPeriod = 9;
+        params.minValidators = 3;
+        saDiamond = createSubnetActor(params);
+        gatewayDiamond.manager().approveSubnet(address(saDiamond));
+
+        (address[] memory validators, uint256[] memory keys, bytes[] memory publicKeys) = TestUtils.newValidators(3);
+        for (uint256 i = 0; i < 3; i++) {
+            vm.deal(validators[i], 10 ether);
+            vm.prank(validators[i]);
+            saDiamond.manager().join{value: 10 ether}(publicKeys[i], 10 ether);
+        }
+
+        uint64 height = 9;
+        bytes32 appHash = keccak256("app hash");
+        bytes32 digest = saDiamond.checkpointer().checkpointDigest(height, appHash);
+
+        bytes[] memory signatures = new bytes[](3);
+        for (uint256 i = 0; i < 3; i++) {
+            (uint8 v, bytes32 r, bytes32 s) = vm.sign(keys[i], digest);
+            signatures[i] = abi.encodePacked(r, s, v);
+        }
+
+        // two out of three equal validators is not more than 2/3 of the power
+        address[] memory someValidators = new address[](2);
+        bytes[] memory someSignatures = new bytes[](2);
+        for (uint256 i = 0; i < 2; i++) {
+            someValidators[i] = validators[i];
+            someSignatures[i] = signatures[i];
+        }
+        vm.expectRevert(
+            abi.encodeWithSelector(
+                SubnetActorCheckpointingFacet.InvalidCheckpointSignatures.selector,
+                MultisignatureChecker.Error.WeightsSumLessThanThreshold
+            )
+        );
+        saDiamond.checkpointer().submitBottomUpCheckpointSignatures(height, appHash, someValidators, someSignatures);
+
+        // the same validator cannot be counted twice
+        someValidators[1] = validators[0];
+        someSignatures[1] = signatures[0];
+        vm.expectRevert(SignatureAddressesNotSorted.selector);
+        saDiamond.checkpointer().submitBottomUpCheckpointSignatures(height, appHash, someValidators, someSignatures);
+
+        // signatures over a different app hash are rejected
+        vm.expectRevert(
+            abi.encodeWithSelector(
+                SubnetActorCheckpointingFacet.InvalidCheckpointSignatures.selector,
+                MultisignatureChecker.Error.InvalidSignatory
+            )
+        );
+        saDiamond.checkpointer().submitBottomUpCheckpointSignatures(height, keccak256("other"), validators, signatures);
+
+        saDiamond.checkpointer().submitBottomUpCheckpointSignatures(height, appHash, validators, signatures);
+
+        require(saDiamond.checkpointer().lastBottomUpCheckpointHeight() == height, "checkpoint height");
+
+        vm.expectRevert(BottomUpCheckpointAlreadySubmitted.selector);
+        saDiamond.checkpointer().submitBottomUpCheckpointSignatures(height, appHash, validators, signatures);
+    }
+
     function testSubnetActorDiamond_checkBitMap() public {
         // 10000011
         uint256 bitmap = 131;
@@ -3,7 +3,7 @@
 
 use std::path::PathBuf;
 
-use crate::parse::parse_eth_address;
+use crate::parse::{parse_actor_address, parse_eth_address};
 use clap::{Args, Subcommand};
 use fvm_shared::address::Address;
 use ipc_api::subnet_id::SubnetID;
@@ -21,6 +21,24 @@ pub enum DebugCommands {
         #[command(subcommand)]
         command: DebugIpcCommands,
     },
+    /// Inspect the evidence of misbehaving validators collected by the node.
+    ///
+    /// The evidence is only stored locally; it is not submitted to the parent subnet.
+    Evidence(DebugEvidenceArgs),
+    /// Re-execute a committed block on the state before it and compare the outcome
+    /// with what CometBFT and the database recorded, e.g. to investigate an app hash mismatch.
+    ///
+    /// The node must be stopped, as its database is opened directly; nothing is written to it.
+    Replay(DebugReplayArgs),
+    /// List the actors and EVM storage slots which changed between the states at two heights.
+    ///
+    /// The node must be stopped, as its database is opened directly.
+    StateDiff(DebugStateDiffArgs),
+    /// Decode the state of an actor at a given height into JSON, including the contents
+    /// of its HAMTs and AMTs, or the bytecode and storage slots of EVM contracts.
+    ///
+    /// The node must be stopped, as its database is opened directly.
+    Inspect(DebugInspectArgs),
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -65,3 +83,63 @@ pub struct DebugExportTopDownEventsArgs {
     #[arg(long)]
     pub events_file: PathBuf,
 }
+
+#[derive(Args, Debug)]
+pub struct DebugEvidenceArgs {
+    /// Directory where the node stores evidence.
+    ///
+    /// Defaults to `evidence` under the data directory in the node's settings.
+    #[arg(long, short)]
+    pub evidence_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: DebugEvidenceCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DebugEvidenceCommands {
+    /// List all equivocations on parent finality votes, verifying the signatures.
+    List,
+    /// Show the evidence against a validator at a given parent block height.
+    Show {
+        /// Peer ID of the validator.
+        #[arg(long)]
+        validator: String,
+        /// Parent block height the conflicting votes are about.
+        #[arg(long)]
+        height: u64,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct DebugReplayArgs {
+    /// Height of the block to replay.
+    #[arg(long)]
+    pub height: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct DebugStateDiffArgs {
+    /// Height of the state to compare from; this is the state before executing the block at this height.
+    #[arg(long)]
+    pub from: u64,
+
+    /// Height of the state to compare to.
+    #[arg(long)]
+    pub to: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct DebugInspectArgs {
+    /// Address of the actor; either a Filecoin address or a 0x prefixed Ethereum address.
+    #[arg(long, short, value_parser = parse_actor_address)]
+    pub address: Address,
+
+    /// Height of the state to inspect; this is the state before executing the block at this height.
+    #[arg(long)]
+    pub height: u64,
+
+    /// Maximum number of entries to show from each collection, e.g. the storage slots of a contract.
+    #[arg(long, default_value_t = 1000)]
+    pub limit: usize,
+}
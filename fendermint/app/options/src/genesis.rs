@@ -8,7 +8,7 @@ use ipc_api::subnet_id::SubnetID;
 
 use super::parse::{
     parse_eth_address, parse_full_fil, parse_network_version, parse_percentage, parse_signer_addr,
-    parse_token_amount,
+    parse_storage_slot, parse_token_amount,
 };
 use fendermint_vm_genesis::SignerAddr;
 use fvm_shared::{address::Address, econ::TokenAmount, version::NetworkVersion};
@@ -27,6 +27,10 @@ pub enum GenesisCommands {
     AddAccount(GenesisAddAccountArgs),
     /// Add a multi-sig account to the genesis file.
     AddMultisig(GenesisAddMultisigArgs),
+    /// Add an EVM contract with its runtime bytecode and storage to the genesis file.
+    AddContract(GenesisAddContractArgs),
+    /// Add the accounts and contracts in the `alloc` section of a geth genesis file to the genesis file.
+    ImportGeth(GenesisImportGethArgs),
     /// Add a validator to the genesis file.
     AddValidator(GenesisAddValidatorArgs),
     /// Set the chain id explicitly
@@ -40,6 +44,9 @@ pub enum GenesisCommands {
     },
     /// Convert the genesis file into the format expected by Tendermint.
     IntoTendermint(GenesisIntoTendermintArgs),
+    /// Seal a genesis which starts from the state of an existing chain, with the chain ID,
+    /// timestamp and validators of the genesis file.
+    Fork(GenesisForkArgs),
 }
 
 #[derive(Args, Debug)]
@@ -129,6 +136,32 @@ pub struct GenesisAddMultisigArgs {
     pub vesting_start: u64,
 }
 
+#[derive(Args, Debug)]
+pub struct GenesisAddContractArgs {
+    /// Ethereum address the contract is deployed at.
+    #[arg(long, short)]
+    pub address: ethers::types::Address,
+    /// Path to the hex encoded runtime bytecode of the contract.
+    #[arg(long, short)]
+    pub code: PathBuf,
+    /// Initial storage slots, as `<key>=<value>` pairs of 32 byte hex numbers.
+    #[arg(long, short, value_parser = parse_storage_slot)]
+    pub storage: Vec<(ethers::types::H256, ethers::types::H256)>,
+    /// Initial balance in full FIL units.
+    #[arg(long, short, default_value = "0", value_parser = parse_full_fil)]
+    pub balance: TokenAmount,
+    /// Initial nonce of the contract.
+    #[arg(long, short, default_value_t = 0)]
+    pub nonce: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct GenesisImportGethArgs {
+    /// Path to the geth genesis JSON file; only its `alloc` section is used.
+    #[arg(long, short)]
+    pub file: PathBuf,
+}
+
 #[derive(Args, Debug)]
 pub struct GenesisAddValidatorArgs {
     /// Path to the Secp256k1 public key exported in base64 format.
@@ -159,6 +192,28 @@ pub struct GenesisIntoTendermintArgs {
     pub block_max_bytes: u64,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct GenesisForkArgs {
+    /// Path to a snapshot CAR file with the state to fork.
+    #[arg(
+        long,
+        conflicts_with = "data_dir",
+        required_unless_present = "data_dir"
+    )]
+    pub snapshot: Option<PathBuf>,
+
+    /// Data directory of a stopped node with the state to fork.
+    #[arg(long, requires = "height")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Block height of the state to fork from the node database; it must not have been pruned.
+    #[arg(long, requires = "data_dir")]
+    pub height: Option<u64>,
+
+    #[command(flatten)]
+    pub seal: SealGenesisArgs,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum GenesisIpcCommands {
     /// Set all gateway parameters.
@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use bytes::Bytes;
 use cid::Cid;
+use ethers::types::{H256, U256};
 use num_traits::{FromPrimitive, Num};
 
 use fendermint_vm_genesis::SignerAddr;
@@ -58,6 +59,15 @@ pub fn parse_address(s: &str) -> Result<Address, String> {
     Address::from_str(s).map_err(|e| format!("error parsing address: {e}"))
 }
 
+/// Parse either a Filecoin address, or a 20 byte Ethereum address in 0x prefixed hex format.
+pub fn parse_actor_address(s: &str) -> Result<Address, String> {
+    if s.starts_with("0x") {
+        parse_eth_address(s)
+    } else {
+        parse_address(s)
+    }
+}
+
 pub fn parse_signer_addr(s: &str) -> Result<SignerAddr, String> {
     Address::from_str(s)
         .map(SignerAddr)
@@ -102,6 +112,25 @@ pub fn parse_network(s: &str) -> Result<Network, String> {
     }
 }
 
+/// Parse a `<key>=<value>` storage slot assignment, where both are 32 byte hex numbers.
+pub fn parse_storage_slot(s: &str) -> Result<(H256, H256), String> {
+    let (k, v) = s
+        .split_once('=')
+        .ok_or_else(|| format!("`{s}` is not in the form <key>=<value>"))?;
+
+    Ok((parse_h256(k)?, parse_h256(v)?))
+}
+
+/// Parse a hex number of up to 32 bytes, left padding it with zeroes, the way geth does with storage slots.
+pub fn parse_h256(s: &str) -> Result<H256, String> {
+    let s = s.trim();
+    let u = U256::from_str_radix(s.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("`{s}` is not a 32 byte hex number: {e}"))?;
+    let mut bz = [0u8; 32];
+    u.to_big_endian(&mut bz);
+    Ok(H256(bz))
+}
+
 pub fn parse_eth_address(s: &str) -> Result<Address, String> {
     match ipc_types::EthAddress::from_str(s) {
         Ok(a) => Ok(a.into()),
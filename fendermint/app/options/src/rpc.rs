@@ -152,11 +152,26 @@ pub struct TransArgs {
     #[arg(long, short, value_parser = parse_full_fil, default_value = "0")]
     pub value: TokenAmount,
     /// Path to the secret key of the sender to sign the transaction.
-    #[arg(long, short)]
-    pub secret_key: PathBuf,
+    #[arg(long, short, required_unless_present = "remote_signer_url")]
+    pub secret_key: Option<PathBuf>,
     /// Indicate whether its a regular or ethereum account.
     #[arg(long, short, default_value = "regular")]
     pub account_kind: AccountKind,
+    /// URL of a remote signing service (e.g. web3signer) to sign with instead of a secret key.
+    ///
+    /// The sender is the `f410` address of the account; only messages to Ethereum addresses can be signed.
+    #[arg(
+        long,
+        conflicts_with = "secret_key",
+        requires = "remote_signer_address"
+    )]
+    pub remote_signer_url: Option<url::Url>,
+    /// Hex encoded Ethereum address of the account the remote signer signs for.
+    #[arg(long)]
+    pub remote_signer_address: Option<String>,
+    /// Bearer token to send to the remote signer.
+    #[arg(long, env = "FM_REMOTE_SIGNER_TOKEN", hide_env_values = true)]
+    pub remote_signer_token: Option<String>,
     /// Sender account nonce.
     #[arg(long, short = 'n')]
     pub sequence: u64,
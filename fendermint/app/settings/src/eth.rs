@@ -8,6 +8,9 @@ use ipc_provider::config::serialize::{
 use serde::de::Error as DeError;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_with::{serde_as, DurationSeconds};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin};
 
@@ -25,9 +28,15 @@ pub struct EthSettings {
     pub cache_capacity: usize,
     pub gas: GasOpt,
     pub max_nonce_gap: u64,
+    /// Maximum time `eth_sendRawTransactionSync` waits for a transaction to be included.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_tx_sync_timeout")]
+    pub tx_sync_timeout: Duration,
     pub metrics: MetricsSettings,
     pub cors: CorsOpt,
     pub tracing: TracingSettings,
+    #[serde(default)]
+    pub access: AccessSettings,
 }
 
 impl Default for EthSettings {
@@ -36,10 +45,12 @@ impl Default for EthSettings {
             filter_timeout: Duration::from_secs(300),
             cache_capacity: 1000000,
             max_nonce_gap: 10,
+            tx_sync_timeout: default_tx_sync_timeout(),
             gas: GasOpt {
                 min_gas_premium: TokenAmount::from_atto(100000),
                 num_blocks_max_prio_fee: 10,
                 max_fee_hist_size: 1024,
+                fee_index_path: None,
             },
             listen: SocketAddress {
                 host: "127.0.0.1".into(),
@@ -54,10 +65,15 @@ impl Default for EthSettings {
             },
             cors: CorsOpt::default(),
             tracing: TracingSettings::default(),
+            access: AccessSettings::default(),
         }
     }
 }
 
+fn default_tx_sync_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasOpt {
@@ -66,6 +82,60 @@ pub struct GasOpt {
     pub min_gas_premium: TokenAmount,
     pub num_blocks_max_prio_fee: u64,
     pub max_fee_hist_size: u64,
+    /// File where the per-block fee records are persisted, relative to the home directory.
+    /// If missing, the records are only kept in memory and rebuilt after a restart.
+    #[serde(default)]
+    pub fee_index_path: Option<PathBuf>,
+}
+
+/// Access control of the JSON-RPC endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AccessSettings {
+    /// Static bearer tokens accepted in the `Authorization` header.
+    #[serde(default)]
+    pub auth_tokens: Vec<String>,
+    /// Secret to validate HS256 JSON Web Tokens in the `Authorization` header with.
+    ///
+    /// Authentication is disabled if neither tokens nor a secret are configured.
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    /// Maximum number of requests in a JSON-RPC batch.
+    #[serde(default)]
+    pub max_batch_size: Option<usize>,
+    /// If not empty, only these methods can be called.
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+    /// Methods which cannot be called.
+    #[serde(default)]
+    pub denied_methods: Vec<String>,
+    /// Maximum number of active subscriptions on a WebSocket connection.
+    #[serde(default)]
+    pub max_ws_subscriptions: Option<usize>,
+    /// Rate limits applied to the IP address of the callers, and to the identity of the
+    /// authenticated ones as well; disabled if missing.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitSettings>,
+    /// Reverse proxies trusted to tell the IP address of the caller in `X-Forwarded-For`.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitSettings {
+    /// Request units replenished every second.
+    pub units_per_second: u32,
+    /// Maximum number of request units that can be spent at once.
+    pub burst: u32,
+    /// Cost of methods in request units; the ones not listed cost 1 unit.
+    #[serde(default = "default_method_costs")]
+    pub method_costs: HashMap<String, u32>,
+}
+
+fn default_method_costs() -> HashMap<String, u32> {
+    [("eth_getLogs", 20), ("eth_call", 5), ("eth_estimateGas", 5)]
+        .into_iter()
+        .map(|(m, c)| (m.to_owned(), c))
+        .collect()
 }
 
 #[serde_as]
@@ -197,6 +197,11 @@ pub struct TopDownSettings {
     /// conservative and avoid other from rejecting the proposal because they don't see the
     /// height as final yet.
     pub chain_head_delay: BlockHeight,
+    /// Use the parent's `finalized` block tag, if it has one, to decide which blocks are final,
+    /// instead of only relying on `chain_head_delay`. Useful for parents without instant finality,
+    /// where blocks can be reorganised; the syncer rolls back to the fork point when they are.
+    #[serde(default)]
+    pub use_finalized_tag: bool,
     /// The number of blocks on top of `chain_head_delay` to wait before proposing a height
     /// as final on the parent chain, to avoid slight disagreements between validators whether
     /// a block is final, or not just yet.
@@ -205,6 +210,10 @@ pub struct TopDownSettings {
     pub max_proposal_range: BlockHeight,
     /// The max number of blocks to hold in memory for parent syncer
     pub max_cache_blocks: Option<BlockHeight>,
+    /// The max estimated gas the top-down messages in our proposals can use; parent blocks over
+    /// the budget are deferred to the next proposals. Capped at the block gas limit of the chain.
+    #[serde(default)]
+    pub gas_budget: Option<u64>,
     /// Parent syncing cron period, in seconds
     #[serde_as(as = "DurationSeconds<u64>")]
     pub polling_interval: Duration,
@@ -63,6 +63,7 @@ impl Default for NetworkSettings {
 }
 
 /// Configuration for [`discovery::Behaviour`].
+#[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DiscoverySettings {
     /// Custom nodes which never expire, e.g. bootstrap or reserved nodes.
@@ -73,6 +74,13 @@ pub struct DiscoverySettings {
     pub target_connections: usize,
     /// Option to disable Kademlia, for example in a fixed static network.
     pub enable_kademlia: bool,
+    /// How long to remember peers we haven't heard from in the database, in seconds.
+    ///
+    /// 0 means peers aren't persisted, and we only bootstrap from the static addresses.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub peer_ttl: Duration,
+    /// Maximum number of peers to remember; the least recently seen ones are forgotten first.
+    pub max_remembered_peers: usize,
 }
 
 impl Default for DiscoverySettings {
@@ -81,6 +89,8 @@ impl Default for DiscoverySettings {
             static_addresses: Vec::new(),
             target_connections: 50,
             enable_kademlia: true,
+            peer_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            max_remembered_peers: 1000,
         }
     }
 }
@@ -136,6 +146,12 @@ pub struct ConnectionSettings {
     /// Maximum number of events in the push-based broadcast channel before a slow
     /// consumer gets an error because it's falling behind.
     pub event_buffer_capacity: u32,
+    /// Offer mplex as a fallback to yamux on TCP connections.
+    pub mplex_fallback: bool,
+    /// Dial and accept QUIC connections besides TCP.
+    ///
+    /// Always enabled when the `listen_addr` is a `quic-v1` address.
+    pub enable_quic: bool,
 }
 
 impl Default for ConnectionSettings {
@@ -147,6 +163,8 @@ impl Default for ConnectionSettings {
             expected_peer_count: 10000,
             max_peers_per_query: 5,
             event_buffer_capacity: 100,
+            mplex_fallback: true,
+            enable_quic: false,
         }
     }
 }
@@ -29,16 +29,15 @@ use fendermint_vm_interpreter::fvm::state::{
 use fendermint_vm_interpreter::fvm::store::ReadOnlyBlockstore;
 use fendermint_vm_interpreter::genesis::{read_genesis_car, GenesisAppState};
 
-use fendermint_vm_interpreter::errors::{ApplyMessageError, CheckMessageError, QueryError};
-use fendermint_vm_interpreter::types::{
-    ApplyMessageResponse, AttestMessagesResponse, EndBlockResponse, Query,
-};
+use fendermint_vm_interpreter::errors::{CheckMessageError, QueryError};
+use fendermint_vm_interpreter::types::{AttestMessagesResponse, EndBlockResponse, Query};
 use fendermint_vm_interpreter::MessagesInterpreter;
 
+use crate::checkpoint::{BottomUpBatchAnnouncer, CheckpointSigner, SignatureCollector};
 use crate::ipc::derive_subnet_app_hash;
 use fendermint_vm_interpreter::fvm::end_block_hook::LightClientCommitments;
 use fendermint_vm_interpreter::fvm::state::snapshot::SnapshotPayload;
-use fendermint_vm_message::query::FvmQueryHeight;
+use fendermint_vm_message::query::{FvmQueryHeight, CHECKPOINT_SIGNATURES_QUERY_PATH};
 use fendermint_vm_snapshot::{SnapshotClient, SnapshotError};
 use fvm::engine::MultiEngine;
 use fvm_ipld_blockstore::Blockstore;
@@ -178,6 +177,12 @@ where
     state_hist_size: u64,
     /// Caches the validators.
     validators_cache: Arc<tokio::sync::Mutex<Option<ValidatorCache>>>,
+    /// Checkpoint signatures gossiped by the validators, if the resolver is enabled.
+    checkpoint_signatures: Option<SignatureCollector>,
+    /// Signs the app hash at checkpoint heights, if we are a validator.
+    checkpoint_signer: Option<Arc<CheckpointSigner>>,
+    /// Announces bottom-up message batches to the parent, if we are a validator.
+    bottom_up_announcer: Option<Arc<BottomUpBatchAnnouncer>>,
 }
 
 impl<DB, BS, KV, MI> App<DB, BS, KV, MI>
@@ -212,10 +217,31 @@ where
             exec_state: Arc::new(tokio::sync::Mutex::new(None)),
             check_state: Arc::new(tokio::sync::Mutex::new(None)),
             validators_cache: Arc::new(tokio::sync::Mutex::new(None)),
+            checkpoint_signatures: None,
+            checkpoint_signer: None,
+            bottom_up_announcer: None,
         };
         app.init_committed_state()?;
         Ok(app)
     }
+
+    /// Serve the checkpoint signatures gossiped by the validators to relayers,
+    /// and if we are a validator, sign the app hash at checkpoint heights.
+    pub fn with_checkpoint_signatures(
+        mut self,
+        collector: SignatureCollector,
+        signer: Option<CheckpointSigner>,
+    ) -> Self {
+        self.checkpoint_signatures = Some(collector);
+        self.checkpoint_signer = signer.map(Arc::new);
+        self
+    }
+
+    /// Announce the bottom-up message batches of checkpoints to the parent subnet.
+    pub fn with_bottom_up_announcer(mut self, announcer: BottomUpBatchAnnouncer) -> Self {
+        self.bottom_up_announcer = Some(Arc::new(announcer));
+        self
+    }
 }
 
 impl<DB, BS, KV, MI> App<DB, BS, KV, MI>
@@ -432,6 +458,25 @@ where
         !(height == 0 && params.timestamp.0 == 0 && params.network_version == NetworkVersion::V0)
     }
 
+    /// Look up the checkpoint signatures collected at the height in the query data.
+    fn query_checkpoint_signatures(&self, request: request::Query) -> Result<response::Query> {
+        let Some(ref collector) = self.checkpoint_signatures else {
+            return Ok(invalid_query(
+                AppError::InvalidEncoding,
+                "Checkpoint signatures are not collected by this node.".to_owned(),
+            ));
+        };
+
+        let height: BlockHeight = match fvm_ipld_encoding::from_slice(&request.data) {
+            Ok(height) => height,
+            Err(e) => return Ok(invalid_query(AppError::InvalidEncoding, e.to_string())),
+        };
+
+        let block_height = self.committed_state()?.app_state.block_height;
+
+        to_checkpoint_signatures_query(collector.get(height), block_height)
+    }
+
     fn parse_genesis_app_bytes(bytes: &[u8]) -> Result<Vec<u8>> {
         // cometbft serves data in json format, convert from json string
         match serde_json::from_slice(bytes)? {
@@ -464,6 +509,15 @@ where
 
     /// Retrieves a validator from the cache, initializing it if necessary.
     async fn get_validator_from_cache(&self, id: &tendermint::account::Id) -> Result<PublicKey> {
+        self.with_validators_cache(|cache| cache.get_validator(id))
+            .await
+    }
+
+    /// Look up something in the validators cache, initializing it if necessary.
+    async fn with_validators_cache<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&ValidatorCache) -> Result<T>,
+    {
         let mut cache = self.validators_cache.lock().await;
 
         // If cache is not initialized, update it from the state
@@ -475,11 +529,7 @@ where
             *cache = Some(ValidatorCache::new_from_state(&mut state)?);
         }
 
-        // Retrieve the validator from the cache
-        cache
-            .as_ref()
-            .context("Validator cache is not available")?
-            .get_validator(id)
+        f(cache.as_ref().context("Validator cache is not available")?)
     }
 }
 
@@ -582,6 +632,10 @@ where
     /// Query the application for data at the current or past height.
     #[instrument(skip(self))]
     async fn query(&self, request: request::Query) -> AbciResult<response::Query> {
+        if request.path == CHECKPOINT_SIGNATURES_QUERY_PATH {
+            return Ok(self.query_checkpoint_signatures(request)?);
+        }
+
         let db = self.state_store_clone();
         let height = FvmQueryHeight::from(request.height.value());
         let (state_params, block_height) = self.state_params_at_height(height)?;
@@ -845,19 +899,7 @@ where
             (result, block_hash)
         };
 
-        let response = match result {
-            Ok(ApplyMessageResponse {
-                applied_message,
-                domain_hash,
-            }) => to_deliver_tx(applied_message, domain_hash, block_hash),
-            Err(ApplyMessageError::InvalidSignature(err)) => {
-                invalid_deliver_tx(AppError::InvalidSignature, err.to_string())
-            }
-            Err(ApplyMessageError::InvalidMessage(s)) => {
-                invalid_deliver_tx(AppError::InvalidEncoding, s)
-            }
-            Err(ApplyMessageError::Other(e)) => Err(e).context("failed to apply message")?,
-        };
+        let response = to_deliver_tx_result(result, block_hash)?;
 
         if response.code != 0.into() {
             tracing::info!(
@@ -888,9 +930,27 @@ where
             power_updates,
             gas_market,
             light_client_commitments,
+            bottom_up_batch,
             end_block_events,
         } = response;
 
+        // Keep the batch available for the parent subnet to resolve, then let it know about it.
+        if let Some(batch) = bottom_up_batch {
+            self.state_store
+                .put_keyed(&batch.cid, &batch.data)
+                .context("failed to store bottom-up batch")?;
+
+            if let Some(ref announcer) = self.bottom_up_announcer {
+                if let Err(e) = announcer.announce(&batch) {
+                    tracing::error!(
+                        error = e.to_string(),
+                        height = batch.height,
+                        "failed to announce bottom-up batch"
+                    );
+                }
+            }
+        }
+
         let mut c = self.light_client_commitments.lock().await;
         *c = light_client_commitments;
 
@@ -953,6 +1013,34 @@ where
         let app_hash = state.app_hash();
         let block_height = state.app_state.block_height;
 
+        // Only accept checkpoint signatures from the current validators around this height.
+        if let Some(ref collector) = self.checkpoint_signatures {
+            match self
+                .with_validators_cache(|cache| Ok(cache.eth_addresses()))
+                .await
+            {
+                Ok(validators) => collector.set_committed(block_height, validators),
+                Err(e) => tracing::error!(
+                    error = e.to_string(),
+                    block_height,
+                    "failed to update checkpoint validators"
+                ),
+            }
+        }
+
+        // Certify the app hash at checkpoint heights for the relayers.
+        if let Some(ref signer) = self.checkpoint_signer {
+            if state.state_commitments.is_some() {
+                if let Err(e) = signer.sign(block_height, app_hash.as_bytes()) {
+                    tracing::error!(
+                        error = e.to_string(),
+                        block_height,
+                        "failed to sign checkpoint"
+                    );
+                }
+            }
+        }
+
         // Tell CometBFT how much of the block history it can forget.
         let retain_height = if self.state_hist_size == 0 {
             Default::default()
@@ -0,0 +1,417 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Signatures over bottom-up checkpoints, gossiped among the validators.
+//!
+//! At every checkpoint height each validator signs the app hash of the subnet, which commits
+//! to the app hash breakdown, including the bottom-up message batch commitment. The signatures
+//! are published over the IPLD Resolver like parent finality votes, and every node collects
+//! them in a [`SignatureCollector`], from where relayers can query them to submit a
+//! quorum-weighted multi-signature to the parent instead of a CometBFT signed header.
+//!
+//! The validators also announce the CID of the bottom-up message batch of the checkpoint to
+//! the parent subnet, so that its validators can resolve and execute it without a relayer.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Context};
+use fendermint_crypto::SecretKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_interpreter::fvm::bottomup::{BottomUpBatch, SignedBottomUpBatchRef};
+use ipc_api::checkpoint::{
+    checkpoint_signature_digest, CheckpointSignature, CheckpointSignatures, ValidatorSignature,
+};
+use ipc_api::subnet_id::SubnetID;
+use ipc_ipld_resolver::{SignedVoteRecord, ValidatorKey, VoteRecord};
+
+use crate::ipc::AppVote;
+use crate::BlockHeight;
+
+/// Number of committed heights below the last one for which signatures are retained.
+const RETAINED_HEIGHTS: BlockHeight = 64;
+
+/// Number of heights above the last committed one for which signatures are accepted,
+/// to tolerate validators which are slightly ahead of us.
+const FUTURE_HEIGHTS: BlockHeight = 16;
+
+type Signatures = BTreeMap<ethers::types::Address, Vec<u8>>;
+
+#[derive(Default)]
+struct Collection {
+    /// The last height committed by our node.
+    committed_height: BlockHeight,
+    /// The validators whose signatures are accepted.
+    validators: HashSet<ethers::types::Address>,
+    /// Signatures grouped by height and app hash.
+    signatures: BTreeMap<BlockHeight, HashMap<Vec<u8>, Signatures>>,
+}
+
+/// Collects the checkpoint signatures of validators, grouped by height and app hash.
+///
+/// Only signatures from the current validators are accepted, at heights within a window
+/// around the last committed height, and at most one per validator at any height, which
+/// bounds the number of entries per height by the size of the validator set.
+///
+/// The collector does not know about validator power. It returns every app hash signed
+/// at a height, and it is up to the relayer to pick out the signatures of the validators
+/// active on the parent, and the subnet actor to check that they form a quorum.
+#[derive(Clone, Default)]
+pub struct SignatureCollector {
+    collection: Arc<RwLock<Collection>>,
+}
+
+impl SignatureCollector {
+    /// Move the window of accepted heights to a newly committed height, forgetting signatures
+    /// which are too old, and replace the set of validators whose signatures are accepted.
+    pub fn set_committed(
+        &self,
+        height: BlockHeight,
+        validators: impl IntoIterator<Item = ethers::types::Address>,
+    ) {
+        let mut collection = self.collection.write().unwrap();
+        collection.committed_height = height;
+        collection.validators = validators.into_iter().collect();
+
+        let oldest = height.saturating_sub(RETAINED_HEIGHTS);
+        collection.signatures = collection.signatures.split_off(&oldest);
+    }
+
+    /// Add a signature which has already been checked to be from the given validator.
+    pub fn add(
+        &self,
+        validator: ethers::types::Address,
+        signature: CheckpointSignature,
+    ) -> anyhow::Result<()> {
+        let mut collection = self.collection.write().unwrap();
+
+        let oldest = collection.committed_height.saturating_sub(RETAINED_HEIGHTS);
+        let newest = collection.committed_height.saturating_add(FUTURE_HEIGHTS);
+
+        if signature.height < oldest || signature.height > newest {
+            return Err(anyhow!(
+                "checkpoint height {} is outside the accepted range {oldest}..={newest}",
+                signature.height
+            ));
+        }
+
+        if !collection.validators.contains(&validator) {
+            return Err(anyhow!("checkpoint signed by non-validator {validator:?}"));
+        }
+
+        let groups = collection.signatures.entry(signature.height).or_default();
+
+        if groups.values().any(|sigs| sigs.contains_key(&validator)) {
+            return Err(anyhow!(
+                "{validator:?} already signed a checkpoint at height {}",
+                signature.height
+            ));
+        }
+
+        groups
+            .entry(signature.app_hash)
+            .or_default()
+            .insert(validator, signature.signature);
+
+        Ok(())
+    }
+
+    /// Check that a gossiped signature is by the validator who published it, and add it.
+    pub fn add_vote(
+        &self,
+        subnet_id: &SubnetID,
+        public_key: &ValidatorKey,
+        signature: CheckpointSignature,
+    ) -> anyhow::Result<()> {
+        let signer = signature
+            .recover(subnet_id)
+            .context("failed to recover signer")?;
+
+        let expected = eth_address(public_key)?;
+
+        if signer != expected {
+            return Err(anyhow!(
+                "checkpoint signed by {signer:?} but published by {expected:?}"
+            ));
+        }
+
+        self.add(signer, signature)
+    }
+
+    /// The signatures collected at a height, one entry for each app hash that was signed.
+    pub fn get(&self, height: BlockHeight) -> Vec<CheckpointSignatures> {
+        let collection = self.collection.read().unwrap();
+
+        let Some(groups) = collection.signatures.get(&height) else {
+            return Vec::new();
+        };
+
+        groups
+            .iter()
+            .map(|(app_hash, sigs)| CheckpointSignatures {
+                height,
+                app_hash: app_hash.clone(),
+                signatures: sigs
+                    .iter()
+                    .map(|(validator, signature)| ValidatorSignature {
+                        validator: *validator,
+                        signature: signature.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Signs the app hash at checkpoint heights and publishes the signature to the other validators.
+pub struct CheckpointSigner {
+    secret_key: SecretKey,
+    keypair: libp2p::identity::Keypair,
+    subnet_id: SubnetID,
+    client: ipc_ipld_resolver::Client<AppVote>,
+    collector: SignatureCollector,
+}
+
+impl CheckpointSigner {
+    pub fn new(
+        secret_key: SecretKey,
+        keypair: libp2p::identity::Keypair,
+        subnet_id: SubnetID,
+        client: ipc_ipld_resolver::Client<AppVote>,
+        collector: SignatureCollector,
+    ) -> Self {
+        Self {
+            secret_key,
+            keypair,
+            subnet_id,
+            client,
+            collector,
+        }
+    }
+
+    /// Sign the app hash committed at a checkpoint height, add it to our own collection,
+    /// then gossip it, since our own gossip messages are not delivered back to us.
+    pub fn sign(&self, height: BlockHeight, app_hash: &[u8]) -> anyhow::Result<()> {
+        let signature = sign_checkpoint(&self.secret_key, &self.subnet_id, height, app_hash)?;
+
+        let validator = EthAddress::from(self.secret_key.public_key());
+        self.collector.add(validator.into(), signature.clone())?;
+
+        let vote: SignedVoteRecord<AppVote> = VoteRecord::signed(
+            &self.keypair,
+            self.subnet_id.clone(),
+            AppVote::CheckpointSignature(signature),
+        )?;
+
+        self.client.publish_vote(vote)
+    }
+}
+
+/// Announces the bottom-up message batches of our subnet to the parent subnet.
+///
+/// The parent subscribes to the data pre-emptively published in its child subnets, then
+/// resolves the content from us through Bitswap; the batch has to be in our state store.
+/// Announcements are signed with our validator key, because the parent only resolves
+/// batches announced by the active validators of the subnet.
+pub struct BottomUpBatchAnnouncer {
+    secret_key: SecretKey,
+    subnet_id: SubnetID,
+    client: ipc_ipld_resolver::Client<AppVote>,
+}
+
+impl BottomUpBatchAnnouncer {
+    pub fn new(
+        secret_key: SecretKey,
+        subnet_id: SubnetID,
+        client: ipc_ipld_resolver::Client<AppVote>,
+    ) -> Self {
+        Self {
+            secret_key,
+            subnet_id,
+            client,
+        }
+    }
+
+    pub fn announce(&self, batch: &BottomUpBatch) -> anyhow::Result<()> {
+        let batch_ref = batch.to_ref(self.subnet_id.clone());
+        let signed = SignedBottomUpBatchRef::sign(batch_ref, &self.secret_key)?;
+        let data = fvm_ipld_encoding::to_vec(&signed)?;
+        self.client.publish_preemptive(self.subnet_id.clone(), data)
+    }
+}
+
+/// Sign the checkpoint digest, producing a signature in the format expected by the subnet actor.
+pub fn sign_checkpoint(
+    secret_key: &SecretKey,
+    subnet_id: &SubnetID,
+    height: BlockHeight,
+    app_hash: &[u8],
+) -> anyhow::Result<CheckpointSignature> {
+    let digest = checkpoint_signature_digest(subnet_id, height, app_hash)?;
+    let (sig, recovery_id) = secret_key.sign(&digest);
+
+    let mut signature = sig.serialize().to_vec();
+    signature.push(recovery_id.serialize() + 27);
+
+    Ok(CheckpointSignature {
+        height,
+        app_hash: app_hash.to_vec(),
+        signature,
+    })
+}
+
+fn eth_address(public_key: &ValidatorKey) -> anyhow::Result<ethers::types::Address> {
+    let public_key = libp2p::identity::PublicKey::from(public_key.clone())
+        .try_into_secp256k1()
+        .context("validator key is not secp256k1")?;
+
+    let addr = EthAddress::new_secp256k1(&public_key.to_bytes_uncompressed())?;
+
+    Ok(addr.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use fendermint_crypto::SecretKey;
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use fvm_shared::address::Address;
+    use ipc_api::subnet_id::SubnetID;
+    use ipc_ipld_resolver::ValidatorKey;
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    use super::{sign_checkpoint, SignatureCollector, FUTURE_HEIGHTS, RETAINED_HEIGHTS};
+
+    fn subnet_id() -> SubnetID {
+        SubnetID::new(314159, vec![Address::new_delegated(10, &[1; 20]).unwrap()])
+    }
+
+    fn random_keys(n: usize) -> Vec<SecretKey> {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        (0..n).map(|_| SecretKey::random(&mut rng)).collect()
+    }
+
+    fn collector_with_validators(height: u64, keys: &[SecretKey]) -> SignatureCollector {
+        let collector = SignatureCollector::default();
+        collector.set_committed(
+            height,
+            keys.iter()
+                .map(|sk| EthAddress::from(sk.public_key()).into()),
+        );
+        collector
+    }
+
+    fn add_vote(
+        collector: &SignatureCollector,
+        sk: &SecretKey,
+        height: u64,
+        app_hash: &[u8],
+    ) -> anyhow::Result<()> {
+        let sig = sign_checkpoint(sk, &subnet_id(), height, app_hash).unwrap();
+        let key = ValidatorKey::from(sk.public_key());
+        collector.add_vote(&subnet_id(), &key, sig)
+    }
+
+    #[test]
+    fn signatures_are_grouped_by_app_hash() {
+        let keys = random_keys(3);
+        let collector = collector_with_validators(10, &keys);
+
+        for (i, sk) in keys.iter().enumerate() {
+            let app_hash = if i == 0 { [1; 32] } else { [2; 32] };
+            add_vote(&collector, sk, 10, &app_hash).unwrap();
+        }
+
+        let mut groups = collector.get(10);
+        groups.sort_by_key(|g| g.signatures.len());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].app_hash, vec![1; 32]);
+        assert_eq!(groups[0].signatures.len(), 1);
+
+        let sigs = groups.pop().unwrap();
+        assert_eq!(sigs.app_hash, vec![2; 32]);
+        assert_eq!(sigs.signatures.len(), 2);
+        assert!(sigs.signatures[0].validator < sigs.signatures[1].validator);
+
+        for s in sigs.signatures {
+            let sig = ipc_api::checkpoint::CheckpointSignature {
+                height: 10,
+                app_hash: sigs.app_hash.clone(),
+                signature: s.signature,
+            };
+            assert_eq!(sig.recover(&subnet_id()).unwrap(), s.validator);
+        }
+
+        assert!(collector.get(20).is_empty());
+    }
+
+    #[test]
+    fn signature_must_match_publisher() {
+        let keys = random_keys(2);
+        let (signer, publisher) = (&keys[0], &keys[1]);
+        let collector = collector_with_validators(10, &keys);
+
+        let sig = sign_checkpoint(signer, &subnet_id(), 10, &[1; 32]).unwrap();
+
+        let key = ValidatorKey::from(publisher.public_key());
+        assert!(collector.add_vote(&subnet_id(), &key, sig.clone()).is_err());
+        assert!(collector.get(10).is_empty());
+
+        let key = ValidatorKey::from(signer.public_key());
+        collector.add_vote(&subnet_id(), &key, sig).unwrap();
+        let sigs = collector.get(10);
+        assert_eq!(
+            sigs[0].signatures[0].validator,
+            EthAddress::from(signer.public_key()).into()
+        );
+    }
+
+    #[test]
+    fn signature_must_be_from_validator() {
+        let keys = random_keys(2);
+        let collector = collector_with_validators(10, &keys[..1]);
+
+        assert!(add_vote(&collector, &keys[1], 10, &[1; 32]).is_err());
+        assert!(collector.get(10).is_empty());
+
+        add_vote(&collector, &keys[0], 10, &[1; 32]).unwrap();
+        assert_eq!(collector.get(10).len(), 1);
+    }
+
+    #[test]
+    fn one_signature_per_validator_and_height() {
+        let keys = random_keys(1);
+        let collector = collector_with_validators(10, &keys);
+
+        add_vote(&collector, &keys[0], 10, &[1; 32]).unwrap();
+        assert!(add_vote(&collector, &keys[0], 10, &[2; 32]).is_err());
+
+        let groups = collector.get(10);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].app_hash, vec![1; 32]);
+    }
+
+    #[test]
+    fn signatures_outside_the_window_are_rejected() {
+        let keys = random_keys(1);
+        let height = 2 * RETAINED_HEIGHTS;
+        let collector = collector_with_validators(height, &keys);
+
+        let too_old = height - RETAINED_HEIGHTS - 1;
+        let too_new = height + FUTURE_HEIGHTS + 1;
+
+        assert!(add_vote(&collector, &keys[0], too_old, &[1; 32]).is_err());
+        assert!(add_vote(&collector, &keys[0], too_new, &[1; 32]).is_err());
+        add_vote(&collector, &keys[0], height + FUTURE_HEIGHTS, &[1; 32]).unwrap();
+        add_vote(&collector, &keys[0], height, &[1; 32]).unwrap();
+
+        // Far future heights cannot push out the ones we need, only committing can.
+        assert_eq!(collector.get(height).len(), 1);
+
+        collector.set_committed(
+            height + RETAINED_HEIGHTS + 1,
+            [EthAddress::from(keys[0].public_key()).into()],
+        );
+        assert!(collector.get(height).is_empty());
+        assert_eq!(collector.get(height + FUTURE_HEIGHTS).len(), 1);
+    }
+}
@@ -1,23 +1,63 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+
 use crate::fs;
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
+use cid::Cid;
 use fendermint_app_options::debug::{
-    DebugArgs, DebugCommands, DebugExportTopDownEventsArgs, DebugIpcCommands,
+    DebugArgs, DebugCommands, DebugEvidenceArgs, DebugEvidenceCommands,
+    DebugExportTopDownEventsArgs, DebugInspectArgs, DebugIpcCommands, DebugReplayArgs,
+    DebugStateDiffArgs,
 };
+use fendermint_rocksdb::blockstore::NamespaceBlockstore;
+use fendermint_vm_interpreter::fvm::bottomup::{BottomUpManager, BottomUpPool};
+use fendermint_vm_interpreter::fvm::state::diff::{diff_state_trees, ActorDiff};
+use fendermint_vm_interpreter::fvm::state::inspect::inspect_actor;
+use fendermint_vm_interpreter::fvm::state::FvmExecState;
+use fendermint_vm_interpreter::fvm::store::overlay::OverlayBlockstore;
+use fendermint_vm_interpreter::types::EndBlockResponse;
+use fendermint_vm_interpreter::MessagesInterpreter;
 use fendermint_vm_topdown::proxy::IPCProviderProxy;
+use fendermint_vm_topdown::voting::VoteTally;
+use fvm::engine::MultiEngine;
+use fvm_shared::clock::ChainEpoch;
 use ipc_provider::{
     config::subnet::{EVMSubnet, SubnetConfig},
     IpcProvider,
 };
+use tendermint_rpc::endpoint::block_results;
+use tendermint_rpc::Client;
 
 use crate::cmd;
+use crate::cmd::load_settings;
+use crate::evidence::{EvidenceStore, EVIDENCE_DIR};
+use crate::options::Options;
+use crate::service::node::{make_interpreter, make_parent_finality_provider, StateHistory};
+use crate::settings::Settings;
+use crate::tmconv::{
+    to_begin_block, to_deliver_tx_result, to_events, to_timestamp, to_validator_updates,
+};
+use crate::validators::ValidatorCache;
+use crate::BlockHeight;
 
 cmd! {
-  DebugArgs(self) {
+  DebugArgs(self, options: Arc<Options>) {
     match &self.command {
         DebugCommands::Ipc { command } => command.exec(()).await,
+        DebugCommands::Evidence(args) => {
+            let evidence_dir = match &args.evidence_dir {
+                Some(dir) => dir.clone(),
+                None => load_settings(options)?.data_dir().join(EVIDENCE_DIR),
+            };
+            args.exec(evidence_dir).await
+        }
+        DebugCommands::Replay(args) => args.exec(load_settings(options)?).await,
+        DebugCommands::StateDiff(args) => args.exec(load_settings(options)?).await,
+        DebugCommands::Inspect(args) => args.exec(load_settings(options)?).await,
     }
   }
 }
@@ -33,6 +73,77 @@ cmd! {
   }
 }
 
+cmd! {
+  DebugEvidenceArgs(self, evidence_dir: PathBuf) {
+    let store = EvidenceStore::new(evidence_dir);
+
+    let evidence = match &self.command {
+        DebugEvidenceCommands::List => store.list()?,
+        DebugEvidenceCommands::Show { validator, height } => {
+            let evidence = store.get(validator, *height)?.ok_or_else(|| {
+                anyhow!("no evidence against {validator} at height {height}")
+            })?;
+            vec![evidence]
+        }
+    };
+
+    for e in evidence.iter() {
+        if let Err(err) = e.verify() {
+            tracing::warn!(
+                validator = e.validator,
+                height = e.height,
+                error = format!("{err:#}"),
+                "invalid evidence"
+            );
+        }
+    }
+
+    println!("{}", serde_json::to_string_pretty(&evidence)?);
+
+    Ok(())
+  }
+}
+
+cmd! {
+  DebugReplayArgs(self, settings) {
+    replay_block(&settings, self.height).await
+  }
+}
+
+cmd! {
+  DebugStateDiffArgs(self, settings) {
+    let history = StateHistory::open(&settings.data_dir())?;
+    let from = history.state_params(self.from)?;
+    let to = history.state_params(self.to)?;
+
+    let diffs = diff_state_trees(history.state_store(), &from.state_root, &to.state_root)
+        .context("failed to compare the state trees")?;
+
+    print_actor_diffs(&diffs);
+
+    Ok(())
+  }
+}
+
+cmd! {
+  DebugInspectArgs(self, settings) {
+    let history = StateHistory::open(&settings.data_dir())?;
+    let state_params = history.state_params(self.height)?;
+
+    let actor = inspect_actor(
+        history.state_store(),
+        &state_params.state_root,
+        &self.address,
+        self.limit,
+    )
+    .with_context(|| format!("failed to inspect {} at height {}", self.address, self.height))?;
+
+    println!("{}", serde_json::to_string_pretty(&actor)?);
+
+    Ok(())
+  }
+}
+
 async fn export_topdown_events(args: &DebugExportTopDownEventsArgs) -> anyhow::Result<()> {
     // Configuration for the child subnet on the parent network,
     // based on how it's done in `run.rs` and the `genesis ipc from-parent` command.
@@ -69,3 +180,602 @@ async fn export_topdown_events(args: &DebugExportTopDownEventsArgs) -> anyhow::R
 
     Ok(())
 }
+
+/// Execute a block on the state the node committed before it, the same way the node would,
+/// and compare the receipts, events and resulting state with what has been recorded.
+///
+/// Whatever the execution writes stays in memory, so the database is left untouched.
+async fn replay_block(settings: &Settings, height: BlockHeight) -> anyhow::Result<()> {
+    if height == 0 {
+        bail!("the genesis cannot be replayed");
+    }
+
+    let client = tendermint_rpc::HttpClient::new(settings.tendermint_rpc_url()?)
+        .context("failed to create Tendermint client")?;
+
+    let tm_height = tendermint::block::Height::try_from(height)?;
+
+    let block = client
+        .block(tm_height)
+        .await
+        .context("failed to fetch the block")?
+        .block;
+
+    let results = client
+        .block_results(tm_height)
+        .await
+        .context("failed to fetch the block results")?;
+
+    let history = StateHistory::open(&settings.data_dir())?;
+
+    // Top-down messages are fetched from the parent again, as the node would have done.
+    let (parent_finality_provider, _) = make_parent_finality_provider(settings).await?;
+
+    let bottom_up_manager = settings.resolver_enabled().then(|| {
+        BottomUpManager::new(
+            BottomUpPool::new(),
+            OverlayBlockstore::new(history.bit_store().clone()),
+        )
+    });
+
+    let interpreter = make_interpreter(
+        settings,
+        parent_finality_provider,
+        VoteTally::empty(),
+        bottom_up_manager,
+    );
+
+    let replay = replay_recorded_block(&history, &interpreter, &block, results).await?;
+
+    if !replay.mismatches.is_empty() {
+        for m in replay.mismatches.iter() {
+            println!("{m}");
+        }
+        bail!(
+            "replaying block {height} produced {} mismatches",
+            replay.mismatches.len()
+        );
+    }
+
+    println!(
+        "replayed block {height} with {} transactions; state root {} matches the record",
+        block.data().len(),
+        replay.state_root
+    );
+
+    Ok(())
+}
+
+/// The outcome of replaying a block.
+struct Replay {
+    /// The state root after the replayed block.
+    state_root: Cid,
+    /// Everything that differs from what was recorded, in a human readable form.
+    mismatches: Vec<String>,
+}
+
+/// Replay a block on the state the node committed before it, with the interpreter the node would use,
+/// comparing the outcome with the results CometBFT recorded for it.
+async fn replay_recorded_block<I>(
+    history: &StateHistory,
+    interpreter: &I,
+    block: &tendermint::Block,
+    results: block_results::Response,
+) -> anyhow::Result<Replay>
+where
+    I: MessagesInterpreter<OverlayBlockstore<NamespaceBlockstore>>,
+{
+    let height = block.header.height.value();
+
+    let block_hash = match block.header.hash() {
+        tendermint::Hash::Sha256(h) => h,
+        tendermint::Hash::None => bail!("empty block hash"),
+    };
+
+    let mut state_params = history.state_params(height)?;
+    state_params.timestamp = to_timestamp(block.header.time);
+
+    let store = OverlayBlockstore::new(history.state_store().clone());
+    let multi_engine = MultiEngine::new(1);
+
+    let mut state = FvmExecState::new(
+        store.clone(),
+        &multi_engine,
+        height as ChainEpoch,
+        state_params,
+    )
+    .context("error creating new state")?;
+
+    let producer = ValidatorCache::new_from_state(&mut state)?
+        .get_validator(&block.header.proposer_address)
+        .context("block proposer is not in the power table")?;
+
+    let mut state = state
+        .with_block_hash(block_hash)
+        .with_block_producer(producer);
+
+    let mut mismatches = Vec::new();
+
+    let begin = interpreter
+        .begin_block(&mut state)
+        .await
+        .context("failed to begin block")?;
+
+    check(
+        &mut mismatches,
+        "begin block events",
+        &to_begin_block(begin.applied_cron_message).events,
+        &results.begin_block_events.unwrap_or_default(),
+    );
+
+    let txs_results = results.txs_results.unwrap_or_default();
+
+    if txs_results.len() != block.data().len() {
+        mismatches.push(format!(
+            "the block has {} transactions but {} results were recorded",
+            block.data().len(),
+            txs_results.len()
+        ));
+    }
+
+    for (i, tx) in block.data().iter().enumerate() {
+        let result = interpreter.apply_message(&mut state, tx.clone()).await;
+        let replayed = to_deliver_tx_result(result, state.block_hash())
+            .with_context(|| format!("failed to replay transaction {i}"))?;
+
+        let Some(recorded) = txs_results.get(i) else {
+            continue;
+        };
+
+        check(
+            &mut mismatches,
+            &format!("tx {i} code"),
+            &replayed.code,
+            &recorded.code,
+        );
+        check(
+            &mut mismatches,
+            &format!("tx {i} return data"),
+            &replayed.data,
+            &recorded.data,
+        );
+        check(
+            &mut mismatches,
+            &format!("tx {i} gas used"),
+            &replayed.gas_used,
+            &recorded.gas_used,
+        );
+        check(
+            &mut mismatches,
+            &format!("tx {i} events"),
+            &replayed.events,
+            &recorded.events,
+        );
+    }
+
+    let EndBlockResponse {
+        power_updates,
+        end_block_events,
+        ..
+    } = interpreter
+        .end_block(&mut state)
+        .await
+        .context("failed to end block")?;
+
+    let end_block_events = end_block_events
+        .into_iter()
+        .flat_map(|(stamped, emitters)| to_events("event", stamped, emitters))
+        .collect::<Vec<_>>();
+
+    check(
+        &mut mismatches,
+        "end block events",
+        &end_block_events,
+        &results.end_block_events.unwrap_or_default(),
+    );
+    check(
+        &mut mismatches,
+        "validator updates",
+        &to_validator_updates(power_updates.0)?,
+        &results.validator_updates,
+    );
+
+    let (state_root, params, _) = state.commit().context("failed to commit FVM")?;
+
+    // The state after the block is recorded at the next height.
+    match history.state_params(height + 1) {
+        Ok(recorded) => {
+            check(
+                &mut mismatches,
+                "app version",
+                &params.app_version,
+                &recorded.app_version,
+            );
+            check(
+                &mut mismatches,
+                "base fee",
+                &params.base_fee,
+                &recorded.base_fee,
+            );
+            check(
+                &mut mismatches,
+                "circulating supply",
+                &params.circ_supply,
+                &recorded.circ_supply,
+            );
+            check(
+                &mut mismatches,
+                "power scale",
+                &params.power_scale,
+                &recorded.power_scale,
+            );
+
+            if state_root != recorded.state_root {
+                mismatches.push(format!(
+                    "state root:\n  replayed: {state_root}\n  recorded: {}",
+                    recorded.state_root
+                ));
+
+                // The replayed state is in the overlay, on top of the recorded one.
+                let diffs = diff_state_trees(&store, &recorded.state_root, &state_root)
+                    .context("failed to compare the state trees")?;
+
+                println!("changes from the recorded to the replayed state:");
+                print_actor_diffs(&diffs);
+            }
+        }
+        Err(e) => {
+            tracing::warn!(
+                error = format!("{e:#}"),
+                "cannot compare the state root with the one recorded"
+            );
+        }
+    }
+
+    Ok(Replay {
+        state_root,
+        mismatches,
+    })
+}
+
+/// Record a mismatch if the replayed value differs from the recorded one.
+fn check<T>(mismatches: &mut Vec<String>, what: &str, replayed: &T, recorded: &T)
+where
+    T: PartialEq + Debug,
+{
+    if replayed != recorded {
+        mismatches.push(format!(
+            "{what}:\n  replayed: {replayed:?}\n  recorded: {recorded:?}"
+        ));
+    }
+}
+
+fn print_actor_diffs(diffs: &[ActorDiff]) {
+    if diffs.is_empty() {
+        println!("no actors changed");
+        return;
+    }
+
+    for diff in diffs {
+        let id = diff.id;
+
+        match (&diff.before, &diff.after) {
+            (None, Some(a)) => {
+                println!(
+                    "actor {id} created: code {}, balance {}, nonce {}",
+                    a.code, a.balance, a.sequence
+                );
+                if let Some(ref addr) = a.delegated_address {
+                    println!("  delegated address: {addr}");
+                }
+            }
+            (Some(_), None) => println!("actor {id} deleted"),
+            (Some(b), Some(a)) => {
+                println!("actor {id} changed:");
+                if b.code != a.code {
+                    println!("  code: {} -> {}", b.code, a.code);
+                }
+                if b.balance != a.balance {
+                    println!("  balance: {} -> {}", b.balance, a.balance);
+                }
+                if b.sequence != a.sequence {
+                    println!("  nonce: {} -> {}", b.sequence, a.sequence);
+                }
+                if b.state != a.state {
+                    println!("  state: {} -> {}", b.state, a.state);
+                }
+                if b.delegated_address != a.delegated_address {
+                    println!(
+                        "  delegated address: {:?} -> {:?}",
+                        b.delegated_address, a.delegated_address
+                    );
+                }
+            }
+            (None, None) => {}
+        }
+
+        for slot in diff.storage.iter() {
+            println!(
+                "  slot {:#x}: {:#x} -> {:#x}",
+                slot.slot, slot.before, slot.after
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use fendermint_abci::Application;
+    use fendermint_crypto::SecretKey;
+    use fendermint_rocksdb::blockstore::NamespaceBlockstore;
+    use fendermint_rocksdb::RocksDb;
+    use fendermint_rpc::message::{GasParams, SignedMessageFactory};
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use fendermint_vm_core::Timestamp;
+    use fendermint_vm_genesis::ipc::{GatewayParams, IpcParams};
+    use fendermint_vm_genesis::{
+        Account, Actor, ActorMeta, Collateral, Genesis, PermissionMode, SignerAddr, Validator,
+        ValidatorKey,
+    };
+    use fendermint_vm_interpreter::fvm::bundle::contracts_path;
+    use fendermint_vm_interpreter::fvm::end_block_hook::EndBlockManager;
+    use fendermint_vm_interpreter::fvm::interpreter::FvmMessagesInterpreter;
+    use fendermint_vm_interpreter::fvm::topdown::TopDownManager;
+    use fendermint_vm_interpreter::fvm::upgrades::UpgradeScheduler;
+    use fendermint_vm_interpreter::genesis::{GenesisAppState, GenesisBuilder};
+    use fendermint_vm_topdown::voting::VoteTally;
+    use fendermint_vm_topdown::Toggle;
+    use fvm_ipld_blockstore::Blockstore;
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::address::Address;
+    use fvm_shared::bigint::Zero;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::version::NetworkVersion;
+    use fvm_shared::METHOD_SEND;
+    use ipc_api::subnet_id::SubnetID;
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use tendermint::abci::{request, types::CommitInfo};
+    use tendermint::block::{self, Height};
+    use tendermint::{account, Hash};
+    use tendermint_rpc::endpoint::block_results;
+
+    use super::replay_recorded_block;
+    use crate::service::node::StateHistory;
+    use crate::{App, AppConfig, AppStore};
+
+    const CHAIN_ID: u64 = 1942764459484029;
+    const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
+    type TestApp =
+        App<RocksDb, NamespaceBlockstore, AppStore, FvmMessagesInterpreter<NamespaceBlockstore>>;
+
+    /// An interpreter like the node's, without a parent to follow.
+    fn new_interpreter<DB>() -> FvmMessagesInterpreter<DB>
+    where
+        DB: Blockstore + Clone + Send + Sync + 'static,
+    {
+        FvmMessagesInterpreter::new(
+            EndBlockManager::new(),
+            TopDownManager::new(Arc::new(Toggle::disabled()), VoteTally::empty()),
+            UpgradeScheduler::new(),
+            true,
+            1000,
+            1.25,
+            1.25,
+        )
+    }
+
+    fn new_app(history: &StateHistory) -> TestApp {
+        App::new(
+            AppConfig {
+                app_namespace: "app".to_string(),
+                state_hist_namespace: "state_hist".to_string(),
+                state_hist_size: 0,
+                halt_height: 0,
+            },
+            history.db().clone(),
+            history.state_store().clone(),
+            new_interpreter(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// A genesis with a single validator and a single funded account.
+    async fn app_state_bytes(validator: &SecretKey, sender: Address) -> Bytes {
+        let genesis = Genesis {
+            chain_name: "replay".to_string(),
+            chain_id: CHAIN_ID,
+            timestamp: Timestamp(GENESIS_TIMESTAMP),
+            network_version: NetworkVersion::V21,
+            base_fee: TokenAmount::from_atto(1000),
+            power_scale: 0,
+            validators: vec![Validator {
+                public_key: ValidatorKey::new(validator.public_key()),
+                power: Collateral(TokenAmount::from_whole(10)),
+            }],
+            accounts: vec![Actor {
+                meta: ActorMeta::Account(Account {
+                    owner: SignerAddr(sender),
+                }),
+                balance: TokenAmount::from_whole(100),
+            }],
+            eam_permission_mode: PermissionMode::Unrestricted,
+            ipc: Some(IpcParams {
+                gateway: GatewayParams {
+                    subnet_id: SubnetID::new_root(CHAIN_ID),
+                    bottom_up_check_period: 10,
+                    majority_percentage: 67,
+                    active_validators_limit: 100,
+                },
+            }),
+            ipc_contracts_owner: ethers::types::Address::zero(),
+            f3: None,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let car_path = dir.path().join("genesis.car");
+
+        GenesisBuilder::new(
+            actors_builtin_car::CAR,
+            actors_custom_car::CAR,
+            contracts_path(),
+            genesis,
+        )
+        .write_to(car_path.clone())
+        .await
+        .unwrap();
+
+        let car = std::fs::read(&car_path).unwrap();
+        let app_state = GenesisAppState::v1(car).compress_and_encode().unwrap();
+
+        Bytes::from(serde_json::to_vec(&app_state).unwrap())
+    }
+
+    /// Consensus parameters based on the defaults of `cometbft init`.
+    fn consensus_params() -> tendermint::consensus::Params {
+        tendermint::consensus::Params {
+            block: tendermint::block::Size {
+                max_bytes: 22020096,
+                max_gas: -1,
+                time_iota_ms: tendermint::block::Size::default_time_iota_ms(),
+            },
+            evidence: tendermint::evidence::Params {
+                max_age_num_blocks: 100000,
+                max_age_duration: tendermint::evidence::Duration(std::time::Duration::from_nanos(
+                    172800000000000,
+                )),
+                max_bytes: 1048576,
+            },
+            validator: tendermint::consensus::params::ValidatorParams {
+                pub_key_types: vec![tendermint::public_key::Algorithm::Secp256k1],
+            },
+            version: Some(tendermint::consensus::params::VersionParams { app: 0 }),
+        }
+    }
+
+    /// Commit a block with a transfer on an application backed by RocksDB, the way CometBFT
+    /// would drive it, then replay the block from the database and expect the same outcome.
+    #[tokio::test]
+    async fn replayed_block_matches_the_record() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let validator_key = SecretKey::random(&mut rng);
+        let sender_key = SecretKey::random(&mut rng);
+        let sender = Address::new_secp256k1(&sender_key.public_key().serialize()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let history = StateHistory::open(dir.path()).unwrap();
+        let app = new_app(&history);
+
+        let time = tendermint::Time::from_unix_timestamp(GENESIS_TIMESTAMP as i64, 0).unwrap();
+
+        let genesis = app
+            .init_chain(request::InitChain {
+                time,
+                chain_id: CHAIN_ID.to_string(),
+                consensus_params: consensus_params(),
+                validators: Vec::new(),
+                app_state_bytes: app_state_bytes(&validator_key, sender).await,
+                initial_height: Height::from(1u32),
+            })
+            .await
+            .unwrap();
+
+        let tx = SignedMessageFactory::new_secp256k1(sender_key, 0, CHAIN_ID.into())
+            .transaction(
+                Address::from(EthAddress([0xaa; 20])),
+                METHOD_SEND,
+                RawBytes::default(),
+                TokenAmount::from_whole(1),
+                GasParams {
+                    gas_limit: 10_000_000,
+                    gas_fee_cap: TokenAmount::from_atto(1_000_000_000),
+                    gas_premium: TokenAmount::zero(),
+                },
+            )
+            .await
+            .unwrap();
+        let tx = fvm_ipld_encoding::to_vec(&tx).unwrap();
+
+        let proposer =
+            tendermint::PublicKey::try_from(ValidatorKey::new(validator_key.public_key()))
+                .map(account::Id::from)
+                .unwrap();
+
+        let header = block::Header {
+            version: block::header::Version { block: 11, app: 0 },
+            chain_id: tendermint::chain::Id::try_from(CHAIN_ID.to_string()).unwrap(),
+            height: Height::from(1u32),
+            time,
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::None,
+            next_validators_hash: Hash::None,
+            consensus_hash: Hash::None,
+            app_hash: genesis.app_hash,
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: proposer,
+        };
+
+        let begin = app
+            .begin_block(request::BeginBlock {
+                hash: header.hash(),
+                header: header.clone(),
+                last_commit_info: CommitInfo {
+                    round: block::Round::default(),
+                    votes: Vec::new(),
+                },
+                byzantine_validators: Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        let deliver = app
+            .deliver_tx(request::DeliverTx {
+                tx: tx.clone().into(),
+            })
+            .await
+            .unwrap();
+
+        assert!(deliver.code.is_ok(), "the transfer should succeed");
+
+        let end = app
+            .end_block(request::EndBlock { height: 1 })
+            .await
+            .unwrap();
+
+        app.commit().await.unwrap();
+
+        let block = tendermint::Block::new(
+            header,
+            vec![tx],
+            tendermint::evidence::Data::default(),
+            None,
+        )
+        .unwrap();
+
+        let results = block_results::Response {
+            height: block.header.height,
+            txs_results: Some(vec![deliver]),
+            begin_block_events: Some(begin.events),
+            end_block_events: Some(end.events),
+            validator_updates: end.validator_updates,
+            consensus_param_updates: end.consensus_param_updates,
+        };
+
+        let replay = replay_recorded_block(&history, &new_interpreter(), &block, results)
+            .await
+            .unwrap();
+
+        assert!(replay.mismatches.is_empty(), "{:?}", replay.mismatches);
+        assert_eq!(
+            replay.state_root,
+            history.state_params(2).unwrap().state_root
+        );
+    }
+}
@@ -1,30 +1,38 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use fendermint_actor_f3_light_client::types;
 use fendermint_crypto::PublicKey;
 use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
 use ipc_api::subnet_id::SubnetID;
 use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
 use ipc_provider::jsonrpc::JsonRpcClientImpl;
 use ipc_provider::lotus::client::LotusJsonRPCClient;
 use ipc_provider::lotus::LotusClient;
 use ipc_provider::IpcProvider;
+use num_traits::{Num, Zero};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_core::{chainid, Timestamp};
 use fendermint_vm_genesis::{
-    ipc, Account, Actor, ActorMeta, Collateral, Genesis, Multisig, PermissionMode, SignerAddr,
-    Validator, ValidatorKey,
+    ipc, Account, Actor, ActorMeta, Collateral, Contract, Genesis, Multisig, PermissionMode,
+    SignerAddr, Validator, ValidatorKey,
 };
-use fendermint_vm_interpreter::genesis::{GenesisAppState, GenesisBuilder};
+use fendermint_vm_interpreter::genesis::{GenesisAppState, GenesisBuilder, GenesisFork};
 
 use crate::fs;
+use crate::service::node::open_state_at_height;
 
 use crate::cmd;
 use crate::options::genesis::*;
+use crate::options::parse::parse_h256;
 
 use super::key::read_public_key;
 
@@ -36,9 +44,12 @@ cmd! {
         GenesisCommands::New(args) => args.exec(genesis_file).await,
         GenesisCommands::AddAccount(args) => args.exec(genesis_file).await,
         GenesisCommands::AddMultisig(args) => args.exec(genesis_file).await,
+        GenesisCommands::AddContract(args) => args.exec(genesis_file).await,
+        GenesisCommands::ImportGeth(args) => args.exec(genesis_file).await,
         GenesisCommands::AddValidator(args) => args.exec(genesis_file).await,
         GenesisCommands::SetChainId(args) => args.exec(genesis_file).await,
         GenesisCommands::IntoTendermint(args) => args.exec(genesis_file).await,
+        GenesisCommands::Fork(args) => args.exec(genesis_file).await,
         GenesisCommands::SetEamPermissions(args) => args.exec(genesis_file).await,
         GenesisCommands::Ipc { command } => command.exec(genesis_file).await,
     }
@@ -81,6 +92,18 @@ cmd! {
   }
 }
 
+cmd! {
+  GenesisAddContractArgs(self, genesis_file: PathBuf) {
+    add_contract(&genesis_file, self)
+  }
+}
+
+cmd! {
+  GenesisImportGethArgs(self, genesis_file: PathBuf) {
+    import_geth(&genesis_file, self)
+  }
+}
+
 cmd! {
   GenesisAddValidatorArgs(self, genesis_file: PathBuf) {
     add_validator(&genesis_file, self)
@@ -98,6 +121,12 @@ cmd! {
   }
 }
 
+cmd! {
+  GenesisForkArgs(self, genesis_file: PathBuf) {
+    fork_genesis(&genesis_file, self).await
+  }
+}
+
 cmd! {
   GenesisSetEAMPermissionsArgs(self, genesis_file: PathBuf) {
     set_eam_permissions(&genesis_file, self)
@@ -187,6 +216,168 @@ fn add_multisig(genesis_file: &PathBuf, args: &GenesisAddMultisigArgs) -> anyhow
     })
 }
 
+fn add_contract(genesis_file: &PathBuf, args: &GenesisAddContractArgs) -> anyhow::Result<()> {
+    update_genesis(genesis_file, |mut genesis| {
+        let code = fs::read_to_string(&args.code).context("failed to read contract code")?;
+        let code = hex::decode(code.trim().trim_start_matches("0x"))
+            .context("contract code is not valid hex")?;
+
+        let contract = Contract {
+            address: args.address,
+            code: code.into(),
+            storage: args.storage.iter().cloned().collect(),
+            nonce: args.nonce,
+        };
+
+        push_contract(&mut genesis, contract, args.balance.clone())?;
+
+        Ok(genesis)
+    })
+}
+
+fn import_geth(genesis_file: &PathBuf, args: &GenesisImportGethArgs) -> anyhow::Result<()> {
+    let json = fs::read_to_string(&args.file).context("failed to read geth genesis")?;
+    let geth =
+        serde_json::from_str::<GethGenesis>(&json).context("failed to parse geth genesis")?;
+
+    update_genesis(genesis_file, |mut genesis| {
+        for (address, alloc) in geth.alloc {
+            let address = ethers::types::Address::from_str(address.trim_start_matches("0x"))
+                .with_context(|| format!("invalid alloc address: {address}"))?;
+
+            let balance = match alloc.balance {
+                Some(b) => TokenAmount::from_atto(
+                    parse_geth_number(&b)
+                        .with_context(|| format!("invalid balance of {address:?}"))?,
+                ),
+                None => TokenAmount::zero(),
+            };
+
+            let nonce = match alloc.nonce {
+                Some(n) => u64::try_from(
+                    parse_geth_number(&n)
+                        .with_context(|| format!("invalid nonce of {address:?}"))?,
+                )
+                .with_context(|| format!("nonce of {address:?} out of range"))?,
+                None => 0,
+            };
+
+            let code = match alloc.code {
+                Some(c) => hex::decode(c.trim_start_matches("0x"))
+                    .with_context(|| format!("invalid code of {address:?}"))?,
+                None => Vec::new(),
+            };
+
+            if code.is_empty() {
+                if !alloc.storage.is_empty() {
+                    bail!("account {address:?} has storage but no code");
+                }
+                if nonce != 0 {
+                    bail!("account {address:?} has a nonce, which is only supported for contracts");
+                }
+                if has_eth_address(&genesis, &address) {
+                    bail!("account {address:?} already exists in the genesis file");
+                }
+                genesis.accounts.push(Actor {
+                    meta: ActorMeta::Account(Account {
+                        owner: SignerAddr(Address::from(EthAddress::from(address))),
+                    }),
+                    balance,
+                });
+            } else {
+                let mut storage = BTreeMap::new();
+                for (k, v) in alloc.storage {
+                    let k = parse_h256(&k).map_err(|e| anyhow!("invalid storage key: {e}"))?;
+                    let v = parse_h256(&v).map_err(|e| anyhow!("invalid storage value: {e}"))?;
+                    storage.insert(k, v);
+                }
+                let contract = Contract {
+                    address,
+                    code: code.into(),
+                    storage,
+                    nonce,
+                };
+                push_contract(&mut genesis, contract, balance)?;
+            }
+        }
+        Ok(genesis)
+    })
+}
+
+/// Add a contract to the genesis, unless there is already an actor at the same address.
+fn push_contract(
+    genesis: &mut Genesis,
+    contract: Contract,
+    balance: TokenAmount,
+) -> anyhow::Result<()> {
+    if contract.code.is_empty() {
+        bail!("contract {:?} has no code", contract.address);
+    }
+    if contract.code.first() == Some(&0xef) {
+        bail!(
+            "contract {:?} code cannot start with 0xEF",
+            contract.address
+        );
+    }
+    if has_eth_address(genesis, &contract.address) {
+        bail!(
+            "an actor with address {:?} already exists in the genesis file",
+            contract.address
+        );
+    }
+    genesis.accounts.push(Actor {
+        meta: ActorMeta::Contract(contract),
+        balance,
+    });
+    Ok(())
+}
+
+/// Check whether any account or contract in the genesis would end up with the same delegated address.
+fn has_eth_address(genesis: &Genesis, address: &ethers::types::Address) -> bool {
+    let f4_addr = Address::from(EthAddress::from(*address));
+    genesis.accounts.iter().any(|a| match &a.meta {
+        ActorMeta::Account(acc) => acc.owner.0 == f4_addr,
+        ActorMeta::Multisig(_) => false,
+        ActorMeta::Contract(c) => c.address == *address,
+    })
+}
+
+/// Parse a number the way geth does in its genesis file: a hex string, a decimal string or a JSON number.
+fn parse_geth_number(value: &serde_json::Value) -> anyhow::Result<BigInt> {
+    match value {
+        serde_json::Value::String(s) => {
+            let n = match s.strip_prefix("0x") {
+                Some(h) => BigInt::from_str_radix(h, 16)?,
+                None => BigInt::from_str_radix(s, 10)?,
+            };
+            if n < BigInt::zero() {
+                bail!("not a non-negative integer: {s}");
+            }
+            Ok(n)
+        }
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .map(BigInt::from)
+            .ok_or_else(|| anyhow!("not a non-negative integer: {n}")),
+        other => bail!("not a number: {other}"),
+    }
+}
+
+/// The parts of a geth genesis file we can import.
+#[derive(Deserialize)]
+struct GethGenesis {
+    alloc: BTreeMap<String, GethAlloc>,
+}
+
+#[derive(Deserialize)]
+struct GethAlloc {
+    balance: Option<serde_json::Value>,
+    nonce: Option<serde_json::Value>,
+    code: Option<String>,
+    #[serde(default)]
+    storage: BTreeMap<String, String>,
+}
+
 fn add_validator(genesis_file: &PathBuf, args: &GenesisAddValidatorArgs) -> anyhow::Result<()> {
     update_genesis(genesis_file, |mut genesis| {
         let pk = read_public_key(&args.public_key)?;
@@ -315,6 +506,27 @@ fn set_ipc_gateway(genesis_file: &PathBuf, args: &GenesisIpcGatewayArgs) -> anyh
 }
 
 pub async fn seal_genesis(genesis_file: &PathBuf, args: &SealGenesisArgs) -> anyhow::Result<()> {
+    seal_genesis_with_fork(genesis_file, args, None).await
+}
+
+async fn fork_genesis(genesis_file: &PathBuf, args: &GenesisForkArgs) -> anyhow::Result<()> {
+    let fork = match (&args.snapshot, &args.data_dir, args.height) {
+        (Some(snapshot), _, _) => GenesisFork::from_snapshot(snapshot).await?,
+        (None, Some(data_dir), Some(height)) => {
+            let (store, state_params) = open_state_at_height(data_dir, height)?;
+            GenesisFork::from_store(store, state_params)
+        }
+        _ => bail!("either a snapshot or a data directory and height are required"),
+    };
+
+    seal_genesis_with_fork(genesis_file, &args.seal, Some(fork)).await
+}
+
+async fn seal_genesis_with_fork(
+    genesis_file: &PathBuf,
+    args: &SealGenesisArgs,
+    fork: Option<GenesisFork>,
+) -> anyhow::Result<()> {
     let genesis_params = read_genesis(genesis_file)?;
 
     fn actors_car_blob(
@@ -347,6 +559,11 @@ pub async fn seal_genesis(genesis_file: &PathBuf, args: &SealGenesisArgs) -> any
         genesis_params,
     );
 
+    let builder = match fork {
+        Some(fork) => builder.with_fork(fork),
+        None => builder,
+    };
+
     builder.write_to(args.output_path.clone()).await
 }
 
@@ -7,7 +7,10 @@ use std::sync::Arc;
 
 use crate::{
     options::{Commands, Options},
-    settings::{utils::expand_tilde, Settings},
+    settings::{
+        utils::{expand_path, expand_tilde},
+        Settings,
+    },
 };
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
@@ -72,7 +75,7 @@ pub async fn exec(opts: Arc<Options>) -> anyhow::Result<()> {
         Commands::Config(args) => args.exec(opts.clone()).await,
         Commands::Debug(args) => {
             let _trace_file_guard = set_global_tracing_subscriber(&TracingSettings::default());
-            args.exec(()).await
+            args.exec(opts.clone()).await
         }
         Commands::Run(args) => {
             let settings = load_settings(opts.clone())?;
@@ -92,7 +95,13 @@ pub async fn exec(opts: Arc<Options>) -> anyhow::Result<()> {
             args.exec(()).await
         }
         Commands::Eth(args) => {
-            let settings = load_settings(opts.clone())?.eth;
+            let settings = load_settings(opts.clone())?;
+            let home_dir = settings.home_dir().to_path_buf();
+            let mut settings = settings.eth;
+            settings.gas.fee_index_path = settings
+                .gas
+                .fee_index_path
+                .map(|p| expand_path(&home_dir, &p));
             let _trace_file_guard = set_global_tracing_subscriber(&settings.tracing);
             args.exec(settings).await
         }
@@ -4,8 +4,9 @@
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
 use bytes::Bytes;
 use fendermint_app_options::genesis::AccountKind;
@@ -22,6 +23,7 @@ use fvm_ipld_encoding::RawBytes;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::MethodNum;
+use ipc_wallet::{RemoteSigner, RemoteSignerConfig};
 use serde::Serialize;
 use serde_json::json;
 use tendermint::abci::response::DeliverTx;
@@ -327,10 +329,27 @@ struct TransClient {
 
 impl TransClient {
     pub fn new(client: FendermintClient, args: &TransArgs) -> anyhow::Result<Self> {
-        let sk = read_secret_key(&args.secret_key)?;
-        let addr = to_address(&sk, &args.account_kind)?;
         let chain_id = chainid::from_str_hashed(&args.chain_name)?;
-        let mf = SignedMessageFactory::new(sk, addr, args.sequence, chain_id);
+        let mf = match (&args.secret_key, &args.remote_signer_url) {
+            (Some(secret_key), _) => {
+                let sk = read_secret_key(secret_key)?;
+                let addr = to_address(&sk, &args.account_kind)?;
+                SignedMessageFactory::new(sk, addr, args.sequence, chain_id)
+            }
+            (None, Some(url)) => {
+                let config = RemoteSignerConfig {
+                    url: url.clone(),
+                    address: args
+                        .remote_signer_address
+                        .clone()
+                        .context("the remote signer address is required")?,
+                    auth_token: args.remote_signer_token.clone(),
+                };
+                let signer = RemoteSigner::from_config(&config)?;
+                SignedMessageFactory::new_with_signer(Arc::new(signer), args.sequence, chain_id)
+            }
+            (None, None) => bail!("either a secret key or a remote signer is required"),
+        };
         let client = client.bind(mf);
         let client = Self {
             inner: client,
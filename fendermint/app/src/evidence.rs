@@ -0,0 +1,376 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Evidence of validators equivocating on parent finality votes.
+//!
+//! The [`VoteTally`](fendermint_vm_topdown::voting::VoteTally) rejects a second vote from
+//! the same validator for a different block hash at the same height, but only the tally
+//! itself knows about the first vote. The [`EvidenceCollector`] keeps the signed envelopes
+//! of the votes the tally accepted, so that when it rejects a conflicting one we can pair
+//! the two up and persist them as [`EquivocationEvidence`] that anyone can verify.
+//!
+//! The evidence is only collected locally, for operators to inspect with `fendermint debug evidence`.
+//! Submitting it to the parent as a misbehaviour report is left for a follow-up, because neither
+//! the gateway nor the subnet actor contracts have an entrypoint to accept or act on such reports.
+//! It needs one on the subnet actor verifying the envelopes, and a method on `SubnetManager` in
+//! `ipc_provider` to call it, before the node can submit what it collects here.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use fendermint_vm_topdown::IPCParentFinality;
+use fs_err as fs;
+use ipc_ipld_resolver::{SignedVoteRecord, ValidatorKey};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::ipc::AppVote;
+use crate::BlockHeight;
+
+/// Name of the directory under the data directory where evidence is stored.
+pub const EVIDENCE_DIR: &str = "evidence";
+
+/// One of the conflicting votes, with the signed envelope it arrived in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedVoteEvidence {
+    /// Hex encoded block hash the validator voted for.
+    pub block_hash: String,
+    /// Timestamp of the vote, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Hex encoded protobuf of the signed envelope, as gossiped by the validator.
+    pub envelope: String,
+}
+
+impl SignedVoteEvidence {
+    fn new(vote: &SignedVoteRecord<AppVote>, finality: &IPCParentFinality) -> Self {
+        Self {
+            block_hash: hex::encode(&finality.block_hash),
+            timestamp: vote.record().timestamp.as_secs(),
+            envelope: hex::encode(vote.envelope().clone().into_protobuf_encoding()),
+        }
+    }
+
+    /// Decode the envelope and check its signature.
+    pub fn signed_vote(&self) -> anyhow::Result<SignedVoteRecord<AppVote>> {
+        let bytes = hex::decode(&self.envelope).context("envelope is not hex")?;
+        SignedVoteRecord::from_bytes(&bytes)
+    }
+}
+
+/// Two signed votes from the same validator for different block hashes at the same height.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EquivocationEvidence {
+    /// Peer ID of the validator, derived from its public key.
+    pub validator: String,
+    /// The subnet in which the votes were cast.
+    pub subnet_id: String,
+    /// The parent block height both votes are about.
+    pub height: BlockHeight,
+    /// The vote the tally accepted first, followed by the one it rejected.
+    pub votes: [SignedVoteEvidence; 2],
+}
+
+impl EquivocationEvidence {
+    /// Pair up two votes, checking that they are actually in conflict.
+    pub fn new(
+        first: &SignedVoteRecord<AppVote>,
+        second: &SignedVoteRecord<AppVote>,
+    ) -> anyhow::Result<Self> {
+        let (a, b) = (first.record(), second.record());
+        let (Some(fa), Some(fb)) = (parent_finality(first), parent_finality(second)) else {
+            bail!("votes are not about parent finality");
+        };
+
+        if a.public_key != b.public_key {
+            bail!("votes are from different validators");
+        }
+        if a.subnet_id != b.subnet_id {
+            bail!("votes are from different subnets");
+        }
+        if fa.height != fb.height {
+            bail!("votes are for different heights");
+        }
+        if fa.block_hash == fb.block_hash {
+            bail!("votes are for the same block hash");
+        }
+
+        Ok(Self {
+            validator: a.public_key.to_string(),
+            subnet_id: a.subnet_id.to_string(),
+            height: fa.height,
+            votes: [
+                SignedVoteEvidence::new(first, fa),
+                SignedVoteEvidence::new(second, fb),
+            ],
+        })
+    }
+
+    /// Check the signatures on the envelopes and that they really are conflicting votes
+    /// by the validator the evidence is about.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let first = self.votes[0].signed_vote().context("invalid first vote")?;
+        let second = self.votes[1].signed_vote().context("invalid second vote")?;
+        let evidence = Self::new(&first, &second)?;
+        if evidence != *self {
+            return Err(anyhow!("evidence does not match the signed votes"));
+        }
+        Ok(())
+    }
+}
+
+/// Persists evidence as JSON files in a directory, one file per validator and height.
+#[derive(Debug, Clone)]
+pub struct EvidenceStore {
+    dir: PathBuf,
+}
+
+impl EvidenceStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file of the evidence against a validator at a height.
+    ///
+    /// The validator is parsed as a peer ID and written in its canonical form, so that a name
+    /// coming from the command line can't point outside the directory.
+    fn path(&self, validator: &str, height: BlockHeight) -> anyhow::Result<PathBuf> {
+        let validator = PeerId::from_str(validator)
+            .map_err(|e| anyhow!("invalid validator peer ID {validator:?}: {e}"))?;
+        Ok(self.dir.join(format!("{height}-{validator}.json")))
+    }
+
+    /// Write the evidence to disk, unless we already have evidence for the same incident.
+    ///
+    /// Returns `true` if the evidence was new.
+    pub fn put(&self, evidence: &EquivocationEvidence) -> anyhow::Result<bool> {
+        let path = self.path(&evidence.validator, evidence.height)?;
+        if path.exists() {
+            return Ok(false);
+        }
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(evidence)?;
+        fs::write(path, json)?;
+        Ok(true)
+    }
+
+    /// Read the evidence collected about a validator at a given height.
+    pub fn get(
+        &self,
+        validator: &str,
+        height: BlockHeight,
+    ) -> anyhow::Result<Option<EquivocationEvidence>> {
+        let path = self.path(validator, height)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::read(&path).map(Some)
+    }
+
+    /// Read all evidence, ordered by height.
+    pub fn list(&self) -> anyhow::Result<Vec<EquivocationEvidence>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut all = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().map(|e| e == "json").unwrap_or_default() {
+                all.push(Self::read(&path)?);
+            }
+        }
+        all.sort_by(|a, b| (a.height, &a.validator).cmp(&(b.height, &b.validator)));
+        Ok(all)
+    }
+
+    fn read(path: &Path) -> anyhow::Result<EquivocationEvidence> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).with_context(|| format!("failed to parse {path:?}"))
+    }
+}
+
+/// Remembers the signed votes accepted by the tally, until the height they are about
+/// is finalized, so they can be paired with conflicting votes as evidence.
+pub struct EvidenceCollector {
+    store: EvidenceStore,
+    votes: BTreeMap<BlockHeight, HashMap<ValidatorKey, SignedVoteRecord<AppVote>>>,
+}
+
+impl EvidenceCollector {
+    pub fn new(store: EvidenceStore) -> Self {
+        Self {
+            store,
+            votes: Default::default(),
+        }
+    }
+
+    /// Remember a vote which has been accepted by the tally.
+    ///
+    /// Only the first vote of a validator at any height is kept; anything conflicting
+    /// with it would have been rejected by the tally.
+    pub fn add_vote(&mut self, vote: SignedVoteRecord<AppVote>) {
+        let Some(height) = parent_finality(&vote).map(|f| f.height) else {
+            return;
+        };
+        self.votes
+            .entry(height)
+            .or_default()
+            .entry(vote.record().public_key.clone())
+            .or_insert(vote);
+    }
+
+    /// Pair up a vote rejected by the tally as an equivocation with the one it conflicts with,
+    /// and persist it as evidence.
+    ///
+    /// Returns the evidence if it was new, or `None` if we have already seen it, or if we
+    /// don't have the original vote, e.g. because it was received before a restart.
+    pub fn add_equivocation(
+        &mut self,
+        vote: &SignedVoteRecord<AppVote>,
+    ) -> anyhow::Result<Option<EquivocationEvidence>> {
+        let Some(f) = parent_finality(vote) else {
+            return Ok(None);
+        };
+
+        let Some(first) = self
+            .votes
+            .get(&f.height)
+            .and_then(|vs| vs.get(&vote.record().public_key))
+        else {
+            return Ok(None);
+        };
+
+        let evidence = EquivocationEvidence::new(first, vote)?;
+
+        if self.store.put(&evidence)? {
+            Ok(Some(evidence))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Forget votes at or below a finalized height.
+    pub fn set_finalized(&mut self, height: BlockHeight) {
+        self.votes = self.votes.split_off(&(height + 1));
+    }
+}
+
+fn parent_finality(vote: &SignedVoteRecord<AppVote>) -> Option<&IPCParentFinality> {
+    match &vote.record().content {
+        AppVote::ParentFinality(f) => Some(f),
+        AppVote::CheckpointSignature(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fendermint_vm_topdown::IPCParentFinality;
+    use ipc_api::subnet_id::SubnetID;
+    use ipc_ipld_resolver::VoteRecord;
+    use libp2p::identity::Keypair;
+
+    use super::{EquivocationEvidence, EvidenceCollector, EvidenceStore};
+    use crate::ipc::AppVote;
+
+    fn vote(
+        key: &Keypair,
+        height: u64,
+        block_hash: &[u8],
+    ) -> ipc_ipld_resolver::SignedVoteRecord<AppVote> {
+        let content = AppVote::ParentFinality(IPCParentFinality {
+            height,
+            block_hash: block_hash.to_vec(),
+        });
+        VoteRecord::signed(key, SubnetID::new_root(123), content).unwrap()
+    }
+
+    #[test]
+    fn equivocation_evidence_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EvidenceStore::new(dir.path().join("evidence"));
+        let mut collector = EvidenceCollector::new(store.clone());
+
+        let key = Keypair::generate_secp256k1();
+        let other = Keypair::generate_secp256k1();
+
+        collector.add_vote(vote(&key, 10, b"foo"));
+        collector.add_vote(vote(&other, 10, b"foo"));
+
+        // No evidence without the original vote.
+        assert!(collector
+            .add_equivocation(&vote(&key, 11, b"bar"))
+            .unwrap()
+            .is_none());
+
+        let evidence = collector
+            .add_equivocation(&vote(&key, 10, b"bar"))
+            .unwrap()
+            .expect("should be new evidence");
+
+        assert_eq!(evidence.height, 10);
+        assert_eq!(evidence.validator, key.public().to_peer_id().to_string());
+        assert_eq!(evidence.votes[0].block_hash, hex::encode(b"foo"));
+        assert_eq!(evidence.votes[1].block_hash, hex::encode(b"bar"));
+        evidence.verify().unwrap();
+
+        // Repeated equivocation at the same height is the same incident.
+        assert!(collector
+            .add_equivocation(&vote(&key, 10, b"baz"))
+            .unwrap()
+            .is_none());
+
+        assert_eq!(store.list().unwrap(), vec![evidence.clone()]);
+        assert_eq!(store.get(&evidence.validator, 10).unwrap(), Some(evidence));
+
+        // Once finalized the original votes are forgotten.
+        collector.set_finalized(10);
+        assert!(collector.votes.is_empty());
+    }
+
+    #[test]
+    fn evidence_requires_conflict() {
+        let key = Keypair::generate_secp256k1();
+        let other = Keypair::generate_secp256k1();
+
+        assert!(
+            EquivocationEvidence::new(&vote(&key, 10, b"foo"), &vote(&key, 10, b"foo")).is_err()
+        );
+        assert!(
+            EquivocationEvidence::new(&vote(&key, 10, b"foo"), &vote(&key, 11, b"bar")).is_err()
+        );
+        assert!(
+            EquivocationEvidence::new(&vote(&key, 10, b"foo"), &vote(&other, 10, b"bar")).is_err()
+        );
+    }
+
+    #[test]
+    fn evidence_path_requires_a_peer_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EvidenceStore::new(dir.path().join("evidence"));
+
+        assert!(store.get("../../secret", 10).is_err());
+
+        let key = Keypair::generate_secp256k1();
+        let mut evidence =
+            EquivocationEvidence::new(&vote(&key, 10, b"foo"), &vote(&key, 10, b"bar")).unwrap();
+        evidence.validator = "../outside".into();
+        assert!(store.put(&evidence).is_err());
+        assert!(!dir.path().join("outside.json").exists());
+    }
+
+    #[test]
+    fn tampered_evidence_fails_verification() {
+        let key = Keypair::generate_secp256k1();
+        let mut evidence =
+            EquivocationEvidence::new(&vote(&key, 10, b"foo"), &vote(&key, 10, b"bar")).unwrap();
+
+        evidence.verify().unwrap();
+        evidence.votes[1].block_hash = hex::encode(b"baz");
+        assert!(evidence.verify().is_err());
+    }
+}
@@ -4,9 +4,12 @@
 
 use crate::app::{AppStoreKey, SubnetAppState};
 use crate::{App, BlockHeight};
+use anyhow::anyhow;
 use ethers::utils::keccak256;
 use fendermint_storage::{Codec, Encode, KVReadable, KVStore, KVWritable};
+use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_genesis::{Power, Validator};
+use fendermint_vm_interpreter::fvm::bottomup::{check_announcement, SignedBottomUpBatchRef};
 use fendermint_vm_interpreter::fvm::end_block_hook::LightClientCommitments;
 use fendermint_vm_interpreter::fvm::state::ipc::GatewayCaller;
 use fendermint_vm_interpreter::fvm::state::{FvmExecState, FvmStateParams};
@@ -18,6 +21,7 @@ use fvm_ipld_blockstore::Blockstore;
 use ipc_actors_abis::subnet_actor_checkpointing_facet::{
     AppHashBreakdown, Commitment, CompressedActivityRollup,
 };
+use ipc_api::checkpoint::CheckpointSignature;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -57,6 +61,8 @@ pub fn derive_subnet_app_hash(state: &SubnetAppState) -> tendermint::hash::AppHa
 pub enum AppVote {
     /// The validator considers a certain block final on the parent chain.
     ParentFinality(IPCParentFinality),
+    /// The validator certifies the app hash of the subnet at a bottom-up checkpoint height.
+    CheckpointSignature(CheckpointSignature),
 }
 
 /// Queries the LATEST COMMITTED parent finality from the storage
@@ -127,6 +133,41 @@ where
     }
 }
 
+/// Checks the bottom-up batch announcements of child subnets against the LATEST COMMITTED state.
+pub struct AppBottomUpAnnouncementQuery<DB, SS, S, I>
+where
+    SS: Blockstore + Clone + 'static + Send + Sync,
+    S: KVStore,
+    I: MessagesInterpreter<SS> + Send + Sync,
+{
+    app: App<DB, SS, S, I>,
+}
+
+impl<DB, SS, S, I> AppBottomUpAnnouncementQuery<DB, SS, S, I>
+where
+    S: KVStore
+        + Codec<SubnetAppState>
+        + Encode<AppStoreKey>
+        + Encode<BlockHeight>
+        + Codec<FvmStateParams>,
+    DB: KVWritable<S> + KVReadable<S> + 'static + Clone,
+    SS: Blockstore + Clone + 'static + Send + Sync,
+    I: MessagesInterpreter<SS> + Send + Sync,
+{
+    pub fn new(app: App<DB, SS, S, I>) -> Self {
+        Self { app }
+    }
+
+    /// Check that the announcement is signed by an active validator of the child subnet,
+    /// returning the signer.
+    pub fn check(&self, announcement: &SignedBottomUpBatchRef) -> anyhow::Result<EthAddress> {
+        match self.app.read_only_view(None)? {
+            Some(mut state) => check_announcement(&mut state, announcement),
+            None => Err(anyhow!("the state cannot be queried yet")),
+        }
+    }
+}
+
 fn abi_encode_tuple_manual(b: &AppHashBreakdown) -> Vec<u8> {
     use ethers::abi::{encode, Token};
     use ethers::types::U256;
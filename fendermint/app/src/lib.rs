@@ -1,7 +1,9 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 pub mod app;
+pub mod checkpoint;
 pub mod cmd;
+pub mod evidence;
 pub mod ipc;
 pub mod metrics;
 pub mod observe;
@@ -18,7 +20,7 @@ pub use fendermint_app_settings as settings;
 use fs_err as fs;
 
 pub use app::{App, AppConfig};
-pub use store::{AppStore, BitswapBlockstore};
+pub use store::{AppStore, BitswapBlockstore, ResolverPeerStore};
 
 // Different type from `ChainEpoch` just because we might use epoch in a more traditional sense for checkpointing.
 pub type BlockHeight = u64;
@@ -6,9 +6,12 @@
 pub mod eth {
     // TODO - migrate these metrics to new observability architecture
     use fendermint_eth_api::apis::RPC_METHOD_CALL_LATENCY_SECONDS;
+    use fendermint_eth_api::{RPC_REQUESTS_REJECTED, RPC_REQUEST_UNITS};
 
     pub fn register_metrics(registry: &prometheus::Registry) -> anyhow::Result<()> {
         registry.register(Box::new(RPC_METHOD_CALL_LATENCY_SECONDS.clone()))?;
+        registry.register(Box::new(RPC_REQUESTS_REJECTED.clone()))?;
+        registry.register(Box::new(RPC_REQUEST_UNITS.clone()))?;
         Ok(())
     }
 }
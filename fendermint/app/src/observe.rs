@@ -9,7 +9,10 @@ use ipc_observability::{
     impl_traceable, impl_traceables, lazy_static, register_metrics, serde::HexEncodableBlockHash,
     Recordable, TraceLevel, Traceable,
 };
-use prometheus::{register_counter_vec, register_int_gauge, CounterVec, IntGauge, Registry};
+use prometheus::{
+    register_counter_vec, register_int_counter, register_int_gauge, CounterVec, IntCounter,
+    IntGauge, Registry,
+};
 use tendermint::account::Id;
 
 register_metrics! {
@@ -24,6 +27,8 @@ register_metrics! {
     CONSENSUS_BLOCK_COMMITTED: IntGauge
         = register_int_gauge!("consensus_block_committed_height", "Block committed (last height)");
     MPOOL_RECEIVED: CounterVec = register_counter_vec!("mpool_received", "Message received in mpool", &["accept"]);
+    CONSENSUS_EQUIVOCATION_DETECTED: IntCounter
+        = register_int_counter!("consensus_equivocation_detected", "Equivocating parent finality votes detected");
 }
 
 impl_traceables!(
@@ -37,6 +42,8 @@ impl_traceables!(
 
 impl_traceables!(TraceLevel::Info, "Mpool", MpoolReceived);
 
+impl_traceables!(TraceLevel::Warn, "Consensus", EquivocationDetected<'a>);
+
 pub type BlockHeight = u64;
 
 #[derive(Debug)]
@@ -141,6 +148,18 @@ impl Recordable for MpoolReceived {
     }
 }
 
+#[derive(Debug)]
+pub struct EquivocationDetected<'a> {
+    pub validator: &'a str,
+    pub height: BlockHeight,
+}
+
+impl Recordable for EquivocationDetected<'_> {
+    fn record_metrics(&self) {
+        CONSENSUS_EQUIVOCATION_DETECTED.inc();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,5 +208,10 @@ mod tests {
             height: 1,
             app_hash: HexEncodableBlockHash(vec![0x01, 0x02, 0x03]),
         });
+
+        emit(EquivocationDetected {
+            validator: "16Uiu2HAm",
+            height: 1,
+        });
     }
 }
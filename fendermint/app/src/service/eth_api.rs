@@ -37,20 +37,40 @@ pub async fn run(
         min_gas_premium: settings.gas.min_gas_premium,
         num_blocks_max_prio_fee: settings.gas.num_blocks_max_prio_fee,
         max_fee_hist_size: settings.gas.max_fee_hist_size,
+        fee_index_path: settings.gas.fee_index_path,
     };
     let cors = fendermint_eth_api::CorsOpt {
         allowed_origins: settings.cors.allowed_origins,
         allowed_methods: settings.cors.allowed_methods,
         allowed_headers: settings.cors.allowed_headers,
     };
+    let access = fendermint_eth_api::AccessOpt {
+        auth_tokens: settings.access.auth_tokens,
+        jwt_secret: settings.access.jwt_secret.map(|s| s.into_bytes()),
+        max_batch_size: settings.access.max_batch_size,
+        allowed_methods: settings.access.allowed_methods,
+        denied_methods: settings.access.denied_methods,
+        max_ws_subscriptions: settings.access.max_ws_subscriptions,
+        rate_limit: settings
+            .access
+            .rate_limit
+            .map(|r| fendermint_eth_api::RateLimitOpt {
+                units_per_second: r.units_per_second,
+                burst: r.burst,
+                method_costs: r.method_costs,
+            }),
+        trusted_proxies: settings.access.trusted_proxies,
+    };
     fendermint_eth_api::listen(
         settings.listen,
         client,
         settings.filter_timeout,
         settings.cache_capacity,
         settings.max_nonce_gap,
+        settings.tx_sync_timeout,
         gas,
         cors,
+        access,
     )
     .await
 }
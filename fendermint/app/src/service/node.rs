@@ -2,28 +2,38 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::{anyhow, bail, Context};
-use async_stm::atomically_or_err;
+use async_stm::{atomically, atomically_or_err};
 use fendermint_abci::ApplicationService;
 use fendermint_crypto::SecretKey;
 use fendermint_rocksdb::{blockstore::NamespaceBlockstore, namespaces, RocksDb, RocksDbConfig};
+use fendermint_storage::{KVCollection, KVReadable};
 use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_interpreter::fvm::bottomup::{
+    BottomUpManager, BottomUpPool, SignedBottomUpBatchRef,
+};
 use fendermint_vm_interpreter::fvm::interpreter::FvmMessagesInterpreter;
 use fendermint_vm_interpreter::fvm::observe::register_metrics as register_interpreter_metrics;
+use fendermint_vm_interpreter::fvm::state::FvmStateParams;
 use fendermint_vm_interpreter::fvm::topdown::TopDownManager;
 use fendermint_vm_interpreter::fvm::upgrades::UpgradeScheduler;
+use fendermint_vm_resolver::ipld::IpldResolver;
 use fendermint_vm_snapshot::{SnapshotManager, SnapshotParams};
 use fendermint_vm_topdown::observe::register_metrics as register_topdown_metrics;
 use fendermint_vm_topdown::proxy::{IPCProviderProxy, IPCProviderProxyWithLatency};
 use fendermint_vm_topdown::sync::launch_polling_syncer;
 use fendermint_vm_topdown::voting::{publish_vote_loop, Error as VoteError, VoteTally};
 use fendermint_vm_topdown::{CachedFinalityProvider, IPCParentFinality, Toggle};
+use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::address::{current_network, Address, Network};
-use ipc_ipld_resolver::{Event as ResolverEvent, VoteRecord};
+use ipc_api::subnet_id::SubnetID;
+use ipc_ipld_resolver::{Event as ResolverEvent, SignedVoteRecord};
+use ipc_observability::emit;
 use ipc_observability::observe::register_metrics as register_default_metrics;
 use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
 use ipc_provider::IpcProvider;
 use libp2p::identity::secp256k1;
 use libp2p::identity::Keypair;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::broadcast::error::RecvError;
@@ -31,10 +41,12 @@ use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
 use tracing::info;
 
+use crate::checkpoint::{BottomUpBatchAnnouncer, CheckpointSigner, SignatureCollector};
 use crate::cmd::key::read_secret_key;
-use crate::ipc::{AppParentFinalityQuery, AppVote};
-use crate::observe::register_metrics as register_consensus_metrics;
-use crate::{App, AppConfig, AppStore, BitswapBlockstore};
+use crate::evidence::{EvidenceCollector, EvidenceStore, EVIDENCE_DIR};
+use crate::ipc::{AppBottomUpAnnouncementQuery, AppParentFinalityQuery, AppVote};
+use crate::observe::{register_metrics as register_consensus_metrics, EquivocationDetected};
+use crate::{App, AppConfig, AppStore, BitswapBlockstore, BlockHeight, ResolverPeerStore};
 use fendermint_app_settings::{AccountKind, Settings};
 
 use fendermint_vm_interpreter::fvm::end_block_hook::EndBlockManager;
@@ -45,7 +57,8 @@ namespaces! {
         app,
         state_hist,
         state_store,
-        bit_store
+        bit_store,
+        peer_store
     }
 }
 
@@ -107,12 +120,9 @@ pub async fn run(
         libp2p::identity::Keypair::from(kp)
     });
 
-    let testing_settings = match settings.testing.as_ref() {
-        Some(_) if current_network() == Network::Mainnet => {
-            bail!("testing settings are not allowed on Mainnet");
-        }
-        other => other,
-    };
+    if settings.testing.is_some() && current_network() == Network::Mainnet {
+        bail!("testing settings are not allowed on Mainnet");
+    }
 
     let ns = Namespaces::default();
     let db = open_db(&settings, &ns).context("error opening DB")?;
@@ -125,10 +135,32 @@ pub async fn run(
 
     let topdown_enabled = settings.topdown_enabled();
 
+    let checkpoint_signatures = SignatureCollector::default();
+    let mut checkpoint_signer = None;
+    let mut bottom_up_announcer = None;
+    let mut bottom_up_manager = None;
+    let mut dispatch_resolver = None;
+
     // If enabled, start a resolver that communicates with the application through the resolve pool.
     if settings.resolver_enabled() {
-        let mut service =
-            make_resolver_service(&settings, db.clone(), state_store.clone(), ns.bit_store)?;
+        // Blockstore for Bitswap.
+        let bit_store =
+            NamespaceBlockstore::new(db.clone(), ns.bit_store).context("error creating bit DB")?;
+
+        let mut service = make_resolver_service(&settings, state_store.clone(), bit_store.clone())?;
+
+        // Remember peers across restarts, so we can rejoin without the bootstrap nodes.
+        let peer_ttl = settings.resolver.discovery.peer_ttl;
+        if !peer_ttl.is_zero() {
+            let peer_store = ResolverPeerStore::new(db.clone(), ns.peer_store);
+            service = service
+                .with_peer_store(
+                    peer_store,
+                    peer_ttl,
+                    settings.resolver.discovery.max_remembered_peers,
+                )
+                .context("error loading the resolver peer store")?;
+        }
 
         // Register all metrics from the IPLD resolver stack
         if let Some(ref registry) = metrics_registry {
@@ -145,6 +177,37 @@ pub async fn run(
             .add_provided_subnet(own_subnet_id.clone())
             .context("error adding own provided subnet.")?;
 
+        if let (Some((sk, _)), Some(key)) = (&validator, &validator_keypair) {
+            checkpoint_signer = Some(CheckpointSigner::new(
+                sk.clone(),
+                key.clone(),
+                own_subnet_id.clone(),
+                client.clone(),
+                checkpoint_signatures.clone(),
+            ));
+            if own_subnet_id.parent().is_some() {
+                bottom_up_announcer = Some(BottomUpBatchAnnouncer::new(
+                    sk.clone(),
+                    own_subnet_id.clone(),
+                    client.clone(),
+                ));
+            }
+        }
+
+        // Resolve the bottom-up message batches announced by the child subnets.
+        let bottom_up_pool = BottomUpPool::new();
+        let resolver = IpldResolver::new(
+            client.clone(),
+            bottom_up_pool.queue(),
+            settings.resolver.retry_delay,
+            own_subnet_id.clone(),
+        );
+        tracing::info!("starting the bottom-up batch resolver...");
+        tokio::spawn(async move { resolver.run().await });
+
+        let manager = BottomUpManager::new(bottom_up_pool, bit_store);
+        bottom_up_manager = Some(manager.clone());
+
         if topdown_enabled {
             if let Some(key) = validator_keypair {
                 let parent_finality_votes = parent_finality_votes.clone();
@@ -172,8 +235,22 @@ pub async fn run(
         tracing::info!("subscribing to gossip...");
         let rx = service.subscribe();
         let parent_finality_votes = parent_finality_votes.clone();
-        tokio::spawn(async move {
-            dispatch_resolver_events(rx, parent_finality_votes, topdown_enabled).await;
+        let evidence =
+            EvidenceCollector::new(EvidenceStore::new(settings.data_dir().join(EVIDENCE_DIR)));
+        let checkpoint_signatures = checkpoint_signatures.clone();
+        let subnet_id = settings.ipc.subnet_id.clone();
+        // Started once the app is ready to check announcements against its state.
+        dispatch_resolver = Some(move |check_announcement: AnnouncementCheck| {
+            dispatch_resolver_events(
+                rx,
+                parent_finality_votes,
+                evidence,
+                checkpoint_signatures,
+                manager,
+                check_announcement,
+                subnet_id,
+                topdown_enabled,
+            )
         });
 
         tracing::info!("starting the IPLD Resolver Service...");
@@ -186,37 +263,7 @@ pub async fn run(
         tracing::info!("IPLD Resolver disabled.")
     }
 
-    let (parent_finality_provider, ipc_tuple) = if topdown_enabled {
-        info!("topdown finality enabled");
-        let topdown_config = settings.ipc.topdown_config()?;
-        let mut config = fendermint_vm_topdown::Config::new(
-            topdown_config.chain_head_delay,
-            topdown_config.polling_interval,
-            topdown_config.exponential_back_off,
-            topdown_config.exponential_retry_limit,
-        )
-        .with_proposal_delay(topdown_config.proposal_delay)
-        .with_max_proposal_range(topdown_config.max_proposal_range);
-
-        if let Some(v) = topdown_config.max_cache_blocks {
-            info!(value = v, "setting max cache blocks");
-            config = config.with_max_cache_blocks(v);
-        }
-
-        let ipc_provider = {
-            let p = make_ipc_provider_proxy(&settings)?;
-            Arc::new(IPCProviderProxyWithLatency::new(p))
-        };
-
-        let finality_provider =
-            CachedFinalityProvider::uninitialized(config.clone(), ipc_provider.clone()).await?;
-
-        let p = Arc::new(Toggle::enabled(finality_provider));
-        (p, Some((ipc_provider, config)))
-    } else {
-        info!("topdown finality disabled");
-        (Arc::new(Toggle::disabled()), None)
-    };
+    let (parent_finality_provider, ipc_tuple) = make_parent_finality_provider(&settings).await?;
 
     // Start a snapshot manager in the background.
     let snapshots = if settings.snapshots.enabled {
@@ -244,23 +291,14 @@ pub async fn run(
         None
     };
 
-    let end_block_manager = EndBlockManager::new();
-    let top_down_manager = TopDownManager::new(
+    let interpreter = make_interpreter(
+        &settings,
         parent_finality_provider.clone(),
         parent_finality_votes.clone(),
+        bottom_up_manager,
     );
 
-    let interpreter = FvmMessagesInterpreter::new(
-        end_block_manager,
-        top_down_manager,
-        UpgradeScheduler::new(),
-        testing_settings.is_none_or(|t| t.push_chain_meta),
-        settings.abci.block_max_msgs,
-        settings.fvm.gas_overestimation_rate,
-        settings.fvm.gas_search_step,
-    );
-
-    let app: App<_, _, AppStore, _> = App::new(
+    let mut app: App<_, _, AppStore, _> = App::new(
         AppConfig {
             app_namespace: ns.app,
             state_hist_namespace: ns.state_hist,
@@ -272,7 +310,18 @@ pub async fn run(
         state_store,
         interpreter,
         snapshots,
-    )?;
+    )?
+    .with_checkpoint_signatures(checkpoint_signatures, checkpoint_signer);
+
+    if let Some(announcer) = bottom_up_announcer {
+        app = app.with_bottom_up_announcer(announcer);
+    }
+
+    if let Some(dispatch) = dispatch_resolver {
+        let announcements = AppBottomUpAnnouncementQuery::new(app.clone());
+        let check_announcement: AnnouncementCheck = Arc::new(move |a| announcements.check(a));
+        tokio::spawn(dispatch(check_announcement));
+    }
 
     if let Some((agent_proxy, config)) = ipc_tuple {
         let app_parent_finality_query = AppParentFinalityQuery::new(app.clone());
@@ -370,15 +419,161 @@ fn open_db(settings: &Settings, ns: &Namespaces) -> anyhow::Result<RocksDb> {
     Ok(db)
 }
 
-fn make_resolver_service(
+type ParentFinalityProvider = Arc<Toggle<CachedFinalityProvider<IPCProviderProxyWithLatency>>>;
+
+/// What the parent syncer needs besides the provider.
+type ParentSyncerParams = (
+    Arc<IPCProviderProxyWithLatency>,
+    fendermint_vm_topdown::Config,
+);
+
+/// The top-down finality provider and, if top-down is enabled, what the parent syncer needs.
+pub(crate) async fn make_parent_finality_provider(
     settings: &Settings,
+) -> anyhow::Result<(ParentFinalityProvider, Option<ParentSyncerParams>)> {
+    if !settings.topdown_enabled() {
+        info!("topdown finality disabled");
+        return Ok((Arc::new(Toggle::disabled()), None));
+    }
+
+    info!("topdown finality enabled");
+    let topdown_config = settings.ipc.topdown_config()?;
+    let mut config = fendermint_vm_topdown::Config::new(
+        topdown_config.chain_head_delay,
+        topdown_config.polling_interval,
+        topdown_config.exponential_back_off,
+        topdown_config.exponential_retry_limit,
+    )
+    .with_proposal_delay(topdown_config.proposal_delay)
+    .with_max_proposal_range(topdown_config.max_proposal_range)
+    .with_use_finalized_tag(topdown_config.use_finalized_tag);
+
+    if let Some(v) = topdown_config.max_cache_blocks {
+        info!(value = v, "setting max cache blocks");
+        config = config.with_max_cache_blocks(v);
+    }
+
+    let ipc_provider = {
+        let p = make_ipc_provider_proxy(settings)?;
+        Arc::new(IPCProviderProxyWithLatency::new(p))
+    };
+
+    let finality_provider =
+        CachedFinalityProvider::uninitialized(config.clone(), ipc_provider.clone()).await?;
+
+    let p = Arc::new(Toggle::enabled(finality_provider));
+    Ok((p, Some((ipc_provider, config))))
+}
+
+/// Create the interpreter which executes the blocks, configured by the settings.
+pub(crate) fn make_interpreter<DB>(
+    settings: &Settings,
+    parent_finality_provider: ParentFinalityProvider,
+    parent_finality_votes: VoteTally,
+    bottom_up_manager: Option<BottomUpManager<DB>>,
+) -> FvmMessagesInterpreter<DB>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+{
+    let end_block_manager = EndBlockManager::new();
+    let mut top_down_manager = TopDownManager::new(parent_finality_provider, parent_finality_votes);
+    if let Some(gas_budget) = settings.ipc.topdown.as_ref().and_then(|t| t.gas_budget) {
+        top_down_manager = top_down_manager.with_gas_budget(gas_budget);
+    }
+
+    let mut interpreter = FvmMessagesInterpreter::new(
+        end_block_manager,
+        top_down_manager,
+        UpgradeScheduler::new(),
+        settings.testing.as_ref().is_none_or(|t| t.push_chain_meta),
+        settings.abci.block_max_msgs,
+        settings.fvm.gas_overestimation_rate,
+        settings.fvm.gas_search_step,
+    );
+
+    if let Some(bottom_up_manager) = bottom_up_manager {
+        interpreter = interpreter.with_bottom_up_manager(bottom_up_manager);
+    }
+
+    interpreter
+}
+
+/// The database in the data directory of a node which isn't running,
+/// with access to the states it committed at past heights.
+pub struct StateHistory {
     db: RocksDb,
+    state_hist: KVCollection<AppStore, BlockHeight, FvmStateParams>,
     state_store: NamespaceBlockstore,
-    bit_store_ns: String,
-) -> anyhow::Result<ipc_ipld_resolver::Service<libipld::DefaultParams, AppVote>> {
-    // Blockstore for Bitswap.
-    let bit_store = NamespaceBlockstore::new(db, bit_store_ns).context("error creating bit DB")?;
+    bit_store: NamespaceBlockstore,
+}
+
+impl StateHistory {
+    pub fn open(data_dir: &Path) -> anyhow::Result<Self> {
+        let ns = Namespaces::default();
+        let path = data_dir.join("rocksdb");
+        let db = RocksDb::open_cf(path, &RocksDbConfig::default(), ns.values().iter())
+            .context("error opening DB")?;
+
+        let state_hist = KVCollection::new(ns.state_hist);
+
+        let state_store = NamespaceBlockstore::new(db.clone(), ns.state_store)
+            .context("error creating state DB")?;
+
+        let bit_store =
+            NamespaceBlockstore::new(db.clone(), ns.bit_store).context("error creating bit DB")?;
 
+        Ok(Self {
+            db,
+            state_hist,
+            state_store,
+            bit_store,
+        })
+    }
+
+    /// The database itself, to run an application over it.
+    #[cfg(test)]
+    pub(crate) fn db(&self) -> &RocksDb {
+        &self.db
+    }
+
+    /// The store of the actor states.
+    pub fn state_store(&self) -> &NamespaceBlockstore {
+        &self.state_store
+    }
+
+    /// The store of the content resolved by the IPLD Resolver.
+    pub fn bit_store(&self) -> &NamespaceBlockstore {
+        &self.bit_store
+    }
+
+    /// The state at a given height, which is the result of executing the block before it,
+    /// following the CometBFT convention of the app hash in a header.
+    pub fn state_params(&self, height: BlockHeight) -> anyhow::Result<FvmStateParams> {
+        self.state_hist
+            .get(&self.db.read(), &height)
+            .context("error looking up history")?
+            .ok_or_else(|| anyhow!("no state at height {height}; it might have been pruned"))
+    }
+}
+
+/// Open the database in the data directory of a node which isn't running, and look up the
+/// state committed at a given height, e.g. to fork it into a new genesis.
+///
+/// Returns the state store and the state parameters.
+pub fn open_state_at_height(
+    data_dir: &Path,
+    height: BlockHeight,
+) -> anyhow::Result<(NamespaceBlockstore, FvmStateParams)> {
+    let history = StateHistory::open(data_dir)?;
+    let state_params = history.state_params(height)?;
+    Ok((history.state_store, state_params))
+}
+
+fn make_resolver_service(
+    settings: &Settings,
+    state_store: NamespaceBlockstore,
+    bit_store: NamespaceBlockstore,
+) -> anyhow::Result<ipc_ipld_resolver::Service<libipld::DefaultParams, AppVote>> {
     // Blockstore for Bitswap with a fallback on the actor store for reads.
     let bitswap_store = BitswapBlockstore::new(state_store, bit_store);
 
@@ -390,7 +585,7 @@ fn make_resolver_service(
     Ok(service)
 }
 
-fn make_ipc_provider_proxy(settings: &Settings) -> anyhow::Result<IPCProviderProxy> {
+pub(crate) fn make_ipc_provider_proxy(settings: &Settings) -> anyhow::Result<IPCProviderProxy> {
     let topdown_config = settings.ipc.topdown_config()?;
     let subnet = ipc_provider::config::Subnet {
         id: settings
@@ -444,6 +639,8 @@ fn to_resolver_config(settings: &Settings) -> anyhow::Result<ipc_ipld_resolver::
             max_incoming: r.connection.max_incoming,
             max_peers_per_query: r.connection.max_peers_per_query,
             event_buffer_capacity: r.connection.event_buffer_capacity,
+            mplex_fallback: r.connection.mplex_fallback,
+            enable_quic: r.connection.enable_quic,
         },
         network: NetworkConfig {
             local_key,
@@ -478,17 +675,40 @@ fn to_address(sk: &SecretKey, kind: &AccountKind) -> anyhow::Result<Address> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn dispatch_resolver_events(
     mut rx: tokio::sync::broadcast::Receiver<ResolverEvent<AppVote>>,
     parent_finality_votes: VoteTally,
+    mut evidence: EvidenceCollector,
+    checkpoint_signatures: SignatureCollector,
+    bottom_up_manager: BottomUpManager<NamespaceBlockstore>,
+    check_announcement: AnnouncementCheck,
+    subnet_id: SubnetID,
     topdown_enabled: bool,
 ) {
     loop {
         match rx.recv().await {
             Ok(event) => match event {
-                ResolverEvent::ReceivedPreemptive(_, _) => {}
+                ResolverEvent::ReceivedPreemptive(from_subnet_id, data) => {
+                    dispatch_preemptive(
+                        from_subnet_id,
+                        data,
+                        &bottom_up_manager,
+                        &check_announcement,
+                        &subnet_id,
+                    )
+                    .await;
+                }
                 ResolverEvent::ReceivedVote(vote) => {
-                    dispatch_vote(*vote, &parent_finality_votes, topdown_enabled).await;
+                    dispatch_vote(
+                        *vote,
+                        &parent_finality_votes,
+                        &mut evidence,
+                        &checkpoint_signatures,
+                        &subnet_id,
+                        topdown_enabled,
+                    )
+                    .await;
                 }
             },
             Err(RecvError::Lagged(n)) => {
@@ -502,12 +722,74 @@ async fn dispatch_resolver_events(
     }
 }
 
+/// Checks that a bottom-up batch announcement is signed by an active validator of the child subnet.
+type AnnouncementCheck =
+    Arc<dyn Fn(&SignedBottomUpBatchRef) -> anyhow::Result<EthAddress> + Send + Sync>;
+
+/// Queue the bottom-up batches announced by the validators of our child subnets for resolution.
+async fn dispatch_preemptive(
+    from_subnet_id: SubnetID,
+    data: Vec<u8>,
+    bottom_up_manager: &BottomUpManager<NamespaceBlockstore>,
+    check_announcement: &AnnouncementCheck,
+    subnet_id: &SubnetID,
+) {
+    let announcement = match fvm_ipld_encoding::from_slice::<SignedBottomUpBatchRef>(&data) {
+        Ok(announcement) => announcement,
+        Err(e) => {
+            tracing::debug!(
+                error = e.to_string(),
+                from_subnet_id = from_subnet_id.to_string(),
+                "ignoring preemptive data; not a bottom-up batch"
+            );
+            return;
+        }
+    };
+
+    let batch = &announcement.batch;
+
+    if batch.subnet_id != from_subnet_id || from_subnet_id.parent().as_ref() != Some(subnet_id) {
+        tracing::debug!(
+            from_subnet_id = from_subnet_id.to_string(),
+            batch_subnet_id = batch.subnet_id.to_string(),
+            "ignoring bottom-up batch; not from a child subnet"
+        );
+        return;
+    }
+
+    let signer = match check_announcement(&announcement) {
+        Ok(signer) => signer,
+        Err(e) => {
+            tracing::debug!(
+                error = format!("{e:#}"),
+                subnet_id = batch.subnet_id.to_string(),
+                "ignoring bottom-up batch; not announced by an active validator"
+            );
+            return;
+        }
+    };
+
+    tracing::debug!(
+        subnet_id = batch.subnet_id.to_string(),
+        height = batch.height,
+        cid = batch.cid.to_string(),
+        signer = signer.to_string(),
+        "received bottom-up batch announcement"
+    );
+
+    bottom_up_manager.add_announced(announcement.batch).await;
+}
+
 async fn dispatch_vote(
-    vote: VoteRecord<AppVote>,
+    vote: SignedVoteRecord<AppVote>,
     parent_finality_votes: &VoteTally,
+    evidence: &mut EvidenceCollector,
+    checkpoint_signatures: &SignatureCollector,
+    subnet_id: &SubnetID,
     topdown_enabled: bool,
 ) {
-    match vote.content {
+    let record = vote.record();
+    match &record.content {
         AppVote::ParentFinality(f) => {
             if !topdown_enabled {
                 tracing::debug!("ignoring vote; topdown disabled");
@@ -515,7 +797,7 @@ async fn dispatch_vote(
             }
             let res = atomically_or_err(|| {
                 parent_finality_votes.add_vote(
-                    vote.public_key.clone(),
+                    record.public_key.clone(),
                     f.height,
                     f.block_hash.clone(),
                 )
@@ -525,6 +807,17 @@ async fn dispatch_vote(
             match res {
                 Err(e @ VoteError::Equivocation(_, _, _, _)) => {
                     tracing::warn!(error = e.to_string(), "failed to handle vote");
+
+                    match evidence.add_equivocation(&vote) {
+                        Ok(Some(ev)) => emit(EquivocationDetected {
+                            validator: &ev.validator,
+                            height: ev.height,
+                        }),
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::error!(error = e.to_string(), "failed to record evidence")
+                        }
+                    }
                 }
                 Err(e @ (
                 VoteError::Uninitialized // early vote, we're not ready yet
@@ -533,10 +826,29 @@ async fn dispatch_vote(
                 )) => {
                     tracing::debug!(error = e.to_string(), "failed to handle vote");
                 }
-                _ => {
+                Ok(_) => {
                     tracing::debug!("vote handled");
+                    evidence.add_vote(vote);
                 }
             };
+
+            let finalized = atomically(|| parent_finality_votes.last_finalized_height()).await;
+            evidence.set_finalized(finalized);
+        }
+        AppVote::CheckpointSignature(sig) => {
+            if record.subnet_id != *subnet_id {
+                tracing::debug!("ignoring checkpoint signature; different subnet");
+                return;
+            }
+            match checkpoint_signatures.add_vote(subnet_id, &record.public_key, sig.clone()) {
+                Ok(()) => tracing::debug!(height = sig.height, "checkpoint signature handled"),
+                Err(e) => {
+                    tracing::debug!(
+                        error = e.to_string(),
+                        "failed to handle checkpoint signature"
+                    )
+                }
+            }
         }
     }
 }
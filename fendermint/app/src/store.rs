@@ -7,12 +7,18 @@
 // This is a temporary situation until all dependencies align on the same CID version.
 use cid::Cid as FvmCid;
 use ipc_ipld_resolver::missing_blocks::missing_blocks;
+use ipc_ipld_resolver::{PeerEntry, PeerStore};
 use libipld::Cid;
+use libp2p::PeerId;
 use libp2p_bitswap::BitswapStore;
 use std::borrow::Cow;
+use std::str::FromStr;
 
 use fendermint_rocksdb::blockstore::NamespaceBlockstore;
-use fendermint_storage::{Codec, Decode, Encode, KVError, KVResult, KVStore};
+use fendermint_rocksdb::RocksDb;
+use fendermint_storage::{
+    Codec, Decode, Encode, KVCollection, KVError, KVReadable, KVResult, KVStore, KVWritable,
+};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{de::DeserializeOwned, serde::Serialize};
 
@@ -129,3 +135,43 @@ impl BitswapStore for BitswapBlockstore {
         missing_blocks::<Self, Self::Params>(self, cid)
     }
 }
+
+/// A [`PeerStore`] implementation to persist the address book of the IPLD Resolver
+/// in a dedicated namespace, keyed by the base58 encoded peer ID.
+pub struct ResolverPeerStore {
+    db: RocksDb,
+    peers: KVCollection<AppStore, String, PeerEntry>,
+}
+
+impl ResolverPeerStore {
+    pub fn new(db: RocksDb, ns: String) -> Self {
+        Self {
+            db,
+            peers: KVCollection::new(ns),
+        }
+    }
+}
+
+impl PeerStore for ResolverPeerStore {
+    fn list(&self) -> anyhow::Result<Vec<(PeerId, PeerEntry)>> {
+        let tx = self.db.read();
+        let mut entries = Vec::new();
+        for item in self.peers.iterate(&tx) {
+            let (peer_id, entry) = item?;
+            entries.push((PeerId::from_str(&peer_id)?, entry));
+        }
+        Ok(entries)
+    }
+
+    fn put(&self, peer_id: &PeerId, entry: &PeerEntry) -> anyhow::Result<()> {
+        let key = peer_id.to_base58();
+        self.db.with_write(|tx| self.peers.put(tx, &key, entry))?;
+        Ok(())
+    }
+
+    fn delete(&self, peer_id: &PeerId) -> anyhow::Result<()> {
+        let key = peer_id.to_base58();
+        self.db.with_write(|tx| self.peers.delete(tx, &key))?;
+        Ok(())
+    }
+}
@@ -6,12 +6,16 @@ use crate::{app::AppError, BlockHeight};
 use anyhow::{anyhow, bail, Context};
 use fendermint_vm_core::Timestamp;
 use fendermint_vm_genesis::{Power, Validator};
+use fendermint_vm_interpreter::errors::ApplyMessageError;
 use fendermint_vm_interpreter::fvm::state::snapshot::SnapshotPayload;
 use fendermint_vm_interpreter::fvm::state::BlockHash;
-use fendermint_vm_interpreter::types::{AppliedMessage, CheckResponse, QueryResponse};
+use fendermint_vm_interpreter::types::{
+    AppliedMessage, ApplyMessageResponse, CheckResponse, QueryResponse, TopDownReceipt,
+};
 use fendermint_vm_message::signed::DomainHash;
 use fendermint_vm_snapshot::{SnapshotItem, SnapshotManifest};
 use fvm_shared::{address::Address, error::ExitCode, event::StampedEvent, ActorID};
+use ipc_api::checkpoint::CheckpointSignatures;
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, num::NonZeroU32};
@@ -124,6 +128,35 @@ pub fn to_deliver_tx(
     }
 }
 
+/// Response to delivery, depending on whether the message could be applied;
+/// returns an error if the application cannot carry on.
+pub fn to_deliver_tx_result(
+    result: Result<ApplyMessageResponse, ApplyMessageError>,
+    block_hash: Option<BlockHash>,
+) -> anyhow::Result<response::DeliverTx> {
+    let response = match result {
+        Ok(ApplyMessageResponse {
+            applied_message,
+            domain_hash,
+            topdown_receipts,
+        }) => {
+            let mut response = to_deliver_tx(applied_message, domain_hash, block_hash);
+            response
+                .events
+                .extend(to_topdown_receipt_events(topdown_receipts));
+            response
+        }
+        Err(ApplyMessageError::InvalidSignature(err)) => {
+            invalid_deliver_tx(AppError::InvalidSignature, err.to_string())
+        }
+        Err(ApplyMessageError::InvalidMessage(s)) => {
+            invalid_deliver_tx(AppError::InvalidEncoding, s)
+        }
+        Err(ApplyMessageError::Other(e)) => return Err(e).context("failed to apply message"),
+    };
+    Ok(response)
+}
+
 pub fn to_check_tx(ret: CheckResponse) -> response::CheckTx {
     // Putting the message `log` because only `log` appears in the `tx_sync` JSON-RPC response.
     let message = ret
@@ -235,6 +268,68 @@ pub fn to_message_event(from: Address, to: Address) -> Event {
     )
 }
 
+/// Events with the outcome of each top-down message, indexed by the cross-message ID
+/// so that clients can look up the execution of the messages they sent from the parent.
+pub fn to_topdown_receipt_events(receipts: Vec<TopDownReceipt>) -> Vec<Event> {
+    receipts
+        .into_iter()
+        .map(|r| {
+            Event::new(
+                "topdown",
+                vec![
+                    EventAttribute {
+                        key: "id".to_string(),
+                        value: hex::encode(r.id),
+                        index: true,
+                    },
+                    EventAttribute {
+                        key: "nonce".to_string(),
+                        value: r.nonce.to_string(),
+                        index: true,
+                    },
+                    EventAttribute {
+                        key: "exit_code".to_string(),
+                        value: r.exit_code.value().to_string(),
+                        index: false,
+                    },
+                    EventAttribute {
+                        key: "gas_used".to_string(),
+                        value: r.gas_used.to_string(),
+                        index: false,
+                    },
+                    EventAttribute {
+                        key: "return_data".to_string(),
+                        value: hex::encode(r.return_data),
+                        index: false,
+                    },
+                ],
+            )
+        })
+        .collect()
+}
+
+/// Map the checkpoint signatures collected at some height, grouped by app hash, to a query response.
+pub fn to_checkpoint_signatures_query(
+    sigs: Vec<CheckpointSignatures>,
+    block_height: BlockHeight,
+) -> anyhow::Result<response::Query> {
+    let (exit_code, value) = if sigs.is_empty() {
+        (ExitCode::USR_NOT_FOUND, Vec::new())
+    } else {
+        (ExitCode::OK, ipld_encode!(sigs))
+    };
+
+    let height = tendermint::block::Height::try_from(block_height).context("height too big")?;
+
+    Ok(response::Query {
+        code: to_code(exit_code),
+        info: to_error_msg(exit_code).to_owned(),
+        value: value.into(),
+        height,
+        ..Default::default()
+    })
+}
+
 /// Map to query results.
 pub fn to_query(ret: QueryResponse, block_height: BlockHeight) -> anyhow::Result<response::Query> {
     let exit_code = match ret {
@@ -243,6 +338,7 @@ pub fn to_query(ret: QueryResponse, block_height: BlockHeight) -> anyhow::Result
         // For calls and estimates, the caller needs to look into the `value` field to see the real exit code;
         // the query itself is successful, even if the value represents a failure.
         QueryResponse::Call(_) | QueryResponse::EstimateGas(_) => ExitCode::OK,
+        QueryResponse::Trace(_) => ExitCode::OK,
         QueryResponse::StateParams(_) => ExitCode::OK,
         QueryResponse::BuiltinActors(_) => ExitCode::OK,
     };
@@ -277,6 +373,10 @@ pub fn to_query(ret: QueryResponse, block_height: BlockHeight) -> anyhow::Result
             let v = ipld_encode!(est);
             (Vec::new(), v)
         }
+        QueryResponse::Trace(traces) => {
+            let v = ipld_encode!(traces);
+            (Vec::new(), v)
+        }
         QueryResponse::StateParams(sp) => {
             let v = ipld_encode!(sp);
             (Vec::new(), v)
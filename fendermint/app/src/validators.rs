@@ -3,6 +3,7 @@
 
 use anyhow::{anyhow, Ok, Result};
 use fendermint_crypto::PublicKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_interpreter::fvm::state::ipc::GatewayCaller;
 use fendermint_vm_interpreter::fvm::state::FvmExecState;
 use std::collections::HashMap;
@@ -46,4 +47,12 @@ impl ValidatorCache {
             .cloned()
             .ok_or_else(|| anyhow!("validator not found"))
     }
+
+    /// The Ethereum addresses of the validators, which they sign checkpoints with.
+    pub fn eth_addresses(&self) -> Vec<ethers::types::Address> {
+        self.map
+            .values()
+            .map(|pk| EthAddress::from(*pk).into())
+            .collect()
+    }
 }
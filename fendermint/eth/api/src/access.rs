@@ -0,0 +1,593 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Access control of the JSON-RPC endpoints: authentication, rate limits and method lists.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use axum::http::{header, HeaderMap, StatusCode};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use lazy_static::lazy_static;
+use lru_time_cache::LruCache;
+use prometheus::{register_int_counter, register_int_counter_vec, IntCounter, IntCounterVec};
+use serde::Deserialize;
+use serde_json::json;
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::error::JsonRpcError;
+
+// See https://eips.ethereum.org/EIPS/eip-1474#error-codes
+const RESOURCE_UNAVAILABLE: i64 = -32002;
+const METHOD_NOT_SUPPORTED: i64 = -32004;
+const LIMIT_EXCEEDED: i64 = -32005;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Maximum number of callers we keep rate limiting buckets for.
+const MAX_BUCKETS: usize = 100_000;
+/// A bucket not used for this long is full again anyway, so it can be forgotten.
+const BUCKET_TTL: Duration = Duration::from_secs(3600);
+
+lazy_static! {
+    pub static ref RPC_REQUESTS_REJECTED: IntCounterVec = register_int_counter_vec!(
+        "rpc_requests_rejected_total",
+        "Number of JSON-RPC requests rejected by access control",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref RPC_REQUEST_UNITS: IntCounter = register_int_counter!(
+        "rpc_request_units_total",
+        "Number of request units charged for rate limiting"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AccessOpt {
+    /// Static bearer tokens accepted in the `Authorization` header.
+    pub auth_tokens: Vec<String>,
+    /// Secret to validate HS256 JSON Web Tokens in the `Authorization` header.
+    pub jwt_secret: Option<Vec<u8>>,
+    /// Maximum number of requests in a batch.
+    pub max_batch_size: Option<usize>,
+    /// If not empty, only these methods can be called.
+    pub allowed_methods: Vec<String>,
+    /// Methods which cannot be called.
+    pub denied_methods: Vec<String>,
+    /// Maximum number of active subscriptions on a WebSocket connection.
+    pub max_ws_subscriptions: Option<usize>,
+    pub rate_limit: Option<RateLimitOpt>,
+    /// Reverse proxies whose `X-Forwarded-For` header is trusted to tell the IP address of the caller.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitOpt {
+    /// Request units replenished per second, for each IP address,
+    /// and for each identity of authenticated callers.
+    pub units_per_second: u32,
+    /// Maximum number of request units that can be spent at once.
+    pub burst: u32,
+    /// Cost of methods in request units; the ones not listed cost 1 unit.
+    pub method_costs: HashMap<String, u32>,
+}
+
+/// Reasons to reject a request.
+#[derive(Debug, Clone)]
+pub enum AccessError {
+    Unauthorized(String),
+    MethodNotAllowed(String),
+    BatchTooLarge(usize),
+    RateLimited,
+    TooManySubscriptions(usize),
+}
+
+impl AccessError {
+    /// Label used in the metrics.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AccessError::Unauthorized(_) => "unauthorized",
+            AccessError::MethodNotAllowed(_) => "method_not_allowed",
+            AccessError::BatchTooLarge(_) => "batch_too_large",
+            AccessError::RateLimited => "rate_limited",
+            AccessError::TooManySubscriptions(_) => "too_many_subscriptions",
+        }
+    }
+
+    /// HTTP status code to respond with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AccessError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AccessError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::OK,
+        }
+    }
+
+    /// JSON-RPC error response to the request with the given ID.
+    pub fn to_response(&self, id: serde_json::Value) -> serde_json::Value {
+        let error = JsonRpcError::from(self);
+        json!({
+            "jsonrpc": "2.0",
+            "error": {
+                "code": error.code,
+                "message": error.message,
+            },
+            "id": id,
+        })
+    }
+
+    /// Count the rejection in the metrics.
+    pub fn record(&self) {
+        RPC_REQUESTS_REJECTED
+            .with_label_values(&[self.reason()])
+            .inc();
+    }
+}
+
+impl std::fmt::Display for AccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessError::Unauthorized(e) => write!(f, "unauthorized: {e}"),
+            AccessError::MethodNotAllowed(m) => write!(f, "method '{m}' is not allowed"),
+            AccessError::BatchTooLarge(max) => write!(f, "batch exceeds the maximum size of {max}"),
+            AccessError::RateLimited => write!(f, "request rate limit exceeded"),
+            AccessError::TooManySubscriptions(max) => {
+                write!(f, "connection exceeds the maximum of {max} subscriptions")
+            }
+        }
+    }
+}
+
+impl From<&AccessError> for JsonRpcError {
+    fn from(value: &AccessError) -> Self {
+        let code = match value {
+            AccessError::Unauthorized(_) => RESOURCE_UNAVAILABLE,
+            AccessError::MethodNotAllowed(_) => METHOD_NOT_SUPPORTED,
+            AccessError::BatchTooLarge(_)
+            | AccessError::RateLimited
+            | AccessError::TooManySubscriptions(_) => LIMIT_EXCEEDED,
+        };
+        Self {
+            code,
+            message: value.to_string(),
+            data: None,
+        }
+    }
+}
+
+/// Identity established by the bearer token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// The static token itself, or the subject of a JSON Web Token.
+    pub id: String,
+    /// When the token expires, if ever.
+    pub expires_at: Option<SystemTime>,
+}
+
+/// Who is calling the API.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub ip: IpAddr,
+    /// Identity established by the bearer token, if authentication is enabled.
+    pub identity: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum BucketKey {
+    Ip(IpAddr),
+    Identity(String),
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    units: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(burst: u32) -> Self {
+        Self {
+            units: burst as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant, opt: &RateLimitOpt) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.units = (self.units + elapsed * opt.units_per_second as f64).min(opt.burst as f64);
+        self.updated = now;
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    exp: Option<u64>,
+}
+
+/// Enforce the access rules on incoming requests.
+pub struct AccessControl {
+    opt: AccessOpt,
+    allowed_methods: HashSet<String>,
+    denied_methods: HashSet<String>,
+    buckets: Mutex<LruCache<BucketKey, Bucket>>,
+}
+
+impl AccessControl {
+    pub fn new(opt: AccessOpt) -> Self {
+        Self {
+            allowed_methods: opt.allowed_methods.iter().cloned().collect(),
+            denied_methods: opt.denied_methods.iter().cloned().collect(),
+            buckets: Mutex::new(LruCache::with_expiry_duration_and_capacity(
+                BUCKET_TTL,
+                MAX_BUCKETS,
+            )),
+            opt,
+        }
+    }
+
+    fn is_auth_enabled(&self) -> bool {
+        !self.opt.auth_tokens.is_empty() || self.opt.jwt_secret.is_some()
+    }
+
+    /// Establish the identity of the caller from the bearer token in the `Authorization` header.
+    ///
+    /// Returns `None` if authentication is disabled.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Identity>, AccessError> {
+        if !self.is_auth_enabled() {
+            return Ok(None);
+        }
+
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|v| v.trim())
+            .ok_or_else(|| AccessError::Unauthorized("missing bearer token".into()))?;
+
+        // Compare against every token in constant time, so the timing doesn't reveal how much of one matched.
+        let is_static = self.opt.auth_tokens.iter().fold(Choice::from(0), |acc, t| {
+            acc | t.as_bytes().ct_eq(token.as_bytes())
+        });
+
+        if bool::from(is_static) {
+            return Ok(Some(Identity {
+                id: token.to_owned(),
+                expires_at: None,
+            }));
+        }
+
+        if let Some(ref secret) = self.opt.jwt_secret {
+            let claims = jsonwebtoken::decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(secret),
+                &Validation::new(Algorithm::HS256),
+            )
+            .map_err(|e| AccessError::Unauthorized(e.to_string()))?
+            .claims;
+
+            return Ok(Some(Identity {
+                id: claims.sub.unwrap_or_else(|| token.to_owned()),
+                expires_at: claims
+                    .exp
+                    .map(|exp| SystemTime::UNIX_EPOCH + Duration::from_secs(exp)),
+            }));
+        }
+
+        Err(AccessError::Unauthorized("invalid bearer token".into()))
+    }
+
+    /// The IP address of the caller: the peer address, unless the peer is a trusted proxy, in which case
+    /// the last address in the `X-Forwarded-For` header which wasn't added by a trusted proxy.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.opt.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+
+        headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .rev()
+            .map(|ip| ip.trim().parse::<IpAddr>())
+            .find(|ip| !matches!(ip, Ok(ip) if self.opt.trusted_proxies.contains(ip)))
+            .and_then(|ip| ip.ok())
+            .unwrap_or(peer)
+    }
+
+    pub fn check_batch_size(&self, size: usize) -> Result<(), AccessError> {
+        match self.opt.max_batch_size {
+            Some(max) if size > max => Err(AccessError::BatchTooLarge(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_method(&self, method: &str) -> Result<(), AccessError> {
+        if self.denied_methods.contains(method)
+            || !(self.allowed_methods.is_empty() || self.allowed_methods.contains(method))
+        {
+            return Err(AccessError::MethodNotAllowed(method.to_owned()));
+        }
+        Ok(())
+    }
+
+    pub fn check_subscriptions(&self, active: usize) -> Result<(), AccessError> {
+        match self.opt.max_ws_subscriptions {
+            Some(max) if active >= max => Err(AccessError::TooManySubscriptions(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Charge the cost of the methods to the IP address of the caller, and to its identity
+    /// if it is authenticated, rejecting the call if either ran out of request units.
+    pub fn charge<'a>(
+        &self,
+        caller: &Caller,
+        methods: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), AccessError> {
+        let Some(ref opt) = self.opt.rate_limit else {
+            return Ok(());
+        };
+
+        let cost: u64 = methods
+            .into_iter()
+            .map(|m| opt.method_costs.get(m).copied().unwrap_or(1) as u64)
+            .sum();
+
+        // A token shared across many addresses cannot get around the limit of each address,
+        // and many addresses cannot get around the limit of the token they share.
+        let mut keys = vec![BucketKey::Ip(caller.ip)];
+        if let Some(ref id) = caller.identity {
+            keys.push(BucketKey::Identity(id.clone()));
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("buckets lock poisoned");
+
+        let mut charged = Vec::with_capacity(keys.len());
+        for key in keys {
+            let mut bucket = buckets
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| Bucket::full(opt.burst));
+            bucket.refill(now, opt);
+
+            // A rejected call costs nothing, not even to the buckets which had enough units.
+            if bucket.units < cost as f64 {
+                return Err(AccessError::RateLimited);
+            }
+            bucket.units -= cost as f64;
+            charged.push((key, bucket));
+        }
+
+        for (key, bucket) in charged {
+            buckets.insert(key, bucket);
+        }
+
+        RPC_REQUEST_UNITS.inc_by(cost);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, SystemTime};
+
+    use axum::http::{header, HeaderMap, HeaderValue};
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    use super::{AccessControl, AccessError, AccessOpt, Caller, Identity, RateLimitOpt};
+
+    fn caller(identity: Option<&str>) -> Caller {
+        caller_at(Ipv4Addr::LOCALHOST, identity)
+    }
+
+    fn caller_at(ip: Ipv4Addr, identity: Option<&str>) -> Caller {
+        Caller {
+            ip: IpAddr::V4(ip),
+            identity: identity.map(|t| t.to_owned()),
+        }
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn authenticates_static_tokens() {
+        let access = AccessControl::new(AccessOpt {
+            auth_tokens: vec!["secret".into()],
+            ..Default::default()
+        });
+
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            access.authenticate(&headers),
+            Err(AccessError::Unauthorized(_))
+        ));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer other"),
+        );
+        assert!(access.authenticate(&headers).is_err());
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert_eq!(
+            access.authenticate(&headers).unwrap(),
+            Some(Identity {
+                id: "secret".into(),
+                expires_at: None
+            })
+        );
+    }
+
+    #[test]
+    fn authenticates_jwt_with_expiry() {
+        let secret = b"jwt-secret";
+        let access = AccessControl::new(AccessOpt {
+            jwt_secret: Some(secret.to_vec()),
+            ..Default::default()
+        });
+
+        let exp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 600;
+
+        let token = |exp: u64| {
+            jsonwebtoken::encode(
+                &Header::default(),
+                &json!({"sub": "alice", "exp": exp}),
+                &EncodingKey::from_secret(secret),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            access.authenticate(&bearer(&token(exp))).unwrap(),
+            Some(Identity {
+                id: "alice".into(),
+                expires_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(exp))
+            })
+        );
+
+        // Expired tokens are rejected.
+        assert!(access.authenticate(&bearer(&token(exp - 3600))).is_err());
+    }
+
+    #[test]
+    fn trusts_forwarded_for_only_from_proxies() {
+        let proxy = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let access = AccessControl::new(AccessOpt {
+            trusted_proxies: vec![proxy],
+            ..Default::default()
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.1.1.1, 2.2.2.2, 10.0.0.1"),
+        );
+
+        // The caller can put anything at the start, only the address the proxy saw counts.
+        assert_eq!(
+            access.client_ip(proxy, &headers),
+            IpAddr::V4(Ipv4Addr::new(2, 2, 2, 2))
+        );
+
+        let other = IpAddr::V4(Ipv4Addr::new(3, 3, 3, 3));
+        assert_eq!(access.client_ip(other, &headers), other);
+        assert_eq!(access.client_ip(proxy, &HeaderMap::new()), proxy);
+    }
+
+    #[test]
+    fn checks_method_lists() {
+        let access = AccessControl::new(AccessOpt {
+            allowed_methods: vec!["eth_call".into(), "eth_getLogs".into()],
+            denied_methods: vec!["eth_getLogs".into()],
+            ..Default::default()
+        });
+        assert!(access.check_method("eth_call").is_ok());
+        assert!(access.check_method("eth_getLogs").is_err());
+        assert!(access.check_method("eth_chainId").is_err());
+    }
+
+    #[test]
+    fn rate_limits_by_cost() {
+        let access = AccessControl::new(AccessOpt {
+            rate_limit: Some(RateLimitOpt {
+                units_per_second: 0,
+                burst: 10,
+                method_costs: [("eth_getLogs".to_owned(), 8)].into_iter().collect(),
+            }),
+            ..Default::default()
+        });
+
+        assert!(access.charge(&caller(None), ["eth_getLogs"]).is_ok());
+        assert!(access.charge(&caller(None), ["eth_getLogs"]).is_err());
+        // The rejected call was not charged.
+        assert!(access
+            .charge(&caller(None), ["eth_chainId", "eth_chainId"])
+            .is_ok());
+        assert!(access.charge(&caller(None), ["eth_chainId"]).is_err());
+    }
+
+    #[test]
+    fn rate_limits_by_identity() {
+        let access = AccessControl::new(AccessOpt {
+            rate_limit: Some(RateLimitOpt {
+                units_per_second: 0,
+                burst: 1,
+                method_costs: Default::default(),
+            }),
+            ..Default::default()
+        });
+
+        let ip = Ipv4Addr::new(1, 1, 1, 1);
+        let other = Ipv4Addr::new(2, 2, 2, 2);
+
+        assert!(access
+            .charge(&caller_at(ip, Some("alice")), ["eth_chainId"])
+            .is_ok());
+
+        // The same identity shares its limit across addresses.
+        assert!(access
+            .charge(&caller_at(other, Some("alice")), ["eth_chainId"])
+            .is_err());
+    }
+
+    #[test]
+    fn rate_limits_authenticated_callers_by_address_too() {
+        let access = AccessControl::new(AccessOpt {
+            rate_limit: Some(RateLimitOpt {
+                units_per_second: 0,
+                burst: 2,
+                method_costs: Default::default(),
+            }),
+            ..Default::default()
+        });
+
+        let ip = Ipv4Addr::new(1, 1, 1, 1);
+        let other = Ipv4Addr::new(2, 2, 2, 2);
+
+        assert!(access
+            .charge(&caller_at(ip, Some("alice")), ["eth_chainId"])
+            .is_ok());
+        assert!(access
+            .charge(&caller_at(ip, Some("bob")), ["eth_chainId"])
+            .is_ok());
+
+        // Switching tokens doesn't get around the limit of the address.
+        assert!(access
+            .charge(&caller_at(ip, Some("carol")), ["eth_chainId"])
+            .is_err());
+        assert!(access
+            .charge(&caller_at(ip, None), ["eth_chainId"])
+            .is_err());
+
+        // The rejected calls weren't charged to the identities either.
+        assert!(access
+            .charge(&caller_at(other, Some("carol")), ["eth_chainId"])
+            .is_ok());
+        assert!(access
+            .charge(&caller_at(other, Some("carol")), ["eth_chainId"])
+            .is_ok());
+    }
+}
@@ -7,47 +7,49 @@
 // * https://github.com/filecoin-project/lotus/blob/v1.23.1-rc2/node/impl/full/eth.go
 
 use std::collections::HashSet;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context};
 use ethers_core::abi::AbiEncode;
-use ethers_core::types::transaction::eip2718::TypedTransaction;
 use ethers_core::types::{self as et, BlockNumber};
-use ethers_core::utils::rlp;
 use fendermint_rpc::message::SignedMessageFactory;
 use fendermint_rpc::query::QueryClient;
 use fendermint_rpc::response::{decode_data, decode_fevm_invoke, decode_fevm_return_data};
 use fendermint_vm_actor_interface::eam::{EthAddress, EAM_ACTOR_ADDR};
 use fendermint_vm_actor_interface::evm;
 use fendermint_vm_message::chain::ChainMessage;
-use fendermint_vm_message::query::FvmQueryHeight;
+use fendermint_vm_message::eth::EthTransaction;
+use fendermint_vm_message::query::{FvmQueryHeight, CHECKPOINT_SIGNATURES_QUERY_PATH};
 use fendermint_vm_message::signed::SignedMessage;
 use fil_actors_evm_shared::uints;
-use futures::FutureExt;
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared::address::Address;
 use fvm_shared::bigint::BigInt;
 use fvm_shared::crypto::signature::Signature;
-use fvm_shared::{chainid::ChainID, error::ExitCode};
-use jsonrpc_v2::Params;
+use fvm_shared::{chainid::ChainID, econ::TokenAmount, error::ExitCode};
+use ipc_api::checkpoint::CheckpointSignatures;
+use jsonrpc_v2::{Data, Params};
 
 use rand::Rng;
 use tendermint::block::Height;
 use tendermint_rpc::endpoint::{self, status};
 use tendermint_rpc::SubscriptionClient;
 use tendermint_rpc::{
-    endpoint::{block, block_results, broadcast::tx_sync, consensus_params, header},
+    endpoint::{block_results, broadcast::tx_sync, header},
     Client,
 };
 
-use crate::conv::from_eth::{self, derive_origin_kind, to_fvm_message};
-use crate::conv::from_tm::{self, msg_hash, to_chain_message, to_cumulative, to_eth_block_zero};
-use crate::error::{error_with_revert, OutOfSequence};
+use crate::conv::from_eth::{self, derive_origin_kind, to_eth_extras, to_fvm_message};
+use crate::conv::from_tm::{
+    self, msg_hash, to_chain_message, to_cumulative, to_eth_block_zero, tx_hash,
+};
+use crate::error::{error_with_revert, JsonRpcError, OutOfSequence};
 use crate::filters::{matches_topics, FilterId, FilterKind, FilterRecords};
 use crate::{
     conv::{
         from_eth::to_fvm_address,
         from_fvm::to_eth_tokens,
-        from_tm::{to_eth_receipt, to_eth_transaction_response},
+        from_tm::{to_eth_receipt, to_eth_transaction_response, to_topdown_receipt},
     },
     error, JsonRpcData, JsonRpcResult,
 };
@@ -55,6 +57,10 @@ use crate::{
 // BLOCK_GAS_LIMIT was removed in FVM 4.7, define locally for IPC
 const BLOCK_GAS_LIMIT: u64 = 10_000_000_000;
 
+/// How often to look for the receipt of a transaction sent with `eth_sendRawTransactionSync`
+/// after it has been included in a block but not indexed yet.
+const TX_SYNC_POLL_MILLIS: u64 = 100;
+
 /// Returns a list of addresses owned by client.
 ///
 /// It will always return [] since we don't expect Fendermint to manage private keys.
@@ -96,65 +102,42 @@ pub async fn max_priority_fee_per_gas<C>(data: JsonRpcData<C>) -> JsonRpcResult<
 where
     C: Client + Sync + Send,
 {
-    // get the latest block
-    let res: block::Response = data.tm().latest_block().await?;
-    let latest_h = res.block.header.height;
-
-    // get consensus params to fetch block gas limit
-    // (this just needs to be done once as we assume that is constant
-    // for all blocks)
-    let consensus_params: consensus_params::Response = data
-        .tm()
-        .consensus_params(latest_h)
-        .await
-        .context("failed to get consensus params")?;
-    let mut block_gas_limit = consensus_params.consensus_params.block.max_gas;
-    if block_gas_limit <= 0 {
-        block_gas_limit = i64::try_from(BLOCK_GAS_LIMIT).expect("FVM block gas limit not i64")
+    let premium = priority_fee_estimate(&data).await?;
+    Ok(to_eth_tokens(&premium)?)
+}
+
+/// Estimate the priority fee from the premiums paid in the most recent blocks.
+async fn priority_fee_estimate<C>(data: &JsonRpcData<C>) -> JsonRpcResult<TokenAmount>
+where
+    C: Client + Sync + Send,
+{
+    let latest_h = match data.fee_index.last_height() {
+        Some(h) => h,
+        None => data.latest_height().await?.value(),
     };
 
     let mut premiums = Vec::new();
-    // iterate through the blocks in the range
-    // we may be able to de-duplicate a lot of this code from fee_history
-    let latest_h: u64 = latest_h.into();
-    let mut blk = latest_h;
-    while blk > latest_h - data.gas_opt.num_blocks_max_prio_fee {
-        let block = data
-            .block_by_height(blk.into())
-            .await
-            .context("failed to get block")?;
-
-        let height = block.header().height;
+    let mut block_gas_limit = None;
 
+    // iterate through the blocks in the range, newest first
+    let mut blk = latest_h;
+    while blk > latest_h.saturating_sub(data.gas_opt.num_blocks_max_prio_fee) {
         // Genesis has height 1, but no relevant fees.
-        if height.value() <= 1 {
+        if blk <= 1 {
             break;
         }
 
-        let state_params = data
-            .client
-            .state_params(FvmQueryHeight::Height(height.value()))
-            .await?;
-
-        let base_fee = &state_params.value.base_fee;
-
-        // The latest block might not have results yet.
-        if let Ok(block_results) = data.tm().block_results(height).await {
-            let txs_results = block_results.txs_results.unwrap_or_default();
-
-            for (tx, txres) in block.data().iter().zip(txs_results) {
-                let msg = fvm_ipld_encoding::from_slice::<ChainMessage>(tx)
-                    .context("failed to decode tx as ChainMessage")?;
-
-                if let ChainMessage::Signed(msg) = msg {
-                    let premium = crate::gas::effective_gas_premium(&msg.message, base_fee);
-                    premiums.push((premium, txres.gas_used));
-                }
-            }
+        if let Some(record) = data.fee_record_at(blk).await? {
+            // We assume the block gas limit is constant over the range.
+            block_gas_limit.get_or_insert(record.gas_limit);
+            premiums.extend(record.premiums.into_iter().map(|p| (p.premium, p.gas_used)));
         }
         blk -= 1;
     }
 
+    let block_gas_limit = block_gas_limit
+        .unwrap_or_else(|| i64::try_from(BLOCK_GAS_LIMIT).expect("FVM block gas limit not i64"));
+
     // compute median gas price
     let mut median = crate::gas::median_gas_premium(&mut premiums, block_gas_limit);
     let min_premium = data.gas_opt.min_gas_premium.clone();
@@ -167,13 +150,12 @@ where
     const PRECISION: u32 = 32;
     let mut rng = rand::thread_rng();
     let noise: f64 = 1.0 + rng.gen::<f64>() * 0.005;
-    let precision: i64 = 32;
-    let coeff: u64 = ((noise * (1 << precision) as f64) as u64) + 1;
+    let coeff: u64 = ((noise * (1u64 << PRECISION) as f64) as u64) + 1;
 
     median *= BigInt::from(coeff);
-    median.div_ceil(BigInt::from(1 << PRECISION));
+    let median = median.div_ceil(BigInt::from(1u64 << PRECISION));
 
-    Ok(to_eth_tokens(&median)?)
+    Ok(median)
 }
 
 /// Returns transaction base fee per gas and effective priority fee per gas for the requested/supported block range.
@@ -191,117 +173,62 @@ where
     if block_count > et::U256::from(data.gas_opt.max_fee_hist_size) {
         return error(
             ExitCode::USR_ILLEGAL_ARGUMENT,
-            "block_count must be <= 1024",
+            format!("block_count must be <= {}", data.gas_opt.max_fee_hist_size),
         );
     }
 
-    let mut hist = et::FeeHistory {
-        base_fee_per_gas: Vec::new(),
-        gas_used_ratio: Vec::new(),
-        oldest_block: et::U256::default(),
-        reward: Vec::new(),
-    };
-    let mut block_number = last_block;
-    let mut block_count = block_count.as_usize();
+    let block_count = block_count.as_usize();
+    let last_height = data.header_by_height(last_block).await?.height.value();
 
-    let get_base_fee = |height: Height| {
-        data.client
-            .state_params(FvmQueryHeight::Height(height.value()))
-            .map(|result| result.map(|state_params| state_params.value.base_fee))
-    };
-
-    while block_count > 0 {
-        let block = data
-            .block_by_height(block_number)
-            .await
-            .context("failed to get block")?;
-
-        let height = block.header().height;
-
-        // Apparently the base fees have to include the next fee after the newest block.
-        // See https://github.com/filecoin-project/lotus/blob/v1.25.2/node/impl/full/eth.go#L721-L725
-        if hist.base_fee_per_gas.is_empty() {
-            let base_fee = get_base_fee(height.increment())
-                .await
-                .context("failed to get next base fee")?;
-
-            hist.base_fee_per_gas.push(to_eth_tokens(&base_fee)?);
+    // Collect the records newest-to-oldest, skipping blocks without results.
+    let mut records = Vec::with_capacity(block_count);
+    let mut height = last_height;
+    // Genesis has height 1.
+    while records.len() < block_count && height >= 1 {
+        if let Some(record) = data.fee_record_at(height).await? {
+            records.push(record);
         }
+        height -= 1;
+    }
+    // Reverse data to be oldest-to-newest.
+    records.reverse();
 
-        let base_fee = get_base_fee(height)
-            .await
-            .context("failed to get block base fee")?;
-
-        let consensus_params: consensus_params::Response = data
-            .tm()
-            .consensus_params(height)
-            .await
-            .context("failed to get consensus params")?;
-
-        let mut block_gas_limit = consensus_params.consensus_params.block.max_gas;
-        if block_gas_limit <= 0 {
-            block_gas_limit = i64::try_from(BLOCK_GAS_LIMIT).expect("FVM block gas limit not i64")
-        };
+    let mut hist = et::FeeHistory {
+        base_fee_per_gas: Vec::with_capacity(records.len() + 1),
+        gas_used_ratio: Vec::with_capacity(records.len()),
+        oldest_block: et::U256::from(records.first().map(|r| r.height).unwrap_or_default()),
+        reward: Vec::with_capacity(records.len()),
+    };
 
-        // The latest block might not have results yet.
-        if let Ok(block_results) = data.tm().block_results(height).await {
-            let txs_results = block_results.txs_results.unwrap_or_default();
-            let total_gas_used: i64 = txs_results.iter().map(|r| r.gas_used).sum();
+    for record in records.iter() {
+        let rewards: Result<Vec<et::U256>, _> = reward_percentiles
+            .iter()
+            .map(|p| to_eth_tokens(&record.reward(*p)))
+            .collect();
 
-            let mut premiums = Vec::new();
-            for (tx, txres) in block.data().iter().zip(txs_results) {
-                let msg = fvm_ipld_encoding::from_slice::<ChainMessage>(tx)
-                    .context("failed to decode tx as ChainMessage")?;
+        hist.base_fee_per_gas.push(to_eth_tokens(&record.base_fee)?);
+        hist.gas_used_ratio.push(record.gas_used_ratio());
+        hist.reward.push(rewards?);
+    }
 
-                if let ChainMessage::Signed(msg) = msg {
-                    let premium = crate::gas::effective_gas_premium(&msg.message, &base_fee);
-                    premiums.push((premium, txres.gas_used));
-                }
+    // Apparently the base fees have to include the next fee after the newest block.
+    // See https://github.com/filecoin-project/lotus/blob/v1.25.2/node/impl/full/eth.go#L721-L725
+    if let Some(newest) = records.last() {
+        let next_height = newest.height + 1;
+        let next_base_fee = match data.fee_index.get(next_height) {
+            Some(record) => record.base_fee,
+            None => {
+                data.client
+                    .state_params(FvmQueryHeight::Height(next_height))
+                    .await
+                    .context("failed to get next base fee")?
+                    .value
+                    .base_fee
             }
-            premiums.sort();
-
-            let premium_gas_used: i64 = premiums.iter().map(|(_, gas)| *gas).sum();
-
-            let rewards: Result<Vec<et::U256>, _> = reward_percentiles
-                .iter()
-                .map(|p| {
-                    if premiums.is_empty() {
-                        Ok(et::U256::zero())
-                    } else {
-                        let threshold_gas_used = (premium_gas_used as f64 * p / 100f64) as i64;
-                        let mut sum_gas_used = 0;
-                        let mut idx = 0;
-                        while sum_gas_used < threshold_gas_used && idx < premiums.len() - 1 {
-                            sum_gas_used += premiums[idx].1;
-                            idx += 1;
-                        }
-                        to_eth_tokens(&premiums[idx].0)
-                    }
-                })
-                .collect();
-
-            hist.oldest_block = et::U256::from(height.value());
-            hist.base_fee_per_gas.push(to_eth_tokens(&base_fee)?);
-            hist.gas_used_ratio
-                .push(total_gas_used as f64 / block_gas_limit as f64);
-            hist.reward.push(rewards?);
-
-            block_count -= 1;
-        }
-
-        // Genesis has height 1.
-        if height.value() <= 1 {
-            break;
-        }
-
-        block_number = et::BlockNumber::Number(et::U64::from(height.value() - 1));
+        };
+        hist.base_fee_per_gas.push(to_eth_tokens(&next_base_fee)?);
     }
 
-    // Reverse data to be oldest-to-newest.
-    hist.base_fee_per_gas.reverse();
-    hist.gas_used_ratio.reverse();
-    hist.reward.reverse();
-
     Ok(hist)
 }
 
@@ -310,8 +237,15 @@ pub async fn gas_price<C>(data: JsonRpcData<C>) -> JsonRpcResult<et::U256>
 where
     C: Client + Sync + Send,
 {
-    let res = data.client.state_params(FvmQueryHeight::default()).await?;
-    let price = to_eth_tokens(&res.value.base_fee)?;
+    let base_fee = match data.fee_index.last() {
+        Some(record) => record.base_fee,
+        None => {
+            let res = data.client.state_params(FvmQueryHeight::default()).await?;
+            res.value.base_fee
+        }
+    };
+    let premium = priority_fee_estimate(&data).await?;
+    let price = to_eth_tokens(&(base_fee + premium))?;
     Ok(price)
 }
 
@@ -491,7 +425,7 @@ where
     C: Client + Sync + Send,
 {
     let Some(tx_res) = data.tx_by_hash(tx_hash).await? else {
-        return Ok(None);
+        return get_topdown_receipt(data, tx_hash).await;
     };
 
     let Ok(header) = data.tm().header(tx_res.height).await else {
@@ -527,6 +461,31 @@ where
     }
 }
 
+/// Returns a synthetic receipt for a top-down message, if the hash is a cross-message ID.
+async fn get_topdown_receipt<C>(
+    data: JsonRpcData<C>,
+    id: et::H256,
+) -> JsonRpcResult<Option<et::TransactionReceipt>>
+where
+    C: Client + Sync + Send,
+{
+    let Some(tx_res) = data.topdown_tx_by_id(id).await? else {
+        return Ok(None);
+    };
+
+    let Ok(header) = data.tm().header(tx_res.height).await else {
+        return Ok(None);
+    };
+
+    let block_results: block_results::Response = data.tm().block_results(tx_res.height).await?;
+    let cumulative = to_cumulative(&block_results);
+
+    let receipt = to_topdown_receipt(id, &tx_res, &cumulative, &header.header)
+        .context("failed to convert to top-down receipt")?;
+
+    Ok(receipt)
+}
+
 /// Returns receipts for all the transactions in a block.
 pub async fn get_block_receipts<C>(
     data: JsonRpcData<C>,
@@ -635,29 +594,143 @@ pub async fn send_raw_transaction<C>(
 where
     C: Client + Sync + Send,
 {
-    let rlp = rlp::Rlp::new(tx.as_ref());
-    let (tx, mut sig): (TypedTransaction, et::Signature) = TypedTransaction::decode_signed(&rlp)
-        .context("failed to decode RLP as signed TypedTransaction")?;
-
-    // for legacy eip155 transactions, the chain id is encoded in it. The `v` most likely will not
-    // be normalized, normalize to ensure consistent txn hash calculation.
-    normalize_signature(&mut sig)?;
-
-    let sighash = tx.sighash();
-    let msghash = tx.hash(&sig);
-    tracing::debug!(?sighash, eth_hash = ?msghash, ?tx, "received raw transaction");
-
-    let msg = to_fvm_message(tx.clone())?;
-    let sender = msg.from;
-    let nonce = msg.sequence;
-
-    let msg = SignedMessage {
-        origin_kind: derive_origin_kind(&tx)?,
-        message: msg,
-        signature: Signature::new_secp256k1(sig.to_vec()),
+    let raw = RawTransaction::decode(&tx)?;
+    broadcast_raw_transaction(&data, raw).await
+}
+
+/// Sends a signed transaction and waits until it's included in a block, returning its receipt.
+///
+/// The optional second parameter is the maximum time to wait in milliseconds, which is capped
+/// by the timeout configured for the facade. If the transaction isn't included by then, the
+/// call fails with the transaction hash in the error data, so the caller can keep polling.
+///
+/// See <https://eips.ethereum.org/EIPS/eip-7966>
+pub async fn send_raw_transaction_sync<C>(
+    data: JsonRpcData<C>,
+    Params(params): Params<SendRawTransactionSyncParams>,
+) -> JsonRpcResult<et::TransactionReceipt>
+where
+    C: Client + Sync + Send,
+{
+    let (tx, timeout) = match params {
+        SendRawTransactionSyncParams::One((tx,)) => (tx, data.tx_sync_timeout),
+        SendRawTransactionSyncParams::Two((tx, timeout_ms)) => (
+            tx,
+            Duration::from_millis(timeout_ms).min(data.tx_sync_timeout),
+        ),
     };
-    let msg = ChainMessage::Signed(msg);
-    let bz: Vec<u8> = SignedMessageFactory::serialize(&msg)?;
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    let raw = RawTransaction::decode(&tx)?;
+
+    // Register before broadcasting, so we can't miss the event if the transaction is included quickly.
+    // Buffered transactions are re-submitted with the same payload, so they raise the same event.
+    let mut waiter = data.tx_waiters.wait(tx_hash(&raw.bz));
+
+    let msghash = broadcast_raw_transaction(&data, raw).await?;
+
+    match tokio::time::timeout_at(deadline, waiter.included()).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!(eth_hash = ?msghash, "transaction notifications stopped; polling for receipt");
+        }
+        Err(_) => {
+            tracing::debug!(eth_hash = ?msghash, "timed out waiting for transaction to be included");
+        }
+    }
+    drop(waiter);
+
+    // The event is raised when the block is committed, but the transaction might not be indexed yet.
+    loop {
+        let receipt = get_transaction_receipt(Data(data.0.clone()), Params((msghash,))).await?;
+
+        if let Some(receipt) = receipt {
+            return Ok(receipt);
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            // EIP-7966 reserves code 4 for transactions that were accepted but not included in time.
+            return Err(JsonRpcError {
+                code: 4,
+                message: format!(
+                    "the transaction was added to the mempool but wasn't processed in {}ms",
+                    timeout.as_millis()
+                ),
+                data: Some(serde_json::Value::String(format!("{msghash:?}"))),
+            });
+        }
+
+        let next = now + Duration::from_millis(TX_SYNC_POLL_MILLIS);
+        tokio::time::sleep_until(next.min(deadline)).await;
+    }
+}
+
+/// A raw transaction decoded and converted into the message we broadcast to CometBFT.
+struct RawTransaction {
+    tx: EthTransaction,
+    sig: et::Signature,
+    msghash: et::TxHash,
+    sender: Address,
+    nonce: u64,
+    msg: ChainMessage,
+    bz: Vec<u8>,
+}
+
+impl RawTransaction {
+    fn decode(tx: &et::Bytes) -> JsonRpcResult<Self> {
+        let (tx, mut sig) = EthTransaction::decode_signed(tx.as_ref())?;
+
+        // for legacy eip155 transactions, the chain id is encoded in it. The `v` most likely will not
+        // be normalized, normalize to ensure consistent txn hash calculation.
+        normalize_signature(&mut sig)?;
+
+        let sighash = tx.sighash();
+        let msghash = tx.hash(&sig);
+        tracing::debug!(?sighash, eth_hash = ?msghash, ?tx, "received raw transaction");
+
+        let msg = to_fvm_message(tx.clone())?;
+        let sender = msg.from;
+        let nonce = msg.sequence;
+
+        let msg = SignedMessage {
+            origin_kind: derive_origin_kind(&tx)?,
+            message: msg,
+            signature: Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: to_eth_extras(&tx)?,
+        };
+        let msg = ChainMessage::Signed(msg);
+        let bz: Vec<u8> = SignedMessageFactory::serialize(&msg)?;
+
+        Ok(Self {
+            tx,
+            sig,
+            msghash,
+            sender,
+            nonce,
+            msg,
+            bz,
+        })
+    }
+}
+
+/// Broadcast a transaction, or buffer it if its nonce is ahead of the sender's.
+async fn broadcast_raw_transaction<C>(
+    data: &JsonRpcData<C>,
+    raw: RawTransaction,
+) -> JsonRpcResult<et::TxHash>
+where
+    C: Client + Sync + Send,
+{
+    let RawTransaction {
+        tx,
+        sig,
+        msghash,
+        sender,
+        nonce,
+        msg,
+        bz,
+    } = raw;
 
     // Use the broadcast version which waits for basic checks to complete,
     // but not the execution results - those will have to be polled with get_transaction_receipt.
@@ -1216,6 +1289,45 @@ where
     Ok(et::Bytes::from(header.encode()))
 }
 
+/// Obtain the bottom-up checkpoint signatures the validators gossiped at a checkpoint height,
+/// one entry for each app hash that was signed.
+///
+/// Returns an empty list if the node has not collected any signatures at that height.
+pub async fn get_checkpoint_signatures<C>(
+    data: JsonRpcData<C>,
+    Params((block_number,)): Params<(et::BlockNumber,)>,
+) -> JsonRpcResult<Vec<CheckpointSignatures>>
+where
+    C: Client + Sync + Send,
+{
+    let h = block_number
+        .as_number()
+        .ok_or_else(|| anyhow!("invalid block #{}", block_number))?
+        .as_u64();
+
+    let res = data
+        .tm()
+        .abci_query(
+            Some(CHECKPOINT_SIGNATURES_QUERY_PATH.to_string()),
+            fvm_ipld_encoding::to_vec(&h)?,
+            None,
+            false,
+        )
+        .await?;
+
+    if res.code.is_err() {
+        if ExitCode::new(res.code.value()) == ExitCode::USR_NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        return error(ExitCode::new(res.code.value()), res.info);
+    }
+
+    let sigs = fvm_ipld_encoding::from_slice(&res.value)
+        .context("failed to decode checkpoint signatures")?;
+
+    Ok(sigs)
+}
+
 pub async fn get_state_root<C>(
     data: JsonRpcData<C>,
     Params((block_number,)): Params<(et::BlockNumber,)>,
@@ -1240,7 +1352,9 @@ pub async fn unsubscribe<C>(
 }
 
 use crate::state::ActorType;
-use params::{EstimateGasParams, SubscribeParams, TypedTransactionCompat};
+use params::{
+    EstimateGasParams, SendRawTransactionSyncParams, SubscribeParams, TypedTransactionCompat,
+};
 
 mod params {
     use ethers_core::types::transaction::eip2718::TypedTransaction;
@@ -1248,6 +1362,8 @@ mod params {
     use ethers_core::types::{self as et, Eip2930TransactionRequest, TransactionRequest};
     use serde::Deserialize;
 
+    use fendermint_vm_message::eth::EthTransaction;
+
     use crate::state::WebSocketId;
 
     /// Copied from `ethers` to override `data` deserialization.
@@ -1312,6 +1428,12 @@ mod params {
         }
     }
 
+    impl From<TypedTransactionCompat> for EthTransaction {
+        fn from(value: TypedTransactionCompat) -> Self {
+            TypedTransaction::from(value).into()
+        }
+    }
+
     /// The client either sends one or two items in the array, depending on whether a block ID is specified.
     /// This is to keep it backwards compatible with nodes that do not support the block ID parameter.
     /// If we were using `Option`, they would have to send `null`; this way it works with both 1 or 2 parameters.
@@ -1322,6 +1444,14 @@ mod params {
         Two((TypedTransactionCompat, et::BlockId)),
     }
 
+    /// The client either sends just the raw transaction, or the raw transaction and a timeout in milliseconds.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum SendRawTransactionSyncParams {
+        One((et::Bytes,)),
+        Two((et::Bytes, u64)),
+    }
+
     /// The client either sends one or two items in the array, depending on whether it's subscribing to block,
     /// transactions or logs. To that we add the web socket ID.
     #[derive(Deserialize)]
@@ -1336,7 +1466,9 @@ mod params {
     mod tests {
         use ethers_core::types::Eip1559TransactionRequest;
 
-        use crate::apis::eth::params::{Eip1559TransactionRequestCompat, EstimateGasParams};
+        use crate::apis::eth::params::{
+            Eip1559TransactionRequestCompat, EstimateGasParams, SendRawTransactionSyncParams,
+        };
 
         #[test]
         fn deserialize_estimate_gas_params() {
@@ -1347,6 +1479,18 @@ mod params {
             assert!(r.is_ok());
         }
 
+        #[test]
+        fn deserialize_send_raw_transaction_sync_params() {
+            let r = serde_json::from_str::<SendRawTransactionSyncParams>(r#"["0x01"]"#);
+            assert!(matches!(r, Ok(SendRawTransactionSyncParams::One(_))));
+
+            let r = serde_json::from_str::<SendRawTransactionSyncParams>(r#"["0x01", 2000]"#);
+            assert!(matches!(
+                r,
+                Ok(SendRawTransactionSyncParams::Two((_, 2000)))
+            ));
+        }
+
         #[test]
         fn deserialize_input_and_data() {
             let examples = [
@@ -1371,3 +1515,187 @@ mod params {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use ethers_core::types as et;
+    use fvm_shared::econ::TokenAmount;
+    use jsonrpc_v2::Data;
+    use tendermint::abci::{response::DeliverTx, Event, EventAttribute};
+    use tendermint::block::Height;
+    use tendermint_rpc::endpoint::{block_results, header, tx, tx_search};
+    use tendermint_rpc::{
+        Client, Error, Method, MockClient, MockRequestMethodMatcher, SimpleRequest,
+    };
+
+    use super::{gas_price, get_topdown_receipt};
+    use crate::conv::from_fvm::to_eth_tokens;
+    use crate::conv::from_tm::BLOCK_ZERO;
+    use crate::gas::{FeeIndex, FeeRecord, Premium};
+    use crate::state::JsonRpcState;
+    use crate::GasOpt;
+
+    /// The state needs a clonable client, answering the requests the matcher was set up with.
+    #[derive(Clone)]
+    struct SharedMockClient(Arc<MockClient<MockRequestMethodMatcher>>);
+
+    #[async_trait]
+    impl Client for SharedMockClient {
+        async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+        where
+            R: SimpleRequest,
+        {
+            self.0.perform(request).await
+        }
+    }
+
+    fn mock_state(
+        matcher: MockRequestMethodMatcher,
+        gas_opt: GasOpt,
+        fee_index: Arc<FeeIndex>,
+    ) -> Data<JsonRpcState<SharedMockClient>> {
+        let (client, _driver) = MockClient::new(matcher);
+        let state = JsonRpcState::new(
+            SharedMockClient(Arc::new(client)),
+            Duration::from_secs(60),
+            100,
+            10,
+            Duration::from_secs(10),
+            gas_opt,
+            fee_index,
+        );
+        Data(Arc::new(state))
+    }
+
+    /// Wrap a result the way the Tendermint RPC responds with it.
+    fn rpc_response<T: serde::Serialize>(result: T) -> Result<String, Error> {
+        Ok(serde_json::json!({ "jsonrpc": "2.0", "id": -1, "result": result }).to_string())
+    }
+
+    #[tokio::test]
+    async fn gas_price_is_base_fee_plus_premium() {
+        let base_fee = TokenAmount::from_atto(1_000_000_000);
+        let premium = TokenAmount::from_atto(1_000_000);
+
+        let fee_index = Arc::new(FeeIndex::new(10));
+        for height in 2..=5 {
+            let premiums = vec![Premium {
+                premium: premium.clone(),
+                gas_used: 1000,
+            }];
+            let record = FeeRecord::new(height, base_fee.clone(), 1000, 10_000, premiums);
+            fee_index.insert(record).unwrap();
+        }
+
+        let gas_opt = GasOpt {
+            min_gas_premium: TokenAmount::from_atto(0),
+            num_blocks_max_prio_fee: 3,
+            max_fee_hist_size: 10,
+            fee_index_path: None,
+        };
+
+        let state = mock_state(MockRequestMethodMatcher::default(), gas_opt, fee_index);
+
+        let price = gas_price(state).await.unwrap();
+
+        // The premium estimate has up to 0.5% of noise added to it.
+        let min = to_eth_tokens(&TokenAmount::from_atto(1_001_000_000)).unwrap();
+        let max = to_eth_tokens(&TokenAmount::from_atto(1_001_005_001)).unwrap();
+        assert!(
+            min <= price && price <= max,
+            "gas price {price} should be within [{min}, {max}]"
+        );
+    }
+
+    #[tokio::test]
+    async fn topdown_receipt_is_found_by_id() {
+        let id = et::H256::from([0xab; 32]);
+        let height = Height::from(5u32);
+
+        let mut block_header = BLOCK_ZERO.header.clone();
+        block_header.height = height;
+
+        // The events the app emits for a top-down message that failed in the gateway.
+        let attr = |key: &str, value: &str| EventAttribute {
+            key: key.to_string(),
+            value: value.to_string(),
+            index: true,
+        };
+        let tx_result = DeliverTx {
+            gas_used: 2500,
+            events: vec![Event::new(
+                "topdown",
+                vec![
+                    attr("id", &hex::encode(id.as_bytes())),
+                    attr("nonce", "3"),
+                    attr("exit_code", "33"),
+                    attr("gas_used", "1500"),
+                    attr("return_data", ""),
+                ],
+            )],
+            ..Default::default()
+        };
+
+        let tx_res = tx::Response {
+            hash: Default::default(),
+            height,
+            index: 0,
+            tx_result: tx_result.clone(),
+            tx: Vec::new(),
+            proof: None,
+        };
+
+        let matcher = MockRequestMethodMatcher::default()
+            .map(
+                Method::TxSearch,
+                rpc_response(tx_search::Response {
+                    txs: vec![tx_res],
+                    total_count: 1,
+                }),
+            )
+            .map(
+                Method::Header,
+                rpc_response(header::Response {
+                    header: block_header.clone(),
+                }),
+            )
+            .map(
+                Method::BlockResults,
+                rpc_response(block_results::Response {
+                    height,
+                    txs_results: Some(vec![tx_result]),
+                    begin_block_events: None,
+                    end_block_events: None,
+                    validator_updates: Vec::new(),
+                    consensus_param_updates: None,
+                }),
+            );
+
+        let gas_opt = GasOpt {
+            min_gas_premium: TokenAmount::from_atto(0),
+            num_blocks_max_prio_fee: 3,
+            max_fee_hist_size: 10,
+            fee_index_path: None,
+        };
+        let state = mock_state(matcher, gas_opt, Arc::new(FeeIndex::new(10)));
+
+        let receipt = get_topdown_receipt(state, id)
+            .await
+            .unwrap()
+            .expect("receipt should be found");
+
+        assert_eq!(receipt.transaction_hash, id);
+        assert_eq!(receipt.block_number, Some(et::U64::from(5)));
+        assert_eq!(
+            receipt.block_hash,
+            Some(et::H256::from_slice(block_header.hash().as_bytes()))
+        );
+        assert_eq!(receipt.gas_used, Some(et::U256::from(1500)));
+        assert_eq!(receipt.cumulative_gas_used, et::U256::from(2500));
+        assert_eq!(receipt.status, Some(et::U64::from(0)));
+    }
+}
@@ -12,6 +12,7 @@ use std::marker::PhantomData;
 
 mod eth;
 mod net;
+mod trace;
 mod web3;
 
 // TODO - move this to a more appropriate place - perhaps in the metrics module?
@@ -121,14 +122,23 @@ pub fn register_methods(server: ServerBuilder<MapRouter>) -> ServerBuilder<MapRo
         newPendingTransactionFilter,
         protocolVersion,
         sendRawTransaction,
+        sendRawTransactionSync,
         subscribe,
         syncing,
         getCommitSignedHeader,
+        getCheckpointSignatures,
         getStateRoot,
         uninstallFilter,
         unsubscribe
     });
 
+    let server = with_methods!(server, trace, {
+        block,
+        filter,
+        replayBlockTransactions,
+        transaction
+    });
+
     let server = with_methods!(server, web3, {
         clientVersion,
         sha3
@@ -0,0 +1,780 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// See the following for inspiration:
+// * https://openethereum.github.io/JSONRPC-trace-module
+// * https://github.com/filecoin-project/lotus/blob/v1.26.0/node/impl/full/eth_trace.go
+
+use cid::Cid;
+use ethers_core::types as et;
+use fendermint_rpc::query::QueryClient;
+use fendermint_rpc::response::decode_fevm_return_data;
+use fendermint_vm_actor_interface::eam::{self, EthAddress, EAM_ACTOR_ADDR};
+use fendermint_vm_actor_interface::evm;
+use fendermint_vm_actor_interface::init::INIT_ACTOR_ADDR;
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_message::query::{CallTrace, FvmQueryHeight, MessageTrace, TraceBlock};
+use fvm_ipld_encoding::tuple::Deserialize_tuple;
+use fvm_ipld_encoding::{strict_bytes, BytesDe, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::{METHOD_CONSTRUCTOR, METHOD_SEND};
+use jsonrpc_v2::Params;
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::endpoint::block_results;
+use tendermint_rpc::Client;
+
+use crate::conv::from_fvm::{to_eth_address, to_eth_tokens};
+use crate::conv::from_tm::{self, msg_hash, to_chain_message};
+use crate::{error, JsonRpcData, JsonRpcResult};
+
+/// Maximum number of blocks `trace_filter` is willing to replay in a single request.
+const MAX_TRACE_FILTER_BLOCKS: u64 = 100;
+
+/// Exit code of the EVM actor when the contract reverted.
+const EVM_CONTRACT_REVERTED: ExitCode = ExitCode::new(33);
+
+/// A single call in the flattened Parity trace of a transaction.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    pub action: Action,
+    pub result: Option<TraceResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub subtraces: usize,
+    pub trace_address: Vec<usize>,
+    #[serde(rename = "type")]
+    pub trace_type: TraceType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<et::H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<et::H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_position: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceType {
+    Call,
+    Create,
+    Suicide,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Action {
+    Call(CallAction),
+    Create(CreateAction),
+    Suicide(SuicideAction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallType {
+    Call,
+    DelegateCall,
+    StaticCall,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallAction {
+    pub call_type: CallType,
+    pub from: et::Address,
+    pub to: et::Address,
+    pub gas: et::U256,
+    pub input: et::Bytes,
+    pub value: et::U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CreationMethod {
+    Create,
+    Create2,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAction {
+    pub creation_method: CreationMethod,
+    pub from: et::Address,
+    pub gas: et::U256,
+    pub init: et::Bytes,
+    pub value: et::U256,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuicideAction {
+    pub address: et::Address,
+    pub refund_address: et::Address,
+    pub balance: et::U256,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum TraceResult {
+    Call(CallResult),
+    Create(CreateResult),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallResult {
+    pub gas_used: et::U256,
+    pub output: et::Bytes,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateResult {
+    pub address: et::Address,
+    /// The deployed bytecode is not part of the FVM trace, so this is always empty.
+    pub code: et::Bytes,
+    pub gas_used: et::U256,
+}
+
+/// The result of replaying a transaction in `trace_replayBlockTransactions`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayTrace {
+    pub output: et::Bytes,
+    /// State diffs are not supported.
+    pub state_diff: Option<()>,
+    pub trace: Vec<Trace>,
+    /// VM traces are not supported.
+    pub vm_trace: Option<()>,
+    pub transaction_hash: et::H256,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+    pub from_block: Option<et::BlockNumber>,
+    pub to_block: Option<et::BlockNumber>,
+    #[serde(default)]
+    pub from_address: Vec<et::Address>,
+    #[serde(default)]
+    pub to_address: Vec<et::Address>,
+    pub after: Option<usize>,
+    pub count: Option<usize>,
+}
+
+impl Trace {
+    /// The account the call was made by.
+    fn from_address(&self) -> et::Address {
+        match &self.action {
+            Action::Call(a) => a.from,
+            Action::Create(a) => a.from,
+            Action::Suicide(a) => a.address,
+        }
+    }
+
+    /// The account the call was made to, which for contract creation is the new contract.
+    fn to_address(&self) -> Option<et::Address> {
+        match (&self.action, &self.result) {
+            (Action::Call(a), _) => Some(a.to),
+            (Action::Create(_), Some(TraceResult::Create(r))) => Some(r.address),
+            (Action::Create(_), _) => None,
+            (Action::Suicide(a), _) => Some(a.refund_address),
+        }
+    }
+
+    fn with_tx(mut self, block_hash: et::H256, block_number: u64, tx: &BlockTx) -> Self {
+        self.block_hash = Some(block_hash);
+        self.block_number = Some(block_number);
+        self.transaction_hash = Some(tx.hash);
+        self.transaction_position = Some(tx.index);
+        self
+    }
+}
+
+impl TraceFilter {
+    fn matches(&self, trace: &Trace) -> bool {
+        (self.from_address.is_empty() || self.from_address.contains(&trace.from_address()))
+            && (self.to_address.is_empty()
+                || trace
+                    .to_address()
+                    .is_some_and(|to| self.to_address.contains(&to)))
+    }
+}
+
+/// Returns the traces of all transactions in a block.
+pub async fn block<C>(
+    data: JsonRpcData<C>,
+    Params((block_number,)): Params<(et::BlockNumber,)>,
+) -> JsonRpcResult<Vec<Trace>>
+where
+    C: Client + Sync + Send,
+{
+    let block = data.block_by_height(block_number).await?;
+    if from_tm::is_block_zero(&block) {
+        return Ok(Vec::new());
+    }
+    block_traces(&data, &block).await
+}
+
+/// Returns the traces of a transaction, or `null` if the transaction is unknown.
+pub async fn transaction<C>(
+    data: JsonRpcData<C>,
+    Params((tx_hash,)): Params<(et::H256,)>,
+) -> JsonRpcResult<Option<Vec<Trace>>>
+where
+    C: Client + Sync + Send,
+{
+    let Some(res) = data.tx_by_hash(tx_hash).await? else {
+        return Ok(None);
+    };
+
+    let index = res.index as usize;
+    let block = data
+        .block_by_height(et::BlockNumber::Number(res.height.value().into()))
+        .await?;
+    let block_hash = et::H256::from_slice(block.header.hash().as_bytes());
+    let block_number = block.header.height.value();
+
+    // Only the transactions before the one we are interested in have to be replayed.
+    let replayed = replay_block(&data, &block, Some(index)).await?;
+
+    match replayed.into_iter().find(|(tx, _)| tx.index == index) {
+        Some((tx, trace)) => {
+            let traces = to_traces(&trace)?
+                .into_iter()
+                .map(|t| t.with_tx(block_hash, block_number, &tx))
+                .collect();
+            Ok(Some(traces))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Replays all transactions in a block, returning the requested traces for each transaction.
+///
+/// Only the `trace` type is supported; `vmTrace` and `stateDiff` are always `null`.
+pub async fn replay_block_transactions<C>(
+    data: JsonRpcData<C>,
+    Params((block_number, trace_types)): Params<(et::BlockNumber, Vec<String>)>,
+) -> JsonRpcResult<Vec<ReplayTrace>>
+where
+    C: Client + Sync + Send,
+{
+    let block = data.block_by_height(block_number).await?;
+    if from_tm::is_block_zero(&block) {
+        return Ok(Vec::new());
+    }
+
+    let with_trace = trace_types.iter().any(|t| t == "trace");
+    let mut results = Vec::new();
+
+    for (tx, trace) in replay_block(&data, &block, None).await? {
+        let output = decode_fevm_return_data(trace.return_data.clone())
+            .unwrap_or_else(|_| trace.return_data.to_vec());
+
+        let traces = if with_trace {
+            to_traces(&trace)?
+        } else {
+            Vec::new()
+        };
+
+        results.push(ReplayTrace {
+            output: output.into(),
+            state_diff: None,
+            trace: traces,
+            vm_trace: None,
+            transaction_hash: tx.hash,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Returns the traces matching a filter, replaying the blocks in its range.
+pub async fn filter<C>(
+    data: JsonRpcData<C>,
+    Params((filter,)): Params<(TraceFilter,)>,
+) -> JsonRpcResult<Vec<Trace>>
+where
+    C: Client + Sync + Send,
+{
+    let latest = data.latest_height().await?.value();
+
+    let height_of = |block_number: Option<et::BlockNumber>| match block_number {
+        Some(et::BlockNumber::Number(n)) => n.as_u64(),
+        Some(et::BlockNumber::Earliest) => 1,
+        _ => latest,
+    };
+
+    let from_height = height_of(filter.from_block).max(1);
+    let to_height = height_of(filter.to_block).min(latest);
+
+    if from_height > to_height {
+        return Ok(Vec::new());
+    }
+    if to_height - from_height >= MAX_TRACE_FILTER_BLOCKS {
+        return error(
+            ExitCode::USR_ILLEGAL_ARGUMENT,
+            format!("cannot trace more than {MAX_TRACE_FILTER_BLOCKS} blocks at a time"),
+        );
+    }
+
+    let after = filter.after.unwrap_or_default();
+    let limit = filter.count.map(|c| after.saturating_add(c));
+    let mut traces = Vec::new();
+
+    for height in from_height..=to_height {
+        let block = data
+            .block_by_height(et::BlockNumber::Number(height.into()))
+            .await?;
+
+        traces.extend(
+            block_traces(&data, &block)
+                .await?
+                .into_iter()
+                .filter(|t| filter.matches(t)),
+        );
+
+        if limit.is_some_and(|limit| traces.len() >= limit) {
+            break;
+        }
+    }
+
+    let traces = traces
+        .into_iter()
+        .skip(after)
+        .take(filter.count.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(traces)
+}
+
+/// A signed transaction in a block.
+struct BlockTx {
+    /// Position of the transaction in the block.
+    index: usize,
+    hash: et::H256,
+    msg: Message,
+}
+
+/// Replay all the transactions of a block and return their traces in the context of the block.
+async fn block_traces<C>(
+    data: &JsonRpcData<C>,
+    block: &tendermint::Block,
+) -> JsonRpcResult<Vec<Trace>>
+where
+    C: Client + Sync + Send,
+{
+    let block_hash = et::H256::from_slice(block.header.hash().as_bytes());
+    let block_number = block.header.height.value();
+    let mut traces = Vec::new();
+
+    for (tx, trace) in replay_block(data, block, None).await? {
+        traces.extend(
+            to_traces(&trace)?
+                .into_iter()
+                .map(|t| t.with_tx(block_hash, block_number, &tx)),
+        );
+    }
+
+    Ok(traces)
+}
+
+/// Replay the messages of a block, up to and including the one at index `until`, over the
+/// state left by the previous block, and return the traces of the signed transactions.
+///
+/// The node executes the block with its own height, time and base fee, applying the implicit
+/// messages at the beginning of the block and the IPC messages the same way as on chain, so the
+/// transactions see their effects.
+async fn replay_block<C>(
+    data: &JsonRpcData<C>,
+    block: &tendermint::Block,
+    until: Option<usize>,
+) -> JsonRpcResult<Vec<(BlockTx, MessageTrace)>>
+where
+    C: Client + Sync + Send,
+{
+    let height = block.header.height;
+    let block_results: block_results::Response = data.tm().block_results(height).await?;
+    let tx_results = block_results.txs_results.unwrap_or_default();
+
+    let mut txs = Vec::new();
+    let mut messages = Vec::new();
+
+    for (index, (tx, tx_result)) in block.data.iter().zip(tx_results.iter()).enumerate() {
+        if until.is_some_and(|until| index > until) {
+            break;
+        }
+        let msg = to_chain_message(tx)?;
+        if let ChainMessage::Signed(ref msg) = msg {
+            txs.push(BlockTx {
+                index,
+                hash: msg_hash(&tx_result.events, tx),
+                msg: msg.message().clone(),
+            });
+        }
+        messages.push(msg);
+    }
+
+    if txs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let trace_block = TraceBlock {
+        height: height.value() as ChainEpoch,
+        timestamp: block.header.time.unix_timestamp() as u64,
+        block_hash: match block.header.hash() {
+            tendermint::Hash::Sha256(hash) => Some(hash),
+            tendermint::Hash::None => None,
+        },
+        messages,
+    };
+
+    // The app keeps the state a block was executed on under the height of the block itself.
+    let parent = FvmQueryHeight::Height(height.value());
+    let res = data.client.trace(trace_block, parent).await?;
+
+    if res.value.len() != txs.len() {
+        return error(
+            ExitCode::USR_ASSERTION_FAILED,
+            "unexpected number of traces in the response",
+        );
+    }
+
+    Ok(txs.into_iter().zip(res.value).collect())
+}
+
+#[derive(Deserialize_tuple)]
+struct CreateParams {
+    #[serde(with = "strict_bytes")]
+    initcode: Vec<u8>,
+    _nonce: u64,
+}
+
+#[derive(Deserialize_tuple)]
+struct Create2Params {
+    #[serde(with = "strict_bytes")]
+    initcode: Vec<u8>,
+    #[serde(with = "strict_bytes")]
+    _salt: Vec<u8>,
+}
+
+#[derive(Deserialize_tuple)]
+struct DelegateCallParams {
+    _code: Cid,
+    #[serde(with = "strict_bytes")]
+    input: Vec<u8>,
+    _caller: EthAddress,
+    value: TokenAmount,
+}
+
+/// Flatten the call tree of a message into Parity traces, in depth-first order.
+fn to_traces(trace: &MessageTrace) -> anyhow::Result<Vec<Trace>> {
+    let mut traces = Vec::new();
+    if let Some(ref call) = trace.trace {
+        // The gas used by the message includes charges made before the call.
+        add_traces(call, None, Vec::new(), Some(trace.gas_used), &mut traces)?;
+    }
+    Ok(traces)
+}
+
+fn add_traces(
+    call: &CallTrace,
+    code_address: Option<Address>,
+    trace_address: Vec<usize>,
+    gas_used: Option<u64>,
+    traces: &mut Vec<Trace>,
+) -> anyhow::Result<()> {
+    let mut calls = Vec::new();
+    collect_calls(call, &mut calls);
+
+    let trace = to_trace(
+        call,
+        code_address,
+        trace_address.clone(),
+        calls.len(),
+        gas_used.unwrap_or(call.gas_used),
+    )?;
+    traces.push(trace);
+
+    for (i, (call, code_address)) in calls.into_iter().enumerate() {
+        let mut trace_address = trace_address.clone();
+        trace_address.push(i);
+        add_traces(call, code_address, trace_address, None, traces)?;
+    }
+    Ok(())
+}
+
+/// Collect the subcalls which appear in Ethereum traces.
+///
+/// Bytecode lookups are left out, and the subcalls of the Init actor and of constructors
+/// are lifted up, so that the calls made during contract creation appear under the creation.
+/// Delegate calls are paired with the contract whose bytecode was looked up before them.
+fn collect_calls<'a>(call: &'a CallTrace, calls: &mut Vec<(&'a CallTrace, Option<Address>)>) {
+    let mut bytecode_of = None;
+
+    for call in call.calls.iter() {
+        if is_bytecode_lookup(call) {
+            bytecode_of = Some(call.to);
+        } else if call.to == INIT_ACTOR_ADDR || call.method_num == METHOD_CONSTRUCTOR {
+            collect_calls(call, calls);
+        } else if call.method_num == evm::Method::InvokeContractDelegate as u64 {
+            calls.push((call, bytecode_of));
+        } else {
+            calls.push((call, None));
+        }
+    }
+}
+
+fn is_bytecode_lookup(call: &CallTrace) -> bool {
+    call.read_only
+        && call.params.is_empty()
+        && (call.method_num == evm::Method::GetBytecode as u64
+            || call.method_num == evm::Method::GetBytecodeHash as u64)
+}
+
+fn to_trace(
+    call: &CallTrace,
+    code_address: Option<Address>,
+    trace_address: Vec<usize>,
+    subtraces: usize,
+    gas_used: u64,
+) -> anyhow::Result<Trace> {
+    let from = to_eth_addr(&call.from);
+    let to = to_eth_addr(&call.to);
+    let value = to_eth_tokens(&call.value)?;
+    let gas = et::U256::from(call.gas_limit);
+    let gas_used = et::U256::from(gas_used);
+    let error = call_error(call);
+
+    let (action, result, trace_type) = if call.self_destruct {
+        let action = Action::Suicide(SuicideAction {
+            address: from,
+            refund_address: to,
+            balance: value,
+        });
+        (action, None, TraceType::Suicide)
+    } else if let Some(creation_method) = creation_method(call) {
+        let init = if call.method_num == eam::Method::CreateExternal as u64 {
+            fvm_ipld_encoding::from_slice::<BytesDe>(&call.params).map(|p| p.0)
+        } else if creation_method == CreationMethod::Create2 {
+            fvm_ipld_encoding::from_slice::<Create2Params>(&call.params).map(|p| p.initcode)
+        } else {
+            fvm_ipld_encoding::from_slice::<CreateParams>(&call.params).map(|p| p.initcode)
+        }
+        .unwrap_or_else(|_| call.params.to_vec());
+
+        let address = fvm_ipld_encoding::from_slice::<eam::CreateReturn>(&call.return_data)
+            .ok()
+            .map(|r| et::Address::from(r.eth_address));
+
+        let action = Action::Create(CreateAction {
+            creation_method,
+            from,
+            gas,
+            init: init.into(),
+            value,
+        });
+
+        let result = match (&error, address) {
+            (None, Some(address)) => Some(TraceResult::Create(CreateResult {
+                address,
+                code: et::Bytes::default(),
+                gas_used,
+            })),
+            _ => None,
+        };
+
+        (action, result, TraceType::Create)
+    } else {
+        let is_invoke = call.method_num == evm::Method::InvokeContract as u64;
+        let is_delegate = call.method_num == evm::Method::InvokeContractDelegate as u64;
+
+        let (call_type, to, input, value) = if is_delegate {
+            let to = code_address.map(|a| to_eth_addr(&a)).unwrap_or(to);
+            match fvm_ipld_encoding::from_slice::<DelegateCallParams>(&call.params) {
+                Ok(p) => (
+                    CallType::DelegateCall,
+                    to,
+                    p.input,
+                    to_eth_tokens(&p.value)?,
+                ),
+                Err(_) => (CallType::DelegateCall, to, call.params.to_vec(), value),
+            }
+        } else {
+            let call_type = if call.read_only {
+                CallType::StaticCall
+            } else {
+                CallType::Call
+            };
+            let input = if is_invoke {
+                decode_or_raw(&call.params)
+            } else {
+                call.params.to_vec()
+            };
+            (call_type, to, input, value)
+        };
+
+        let output = if is_invoke || is_delegate {
+            decode_or_raw(&call.return_data)
+        } else {
+            call.return_data.to_vec()
+        };
+
+        let action = Action::Call(CallAction {
+            call_type,
+            from,
+            to,
+            gas,
+            input: input.into(),
+            value,
+        });
+
+        let result = error.is_none().then(|| {
+            TraceResult::Call(CallResult {
+                gas_used,
+                output: output.into(),
+            })
+        });
+
+        (action, result, TraceType::Call)
+    };
+
+    Ok(Trace {
+        action,
+        result,
+        error,
+        subtraces,
+        trace_address,
+        trace_type,
+        block_hash: None,
+        block_number: None,
+        transaction_hash: None,
+        transaction_position: None,
+    })
+}
+
+fn creation_method(call: &CallTrace) -> Option<CreationMethod> {
+    if call.to != EAM_ACTOR_ADDR {
+        return None;
+    }
+    match call.method_num {
+        m if m == eam::Method::Create as u64 || m == eam::Method::CreateExternal as u64 => {
+            Some(CreationMethod::Create)
+        }
+        m if m == eam::Method::Create2 as u64 => Some(CreationMethod::Create2),
+        _ => None,
+    }
+}
+
+fn call_error(call: &CallTrace) -> Option<String> {
+    if let Some(ref e) = call.error {
+        Some(e.clone())
+    } else if call.exit_code.is_success() {
+        None
+    } else if call.exit_code == EVM_CONTRACT_REVERTED {
+        Some("Reverted".to_owned())
+    } else if call.exit_code == ExitCode::SYS_OUT_OF_GAS {
+        Some("Out of gas".to_owned())
+    } else {
+        Some(format!("exit code {}", call.exit_code.value()))
+    }
+}
+
+/// Decode the bytes the FEVM wraps its input and output in, or return them as they are.
+fn decode_or_raw(data: &RawBytes) -> Vec<u8> {
+    decode_fevm_return_data(data.clone()).unwrap_or_else(|_| data.to_vec())
+}
+
+/// Present an address in Ethereum format, falling back to a masked ID or zero.
+fn to_eth_addr(addr: &Address) -> et::Address {
+    match to_eth_address(addr) {
+        Ok(Some(addr)) => addr,
+        _ => match addr.id() {
+            Ok(id) => EthAddress::from_id(id).into(),
+            Err(_) => et::Address::zero(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fendermint_vm_actor_interface::evm;
+    use fendermint_vm_message::query::{CallTrace, MessageTrace};
+    use fvm_ipld_encoding::{BytesSer, RawBytes};
+    use fvm_shared::{address::Address, econ::TokenAmount, error::ExitCode, METHOD_SEND};
+
+    use super::{to_traces, Action, CallType, TraceType};
+
+    fn call(from: u64, to: u64, method_num: u64, calls: Vec<CallTrace>) -> CallTrace {
+        CallTrace {
+            from: Address::new_id(from),
+            to: Address::new_id(to),
+            method_num,
+            value: TokenAmount::from_atto(0),
+            params: RawBytes::default(),
+            gas_limit: 1000,
+            gas_used: 10,
+            read_only: false,
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::default(),
+            error: None,
+            self_destruct: false,
+            calls,
+        }
+    }
+
+    #[test]
+    fn flattens_call_tree() {
+        let invoke = evm::Method::InvokeContract as u64;
+        let delegate = evm::Method::InvokeContractDelegate as u64;
+
+        let mut lookup = call(101, 102, evm::Method::GetBytecode as u64, vec![]);
+        lookup.read_only = true;
+
+        let mut transfer = call(101, 103, METHOD_SEND, vec![]);
+        transfer.self_destruct = true;
+
+        let mut root = call(
+            100,
+            101,
+            invoke,
+            vec![lookup, call(101, 101, delegate, vec![]), transfer],
+        );
+        root.params = RawBytes::serialize(BytesSer(&[1, 2, 3])).unwrap();
+
+        let trace = MessageTrace {
+            exit_code: ExitCode::OK,
+            info: String::new(),
+            return_data: RawBytes::default(),
+            gas_used: 100,
+            trace: Some(root),
+        };
+
+        let traces = to_traces(&trace).unwrap();
+
+        assert_eq!(traces.len(), 3);
+        assert_eq!(traces[0].subtraces, 2);
+
+        match &traces[0].action {
+            Action::Call(a) => assert_eq!(a.input.to_vec(), vec![1, 2, 3]),
+            other => panic!("unexpected action: {other:?}"),
+        }
+        match &traces[1].action {
+            Action::Call(a) => {
+                assert_eq!(a.call_type, CallType::DelegateCall);
+                assert_eq!(a.to, super::to_eth_addr(&Address::new_id(102)));
+            }
+            other => panic!("unexpected action: {other:?}"),
+        }
+        assert_eq!(traces[1].trace_address, vec![0]);
+        assert_eq!(traces[2].trace_type, TraceType::Suicide);
+        assert_eq!(traces[2].trace_address, vec![1]);
+    }
+}
@@ -3,56 +3,63 @@
 
 //! Helper methods to convert between Ethereum and FVM data formats.
 
+use anyhow::Context;
 use ethers_core::types as et;
 use ethers_core::types::transaction::eip2718::TypedTransaction;
-use ethers_core::types::{Eip1559TransactionRequest, TransactionRequest};
 
 pub use fendermint_vm_message::conv::from_eth::*;
+use fendermint_vm_message::eth::{EthExtras, EthTransaction};
 use fendermint_vm_message::signed::OriginKind;
 use fvm_shared::{error::ExitCode, message::Message};
 
 use crate::error::error_with_revert;
 use crate::JsonRpcResult;
 
-fn handle_typed_txn<
-    R,
-    F1: Fn(&TransactionRequest) -> JsonRpcResult<R>,
-    F2: Fn(&Eip1559TransactionRequest) -> JsonRpcResult<R>,
->(
-    tx: &TypedTransaction,
-    handle_legacy: F1,
-    handle_eip1559: F2,
-) -> JsonRpcResult<R> {
+/// Set code transactions are decoded, so the sender gets a clear error, but not accepted:
+/// the EVM actor cannot apply code delegations, so executing them would not do what was signed.
+///
+/// Their hashing and storage with the authorization list is in place for when it can.
+fn unsupported_eip7702<R>() -> JsonRpcResult<R> {
+    error_with_revert(
+        ExitCode::USR_ILLEGAL_ARGUMENT,
+        "EIP-7702 transactions are not supported: the EVM cannot apply code delegations",
+        None::<Vec<u8>>,
+    )
+}
+
+pub fn derive_origin_kind(tx: &EthTransaction) -> JsonRpcResult<OriginKind> {
     match tx {
-        TypedTransaction::Legacy(ref t) => handle_legacy(t),
-        TypedTransaction::Eip1559(ref t) => handle_eip1559(t),
-        _ => error_with_revert(
-            ExitCode::USR_ILLEGAL_ARGUMENT,
-            "txn type not supported",
-            None::<Vec<u8>>,
-        ),
+        EthTransaction::Typed(TypedTransaction::Legacy(_)) => Ok(OriginKind::EthereumLegacy),
+        EthTransaction::Typed(TypedTransaction::Eip2930(_)) => Ok(OriginKind::EthereumEIP2930),
+        EthTransaction::Typed(TypedTransaction::Eip1559(_)) => Ok(OriginKind::EthereumEIP1559),
+        EthTransaction::Eip7702(_) => unsupported_eip7702(),
     }
 }
 
-pub fn derive_origin_kind(tx: &TypedTransaction) -> JsonRpcResult<OriginKind> {
-    handle_typed_txn(
-        tx,
-        |_| Ok(OriginKind::EthereumLegacy),
-        |_| Ok(OriginKind::EthereumEIP1559),
-    )
+pub fn to_fvm_message(tx: EthTransaction) -> JsonRpcResult<Message> {
+    match tx {
+        EthTransaction::Typed(TypedTransaction::Legacy(ref t)) => Ok(fvm_message_from_legacy(t)?),
+        EthTransaction::Typed(TypedTransaction::Eip2930(ref t)) => Ok(fvm_message_from_eip2930(t)?),
+        EthTransaction::Typed(TypedTransaction::Eip1559(ref t)) => Ok(fvm_message_from_eip1559(t)?),
+        EthTransaction::Eip7702(_) => unsupported_eip7702(),
+    }
 }
 
-pub fn to_fvm_message(tx: TypedTransaction) -> JsonRpcResult<Message> {
-    handle_typed_txn(
-        &tx,
-        |r| Ok(fvm_message_from_legacy(r)?),
-        |r| Ok(fvm_message_from_eip1559(r)?),
-    )
+/// Collect the parts of the transaction which need to be stored next to the FVM message.
+pub fn to_eth_extras(tx: &EthTransaction) -> JsonRpcResult<Option<EthExtras>> {
+    let extras = match tx {
+        EthTransaction::Typed(TypedTransaction::Eip2930(t)) => {
+            Some(EthExtras::new(&t.access_list, &[])?)
+        }
+        EthTransaction::Eip7702(t) => Some(EthExtras::new(&t.access_list, &t.authorization_list)?),
+        _ => None,
+    };
+    Ok(extras)
 }
 
 /// Turn a request into the DTO returned by the API.
 pub fn to_eth_transaction_response(
-    tx: &TypedTransaction,
+    tx: &EthTransaction,
     sig: et::Signature,
 ) -> JsonRpcResult<et::Transaction> {
     macro_rules! essential_txn_response {
@@ -77,15 +84,21 @@ pub fn to_eth_transaction_response(
 
     let hash = tx.hash(&sig);
 
-    handle_typed_txn(
-        tx,
-        |tx| {
+    match tx {
+        EthTransaction::Typed(TypedTransaction::Legacy(tx)) => {
             let mut r = essential_txn_response!(tx, hash);
             r.gas_price = tx.gas_price;
             r.transaction_type = Some(0u64.into());
             Ok(r)
-        },
-        |tx| {
+        }
+        EthTransaction::Typed(TypedTransaction::Eip2930(tx)) => {
+            let mut r = essential_txn_response!(tx.tx, hash);
+            r.gas_price = tx.tx.gas_price;
+            r.transaction_type = Some(1u64.into());
+            r.access_list = Some(tx.access_list.clone());
+            Ok(r)
+        }
+        EthTransaction::Typed(TypedTransaction::Eip1559(tx)) => {
             let mut r = essential_txn_response!(tx, hash);
             r.max_fee_per_gas = tx.max_fee_per_gas;
             r.max_priority_fee_per_gas = tx.max_priority_fee_per_gas;
@@ -98,18 +111,43 @@ pub fn to_eth_transaction_response(
             r.transaction_type = Some(2u64.into());
             r.access_list = Some(tx.access_list.clone());
             Ok(r)
-        },
-    )
+        }
+        EthTransaction::Eip7702(tx) => {
+            let mut r = essential_txn_response!(tx, hash);
+            r.max_fee_per_gas = tx.max_fee_per_gas;
+            r.max_priority_fee_per_gas = tx.max_priority_fee_per_gas;
+            // Same as with "Type 2" above.
+            r.gas_price = Some(
+                tx.max_fee_per_gas.unwrap_or_default()
+                    + tx.max_priority_fee_per_gas.unwrap_or_default(),
+            );
+            r.transaction_type = Some(4u64.into());
+            r.access_list = Some(tx.access_list.clone());
+            // `ethers` doesn't know about authorization lists, so they go into the flattened extra fields.
+            r.other.insert(
+                "authorizationList".to_string(),
+                serde_json::to_value(&tx.authorization_list)
+                    .context("failed to serialize authorization list")?,
+            );
+            Ok(r)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::conv::from_eth::to_fvm_message;
+    use crate::conv::from_eth::{derive_origin_kind, to_eth_extras, to_fvm_message};
+    use ethers::signers::{LocalWallet, Signer};
     use ethers_core::types::transaction::eip2718::TypedTransaction;
-    use ethers_core::types::Signature;
+    use ethers_core::types::transaction::eip2930::{AccessList, AccessListItem};
+    use ethers_core::types::{
+        Address, Eip2930TransactionRequest, Signature, TransactionRequest, H256,
+    };
     use ethers_core::utils::rlp;
-    use fendermint_vm_message::signed::{OriginKind, SignedMessage};
+    use fendermint_vm_message::eth::{Eip7702TransactionRequest, EthTransaction};
+    use fendermint_vm_message::signed::{DomainHash, OriginKind, SignedMessage};
     use fvm_shared::chainid::ChainID;
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn test_legacy_transaction() {
@@ -120,13 +158,73 @@ mod tests {
         let (tx, sig): (TypedTransaction, Signature) =
             TypedTransaction::decode_signed(&rlp).unwrap();
 
-        let msg = to_fvm_message(tx).unwrap();
+        let msg = to_fvm_message(tx.into()).unwrap();
 
         let signed_msg = SignedMessage {
             origin_kind: OriginKind::EthereumLegacy,
             message: msg,
             signature: fvm_shared::crypto::signature::Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: None,
         };
         assert!(signed_msg.verify(&ChainID::from(1)).is_ok());
     }
+
+    #[test]
+    fn test_eip2930_transaction() {
+        let chain_id = 314u64;
+        let wallet = LocalWallet::new(&mut StdRng::seed_from_u64(2930)).with_chain_id(chain_id);
+
+        let tx: TypedTransaction = Eip2930TransactionRequest::new(
+            TransactionRequest::new()
+                .from(wallet.address())
+                .to(Address::repeat_byte(1))
+                .nonce(1)
+                .gas(21000)
+                .gas_price(100)
+                .value(1)
+                .chain_id(chain_id),
+            AccessList(vec![AccessListItem {
+                address: Address::repeat_byte(2),
+                storage_keys: vec![H256::repeat_byte(3)],
+            }]),
+        )
+        .into();
+
+        let sig = wallet.sign_transaction_sync(&tx).unwrap();
+        let raw_tx = tx.rlp_signed(&sig);
+
+        let (tx, sig) = EthTransaction::decode_signed(&raw_tx).unwrap();
+
+        let signed_msg = SignedMessage {
+            origin_kind: derive_origin_kind(&tx).unwrap(),
+            message: to_fvm_message(tx.clone()).unwrap(),
+            signature: fvm_shared::crypto::signature::Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: to_eth_extras(&tx).unwrap(),
+        };
+        assert_eq!(signed_msg.origin_kind, OriginKind::EthereumEIP2930);
+
+        let chain_id = ChainID::from(chain_id);
+        assert!(signed_msg.verify(&chain_id).is_ok());
+
+        match signed_msg.domain_hash(&chain_id).unwrap() {
+            Some(DomainHash::Eth(h)) => assert_eq!(h, tx.hash(&sig).0),
+            other => panic!("unexpected domain hash: {other:?}"),
+        }
+
+        // Without the access list the signature no longer checks out.
+        let mut stripped = signed_msg;
+        stripped.eth_extras = None;
+        assert!(stripped.verify(&chain_id).is_err());
+    }
+
+    #[test]
+    fn test_eip7702_transaction_rejected() {
+        let tx = EthTransaction::Eip7702(Eip7702TransactionRequest {
+            to: Some(Address::repeat_byte(1).into()),
+            ..Default::default()
+        });
+        let err = derive_origin_kind(&tx).unwrap_err();
+        assert!(err.message.contains("EIP-7702"), "{}", err.message);
+        assert!(to_fvm_message(tx).is_err());
+    }
 }
@@ -9,8 +9,13 @@ use std::str::FromStr;
 use anyhow::{anyhow, Context};
 use ethers_core::types::{self as et};
 use fendermint_vm_actor_interface::eam::EthAddress;
-use fendermint_vm_message::conv::from_fvm::to_eth_typed_transaction;
-use fendermint_vm_message::{chain::ChainMessage, signed::SignedMessage};
+use fendermint_vm_actor_interface::{init::builtin_actor_eth_addr, ipc::GATEWAY_ACTOR_ID};
+use fendermint_vm_message::conv::from_fvm::to_eth_transaction;
+use fendermint_vm_message::{
+    chain::ChainMessage,
+    eth::{EIP2930_TX_TYPE, EIP7702_TX_TYPE},
+    signed::{OriginKind, SignedMessage},
+};
 use fvm_shared::address::Address;
 use fvm_shared::bigint::Zero;
 use fvm_shared::chainid::ChainID;
@@ -214,14 +219,30 @@ pub fn to_eth_transaction_response(
         to_eth_signature(msg.signature(), true).context("failed to convert to eth signature")?;
 
     // Recover the original request; this method has better tests.
-    let tx = to_eth_typed_transaction(msg.origin_kind, &msg.message, &chain_id)
-        .context("failed to convert to tx request")?;
+    let tx = to_eth_transaction(
+        msg.origin_kind,
+        &msg.message,
+        msg.eth_extras.as_ref(),
+        &chain_id,
+    )
+    .context("failed to convert to tx request")?;
 
     let tx = from_eth::to_eth_transaction_response(&tx, sig)?;
 
     Ok(tx)
 }
 
+/// The EIP-2718 type of the Ethereum transaction a message originates from.
+fn to_eth_transaction_type(origin_kind: OriginKind) -> u64 {
+    match origin_kind {
+        OriginKind::EthereumLegacy => 0,
+        OriginKind::EthereumEIP2930 => EIP2930_TX_TYPE as u64,
+        OriginKind::EthereumEIP7702 => EIP7702_TX_TYPE as u64,
+        // Value used by Lotus.
+        OriginKind::EthereumEIP1559 | OriginKind::Fvm => 2,
+    }
+}
+
 /// Helper function to produce cumulative gas used after the execution of each transaction in a block,
 /// along with cumulative event log count.
 pub fn to_cumulative(block_results: &endpoint::block_results::Response) -> Vec<(et::U256, usize)> {
@@ -251,6 +272,7 @@ pub async fn to_eth_receipt(
     let block_number = et::U64::from(result.height.value());
     let transaction_index = et::U64::from(result.index);
     let transaction_hash = msg_hash(&result.tx_result.events, &result.tx);
+    let transaction_type = to_eth_transaction_type(msg.origin_kind);
 
     let msg = &msg.message;
     // Lotus effective gas price is based on total spend divided by gas used,
@@ -306,13 +328,78 @@ pub async fn to_eth_receipt(
         })),
         root: Some(app_hash_to_root(&header.app_hash)?),
         logs_bloom: et::Bloom::from_slice(&*EMPTY_ETH_BLOOM),
-        transaction_type: Some(et::U64::from(2)), // Value used by Lotus.
+        transaction_type: Some(et::U64::from(transaction_type)),
         effective_gas_price: Some(to_eth_tokens(&effective_gas_price)?),
         other: Default::default(),
     };
     Ok(receipt)
 }
 
+/// Synthetic receipt for a top-down message, based on the `topdown` event emitted
+/// for it by the transaction which executed the parent finality it was part of.
+///
+/// Returns `None` if the transaction has no receipt for the cross-message ID.
+pub fn to_topdown_receipt(
+    id: et::H256,
+    result: &endpoint::tx::Response,
+    cumulative: &[(et::U256, usize)],
+    header: &tendermint::block::Header,
+) -> anyhow::Result<Option<et::TransactionReceipt>> {
+    let id_hex = hex::encode(id.as_bytes());
+
+    let Some(event) = result.tx_result.events.iter().find(|e| {
+        e.kind == "topdown"
+            && e.attributes
+                .iter()
+                .any(|a| a.key == "id" && a.value == id_hex)
+    }) else {
+        return Ok(None);
+    };
+
+    let attr = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|a| a.key == key)
+            .map(|a| a.value.as_str())
+            .ok_or_else(|| anyhow!("cannot find the '{key}' key in the top-down receipt"))
+    };
+
+    let exit_code = attr("exit_code")?
+        .parse::<u32>()
+        .context("invalid exit code")?;
+    let gas_used = attr("gas_used")?
+        .parse::<u64>()
+        .context("invalid gas used")?;
+
+    let (cumulative_gas_used, _) = cumulative
+        .get(result.index as usize)
+        .cloned()
+        .unwrap_or_default();
+
+    let receipt = et::TransactionReceipt {
+        transaction_hash: id,
+        transaction_index: et::U64::from(result.index),
+        block_hash: Some(et::H256::from_slice(header.hash().as_bytes())),
+        block_number: Some(et::U64::from(result.height.value())),
+        // Top-down messages are applied by the system actor, identified by the null-address.
+        from: et::H160::zero(),
+        to: Some(et::H160::from(builtin_actor_eth_addr(GATEWAY_ACTOR_ID).0)),
+        cumulative_gas_used,
+        gas_used: Some(et::U256::from(gas_used)),
+        contract_address: None,
+        logs: Vec::new(),
+        status: Some(et::U64::from(if exit_code == 0 { 1 } else { 0 })),
+        root: Some(app_hash_to_root(&header.app_hash)?),
+        logs_bloom: et::Bloom::from_slice(&*EMPTY_ETH_BLOOM),
+        transaction_type: Some(et::U64::from(2)),
+        effective_gas_price: Some(et::U256::zero()),
+        other: Default::default(),
+    };
+
+    Ok(Some(receipt))
+}
+
 /// Change the type of transactions in a block by mapping a function over them.
 pub fn map_rpc_block_txs<F, A, B, E>(block: et::Block<A>, f: F) -> Result<et::Block<B>, E>
 where
@@ -0,0 +1,422 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Per-block fee records, maintained as blocks are committed, so that
+//! `eth_feeHistory`, `eth_gasPrice` and `eth_maxPriorityFeePerGas` don't
+//! have to replay blocks from CometBFT on every request.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use fs_err as fs;
+use futures::StreamExt;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+use tendermint::block::Height;
+use tendermint_rpc::{
+    endpoint::commit,
+    event::EventData,
+    query::{EventType, Query},
+    Client, SubscriptionClient,
+};
+
+use crate::{state::JsonRpcState, HybridClient};
+
+const RETRY_SLEEP_SECS: u64 = 5;
+
+/// Fee related facts about a single block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeRecord {
+    pub height: u64,
+    /// Base fee reported by the gas market at this height.
+    #[serde(with = "atto")]
+    pub base_fee: TokenAmount,
+    /// Total gas used by the transactions in the block.
+    pub gas_used: i64,
+    /// Block gas limit from the consensus parameters.
+    pub gas_limit: i64,
+    /// Effective premiums of the signed messages in the block, in ascending order.
+    pub premiums: Vec<Premium>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Premium {
+    #[serde(with = "atto")]
+    pub premium: TokenAmount,
+    pub gas_used: i64,
+}
+
+impl FeeRecord {
+    pub fn new(
+        height: u64,
+        base_fee: TokenAmount,
+        gas_used: i64,
+        gas_limit: i64,
+        mut premiums: Vec<Premium>,
+    ) -> Self {
+        premiums.sort_by(|a, b| {
+            a.premium
+                .cmp(&b.premium)
+                .then_with(|| a.gas_used.cmp(&b.gas_used))
+        });
+        Self {
+            height,
+            base_fee,
+            gas_used,
+            gas_limit,
+            premiums,
+        }
+    }
+
+    /// Ratio of the gas used to the block gas limit.
+    pub fn gas_used_ratio(&self) -> f64 {
+        if self.gas_limit <= 0 {
+            0.0
+        } else {
+            self.gas_used as f64 / self.gas_limit as f64
+        }
+    }
+
+    /// The premium at the given percentile (0-100) of the gas used by the signed messages.
+    pub fn reward(&self, percentile: f64) -> TokenAmount {
+        if self.premiums.is_empty() {
+            return TokenAmount::default();
+        }
+        let premium_gas_used: i64 = self.premiums.iter().map(|p| p.gas_used).sum();
+        let threshold_gas_used = (premium_gas_used as f64 * percentile / 100f64) as i64;
+        let mut sum_gas_used = 0;
+        let mut idx = 0;
+        while sum_gas_used < threshold_gas_used && idx < self.premiums.len() - 1 {
+            sum_gas_used += self.premiums[idx].gas_used;
+            idx += 1;
+        }
+        self.premiums[idx].premium.clone()
+    }
+}
+
+/// Bounded index of the fee records of the most recent blocks,
+/// optionally persisted to a file so it survives restarts.
+pub struct FeeIndex {
+    capacity: usize,
+    records: RwLock<BTreeMap<u64, FeeRecord>>,
+    store: Option<Mutex<FeeStore>>,
+}
+
+impl FeeIndex {
+    /// Create an index which only lives in memory.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            records: Default::default(),
+            store: None,
+        }
+    }
+
+    /// Create an index backed by a file, loading any records already in it.
+    pub fn open(capacity: usize, path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let mut index = Self::new(capacity);
+        let (store, records) = FeeStore::open(path.into(), index.capacity)?;
+        index.records = RwLock::new(records);
+        index.store = Some(Mutex::new(store));
+        Ok(index)
+    }
+
+    /// Maximum number of blocks kept in the index.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Add or replace the record of a block, evicting the oldest ones beyond capacity.
+    ///
+    /// The store is locked for the whole insert, so the file sees the records in the same order
+    /// as the index, but the index is only locked while it is updated, not during file I/O.
+    pub fn insert(&self, record: FeeRecord) -> anyhow::Result<()> {
+        let store = self
+            .store
+            .as_ref()
+            .map(|store| store.lock().expect("fee store poisoned"));
+
+        // Records to rewrite the file with if it has grown too large.
+        let compacted = {
+            let mut records = self.records.write().expect("fee index poisoned");
+            records.insert(record.height, record.clone());
+            while records.len() > self.capacity {
+                records.pop_first();
+            }
+            match store {
+                Some(ref store) if store.num_lines >= 2 * self.capacity => {
+                    Some(records.values().cloned().collect::<Vec<_>>())
+                }
+                _ => None,
+            }
+        };
+
+        if let Some(mut store) = store {
+            match compacted {
+                Some(records) => store.compact(records.iter())?,
+                None => store.append(&record)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, height: u64) -> Option<FeeRecord> {
+        let records = self.records.read().expect("fee index poisoned");
+        records.get(&height).cloned()
+    }
+
+    /// Height of the most recent block in the index.
+    pub fn last_height(&self) -> Option<u64> {
+        let records = self.records.read().expect("fee index poisoned");
+        records.last_key_value().map(|(h, _)| *h)
+    }
+
+    /// The most recent record in the index.
+    pub fn last(&self) -> Option<FeeRecord> {
+        let records = self.records.read().expect("fee index poisoned");
+        records.last_key_value().map(|(_, r)| r.clone())
+    }
+}
+
+/// Append-only file with one JSON record per line, compacted when it grows too large.
+struct FeeStore {
+    path: PathBuf,
+    file: fs::File,
+    num_lines: usize,
+}
+
+impl FeeStore {
+    fn open(path: PathBuf, capacity: usize) -> anyhow::Result<(Self, BTreeMap<u64, FeeRecord>)> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut records = BTreeMap::new();
+
+        if path.exists() {
+            let reader = BufReader::new(fs::File::open(&path)?);
+            for (i, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<FeeRecord>(&line) {
+                    Ok(record) => {
+                        records.insert(record.height, record);
+                    }
+                    Err(e) => {
+                        // Most likely the last line was only partially written before a crash.
+                        tracing::warn!(error = ?e, line = i + 1, ?path, "ignoring invalid fee record");
+                        break;
+                    }
+                }
+            }
+            while records.len() > capacity {
+                records.pop_first();
+            }
+        }
+
+        let mut store = Self {
+            file: Self::open_append(&path)?,
+            path,
+            num_lines: 0,
+        };
+        // Start from a clean file, dropping evicted or corrupted records.
+        store.compact(records.values())?;
+
+        Ok((store, records))
+    }
+
+    fn open_append(path: &Path) -> anyhow::Result<fs::File> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(file)
+    }
+
+    fn append(&mut self, record: &FeeRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(record).context("failed to serialize fee record")?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.num_lines += 1;
+        Ok(())
+    }
+
+    /// Rewrite the file with only the given records, replacing it atomically.
+    fn compact<'a>(&mut self, records: impl Iterator<Item = &'a FeeRecord>) -> anyhow::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp = fs::File::create(&tmp_path)?;
+        let mut num_lines = 0;
+        for record in records {
+            let line = serde_json::to_string(record).context("failed to serialize fee record")?;
+            writeln!(tmp, "{line}")?;
+            num_lines += 1;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.file = Self::open_append(&self.path)?;
+        self.num_lines = num_lines;
+        Ok(())
+    }
+}
+
+/// Keep the fee index up to date by indexing blocks as they are committed.
+///
+/// Catches up with whatever was committed since the index was last updated,
+/// so restarts and dropped subscriptions don't leave gaps behind.
+pub fn start_fee_indexing(state: Arc<JsonRpcState<HybridClient>>) {
+    tokio::task::spawn(async move {
+        fee_indexing_loop(state).await;
+    });
+}
+
+async fn fee_indexing_loop<C>(state: Arc<JsonRpcState<C>>)
+where
+    C: Client + SubscriptionClient + Send + Sync,
+{
+    loop {
+        let query = Query::from(EventType::NewBlock);
+
+        match state.tm().subscribe(query).await {
+            Err(e) => {
+                tracing::warn!(error=?e, "failed to subscribe to NewBlocks; retrying later...");
+                tokio::time::sleep(Duration::from_secs(RETRY_SLEEP_SECS)).await;
+            }
+            Ok(mut subscription) => {
+                index_committed_blocks(&state).await;
+
+                while let Some(result) = subscription.next().await {
+                    match result {
+                        Err(e) => {
+                            tracing::warn!(error=?e, "NewBlocks subscription failed; resubscribing...");
+                            break;
+                        }
+                        Ok(event) => {
+                            if let EventData::NewBlock { .. } = event.data {
+                                index_committed_blocks(&state).await;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn index_committed_blocks<C>(state: &JsonRpcState<C>)
+where
+    C: Client + Send + Sync,
+{
+    if let Err(e) = try_index_committed_blocks(state).await {
+        tracing::warn!(error=?e, "failed to index block fees");
+    }
+}
+
+/// Index every block between the last indexed one and the latest one with results,
+/// skipping anything that wouldn't fit into the index anyway.
+async fn try_index_committed_blocks<C>(state: &JsonRpcState<C>) -> anyhow::Result<()>
+where
+    C: Client + Send + Sync,
+{
+    // The latest commit is for the block before the last one, which should have results.
+    let res: commit::Response = state.tm().latest_commit().await?;
+    let latest = res.signed_header.header.height.value();
+
+    let capacity = state.fee_index.capacity() as u64;
+    let first = latest.saturating_sub(capacity) + 1;
+    let next = state
+        .fee_index
+        .last_height()
+        .map(|h| h + 1)
+        .unwrap_or(first)
+        .max(first);
+
+    for height in next..=latest {
+        let height = Height::try_from(height).context("invalid height")?;
+        match state.fee_record(height).await? {
+            Some(record) => state.fee_index.insert(record)?,
+            // Results aren't available yet; try again on the next block.
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Store token amounts as decimal strings of atto.
+mod atto {
+    use std::str::FromStr;
+
+    use fvm_shared::{bigint::BigInt, econ::TokenAmount};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &TokenAmount, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&value.atto().to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<TokenAmount, D::Error> {
+        let s = String::deserialize(d)?;
+        let atto = BigInt::from_str(&s).map_err(D::Error::custom)?;
+        Ok(TokenAmount::from_atto(atto))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+
+    use super::{FeeIndex, FeeRecord, Premium};
+
+    fn record(height: u64) -> FeeRecord {
+        let premiums = [300, 100, 200]
+            .into_iter()
+            .map(|p| Premium {
+                premium: TokenAmount::from_atto(p),
+                gas_used: 1000,
+            })
+            .collect();
+        FeeRecord::new(
+            height,
+            TokenAmount::from_atto(100 + height),
+            3000,
+            10000,
+            premiums,
+        )
+    }
+
+    #[test]
+    fn test_reward_percentiles() {
+        let r = record(1);
+        assert_eq!(r.premiums[0].premium, TokenAmount::from_atto(100));
+        assert_eq!(r.reward(0.0), TokenAmount::from_atto(100));
+        assert_eq!(r.reward(50.0), TokenAmount::from_atto(300));
+        assert_eq!(r.reward(100.0), TokenAmount::from_atto(300));
+        assert_eq!(r.gas_used_ratio(), 0.3);
+    }
+
+    #[test]
+    fn test_index_persisted_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fees.jsonl");
+
+        {
+            let index = FeeIndex::open(3, &path).unwrap();
+            // Enough inserts to trigger a compaction.
+            for h in 1..=10 {
+                index.insert(record(h)).unwrap();
+            }
+            assert_eq!(index.get(7), None);
+            assert_eq!(index.last_height(), Some(10));
+        }
+
+        let index = FeeIndex::open(3, &path).unwrap();
+        assert_eq!(index.last_height(), Some(10));
+        assert_eq!(index.get(8), Some(record(8)));
+        assert_eq!(index.get(7), None);
+    }
+}
@@ -6,9 +6,12 @@ use fvm_shared::{
     message::Message,
 };
 
+mod index;
 // Copy of https://github.com/filecoin-project/ref-fvm/blob/fvm%40v3.3.1/fvm/src/gas/outputs.rs
 mod output;
 
+pub use index::{start_fee_indexing, FeeIndex, FeeRecord, Premium};
+
 // https://github.com/filecoin-project/lotus/blob/6cc506f5cf751215be6badc94a960251c6453202/node/impl/full/eth.go#L2220C41-L2228
 pub fn effective_gas_price(msg: &Message, base_fee: &TokenAmount, gas_used: i64) -> TokenAmount {
     let out = output::GasOutputs::compute(
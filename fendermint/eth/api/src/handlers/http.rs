@@ -4,11 +4,17 @@
 
 // Based on https://github.com/ChainSafe/forest/blob/v0.8.2/node/rpc/src/rpc_http_handler.rs
 
+use std::net::{IpAddr, SocketAddr};
+
+use axum::body::Bytes;
+use axum::extract::ConnectInfo;
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use jsonrpc_v2::{RequestObject, ResponseObjects};
 use serde::Deserialize;
+use serde_json::json;
 
+use crate::access::{AccessError, Caller};
 use crate::{apis, AppState};
 
 type ResponseHeaders = [(&'static str, &'static str); 1];
@@ -26,13 +32,45 @@ pub enum RequestKind {
     Many(Vec<RequestObject>),
 }
 
+impl RequestKind {
+    fn methods(&self) -> Vec<&str> {
+        match self {
+            RequestKind::One(request) => vec![request.method_ref()],
+            RequestKind::Many(requests) => requests.iter().map(|r| r.method_ref()).collect(),
+        }
+    }
+}
+
 /// Handle JSON-RPC calls.
 pub async fn handle(
-    _headers: HeaderMap,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     axum::extract::State(state): axum::extract::State<AppState>,
-    axum::Json(request): axum::Json<RequestKind>,
+    body: Bytes,
 ) -> impl IntoResponse {
-    // NOTE: Any authorization can come here.
+    let request = match serde_json::from_slice::<RequestKind>(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let response = json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32700, "message": format!("parse error: {e}") },
+                "id": null,
+            });
+            return (
+                StatusCode::BAD_REQUEST,
+                RESPONSE_HEADERS,
+                response.to_string(),
+            );
+        }
+    };
+
+    let ip = state.access.client_ip(addr.ip(), &headers);
+    if let Err(e) = check_access(&state, &headers, ip, &request) {
+        tracing::debug!(?ip, error = e.to_string(), "RPC request rejected");
+        e.record();
+        return access_error_response(&e, &body);
+    }
+
     let response = match request {
         RequestKind::One(request) => {
             if let Err(response) = check_request(&request) {
@@ -84,6 +122,49 @@ fn json_response(response: &ResponseObjects) -> (StatusCode, ResponseHeaders, st
     }
 }
 
+/// Authenticate the caller and apply the method lists and the rate limits to the request.
+fn check_access(
+    state: &AppState,
+    headers: &HeaderMap,
+    ip: IpAddr,
+    request: &RequestKind,
+) -> Result<(), AccessError> {
+    let identity = state.access.authenticate(headers)?;
+
+    if let RequestKind::Many(requests) = request {
+        state.access.check_batch_size(requests.len())?;
+    }
+
+    let methods = request.methods();
+    for method in methods.iter() {
+        state.access.check_method(method)?;
+    }
+
+    let caller = Caller {
+        ip,
+        identity: identity.map(|i| i.id),
+    };
+    state.access.charge(&caller, methods)
+}
+
+/// Respond with an error to each request in the body, keeping their IDs.
+fn access_error_response(
+    e: &AccessError,
+    body: &[u8],
+) -> (StatusCode, ResponseHeaders, std::string::String) {
+    let id = |request: &serde_json::Value| request.get("id").cloned().unwrap_or_default();
+
+    let response = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Array(requests)) => {
+            serde_json::Value::Array(requests.iter().map(|r| e.to_response(id(r))).collect())
+        }
+        Ok(request) => e.to_response(id(&request)),
+        Err(_) => e.to_response(serde_json::Value::Null),
+    };
+
+    (e.status(), RESPONSE_HEADERS, response.to_string())
+}
+
 fn check_request(
     request: &RequestObject,
 ) -> Result<(), (StatusCode, ResponseHeaders, std::string::String)> {
@@ -4,19 +4,27 @@
 
 // Based on https://github.com/ChainSafe/forest/blob/v0.8.2/node/rpc/src/rpc_ws_handler.rs
 
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
-        WebSocketUpgrade,
+        ws::{close_code, CloseFrame, Message, WebSocket},
+        ConnectInfo, WebSocketUpgrade,
     },
     http::HeaderMap,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use futures::{stream::SplitSink, SinkExt, StreamExt};
 use jsonrpc_v2::{RequestObject, ResponseObject, ResponseObjects, V2};
 use serde_json::json;
 
-use crate::{apis, state::WebSocketId, AppState, JsonRpcServer};
+use crate::{
+    access::{AccessError, Caller},
+    apis,
+    state::WebSocketId,
+    AppState, JsonRpcServer,
+};
 
 /// Mirroring [ethers_providers::rpc::transports::ws::types::Notification], which is what the library
 /// expects for non-request-response payloads in [PubSubItem::deserialize].
@@ -33,19 +41,53 @@ pub struct MethodNotification {
     pub notification: Notification,
 }
 
+/// State of a WebSocket connection used for access control.
+struct Connection {
+    caller: Caller,
+    /// When the token the connection was authenticated with expires.
+    expires_at: Option<SystemTime>,
+    /// Number of active subscriptions.
+    subscriptions: usize,
+}
+
 pub async fn handle(
-    _headers: HeaderMap,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     axum::extract::State(state): axum::extract::State<AppState>,
     ws: WebSocketUpgrade,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| async { rpc_ws_handler_inner(state, socket).await })
+) -> Response {
+    // Authenticate once, before the upgrade, so unauthorized clients cannot open a connection.
+    let identity = match state.access.authenticate(&headers) {
+        Ok(identity) => identity,
+        Err(e) => {
+            e.record();
+            let response = e.to_response(serde_json::Value::Null);
+            return (e.status(), axum::Json(response)).into_response();
+        }
+    };
+
+    let (identity, expires_at) = match identity {
+        Some(i) => (Some(i.id), i.expires_at),
+        None => (None, None),
+    };
+
+    let conn = Connection {
+        caller: Caller {
+            ip: state.access.client_ip(addr.ip(), &headers),
+            identity,
+        },
+        expires_at,
+        subscriptions: 0,
+    };
+
+    ws.on_upgrade(move |socket| async { rpc_ws_handler_inner(state, conn, socket).await })
 }
 
 /// Handle requests in a loop, interpreting each message as a JSON-RPC request.
 ///
 /// Messages are evaluated one by one. We could spawn tasks like Forest,
 /// but there should be some rate limiting applied to avoid DoS attacks.
-async fn rpc_ws_handler_inner(state: AppState, socket: WebSocket) {
+async fn rpc_ws_handler_inner(state: AppState, mut conn: Connection, socket: WebSocket) {
     tracing::debug!("Accepted WS connection!");
     let (mut sender, mut receiver) = socket.split();
 
@@ -54,14 +96,27 @@ async fn rpc_ws_handler_inner(state: AppState, socket: WebSocket) {
 
     let web_socket_id = state.rpc_state.add_web_socket(notif_tx).await;
 
+    // The connection cannot outlive the token it was authenticated with.
+    let expiry = expiry(conn.expires_at);
+    tokio::pin!(expiry);
+
     loop {
         let keep = tokio::select! {
             Some(Ok(message)) = receiver.next() => {
-                handle_incoming(web_socket_id, &state.rpc_server, &mut sender, message).await
+                handle_incoming(web_socket_id, &state, &mut conn, &mut sender, message).await
             },
             Some(notif) = notif_rx.recv() => {
                 handle_outgoing(web_socket_id, &mut sender, notif).await
             },
+            () = &mut expiry => {
+                tracing::debug!(web_socket_id, "closing WS connection with expired token");
+                let frame = CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "token expired".into(),
+                };
+                let _ = sender.send(Message::Close(Some(frame))).await;
+                false
+            },
             else => break,
         };
 
@@ -75,10 +130,24 @@ async fn rpc_ws_handler_inner(state: AppState, socket: WebSocket) {
     state.rpc_state.remove_web_socket(&web_socket_id).await;
 }
 
+/// Resolve when the token expires, or never if it doesn't.
+async fn expiry(expires_at: Option<SystemTime>) {
+    match expires_at {
+        Some(expires_at) => {
+            let left = expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            tokio::time::sleep(left).await
+        }
+        None => std::future::pending().await,
+    }
+}
+
 /// Handle an incoming request.
 async fn handle_incoming(
     web_socket_id: WebSocketId,
-    rpc_server: &JsonRpcServer,
+    state: &AppState,
+    conn: &mut Connection,
     sender: &mut SplitSink<WebSocket, Message>,
     message: Message,
 ) -> bool {
@@ -92,7 +161,28 @@ async fn handle_incoming(
 
             match serde_json::from_str::<RequestObject>(&request_text) {
                 Ok(req) => {
-                    return send_call_result(web_socket_id, rpc_server, sender, req).await;
+                    if let Err(e) = check_access(state, conn, req.method_ref()) {
+                        tracing::debug!(
+                            web_socket_id,
+                            error = e.to_string(),
+                            "WS request rejected"
+                        );
+                        e.record();
+                        let id = serde_json::from_str::<serde_json::Value>(&request_text)
+                            .ok()
+                            .and_then(|r| r.get("id").cloned())
+                            .unwrap_or_default();
+                        return send_text(web_socket_id, sender, e.to_response(id).to_string())
+                            .await;
+                    }
+                    return send_call_result(
+                        web_socket_id,
+                        &state.rpc_server,
+                        sender,
+                        req,
+                        &mut conn.subscriptions,
+                    )
+                    .await;
                 }
                 Err(e) => {
                     deserialization_error("RequestObject", e);
@@ -103,6 +193,33 @@ async fn handle_incoming(
     true
 }
 
+/// Apply the method lists, the rate limits and the subscription limit to a request.
+fn check_access(state: &AppState, conn: &Connection, method: &str) -> Result<(), AccessError> {
+    state.access.check_method(method)?;
+    if apis::is_streaming_method(method) {
+        state.access.check_subscriptions(conn.subscriptions)?;
+    }
+    state.access.charge(&conn.caller, [method])
+}
+
+/// Keep count of the active subscriptions of the connection based on the responses.
+fn track_subscriptions(method: &str, response: &ResponseObject, subscriptions: &mut usize) {
+    if method != "eth_subscribe" && method != "eth_unsubscribe" {
+        return;
+    }
+    let result = serde_json::to_value(response)
+        .ok()
+        .and_then(|r| r.get("result").cloned());
+
+    match (method, result) {
+        ("eth_subscribe", Some(_)) => *subscriptions += 1,
+        ("eth_unsubscribe", Some(serde_json::Value::Bool(true))) => {
+            *subscriptions = subscriptions.saturating_sub(1)
+        }
+        _ => {}
+    }
+}
+
 fn deserialization_error(what: &str, e: serde_json::Error) {
     // Not responding to the websocket because it requires valid responses, which need to have
     // the `id` field present, which we'd only get if we managed to parse the request.
@@ -185,16 +302,21 @@ async fn send_call_result(
     server: &JsonRpcServer,
     sender: &mut SplitSink<WebSocket, Message>,
     request: RequestObject,
+    subscriptions: &mut usize,
 ) -> bool {
-    let method = request.method_ref();
+    let method = request.method_ref().to_owned();
 
     tracing::debug!("RPC WS called method: {}", method);
 
     match server.handle(request).await {
         ResponseObjects::Empty => true,
-        ResponseObjects::One(response) => send_response(web_socket_id, sender, response).await,
+        ResponseObjects::One(response) => {
+            track_subscriptions(&method, &response, subscriptions);
+            send_response(web_socket_id, sender, response).await
+        }
         ResponseObjects::Many(responses) => {
             for response in responses {
+                track_subscriptions(&method, &response, subscriptions);
                 if !send_response(web_socket_id, sender, response).await {
                     return false;
                 }
@@ -209,20 +331,25 @@ async fn send_response(
     sender: &mut SplitSink<WebSocket, Message>,
     response: ResponseObject,
 ) -> bool {
-    let response = serde_json::to_string(&response);
-
-    match response {
+    match serde_json::to_string(&response) {
         Err(e) => {
             tracing::error!(error=?e, "failed to serialize response to JSON");
+            true
         }
-        Ok(json) => {
-            tracing::debug!(web_socket_id, json, "sending response to WS");
-            if let Err(e) = sender.send(Message::Text(json)).await {
-                tracing::warn!(web_socket_id, error=?e, "failed to send response to WS");
-                if is_closed_connection(e) {
-                    return false;
-                }
-            }
+        Ok(json) => send_text(web_socket_id, sender, json).await,
+    }
+}
+
+async fn send_text(
+    web_socket_id: WebSocketId,
+    sender: &mut SplitSink<WebSocket, Message>,
+    json: String,
+) -> bool {
+    tracing::debug!(web_socket_id, json, "sending response to WS");
+    if let Err(e) = sender.send(Message::Text(json)).await {
+        tracing::warn!(web_socket_id, error=?e, "failed to send response to WS");
+        if is_closed_connection(e) {
+            return false;
         }
     }
     true
@@ -1,13 +1,19 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use axum::routing::{get, post};
 use fvm_shared::econ::TokenAmount;
 use jsonrpc_v2::Data;
-use std::{net::ToSocketAddrs, sync::Arc, time::Duration};
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 
+mod access;
 pub mod apis;
 mod cache;
 mod client;
@@ -19,6 +25,9 @@ mod handlers;
 mod mpool;
 mod state;
 
+pub use access::{
+    AccessControl, AccessOpt, RateLimitOpt, RPC_REQUESTS_REJECTED, RPC_REQUEST_UNITS,
+};
 pub use client::{HybridClient, HybridClientDriver};
 
 use error::{error, JsonRpcError};
@@ -34,6 +43,7 @@ type JsonRpcResult<T> = Result<T, JsonRpcError>;
 pub struct AppState {
     pub rpc_server: JsonRpcServer,
     pub rpc_state: Arc<JsonRpcState<HybridClient>>,
+    pub access: Arc<AccessControl>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +51,8 @@ pub struct GasOpt {
     pub min_gas_premium: TokenAmount,
     pub num_blocks_max_prio_fee: u64,
     pub max_fee_hist_size: u64,
+    /// File to persist the per-block fee records in; kept only in memory if missing.
+    pub fee_index_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,16 +69,29 @@ pub async fn listen<A: ToSocketAddrs>(
     filter_timeout: Duration,
     cache_capacity: usize,
     max_nonce_gap: Nonce,
+    tx_sync_timeout: Duration,
     gas_opt: GasOpt,
     cors_opt: CorsOpt,
+    access_opt: AccessOpt,
 ) -> anyhow::Result<()> {
     if let Some(listen_addr) = listen_addr.to_socket_addrs()?.next() {
+        let fee_index_capacity = gas_opt
+            .max_fee_hist_size
+            .max(gas_opt.num_blocks_max_prio_fee) as usize;
+        let fee_index = match gas_opt.fee_index_path {
+            Some(ref path) => gas::FeeIndex::open(fee_index_capacity, path)
+                .with_context(|| format!("failed to open fee index at {path:?}"))?,
+            None => gas::FeeIndex::new(fee_index_capacity),
+        };
+
         let rpc_state = Arc::new(JsonRpcState::new(
             client,
             filter_timeout,
             cache_capacity,
             max_nonce_gap,
+            tx_sync_timeout,
             gas_opt,
+            Arc::new(fee_index),
         ));
 
         // Start the transaction cache pruning subscription.
@@ -76,13 +101,21 @@ pub async fn listen<A: ToSocketAddrs>(
             rpc_state.tx_buffer.clone(),
         );
 
+        // Start notifying the requests waiting for their transactions to be included.
+        mpool::start_tx_waiter_notification(rpc_state.tm().clone(), rpc_state.tx_waiters.clone());
+
+        // Start indexing the fees of committed blocks.
+        gas::start_fee_indexing(rpc_state.clone());
+
         let rpc_server = make_server(rpc_state.clone());
         let app_state = AppState {
             rpc_server,
             rpc_state,
+            access: Arc::new(AccessControl::new(access_opt)),
         };
         let router = make_router(app_state, cors_opt);
-        let server = axum::Server::try_bind(&listen_addr)?.serve(router.into_make_service());
+        let server = axum::Server::try_bind(&listen_addr)?
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>());
         tracing::info!(?listen_addr, "bound Ethereum API");
         server.await?;
         Ok(())
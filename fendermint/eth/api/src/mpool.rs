@@ -1,14 +1,19 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 //! Utilities related to caching and buffering Ethereum transactions.
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use ethers_core::types as et;
-use ethers_core::types::transaction::eip2718::TypedTransaction;
 use fendermint_rpc::{
     client::TendermintClient, message::SignedMessageFactory, FendermintClient, QueryClient,
 };
-use fendermint_vm_message::{chain::ChainMessage, query::FvmQueryHeight, signed::DomainHash};
+use fendermint_vm_message::{
+    chain::ChainMessage, eth::EthTransaction, query::FvmQueryHeight, signed::DomainHash,
+};
 use futures::StreamExt;
 use fvm_shared::{address::Address, chainid::ChainID};
 use tendermint::Block;
@@ -17,12 +22,13 @@ use tendermint_rpc::{
     query::{EventType, Query},
     Client, SubscriptionClient,
 };
+use tokio::sync::oneshot;
 
-use crate::{cache::Cache, state::Nonce, HybridClient};
+use crate::{cache::Cache, conv::from_tm::tx_hash, state::Nonce, HybridClient};
 
 const RETRY_SLEEP_SECS: u64 = 5;
 
-pub type SignedTransaction = (TypedTransaction, et::Signature);
+pub type SignedTransaction = (EthTransaction, et::Signature);
 /// Cache submitted transactions by their Ethereum hash, because the CometBFT
 /// API would not be able to find them until they are delivered to the application
 /// and indexed by their domain hash, which some tools interpret as the transaction
@@ -80,6 +86,119 @@ impl TransactionBuffer {
     }
 }
 
+/// Requests waiting for their transactions to be included in a block, by CometBFT transaction hash.
+///
+/// They are all notified from a single `Tx` subscription, rather than each opening their own.
+#[derive(Clone, Default)]
+pub struct TransactionWaiters(Arc<Mutex<HashMap<tendermint::Hash, Vec<oneshot::Sender<()>>>>>);
+
+impl TransactionWaiters {
+    /// Start waiting for a transaction. Register before broadcasting, so the event can't be missed.
+    pub fn wait(&self, hash: tendermint::Hash) -> TransactionWaiter {
+        let (tx, rx) = oneshot::channel();
+        self.with(|ws| ws.entry(hash).or_default().push(tx));
+        TransactionWaiter {
+            hash,
+            waiters: self.clone(),
+            rx,
+        }
+    }
+
+    /// Notify everyone waiting for the transaction that it has been included.
+    fn notify(&self, hash: &tendermint::Hash) {
+        if let Some(txs) = self.with(|ws| ws.remove(hash)) {
+            for tx in txs {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    /// Remove the waiters which have given up on the transaction.
+    fn prune(&self, hash: &tendermint::Hash) {
+        self.with(|ws| {
+            if let Some(txs) = ws.get_mut(hash) {
+                txs.retain(|tx| !tx.is_closed());
+                if txs.is_empty() {
+                    ws.remove(hash);
+                }
+            }
+        })
+    }
+
+    fn with<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut HashMap<tendermint::Hash, Vec<oneshot::Sender<()>>>) -> T,
+    {
+        let mut guard = self.0.lock().expect("transaction waiters poisoned");
+        f(&mut guard)
+    }
+}
+
+/// A registration to be notified when a transaction is included; removed when dropped.
+pub struct TransactionWaiter {
+    hash: tendermint::Hash,
+    waiters: TransactionWaiters,
+    rx: oneshot::Receiver<()>,
+}
+
+impl TransactionWaiter {
+    /// Wait until the transaction is included. Returns `false` if the notifications stopped.
+    pub async fn included(&mut self) -> bool {
+        (&mut self.rx).await.is_ok()
+    }
+}
+
+impl Drop for TransactionWaiter {
+    fn drop(&mut self) {
+        self.rx.close();
+        self.waiters.prune(&self.hash);
+    }
+}
+
+/// Subscribe to `Tx` notifications and wake up the requests waiting for them.
+pub fn start_tx_waiter_notification<C>(client: C, waiters: TransactionWaiters)
+where
+    C: Client + SubscriptionClient + Send + Sync + 'static,
+{
+    tokio::task::spawn(async move {
+        tx_waiter_notification_loop(client, waiters).await;
+    });
+}
+
+/// Subscribe to notifications about executed transactions and notify anyone waiting for them.
+///
+/// Re-subscribe in the event of a subscription failure.
+async fn tx_waiter_notification_loop<C>(client: C, waiters: TransactionWaiters)
+where
+    C: Client + SubscriptionClient + Send + Sync,
+{
+    loop {
+        let query = Query::from(EventType::Tx);
+
+        match client.subscribe(query).await {
+            Err(e) => {
+                tracing::warn!(error=?e, "failed to subscribe to Tx events; retrying later...");
+                tokio::time::sleep(Duration::from_secs(RETRY_SLEEP_SECS)).await;
+            }
+            Ok(mut subscription) => {
+                while let Some(result) = subscription.next().await {
+                    match result {
+                        Err(e) => {
+                            tracing::warn!(error=?e, "Tx subscription failed; resubscribing...");
+                            break;
+                        }
+                        Ok(event) => {
+                            if let EventData::Tx { tx_result } = event.data {
+                                waiters.notify(&tx_hash(&tx_result.tx));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Subscribe to `NewBlock`  notifications and clear transactions from the caches.`
 pub fn start_tx_cache_clearing(
     client: FendermintClient<HybridClient>,
@@ -203,3 +322,30 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TransactionWaiters;
+    use crate::conv::from_tm::tx_hash;
+
+    #[tokio::test]
+    async fn waiters_are_notified_by_hash() {
+        let waiters = TransactionWaiters::default();
+        let (h1, h2) = (tx_hash(b"tx-1"), tx_hash(b"tx-2"));
+
+        let mut w1a = waiters.wait(h1);
+        let mut w1b = waiters.wait(h1);
+        let mut w2 = waiters.wait(h2);
+
+        waiters.notify(&h1);
+
+        assert!(w1a.included().await);
+        assert!(w1b.included().await);
+        assert!(w2.rx.try_recv().is_err());
+        assert!(waiters.with(|ws| !ws.contains_key(&h1)));
+
+        // Giving up removes the registration, so the map doesn't grow with abandoned requests.
+        drop(w2);
+        assert!(waiters.with(|ws| ws.is_empty()));
+    }
+}
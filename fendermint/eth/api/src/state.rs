@@ -6,6 +6,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
@@ -23,7 +24,9 @@ use rand::Rng;
 use tendermint::block::Height;
 use tendermint_rpc::query::Query;
 use tendermint_rpc::{
-    endpoint::{block, block_by_hash, block_results, commit, header, header_by_hash},
+    endpoint::{
+        block, block_by_hash, block_results, commit, consensus_params, header, header_by_hash,
+    },
     Client,
 };
 use tendermint_rpc::{Order, Subscription, SubscriptionClient};
@@ -39,8 +42,9 @@ use crate::filters::{
     run_subscription, BlockHash, FilterCommand, FilterDriver, FilterId, FilterKind, FilterMap,
     FilterRecords,
 };
+use crate::gas::{FeeIndex, FeeRecord, Premium};
 use crate::handlers::ws::MethodNotification;
-use crate::mpool::{TransactionBuffer, TransactionCache};
+use crate::mpool::{TransactionBuffer, TransactionCache, TransactionWaiters};
 use crate::GasOpt;
 use crate::{
     conv::from_tm::{
@@ -67,12 +71,18 @@ pub struct JsonRpcState<C> {
     pub tx_cache: TransactionCache,
     /// Buffer out-of-order transactions until they can be submitted.
     pub tx_buffer: TransactionBuffer,
+    /// Requests waiting for their transactions to be included in a block.
+    pub tx_waiters: TransactionWaiters,
     filter_timeout: Duration,
     filters: FilterMap,
     next_web_socket_id: AtomicUsize,
     web_sockets: RwLock<HashMap<WebSocketId, WebSocketSender>>,
     pub max_nonce_gap: Nonce,
+    /// Maximum time to wait for a transaction to be included in `eth_sendRawTransactionSync`.
+    pub tx_sync_timeout: Duration,
     pub gas_opt: GasOpt,
+    /// Fee records of recent blocks.
+    pub fee_index: Arc<FeeIndex>,
 }
 
 impl<C> JsonRpcState<C>
@@ -84,7 +94,9 @@ where
         filter_timeout: Duration,
         cache_capacity: usize,
         max_nonce_gap: Nonce,
+        tx_sync_timeout: Duration,
         gas_opt: GasOpt,
+        fee_index: Arc<FeeIndex>,
     ) -> Self {
         let client = FendermintClient::new(client);
         let addr_cache = AddressCache::new(client.clone(), cache_capacity);
@@ -98,12 +110,15 @@ where
             addr_cache,
             tx_cache,
             tx_buffer,
+            tx_waiters: Default::default(),
             filter_timeout,
             filters: Default::default(),
             next_web_socket_id: Default::default(),
             web_sockets: Default::default(),
             gas_opt,
             max_nonce_gap,
+            tx_sync_timeout,
+            fee_index,
         }
     }
 }
@@ -400,6 +415,23 @@ where
         }
     }
 
+    /// Find the transaction which executed a top-down message, by its cross-message ID.
+    pub async fn topdown_tx_by_id(
+        &self,
+        id: et::H256,
+    ) -> JsonRpcResult<Option<tendermint_rpc::endpoint::tx::Response>> {
+        let query = Query::eq("topdown.id", hex::encode(id.as_bytes()));
+
+        match self
+            .tm()
+            .tx_search(query, false, 1, 1, Order::Ascending)
+            .await
+        {
+            Ok(res) => Ok(res.txs.into_iter().next()),
+            Err(e) => error(ExitCode::USR_UNSPECIFIED, e),
+        }
+    }
+
     /// Send a message by the system actor to an EVM actor for a read-only query.
     ///
     /// If the actor doesn't exist then the FVM will create a placeholder actor,
@@ -462,6 +494,74 @@ where
         }
     }
 
+    /// Compute the fee record of a block from CometBFT and the ledger state at its height.
+    ///
+    /// Returns `None` if the block doesn't have results yet.
+    pub async fn fee_record(&self, height: Height) -> anyhow::Result<Option<FeeRecord>> {
+        let block_results: block_results::Response = match self.tm().block_results(height).await {
+            Ok(res) => res,
+            Err(_) => return Ok(None),
+        };
+
+        let block: block::Response = self
+            .tm()
+            .block(height)
+            .await
+            .context("failed to get block")?;
+
+        let state_params = self
+            .client
+            .state_params(FvmQueryHeight::Height(height.value()))
+            .await
+            .context("failed to get state params")?;
+
+        let base_fee = state_params.value.base_fee;
+
+        let consensus_params: consensus_params::Response = self
+            .tm()
+            .consensus_params(height)
+            .await
+            .context("failed to get consensus params")?;
+
+        let mut gas_limit = consensus_params.consensus_params.block.max_gas;
+        if gas_limit <= 0 {
+            gas_limit = i64::try_from(BLOCK_GAS_LIMIT).expect("FVM block gas limit not i64")
+        };
+
+        let txs_results = block_results.txs_results.unwrap_or_default();
+        let gas_used: i64 = txs_results.iter().map(|r| r.gas_used).sum();
+
+        let mut premiums = Vec::new();
+        for (tx, txres) in block.block.data().iter().zip(txs_results) {
+            let msg = fvm_ipld_encoding::from_slice::<ChainMessage>(tx)
+                .context("failed to decode tx as ChainMessage")?;
+
+            if let ChainMessage::Signed(msg) = msg {
+                premiums.push(Premium {
+                    premium: crate::gas::effective_gas_premium(&msg.message, &base_fee),
+                    gas_used: txres.gas_used,
+                });
+            }
+        }
+
+        Ok(Some(FeeRecord::new(
+            height.value(),
+            base_fee,
+            gas_used,
+            gas_limit,
+            premiums,
+        )))
+    }
+
+    /// Get the fee record of a block from the index, or compute it if it hasn't been indexed.
+    pub async fn fee_record_at(&self, height: u64) -> anyhow::Result<Option<FeeRecord>> {
+        if let Some(record) = self.fee_index.get(height) {
+            return Ok(Some(record));
+        }
+        let height = Height::try_from(height).context("invalid height")?;
+        self.fee_record(height).await
+    }
+
     pub async fn get_actor_type(
         &self,
         address: &et::H160,
@@ -2,17 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::path::Path;
+use std::sync::Arc;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use base64::Engine;
 use bytes::Bytes;
+use ethers_core::types::transaction::eip2718::TypedTransaction;
 use fendermint_crypto::SecretKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_actor_interface::{eam, evm};
+use fendermint_vm_message::conv::from_fvm;
+use fendermint_vm_message::signed::OriginKind;
 use fendermint_vm_message::{chain::ChainMessage, signed::SignedMessage};
 use fvm_ipld_encoding::{BytesSer, RawBytes};
 use fvm_shared::{
-    address::Address, chainid::ChainID, econ::TokenAmount, message::Message, MethodNum, METHOD_SEND,
+    address::Address, chainid::ChainID, crypto::signature::Signature, econ::TokenAmount,
+    message::Message, MethodNum, METHOD_SEND,
 };
+use ipc_wallet::{y_parity, Signer};
 
 use fs_err as fs;
 
@@ -123,16 +130,34 @@ impl MessageFactory {
 /// For those one must use the Ethereum API, with a suitable client library such as [ethers].
 pub struct SignedMessageFactory {
     inner: MessageFactory,
-    sk: SecretKey,
+    signer: MessageSigner,
     chain_id: ChainID,
 }
 
+/// The key the messages are signed with.
+enum MessageSigner {
+    /// Secret key held in memory.
+    Local(SecretKey),
+    /// Signer which may keep the key elsewhere, e.g. behind a remote signing service.
+    External(Arc<dyn Signer>),
+}
+
 impl SignedMessageFactory {
     /// Create a factor from a secret key and its corresponding address, which could be a delegated one.
     pub fn new(sk: SecretKey, addr: Address, sequence: u64, chain_id: ChainID) -> Self {
         Self {
             inner: MessageFactory::new(addr, sequence),
-            sk,
+            signer: MessageSigner::Local(sk),
+            chain_id,
+        }
+    }
+
+    /// Create a factory signing with an external [`Signer`], sending from its `f410` address.
+    pub fn new_with_signer(signer: Arc<dyn Signer>, sequence: u64, chain_id: ChainID) -> Self {
+        let addr = EthAddress(signer.address().0).into();
+        Self {
+            inner: MessageFactory::new(addr, sequence),
+            signer: MessageSigner::External(signer),
             chain_id,
         }
     }
@@ -165,17 +190,18 @@ impl SignedMessageFactory {
     }
 
     /// Transfer tokens to another account.
-    pub fn transfer(
+    pub async fn transfer(
         &mut self,
         to: Address,
         value: TokenAmount,
         gas_params: GasParams,
     ) -> anyhow::Result<ChainMessage> {
         self.transaction(to, METHOD_SEND, Default::default(), value, gas_params)
+            .await
     }
 
     /// Send a message to an actor.
-    pub fn transaction(
+    pub async fn transaction(
         &mut self,
         to: Address,
         method_num: MethodNum,
@@ -186,13 +212,18 @@ impl SignedMessageFactory {
         let message = self
             .inner
             .transaction(to, method_num, params, value, gas_params);
-        let signed = SignedMessage::new_secp256k1(message, &self.sk, &self.chain_id)?;
+        let signed = match &self.signer {
+            MessageSigner::Local(sk) => SignedMessage::new_secp256k1(message, sk, &self.chain_id)?,
+            MessageSigner::External(signer) => {
+                sign_with_signer(signer.as_ref(), message, &self.chain_id).await?
+            }
+        };
         let chain = ChainMessage::Signed(signed);
         Ok(chain)
     }
 
     /// Deploy a FEVM contract.
-    pub fn fevm_create(
+    pub async fn fevm_create(
         &mut self,
         contract: Bytes,
         constructor_args: Bytes,
@@ -201,18 +232,20 @@ impl SignedMessageFactory {
     ) -> anyhow::Result<ChainMessage> {
         let initcode = [contract.to_vec(), constructor_args.to_vec()].concat();
         let initcode = RawBytes::serialize(BytesSer(&initcode))?;
-        let message = self.transaction(
-            eam::EAM_ACTOR_ADDR,
-            eam::Method::CreateExternal as u64,
-            initcode,
-            value,
-            gas_params,
-        )?;
+        let message = self
+            .transaction(
+                eam::EAM_ACTOR_ADDR,
+                eam::Method::CreateExternal as u64,
+                initcode,
+                value,
+                gas_params,
+            )
+            .await?;
         Ok(message)
     }
 
     /// Invoke a method on a FEVM contract.
-    pub fn fevm_invoke(
+    pub async fn fevm_invoke(
         &mut self,
         contract: Address,
         calldata: Bytes,
@@ -220,25 +253,29 @@ impl SignedMessageFactory {
         gas_params: GasParams,
     ) -> anyhow::Result<ChainMessage> {
         let calldata = RawBytes::serialize(BytesSer(&calldata))?;
-        let message = self.transaction(
-            contract,
-            evm::Method::InvokeContract as u64,
-            calldata,
-            value,
-            gas_params,
-        )?;
+        let message = self
+            .transaction(
+                contract,
+                evm::Method::InvokeContract as u64,
+                calldata,
+                value,
+                gas_params,
+            )
+            .await?;
         Ok(message)
     }
 
     /// Create a message for a read-only operation.
-    pub fn fevm_call(
+    pub async fn fevm_call(
         &mut self,
         contract: Address,
         calldata: Bytes,
         value: TokenAmount,
         gas_params: GasParams,
     ) -> anyhow::Result<Message> {
-        let msg = self.fevm_invoke(contract, calldata, value, gas_params)?;
+        let msg = self
+            .fevm_invoke(contract, calldata, value, gas_params)
+            .await?;
 
         let msg = if let ChainMessage::Signed(signed) = msg {
             signed.into_message()
@@ -253,6 +290,42 @@ impl SignedMessageFactory {
     }
 }
 
+/// Sign a message with an external signer.
+///
+/// Messages are signed as EIP-1559 transactions, so a remote signer can inspect them,
+/// which means the recipient has to have an Ethereum address.
+async fn sign_with_signer(
+    signer: &dyn Signer,
+    message: Message,
+    chain_id: &ChainID,
+) -> anyhow::Result<SignedMessage> {
+    if from_fvm::to_eth_address(&message.to).is_err() {
+        bail!(
+            "external signers can only sign messages to Ethereum addresses, not {}",
+            message.to
+        );
+    }
+
+    let tx: TypedTransaction = from_fvm::to_eth_eip1559_request(&message, chain_id)?.into();
+
+    let signature = signer.sign_transaction(&tx).await?;
+
+    // The FVM expects the recovery ID, not the EIP-155 `v` value.
+    let mut bytes = [0u8; 65];
+    signature.r.to_big_endian(&mut bytes[..32]);
+    signature.s.to_big_endian(&mut bytes[32..64]);
+    bytes[64] = y_parity(&signature, u64::from(*chain_id))?;
+
+    let signature = Signature::new_secp256k1(bytes.to_vec());
+
+    let signed = SignedMessage::new_unchecked(OriginKind::EthereumEIP1559, message, signature);
+    signed
+        .verify(chain_id)
+        .context("signer returned an invalid signature")?;
+
+    Ok(signed)
+}
+
 #[derive(Clone, Debug)]
 pub struct GasParams {
     /// Maximum amount of gas that can be charged.
@@ -265,3 +338,81 @@ pub struct GasParams {
     /// Gas premium.
     pub gas_premium: TokenAmount,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ethers_core::types::transaction::eip2718::TypedTransaction;
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use fendermint_vm_actor_interface::evm;
+    use fendermint_vm_message::chain::ChainMessage;
+    use fendermint_vm_message::conv::from_fvm;
+    use fvm_ipld_encoding::{BytesSer, RawBytes};
+    use fvm_shared::address::Address;
+    use fvm_shared::chainid::ChainID;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_wallet::{LocalSigner, Signer};
+
+    use super::{GasParams, SignedMessageFactory};
+
+    fn gas_params() -> GasParams {
+        GasParams {
+            gas_limit: 10_000_000,
+            gas_fee_cap: TokenAmount::from_atto(1000),
+            gas_premium: TokenAmount::from_atto(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn external_signature_recovers_signer() {
+        let signer = Arc::new(LocalSigner::new(&[0x11; 32]).unwrap());
+        let chain_id = ChainID::from(1942764459484029u64);
+        let mut mf = SignedMessageFactory::new_with_signer(signer.clone(), 0, chain_id);
+
+        let to = Address::from(EthAddress([0x22; 20]));
+        let params = RawBytes::serialize(BytesSer(&[1, 2, 3])).unwrap();
+        let msg = mf
+            .transaction(
+                to,
+                evm::Method::InvokeContract as u64,
+                params,
+                TokenAmount::from_atto(1),
+                gas_params(),
+            )
+            .await
+            .unwrap();
+
+        let ChainMessage::Signed(signed) = msg else {
+            panic!("expected a signed message");
+        };
+
+        let tx: TypedTransaction = from_fvm::to_eth_eip1559_request(signed.message(), &chain_id)
+            .unwrap()
+            .into();
+        let sig = from_fvm::to_eth_signature(signed.signature(), true).unwrap();
+
+        assert_eq!(sig.recover(tx.sighash()).unwrap(), signer.address());
+        assert!(signed.verify(&chain_id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn external_signer_rejects_non_ethereum_recipient() {
+        let signer = Arc::new(LocalSigner::new(&[0x11; 32]).unwrap());
+        let chain_id = ChainID::from(314159u64);
+        let mut mf = SignedMessageFactory::new_with_signer(signer, 0, chain_id);
+
+        let to = Address::new_bls(&[0u8; 48]).unwrap();
+        let res = mf
+            .transaction(
+                to,
+                fvm_shared::METHOD_SEND,
+                RawBytes::default(),
+                TokenAmount::from_atto(1),
+                gas_params(),
+            )
+            .await;
+
+        assert!(res.is_err());
+    }
+}
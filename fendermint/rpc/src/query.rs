@@ -16,7 +16,8 @@ use fvm_shared::ActorID;
 use fvm_shared::{address::Address, error::ExitCode};
 
 use fendermint_vm_message::query::{
-    ActorState, BuiltinActors, FvmQuery, FvmQueryHeight, GasEstimate, StateParams,
+    ActorState, BuiltinActors, FvmQuery, FvmQueryHeight, GasEstimate, MessageTrace, StateParams,
+    TraceBlock,
 };
 
 use crate::response::encode_data;
@@ -91,6 +92,24 @@ pub trait QueryClient: Sync {
         Ok(QueryResponse { height, value })
     }
 
+    /// Replay the messages of a block with execution tracing enabled, over the state of its parent.
+    async fn trace(
+        &self,
+        block: TraceBlock,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<QueryResponse<Vec<MessageTrace>>> {
+        let res = self
+            .perform(FvmQuery::Trace(Box::new(block)), height)
+            .await
+            .context("trace query failed")?;
+        let height = res.height;
+        let value = extract(res, |res| {
+            fvm_ipld_encoding::from_slice(&res.value)
+                .context("failed to decode Vec<MessageTrace> from query")
+        })?;
+        Ok(QueryResponse { height, value })
+    }
+
     /// Slowly changing state parameters.
     async fn state_params(
         &self,
@@ -47,7 +47,7 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<()>> {
         let mf = self.message_factory_mut();
-        let msg = mf.transfer(to, value, gas_params)?;
+        let msg = mf.transfer(to, value, gas_params).await?;
         let fut = self.perform(msg, |_| Ok(()));
         let res = fut.await?;
         Ok(res)
@@ -63,7 +63,9 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<RawBytes>> {
         let mf = self.message_factory_mut();
-        let msg = mf.transaction(to, method_num, params, value, gas_params)?;
+        let msg = mf
+            .transaction(to, method_num, params, value, gas_params)
+            .await?;
         let fut = self.perform(msg, decode_bytes);
         let res = fut.await?;
         Ok(res)
@@ -78,7 +80,9 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<CreateReturn>> {
         let mf = self.message_factory_mut();
-        let msg = mf.fevm_create(contract, constructor_args, value, gas_params)?;
+        let msg = mf
+            .fevm_create(contract, constructor_args, value, gas_params)
+            .await?;
         let fut = self.perform(msg, decode_fevm_create);
         let res = fut.await?;
         Ok(res)
@@ -93,7 +97,9 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<Vec<u8>>> {
         let mf = self.message_factory_mut();
-        let msg = mf.fevm_invoke(contract, calldata, value, gas_params)?;
+        let msg = mf
+            .fevm_invoke(contract, calldata, value, gas_params)
+            .await?;
         let fut = self.perform(msg, decode_fevm_invoke);
         let res = fut.await?;
         Ok(res)
@@ -119,7 +125,8 @@ pub trait CallClient: QueryClient + BoundClient {
     ) -> anyhow::Result<CallResponse<Vec<u8>>> {
         let msg = self
             .message_factory_mut()
-            .fevm_call(contract, calldata, value, gas_params)?;
+            .fevm_call(contract, calldata, value, gas_params)
+            .await?;
 
         let response = self.call(msg, height).await?;
 
@@ -150,7 +157,8 @@ pub trait CallClient: QueryClient + BoundClient {
     ) -> anyhow::Result<QueryResponse<GasEstimate>> {
         let msg = self
             .message_factory_mut()
-            .fevm_call(contract, calldata, value, gas_params)?;
+            .fevm_call(contract, calldata, value, gas_params)
+            .await?;
 
         self.estimate_gas(msg, height).await
     }
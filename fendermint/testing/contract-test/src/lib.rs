@@ -15,7 +15,9 @@ use fendermint_vm_interpreter::fvm::{
     state::{FvmExecState, FvmStateParams, FvmUpdatableParams},
     store::memory::MemoryBlockstore,
 };
-use fendermint_vm_interpreter::genesis::{create_test_genesis_state, GenesisOutput};
+use fendermint_vm_interpreter::genesis::{
+    create_test_genesis_state, GenesisOutput, GENESIS_APP_VERSION,
+};
 use fendermint_vm_interpreter::MessagesInterpreter;
 use fvm::engine::MultiEngine;
 use fvm_ipld_encoding::{self};
@@ -73,7 +75,7 @@ where
             circ_supply: out.circ_supply,
             chain_id: out.chain_id.into(),
             power_scale: out.power_scale,
-            app_version: 0,
+            app_version: GENESIS_APP_VERSION,
             consensus_params: None,
         };
 
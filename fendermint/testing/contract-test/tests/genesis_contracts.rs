@@ -0,0 +1,79 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+
+use ethers::types::H256;
+use fendermint_contract_test::create_test_exec_state;
+use fendermint_crypto::SecretKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_core::Timestamp;
+use fendermint_vm_genesis::{
+    Account, Actor, ActorMeta, Contract, Genesis, PermissionMode, SignerAddr,
+};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::version::NetworkVersion;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Runtime code returning 1.
+const RUNTIME_CODE: &str = "600160005260206000f3";
+
+#[tokio::test]
+async fn genesis_contract_balances_are_in_circ_supply() {
+    let sk = SecretKey::random(&mut StdRng::seed_from_u64(42));
+    let account_addr = Address::new_secp256k1(&sk.public_key().serialize()).unwrap();
+    let contract_addr = ethers::types::Address::repeat_byte(0x4e);
+
+    let mut storage = BTreeMap::new();
+    storage.insert(H256::from_low_u64_be(1), H256::from_low_u64_be(42));
+
+    let genesis = Genesis {
+        chain_name: "genesis-contracts".to_string(),
+        chain_id: 101,
+        timestamp: Timestamp(0),
+        network_version: NetworkVersion::V21,
+        base_fee: TokenAmount::zero(),
+        power_scale: 0,
+        validators: Vec::new(),
+        accounts: vec![
+            Actor {
+                meta: ActorMeta::Account(Account {
+                    owner: SignerAddr(account_addr),
+                }),
+                balance: TokenAmount::from_whole(100),
+            },
+            Actor {
+                meta: ActorMeta::Contract(Contract {
+                    address: contract_addr,
+                    code: hex::decode(RUNTIME_CODE).unwrap().into(),
+                    storage,
+                    nonce: 5,
+                }),
+                balance: TokenAmount::from_whole(50),
+            },
+        ],
+        eam_permission_mode: PermissionMode::Unrestricted,
+        ipc: None,
+        ipc_contracts_owner: ethers::types::Address::zero(),
+        f3: None,
+    };
+
+    let (exec_state, out, _) = create_test_exec_state(genesis).await.unwrap();
+
+    assert_eq!(out.circ_supply, TokenAmount::from_whole(150));
+
+    let balance_of = |addr: &Address| {
+        let tree = exec_state.state_tree();
+        let id = tree.lookup_id(addr).unwrap().expect("actor should exist");
+        tree.get_actor(id).unwrap().expect("actor state").balance
+    };
+
+    assert_eq!(balance_of(&account_addr), TokenAmount::from_whole(100));
+    assert_eq!(
+        balance_of(&Address::from(EthAddress::from(contract_addr))),
+        TokenAmount::from_whole(50)
+    );
+}
@@ -0,0 +1,133 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::Path;
+
+use fendermint_crypto::SecretKey;
+use fendermint_vm_core::Timestamp;
+use fendermint_vm_genesis::{
+    Account, Actor, ActorMeta, Collateral, Genesis, PermissionMode, Power, SignerAddr, Validator,
+    ValidatorKey,
+};
+use fendermint_vm_interpreter::fvm::bundle::contracts_path;
+use fendermint_vm_interpreter::fvm::state::ipc::GatewayCaller;
+use fendermint_vm_interpreter::fvm::state::{FvmExecState, FvmStateParams};
+use fendermint_vm_interpreter::fvm::store::memory::MemoryBlockstore;
+use fendermint_vm_interpreter::genesis::{read_genesis_car, GenesisBuilder, GenesisFork};
+use fvm::engine::MultiEngine;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::version::NetworkVersion;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn validator(rng: &mut StdRng) -> Validator<Collateral> {
+    let sk = SecretKey::random(rng);
+    Validator {
+        public_key: ValidatorKey::new(sk.public_key()),
+        power: Collateral(TokenAmount::from_whole(10)),
+    }
+}
+
+async fn seal(genesis: Genesis, fork: Option<GenesisFork>, path: &Path) {
+    let builder = GenesisBuilder::new(
+        actors_builtin_car::CAR,
+        actors_custom_car::CAR,
+        contracts_path(),
+        genesis,
+    );
+    let builder = match fork {
+        Some(fork) => builder.with_fork(fork),
+        None => builder,
+    };
+    builder.write_to(path.to_path_buf()).await.unwrap();
+}
+
+/// Load a sealed genesis the way the application does in `init_chain`.
+async fn load(path: &Path) -> (MemoryBlockstore, Vec<Validator<Power>>, FvmStateParams) {
+    let store = MemoryBlockstore::new();
+    let bytes = std::fs::read(path).unwrap();
+    let (validators, params) = read_genesis_car(bytes, &store).await.unwrap();
+    (store, validators, params)
+}
+
+#[tokio::test]
+async fn fork_replaces_validators_and_chain_id() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let dir = tempfile::tempdir().unwrap();
+
+    let account = SecretKey::random(&mut rng);
+    let account = Address::new_secp256k1(&account.public_key().serialize()).unwrap();
+
+    let original = Genesis {
+        chain_name: "original".to_string(),
+        chain_id: 101,
+        timestamp: Timestamp(0),
+        network_version: NetworkVersion::V21,
+        base_fee: TokenAmount::zero(),
+        power_scale: 0,
+        validators: vec![validator(&mut rng)],
+        accounts: vec![Actor {
+            meta: ActorMeta::Account(Account {
+                owner: SignerAddr(account),
+            }),
+            balance: TokenAmount::from_whole(100),
+        }],
+        eam_permission_mode: PermissionMode::Unrestricted,
+        ipc: None,
+        ipc_contracts_owner: ethers::types::Address::zero(),
+        f3: None,
+    };
+
+    let original_path = dir.path().join("original.car");
+    seal(original.clone(), None, &original_path).await;
+
+    let (store, _, original_params) = load(&original_path).await;
+
+    let forked = Genesis {
+        chain_name: "forked".to_string(),
+        chain_id: 202,
+        timestamp: Timestamp(1000),
+        validators: vec![validator(&mut rng), validator(&mut rng)],
+        // The accounts of the fork are whatever the forked state has.
+        accounts: Vec::new(),
+        ..original.clone()
+    };
+
+    let forked_path = dir.path().join("forked.car");
+    let fork = GenesisFork::from_store(store, original_params);
+    seal(forked.clone(), Some(fork), &forked_path).await;
+
+    let (store, validators, params) = load(&forked_path).await;
+
+    assert_eq!(params.chain_id, 202);
+    assert_eq!(params.timestamp, Timestamp(1000));
+
+    let mut keys = validators
+        .iter()
+        .map(|v| v.public_key.clone())
+        .collect::<Vec<_>>();
+    let mut expected = forked
+        .validators
+        .iter()
+        .map(|v| v.public_key.clone())
+        .collect::<Vec<_>>();
+    keys.sort_by_key(|k| k.0.serialize());
+    expected.sort_by_key(|k| k.0.serialize());
+    assert_eq!(keys, expected);
+
+    // The new chain can execute on top of the forked state.
+    let multi_engine = MultiEngine::new(1);
+    let mut state = FvmExecState::new(store, &multi_engine, 1, params).unwrap();
+
+    let (_, power_table) = GatewayCaller::default()
+        .current_power_table(&mut state)
+        .unwrap();
+    assert_eq!(power_table.len(), 2);
+
+    let tree = state.state_tree();
+    let id = tree.lookup_id(&account).unwrap().expect("account forked");
+    let actor = tree.get_actor(id).unwrap().expect("account state");
+    assert_eq!(actor.balance, TokenAmount::from_whole(100));
+}
@@ -0,0 +1,153 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fendermint_contract_test::create_test_exec_state;
+use fendermint_crypto::SecretKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_core::Timestamp;
+use fendermint_vm_genesis::ipc::{GatewayParams, IpcParams};
+use fendermint_vm_genesis::{Collateral, Genesis, PermissionMode, Validator, ValidatorKey};
+use fendermint_vm_interpreter::fvm::state::ipc::{tokens_to_mint, GatewayCaller};
+use fendermint_vm_interpreter::fvm::state::FvmExecState;
+use fendermint_vm_interpreter::fvm::store::memory::MemoryBlockstore;
+use fendermint_vm_interpreter::fvm::topdown::{
+    apply_topdown_msgs, PER_MESSAGE_TOPDOWN_APP_VERSION,
+};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::version::NetworkVersion;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const ROOT_ID: u64 = 314159;
+
+fn eth_addr(byte: u8) -> Address {
+    Address::from(EthAddress([byte; 20]))
+}
+
+async fn child_exec_state(subnet_id: SubnetID) -> FvmExecState<MemoryBlockstore> {
+    let sk = SecretKey::random(&mut StdRng::seed_from_u64(42));
+
+    let genesis = Genesis {
+        chain_name: "topdown-messages".to_string(),
+        chain_id: 101,
+        timestamp: Timestamp(0),
+        network_version: NetworkVersion::V21,
+        base_fee: TokenAmount::zero(),
+        power_scale: 0,
+        validators: vec![Validator {
+            public_key: ValidatorKey::new(sk.public_key()),
+            power: Collateral(TokenAmount::from_whole(10)),
+        }],
+        accounts: Vec::new(),
+        eam_permission_mode: PermissionMode::Unrestricted,
+        ipc: Some(IpcParams {
+            gateway: GatewayParams {
+                subnet_id,
+                bottom_up_check_period: 10,
+                majority_percentage: 66,
+                active_validators_limit: 10,
+            },
+        }),
+        ipc_contracts_owner: ethers::types::Address::zero(),
+        f3: None,
+    };
+
+    let (state, _, _) = create_test_exec_state(genesis).await.unwrap();
+    state
+}
+
+/// Two valid transfers from the parent with a message in between that the gateway reverts on,
+/// because it is not travelling top-down.
+fn messages(subnet_id: &SubnetID) -> Vec<IpcEnvelope> {
+    let root = SubnetID::new_root(ROOT_ID);
+    let sibling = SubnetID::new_from_parent(&root, eth_addr(0xee));
+
+    let transfer = |nonce, to| {
+        let mut msg =
+            IpcEnvelope::new_fund_msg(subnet_id, &eth_addr(0x01), &to, TokenAmount::from_whole(1))
+                .unwrap();
+        msg.local_nonce = nonce;
+        msg
+    };
+
+    let mut invalid = IpcEnvelope::new_release_msg(
+        &sibling,
+        &eth_addr(0x02),
+        &eth_addr(0x03),
+        TokenAmount::zero(),
+    )
+    .unwrap();
+    invalid.local_nonce = 1;
+
+    vec![
+        transfer(0, eth_addr(0x10)),
+        invalid,
+        transfer(2, eth_addr(0x11)),
+    ]
+}
+
+fn mint(
+    gateway: &GatewayCaller<MemoryBlockstore>,
+    state: &mut FvmExecState<MemoryBlockstore>,
+    msgs: &[IpcEnvelope],
+) {
+    gateway
+        .mint_to_gateway(state, tokens_to_mint(msgs))
+        .unwrap();
+}
+
+#[tokio::test]
+async fn failed_topdown_message_does_not_abort_the_batch() {
+    let root = SubnetID::new_root(ROOT_ID);
+    let subnet_id = SubnetID::new_from_parent(&root, eth_addr(0xaa));
+
+    let mut state = child_exec_state(subnet_id.clone()).await;
+    assert!(state.app_version() >= PER_MESSAGE_TOPDOWN_APP_VERSION);
+
+    let gateway = GatewayCaller::default();
+    let msgs = messages(&subnet_id);
+    mint(&gateway, &mut state, &msgs);
+
+    let (_, receipts) = apply_topdown_msgs(&gateway, &mut state, msgs).unwrap();
+
+    assert_eq!(receipts.len(), 3);
+    assert_eq!(
+        receipts.iter().map(|r| r.nonce).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert!(receipts[0].exit_code.is_success());
+    assert!(!receipts[1].exit_code.is_success());
+    assert!(receipts[2].exit_code.is_success());
+
+    // The failed message consumed its nonce, so the one after it could be applied.
+    assert_eq!(gateway.applied_top_down_nonce(&mut state).unwrap(), 3);
+
+    let balance_of = |state: &FvmExecState<MemoryBlockstore>, addr: &Address| {
+        let tree = state.state_tree();
+        let id = tree.lookup_id(addr).unwrap().expect("actor should exist");
+        tree.get_actor(id).unwrap().expect("actor state").balance
+    };
+    assert_eq!(
+        balance_of(&state, &eth_addr(0x11)),
+        TokenAmount::from_whole(1)
+    );
+}
+
+#[tokio::test]
+async fn failed_topdown_message_aborts_the_batch_before_the_upgrade() {
+    let root = SubnetID::new_root(ROOT_ID);
+    let subnet_id = SubnetID::new_from_parent(&root, eth_addr(0xaa));
+
+    let mut state = child_exec_state(subnet_id.clone()).await;
+    state.update_app_version(|v| *v = PER_MESSAGE_TOPDOWN_APP_VERSION - 1);
+
+    let gateway = GatewayCaller::default();
+    let msgs = messages(&subnet_id);
+    mint(&gateway, &mut state, &msgs);
+
+    assert!(apply_topdown_msgs(&gateway, &mut state, msgs).is_err());
+}
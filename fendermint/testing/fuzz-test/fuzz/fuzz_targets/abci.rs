@@ -0,0 +1,24 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use arbitrary::Unstructured;
+use fendermint_fuzz_test::AbciMachine;
+use fendermint_testing::smt;
+use libfuzzer_sys::fuzz_target;
+
+/// Maximum number of blocks to produce from a single input.
+const MAX_STEPS: usize = 50;
+
+/// Reused between inputs, so the sealed genesis files are cached.
+static MACHINE: OnceLock<AbciMachine> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let machine = MACHINE.get_or_init(AbciMachine::default);
+    let mut u = Unstructured::new(data);
+    // Inputs too short to even generate a genesis are not interesting.
+    let _ = smt::run_fuzz(&mut u, machine, MAX_STEPS);
+});
@@ -0,0 +1,18 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Deterministic fuzzing of the ABCI application.
+//!
+//! Random sequences of parent blocks, carrying top-down messages and validator power changes,
+//! and child blocks, carrying signed messages with all sorts of nonces, fees and values, are
+//! proposed and executed on independent nodes, each with its own in-memory store and scheduled
+//! upgrades. The nodes must agree on every app hash and result, while the ledger has to
+//! conserve the supply, only ever increase nonces and respect the block gas limit.
+//!
+//! The same [AbciMachine] runs as a state machine test under `cargo test` and under `cargo fuzz`.
+
+mod machine;
+mod node;
+mod state;
+
+pub use machine::AbciMachine;
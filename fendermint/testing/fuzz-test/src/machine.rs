@@ -0,0 +1,539 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use arbitrary::Unstructured;
+use async_stm::{atomically, atomically_or_err};
+use bytes::Bytes;
+use fendermint_crypto::SecretKey;
+use fendermint_testing::smt::StateMachine;
+use fendermint_vm_genesis::Genesis;
+use fendermint_vm_interpreter::fvm::bundle::contracts_path;
+use fendermint_vm_interpreter::genesis::{GenesisAppState, GenesisBuilder};
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_topdown::voting::ValidatorKey;
+use fvm_shared::address::Address;
+use tendermint::abci::request;
+use tendermint::block::{self, Height};
+use tendermint::{account, validator, AppHash, Hash};
+
+use crate::node::{BlockOutcome, FuzzNode, Snapshot, BLOCK_MAX_BYTES};
+use crate::state::{self, FuzzCommand, FuzzState, ParentBlock, GENESIS_TIMESTAMP};
+
+/// Number of nodes executing every block, each with its own store.
+const NUM_NODES: usize = 2;
+/// How long to wait for a tally which has paused voting while it is looking for a quorum.
+const VOTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Drive independent nodes through the same random blocks over ABCI,
+/// checking that they stay in consensus and that the ledger stays consistent.
+#[derive(Default)]
+pub struct AbciMachine {
+    /// Sealed genesis by its JSON, because sealing takes longer than most tests.
+    genesis_cache: Mutex<HashMap<String, Bytes>>,
+}
+
+impl AbciMachine {
+    /// The genesis in the format CometBFT passes it to `init_chain`.
+    async fn app_state_bytes(&self, genesis: &Genesis) -> anyhow::Result<Bytes> {
+        let key = serde_json::to_string(genesis)?;
+
+        if let Some(bytes) = self.genesis_cache.lock().unwrap().get(&key) {
+            return Ok(bytes.clone());
+        }
+
+        let dir = tempfile::tempdir()?;
+        let car_path = dir.path().join("genesis.car");
+
+        GenesisBuilder::new(
+            actors_builtin_car::CAR,
+            actors_custom_car::CAR,
+            contracts_path(),
+            genesis.clone(),
+        )
+        .write_to(car_path.clone())
+        .await
+        .context("failed to seal genesis")?;
+
+        let car = tokio::fs::read(&car_path)
+            .await
+            .with_context(|| format!("failed to read {car_path:?}"))?;
+
+        // This is how `fendermint genesis into-tendermint` encodes the app state for CometBFT.
+        let app_state = GenesisAppState::v1(car).compress_and_encode()?;
+        let bytes = Bytes::from(serde_json::to_vec(&app_state)?);
+
+        self.genesis_cache
+            .lock()
+            .unwrap()
+            .insert(key, bytes.clone());
+
+        Ok(bytes)
+    }
+}
+
+/// The nodes under test, with the runtime to drive them.
+pub struct FuzzSystem {
+    chain: FuzzChain,
+    rt: tokio::runtime::Runtime,
+}
+
+/// Nodes of the subnet, and the consensus between them, standing in for CometBFT.
+pub struct FuzzChain {
+    nodes: Vec<FuzzNode>,
+    /// Every validator which can vote on parent finality, whether they have power or not.
+    validator_keys: Vec<SecretKey>,
+    accounts: Vec<Address>,
+    chain_id: String,
+    height: u64,
+    last_block_id: Option<block::Id>,
+    last_app_hash: AppHash,
+    /// Validators in the consensus; updates take effect two blocks after they are returned.
+    consensus_validators: BTreeMap<account::Id, u64>,
+    /// Validators in the ledger, which the app updates as soon as it returns them.
+    ledger_validators: BTreeMap<account::Id, u64>,
+    pending_updates: BTreeMap<u64, Vec<validator::Update>>,
+    genesis: Snapshot,
+    last: Snapshot,
+}
+
+/// The results of a child block, as seen by one node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeOutcome {
+    pub block: Option<BlockOutcome>,
+    pub snapshot: Snapshot,
+}
+
+/// A block that has been executed.
+#[derive(Debug, Clone)]
+pub struct ExecutedBlock {
+    /// Gas available to the block before execution.
+    pub block_gas_limit: u64,
+    pub txs: Vec<Bytes>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    pub before: Snapshot,
+    pub block: Option<ExecutedBlock>,
+    pub nodes: Vec<NodeOutcome>,
+}
+
+impl FuzzChain {
+    async fn new(state: &FuzzState, app_state_bytes: Bytes) -> anyhow::Result<Self> {
+        let accounts = state.accounts.iter().map(|a| a.addr).collect::<Vec<_>>();
+        let chain_id = state.genesis.chain_id.to_string();
+
+        let mut nodes = Vec::new();
+        let mut genesis_validators = None;
+        let mut last_app_hash = AppHash::default();
+
+        for _ in 0..NUM_NODES {
+            let node = FuzzNode::new(state)?;
+
+            let res = node
+                .init_chain(request::InitChain {
+                    time: block_time(0)?,
+                    chain_id: chain_id.clone(),
+                    consensus_params: consensus_params(),
+                    validators: Vec::new(),
+                    app_state_bytes: app_state_bytes.clone(),
+                    initial_height: Height::from(1u32),
+                })
+                .await?;
+
+            let validators = res
+                .validators
+                .into_iter()
+                .map(|u| (account::Id::from(u.pub_key), u.power.value()))
+                .collect::<BTreeMap<_, _>>();
+
+            match genesis_validators {
+                None => genesis_validators = Some(validators),
+                Some(ref expected) if *expected != validators => {
+                    bail!("nodes disagree about the genesis validators")
+                }
+                Some(_) => {}
+            }
+
+            last_app_hash = res.app_hash;
+            nodes.push(node);
+        }
+
+        let validators = genesis_validators.expect("there are nodes");
+        let genesis = nodes[0].snapshot(&accounts).await?;
+
+        Ok(Self {
+            nodes,
+            validator_keys: state
+                .validators
+                .iter()
+                .map(|v| v.secret_key.clone())
+                .collect(),
+            accounts,
+            chain_id,
+            height: 0,
+            last_block_id: None,
+            last_app_hash,
+            consensus_validators: validators.clone(),
+            ledger_validators: validators,
+            pending_updates: BTreeMap::new(),
+            last: genesis.clone(),
+            genesis,
+        })
+    }
+
+    async fn add_parent_block(&mut self, block: &ParentBlock) -> anyhow::Result<StepOutcome> {
+        for node in self.nodes.iter() {
+            node.add_parent_block(block).await?;
+        }
+        Ok(StepOutcome {
+            before: self.last.clone(),
+            block: None,
+            nodes: vec![
+                NodeOutcome {
+                    block: None,
+                    snapshot: self.last.clone(),
+                };
+                self.nodes.len()
+            ],
+        })
+    }
+
+    /// Propose a block with the transactions on one node, then execute it on all of them.
+    async fn produce_block(&mut self, txs: Vec<Bytes>) -> anyhow::Result<StepOutcome> {
+        let height = self.height + 1;
+        let time = block_time(height)?;
+
+        if let Some(updates) = self.pending_updates.remove(&height) {
+            apply_validator_updates(&mut self.consensus_validators, &updates);
+        }
+
+        self.gossip_votes().await;
+
+        // The app only accepts proposers it knows about, which might not have caught up with CometBFT.
+        let proposers = self
+            .consensus_validators
+            .keys()
+            .filter(|id| self.ledger_validators.contains_key(id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if proposers.is_empty() {
+            bail!("there are no validators to propose at height {height}");
+        }
+
+        let proposer = proposers[height as usize % proposers.len()];
+        let preparer = &self.nodes[height as usize % self.nodes.len()];
+        let block_gas_limit = preparer.block_gas_limit()?;
+
+        let txs = preparer
+            .prepare_proposal(height, time, proposer, txs)
+            .await?;
+
+        let header = block::Header {
+            version: block::header::Version { block: 11, app: 0 },
+            chain_id: tendermint::chain::Id::try_from(self.chain_id.clone())?,
+            height: Height::try_from(height)?,
+            time,
+            last_block_id: self.last_block_id,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::None,
+            next_validators_hash: Hash::None,
+            consensus_hash: Hash::None,
+            app_hash: self.last_app_hash.clone(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: proposer,
+        };
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.process_proposal(&header, txs.clone()).await? {
+                bail!("node {i} rejected the proposal at height {height}");
+            }
+        }
+
+        let mut nodes = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let outcome = node
+                .execute_block(&header, &txs)
+                .await
+                .with_context(|| format!("node {i} failed to execute block {height}"))?;
+
+            let snapshot = node.snapshot(&self.accounts).await?;
+
+            nodes.push(NodeOutcome {
+                block: Some(outcome),
+                snapshot,
+            });
+        }
+
+        let hash = header.hash();
+        self.height = height;
+        self.last_block_id = Some(block::Id {
+            hash,
+            part_set_header: block::parts::Header::new(1, hash)?,
+        });
+
+        let before = std::mem::replace(&mut self.last, nodes[0].snapshot.clone());
+
+        let executed = nodes[0].block.as_ref().expect("the block was executed");
+        self.last_app_hash = executed.app_hash.clone();
+
+        if !executed.validator_updates.is_empty() {
+            apply_validator_updates(&mut self.ledger_validators, &executed.validator_updates);
+            self.pending_updates
+                .entry(height + 2)
+                .or_default()
+                .extend(executed.validator_updates.iter().cloned());
+        }
+
+        Ok(StepOutcome {
+            before,
+            block: Some(ExecutedBlock {
+                block_gas_limit,
+                txs,
+            }),
+            nodes,
+        })
+    }
+
+    /// Every validator votes for the latest parent block it has seen, and every node adds
+    /// the votes to their tally, ignoring the ones from validators without power.
+    async fn gossip_votes(&self) {
+        let latest = atomically(|| {
+            let votes = &self.nodes[0].votes;
+            let height = votes.latest_height()?;
+            let hash = votes.block_hash(height)?;
+            Ok(hash.map(|hash| (height, hash)))
+        })
+        .await;
+
+        let Some((height, hash)) = latest else {
+            return;
+        };
+
+        for node in self.nodes.iter() {
+            for sk in self.validator_keys.iter() {
+                let key = ValidatorKey::from(sk.public_key());
+                let add =
+                    atomically_or_err(|| node.votes.add_vote(key.clone(), height, hash.clone()));
+
+                match tokio::time::timeout(VOTE_TIMEOUT, add).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::debug!(error = e.to_string(), "vote ignored"),
+                    Err(_) => tracing::debug!(height, "vote timed out"),
+                }
+            }
+        }
+    }
+}
+
+impl StateMachine for AbciMachine {
+    type System = FuzzSystem;
+    type State = FuzzState;
+    type Command = FuzzCommand;
+    type Result = anyhow::Result<StepOutcome>;
+
+    fn gen_state(&self, u: &mut Unstructured) -> arbitrary::Result<Self::State> {
+        state::gen_state(u)
+    }
+
+    fn new_system(&self, state: &Self::State) -> Self::System {
+        let rt = tokio::runtime::Runtime::new().expect("create tokio runtime");
+
+        let chain = rt.block_on(async {
+            let app_state_bytes = self
+                .app_state_bytes(&state.genesis)
+                .await
+                .expect("failed to seal genesis");
+
+            FuzzChain::new(state, app_state_bytes)
+                .await
+                .expect("failed to create the nodes")
+        });
+
+        FuzzSystem { chain, rt }
+    }
+
+    fn gen_command(
+        &self,
+        u: &mut Unstructured,
+        state: &Self::State,
+    ) -> arbitrary::Result<Self::Command> {
+        state::gen_command(u, state)
+    }
+
+    fn run_command(&self, system: &mut Self::System, cmd: &Self::Command) -> Self::Result {
+        let chain = &mut system.chain;
+
+        match cmd {
+            FuzzCommand::ParentBlock(block) => system.rt.block_on(chain.add_parent_block(block)),
+            FuzzCommand::ChildBlock(txs) => {
+                let txs = txs
+                    .iter()
+                    .map(|tx| fvm_ipld_encoding::to_vec(&tx.msg).map(Bytes::from))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                system.rt.block_on(chain.produce_block(txs))
+            }
+        }
+    }
+
+    fn check_result(&self, cmd: &Self::Command, pre_state: &Self::State, result: Self::Result) {
+        let outcome = result.unwrap_or_else(|e| panic!("failed to run command: {e:#}"));
+
+        // Every node must end up in the same state, with the same results.
+        let first = &outcome.nodes[0];
+        for (i, other) in outcome.nodes.iter().enumerate().skip(1) {
+            assert_eq!(
+                first.snapshot.app_hash, other.snapshot.app_hash,
+                "node {i} has a different app hash"
+            );
+            assert_eq!(first, other, "node {i} has different results");
+        }
+
+        let before = &outcome.before;
+        let after = &first.snapshot;
+
+        // Tokens can only appear by being minted from the parent.
+        assert!(
+            after.circ_supply >= before.circ_supply,
+            "the circulating supply decreased"
+        );
+        assert_eq!(
+            &after.total_balance - &before.total_balance,
+            &after.circ_supply - &before.circ_supply,
+            "balances changed differently from the circulating supply"
+        );
+
+        let FuzzCommand::ChildBlock(txs) = cmd else {
+            return;
+        };
+
+        let block = outcome.block.expect("child blocks are executed");
+        let results = first.block.as_ref().expect("child blocks have results");
+
+        // Nonces only go up, and at most once per transaction sent.
+        for (i, _) in pre_state.accounts.iter().enumerate() {
+            let sent = txs.iter().filter(|tx| tx.from == i).count() as u64;
+            assert!(
+                after.nonces[i] >= before.nonces[i],
+                "nonce of account {i} decreased"
+            );
+            assert!(
+                after.nonces[i] <= before.nonces[i] + sent,
+                "nonce of account {i} increased more than the transactions sent"
+            );
+        }
+
+        // The proposal must fit into the block gas limit.
+        let mut gas_limit = 0u64;
+        for tx in block.txs.iter() {
+            let msg = fvm_ipld_encoding::from_slice::<ChainMessage>(tx)
+                .expect("proposed transactions are chain messages");
+
+            if let ChainMessage::Signed(msg) = msg {
+                gas_limit += msg.message.gas_limit;
+            }
+        }
+        assert!(
+            gas_limit <= block.block_gas_limit,
+            "proposal wants {gas_limit} gas but the block limit is {}",
+            block.block_gas_limit
+        );
+
+        for res in results.tx_results.iter() {
+            assert!(
+                res.gas_used <= res.gas_wanted,
+                "transaction used more gas than its limit"
+            );
+        }
+    }
+
+    fn next_state(&self, cmd: &Self::Command, state: Self::State) -> Self::State {
+        state::next_state(cmd, state)
+    }
+
+    fn check_system(
+        &self,
+        _cmd: &Self::Command,
+        post_state: &Self::State,
+        post_system: &Self::System,
+    ) -> bool {
+        let last = &post_system.chain.last;
+
+        assert_eq!(last.height, post_state.height, "unexpected block height");
+
+        assert_eq!(
+            last.app_version,
+            post_state.app_version(post_state.height),
+            "unexpected app version at height {}",
+            post_state.height
+        );
+
+        for (i, account) in post_state.accounts.iter().enumerate() {
+            assert!(
+                last.nonces[i] <= account.sent,
+                "account {i} has a higher nonce than the number of transactions it sent"
+            );
+        }
+
+        let minted = &last.circ_supply - &post_system.chain.genesis.circ_supply;
+        assert!(
+            minted <= post_state.funded,
+            "minted {minted} but only {} was sent from the parent",
+            post_state.funded
+        );
+
+        true
+    }
+}
+
+/// Block timestamps are one second apart, starting from the genesis.
+fn block_time(height: u64) -> anyhow::Result<tendermint::Time> {
+    let secs = (GENESIS_TIMESTAMP + height) as i64;
+    Ok(tendermint::Time::from_unix_timestamp(secs, 0)?)
+}
+
+fn apply_validator_updates(
+    validators: &mut BTreeMap<account::Id, u64>,
+    updates: &[validator::Update],
+) {
+    for u in updates {
+        let id = account::Id::from(u.pub_key);
+        let power = u.power.value();
+        if power == 0 {
+            validators.remove(&id);
+        } else {
+            validators.insert(id, power);
+        }
+    }
+}
+
+/// Consensus parameters based on the defaults of `cometbft init`.
+fn consensus_params() -> tendermint::consensus::Params {
+    tendermint::consensus::Params {
+        block: tendermint::block::Size {
+            max_bytes: BLOCK_MAX_BYTES as u64,
+            max_gas: -1,
+            time_iota_ms: tendermint::block::Size::default_time_iota_ms(),
+        },
+        evidence: tendermint::evidence::Params {
+            max_age_num_blocks: 100000,
+            max_age_duration: tendermint::evidence::Duration(std::time::Duration::from_nanos(
+                172800000000000,
+            )),
+            max_bytes: 1048576,
+        },
+        validator: tendermint::consensus::params::ValidatorParams {
+            pub_key_types: vec![tendermint::public_key::Algorithm::Secp256k1],
+        },
+        version: Some(tendermint::consensus::params::VersionParams { app: 0 }),
+    }
+}
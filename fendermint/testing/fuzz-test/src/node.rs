@@ -0,0 +1,407 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use async_stm::atomically_or_err;
+use bytes::Bytes;
+use cid::Cid;
+use fendermint_abci::Application;
+use fendermint_app::checkpoint::SignatureCollector;
+use fendermint_app::{App, AppConfig, AppStore};
+use fendermint_storage::im::InMemoryBackend;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_interpreter::fvm::end_block_hook::EndBlockManager;
+use fendermint_vm_interpreter::fvm::interpreter::FvmMessagesInterpreter;
+use fendermint_vm_interpreter::fvm::store::memory::MemoryBlockstore;
+use fendermint_vm_interpreter::fvm::topdown::TopDownManager;
+use fendermint_vm_interpreter::fvm::upgrades::{Upgrade, UpgradeScheduler};
+use fendermint_vm_message::query::{FvmQuery, StateParams};
+use fendermint_vm_topdown::proxy::{IPCProviderProxy, IPCProviderProxyWithLatency};
+use fendermint_vm_topdown::voting::{ValidatorKey, VoteTally};
+use fendermint_vm_topdown::{CachedFinalityProvider, IPCParentFinality, Toggle};
+use fvm::state_tree::StateTree;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
+use ipc_provider::IpcProvider;
+use tendermint::abci::{request, response, types::CommitInfo};
+use tendermint::block::{self, Height};
+use tendermint::{account, validator, AppHash, Hash};
+
+use crate::state::{
+    child_subnet_id, parent_block_hash, FuzzState, ParentBlock, PARENT_GENESIS_EPOCH,
+};
+
+/// Maximum number of messages in a block, same as the default node settings.
+const BLOCK_MAX_MSGS: usize = 1000;
+/// Gas estimation parameters, same as the default node settings.
+const GAS_OVERESTIMATION_RATE: f64 = 1.25;
+const GAS_SEARCH_STEP: f64 = 1.25;
+
+/// Maximum size of a block; the default of `cometbft init`.
+pub const BLOCK_MAX_BYTES: i64 = 22020096;
+
+/// The ABCI application of a node, with all of its storage kept in memory.
+pub type FuzzApp = App<
+    InMemoryBackend<AppStore>,
+    MemoryBlockstore,
+    AppStore,
+    FvmMessagesInterpreter<MemoryBlockstore>,
+>;
+
+/// The parent finality provider the interpreter expects.
+type FinalityProvider = Arc<Toggle<CachedFinalityProvider<IPCProviderProxyWithLatency>>>;
+
+/// What the state of a node looks like after a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub height: u64,
+    pub app_hash: AppHash,
+    pub app_version: u64,
+    pub state_root: Cid,
+    pub circ_supply: TokenAmount,
+    /// Sum of the balances of every actor in the state tree.
+    pub total_balance: TokenAmount,
+    /// Nonces of the accounts, in the order of the model.
+    pub nonces: Vec<u64>,
+}
+
+/// What we need to remember from executing a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockOutcome {
+    pub tx_results: Vec<response::DeliverTx>,
+    pub validator_updates: Vec<validator::Update>,
+    pub app_hash: AppHash,
+}
+
+/// A node of the subnet under test, with its own storage and view of the parent.
+pub struct FuzzNode {
+    pub app: FuzzApp,
+    /// The same store the app writes to, to walk the state tree.
+    store: MemoryBlockstore,
+    provider: FinalityProvider,
+    /// Parent finality votes; in a real node these are fed by the parent syncer and the IPLD Resolver.
+    pub votes: VoteTally,
+}
+
+impl FuzzNode {
+    /// Create a node with an empty state, waiting for `init_chain`.
+    ///
+    /// The parent finality provider is filled by the test instead of a syncer, starting from the
+    /// genesis epoch of the subnet, so it never has to reach out to the parent.
+    pub fn new(state: &FuzzState) -> anyhow::Result<Self> {
+        let genesis_hash = parent_block_hash(PARENT_GENESIS_EPOCH);
+
+        let config = fendermint_vm_topdown::Config::new(
+            1,
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            0,
+        );
+
+        let provider = CachedFinalityProvider::new(
+            config,
+            PARENT_GENESIS_EPOCH,
+            Some(IPCParentFinality::new(
+                PARENT_GENESIS_EPOCH as ChainEpoch,
+                genesis_hash.clone(),
+            )),
+            Arc::new(offline_parent_proxy()?),
+        );
+        let provider = Arc::new(Toggle::enabled(provider));
+
+        let power_table = state
+            .genesis
+            .validators
+            .iter()
+            .map(|v| {
+                let power = v.power.clone().into_power(state.genesis.power_scale);
+                (ValidatorKey::from(v.public_key.0), power.0)
+            })
+            .collect();
+
+        let votes = VoteTally::new(power_table, (PARENT_GENESIS_EPOCH, genesis_hash));
+
+        let mut upgrade_scheduler = UpgradeScheduler::new();
+        for (height, app_version) in state.upgrades.iter() {
+            upgrade_scheduler.add(Upgrade::new_by_id(
+                state.chain_id(),
+                *height,
+                Some(*app_version),
+                |_state| Ok(()),
+            ))?;
+        }
+
+        let interpreter = FvmMessagesInterpreter::new(
+            EndBlockManager::new(),
+            TopDownManager::new(provider.clone(), votes.clone()),
+            upgrade_scheduler,
+            true,
+            BLOCK_MAX_MSGS,
+            GAS_OVERESTIMATION_RATE,
+            GAS_SEARCH_STEP,
+        );
+
+        let store = MemoryBlockstore::new();
+
+        let app = App::new(
+            AppConfig {
+                app_namespace: "app".to_string(),
+                state_hist_namespace: "state_hist".to_string(),
+                state_hist_size: 0,
+                halt_height: 0,
+            },
+            InMemoryBackend::default(),
+            store.clone(),
+            interpreter,
+            None,
+        )?
+        .with_checkpoint_signatures(SignatureCollector::default(), None);
+
+        Ok(Self {
+            app,
+            store,
+            provider,
+            votes,
+        })
+    }
+
+    /// Let the node know about a new block on the parent, like its syncer would.
+    pub async fn add_parent_block(&self, block: &ParentBlock) -> anyhow::Result<()> {
+        atomically_or_err(|| {
+            self.provider.new_parent_view(
+                block.height,
+                Some((
+                    block.block_hash.clone(),
+                    block.validator_changes.clone(),
+                    block.top_down_msgs.clone(),
+                )),
+            )
+        })
+        .await
+        .map_err(|e| anyhow!("failed to add parent view: {e}"))?;
+
+        atomically_or_err(|| {
+            self.votes
+                .add_block(block.height, Some(block.block_hash.clone()))
+        })
+        .await
+        .map_err(|e| anyhow!("failed to add parent block to the tally: {e}"))?;
+
+        Ok(())
+    }
+
+    pub async fn init_chain(
+        &self,
+        request: request::InitChain,
+    ) -> anyhow::Result<response::InitChain> {
+        self.app
+            .init_chain(request)
+            .await
+            .map_err(|e| anyhow!("init_chain failed: {e}"))
+    }
+
+    pub async fn prepare_proposal(
+        &self,
+        height: u64,
+        time: tendermint::Time,
+        proposer: account::Id,
+        txs: Vec<Bytes>,
+    ) -> anyhow::Result<Vec<Bytes>> {
+        let res = self
+            .app
+            .prepare_proposal(request::PrepareProposal {
+                max_tx_bytes: BLOCK_MAX_BYTES,
+                txs,
+                local_last_commit: None,
+                misbehavior: Vec::new(),
+                height: Height::try_from(height)?,
+                time,
+                next_validators_hash: Hash::None,
+                proposer_address: proposer,
+            })
+            .await
+            .map_err(|e| anyhow!("prepare_proposal failed: {e}"))?;
+
+        Ok(res.txs)
+    }
+
+    pub async fn process_proposal(
+        &self,
+        header: &block::Header,
+        txs: Vec<Bytes>,
+    ) -> anyhow::Result<bool> {
+        let res = self
+            .app
+            .process_proposal(request::ProcessProposal {
+                txs,
+                proposed_last_commit: None,
+                misbehavior: Vec::new(),
+                hash: header.hash(),
+                height: header.height,
+                time: header.time,
+                next_validators_hash: Hash::None,
+                proposer_address: header.proposer_address,
+            })
+            .await
+            .map_err(|e| anyhow!("process_proposal failed: {e}"))?;
+
+        Ok(res == response::ProcessProposal::Accept)
+    }
+
+    /// Execute and commit a block.
+    pub async fn execute_block(
+        &self,
+        header: &block::Header,
+        txs: &[Bytes],
+    ) -> anyhow::Result<BlockOutcome> {
+        self.app
+            .begin_block(request::BeginBlock {
+                hash: header.hash(),
+                header: header.clone(),
+                last_commit_info: CommitInfo {
+                    round: block::Round::default(),
+                    votes: Vec::new(),
+                },
+                byzantine_validators: Vec::new(),
+            })
+            .await
+            .map_err(|e| anyhow!("begin_block failed: {e}"))?;
+
+        let mut tx_results = Vec::new();
+        for tx in txs {
+            let res = self
+                .app
+                .deliver_tx(request::DeliverTx { tx: tx.clone() })
+                .await
+                .map_err(|e| anyhow!("deliver_tx failed: {e}"))?;
+
+            tx_results.push(res);
+        }
+
+        let res = self
+            .app
+            .end_block(request::EndBlock {
+                height: header.height.value().try_into()?,
+            })
+            .await
+            .map_err(|e| anyhow!("end_block failed: {e}"))?;
+
+        let validator_updates = res.validator_updates;
+
+        let res = self
+            .app
+            .commit()
+            .await
+            .map_err(|e| anyhow!("commit failed: {e}"))?;
+
+        let app_hash = AppHash::try_from(res.data.to_vec())?;
+
+        Ok(BlockOutcome {
+            tx_results,
+            validator_updates,
+            app_hash,
+        })
+    }
+
+    /// The gas available to the next block.
+    pub fn block_gas_limit(&self) -> anyhow::Result<u64> {
+        let state = self
+            .app
+            .read_only_view(None)?
+            .ok_or_else(|| anyhow!("the state has not been initialized"))?;
+
+        Ok(state.block_gas_tracker().available())
+    }
+
+    /// Look at the committed state through the ABCI interface and the state tree.
+    pub async fn snapshot(&self, accounts: &[Address]) -> anyhow::Result<Snapshot> {
+        let info = self
+            .app
+            .info(request::Info {
+                version: String::new(),
+                block_version: 0,
+                p2p_version: 0,
+                abci_version: String::new(),
+            })
+            .await
+            .map_err(|e| anyhow!("info failed: {e}"))?;
+
+        let res = self
+            .app
+            .query(request::Query {
+                data: fvm_ipld_encoding::to_vec(&FvmQuery::StateParams)?.into(),
+                path: String::new(),
+                height: Height::from(0u32),
+                prove: false,
+            })
+            .await
+            .map_err(|e| anyhow!("query failed: {e}"))?;
+
+        if res.code.is_err() {
+            bail!("state params query failed: {}", res.log);
+        }
+
+        let params: StateParams = fvm_ipld_encoding::from_slice(&res.value)
+            .context("failed to decode StateParams from query")?;
+
+        let state_root = Cid::try_from(params.state_root)?;
+        let state_tree = StateTree::new_from_root(&self.store, &state_root)
+            .context("failed to load state tree")?;
+
+        let mut total_balance = TokenAmount::default();
+        state_tree.for_each(|_, actor| {
+            total_balance += actor.balance.clone();
+            Ok(())
+        })?;
+
+        let mut nonces = Vec::new();
+        for addr in accounts {
+            let nonce = match state_tree.lookup_id(addr)? {
+                Some(id) => state_tree.get_actor(id)?.map(|a| a.sequence),
+                None => None,
+            };
+            nonces.push(nonce.unwrap_or_default());
+        }
+
+        Ok(Snapshot {
+            height: info.last_block_height.value(),
+            app_hash: info.last_block_app_hash,
+            app_version: info.app_version,
+            state_root,
+            circ_supply: params.circ_supply,
+            total_balance,
+            nonces,
+        })
+    }
+}
+
+/// A parent proxy which is never queried, because every parent block is put into the cache.
+///
+/// Creating it doesn't connect to anything.
+fn offline_parent_proxy() -> anyhow::Result<IPCProviderProxyWithLatency> {
+    let child = child_subnet_id();
+    let parent = child
+        .parent()
+        .ok_or_else(|| anyhow!("subnet has no parent"))?;
+
+    let subnet = ipc_provider::config::Subnet {
+        id: parent,
+        config: SubnetConfig::Fevm(EVMSubnet {
+            provider_http: "http://127.0.0.1:8545".parse()?,
+            provider_timeout: None,
+            auth_token: None,
+            registry_addr: Address::from(EthAddress([0; 20])),
+            gateway_addr: Address::from(EthAddress([0; 20])),
+        }),
+    };
+
+    let ipc_provider = IpcProvider::new_with_subnet(None, subnet)?;
+    let proxy = IPCProviderProxy::new(ipc_provider, child)?;
+
+    Ok(IPCProviderProxyWithLatency::new(proxy))
+}
@@ -0,0 +1,452 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+
+use arbitrary::Unstructured;
+use ethers::abi::Tokenizable;
+use fendermint_crypto::SecretKey;
+use fendermint_rpc::message::{GasParams, MessageFactory};
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_core::Timestamp;
+use fendermint_vm_genesis::ipc::{GatewayParams, IpcParams};
+use fendermint_vm_genesis::{
+    Account, Actor, ActorMeta, Collateral, Genesis, PermissionMode, SignerAddr, Validator,
+    ValidatorKey,
+};
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_message::conv::from_fvm::to_eth_tokens;
+use fendermint_vm_message::signed::SignedMessage;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::METHOD_SEND;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::staking::{PowerChange, PowerChangeRequest, PowerOperation};
+use ipc_api::subnet_id::SubnetID;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Chain ID of the child subnet under test.
+pub const CHAIN_ID: u64 = 1942764459484029;
+/// Chain ID of the root network the child subnet is created on.
+pub const PARENT_CHAIN_ID: u64 = 314159;
+/// The parent epoch the child subnet was created in.
+pub const PARENT_GENESIS_EPOCH: u64 = 100;
+/// Validator changes from the parent are applied at multiples of this height.
+pub const BOTTOM_UP_CHECK_PERIOD: u64 = 5;
+/// Seconds since the epoch of the genesis block; fixed so the sealed genesis is always the same.
+pub const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+/// Significant decimal places of collateral in the voting power.
+pub const POWER_SCALE: i8 = 3;
+/// Initial base fee, in atto.
+pub const BASE_FEE: u64 = 1000;
+
+/// Number of accounts which can send transactions.
+const ACCOUNT_POOL_SIZE: usize = 4;
+/// Number of validators which can join the subnet.
+const VALIDATOR_POOL_SIZE: usize = 4;
+/// Maximum number of upgrades scheduled.
+const MAX_UPGRADES: usize = 2;
+/// Upgrades happen at or below this height.
+const MAX_UPGRADE_HEIGHT: u64 = 20;
+/// Gas limit above the block gas limit of the default gas market.
+const EXCESSIVE_GAS_LIMIT: u64 = 20_000_000_000;
+
+/// The ID of the subnet under test, a child of the root network.
+pub fn child_subnet_id() -> SubnetID {
+    SubnetID::new(PARENT_CHAIN_ID, vec![Address::new_id(1001)])
+}
+
+/// Mock hash of a parent block, derived from its height.
+pub fn parent_block_hash(height: u64) -> Vec<u8> {
+    let mut hash = [0u8; 32];
+    hash[24..].copy_from_slice(&height.to_be_bytes());
+    hash.to_vec()
+}
+
+fn whole_fil(fil: u64) -> TokenAmount {
+    TokenAmount::from_whole(fil)
+}
+
+/// Keys come from fixed seeds, so the same genesis parameters always result in the same genesis.
+fn pool_key(seed: u64) -> SecretKey {
+    SecretKey::random(&mut StdRng::seed_from_u64(seed))
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzAccount {
+    pub secret_key: SecretKey,
+    /// The `f1` address of the account.
+    pub addr: Address,
+    /// The nonce we expect the next transaction to need.
+    ///
+    /// It is only an estimate, as the model doesn't know if a transaction ran out of funds.
+    pub next_nonce: u64,
+    /// Number of transactions sent so far, an upper bound on the nonce of the account.
+    pub sent: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzValidator {
+    pub secret_key: SecretKey,
+    /// Whether the public key of the validator has been registered in the gateway.
+    pub has_metadata: bool,
+}
+
+impl FuzzValidator {
+    /// The address the parent uses to identify the validator in power changes.
+    pub fn eth_addr(&self) -> anyhow::Result<Address> {
+        let pk = self.secret_key.public_key();
+        Ok(Address::from(EthAddress::new_secp256k1(&pk.serialize())?))
+    }
+}
+
+/// The reference state of the child subnet and its parent.
+#[derive(Debug, Clone)]
+pub struct FuzzState {
+    pub genesis: Genesis,
+    pub accounts: Vec<FuzzAccount>,
+    /// Every validator which can join; the genesis ones come first.
+    pub validators: Vec<FuzzValidator>,
+    /// App versions to upgrade to, by block height.
+    pub upgrades: BTreeMap<u64, u64>,
+    /// The last block produced on the child subnet.
+    pub height: u64,
+    /// The last block produced on the parent.
+    pub parent_height: u64,
+    /// The configuration number of the next validator change on the parent.
+    pub next_configuration_number: u64,
+    /// The nonce of the next top-down message.
+    pub next_topdown_nonce: u64,
+    /// Total value sent from the parent to the child.
+    pub funded: TokenAmount,
+}
+
+impl FuzzState {
+    /// The app version expected after executing a block.
+    pub fn app_version(&self, height: u64) -> u64 {
+        self.upgrades
+            .range(..=height)
+            .next_back()
+            .map(|(_, v)| *v)
+            .unwrap_or_default()
+    }
+
+    pub fn chain_id(&self) -> ChainID {
+        ChainID::from(self.genesis.chain_id)
+    }
+}
+
+/// A block on the parent chain, which the nodes learn about through their parent syncer.
+#[derive(Debug, Clone)]
+pub struct ParentBlock {
+    pub height: u64,
+    pub block_hash: Vec<u8>,
+    pub validator_changes: Vec<PowerChangeRequest>,
+    pub top_down_msgs: Vec<IpcEnvelope>,
+}
+
+/// A transaction in the mempool of the child subnet.
+#[derive(Debug, Clone)]
+pub struct FuzzTx {
+    /// Index of the sender in the accounts.
+    pub from: usize,
+    /// Whether we expect the transaction to be executed and bump the nonce of the sender.
+    pub expect_applied: bool,
+    pub msg: ChainMessage,
+}
+
+#[derive(Debug, Clone)]
+pub enum FuzzCommand {
+    ParentBlock(ParentBlock),
+    /// Propose and execute a block with the transactions in the mempool.
+    ChildBlock(Vec<FuzzTx>),
+}
+
+/// Generate a random genesis and schedule of upgrades.
+///
+/// The genesis only varies in a few discrete ways, so sealing it can be cached.
+pub fn gen_state(u: &mut Unstructured) -> arbitrary::Result<FuzzState> {
+    let num_validators = u.int_in_range(1..=VALIDATOR_POOL_SIZE)?;
+    let num_accounts = u.int_in_range(1..=ACCOUNT_POOL_SIZE)?;
+
+    let mut validators = Vec::new();
+    let mut genesis_validators = Vec::new();
+    for i in 0..VALIDATOR_POOL_SIZE {
+        let secret_key = pool_key(i as u64);
+        let has_metadata = i < num_validators;
+        if has_metadata {
+            genesis_validators.push(Validator {
+                public_key: ValidatorKey(secret_key.public_key()),
+                power: Collateral(whole_fil(*u.choose(&[1, 2, 5])?)),
+            });
+        }
+        validators.push(FuzzValidator {
+            secret_key,
+            has_metadata,
+        });
+    }
+
+    let mut accounts = Vec::new();
+    let mut genesis_accounts = Vec::new();
+    for i in 0..num_accounts {
+        let secret_key = pool_key(1000 + i as u64);
+        let addr = Address::new_secp256k1(&secret_key.public_key().serialize())
+            .expect("public key is 65 bytes");
+        genesis_accounts.push(Actor {
+            meta: ActorMeta::Account(Account {
+                owner: SignerAddr(addr),
+            }),
+            balance: whole_fil(*u.choose(&[100, 1000, 10000])?),
+        });
+        accounts.push(FuzzAccount {
+            secret_key,
+            addr,
+            next_nonce: 0,
+            sent: 0,
+        });
+    }
+
+    let mut upgrades = BTreeMap::new();
+    for _ in 0..u.int_in_range(0..=MAX_UPGRADES)? {
+        upgrades.insert(u.int_in_range(1..=MAX_UPGRADE_HEIGHT)?, 0);
+    }
+    for (app_version, v) in upgrades.values_mut().enumerate() {
+        *v = app_version as u64 + 1;
+    }
+
+    let genesis = Genesis {
+        chain_name: "fuzz".to_string(),
+        chain_id: CHAIN_ID,
+        timestamp: Timestamp(GENESIS_TIMESTAMP),
+        network_version: NetworkVersion::V21,
+        base_fee: TokenAmount::from_atto(BASE_FEE),
+        power_scale: POWER_SCALE,
+        validators: genesis_validators,
+        accounts: genesis_accounts,
+        eam_permission_mode: PermissionMode::Unrestricted,
+        ipc: Some(IpcParams {
+            gateway: GatewayParams {
+                subnet_id: child_subnet_id(),
+                bottom_up_check_period: BOTTOM_UP_CHECK_PERIOD,
+                majority_percentage: 67,
+                active_validators_limit: 100,
+            },
+        }),
+        ipc_contracts_owner: ethers::types::Address::zero(),
+        f3: None,
+    };
+
+    Ok(FuzzState {
+        genesis,
+        accounts,
+        validators,
+        upgrades,
+        height: 0,
+        parent_height: PARENT_GENESIS_EPOCH,
+        next_configuration_number: 1,
+        next_topdown_nonce: 0,
+        funded: TokenAmount::default(),
+    })
+}
+
+pub fn gen_command(u: &mut Unstructured, state: &FuzzState) -> arbitrary::Result<FuzzCommand> {
+    // Produce more child blocks than parent ones, so the subnet can catch up with its parent.
+    if u.ratio(1, 3)? {
+        gen_parent_block(u, state).map(FuzzCommand::ParentBlock)
+    } else {
+        gen_child_block(u, state).map(FuzzCommand::ChildBlock)
+    }
+}
+
+fn gen_parent_block(u: &mut Unstructured, state: &FuzzState) -> arbitrary::Result<ParentBlock> {
+    let height = state.parent_height + 1;
+
+    let mut configuration_number = state.next_configuration_number;
+    let mut has_metadata = state
+        .validators
+        .iter()
+        .map(|v| v.has_metadata)
+        .collect::<Vec<_>>();
+
+    let mut validator_changes = Vec::new();
+    for _ in 0..u.int_in_range(0..=2)? {
+        let i = u.choose_index(state.validators.len())?;
+        let validator = &state.validators[i];
+        let addr = validator
+            .eth_addr()
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+
+        let mut change = |op, payload| {
+            validator_changes.push(PowerChangeRequest {
+                configuration_number,
+                change: PowerChange {
+                    op,
+                    payload,
+                    validator: addr,
+                },
+            });
+            configuration_number += 1;
+        };
+
+        if !has_metadata[i] {
+            let pk = validator.secret_key.public_key();
+            change(PowerOperation::SetMetadata, pk.serialize().to_vec());
+            has_metadata[i] = true;
+        }
+
+        // Keep the first validator around, so there is always someone to produce blocks.
+        let collateral = if i == 0 {
+            *u.choose(&[1, 2, 5])?
+        } else {
+            *u.choose(&[0, 1, 2, 5])?
+        };
+        let collateral =
+            to_eth_tokens(&whole_fil(collateral)).map_err(|_| arbitrary::Error::IncorrectFormat)?;
+
+        change(
+            PowerOperation::SetPower,
+            ethers::abi::encode(&[collateral.into_token()]),
+        );
+    }
+
+    let mut top_down_msgs = Vec::new();
+    for i in 0..u.int_in_range(0..=2)? {
+        let to = u.choose(&state.accounts)?.addr;
+        let value = whole_fil(*u.choose(&[1, 10])?);
+        let mut msg =
+            IpcEnvelope::new_fund_msg(&child_subnet_id(), &Address::new_id(100), &to, value)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        msg.local_nonce = state.next_topdown_nonce + i;
+        top_down_msgs.push(msg);
+    }
+
+    Ok(ParentBlock {
+        height,
+        block_hash: parent_block_hash(height),
+        validator_changes,
+        top_down_msgs,
+    })
+}
+
+fn gen_child_block(u: &mut Unstructured, state: &FuzzState) -> arbitrary::Result<Vec<FuzzTx>> {
+    let mut nonces = state
+        .accounts
+        .iter()
+        .map(|a| a.next_nonce)
+        .collect::<Vec<_>>();
+
+    let mut txs = Vec::new();
+    for _ in 0..u.int_in_range(0..=6)? {
+        let from = u.choose_index(state.accounts.len())?;
+        let account = &state.accounts[from];
+
+        let (nonce, correct_nonce) = match u.int_in_range(0..=9)? {
+            0 => (nonces[from] + 1, false),
+            1 if nonces[from] > 0 => (nonces[from] - 1, false),
+            _ => (nonces[from], true),
+        };
+
+        let (gas_limit, sufficient_gas) = match u.int_in_range(0..=9)? {
+            0 => (u.int_in_range(0..=1000)?, false),
+            1 => (EXCESSIVE_GAS_LIMIT, false),
+            _ => (u.int_in_range(10_000_000..=50_000_000)?, true),
+        };
+
+        let (gas_fee_cap, sufficient_fee) = if u.ratio(1, 10)? {
+            (TokenAmount::default(), false)
+        } else {
+            (
+                TokenAmount::from_atto(u.int_in_range(1_000_000_000u64..=10_000_000_000)?),
+                true,
+            )
+        };
+        let gas_premium = TokenAmount::from_atto(u.int_in_range(0..=1_000_000u64)?);
+
+        let value = if u.ratio(1, 10)? {
+            whole_fil(1_000_000)
+        } else {
+            TokenAmount::from_atto(u.int_in_range(0..=1_000_000_000_000_000_000u64)?)
+        };
+
+        let method_num = if u.ratio(1, 5)? {
+            u.int_in_range(1..=u64::MAX)?
+        } else {
+            METHOD_SEND
+        };
+
+        let to = if u.ratio(1, 4)? {
+            let eth_addr = EthAddress(u.arbitrary()?);
+            Address::from(eth_addr)
+        } else {
+            u.choose(&state.accounts)?.addr
+        };
+
+        let message = MessageFactory::new(account.addr, nonce).transaction(
+            to,
+            method_num,
+            RawBytes::default(),
+            value,
+            GasParams {
+                gas_limit,
+                gas_fee_cap,
+                gas_premium,
+            },
+        );
+
+        let msg = SignedMessage::new_secp256k1(message, &account.secret_key, &state.chain_id())
+            .map(ChainMessage::Signed)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+
+        let expect_applied = correct_nonce && sufficient_gas && sufficient_fee;
+        if expect_applied {
+            nonces[from] += 1;
+        }
+
+        txs.push(FuzzTx {
+            from,
+            expect_applied,
+            msg,
+        });
+    }
+
+    Ok(txs)
+}
+
+pub fn next_state(cmd: &FuzzCommand, mut state: FuzzState) -> FuzzState {
+    match cmd {
+        FuzzCommand::ParentBlock(block) => {
+            state.parent_height = block.height;
+            for change in block.validator_changes.iter() {
+                if matches!(change.change.op, PowerOperation::SetMetadata) {
+                    if let Some(v) = state
+                        .validators
+                        .iter_mut()
+                        .find(|v| v.eth_addr().ok().as_ref() == Some(&change.change.validator))
+                    {
+                        v.has_metadata = true;
+                    }
+                }
+                state.next_configuration_number = change.configuration_number + 1;
+            }
+            for msg in block.top_down_msgs.iter() {
+                state.next_topdown_nonce = msg.local_nonce + 1;
+                state.funded += msg.value.clone();
+            }
+        }
+        FuzzCommand::ChildBlock(txs) => {
+            state.height += 1;
+            for tx in txs {
+                let account = &mut state.accounts[tx.from];
+                account.sent += 1;
+                if tx.expect_applied {
+                    account.next_nonce += 1;
+                }
+            }
+        }
+    }
+    state
+}
@@ -0,0 +1,16 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! State Machine Test driving the ABCI application with random blocks.
+//!
+//! It can be executed the following way:
+//!
+//! ```text
+//! cargo test --release -p fendermint_fuzz_test --test abci
+//! ```
+
+use fendermint_fuzz_test::AbciMachine;
+use fendermint_testing::state_machine_test;
+
+state_machine_test!(abci, 30000 ms, 65512 bytes, 50 steps, AbciMachine::default());
+//state_machine_test!(abci, 0x4327d37100000200, 50 steps, AbciMachine::default());
@@ -0,0 +1,634 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, VecDeque};
+
+use anyhow::{anyhow, bail, Context};
+use bytes::Bytes;
+use fendermint_abci::Application;
+use fendermint_crypto::SecretKey;
+use fendermint_materializer::{NodeName, SubnetName};
+use fendermint_vm_genesis::ValidatorKey;
+use fendermint_vm_topdown::voting::{self, Weight};
+use fendermint_vm_topdown::IPCParentFinality;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::manager::mock::MockParent;
+use tendermint::abci::{request, response, types::CommitInfo};
+use tendermint::block::{self, Height};
+use tendermint::{account, validator, AppHash, Hash};
+use tokio::sync::Mutex;
+
+use crate::checkpoints::gossip_checkpoint_signatures;
+use crate::node::{InProcApp, InProcPeer};
+use crate::parent::sync_parent;
+use crate::votes::gossip_votes;
+
+/// Seconds between the timestamps of consecutive blocks.
+const BLOCK_INTERVAL_SECS: i64 = 1;
+
+/// Maximum size of a block; the default of `cometbft init`.
+const BLOCK_MAX_BYTES: i64 = 22020096;
+
+/// A block which was committed by the nodes of the chain.
+#[derive(Debug, Clone)]
+pub struct CommittedBlock {
+    pub header: block::Header,
+    pub txs: Vec<Bytes>,
+    /// Delivery results, as seen by the first node.
+    pub tx_results: Vec<response::DeliverTx>,
+    /// The app hash after committing this block, which goes into the header of the next one.
+    pub app_hash: AppHash,
+}
+
+/// An active validator in the consensus.
+#[derive(Debug, Clone)]
+pub struct ChainValidator {
+    pub pub_key: tendermint::PublicKey,
+    pub power: u64,
+}
+
+/// Consensus among the in-process nodes of a subnet, standing in for CometBFT.
+///
+/// Blocks are only produced when asked for, which makes tests deterministic:
+/// * before each block the nodes learn about the new blocks of the parent and gossip their votes;
+/// * the proposer of each round is picked in turn from the validators, ordered by address;
+/// * the proposal is sent to every running validator to vote on in `process_proposal`;
+/// * if validators with more than 2/3 of the power accept it, every running node executes it,
+///   otherwise the next validator gets to propose in another round;
+/// * the app hashes of all nodes have to agree after the commit;
+/// * at checkpoint heights the validators sign the app hash and share their signatures;
+/// * validator updates take effect two blocks after they are returned, like in CometBFT.
+pub struct InProcChain {
+    name: SubnetName,
+    chain_id: String,
+    genesis_time: i64,
+    /// The sealed genesis in the format CometBFT passes it to `init_chain`.
+    app_state_bytes: Bytes,
+    subnet_id: SubnetID,
+    /// The in-memory parent the subnet follows.
+    parent: MockParent,
+    /// The parent finality the subnet was created with.
+    parent_genesis: IPCParentFinality,
+    /// Voting power of the genesis validators in the parent finality votes.
+    power_table: Vec<(voting::ValidatorKey, Weight)>,
+    state: Mutex<ChainState>,
+}
+
+#[derive(Default)]
+struct ChainState {
+    peers: BTreeMap<NodeName, InProcPeer>,
+    /// Validators as returned by `init_chain` on the first node.
+    genesis_validators: Option<BTreeMap<account::Id, ChainValidator>>,
+    /// The current validator set.
+    validators: BTreeMap<account::Id, ChainValidator>,
+    /// Validator updates to apply at the given heights.
+    pending_updates: BTreeMap<u64, Vec<validator::Update>>,
+    /// Transactions waiting to be proposed.
+    mempool: VecDeque<Bytes>,
+    /// Every block so far, to replay them on nodes that start late.
+    blocks: Vec<CommittedBlock>,
+}
+
+impl InProcChain {
+    pub(crate) fn new(
+        name: SubnetName,
+        chain_id: u64,
+        genesis_time: i64,
+        app_state_bytes: Bytes,
+        parent: MockParent,
+        parent_genesis: IPCParentFinality,
+        power_table: Vec<(voting::ValidatorKey, Weight)>,
+    ) -> Self {
+        Self {
+            name,
+            chain_id: chain_id.to_string(),
+            genesis_time,
+            app_state_bytes,
+            subnet_id: parent.child_subnet(),
+            parent,
+            parent_genesis,
+            power_table,
+            state: Mutex::new(ChainState::default()),
+        }
+    }
+
+    pub fn name(&self) -> &SubnetName {
+        &self.name
+    }
+
+    pub fn subnet_id(&self) -> &SubnetID {
+        &self.subnet_id
+    }
+
+    /// The in-memory parent of the subnet.
+    ///
+    /// Tests can send top-down messages and produce blocks on it; the nodes see
+    /// the new parent blocks the next time a block is produced on this chain.
+    pub fn parent(&self) -> &MockParent {
+        &self.parent
+    }
+
+    /// Height of the last committed block; 0 before the first block.
+    pub async fn height(&self) -> u64 {
+        self.state.lock().await.blocks.len() as u64
+    }
+
+    /// A committed block, if the height has been reached.
+    pub async fn block(&self, height: u64) -> Option<CommittedBlock> {
+        let state = self.state.lock().await;
+        height
+            .checked_sub(1)
+            .and_then(|i| state.blocks.get(i as usize).cloned())
+    }
+
+    /// The current validator set.
+    pub async fn validators(&self) -> Vec<ChainValidator> {
+        let state = self.state.lock().await;
+        state.validators.values().cloned().collect()
+    }
+
+    /// The ABCI application of a node.
+    pub(crate) async fn app(&self, name: &NodeName) -> anyhow::Result<InProcApp> {
+        let state = self.state.lock().await;
+        let peer = state.peer(name)?;
+        Ok(peer.app.clone())
+    }
+
+    /// Add a node which has yet to be started.
+    pub(crate) async fn add_peer(
+        &self,
+        name: NodeName,
+        validator_key: Option<SecretKey>,
+    ) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+        if state.peers.contains_key(&name) {
+            bail!("node {name:?} already exists");
+        }
+        let peer = InProcPeer::new(
+            validator_key,
+            &self.subnet_id,
+            &self.parent_genesis,
+            self.power_table.clone(),
+        )?;
+        state.peers.insert(name, peer);
+        Ok(())
+    }
+
+    /// Initialize the state of a node from genesis and catch up with the blocks it missed.
+    pub(crate) async fn start_peer(&self, name: &NodeName) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+        let peer = state.peer(name)?;
+
+        if peer.started {
+            return Ok(());
+        }
+
+        let res = peer
+            .app
+            .init_chain(request::InitChain {
+                time: self.block_time(0)?,
+                chain_id: self.chain_id.clone(),
+                consensus_params: consensus_params(),
+                validators: Vec::new(),
+                app_state_bytes: self.app_state_bytes.clone(),
+                initial_height: Height::from(1u32),
+            })
+            .await
+            .map_err(|e| anyhow!("init_chain failed: {e}"))?;
+
+        let validators = res
+            .validators
+            .into_iter()
+            .map(|u| {
+                let v = ChainValidator {
+                    pub_key: u.pub_key,
+                    power: u.power.value(),
+                };
+                (account::Id::from(u.pub_key), v)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        match state.genesis_validators {
+            None => {
+                state.validators = validators.clone();
+                state.genesis_validators = Some(validators);
+            }
+            Some(ref genesis_validators) => {
+                let expected = genesis_validators.keys().collect::<Vec<_>>();
+                let actual = validators.keys().collect::<Vec<_>>();
+                if expected != actual {
+                    bail!("node {name:?} disagrees about the genesis validators");
+                }
+            }
+        }
+
+        // The blocks to replay might execute parent finalities, which need the parent blocks.
+        let peer = state.peer(name)?;
+        sync_parent(&self.parent, peer)
+            .await
+            .with_context(|| format!("{name:?} failed to sync with the parent"))?;
+
+        for block in state.blocks.iter() {
+            let (_, app_hash) = execute_block(&peer.app, block)
+                .await
+                .with_context(|| format!("{name:?} failed to replay block"))?;
+
+            if app_hash != block.app_hash {
+                bail!(
+                    "{name:?} diverged while replaying block {}",
+                    block.header.height
+                );
+            }
+        }
+
+        state.peer_mut(name)?.started = true;
+
+        Ok(())
+    }
+
+    /// Send a transaction to the mempool of every running node.
+    ///
+    /// The transaction is kept for the next proposal unless the first node rejects it.
+    pub async fn submit(&self, tx: Vec<u8>) -> anyhow::Result<response::CheckTx> {
+        let mut state = self.state.lock().await;
+        let tx = Bytes::from(tx);
+
+        let mut first = None;
+        for peer in state.running_peers() {
+            let res = peer
+                .app
+                .check_tx(request::CheckTx {
+                    tx: tx.clone(),
+                    kind: request::CheckTxKind::New,
+                })
+                .await
+                .map_err(|e| anyhow!("check_tx failed: {e}"))?;
+
+            first.get_or_insert(res);
+        }
+
+        let res = first.ok_or_else(|| anyhow!("there are no running nodes on {:?}", self.name))?;
+
+        if res.code.is_ok() {
+            state.mempool.push_back(tx);
+        }
+
+        Ok(res)
+    }
+
+    /// Produce a number of blocks, returning the height of the last one.
+    pub async fn produce_blocks(&self, count: u64) -> anyhow::Result<u64> {
+        let mut height = self.height().await;
+        for _ in 0..count {
+            height = self.produce_block().await?;
+        }
+        Ok(height)
+    }
+
+    /// Run rounds of consensus until a block is committed, returning its height.
+    pub async fn produce_block(&self) -> anyhow::Result<u64> {
+        let mut state = self.state.lock().await;
+
+        if state.running_peers().next().is_none() {
+            bail!("there are no running nodes on {:?}", self.name);
+        }
+
+        let height = state.blocks.len() as u64 + 1;
+        let time = self.block_time(height)?;
+
+        if let Some(updates) = state.pending_updates.remove(&height) {
+            state.apply_validator_updates(updates);
+        }
+
+        // Let the nodes see the new blocks of the parent, and the validators
+        // learn about each other's votes before they propose.
+        for peer in state.running_peers() {
+            sync_parent(&self.parent, peer)
+                .await
+                .context("failed to sync with the parent")?;
+        }
+        gossip_votes(state.running_peers()).await;
+
+        let proposers = state.validators.keys().cloned().collect::<Vec<_>>();
+        if proposers.is_empty() {
+            bail!("there are no validators on {:?}", self.name);
+        }
+        let total_power = state.validators.values().map(|v| v.power).sum::<u64>();
+        let txs = state.mempool.iter().cloned().collect::<Vec<_>>();
+
+        for round in 0..proposers.len() {
+            let proposer = proposers[(height as usize + round) % proposers.len()];
+
+            let Some(proposer_peer) = state.validator_peer(&proposer) else {
+                tracing::debug!(height, round, ?proposer, "proposer is not running");
+                continue;
+            };
+
+            let proposal = proposer_peer
+                .app
+                .prepare_proposal(request::PrepareProposal {
+                    max_tx_bytes: BLOCK_MAX_BYTES,
+                    txs: txs.clone(),
+                    local_last_commit: None,
+                    misbehavior: Vec::new(),
+                    height: Height::try_from(height)?,
+                    time,
+                    next_validators_hash: Hash::None,
+                    proposer_address: proposer,
+                })
+                .await
+                .map_err(|e| anyhow!("prepare_proposal failed: {e}"))?;
+
+            let header = block::Header {
+                version: block::header::Version { block: 11, app: 0 },
+                chain_id: tendermint::chain::Id::try_from(self.chain_id.clone())?,
+                height: Height::try_from(height)?,
+                time,
+                last_block_id: state.last_block_id()?,
+                last_commit_hash: None,
+                data_hash: None,
+                validators_hash: Hash::None,
+                next_validators_hash: Hash::None,
+                consensus_hash: Hash::None,
+                app_hash: state.last_app_hash(),
+                last_results_hash: None,
+                evidence_hash: None,
+                proposer_address: proposer,
+            };
+            let hash = header.hash();
+
+            let mut accepted_power = 0;
+            for (id, peer) in state.running_validator_peers() {
+                let res = peer
+                    .app
+                    .process_proposal(request::ProcessProposal {
+                        txs: proposal.txs.clone(),
+                        proposed_last_commit: None,
+                        misbehavior: Vec::new(),
+                        hash,
+                        height: Height::try_from(height)?,
+                        time,
+                        next_validators_hash: Hash::None,
+                        proposer_address: proposer,
+                    })
+                    .await
+                    .map_err(|e| anyhow!("process_proposal failed: {e}"))?;
+
+                if res == response::ProcessProposal::Accept {
+                    accepted_power += state.validators.get(&id).map(|v| v.power).unwrap_or(0);
+                }
+            }
+
+            if accepted_power * 3 <= total_power * 2 {
+                tracing::debug!(
+                    height,
+                    round,
+                    accepted_power,
+                    total_power,
+                    "proposal rejected"
+                );
+                continue;
+            }
+
+            let mut block = CommittedBlock {
+                header,
+                txs: proposal.txs,
+                tx_results: Vec::new(),
+                app_hash: AppHash::default(),
+            };
+
+            let mut outcome: Option<(NodeName, BlockOutcome, AppHash)> = None;
+            for (name, peer) in state.peers.iter().filter(|(_, p)| p.started) {
+                let (result, app_hash) = execute_block(&peer.app, &block)
+                    .await
+                    .with_context(|| format!("{name:?} failed to execute block {height}"))?;
+
+                match outcome {
+                    None => outcome = Some((name.clone(), result, app_hash)),
+                    Some((ref first, _, ref expected)) if *expected != app_hash => {
+                        bail!("{name:?} diverged from {first:?} at height {height}: app hash {app_hash} != {expected}");
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let (_, result, app_hash) = outcome.expect("there are running nodes");
+
+            // The validators certify the app hash of checkpoints for the relayers.
+            gossip_checkpoint_signatures(state.running_peers(), &self.subnet_id, height, &app_hash)
+                .await
+                .context("failed to gossip checkpoint signatures")?;
+
+            block.tx_results = result.tx_results;
+            block.app_hash = app_hash;
+
+            if !result.validator_updates.is_empty() {
+                state
+                    .pending_updates
+                    .entry(height + 2)
+                    .or_default()
+                    .extend(result.validator_updates);
+            }
+
+            state.mempool.retain(|tx| !block.txs.contains(tx));
+            state.blocks.push(block);
+            state.recheck_mempool().await?;
+
+            return Ok(height);
+        }
+
+        bail!(
+            "no proposal was accepted on {:?} at height {height}",
+            self.name
+        )
+    }
+
+    fn block_time(&self, height: u64) -> anyhow::Result<tendermint::Time> {
+        let secs = self.genesis_time + height as i64 * BLOCK_INTERVAL_SECS;
+        Ok(tendermint::Time::from_unix_timestamp(secs, 0)?)
+    }
+}
+
+impl ChainState {
+    fn peer(&self, name: &NodeName) -> anyhow::Result<&InProcPeer> {
+        self.peers
+            .get(name)
+            .ok_or_else(|| anyhow!("node {name:?} does not exist"))
+    }
+
+    fn peer_mut(&mut self, name: &NodeName) -> anyhow::Result<&mut InProcPeer> {
+        self.peers
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("node {name:?} does not exist"))
+    }
+
+    fn running_peers(&self) -> impl Iterator<Item = &InProcPeer> + Clone {
+        self.peers.values().filter(|p| p.started)
+    }
+
+    /// Running nodes with validator keys, whether they have any power or not.
+    fn running_validator_peers(&self) -> impl Iterator<Item = (account::Id, &InProcPeer)> {
+        self.running_peers()
+            .filter_map(|p| validator_id(p).map(|id| (id, p)))
+    }
+
+    /// The running node of a validator.
+    fn validator_peer(&self, id: &account::Id) -> Option<&InProcPeer> {
+        self.running_validator_peers()
+            .find(|(pid, _)| pid == id)
+            .map(|(_, p)| p)
+    }
+
+    fn last_block_id(&self) -> anyhow::Result<Option<block::Id>> {
+        match self.blocks.last() {
+            None => Ok(None),
+            Some(b) => {
+                let hash = b.header.hash();
+                Ok(Some(block::Id {
+                    hash,
+                    part_set_header: block::parts::Header::new(1, hash)?,
+                }))
+            }
+        }
+    }
+
+    fn last_app_hash(&self) -> AppHash {
+        self.blocks
+            .last()
+            .map(|b| b.app_hash.clone())
+            .unwrap_or_default()
+    }
+
+    fn apply_validator_updates(&mut self, updates: Vec<validator::Update>) {
+        for u in updates {
+            let id = account::Id::from(u.pub_key);
+            let power = u.power.value();
+            if power == 0 {
+                self.validators.remove(&id);
+            } else {
+                self.validators.insert(
+                    id,
+                    ChainValidator {
+                        pub_key: u.pub_key,
+                        power,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Check the transactions left in the mempool against the new state, dropping the ones the first node rejects.
+    async fn recheck_mempool(&mut self) -> anyhow::Result<()> {
+        let mut mempool = VecDeque::new();
+
+        for tx in std::mem::take(&mut self.mempool) {
+            let mut keep = None;
+            for peer in self.running_peers() {
+                let res = peer
+                    .app
+                    .check_tx(request::CheckTx {
+                        tx: tx.clone(),
+                        kind: request::CheckTxKind::Recheck,
+                    })
+                    .await
+                    .map_err(|e| anyhow!("check_tx failed: {e}"))?;
+
+                keep.get_or_insert(res.code.is_ok());
+            }
+            if keep.unwrap_or_default() {
+                mempool.push_back(tx);
+            }
+        }
+
+        self.mempool = mempool;
+
+        Ok(())
+    }
+}
+
+/// What we need to remember from executing a block.
+struct BlockOutcome {
+    tx_results: Vec<response::DeliverTx>,
+    validator_updates: Vec<validator::Update>,
+}
+
+/// Execute and commit a block on a node, returning the results and the new app hash.
+async fn execute_block(
+    app: &InProcApp,
+    block: &CommittedBlock,
+) -> anyhow::Result<(BlockOutcome, AppHash)> {
+    app.begin_block(request::BeginBlock {
+        hash: block.header.hash(),
+        header: block.header.clone(),
+        last_commit_info: CommitInfo {
+            round: block::Round::default(),
+            votes: Vec::new(),
+        },
+        byzantine_validators: Vec::new(),
+    })
+    .await
+    .map_err(|e| anyhow!("begin_block failed: {e}"))?;
+
+    let mut tx_results = Vec::new();
+    for tx in block.txs.iter() {
+        let res = app
+            .deliver_tx(request::DeliverTx { tx: tx.clone() })
+            .await
+            .map_err(|e| anyhow!("deliver_tx failed: {e}"))?;
+
+        tx_results.push(res);
+    }
+
+    let res = app
+        .end_block(request::EndBlock {
+            height: block.header.height.value().try_into()?,
+        })
+        .await
+        .map_err(|e| anyhow!("end_block failed: {e}"))?;
+
+    let validator_updates = res.validator_updates;
+
+    let res = app
+        .commit()
+        .await
+        .map_err(|e| anyhow!("commit failed: {e}"))?;
+
+    let app_hash = AppHash::try_from(res.data.to_vec())?;
+
+    Ok((
+        BlockOutcome {
+            tx_results,
+            validator_updates,
+        },
+        app_hash,
+    ))
+}
+
+/// The consensus address of a node with a validator key.
+fn validator_id(peer: &InProcPeer) -> Option<account::Id> {
+    let sk = peer.validator_key.as_ref()?;
+    let pk = tendermint::PublicKey::try_from(ValidatorKey(sk.public_key())).ok()?;
+    Some(account::Id::from(pk))
+}
+
+/// Consensus parameters based on the defaults of `cometbft init`.
+fn consensus_params() -> tendermint::consensus::Params {
+    tendermint::consensus::Params {
+        block: tendermint::block::Size {
+            max_bytes: BLOCK_MAX_BYTES as u64,
+            max_gas: -1,
+            time_iota_ms: tendermint::block::Size::default_time_iota_ms(),
+        },
+        evidence: tendermint::evidence::Params {
+            max_age_num_blocks: 100000,
+            max_age_duration: tendermint::evidence::Duration(std::time::Duration::from_nanos(
+                172800000000000,
+            )),
+            max_bytes: 1048576,
+        },
+        validator: tendermint::consensus::params::ValidatorParams {
+            pub_key_types: vec![tendermint::public_key::Algorithm::Secp256k1],
+        },
+        version: Some(tendermint::consensus::params::VersionParams { app: 0 }),
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use fendermint_app::checkpoint::sign_checkpoint;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_interpreter::fvm::state::ipc::GatewayCaller;
+use ipc_api::subnet_id::SubnetID;
+use tendermint::AppHash;
+
+use crate::node::{InProcApp, InProcPeer};
+
+/// Share the checkpoint signature of every validator with every node, if the block at
+/// the given height created a bottom-up checkpoint.
+///
+/// This stands in for the checkpoint signer of the validators, which would publish their
+/// signature over the app hash through the IPLD Resolver after the commit.
+/// Signatures the collectors reject are ignored, just like a node would drop them
+/// after receiving them from the network.
+pub(crate) async fn gossip_checkpoint_signatures<'a, I>(
+    peers: I,
+    subnet_id: &SubnetID,
+    height: u64,
+    app_hash: &AppHash,
+) -> anyhow::Result<()>
+where
+    I: Iterator<Item = &'a InProcPeer> + Clone,
+{
+    let Some(first) = peers.clone().next() else {
+        return Ok(());
+    };
+
+    if !is_checkpoint_height(&first.app, height)? {
+        return Ok(());
+    }
+
+    let mut signatures = Vec::new();
+
+    for peer in peers.clone() {
+        let Some(ref sk) = peer.validator_key else {
+            continue;
+        };
+
+        let signature = sign_checkpoint(sk, subnet_id, height, app_hash.as_bytes())
+            .context("failed to sign checkpoint")?;
+
+        signatures.push((EthAddress::from(sk.public_key()), signature));
+    }
+
+    for peer in peers {
+        for (validator, signature) in signatures.iter() {
+            if let Err(e) = peer
+                .checkpoint_signatures
+                .add((*validator).into(), signature.clone())
+            {
+                tracing::debug!(
+                    error = e.to_string(),
+                    height,
+                    "checkpoint signature ignored"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether the end block hook created a checkpoint at the last committed height,
+/// which it does at the end of every period and whenever the message batch is full.
+fn is_checkpoint_height(app: &InProcApp, height: u64) -> anyhow::Result<bool> {
+    let Some(mut state) = app.read_only_view(None)? else {
+        return Ok(false);
+    };
+
+    let gateway = GatewayCaller::default();
+
+    let batch = gateway.bottom_up_msg_batch(&mut state, height)?;
+    if batch.block_height.as_u64() != 0 {
+        return Ok(true);
+    }
+
+    let period = gateway.bottom_up_check_period(&mut state)?;
+
+    Ok(height % period == 0)
+}
@@ -0,0 +1,383 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! A [Materializer] which runs every node of a testnet as an ABCI application
+//! inside the test process, with an in-memory stand-in for CometBFT.
+//!
+//! Unlike the docker materializer this needs no containers or network, and
+//! blocks are only produced when a test asks for them, so scenarios involving
+//! several validators (proposer rotation, votes, nodes going offline or catching
+//! up) can run quickly and deterministically in CI.
+//!
+//! The rootnet of the manifest is not a root: it follows an in-memory parent, a
+//! [MockParent](ipc_provider::manager::mock::MockParent), whose blocks are fed to the
+//! parent finality provider and vote tally of every node instead of a syncer, while
+//! the validators gossip their votes through the chain. Tests can fund the subnet or
+//! send other top-down messages on [InProcChain::parent] and produce blocks on both
+//! chains to see them executed once the validators agree on the parent finality.
+//!
+//! In the other direction, the validators sign the app hash at every bottom-up checkpoint
+//! height and share their signatures with every node, where they can be queried like a
+//! relayer would, and submitted to the in-memory parent by the test itself.
+//!
+//! Nodes don't serve any APIs, instead tests drive them through the [InProcChain] of their subnet.
+//!
+//! # Scope
+//!
+//! The following are not supported, and the corresponding [Materializer] methods fail:
+//! * child subnets of the rootnet, ie. `create_subnet`, `approve_subnet`, `fund_subnet`,
+//!   `join_subnet` and `create_subnet_genesis`, which would need a parent proxy reading
+//!   the IPC contracts of an in-process chain;
+//! * relayers, which the manifest only allows in child subnets; tests relay the
+//!   checkpoints of the rootnet to the in-memory parent themselves;
+//! * faucets and deployments to external chains;
+//! * reorgs of the in-memory parent, which the nodes don't follow.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use ethers::{
+    core::rand::{rngs::StdRng, SeedableRng},
+    types::H160,
+};
+use fendermint_materializer::{
+    manifest::Balance,
+    materializer::{Materializer, NodeConfig, RelayerConfig, SubmitConfig, SubnetConfig},
+    materials::{
+        export_json, import_json, DefaultAccount, DefaultDeployment, DefaultGenesis, DefaultSubnet,
+        Materials, WithNodeName,
+    },
+    AccountName, NodeName, RelayerName, ResourceHash, ResourceId, SubnetName, TestnetName,
+};
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_core::{chainid, Timestamp};
+use fendermint_vm_genesis::{
+    ipc::{GatewayParams, IpcParams},
+    Account, Actor, ActorMeta, Collateral, Genesis, SignerAddr, Validator, ValidatorKey,
+};
+use fvm_shared::{bigint::Zero, econ::TokenAmount, version::NetworkVersion};
+
+mod chain;
+mod checkpoints;
+mod network;
+mod node;
+mod parent;
+mod votes;
+
+pub use chain::{ChainValidator, CommittedBlock, InProcChain};
+pub use network::InProcNetwork;
+pub use node::{InProcApp, InProcNode};
+pub use parent::{PARENT_CHAIN_ID, PARENT_GENESIS_EPOCH};
+
+use parent::rootnet_subnet_id;
+
+const DEFAULT_TEST_CHAIN_ID: u64 = 10000;
+
+const CHILD_SUBNETS_UNSUPPORTED: &str =
+    "the in-process materializer does not support child subnets of the rootnet";
+
+#[derive(Debug)]
+pub struct InProcMaterials;
+
+impl Materials for InProcMaterials {
+    type Deployment = DefaultDeployment;
+    type Account = DefaultAccount;
+    type Genesis = DefaultGenesis;
+    type Subnet = DefaultSubnet;
+
+    type Network = InProcNetwork;
+    type Node = InProcNode;
+    type Relayer = RelayerName;
+}
+
+pub struct InProcMaterializer {
+    dir: PathBuf,
+    rng: StdRng,
+}
+
+impl InProcMaterializer {
+    /// Create a materializer with a directory where the keys and
+    /// genesis files of the testnets can be kept.
+    pub fn new(dir: &Path, seed: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Return an existing genesis by parsing it from the `genesis.json` of the subnet,
+    /// or create a new one and export it.
+    fn get_or_create_genesis<F>(
+        &self,
+        subnet_name: &SubnetName,
+        make_genesis: F,
+    ) -> anyhow::Result<DefaultGenesis>
+    where
+        F: FnOnce() -> anyhow::Result<Genesis>,
+    {
+        let genesis_path = self.dir.join(subnet_name.path()).join("genesis.json");
+
+        let genesis = match import_json(&genesis_path)? {
+            Some(genesis) => genesis,
+            None => {
+                let genesis = make_genesis()?;
+                export_json(&genesis_path, &genesis)?;
+                genesis
+            }
+        };
+
+        Ok(DefaultGenesis {
+            name: subnet_name.clone(),
+            genesis,
+            path: genesis_path,
+        })
+    }
+}
+
+#[async_trait]
+impl Materializer<InProcMaterials> for InProcMaterializer {
+    async fn create_network(
+        &mut self,
+        testnet_name: &TestnetName,
+    ) -> anyhow::Result<InProcNetwork> {
+        Ok(InProcNetwork::new(testnet_name.clone()))
+    }
+
+    fn create_account(&mut self, account_name: &AccountName) -> anyhow::Result<DefaultAccount> {
+        DefaultAccount::get_or_create(&mut self.rng, &self.dir, account_name)
+    }
+
+    async fn fund_from_faucet<'s, 'a>(
+        &'s mut self,
+        _account: &'a DefaultAccount,
+        _reference: Option<ResourceHash>,
+    ) -> anyhow::Result<()>
+    where
+        's: 'a,
+    {
+        bail!("the in-process materializer has no faucet; use genesis balances")
+    }
+
+    async fn new_deployment<'s, 'a>(
+        &'s mut self,
+        _subnet_name: &SubnetName,
+        _deployer: &'a DefaultAccount,
+        _urls: Vec<url::Url>,
+    ) -> anyhow::Result<DefaultDeployment>
+    where
+        's: 'a,
+    {
+        bail!("the in-process materializer cannot deploy to external subnets")
+    }
+
+    fn existing_deployment(
+        &mut self,
+        subnet_name: &SubnetName,
+        gateway: H160,
+        registry: H160,
+    ) -> anyhow::Result<DefaultDeployment> {
+        Ok(DefaultDeployment {
+            name: subnet_name.clone(),
+            gateway: EthAddress::from(gateway),
+            registry: EthAddress::from(registry),
+        })
+    }
+
+    fn default_deployment(
+        &mut self,
+        subnet_name: &SubnetName,
+    ) -> anyhow::Result<DefaultDeployment> {
+        Ok(DefaultDeployment::builtin(subnet_name.clone()))
+    }
+
+    /// Same as the docker materializer, so the ledgers are comparable,
+    /// except that the subnet is a child of the in-memory parent.
+    fn create_root_genesis<'a>(
+        &mut self,
+        subnet_name: &SubnetName,
+        validators: BTreeMap<&'a DefaultAccount, Collateral>,
+        balances: BTreeMap<&'a DefaultAccount, Balance>,
+        ipc_contracts_owner: &'a DefaultAccount,
+    ) -> anyhow::Result<DefaultGenesis> {
+        self.get_or_create_genesis(subnet_name, || {
+            let chain_name = subnet_name.path_string();
+            let chain_id = chainid::from_str_hashed(&chain_name)?;
+            let ipc_contracts_owner = ipc_contracts_owner.eth_addr().into();
+
+            let genesis = Genesis {
+                chain_name,
+                chain_id: DEFAULT_TEST_CHAIN_ID,
+                timestamp: Timestamp::current(),
+                network_version: NetworkVersion::V21,
+                base_fee: TokenAmount::zero(),
+                power_scale: 3,
+                validators: validators
+                    .into_iter()
+                    .map(|(v, c)| Validator {
+                        public_key: ValidatorKey(*v.public_key()),
+                        power: c,
+                    })
+                    .collect(),
+                accounts: balances
+                    .into_iter()
+                    .map(|(a, b)| Actor {
+                        meta: ActorMeta::Account(Account {
+                            owner: SignerAddr(a.fvm_addr()),
+                        }),
+                        balance: b.0,
+                    })
+                    .collect(),
+                eam_permission_mode: fendermint_vm_genesis::PermissionMode::Unrestricted,
+                ipc: Some(IpcParams {
+                    gateway: GatewayParams {
+                        subnet_id: rootnet_subnet_id(chain_id.into()),
+                        bottom_up_check_period: 1,
+                        majority_percentage: 67,
+                        active_validators_limit: 100,
+                    },
+                }),
+                ipc_contracts_owner,
+                f3: None,
+            };
+            Ok(genesis)
+        })
+    }
+
+    fn create_root_subnet(
+        &mut self,
+        subnet_name: &SubnetName,
+        contracts_owner: &ResourceId,
+        params: &DefaultGenesis,
+    ) -> anyhow::Result<DefaultSubnet> {
+        let ipc = params
+            .genesis
+            .ipc
+            .as_ref()
+            .ok_or_else(|| anyhow!("IPC configuration missing from genesis"))?;
+
+        Ok(DefaultSubnet {
+            name: subnet_name.clone(),
+            contracts_owner: contracts_owner.clone(),
+            subnet_id: ipc.gateway.subnet_id.clone(),
+        })
+    }
+
+    /// Add a node to the chain of its subnet; the API settings are ignored.
+    async fn create_node<'s, 'a>(
+        &'s mut self,
+        node_name: &NodeName,
+        node_config: &NodeConfig<'a, InProcMaterials>,
+    ) -> anyhow::Result<InProcNode>
+    where
+        's: 'a,
+    {
+        if node_config.parent_node.is_some() {
+            bail!(CHILD_SUBNETS_UNSUPPORTED);
+        }
+
+        let chain = node_config
+            .network
+            .get_or_create_chain(node_config.genesis)
+            .await?;
+
+        chain
+            .add_peer(
+                node_name.clone(),
+                node_config.validator.map(|a| a.secret_key().clone()),
+            )
+            .await?;
+
+        Ok(InProcNode::new(node_name.clone(), chain))
+    }
+
+    /// Run `init_chain` on the node and replay any blocks it missed; seeds are irrelevant.
+    async fn start_node<'s, 'a>(
+        &'s mut self,
+        node: &'a InProcNode,
+        _seed_nodes: &'a [&'a InProcNode],
+    ) -> anyhow::Result<()>
+    where
+        's: 'a,
+    {
+        node.chain().start_peer(node.node_name()).await
+    }
+
+    async fn create_subnet<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _subnet_name: &SubnetName,
+        _subnet_config: &SubnetConfig<'a, InProcMaterials>,
+    ) -> anyhow::Result<DefaultSubnet>
+    where
+        's: 'a,
+    {
+        bail!(CHILD_SUBNETS_UNSUPPORTED)
+    }
+
+    async fn approve_subnet<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _subnet: &'a DefaultSubnet,
+        _contracts_owner: &'a DefaultAccount,
+    ) -> anyhow::Result<()>
+    where
+        's: 'a,
+    {
+        bail!(CHILD_SUBNETS_UNSUPPORTED)
+    }
+
+    async fn fund_subnet<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _account: &'a DefaultAccount,
+        _subnet: &'a DefaultSubnet,
+        _amount: TokenAmount,
+        _reference: Option<ResourceHash>,
+    ) -> anyhow::Result<()>
+    where
+        's: 'a,
+    {
+        bail!(CHILD_SUBNETS_UNSUPPORTED)
+    }
+
+    async fn join_subnet<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _account: &'a DefaultAccount,
+        _subnet: &'a DefaultSubnet,
+        _collateral: Collateral,
+        _balance: Balance,
+        _reference: Option<ResourceHash>,
+    ) -> anyhow::Result<()>
+    where
+        's: 'a,
+    {
+        bail!(CHILD_SUBNETS_UNSUPPORTED)
+    }
+
+    async fn create_subnet_genesis<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _subnet: &'a DefaultSubnet,
+    ) -> anyhow::Result<DefaultGenesis>
+    where
+        's: 'a,
+    {
+        bail!(CHILD_SUBNETS_UNSUPPORTED)
+    }
+
+    async fn create_relayer<'s, 'a>(
+        &'s mut self,
+        _parent_submit_config: &SubmitConfig<'a, InProcMaterials>,
+        _relayer_name: &RelayerName,
+        _relayer_config: RelayerConfig<'a, InProcMaterials>,
+    ) -> anyhow::Result<RelayerName>
+    where
+        's: 'a,
+    {
+        bail!("the in-process materializer does not support relayers without child subnets")
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use fendermint_materializer::{materials::DefaultGenesis, SubnetName, TestnetName};
+use fendermint_vm_interpreter::fvm::bundle::contracts_path;
+use fendermint_vm_interpreter::genesis::{GenesisAppState, GenesisBuilder};
+use fendermint_vm_topdown::voting::ValidatorKey;
+use tokio::sync::Mutex;
+
+use crate::chain::InProcChain;
+use crate::parent::new_parent;
+
+/// The chains of the subnets of a testnet, all living in the same process.
+#[derive(Clone)]
+pub struct InProcNetwork {
+    name: TestnetName,
+    chains: Arc<Mutex<BTreeMap<SubnetName, Arc<InProcChain>>>>,
+}
+
+impl InProcNetwork {
+    pub fn new(name: TestnetName) -> Self {
+        Self {
+            name,
+            chains: Default::default(),
+        }
+    }
+
+    pub fn name(&self) -> &TestnetName {
+        &self.name
+    }
+
+    /// The chain of a subnet, once a node has been created on it.
+    pub async fn chain(&self, subnet_name: &SubnetName) -> Option<Arc<InProcChain>> {
+        self.chains.lock().await.get(subnet_name).cloned()
+    }
+
+    /// Return the chain of the subnet of a genesis, sealing the genesis and creating
+    /// the in-memory parent of the subnet if this is the first node on it.
+    pub(crate) async fn get_or_create_chain(
+        &self,
+        genesis: &DefaultGenesis,
+    ) -> anyhow::Result<Arc<InProcChain>> {
+        let mut chains = self.chains.lock().await;
+
+        if let Some(chain) = chains.get(&genesis.name) {
+            return Ok(chain.clone());
+        }
+
+        let car_path = genesis.path.with_file_name("genesis.car");
+
+        GenesisBuilder::new(
+            actors_builtin_car::CAR,
+            actors_custom_car::CAR,
+            contracts_path(),
+            genesis.genesis.clone(),
+        )
+        .write_to(car_path.clone())
+        .await
+        .context("failed to seal genesis")?;
+
+        let car = tokio::fs::read(&car_path)
+            .await
+            .with_context(|| format!("failed to read {car_path:?}"))?;
+
+        // This is how `fendermint genesis into-tendermint` encodes the app state for CometBFT.
+        let app_state = GenesisAppState::v1(car).compress_and_encode()?;
+        let app_state_bytes = serde_json::to_vec(&app_state)?;
+
+        let ipc = genesis
+            .genesis
+            .ipc
+            .as_ref()
+            .ok_or_else(|| anyhow!("IPC configuration missing from genesis"))?;
+
+        let (parent, parent_genesis) = new_parent(ipc.gateway.subnet_id.clone()).await?;
+
+        let power_table = genesis
+            .genesis
+            .validators
+            .iter()
+            .map(|v| {
+                let power = v.power.clone().into_power(genesis.genesis.power_scale);
+                (ValidatorKey::from(v.public_key.0), power.0)
+            })
+            .collect();
+
+        let chain = Arc::new(InProcChain::new(
+            genesis.name.clone(),
+            genesis.genesis.chain_id,
+            genesis.genesis.timestamp.as_secs(),
+            Bytes::from(app_state_bytes),
+            parent,
+            parent_genesis,
+            power_table,
+        ));
+
+        chains.insert(genesis.name.clone(), chain.clone());
+
+        Ok(chain)
+    }
+}
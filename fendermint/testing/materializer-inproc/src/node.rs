@@ -0,0 +1,136 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::Arc;
+
+use fendermint_app::checkpoint::SignatureCollector;
+use fendermint_app::{App, AppConfig, AppStore};
+use fendermint_crypto::SecretKey;
+use fendermint_materializer::{materials::WithNodeName, NodeName};
+use fendermint_storage::im::InMemoryBackend;
+use fendermint_vm_interpreter::fvm::end_block_hook::EndBlockManager;
+use fendermint_vm_interpreter::fvm::interpreter::FvmMessagesInterpreter;
+use fendermint_vm_interpreter::fvm::store::memory::MemoryBlockstore;
+use fendermint_vm_interpreter::fvm::topdown::TopDownManager;
+use fendermint_vm_interpreter::fvm::upgrades::UpgradeScheduler;
+use fendermint_vm_topdown::voting::{ValidatorKey, VoteTally, Weight};
+use fendermint_vm_topdown::IPCParentFinality;
+use ipc_api::subnet_id::SubnetID;
+
+use crate::chain::InProcChain;
+use crate::parent::{new_finality_provider, FinalityProvider};
+
+/// Maximum number of messages in a block, same as the default node settings.
+const BLOCK_MAX_MSGS: usize = 1000;
+/// Gas estimation parameters, same as the default node settings.
+const GAS_OVERESTIMATION_RATE: f64 = 1.25;
+const GAS_SEARCH_STEP: f64 = 1.25;
+
+/// The ABCI application of an in-process node, with all of its storage kept in memory.
+pub type InProcApp = App<
+    InMemoryBackend<AppStore>,
+    MemoryBlockstore,
+    AppStore,
+    FvmMessagesInterpreter<MemoryBlockstore>,
+>;
+
+/// Handle to a node running inside the test process.
+///
+/// The node itself is owned by the chain of its subnet, which drives it through consensus.
+pub struct InProcNode {
+    name: NodeName,
+    chain: Arc<InProcChain>,
+}
+
+impl InProcNode {
+    pub(crate) fn new(name: NodeName, chain: Arc<InProcChain>) -> Self {
+        Self { name, chain }
+    }
+
+    /// The chain of the subnet the node belongs to, which can be used to produce blocks.
+    pub fn chain(&self) -> &Arc<InProcChain> {
+        &self.chain
+    }
+
+    /// The ABCI application of the node, to send queries to.
+    pub async fn app(&self) -> anyhow::Result<InProcApp> {
+        self.chain.app(&self.name).await
+    }
+}
+
+impl WithNodeName for InProcNode {
+    fn node_name(&self) -> &NodeName {
+        &self.name
+    }
+}
+
+/// The parts of a node the chain needs to drive it.
+pub(crate) struct InProcPeer {
+    pub app: InProcApp,
+    /// Parent blocks; in a real node these are fed by the parent syncer.
+    pub provider: FinalityProvider,
+    /// Parent finality votes; in a real node these are fed by the parent syncer and the IPLD Resolver.
+    pub votes: VoteTally,
+    /// Checkpoint signatures; in a real node these are fed by the IPLD Resolver.
+    pub checkpoint_signatures: SignatureCollector,
+    pub validator_key: Option<SecretKey>,
+    /// Whether the node has been started and takes part in consensus.
+    pub started: bool,
+}
+
+impl InProcPeer {
+    /// Create a node with an empty state, waiting for `init_chain`.
+    ///
+    /// The node follows the parent from the finality the subnet was created with,
+    /// with the genesis validators having a say in the votes.
+    pub fn new(
+        validator_key: Option<SecretKey>,
+        subnet_id: &SubnetID,
+        parent_genesis: &IPCParentFinality,
+        power_table: Vec<(ValidatorKey, Weight)>,
+    ) -> anyhow::Result<Self> {
+        let provider = new_finality_provider(subnet_id, parent_genesis)?;
+
+        let votes = VoteTally::new(
+            power_table,
+            (parent_genesis.height, parent_genesis.block_hash.clone()),
+        );
+
+        let top_down_manager = TopDownManager::new(provider.clone(), votes.clone());
+
+        let interpreter = FvmMessagesInterpreter::new(
+            EndBlockManager::new(),
+            top_down_manager,
+            UpgradeScheduler::new(),
+            true,
+            BLOCK_MAX_MSGS,
+            GAS_OVERESTIMATION_RATE,
+            GAS_SEARCH_STEP,
+        );
+
+        let checkpoint_signatures = SignatureCollector::default();
+
+        let app = App::new(
+            AppConfig {
+                app_namespace: "app".to_string(),
+                state_hist_namespace: "state_hist".to_string(),
+                state_hist_size: 0,
+                halt_height: 0,
+            },
+            InMemoryBackend::default(),
+            MemoryBlockstore::new(),
+            interpreter,
+            None,
+        )?
+        .with_checkpoint_signatures(checkpoint_signatures.clone(), None);
+
+        Ok(Self {
+            app,
+            provider,
+            votes,
+            checkpoint_signatures,
+            validator_key,
+            started: false,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_stm::{atomically, atomically_or_err};
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_topdown::proxy::{IPCProviderProxy, IPCProviderProxyWithLatency};
+use fendermint_vm_topdown::{BlockHeight, CachedFinalityProvider, IPCParentFinality, Toggle};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
+use ipc_provider::manager::mock::MockParent;
+use ipc_provider::manager::TopDownFinalityQuery;
+use ipc_provider::IpcProvider;
+
+use crate::node::InProcPeer;
+
+/// Chain ID of the in-memory parent the rootnet follows.
+pub const PARENT_CHAIN_ID: u64 = 31415926;
+/// Epoch of the parent in which the rootnet was created.
+pub const PARENT_GENESIS_EPOCH: ChainEpoch = 100;
+
+/// The parent finality provider the interpreter expects.
+pub(crate) type FinalityProvider = Arc<Toggle<CachedFinalityProvider<IPCProviderProxyWithLatency>>>;
+
+/// The ID of the rootnet of an in-process testnet, which is a child of the in-memory parent.
+pub(crate) fn rootnet_subnet_id(chain_id: u64) -> SubnetID {
+    SubnetID::new(PARENT_CHAIN_ID, vec![Address::new_id(chain_id)])
+}
+
+/// Create the in-memory parent of a subnet, and the finality the subnet starts from.
+pub(crate) async fn new_parent(
+    subnet_id: SubnetID,
+) -> anyhow::Result<(MockParent, IPCParentFinality)> {
+    let parent = MockParent::new(subnet_id, PARENT_GENESIS_EPOCH).with_chain_id(PARENT_CHAIN_ID);
+
+    let genesis_hash = TopDownFinalityQuery::get_block_hash(&parent, PARENT_GENESIS_EPOCH)
+        .await?
+        .block_hash;
+
+    let finality = IPCParentFinality::new(PARENT_GENESIS_EPOCH, genesis_hash);
+
+    Ok((parent, finality))
+}
+
+/// Create a parent finality provider starting from the genesis of the subnet.
+///
+/// The provider is filled by [sync_parent] instead of a syncer, so it never has to reach out to the parent.
+pub(crate) fn new_finality_provider(
+    subnet_id: &SubnetID,
+    genesis: &IPCParentFinality,
+) -> anyhow::Result<FinalityProvider> {
+    let config =
+        fendermint_vm_topdown::Config::new(1, Duration::from_secs(1), Duration::from_secs(1), 0);
+
+    let provider = CachedFinalityProvider::new(
+        config,
+        genesis.height,
+        Some(genesis.clone()),
+        Arc::new(offline_parent_proxy(subnet_id)?),
+    );
+
+    Ok(Arc::new(Toggle::enabled(provider)))
+}
+
+/// Let a node know about the blocks of the parent it hasn't seen yet, like its syncer would.
+///
+/// Reorgs on the parent are not followed.
+pub(crate) async fn sync_parent(parent: &MockParent, peer: &InProcPeer) -> anyhow::Result<()> {
+    let subnet_id = parent.child_subnet();
+    let from = atomically(|| peer.votes.latest_height()).await + 1;
+    let to = parent.head() as BlockHeight;
+
+    for height in from..=to {
+        let epoch = height as ChainEpoch;
+
+        // Every epoch up to the head exists, so failing to get the hash means it was a null round.
+        let payload = match TopDownFinalityQuery::get_block_hash(parent, epoch).await {
+            Err(_) => None,
+            Ok(res) => {
+                let msgs = parent.get_top_down_msgs(&subnet_id, epoch).await?.value;
+                let changes = parent
+                    .get_validator_changeset(&subnet_id, epoch)
+                    .await?
+                    .value;
+                Some((res.block_hash, changes, msgs))
+            }
+        };
+        let block_hash = payload.as_ref().map(|(hash, _, _)| hash.clone());
+
+        atomically_or_err(|| peer.provider.new_parent_view(height, payload.clone()))
+            .await
+            .map_err(|e| anyhow!("failed to add parent view: {e}"))?;
+
+        atomically_or_err(|| peer.votes.add_block(height, block_hash.clone()))
+            .await
+            .map_err(|e| anyhow!("failed to add parent block to the tally: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// A parent proxy which is never queried, because every parent block is put into the cache.
+///
+/// Creating it doesn't connect to anything.
+fn offline_parent_proxy(subnet_id: &SubnetID) -> anyhow::Result<IPCProviderProxyWithLatency> {
+    let parent = subnet_id
+        .parent()
+        .ok_or_else(|| anyhow!("subnet has no parent"))?;
+
+    let subnet = ipc_provider::config::Subnet {
+        id: parent,
+        config: SubnetConfig::Fevm(EVMSubnet {
+            provider_http: "http://127.0.0.1:8545".parse()?,
+            provider_timeout: None,
+            auth_token: None,
+            registry_addr: Address::from(EthAddress([0; 20])),
+            gateway_addr: Address::from(EthAddress([0; 20])),
+        }),
+    };
+
+    let ipc_provider = IpcProvider::new_with_subnet(None, subnet)?;
+    let proxy = IPCProviderProxy::new(ipc_provider, subnet_id.clone())?;
+
+    Ok(IPCProviderProxyWithLatency::new(proxy))
+}
@@ -0,0 +1,54 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::time::Duration;
+
+use async_stm::{atomically, atomically_or_err};
+use fendermint_vm_topdown::voting::ValidatorKey;
+
+use crate::node::InProcPeer;
+
+/// How long to wait for a tally which has paused voting while it is looking for a quorum.
+const VOTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Share the latest parent finality vote of every validator with every node.
+///
+/// This stands in for the gossip over the IPLD Resolver: each validator votes for
+/// the latest parent block it has seen, and the others add it to their tally.
+/// Votes the tallies reject, e.g. because they don't know the block yet, are ignored,
+/// just like a node would drop them after receiving them from the network.
+pub(crate) async fn gossip_votes<'a, I>(peers: I)
+where
+    I: Iterator<Item = &'a InProcPeer> + Clone,
+{
+    let mut votes = Vec::new();
+
+    for peer in peers.clone() {
+        let Some(ref sk) = peer.validator_key else {
+            continue;
+        };
+
+        let latest = atomically(|| {
+            let height = peer.votes.latest_height()?;
+            let hash = peer.votes.block_hash(height)?;
+            Ok(hash.map(|hash| (height, hash)))
+        })
+        .await;
+
+        if let Some((height, hash)) = latest {
+            votes.push((ValidatorKey::from(sk.public_key()), height, hash));
+        }
+    }
+
+    for peer in peers {
+        for (key, height, hash) in votes.iter() {
+            let add = atomically_or_err(|| peer.votes.add_vote(key.clone(), *height, hash.clone()));
+
+            match tokio::time::timeout(VOTE_TIMEOUT, add).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::debug!(error = e.to_string(), "vote ignored"),
+                Err(_) => tracing::debug!(height, "vote timed out"),
+            }
+        }
+    }
+}
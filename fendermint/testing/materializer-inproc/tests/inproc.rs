@@ -0,0 +1,452 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Multi-validator scenarios running entirely inside the test process.
+//!
+//! # Example
+//!
+//! `cargo test -p fendermint_materializer_inproc --test inproc -- --nocapture`
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use fendermint_abci::Application;
+use fendermint_materializer::{
+    manifest::{Manifest, Rootnet},
+    testnet::Testnet,
+    NodeName, ResourceId, TestnetName,
+};
+use fendermint_materializer_inproc::{
+    InProcChain, InProcMaterializer, InProcMaterials, PARENT_GENESIS_EPOCH,
+};
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_interpreter::fvm::state::ipc::{cross_msg_id, GatewayCaller};
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_message::query::{
+    FvmQuery, MessageTrace, TraceBlock, CHECKPOINT_SIGNATURES_QUERY_PATH,
+};
+use fendermint_vm_message::signed::SignedMessage;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::METHOD_SEND;
+use ipc_api::checkpoint::{CheckpointSignature, CheckpointSignatures};
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::manager::{SignedHeaderRelayer, SubnetManager};
+use tendermint::abci::{request, Event};
+
+type InProcTestnet = Testnet<InProcMaterials, InProcMaterializer>;
+
+/// Parse a manifest from the `tests/manifests` directory.
+fn read_manifest(file_name: &str) -> anyhow::Result<Manifest> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("manifests")
+        .join(file_name);
+    Manifest::from_file(&path)
+}
+
+/// Materialize a testnet in a temporary directory, after applying some changes to the manifest.
+async fn make_testnet<F>(
+    dir: &tempfile::TempDir,
+    manifest_file_name: &str,
+    alter: F,
+) -> anyhow::Result<InProcTestnet>
+where
+    F: FnOnce(&mut Manifest),
+{
+    let mut manifest = read_manifest(manifest_file_name)?;
+    alter(&mut manifest);
+
+    let name = TestnetName::new("inproc");
+    let mut materializer = InProcMaterializer::new(dir.path(), 0);
+
+    InProcTestnet::setup(&mut materializer, &name, &manifest)
+        .await
+        .context("failed to set up testnet")
+}
+
+fn chain(testnet: &InProcTestnet, node_id: &str) -> anyhow::Result<Arc<InProcChain>> {
+    let node_name: NodeName = testnet.root().node(node_id);
+    Ok(testnet.node(&node_name)?.chain().clone())
+}
+
+#[tokio::test]
+async fn test_proposers_rotate() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |_| {})
+        .await
+        .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+
+    assert_eq!(chain.validators().await.len(), 4);
+
+    let height = chain.produce_blocks(8).await.unwrap();
+    assert_eq!(height, 8);
+
+    let mut proposers = BTreeSet::new();
+    for h in 1..=height {
+        let block = chain.block(h).await.expect("block committed");
+        proposers.insert(block.header.proposer_address);
+    }
+
+    assert_eq!(proposers.len(), 4, "every validator should propose");
+}
+
+#[tokio::test]
+async fn test_offline_validator_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |m| {
+        let Rootnet::New { ref mut nodes, .. } = m.rootnet;
+        nodes.remove(&ResourceId::from("dave"));
+    })
+    .await
+    .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+
+    // Dave still has power but isn't around to propose or vote;
+    // the other three have enough power to carry on.
+    assert_eq!(chain.validators().await.len(), 4);
+
+    let height = chain.produce_blocks(8).await.unwrap();
+    assert_eq!(height, 8);
+
+    let mut proposers = BTreeSet::new();
+    for h in 1..=height {
+        let block = chain.block(h).await.expect("block committed");
+        proposers.insert(block.header.proposer_address);
+    }
+
+    assert_eq!(
+        proposers.len(),
+        3,
+        "the offline validator should be skipped"
+    );
+}
+
+#[tokio::test]
+async fn test_topdown_finality() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |_| {})
+        .await
+        .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+    let parent = chain.parent();
+
+    let sender = Address::new_id(100);
+    let recipient = Address::from(EthAddress([0xaa; 20]));
+    let amount = TokenAmount::from_whole(1);
+
+    parent.credit(sender, TokenAmount::from_whole(10));
+    parent
+        .fund(
+            chain.subnet_id().clone(),
+            Address::new_id(64),
+            sender,
+            recipient,
+            amount.clone(),
+        )
+        .await
+        .unwrap();
+
+    let fund_height = parent.produce_block();
+    parent.produce_blocks(2);
+
+    // The nodes see the parent blocks and vote on them, then one of them proposes the finality.
+    chain.produce_blocks(4).await.unwrap();
+
+    let node_name: NodeName = testnet.root().node("full");
+    let app = testnet.node(&node_name).unwrap().app().await.unwrap();
+    let mut state = app
+        .read_only_view(None)
+        .unwrap()
+        .expect("state should be committed");
+
+    let finality = GatewayCaller::default()
+        .get_latest_parent_finality(&mut state)
+        .unwrap();
+
+    assert!(finality.height > PARENT_GENESIS_EPOCH as u64);
+    assert!(finality.height >= fund_height as u64);
+
+    let tree = state.state_tree();
+    let id = tree
+        .lookup_id(&recipient)
+        .unwrap()
+        .expect("funded account should exist");
+    let actor = tree.get_actor(id).unwrap().expect("actor should exist");
+    assert_eq!(actor.balance, amount);
+}
+
+/// Find the value of an attribute of the receipt event of a top-down message.
+fn topdown_attr<'a>(events: &'a [Event], id: &str, key: &str) -> Option<&'a str> {
+    events
+        .iter()
+        .filter(|e| e.kind == "topdown")
+        .find(|e| e.attributes.iter().any(|a| a.key == "id" && a.value == id))
+        .and_then(|e| e.attributes.iter().find(|a| a.key == key))
+        .map(|a| a.value.as_str())
+}
+
+#[tokio::test]
+async fn test_failed_topdown_message_gets_a_receipt() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |_| {})
+        .await
+        .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+    let parent = chain.parent();
+
+    // A message the gateway reverts on, because it is not travelling top-down.
+    let sibling = SubnetID::new_from_parent(
+        &chain.subnet_id().parent().unwrap(),
+        Address::from(EthAddress([0xee; 20])),
+    );
+    let mut invalid = IpcEnvelope::new_release_msg(
+        &sibling,
+        &Address::from(EthAddress([0x02; 20])),
+        &Address::from(EthAddress([0x03; 20])),
+        TokenAmount::from_whole(0),
+    )
+    .unwrap();
+    parent.send_top_down_msg(chain.subnet_id(), invalid.clone());
+    invalid.local_nonce = 0;
+
+    let sender = Address::new_id(100);
+    let recipient = Address::from(EthAddress([0xaa; 20]));
+    let amount = TokenAmount::from_whole(1);
+
+    parent.credit(sender, TokenAmount::from_whole(10));
+    parent
+        .fund(
+            chain.subnet_id().clone(),
+            Address::new_id(64),
+            sender,
+            recipient,
+            amount.clone(),
+        )
+        .await
+        .unwrap();
+
+    parent.produce_blocks(3);
+    let height = chain.produce_blocks(4).await.unwrap();
+
+    let mut events = Vec::new();
+    for h in 1..=height {
+        let block = chain.block(h).await.expect("block committed");
+        for res in block.tx_results {
+            events.extend(res.events);
+        }
+    }
+
+    let id = hex::encode(cross_msg_id(&invalid).unwrap());
+    let exit_code = topdown_attr(&events, &id, "exit_code").expect("receipt of the failed message");
+    assert_ne!(exit_code, "0", "the message should fail");
+    assert_eq!(topdown_attr(&events, &id, "nonce"), Some("0"));
+
+    // The failure did not abort the batch, so the transfer after it went through.
+    let node_name: NodeName = testnet.root().node("full");
+    let app = testnet.node(&node_name).unwrap().app().await.unwrap();
+    let mut state = app
+        .read_only_view(None)
+        .unwrap()
+        .expect("state should be committed");
+
+    assert_eq!(
+        GatewayCaller::default()
+            .applied_top_down_nonce(&mut state)
+            .unwrap(),
+        2
+    );
+
+    let tree = state.state_tree();
+    let id = tree
+        .lookup_id(&recipient)
+        .unwrap()
+        .expect("funded account should exist");
+    let actor = tree.get_actor(id).unwrap().expect("actor should exist");
+    assert_eq!(actor.balance, amount);
+}
+
+#[tokio::test]
+async fn test_trace_replays_block_over_previous_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |_| {})
+        .await
+        .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+    let alice = testnet.account("alice").unwrap();
+    let bob = testnet.account("bob").unwrap();
+
+    let node_name: NodeName = testnet.root().node("full");
+    let node = testnet.node(&node_name).unwrap();
+    let chain_id = node
+        .app()
+        .await
+        .unwrap()
+        .read_only_view(None)
+        .unwrap()
+        .expect("state should be committed")
+        .chain_id();
+
+    let transfer = |sequence| {
+        let msg = Message {
+            version: 0,
+            from: alice.fvm_addr(),
+            to: bob.fvm_addr(),
+            sequence,
+            value: TokenAmount::from_whole(1),
+            method_num: METHOD_SEND,
+            params: RawBytes::default(),
+            gas_limit: 10_000_000,
+            gas_fee_cap: TokenAmount::from_atto(0),
+            gas_premium: TokenAmount::from_atto(0),
+        };
+        let msg = SignedMessage::new_secp256k1(msg, alice.secret_key(), &chain_id).unwrap();
+        fvm_ipld_encoding::to_vec(&ChainMessage::Signed(msg)).unwrap()
+    };
+
+    // The second transfer can only be applied after the nonce was bumped by the first one.
+    for sequence in 0..2 {
+        let res = chain.submit(transfer(sequence)).await.unwrap();
+        assert!(
+            res.code.is_ok(),
+            "transfer should be accepted: {}",
+            res.info
+        );
+        chain.produce_block().await.unwrap();
+    }
+
+    let height = chain.height().await;
+    let block = chain.block(height).await.expect("block committed");
+
+    let trace_block = TraceBlock {
+        height: height as i64,
+        timestamp: block.header.time.unix_timestamp() as u64,
+        block_hash: match block.header.hash() {
+            tendermint::Hash::Sha256(hash) => Some(hash),
+            tendermint::Hash::None => None,
+        },
+        messages: block
+            .txs
+            .iter()
+            .map(|tx| fvm_ipld_encoding::from_slice::<ChainMessage>(tx).unwrap())
+            .collect(),
+    };
+
+    // Query at the same height as the block, the way the `trace_*` methods of the Ethereum API do.
+    let res = node
+        .app()
+        .await
+        .unwrap()
+        .query(request::Query {
+            data: fvm_ipld_encoding::to_vec(&FvmQuery::Trace(Box::new(trace_block)))
+                .unwrap()
+                .into(),
+            path: String::new(),
+            height: height.try_into().unwrap(),
+            prove: false,
+        })
+        .await
+        .unwrap();
+
+    assert!(res.code.is_ok(), "trace query failed: {}", res.info);
+
+    let traces: Vec<MessageTrace> = fvm_ipld_encoding::from_slice(&res.value).unwrap();
+    assert_eq!(traces.len(), 1);
+    assert!(
+        traces[0].exit_code.is_success(),
+        "the replayed transfer should see the nonce left by the previous block: {}",
+        traces[0].info
+    );
+}
+
+#[tokio::test]
+async fn test_bottom_up_checkpoint_is_signed_and_relayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let testnet = make_testnet(&dir, "four-validators.yaml", |_| {})
+        .await
+        .unwrap();
+
+    let chain = chain(&testnet, "alice").unwrap();
+
+    // Every height is a checkpoint height in the genesis of the materializer.
+    let height = chain.produce_blocks(2).await.unwrap();
+    let block = chain.block(height).await.expect("block committed");
+
+    // Query the signatures from a full node, the way a relayer would.
+    let node_name: NodeName = testnet.root().node("full");
+    let res = testnet
+        .node(&node_name)
+        .unwrap()
+        .app()
+        .await
+        .unwrap()
+        .query(request::Query {
+            data: fvm_ipld_encoding::to_vec(&height).unwrap().into(),
+            path: CHECKPOINT_SIGNATURES_QUERY_PATH.to_string(),
+            height: Default::default(),
+            prove: false,
+        })
+        .await
+        .unwrap();
+
+    assert!(res.code.is_ok(), "signatures query failed: {}", res.info);
+
+    let mut groups: Vec<CheckpointSignatures> = fvm_ipld_encoding::from_slice(&res.value).unwrap();
+    assert_eq!(
+        groups.len(),
+        1,
+        "the validators should sign the same app hash"
+    );
+
+    let signatures = groups.remove(0);
+    assert_eq!(signatures.height, height);
+    assert_eq!(signatures.app_hash, block.app_hash.as_bytes());
+
+    let validators = ["alice", "bob", "charlie", "dave"]
+        .into_iter()
+        .map(|name| ethers::types::Address::from(testnet.account(name).unwrap().eth_addr()))
+        .collect::<BTreeSet<_>>();
+
+    let signers = signatures
+        .signatures
+        .iter()
+        .map(|s| {
+            let signature = CheckpointSignature {
+                height,
+                app_hash: signatures.app_hash.clone(),
+                signature: s.signature.clone(),
+            };
+            let signer = signature.recover(chain.subnet_id()).unwrap();
+            assert_eq!(signer, s.validator, "signature should be by the validator");
+            signer
+        })
+        .collect::<BTreeSet<_>>();
+
+    assert_eq!(signers, validators);
+
+    // Relay the checkpoint to the parent, which includes it in its next block.
+    let parent = chain.parent();
+    let alice = testnet.account("alice").unwrap();
+
+    parent
+        .submit_checkpoint_signatures(&alice.fvm_addr(), chain.subnet_id(), signatures)
+        .await
+        .unwrap();
+
+    parent.produce_block();
+
+    assert_eq!(
+        parent.last_bottom_up_checkpoint_height(chain.subnet_id()),
+        height
+    );
+}
@@ -322,6 +322,7 @@ impl DockerMaterializer {
             IpcCliConfig {
                 keystore_path: Some("~/.ipc".to_string()),
                 subnets: Default::default(),
+                remote_signers: Default::default(),
             }
         } else {
             IpcCliConfig::from_file(&file_name).context("failed to read ipc-cli config")?
@@ -1120,6 +1121,7 @@ mod tests {
         let mut config0 = IpcCliConfig {
             keystore_path: Some("~/.ipc".to_string()),
             subnets: Default::default(),
+            remote_signers: Default::default(),
         };
 
         config0.add_subnet(IpcCliSubnet {
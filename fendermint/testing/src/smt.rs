@@ -79,6 +79,32 @@ pub fn run<T: StateMachine>(
     Ok(())
 }
 
+/// Run a state machine test on the input of a fuzzer, e.g. `cargo fuzz`.
+///
+/// Unlike [run], it stops at the end of the input rather than panicking,
+/// because the fuzzer decides how much data to give us, not the test.
+pub fn run_fuzz<T: StateMachine>(
+    u: &mut Unstructured,
+    t: &T,
+    max_steps: usize,
+) -> arbitrary::Result<()> {
+    let mut state = t.gen_state(u)?;
+    let mut system = t.new_system(&state);
+    for _ in 0..max_steps {
+        if u.is_empty() {
+            break;
+        }
+        let cmd = t.gen_command(u, &state)?;
+        let res = t.run_command(&mut system, &cmd);
+        t.check_result(&cmd, &state, res);
+        state = t.next_state(&cmd, state);
+        if !t.check_system(&cmd, &state, &system) {
+            break;
+        }
+    }
+    Ok(())
+}
+
 /// Once we run out of randomness, most of the arbitrary data generated by it will
 /// be zeroes, which is is not very realistic. Calling this method can highlight
 /// this and give us a chance to adjust the min/max size of the builder.
@@ -80,6 +80,7 @@ impl State {
                 vec![acc.owner.0]
             }
             ActorMeta::Multisig(ms) => ms.signers.iter().map(|a| a.0).collect(),
+            ActorMeta::Contract(c) => vec![Address::from(EthAddress::from(c.address))],
         });
 
         let mut next_id = FIRST_NON_SINGLETON_ADDR;
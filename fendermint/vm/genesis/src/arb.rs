@@ -1,7 +1,7 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use crate::{
-    ipc, Account, Actor, ActorMeta, Collateral, Genesis, Multisig, PermissionMode, Power,
+    ipc, Account, Actor, ActorMeta, Collateral, Contract, Genesis, Multisig, PermissionMode, Power,
     SignerAddr, Validator, ValidatorKey,
 };
 use fendermint_crypto::SecretKey;
@@ -15,7 +15,9 @@ use rand::{rngs::StdRng, SeedableRng};
 impl Arbitrary for ActorMeta {
     fn arbitrary(g: &mut Gen) -> Self {
         // Generate keys which the loader knows how to initialize.
-        if bool::arbitrary(g) {
+        if u8::arbitrary(g) % 5 == 0 {
+            ActorMeta::Contract(Contract::arbitrary(g))
+        } else if bool::arbitrary(g) {
             let pk = ValidatorKey::arbitrary(g).0;
             let pk = pk.serialize();
             let addr = if bool::arbitrary(g) {
@@ -50,6 +52,33 @@ impl Arbitrary for ActorMeta {
     }
 }
 
+impl Arbitrary for Contract {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut bytes = [0u8; 20];
+        bytes.fill_with(|| u8::arbitrary(g));
+        // Avoid the `0xff00..` prefix of masked ID addresses.
+        bytes[0] = bytes[0].min(0xfe);
+        // Avoid the 0xEF prefix, which the EVM rejects as deployed code.
+        let code = Vec::<u8>::arbitrary(g)
+            .into_iter()
+            .skip_while(|b| *b == 0xef)
+            .collect::<Vec<_>>();
+        let storage = (0..u8::arbitrary(g) % 3)
+            .map(|_| {
+                let k: [u8; 32] = std::array::from_fn(|_| u8::arbitrary(g));
+                let v: [u8; 32] = std::array::from_fn(|_| u8::arbitrary(g));
+                (k.into(), v.into())
+            })
+            .collect();
+        Self {
+            address: ethers::types::Address::from_slice(&bytes),
+            code: code.into(),
+            storage,
+            nonce: u64::arbitrary(g) % 100,
+        }
+    }
+}
+
 impl Arbitrary for Actor {
     fn arbitrary(g: &mut Gen) -> Self {
         Self {
@@ -3,6 +3,8 @@
 //! A Genesis data structure similar to [genesis.Template](https://github.com/filecoin-project/lotus/blob/v1.20.4/genesis/types.go)
 //! in Lotus, which is used to [initialize](https://github.com/filecoin-project/lotus/blob/v1.20.4/chain/gen/genesis/genesis.go) the state tree.
 
+use std::collections::BTreeMap;
+
 use anyhow::anyhow;
 use fvm_shared::bigint::{BigInt, Integer};
 use serde::{Deserialize, Serialize};
@@ -95,10 +97,27 @@ pub struct Multisig {
     pub vesting_start: u64,
 }
 
+/// An EVM contract which exists from genesis at a fixed Ethereum address,
+/// similar to the entries in the `alloc` section of a geth genesis file.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Contract {
+    /// The Ethereum address the contract is reachable at.
+    pub address: ethers::types::Address,
+    /// The deployed (runtime) bytecode of the contract, not the initcode.
+    pub code: ethers::types::Bytes,
+    /// Initial values of the storage slots; slots not listed are zero.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<ethers::types::H256, ethers::types::H256>,
+    /// The nonce the contract uses to derive the addresses of the contracts it creates.
+    #[serde(default)]
+    pub nonce: u64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ActorMeta {
     Account(Account),
     Multisig(Multisig),
+    Contract(Contract),
 }
 
 #[serde_as]
@@ -0,0 +1,657 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Execution of the bottom-up message batches of child subnets, resolved by CID.
+//!
+//! At every checkpoint with cross messages the child subnet stores the ABI encoded batch as a
+//! raw IPLD block, and its validators announce the CID to the parent subnet, signed with their
+//! validator key. The parent only adds announcements signed by an active validator of the child
+//! subnet to the [`ResolvePool`], from where the IPLD Resolver fetches the content from the child
+//! subnet through Bitswap. Once the content is resolved and the subnet actor has a pending
+//! commitment for the batch, the proposer includes an [`IpcMessage::BottomUpExec`], which
+//! executes the messages with inclusion proofs generated from the content.
+//!
+//! Validators only propose and accept batches they have resolved themselves, so that executing
+//! the block does not have to wait for the network. A node which doesn't have the content of a
+//! committed batch, e.g. because it runs without the IPLD Resolver, fails the message with a
+//! receipt instead of stopping. Batches at heights the subnet actor has already moved past are
+//! evicted from the pool, whether they were resolved or not.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Context};
+use async_stm::atomically;
+use cid::Cid;
+use ethers::abi::{AbiType, Detokenize, ParamType, Tokenizable, Tokenize};
+use ethers::utils::keccak256;
+use fendermint_crypto::SecretKey;
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_message::ipc::{BottomUpBatchRef, IpcMessage};
+use fendermint_vm_resolver::pool::{ResolveKey, ResolvePool};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::checkpointing_facet::IpcEnvelope;
+use ipc_actors_abis::subnet_actor_checkpointing_facet::{
+    self as subnet_checkpointing, Inclusion, SubnetActorCheckpointingFacet,
+};
+use ipc_actors_abis::subnet_actor_getter_facet::SubnetActorGetterFacet;
+use ipc_api::checkpoint::{abi_encode_envelope, abi_encode_envelope_fields};
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::merkle::MerkleGen;
+use ipc_api::subnet_id::SubnetID;
+use multihash_codetable::{Code, MultihashDigest};
+use serde::{Deserialize, Serialize};
+
+use super::state::fevm::{ContractCaller, MockProvider, NoRevert};
+use super::state::FvmExecState;
+use crate::errors::ApplyMessageError;
+use crate::types::AppliedMessage;
+
+/// Content the parent subnet has to resolve from its children before it can execute it.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ResolvePoolItem {
+    BottomUpBatch(BottomUpBatchRef),
+}
+
+impl From<&ResolvePoolItem> for ResolveKey {
+    fn from(value: &ResolvePoolItem) -> Self {
+        match value {
+            ResolvePoolItem::BottomUpBatch(b) => (b.subnet_id.clone(), b.cid),
+        }
+    }
+}
+
+pub type BottomUpPool = ResolvePool<ResolvePoolItem>;
+
+/// The bottom-up messages of a checkpoint, encoded as the raw IPLD block the parent resolves.
+#[derive(Debug, Clone)]
+pub struct BottomUpBatch {
+    pub height: ChainEpoch,
+    pub cid: Cid,
+    pub data: Vec<u8>,
+}
+
+impl BottomUpBatch {
+    pub fn new(height: ChainEpoch, msgs: &[IpcEnvelope]) -> Self {
+        let data = ethers::abi::encode(&[msgs.to_vec().into_token()]);
+        let cid = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, Code::Blake2b256.digest(&data));
+        Self { height, cid, data }
+    }
+
+    /// Reference to the batch to announce to the parent subnet.
+    pub fn to_ref(&self, subnet_id: SubnetID) -> BottomUpBatchRef {
+        BottomUpBatchRef {
+            subnet_id,
+            height: self.height,
+            cid: self.cid,
+        }
+    }
+}
+
+/// A [`BottomUpBatchRef`] announced by a validator of the child subnet, signed with its
+/// validator key, so the parent only spends resources on batches vouched for by the child.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignedBottomUpBatchRef {
+    pub batch: BottomUpBatchRef,
+    /// Recoverable secp256k1 signature over the digest of the batch reference, with `v` in 27/28.
+    pub signature: Vec<u8>,
+}
+
+impl SignedBottomUpBatchRef {
+    pub fn sign(batch: BottomUpBatchRef, secret_key: &SecretKey) -> anyhow::Result<Self> {
+        let digest = announcement_digest(&batch)?;
+        let (sig, recovery_id) = secret_key.sign(&digest);
+
+        let mut signature = sig.serialize().to_vec();
+        signature.push(recovery_id.serialize() + 27);
+
+        Ok(Self { batch, signature })
+    }
+
+    /// Recover the Ethereum address of the validator who signed the announcement.
+    pub fn recover(&self) -> anyhow::Result<EthAddress> {
+        let digest = announcement_digest(&self.batch)?;
+        let signature = ethers::types::Signature::try_from(self.signature.as_slice())?;
+        let signer = signature.recover(ethers::types::H256::from(digest))?;
+        Ok(EthAddress::from(signer))
+    }
+}
+
+fn announcement_digest(batch: &BottomUpBatchRef) -> anyhow::Result<[u8; 32]> {
+    Ok(keccak256(fvm_ipld_encoding::to_vec(batch)?))
+}
+
+/// Check that an announcement is signed by an active validator of the child subnet,
+/// according to its subnet actor in the state, returning the signer.
+pub fn check_announcement<DB>(
+    state: &mut FvmExecState<DB>,
+    announcement: &SignedBottomUpBatchRef,
+) -> anyhow::Result<EthAddress>
+where
+    DB: Blockstore + Clone + 'static,
+{
+    let signer = announcement.recover().context("failed to recover signer")?;
+
+    let addr = subnet_actor_addr(&announcement.batch.subnet_id)?;
+    let getter = SubnetGetterCaller::new(addr, SubnetActorGetterFacet::new);
+
+    if !getter.call(state, |c| c.is_active_validator(signer.into()))? {
+        bail!("announcement signed by inactive validator {signer:?}");
+    }
+
+    Ok(signer)
+}
+
+/// Decode the messages of a batch from the content of the IPLD block.
+pub fn decode_bottom_up_batch(data: &[u8]) -> anyhow::Result<Vec<IpcEnvelope>> {
+    let param = ParamType::Array(Box::new(IpcEnvelope::param_type()));
+    let mut tokens = ethers::abi::decode(&[param], data).context("invalid batch encoding")?;
+    let token = tokens
+        .pop()
+        .ok_or_else(|| anyhow!("empty batch encoding"))?;
+    Ok(Vec::<IpcEnvelope>::from_token(token)?)
+}
+
+#[derive(Clone)]
+pub struct BottomUpManager<DB>
+where
+    DB: Blockstore + Clone + 'static + Send + Sync,
+{
+    pool: BottomUpPool,
+    /// The store the IPLD Resolver writes the resolved content to.
+    ///
+    /// It must be kept separate from the state store; we only read from it when executing
+    /// a batch that the validators have agreed they all have.
+    content_store: DB,
+}
+
+impl<DB> BottomUpManager<DB>
+where
+    DB: Blockstore + Clone + 'static + Send + Sync,
+{
+    pub fn new(pool: BottomUpPool, content_store: DB) -> Self {
+        Self {
+            pool,
+            content_store,
+        }
+    }
+
+    /// Add a batch announced by an active validator of the child subnet to the pool,
+    /// to start resolving it in the background.
+    ///
+    /// The announcement is expected to have been checked with [`check_announcement`].
+    pub async fn add_announced(&self, batch: BottomUpBatchRef) {
+        let item = ResolvePoolItem::BottomUpBatch(batch);
+        atomically(|| self.pool.add(item.clone(), false)).await;
+    }
+
+    /// Evict every batch, resolved or not, at a height the subnet actor has already moved past,
+    /// e.g. because a relayer executed it, or because it was never committed to.
+    ///
+    /// Batches of subnets without a subnet actor we can query are evicted as well.
+    pub async fn evict_stale<S>(&self, state: &mut FvmExecState<S>)
+    where
+        S: Blockstore + Clone + 'static,
+    {
+        let items = atomically(|| self.pool.collect_all()).await;
+
+        let subnet_ids = items
+            .iter()
+            .map(|ResolvePoolItem::BottomUpBatch(b)| b.subnet_id.clone())
+            .collect::<HashSet<_>>();
+
+        let mut commitments = HashMap::new();
+        for subnet_id in subnet_ids {
+            match pending_commitments(state, &subnet_id) {
+                Ok(c) => {
+                    commitments.insert(subnet_id, c);
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        error = e.to_string(),
+                        subnet_id = subnet_id.to_string(),
+                        "evicting bottom-up batches of subnet without pending commitments"
+                    );
+                }
+            }
+        }
+
+        atomically(|| {
+            self.pool.retain(|ResolvePoolItem::BottomUpBatch(b)| {
+                let height = b.height as u64;
+                match commitments.get(&b.subnet_id) {
+                    Some((pending, last_height)) => {
+                        height > *last_height || pending.contains_key(&height)
+                    }
+                    None => false,
+                }
+            })
+        })
+        .await;
+    }
+
+    /// Propose the execution of every resolved batch which the subnet actor has a pending
+    /// commitment for, and evict the ones which have already been executed, e.g. by a relayer,
+    /// or which don't match the commitment.
+    pub async fn chain_messages_from_resolved<S>(
+        &self,
+        state: &mut FvmExecState<S>,
+    ) -> Vec<ChainMessage>
+    where
+        S: Blockstore + Clone + 'static,
+    {
+        self.evict_stale(state).await;
+
+        let resolved = atomically(|| self.pool.collect_resolved()).await;
+
+        let mut by_subnet = HashMap::<SubnetID, Vec<BottomUpBatchRef>>::new();
+        for ResolvePoolItem::BottomUpBatch(batch) in resolved {
+            by_subnet
+                .entry(batch.subnet_id.clone())
+                .or_default()
+                .push(batch);
+        }
+
+        let mut msgs = Vec::new();
+        let mut evicted = Vec::new();
+
+        for (subnet_id, batches) in by_subnet {
+            let (pending, last_height) = match pending_commitments(state, &subnet_id) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!(
+                        error = e.to_string(),
+                        subnet_id = subnet_id.to_string(),
+                        "failed to list pending bottom-up batch commitments"
+                    );
+                    continue;
+                }
+            };
+
+            for batch in batches {
+                let height = batch.height as u64;
+                match pending.get(&height) {
+                    Some(msgs_root) => match self.batch_root(&batch) {
+                        Ok(root) if root == *msgs_root => {
+                            msgs.push(ChainMessage::Ipc(IpcMessage::BottomUpExec(batch)));
+                        }
+                        Ok(_) => {
+                            tracing::warn!(
+                                subnet_id = subnet_id.to_string(),
+                                height,
+                                cid = batch.cid.to_string(),
+                                "resolved bottom-up batch does not match the commitment"
+                            );
+                            evicted.push(ResolvePoolItem::BottomUpBatch(batch));
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                error = e.to_string(),
+                                cid = batch.cid.to_string(),
+                                "failed to load resolved bottom-up batch"
+                            );
+                            evicted.push(ResolvePoolItem::BottomUpBatch(batch));
+                        }
+                    },
+                    None if height <= last_height => {
+                        evicted.push(ResolvePoolItem::BottomUpBatch(batch));
+                    }
+                    // The relayer hasn't recorded the commitment yet.
+                    None => {}
+                }
+            }
+        }
+
+        if !evicted.is_empty() {
+            atomically(|| {
+                for item in evicted.iter() {
+                    self.pool.remove(item)?;
+                }
+                Ok(())
+            })
+            .await;
+        }
+
+        // Execute older batches first.
+        msgs.sort_by_key(|msg| match msg {
+            ChainMessage::Ipc(IpcMessage::BottomUpExec(b)) => b.height,
+            _ => 0,
+        });
+
+        msgs
+    }
+
+    /// Check that we have resolved the content of a proposed batch.
+    ///
+    /// Proposals can only refer to batches announced by the validators of the child subnet;
+    /// anything else is rejected without adding it to the pool.
+    pub async fn is_batch_resolved(&self, batch: BottomUpBatchRef) -> bool {
+        let cid = batch.cid;
+        let item = ResolvePoolItem::BottomUpBatch(batch);
+        let is_resolved = atomically(|| match self.pool.get_status(&item)? {
+            Some(status) => status.is_resolved(),
+            None => Ok(false),
+        })
+        .await;
+        is_resolved && self.content_store.has(&cid).unwrap_or_default()
+    }
+
+    /// Execute the messages of a batch through the subnet actor, then evict it from the pool.
+    ///
+    /// The batch is part of the ledger by now, so nothing about it may stop the node; if we
+    /// don't have its content, e.g. because we rejected the proposal, the message fails.
+    pub async fn execute_bottom_up_batch(
+        &self,
+        state: &mut FvmExecState<DB>,
+        batch: BottomUpBatchRef,
+    ) -> Result<AppliedMessage, ApplyMessageError> {
+        let result = self
+            .load_committed_batch(&batch)
+            .and_then(|msgs| execute_batch(state, &batch, msgs));
+
+        let item = ResolvePoolItem::BottomUpBatch(batch);
+        atomically(|| self.pool.remove(&item)).await;
+
+        result
+    }
+
+    /// Execute the messages of a committed batch on a state that is never committed, such as one
+    /// used for tracing, without resolving the batch or touching the pool.
+    pub fn replay_bottom_up_batch<S>(
+        &self,
+        state: &mut FvmExecState<S>,
+        batch: BottomUpBatchRef,
+    ) -> Result<AppliedMessage, ApplyMessageError>
+    where
+        S: Blockstore + Clone + 'static,
+    {
+        let msgs = self.load_committed_batch(&batch)?;
+        execute_batch(state, &batch, msgs)
+    }
+
+    /// Load the messages of a batch committed in a block from the content store, failing the
+    /// message if we don't have them.
+    fn load_committed_batch(
+        &self,
+        batch: &BottomUpBatchRef,
+    ) -> Result<Vec<IpcEnvelope>, ApplyMessageError> {
+        self.load_batch(batch).map_err(|e| {
+            ApplyMessageError::InvalidMessage(format!("cannot execute bottom-up batch: {e:#}"))
+        })
+    }
+
+    fn load_batch(&self, batch: &BottomUpBatchRef) -> anyhow::Result<Vec<IpcEnvelope>> {
+        let data = self
+            .content_store
+            .get(&batch.cid)?
+            .ok_or_else(|| anyhow!("bottom-up batch {} not found in the store", batch.cid))?;
+
+        decode_bottom_up_batch(&data)
+    }
+
+    fn batch_root(&self, batch: &BottomUpBatchRef) -> anyhow::Result<[u8; 32]> {
+        let msgs = self.load_batch(batch)?;
+        let merkle = MerkleGen::new(
+            abi_encode_envelope,
+            msgs.as_slice(),
+            &abi_encode_envelope_fields(),
+        )?;
+        Ok(merkle.root().to_fixed_bytes())
+    }
+}
+
+type SubnetGetterCaller<DB> = ContractCaller<DB, SubnetActorGetterFacet<MockProvider>, NoRevert>;
+
+type SubnetCheckpointingCaller<DB> = ContractCaller<
+    DB,
+    SubnetActorCheckpointingFacet<MockProvider>,
+    subnet_checkpointing::SubnetActorCheckpointingFacetErrors,
+>;
+
+/// Execute the batch through the subnet actor.
+///
+/// Anything wrong with the batch itself, including the subnet actor not existing,
+/// is reported as an invalid message rather than a failure of the node.
+fn execute_batch<DB>(
+    state: &mut FvmExecState<DB>,
+    batch: &BottomUpBatchRef,
+    msgs: Vec<IpcEnvelope>,
+) -> Result<AppliedMessage, ApplyMessageError>
+where
+    DB: Blockstore + Clone + 'static,
+{
+    let invalid = ApplyMessageError::InvalidMessage;
+
+    let addr = subnet_actor_addr(&batch.subnet_id).map_err(|e| invalid(e.to_string()))?;
+    let getter = SubnetGetterCaller::new(addr, SubnetActorGetterFacet::new);
+    let checkpointing = SubnetCheckpointingCaller::new(addr, SubnetActorCheckpointingFacet::new);
+
+    let height = batch.height as u64;
+
+    let executed = getter
+        .try_call(state, |c| c.list_pending_bottom_up_batch_commitments())
+        .map_err(|e| invalid(format!("{e:#}")))?
+        .map_err(|e| invalid(format!("failed to list pending commitments: {:?}", e.error)))?
+        .into_iter()
+        .find(|c| c.height == height)
+        .map(|c| c.executed)
+        .ok_or_else(|| {
+            invalid(format!(
+                "no pending bottom-up batch commitment at height {height}"
+            ))
+        })?;
+
+    let inclusions = make_inclusions(msgs, &executed).map_err(|e| invalid(format!("{e:#}")))?;
+
+    tracing::debug!(
+        subnet_id = batch.subnet_id.to_string(),
+        height,
+        inclusions = inclusions.len(),
+        "executing bottom-up batch"
+    );
+
+    match checkpointing
+        .try_call_with_ret(state, |c| c.exec_bottom_up_msg_batch(height, inclusions))
+        .map_err(|e| invalid(format!("{e:#}")))?
+    {
+        Ok(ret) => Ok(ret.into_return()),
+        Err(e) => Err(invalid(format!(
+            "failed to execute bottom-up batch: {:?}",
+            e.error
+        ))),
+    }
+}
+
+/// Create inclusion proofs for the messages in the batch which haven't been executed yet.
+fn make_inclusions(
+    msgs: Vec<IpcEnvelope>,
+    executed: &[[u8; 32]],
+) -> anyhow::Result<Vec<Inclusion>> {
+    let merkle = MerkleGen::new(
+        abi_encode_envelope,
+        msgs.as_slice(),
+        &abi_encode_envelope_fields(),
+    )?;
+
+    let mut inclusions = Vec::new();
+    for msg in msgs.iter() {
+        let leaf_hash = merkle.leaf_hash(&abi_encode_envelope(msg))?;
+        if executed.contains(&leaf_hash.0) {
+            continue;
+        }
+        let proof = merkle.get_proof(msg)?;
+        let msg = subnet_checkpointing::IpcEnvelope::from_tokens(msg.clone().into_tokens())?;
+        inclusions.push(Inclusion {
+            msg,
+            proof: proof.into_iter().map(|v| v.into()).collect(),
+        });
+    }
+    Ok(inclusions)
+}
+
+/// Pending batch commitments of a child subnet by height, and the last height with a commitment.
+fn pending_commitments<DB>(
+    state: &mut FvmExecState<DB>,
+    subnet_id: &SubnetID,
+) -> anyhow::Result<(HashMap<u64, [u8; 32]>, u64)>
+where
+    DB: Blockstore + Clone + 'static,
+{
+    let addr = subnet_actor_addr(subnet_id)?;
+    let getter = SubnetGetterCaller::new(addr, SubnetActorGetterFacet::new);
+    let checkpointing = SubnetCheckpointingCaller::new(addr, SubnetActorCheckpointingFacet::new);
+
+    let pending = getter
+        .call(state, |c| c.list_pending_bottom_up_batch_commitments())?
+        .into_iter()
+        .map(|c| (c.height, c.commitment.msgs_root))
+        .collect();
+
+    let last_height = checkpointing
+        .call(state, |c| c.last_commitment_height())?
+        .as_u64();
+
+    Ok((pending, last_height))
+}
+
+fn subnet_actor_addr(subnet_id: &SubnetID) -> anyhow::Result<EthAddress> {
+    if subnet_id.is_root() {
+        return Err(anyhow!("{subnet_id} has no subnet actor"));
+    }
+    let addr = payload_to_evm_address(subnet_id.subnet_actor().payload())?;
+    Ok(EthAddress::from(addr))
+}
+
+#[cfg(test)]
+mod tests {
+    use fendermint_crypto::SecretKey;
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use fendermint_vm_message::ipc::BottomUpBatchRef;
+    use fendermint_vm_resolver::pool::ResolvePool;
+    use fvm_ipld_blockstore::Blockstore;
+    use ipc_actors_abis::checkpointing_facet::{FvmAddress, IpcEnvelope, Ipcaddress, SubnetID};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::errors::ApplyMessageError;
+    use crate::fvm::store::memory::MemoryBlockstore;
+
+    use super::{
+        decode_bottom_up_batch, make_inclusions, BottomUpBatch, BottomUpManager,
+        SignedBottomUpBatchRef,
+    };
+
+    fn envelope(nonce: u64) -> IpcEnvelope {
+        let addr = Ipcaddress {
+            subnet_id: SubnetID {
+                root: 123,
+                route: vec![ethers::types::Address::repeat_byte(1)],
+            },
+            raw_address: FvmAddress {
+                addr_type: 1,
+                payload: vec![2; 20].into(),
+            },
+        };
+        IpcEnvelope {
+            kind: 0,
+            local_nonce: nonce,
+            from: addr.clone(),
+            to: addr,
+            value: ethers::types::U256::from(1000u64),
+            original_nonce: 0,
+            message: Default::default(),
+        }
+    }
+
+    fn batch_ref(height: i64) -> BottomUpBatchRef {
+        let msgs = vec![envelope(0)];
+        BottomUpBatchRef {
+            subnet_id: ipc_api::subnet_id::SubnetID::new(
+                123,
+                vec![fvm_shared::address::Address::new_id(1001)],
+            ),
+            height,
+            cid: BottomUpBatch::new(height, &msgs).cid,
+        }
+    }
+
+    #[test]
+    fn batch_roundtrip() {
+        let msgs = vec![envelope(0), envelope(1)];
+        let batch = BottomUpBatch::new(10, &msgs);
+        assert_eq!(batch.cid.codec(), fvm_ipld_encoding::IPLD_RAW);
+        assert_eq!(decode_bottom_up_batch(&batch.data).unwrap(), msgs);
+        // Content addressed.
+        assert_eq!(BottomUpBatch::new(20, &msgs).cid, batch.cid);
+    }
+
+    #[test]
+    fn executed_messages_are_skipped() {
+        let msgs = vec![envelope(0), envelope(1), envelope(2)];
+        let all = make_inclusions(msgs.clone(), &[]).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let merkle = ipc_api::merkle::MerkleGen::new(
+            ipc_api::checkpoint::abi_encode_envelope,
+            msgs.as_slice(),
+            &ipc_api::checkpoint::abi_encode_envelope_fields(),
+        )
+        .unwrap();
+        let leaf = merkle
+            .leaf_hash(&ipc_api::checkpoint::abi_encode_envelope(&msgs[1]))
+            .unwrap();
+
+        let rest = make_inclusions(msgs, &[leaf.0]).unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].msg.local_nonce, 0);
+        assert_eq!(rest[1].msg.local_nonce, 2);
+    }
+
+    #[test]
+    fn announcement_signer_recovered() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let sk = SecretKey::random(&mut rng);
+        let signer = EthAddress::from(sk.public_key());
+
+        let mut ann = SignedBottomUpBatchRef::sign(batch_ref(10), &sk).unwrap();
+        assert_eq!(ann.recover().unwrap(), signer);
+
+        // The signature does not carry over to a different batch.
+        ann.batch.height = 20;
+        assert_ne!(ann.recover().ok(), Some(signer));
+    }
+
+    #[test]
+    fn missing_batches_fail_the_message() {
+        let store = MemoryBlockstore::new();
+        let manager = BottomUpManager::new(ResolvePool::new(), store.clone());
+
+        let msgs = vec![envelope(0)];
+        let batch = BottomUpBatch::new(10, &msgs);
+        let batch_ref = batch.to_ref(batch_ref(10).subnet_id);
+
+        assert!(matches!(
+            manager.load_committed_batch(&batch_ref),
+            Err(ApplyMessageError::InvalidMessage(_))
+        ));
+
+        store.put_keyed(&batch.cid, &batch.data).unwrap();
+        assert_eq!(manager.load_committed_batch(&batch_ref).unwrap(), msgs);
+    }
+
+    #[tokio::test]
+    async fn unannounced_proposals_are_not_resolved() {
+        let manager = BottomUpManager::new(ResolvePool::new(), MemoryBlockstore::new());
+        let batch = batch_ref(10);
+
+        assert!(!manager.is_batch_resolved(batch.clone()).await);
+        // Asking about a proposal does not start resolving it.
+        let all = async_stm::atomically(|| manager.pool.collect_all()).await;
+        assert!(all.is_empty());
+
+        manager.add_announced(batch.clone()).await;
+        let all = async_stm::atomically(|| manager.pool.collect_all()).await;
+        assert_eq!(all.len(), 1);
+        assert!(!manager.is_batch_resolved(batch).await);
+    }
+}
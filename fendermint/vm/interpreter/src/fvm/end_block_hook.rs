@@ -1,6 +1,7 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use super::bottomup::BottomUpBatch;
 use super::state::ipc::tokens_to_burn;
 use super::state::{ipc::GatewayCaller, FvmExecState};
 
@@ -10,6 +11,7 @@ use anyhow::Context;
 use ethers::abi::Tokenizable;
 use fendermint_vm_genesis::{Power, Validator};
 use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
 use ipc_actors_abis::checkpointing_facet as checkpoint;
 use ipc_actors_abis::checkpointing_facet::{FvmAddress, Ipcaddress, SubnetID};
 use ipc_actors_abis::gateway_getter_facet::gateway_getter_facet;
@@ -46,6 +48,8 @@ pub struct LightClientCommitments {
 pub struct EndBlockOutcome {
     pub light_client_commitments: LightClientCommitments,
     pub power_updates: PowerUpdates,
+    /// The bottom-up messages of the checkpoint, if there were any, to be resolved by the parent.
+    pub bottom_up_batch: Option<BottomUpBatch>,
 }
 
 #[derive(Clone, Default)]
@@ -124,7 +128,10 @@ where
     let msgs_count = msgs.len();
 
     let mut msgs_root = [0u8; 32];
+    let mut bottom_up_batch = None;
     if msgs_count > 0 {
+        bottom_up_batch = Some(BottomUpBatch::new(height.value() as ChainEpoch, &msgs));
+
         msgs_root = MerkleGen::new(
             abi_encode_envelope,
             msgs.as_slice(),
@@ -167,6 +174,7 @@ where
     Ok(Some(EndBlockOutcome {
         light_client_commitments: commitments,
         power_updates,
+        bottom_up_batch,
     }))
 }
 
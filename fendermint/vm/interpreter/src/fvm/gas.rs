@@ -23,6 +23,9 @@ pub struct BlockGasTracker {
     cumul_gas_premium: TokenAmount,
     /// The accumulated gas usage throughout the block.
     cumul_gas_used: Gas,
+    /// The accumulated gas usage of implicit messages, which doesn't count towards the block gas limit,
+    /// except for the execution of top-down messages, see [BlockGasTracker::record_topdown_utilization].
+    cumul_implicit_gas_used: Gas,
 }
 
 impl BlockGasTracker {
@@ -36,6 +39,7 @@ impl BlockGasTracker {
             block_gas_limit: Zero::zero(),
             cumul_gas_premium: Zero::zero(),
             cumul_gas_used: Zero::zero(),
+            cumul_implicit_gas_used: Zero::zero(),
         };
 
         let reading = Self::read_gas_market(executor)?;
@@ -46,6 +50,10 @@ impl BlockGasTracker {
         Ok(ret)
     }
 
+    pub fn block_gas_limit(&self) -> Gas {
+        self.block_gas_limit
+    }
+
     pub fn available(&self) -> Gas {
         self.block_gas_limit.saturating_sub(self.cumul_gas_used)
     }
@@ -71,6 +79,22 @@ impl BlockGasTracker {
         }
     }
 
+    pub fn record_implicit_utilization(&mut self, ret: &ApplyRet) {
+        self.cumul_implicit_gas_used = self
+            .cumul_implicit_gas_used
+            .saturating_add(ret.msg_receipt.gas_used);
+    }
+
+    /// Count the gas the implicit execution of top-down messages used towards the block gas limit,
+    /// on top of the implicit gas usage it was already recorded in.
+    pub fn record_topdown_utilization(&mut self, gas_used: Gas) {
+        self.cumul_gas_used = self.cumul_gas_used.saturating_add(gas_used);
+    }
+
+    pub fn implicit_gas_used(&self) -> Gas {
+        self.cumul_implicit_gas_used
+    }
+
     pub fn finalize<E: Executor>(
         &self,
         executor: &mut E,
@@ -166,3 +190,31 @@ impl BlockGasTracker {
         Ok(apply_ret)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+    use num_traits::Zero;
+
+    use super::BlockGasTracker;
+
+    fn tracker(block_gas_limit: u64) -> BlockGasTracker {
+        BlockGasTracker {
+            base_fee: TokenAmount::zero(),
+            block_gas_limit,
+            cumul_gas_premium: TokenAmount::zero(),
+            cumul_gas_used: 0,
+            cumul_implicit_gas_used: 0,
+        }
+    }
+
+    #[test]
+    fn topdown_utilization_counts_towards_the_block_gas_limit() {
+        let mut tracker = tracker(1000);
+        tracker.record_topdown_utilization(300);
+        assert_eq!(tracker.available(), 700);
+
+        tracker.record_topdown_utilization(900);
+        assert_eq!(tracker.available(), 0);
+    }
+}
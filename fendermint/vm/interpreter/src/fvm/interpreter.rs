@@ -3,10 +3,11 @@
 
 use anyhow::{Context, Result};
 use cid::Cid;
+use fendermint_vm_core::Timestamp;
 use fendermint_vm_message::chain::ChainMessage;
 use fendermint_vm_message::ipc::IpcMessage;
-use fendermint_vm_message::query::{FvmQuery, StateParams};
-use fendermint_vm_message::signed::SignedMessage;
+use fendermint_vm_message::query::{FvmQuery, MessageTrace, StateParams, TraceBlock};
+use fendermint_vm_message::signed::{OriginKind, SignedMessage};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{self};
 use fvm_shared::{address::Address, error::ExitCode};
@@ -14,6 +15,7 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use crate::errors::*;
+use crate::fvm::bottomup::BottomUpManager;
 use crate::fvm::end_block_hook::{EndBlockManager, PowerUpdates};
 use crate::fvm::executions::{
     execute_cron_message, execute_signed_message, push_block_to_chainmeta_actor_if_possible,
@@ -22,8 +24,8 @@ use crate::fvm::gas_estimation::{estimate_gassed_msg, gas_search};
 use crate::fvm::topdown::TopDownManager;
 use crate::fvm::{
     activity::ValidatorActivityTracker,
-    observe::{MsgExec, MsgExecPurpose},
-    state::{FvmExecState, FvmQueryState},
+    observe::{ImplicitGasUsed, MsgExec, MsgExecPurpose},
+    state::{trace_message, FvmExecState, FvmQueryState},
     store::ReadOnlyBlockstore,
     upgrades::UpgradeScheduler,
     FvmMessage,
@@ -52,6 +54,8 @@ where
     end_block_manager: EndBlockManager<DB>,
 
     top_down_manager: TopDownManager<DB>,
+    /// Executes the bottom-up batches of child subnets, if we resolve them.
+    bottom_up_manager: Option<BottomUpManager<DB>>,
     upgrade_scheduler: UpgradeScheduler<DB>,
 
     push_block_data_to_chainmeta_actor: bool,
@@ -77,6 +81,7 @@ where
         Self {
             end_block_manager,
             top_down_manager,
+            bottom_up_manager: None,
             upgrade_scheduler,
             push_block_data_to_chainmeta_actor,
             max_msgs_per_block,
@@ -85,6 +90,61 @@ where
         }
     }
 
+    /// Propose and execute the bottom-up message batches of child subnets resolved by CID.
+    pub fn with_bottom_up_manager(mut self, bottom_up_manager: BottomUpManager<DB>) -> Self {
+        self.bottom_up_manager = Some(bottom_up_manager);
+        self
+    }
+
+    /// Replay the messages of a block over the state of its parent with execution tracing enabled.
+    ///
+    /// The implicit messages at the beginning of the block and the IPC messages are executed the
+    /// same way as on chain, so the signed messages see their effects, but only the signed ones
+    /// are traced. Upgrades scheduled at the height of the block are not replayed.
+    async fn trace_block(
+        &self,
+        state: FvmQueryState<DB>,
+        block: TraceBlock,
+    ) -> Result<Vec<MessageTrace>> {
+        let height = block.height as u64;
+        let mut state =
+            state.tracing_exec_state(block.height, Timestamp(block.timestamp), block.block_hash)?;
+
+        execute_cron_message(&mut state, height).context("failed to trigger cron event")?;
+
+        if self.push_block_data_to_chainmeta_actor {
+            push_block_to_chainmeta_actor_if_possible(&mut state, height)
+                .context("failed to push block data to chainmetadata")?;
+        }
+
+        let mut traces = Vec::new();
+
+        for msg in block.messages {
+            match msg {
+                ChainMessage::Signed(msg) => {
+                    traces.push(trace_message(&mut state, msg.into_message())?);
+                }
+                ChainMessage::Ipc(IpcMessage::TopDownExec(finality)) => {
+                    self.top_down_manager
+                        .replay_topdown_msg(&mut state, finality)
+                        .await
+                        .context("failed to replay top-down messages")?;
+                }
+                ChainMessage::Ipc(IpcMessage::BottomUpExec(batch)) => {
+                    let Some(ref m) = self.bottom_up_manager else {
+                        anyhow::bail!(
+                            "cannot replay bottom-up batch: the IPLD Resolver is disabled"
+                        );
+                    };
+                    m.replay_bottom_up_batch(&mut state, batch)
+                        .context("failed to replay bottom-up batch")?;
+                }
+            }
+        }
+
+        Ok(traces)
+    }
+
     /// Performs an upgrade if one is scheduled at the current block height.
     fn perform_upgrade_if_needed(&self, state: &mut FvmExecState<DB>) -> Result<()> {
         let chain_id = state.chain_id();
@@ -211,6 +271,16 @@ where
             .check()
             .map_err(|e| CheckMessageError::InvalidMessage(e.to_string()))?;
 
+        // The EVM actor cannot apply code delegations, so these would not do what was signed.
+        if signed_msg.origin_kind == OriginKind::EthereumEIP7702 {
+            return Ok(CheckResponse::new(
+                fvm_msg,
+                ExitCode::USR_ILLEGAL_ARGUMENT,
+                Some("EIP-7702 transactions are not supported".to_string()),
+                None,
+            ));
+        }
+
         let base_fee = state.block_gas_tracker().base_fee();
         // Regardless it is recheck or not, ensure gas fee cap is more than current
         // base fee.
@@ -252,7 +322,7 @@ where
 
     async fn prepare_messages_for_block(
         &self,
-        state: FvmExecState<ReadOnlyBlockstore<Arc<DB>>>,
+        mut state: FvmExecState<ReadOnlyBlockstore<Arc<DB>>>,
         msgs: Vec<Vec<u8>>,
         max_transaction_bytes: u64,
     ) -> Result<PrepareMessagesResponse, PrepareMessagesError> {
@@ -277,11 +347,18 @@ where
 
         let top_down_iter = self
             .top_down_manager
-            .chain_message_from_finality_or_quorum()
+            .chain_message_from_finality_or_quorum(state.block_gas_tracker().block_gas_limit())
             .await
             .into_iter();
 
+        let bottom_up_iter = match self.bottom_up_manager {
+            Some(ref m) => m.chain_messages_from_resolved(&mut state).await,
+            None => Vec::new(),
+        }
+        .into_iter();
+
         let mut all_msgs = top_down_iter
+            .chain(bottom_up_iter)
             .chain(signed_msgs_iter)
             .map(|msg| fvm_ipld_encoding::to_vec(&msg).context("failed to encode message as IPLD"))
             .collect::<Result<Vec<Vec<u8>>>>()?;
@@ -317,7 +394,7 @@ where
 
     async fn attest_block_messages(
         &self,
-        state: FvmExecState<ReadOnlyBlockstore<Arc<DB>>>,
+        mut state: FvmExecState<ReadOnlyBlockstore<Arc<DB>>>,
         msgs: Vec<Vec<u8>>,
     ) -> Result<AttestMessagesResponse, AttestMessagesError> {
         if msgs.len() > self.max_msgs_per_block {
@@ -328,13 +405,33 @@ where
             return Ok(AttestMessagesResponse::Reject);
         }
 
+        // Every validator attests, so this is where they all get to forget about old batches.
+        if let Some(ref m) = self.bottom_up_manager {
+            m.evict_stale(&mut state).await;
+        }
+
         let mut block_gas_usage = 0;
         let base_fee = state.block_gas_tracker().base_fee();
+        let block_gas_limit = state.block_gas_tracker().block_gas_limit();
         for msg in msgs {
             match fvm_ipld_encoding::from_slice::<ChainMessage>(&msg) {
                 Ok(chain_msg) => match chain_msg {
                     ChainMessage::Ipc(IpcMessage::TopDownExec(finality)) => {
-                        if !self.top_down_manager.is_finality_valid(finality).await {
+                        if !self
+                            .top_down_manager
+                            .is_finality_valid(finality, block_gas_limit)
+                            .await
+                        {
+                            return Ok(AttestMessagesResponse::Reject);
+                        }
+                    }
+                    ChainMessage::Ipc(IpcMessage::BottomUpExec(batch)) => {
+                        let is_resolved = match self.bottom_up_manager {
+                            Some(ref m) => m.is_batch_resolved(batch).await,
+                            None => false,
+                        };
+                        if !is_resolved {
+                            tracing::debug!("rejecting block: bottom-up batch not resolved");
                             return Ok(AttestMessagesResponse::Reject);
                         }
                     }
@@ -403,17 +500,24 @@ where
             .end_block_manager
             .trigger_end_block_hook(state, &mut end_block_events)?;
 
-        let (power_updates, maybe_commitment) = if let Some(outcome) = maybe_result {
+        let (power_updates, maybe_commitment, bottom_up_batch) = if let Some(outcome) = maybe_result
+        {
             (
                 outcome.power_updates,
                 Some(outcome.light_client_commitments),
+                outcome.bottom_up_batch,
             )
         } else {
-            (PowerUpdates::default(), None)
+            (PowerUpdates::default(), None, None)
         };
 
         let next_gas_market = state.finalize_gas_market()?;
 
+        emit(ImplicitGasUsed {
+            height: state.block_height(),
+            gas_used: state.block_gas_tracker().implicit_gas_used(),
+        });
+
         if !power_updates.0.is_empty() {
             self.top_down_manager
                 .update_voting_power_table(&power_updates)
@@ -424,6 +528,7 @@ where
             power_updates,
             gas_market: next_gas_market,
             light_client_commitments: maybe_commitment,
+            bottom_up_batch,
             end_block_events,
         };
         Ok(response)
@@ -456,15 +561,31 @@ where
                 Ok(ApplyMessageResponse {
                     applied_message,
                     domain_hash,
+                    topdown_receipts: Vec::new(),
                 })
             }
             ChainMessage::Ipc(ipc_msg) => match ipc_msg {
                 IpcMessage::TopDownExec(p) => {
-                    let applied_message =
+                    let (applied_message, topdown_receipts) =
                         self.top_down_manager.execute_topdown_msg(state, p).await?;
                     Ok(ApplyMessageResponse {
                         applied_message,
                         domain_hash: None,
+                        topdown_receipts,
+                    })
+                }
+                IpcMessage::BottomUpExec(batch) => {
+                    // We can't have accepted the proposal, but the block is final, so we fail the message.
+                    let Some(ref m) = self.bottom_up_manager else {
+                        return Err(ApplyMessageError::InvalidMessage(
+                            "cannot execute bottom-up batch: the IPLD Resolver is disabled".into(),
+                        ));
+                    };
+                    let applied_message = m.execute_bottom_up_batch(state, batch).await?;
+                    Ok(ApplyMessageResponse {
+                        applied_message,
+                        domain_hash: None,
+                        topdown_receipts: Vec::new(),
                     })
                 }
             },
@@ -551,6 +672,21 @@ where
                     }
                 }
             }
+            FvmQuery::Trace(block) => {
+                tracing::info!(
+                    height = block.height,
+                    num_msgs = block.messages.len(),
+                    "query trace"
+                );
+                if block.messages.len() > self.max_msgs_per_block {
+                    return Err(QueryError::InvalidQuery(format!(
+                        "cannot trace more than {} messages",
+                        self.max_msgs_per_block
+                    )));
+                }
+                let traces = self.trace_block(state, *block).await?;
+                Ok(QueryResponse::Trace(traces))
+            }
             FvmQuery::StateParams => {
                 let state_params = state.state_params();
                 let state_params = StateParams {
@@ -23,6 +23,10 @@ register_metrics! {
         = register_histogram!("exec_fvm_apply_execution_time_secs", "Execution time of FVM apply in seconds");
     EXEC_FVM_CALL_EXECUTION_TIME_SECS: Histogram
         = register_histogram!("exec_fvm_call_execution_time_secs", "Execution time of FVM call in seconds");
+    EXEC_FVM_IMPLICIT_GAS_USED_TOTAL: IntCounter
+        = register_int_counter!("exec_fvm_implicit_gas_used_total", "Gas used by implicit system messages");
+    EXEC_FVM_IMPLICIT_GAS_USED_BLOCK: IntGauge
+        = register_int_gauge!("exec_fvm_implicit_gas_used_block", "Gas used by implicit system messages in the last block");
     BOTTOMUP_CHECKPOINT_CREATED_TOTAL: IntCounter
         = register_int_counter!("bottomup_checkpoint_created_total", "Bottom-up checkpoint produced");
     BOTTOMUP_CHECKPOINT_CREATED_HEIGHT: IntGauge
@@ -42,6 +46,8 @@ register_metrics! {
 
 impl_traceables!(TraceLevel::Info, "Execution", MsgExec);
 
+impl_traceables!(TraceLevel::Debug, "Execution", ImplicitGasUsed);
+
 #[derive(Debug, strum::EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum MsgExecPurpose {
@@ -74,6 +80,21 @@ impl Recordable for MsgExec {
     }
 }
 
+/// Gas used by the implicit messages of a block, e.g. cron, top-down execution and the end-block hook,
+/// which is not counted towards the block gas limit.
+#[derive(Debug)]
+pub struct ImplicitGasUsed {
+    pub height: i64,
+    pub gas_used: u64,
+}
+
+impl Recordable for ImplicitGasUsed {
+    fn record_metrics(&self) {
+        EXEC_FVM_IMPLICIT_GAS_USED_TOTAL.inc_by(self.gas_used);
+        EXEC_FVM_IMPLICIT_GAS_USED_BLOCK.set(self.gas_used as i64);
+    }
+}
+
 impl_traceables!(
     TraceLevel::Info,
     "Bottomup",
@@ -170,6 +191,12 @@ mod tests {
             exit_code: 1,
             message: message.clone(),
         });
+
+        emit(ImplicitGasUsed {
+            height: 1,
+            gas_used: 1,
+        });
+
         let hash = vec![0x01, 0x02, 0x03];
 
         emit(CheckpointCreated {
@@ -0,0 +1,257 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Compare two state trees, e.g. the states a node committed at different heights,
+//! to see which actors a range of blocks has touched.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fil_actor_evm::interpreter::system::{StateKamt, KAMT_CONFIG};
+use fil_actors_evm_shared::uints::U256;
+use fvm::state_tree::{ActorState, StateTree};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::ActorID;
+
+use super::fevm::evm_actor_state;
+
+/// A storage slot of an EVM contract which changed; missing slots have the value zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotDiff {
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+/// An actor which is different in the two state trees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorDiff {
+    pub id: ActorID,
+    /// The actor before the change; `None` if it has been created.
+    pub before: Option<ActorState>,
+    /// The actor after the change; `None` if it has been deleted.
+    pub after: Option<ActorState>,
+    /// Changes in the storage, if the actor is an EVM contract.
+    pub storage: Vec<SlotDiff>,
+}
+
+/// Walk two state trees and collect the actors whose balance, nonce, code or state changed,
+/// ordered by actor ID.
+pub fn diff_state_trees<DB>(store: &DB, from: &Cid, to: &Cid) -> anyhow::Result<Vec<ActorDiff>>
+where
+    DB: Blockstore,
+{
+    if from == to {
+        return Ok(Vec::new());
+    }
+
+    let before = collect_actors(store, from)?;
+    let after = collect_actors(store, to)?;
+
+    let ids = before
+        .keys()
+        .chain(after.keys())
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let mut diffs = Vec::new();
+
+    for id in ids {
+        let b = before.get(&id);
+        let a = after.get(&id);
+
+        if b == a {
+            continue;
+        }
+
+        let storage = diff_storage(
+            store,
+            b.and_then(|s| contract_state(store, s)).as_ref(),
+            a.and_then(|s| contract_state(store, s)).as_ref(),
+        )
+        .with_context(|| format!("failed to diff the storage of actor {id}"))?;
+
+        diffs.push(ActorDiff {
+            id,
+            before: b.cloned(),
+            after: a.cloned(),
+            storage,
+        });
+    }
+
+    Ok(diffs)
+}
+
+/// Compare the storage of an EVM contract under two KAMT roots.
+pub fn diff_storage<DB>(
+    store: &DB,
+    from: Option<&Cid>,
+    to: Option<&Cid>,
+) -> anyhow::Result<Vec<SlotDiff>>
+where
+    DB: Blockstore,
+{
+    if from == to {
+        return Ok(Vec::new());
+    }
+
+    let before = match from {
+        Some(root) => collect_slots(store, root)?,
+        None => Default::default(),
+    };
+    let after = match to {
+        Some(root) => collect_slots(store, root)?,
+        None => Default::default(),
+    };
+
+    let slots = before
+        .keys()
+        .chain(after.keys())
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let diffs = slots
+        .into_iter()
+        .filter_map(|slot| {
+            let b = before.get(&slot).copied().unwrap_or(U256::zero());
+            let a = after.get(&slot).copied().unwrap_or(U256::zero());
+            (b != a).then_some(SlotDiff {
+                slot,
+                before: b,
+                after: a,
+            })
+        })
+        .collect();
+
+    Ok(diffs)
+}
+
+fn collect_actors<DB>(store: &DB, root: &Cid) -> anyhow::Result<BTreeMap<ActorID, ActorState>>
+where
+    DB: Blockstore,
+{
+    let state_tree = StateTree::new_from_root(store, root)
+        .with_context(|| format!("failed to load state tree {root}"))?;
+
+    let mut actors = BTreeMap::new();
+
+    state_tree.for_each(|addr, state| {
+        let id = addr
+            .id()
+            .map_err(|e| anyhow!("state tree key {addr} is not an ID: {e}"))?;
+        actors.insert(id, state.clone());
+        Ok(())
+    })?;
+
+    Ok(actors)
+}
+
+fn collect_slots<DB>(store: &DB, root: &Cid) -> anyhow::Result<BTreeMap<U256, U256>>
+where
+    DB: Blockstore,
+{
+    let kamt = StateKamt::load_with_config(root, store, KAMT_CONFIG.clone())
+        .with_context(|| format!("failed to load contract storage {root}"))?;
+
+    let mut slots = BTreeMap::new();
+
+    kamt.for_each(|k, v| {
+        slots.insert(*k, *v);
+        Ok(())
+    })?;
+
+    Ok(slots)
+}
+
+/// The root of the storage, if the actor is an EVM contract.
+fn contract_state<DB>(store: &DB, actor: &ActorState) -> Option<Cid>
+where
+    DB: Blockstore,
+{
+    evm_actor_state(store, actor).map(|state| state.contract_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use fil_actor_evm::interpreter::system::{StateKamt, KAMT_CONFIG};
+    use fil_actors_evm_shared::uints::U256;
+    use fvm::state_tree::ActorState;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::EMPTY_ARR_CID;
+
+    use super::{diff_state_trees, diff_storage, SlotDiff};
+    use crate::fvm::state::empty_state_tree;
+    use crate::fvm::store::memory::MemoryBlockstore;
+
+    fn actor(sequence: u64, balance: u64) -> ActorState {
+        ActorState {
+            code: EMPTY_ARR_CID,
+            state: EMPTY_ARR_CID,
+            sequence,
+            balance: TokenAmount::from_atto(balance),
+            delegated_address: None,
+        }
+    }
+
+    fn state_root(store: &MemoryBlockstore, actors: &[(u64, ActorState)]) -> Cid {
+        let mut state_tree = empty_state_tree(store.clone()).unwrap();
+        for (id, state) in actors {
+            state_tree.set_actor(*id, state.clone());
+        }
+        state_tree.flush().unwrap()
+    }
+
+    fn storage_root(store: &MemoryBlockstore, slots: &[(u64, u64)]) -> Cid {
+        let mut kamt = StateKamt::new_with_config(store, KAMT_CONFIG.clone());
+        for (k, v) in slots {
+            kamt.set(U256::from(*k), U256::from(*v)).unwrap();
+        }
+        kamt.flush().unwrap()
+    }
+
+    #[test]
+    fn reports_changed_actors() {
+        let store = MemoryBlockstore::new();
+
+        let from = state_root(&store, &[(100, actor(0, 10)), (101, actor(1, 10))]);
+        let to = state_root(
+            &store,
+            &[(100, actor(1, 5)), (101, actor(1, 10)), (102, actor(0, 5))],
+        );
+
+        let diffs = diff_state_trees(&store, &from, &to).unwrap();
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].id, 100);
+        assert_eq!(diffs[0].before, Some(actor(0, 10)));
+        assert_eq!(diffs[0].after, Some(actor(1, 5)));
+        assert_eq!(diffs[1].id, 102);
+        assert_eq!(diffs[1].before, None);
+        assert!(diffs[1].storage.is_empty());
+
+        assert!(diff_state_trees(&store, &to, &to).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_changed_slots() {
+        let store = MemoryBlockstore::new();
+
+        let from = storage_root(&store, &[(0, 1), (1, 2), (2, 3)]);
+        let to = storage_root(&store, &[(0, 1), (1, 5), (3, 4)]);
+
+        let diffs = diff_storage(&store, Some(&from), Some(&to)).unwrap();
+
+        let slot = |slot: u64, before: u64, after: u64| SlotDiff {
+            slot: U256::from(slot),
+            before: U256::from(before),
+            after: U256::from(after),
+        };
+
+        assert_eq!(diffs, vec![slot(1, 2, 5), slot(2, 3, 0), slot(3, 0, 4)]);
+
+        let created = diff_storage(&store, None, Some(&from)).unwrap();
+        assert_eq!(created.len(), 3);
+    }
+}
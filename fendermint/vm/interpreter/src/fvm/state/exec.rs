@@ -168,6 +168,28 @@ where
         multi_engine: &MultiEngine,
         block_height: ChainEpoch,
         params: FvmStateParams,
+    ) -> anyhow::Result<Self> {
+        Self::create(blockstore, multi_engine, block_height, params, false)
+    }
+
+    /// Create a new FVM execution environment which records the execution trace in the [ApplyRet].
+    ///
+    /// Tracing has an overhead, so this is meant for replaying messages on demand.
+    pub fn new_with_tracing(
+        blockstore: DB,
+        multi_engine: &MultiEngine,
+        block_height: ChainEpoch,
+        params: FvmStateParams,
+    ) -> anyhow::Result<Self> {
+        Self::create(blockstore, multi_engine, block_height, params, true)
+    }
+
+    fn create(
+        blockstore: DB,
+        multi_engine: &MultiEngine,
+        block_height: ChainEpoch,
+        params: FvmStateParams,
+        tracing: bool,
     ) -> anyhow::Result<Self> {
         let mut nc = NetworkConfig::new(params.network_version);
         nc.chain_id = ChainID::from(params.chain_id);
@@ -178,6 +200,9 @@ where
         let mut mc = nc.for_epoch(block_height, params.timestamp.0, params.state_root);
         mc.set_base_fee(params.base_fee.clone());
         mc.set_circulating_supply(params.circ_supply.clone());
+        if tracing {
+            mc.enable_tracing();
+        }
 
         // Creating a new machine every time is prohibitively slow.
         // let ec = EngineConfig::from(&nc);
@@ -279,9 +304,12 @@ where
         let ret = self.executor.execute_message(msg, kind, raw_length)?;
         let addrs = self.emitter_delegated_addresses(&ret)?;
 
-        // Record the utilization of this message if the apply type was Explicit.
+        // Record the utilization of this message if the apply type was Explicit;
+        // implicit messages are only tracked for metrics.
         if kind == ApplyKind::Explicit {
             self.block_gas_tracker.record_utilization(&ret);
+        } else {
+            self.block_gas_tracker.record_implicit_utilization(&ret);
         }
 
         Ok((ret, addrs))
@@ -14,8 +14,9 @@ use ethers::providers as ep;
 use fendermint_vm_actor_interface::{eam::EthAddress, evm, system};
 use fendermint_vm_message::conv::from_eth;
 use fvm::executor::ApplyFailure;
+use fvm::state_tree::ActorState;
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{BytesDe, BytesSer, RawBytes};
+use fvm_ipld_encoding::{BytesDe, BytesSer, CborStore, RawBytes};
 use fvm_shared::{address::Address, econ::TokenAmount, error::ExitCode, message::Message};
 
 use crate::fvm::constants::BLOCK_GAS_LIMIT;
@@ -42,6 +43,7 @@ pub struct CallError<E> {
     pub exit_code: ExitCode,
     pub failure_info: Option<ApplyFailure>,
     pub error: ContractError<E>,
+    pub gas_used: u64,
 }
 
 impl<E> std::fmt::Debug for ContractError<E>
@@ -200,6 +202,7 @@ where
                 exit_code,
                 failure_info,
                 error,
+                ..
             }) => {
                 bail!(
                     "failed to execute contract call to {}:\ncode: {}\nerror: {:?}\ninfo: {}",
@@ -279,6 +282,7 @@ where
         //eprintln!("\nRESULT FROM FVM: {ret:?}");
 
         if !ret.msg_receipt.exit_code.is_success() {
+            let gas_used = ret.msg_receipt.gas_used;
             let output = ret.msg_receipt.return_data;
 
             let output = if output.is_empty() {
@@ -300,6 +304,7 @@ where
                 exit_code: ret.msg_receipt.exit_code,
                 failure_info: ret.failure_info,
                 error,
+                gas_used,
             }))
         } else {
             let ret = AppliedMessage {
@@ -316,6 +321,14 @@ where
 }
 
 /// Fixed decoding until https://github.com/gakonst/ethers-rs/pull/2637 is released.
+/// The state of an actor, if it is an EVM contract; the state of other actors doesn't parse as such.
+pub fn evm_actor_state<DB: Blockstore>(
+    store: &DB,
+    actor: &ActorState,
+) -> Option<fil_actor_evm::State> {
+    store.get_cbor(&actor.state).ok().flatten()
+}
+
 fn decode_revert<E: ContractRevert>(data: &[u8]) -> Option<E> {
     E::decode_with_selector(data).or_else(|| {
         if data.len() < 4 {
@@ -1,12 +1,13 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use actors_custom_car::Manifest as CustomActorManifest;
 use anyhow::{anyhow, bail, Context};
 use cid::Cid;
-use ethers::{abi::Tokenize, core::abi::Abi};
+use ethers::{abi::Tokenize, core::abi::Abi, types::H256};
 use fendermint_vm_actor_interface::{
     account::{self, ACCOUNT_ACTOR_CODE_ID},
     eam::{self, EthAddress},
@@ -17,7 +18,7 @@ use fendermint_vm_actor_interface::{
     system, EMPTY_ARR,
 };
 use fendermint_vm_core::Timestamp;
-use fendermint_vm_genesis::{Account, Multisig, PowerScale};
+use fendermint_vm_genesis::{Account, Contract, Multisig, PowerScale};
 use fvm::{
     engine::MultiEngine,
     machine::Manifest,
@@ -38,6 +39,7 @@ use fvm_shared::{
 use multihash_codetable::Code;
 
 use crate::fvm::constants::BLOCK_GAS_LIMIT;
+use crate::genesis::GENESIS_APP_VERSION;
 use num_traits::Zero;
 use serde::{de, Serialize};
 
@@ -157,7 +159,7 @@ where
                     circ_supply,
                     chain_id,
                     power_scale,
-                    app_version: 0,
+                    app_version: GENESIS_APP_VERSION,
                     consensus_params: None,
                 };
 
@@ -171,6 +173,24 @@ where
         Ok(())
     }
 
+    /// Instantiate the execution state on top of the state of an existing chain, instead of the
+    /// empty state tree, so that we can fork it into a new genesis.
+    ///
+    /// The state has to be in the block store already.
+    pub fn fork_exec_state(&mut self, params: FvmStateParams) -> anyhow::Result<()> {
+        self.stage = match &self.stage {
+            Stage::Exec(_) => bail!("execution engine already initialized"),
+            Stage::Tree(_) => {
+                let exec_state =
+                    FvmExecState::new(self.store.clone(), &self.multi_engine, 1, params)
+                        .context("failed to create exec state from the forked state")?;
+
+                Stage::Exec(Box::new(exec_state))
+            }
+        };
+        Ok(())
+    }
+
     /// Flush the data to the block store. Returns the state root cid and the underlying state store.
     pub fn finalize(self) -> anyhow::Result<(Cid, DB)> {
         match self.stage {
@@ -437,6 +457,76 @@ where
         initcode: Vec<u8>,
         deployer: ethers::types::Address,
     ) -> anyhow::Result<EthAddress> {
+        let f4_addr = Address::from(builtin_actor_eth_addr(id));
+
+        self.construct_evm_actor(id, f4_addr, initcode, deployer, TokenAmount::zero())?;
+
+        let addr: [u8; 20] = match f4_addr.payload() {
+            Payload::Delegated(addr) => addr.subaddress().try_into().expect("hash is 20 bytes"),
+            other => panic!("not an f4 address: {other:?}"),
+        };
+
+        Ok(EthAddress(addr))
+    }
+
+    /// Deploy an EVM contract at a fixed Ethereum address, with the runtime code, storage,
+    /// balance and nonce it would have had if it had been deployed before genesis.
+    ///
+    /// The ID of the contract has to have been allocated by the Init actor for its delegated address.
+    pub fn create_genesis_contract_actor(
+        &mut self,
+        contract: Contract,
+        balance: TokenAmount,
+        ids: &init::AddressMap,
+    ) -> anyhow::Result<ActorID> {
+        let f4_addr = Address::from(EthAddress::from(contract.address));
+        let id = *ids
+            .get(&f4_addr)
+            .ok_or_else(|| anyhow!("can't find ID for contract {f4_addr}"))?;
+
+        let initcode = genesis_contract_initcode(&contract.code, &contract.storage)?;
+
+        // The balance is minted into the actor when it's created, the same way as for accounts,
+        // so it's accounted for in the circulating supply like theirs.
+        self.construct_evm_actor(
+            id,
+            f4_addr,
+            initcode,
+            ethers::types::Address::zero(),
+            balance,
+        )
+        .with_context(|| format!("failed to deploy contract at {:?}", contract.address))?;
+
+        // The constructor leaves the nonce at 1, so we patch the actor.
+        let nonce = contract.nonce;
+        if nonce > 0 {
+            self.with_state_tree(
+                |s| set_evm_actor_nonce(s, id, nonce),
+                |s| set_evm_actor_nonce(s, id, nonce),
+            )?;
+        }
+
+        {
+            let cid = self.with_state_tree(|s| s.flush(), |s| s.flush())?;
+            tracing::debug!(
+                state_root = cid.to_string(),
+                actor_id = id,
+                "interim state root after genesis contract initialisation"
+            );
+        }
+
+        Ok(id)
+    }
+
+    /// Construct an EVM actor with a given ID, delegated address and initial balance by running its initcode.
+    fn construct_evm_actor(
+        &mut self,
+        id: ActorID,
+        f4_addr: Address,
+        initcode: Vec<u8>,
+        deployer: ethers::types::Address,
+        balance: TokenAmount,
+    ) -> anyhow::Result<()> {
         // Here we are circumventing the normal way of creating an actor through the EAM and jump ahead to what the `Init` actor would do:
         // https://github.com/filecoin-project/builtin-actors/blob/421855a7b968114ac59422c1faeca968482eccf4/actors/init/src/lib.rs#L97-L107
 
@@ -450,7 +540,6 @@ where
         // When a contract is constructed the EVM actor verifies that it has an Ethereum delegated address.
         // This has been inserted into the Init actor state as well.
         let f0_addr = Address::new_id(id);
-        let f4_addr = Address::from(builtin_actor_eth_addr(id));
 
         let msg = Message {
             version: 0,
@@ -470,7 +559,7 @@ where
             evm::EVM_ACTOR_CODE_ID,
             id,
             &EMPTY_ARR,
-            TokenAmount::zero(),
+            balance,
             Some(f4_addr),
         )
         .context("failed to create empty actor")?;
@@ -511,12 +600,7 @@ where
             );
         }
 
-        let addr: [u8; 20] = match f4_addr.payload() {
-            Payload::Delegated(addr) => addr.subaddress().try_into().expect("hash is 20 bytes"),
-            other => panic!("not an f4 address: {other:?}"),
-        };
-
-        Ok(EthAddress(addr))
+        Ok(())
     }
 
     pub fn store(&self) -> &DB {
@@ -572,3 +656,116 @@ where
             .ok_or_else(|| anyhow!("actor state by {actor_state_cid} not found"))
     }
 }
+
+/// Set the nonce of an EVM actor, going through the store of the state tree,
+/// because during execution the new actor state is only in the buffer of the machine.
+fn set_evm_actor_nonce<S: Blockstore>(
+    state_tree: &mut StateTree<S>,
+    id: ActorID,
+    nonce: u64,
+) -> anyhow::Result<()> {
+    let mut actor = state_tree
+        .get_actor(id)?
+        .ok_or_else(|| anyhow!("EVM actor {id} not found"))?;
+
+    let mut state: fil_actor_evm::State = state_tree
+        .store()
+        .get_cbor(&actor.state)?
+        .ok_or_else(|| anyhow!("EVM actor state {id} not found"))?;
+
+    state.nonce = nonce;
+
+    actor.state = state_tree.store().put_cbor(&state, Code::Blake2b256)?;
+    state_tree.set_actor(id, actor);
+    Ok(())
+}
+
+/// Create EVM initcode which puts the given entries into storage, then returns the runtime bytecode.
+///
+/// This is how we can deploy a contract with some prepopulated state without having its constructor.
+pub fn genesis_contract_initcode(
+    code: &[u8],
+    storage: &BTreeMap<H256, H256>,
+) -> anyhow::Result<Vec<u8>> {
+    // EIP-3541: new code starting with the 0xEF byte is rejected.
+    if code.first() == Some(&0xef) {
+        bail!("contract code cannot start with 0xEF");
+    }
+    let code_len = u32::try_from(code.len()).context("contract code too long")?;
+
+    // Zero is the default value of a slot; storing it would be a no-op.
+    let slots = storage
+        .iter()
+        .filter(|(_, v)| !v.is_zero())
+        .collect::<Vec<_>>();
+
+    // 66 bytes per SSTORE and 17 bytes to copy and return the code.
+    let header_len = 66 * slots.len() + 17;
+    let code_offset = u32::try_from(header_len).context("too many storage slots")?;
+
+    let mut initcode = Vec::with_capacity(header_len + code.len());
+
+    for (key, value) in slots {
+        initcode.push(PUSH32);
+        initcode.extend_from_slice(value.as_bytes());
+        initcode.push(PUSH32);
+        initcode.extend_from_slice(key.as_bytes());
+        initcode.push(SSTORE);
+    }
+
+    // CODECOPY(destOffset = 0, offset = code_offset, size = code_len)
+    initcode.push(PUSH4);
+    initcode.extend_from_slice(&code_len.to_be_bytes());
+    initcode.push(DUP1);
+    initcode.push(PUSH4);
+    initcode.extend_from_slice(&code_offset.to_be_bytes());
+    initcode.extend_from_slice(&[PUSH1, 0x00, CODECOPY]);
+    // RETURN(offset = 0, size = code_len)
+    initcode.extend_from_slice(&[PUSH1, 0x00, RETURN]);
+
+    debug_assert_eq!(initcode.len(), header_len);
+
+    initcode.extend_from_slice(code);
+
+    Ok(initcode)
+}
+
+const PUSH1: u8 = 0x60;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+const DUP1: u8 = 0x80;
+const CODECOPY: u8 = 0x39;
+const SSTORE: u8 = 0x55;
+const RETURN: u8 = 0xf3;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ethers::types::H256;
+
+    use super::genesis_contract_initcode;
+
+    #[test]
+    fn initcode_stores_slots_and_returns_code() {
+        let code = vec![0x60, 0x01, 0x00];
+        let mut storage = BTreeMap::new();
+        storage.insert(H256::from_low_u64_be(1), H256::from_low_u64_be(42));
+        storage.insert(H256::from_low_u64_be(2), H256::zero());
+
+        let initcode = genesis_contract_initcode(&code, &storage).unwrap();
+
+        // One SSTORE, the zero slot is skipped.
+        assert_eq!(initcode.len(), 66 + 17 + code.len());
+        assert_eq!(initcode[0], 0x7f);
+        assert_eq!(&initcode[1..33], H256::from_low_u64_be(42).as_bytes());
+        assert_eq!(initcode[33], 0x7f);
+        assert_eq!(&initcode[34..66], H256::from_low_u64_be(1).as_bytes());
+        assert_eq!(initcode[66], 0x55);
+        // The offset of the code points right after the header.
+        assert_eq!(&initcode[73..77], &83u32.to_be_bytes());
+        assert_eq!(&initcode[83..], &code[..]);
+
+        assert!(genesis_contract_initcode(&[0xef], &BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Decode the state of actors into JSON, so we don't have to walk the IPLD by hand
+//! to look at e.g. the storage of the IPC contracts or the gas market constants.
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fendermint_actor_activity_tracker::types::ValidatorStats;
+use fendermint_vm_actor_interface::{
+    account, activity, chainmetadata, cron,
+    eam::{EthAddress, EAM_ACTOR_ID},
+    f3_light_client, gas_market, init, multisig, system,
+};
+use fil_actor_evm::interpreter::system::{StateKamt, KAMT_CONFIG};
+use fvm::state_tree::{ActorState, StateTree};
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{CborStore, RawBytes, DAG_CBOR};
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::address::{Address, Payload};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::{ActorID, MethodNum, HAMT_BIT_WIDTH};
+use integer_encoding::VarInt;
+use libipld::Ipld;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+/// A pending transaction of a multisig actor, as stored by the builtin actor.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+struct MultisigTransaction {
+    to: Address,
+    value: TokenAmount,
+    method: MethodNum,
+    params: RawBytes,
+    approved: Vec<Address>,
+}
+
+/// Look up an actor in the state tree and decode its header and state into JSON.
+///
+/// Actors are recognised by their code in the builtin actor manifest of the system actor,
+/// or by their ID in case of the custom actors. Collections such as HAMTs, AMTs and the
+/// storage of EVM contracts are listed up to `limit` entries, along with their total count.
+pub fn inspect_actor<DB>(
+    store: &DB,
+    state_root: &Cid,
+    addr: &Address,
+    limit: usize,
+) -> anyhow::Result<Value>
+where
+    DB: Blockstore,
+{
+    let state_tree = StateTree::new_from_root(store, state_root)
+        .with_context(|| format!("failed to load state tree {state_root}"))?;
+
+    let id = state_tree
+        .lookup_id(addr)
+        .with_context(|| format!("failed to look up {addr}"))?
+        .ok_or_else(|| anyhow!("actor {addr} not found"))?;
+
+    let actor = state_tree
+        .get_actor(id)
+        .with_context(|| format!("failed to get actor {id}"))?
+        .ok_or_else(|| anyhow!("actor {id} not found"))?;
+
+    let name = actor_name(store, &state_tree, id, &actor.code)?;
+
+    let state = decode_state(store, name.as_deref(), &actor, limit)
+        .with_context(|| format!("failed to decode the state of actor {id}"))?;
+
+    Ok(json!({
+        "id": id,
+        "address": Address::new_id(id).to_string(),
+        "delegated_address": actor.delegated_address.map(|a| a.to_string()),
+        "eth_address": eth_address(id, actor.delegated_address.as_ref()),
+        "actor": name,
+        "code": actor.code.to_string(),
+        "state_cid": actor.state.to_string(),
+        "balance": actor.balance.atto().to_string(),
+        "sequence": actor.sequence,
+        "state": state,
+    }))
+}
+
+/// Name of the actor in the builtin actor manifest, or of the custom actor deployed at the ID.
+fn actor_name<DB>(
+    store: &DB,
+    state_tree: &StateTree<&DB>,
+    id: ActorID,
+    code: &Cid,
+) -> anyhow::Result<Option<String>>
+where
+    DB: Blockstore,
+{
+    let system = state_tree
+        .get_actor(system::SYSTEM_ACTOR_ID)
+        .context("failed to get the system actor")?
+        .ok_or_else(|| anyhow!("system actor not found"))?;
+
+    let system = load::<_, system::State>(store, &system.state)?;
+    let manifest = load::<_, Vec<(String, Cid)>>(store, &system.builtin_actors)?;
+
+    if let Some((name, _)) = manifest.into_iter().find(|(_, c)| c == code) {
+        return Ok(Some(name));
+    }
+
+    // Custom actors are not in the manifest, but they are singletons.
+    let name = match id {
+        chainmetadata::CHAINMETADATA_ACTOR_ID => {
+            fendermint_actor_chainmetadata::CHAINMETADATA_ACTOR_NAME
+        }
+        gas_market::GAS_MARKET_ACTOR_ID => fendermint_actor_gas_market_eip1559::ACTOR_NAME,
+        activity::ACTIVITY_TRACKER_ACTOR_ID => {
+            fendermint_actor_activity_tracker::IPC_ACTIVITY_TRACKER_ACTOR_NAME
+        }
+        f3_light_client::F3_LIGHT_CLIENT_ACTOR_ID => {
+            fendermint_actor_f3_light_client::F3_LIGHT_CLIENT_ACTOR_NAME
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(name.to_string()))
+}
+
+fn decode_state<DB>(
+    store: &DB,
+    name: Option<&str>,
+    actor: &ActorState,
+    limit: usize,
+) -> anyhow::Result<Value>
+where
+    DB: Blockstore,
+{
+    let cid = &actor.state;
+
+    let state = match name {
+        Some("system") => {
+            let state = load::<_, system::State>(store, cid)?;
+            let manifest = load::<_, Vec<(String, Cid)>>(store, &state.builtin_actors)?;
+            let manifest = manifest
+                .into_iter()
+                .map(|(name, code)| json!({ "name": name, "code": code.to_string() }))
+                .collect::<Vec<_>>();
+            json!({ "builtin_actors": manifest })
+        }
+        Some("init") => {
+            let state = load::<_, init::State>(store, cid)?;
+            let hamt =
+                Hamt::<_, ActorID>::load_with_bit_width(&state.address_map, store, HAMT_BIT_WIDTH)?;
+            let mut entries = Entries::new(limit);
+            hamt.for_each(|k: &BytesKey, id| {
+                entries.push(|| json!({ "address": key_to_address(k), "id": id }));
+                Ok(())
+            })?;
+            json!({
+                "network_name": state.network_name,
+                "next_id": state.next_id,
+                "address_map": entries.into_json(),
+            })
+        }
+        Some("cron") => {
+            let state = load::<_, cron::State>(store, cid)?;
+            let entries = state
+                .entries
+                .into_iter()
+                .map(|e| json!({ "receiver": e.receiver.to_string(), "method_num": e.method_num }))
+                .collect::<Vec<_>>();
+            json!({ "entries": entries })
+        }
+        Some("account") => {
+            let state = load::<_, account::State>(store, cid)?;
+            json!({ "address": state.address.to_string() })
+        }
+        Some("multisig") => {
+            let state = load::<_, multisig::State>(store, cid)?;
+            let hamt = Hamt::<_, MultisigTransaction>::load_with_bit_width(
+                &state.pending_txs,
+                store,
+                HAMT_BIT_WIDTH,
+            )?;
+            let mut entries = Entries::new(limit);
+            hamt.for_each(|k: &BytesKey, tx| {
+                entries.push(|| {
+                    json!({
+                        "id": i64::decode_var(&k.0).map(|(id, _)| id),
+                        "to": tx.to.to_string(),
+                        "value": tx.value.atto().to_string(),
+                        "method": tx.method,
+                        "params": to_hex(tx.params.bytes()),
+                        "approved": tx.approved.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                    })
+                });
+                Ok(())
+            })?;
+            json!({
+                "signers": state.signers.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                "num_approvals_threshold": state.num_approvals_threshold,
+                "next_tx_id": state.next_tx_id.0,
+                "initial_balance": state.initial_balance.atto().to_string(),
+                "start_epoch": state.start_epoch,
+                "unlock_duration": state.unlock_duration,
+                "pending_txs": entries.into_json(),
+            })
+        }
+        Some("evm") => {
+            let state = load::<_, fil_actor_evm::State>(store, cid)?;
+            let bytecode = store
+                .get(&state.bytecode)?
+                .ok_or_else(|| anyhow!("bytecode {} not found", state.bytecode))?;
+            let kamt =
+                StateKamt::load_with_config(&state.contract_state, store, KAMT_CONFIG.clone())
+                    .with_context(|| {
+                        format!("failed to load contract storage {}", state.contract_state)
+                    })?;
+            let mut entries = Entries::new(limit);
+            kamt.for_each(|k, v| {
+                entries.push(|| json!({ "slot": format!("{k:#x}"), "value": format!("{v:#x}") }));
+                Ok(())
+            })?;
+            json!({
+                "nonce": state.nonce,
+                "tombstone": state.tombstone.map(|t| json!({ "origin": t.origin, "nonce": t.nonce })),
+                "bytecode_cid": state.bytecode.to_string(),
+                "bytecode": to_hex(&bytecode),
+                "storage": entries.into_json(),
+            })
+        }
+        Some(fendermint_actor_chainmetadata::CHAINMETADATA_ACTOR_NAME) => {
+            let state = load::<_, fendermint_actor_chainmetadata::State>(store, cid)?;
+            let amt = Amt::<fendermint_actor_chainmetadata::BlockHash, _>::load(
+                &state.blockhashes,
+                store,
+            )?;
+            let mut entries = Entries::new(limit);
+            amt.for_each(|epoch, hash| {
+                entries.push(|| json!({ "epoch": epoch, "block_hash": to_hex(hash) }));
+                Ok(())
+            })?;
+            json!({
+                "lookback_len": state.lookback_len,
+                "blockhashes": entries.into_json(),
+            })
+        }
+        Some(fendermint_actor_gas_market_eip1559::ACTOR_NAME) => {
+            let state = load::<_, fendermint_actor_gas_market_eip1559::State>(store, cid)?;
+            json!({
+                "base_fee": state.base_fee.atto().to_string(),
+                "constants": {
+                    "block_gas_limit": state.constants.block_gas_limit,
+                    "minimal_base_fee": state.constants.minimal_base_fee.atto().to_string(),
+                    "elasticity_multiplier": state.constants.elasticity_multiplier,
+                    "base_fee_max_change_denominator": state.constants.base_fee_max_change_denominator,
+                },
+            })
+        }
+        Some(fendermint_actor_activity_tracker::IPC_ACTIVITY_TRACKER_ACTOR_NAME) => {
+            let state = load::<_, fendermint_actor_activity_tracker::State>(store, cid)?;
+            let hamt = Hamt::<_, ValidatorStats>::load_with_bit_width(
+                &state.consensus,
+                store,
+                HAMT_BIT_WIDTH,
+            )?;
+            let mut entries = Entries::new(limit);
+            hamt.for_each(|k: &BytesKey, stats| {
+                entries.push(|| {
+                    json!({
+                        "validator": key_to_address(k),
+                        "blocks_committed": stats.blocks_committed,
+                    })
+                });
+                Ok(())
+            })?;
+            json!({
+                "tracking_since": state.tracking_since,
+                "consensus": entries.into_json(),
+            })
+        }
+        Some(fendermint_actor_f3_light_client::F3_LIGHT_CLIENT_ACTOR_NAME) => {
+            let state = load::<_, fendermint_actor_f3_light_client::state::State>(store, cid)?;
+            let lcs = state.light_client_state;
+            let power_table = lcs
+                .power_table
+                .iter()
+                .map(|e| json!({ "public_key": to_hex(&e.public_key), "power": e.power }))
+                .collect::<Vec<_>>();
+            json!({
+                "instance_id": lcs.instance_id,
+                "finalized_epochs": lcs.finalized_epochs,
+                "power_table": power_table,
+            })
+        }
+        _ => {
+            // Anything we don't know the structure of, e.g. the EAM or the placeholders,
+            // is shown as generic IPLD.
+            let bytes = store
+                .get(cid)?
+                .ok_or_else(|| anyhow!("state {cid} not found"))?;
+
+            if cid.codec() != DAG_CBOR {
+                return Ok(json!(to_hex(&bytes)));
+            }
+
+            use libipld::cbor::DagCborCodec;
+            use libipld::codec::Codec;
+
+            let ipld = DagCborCodec
+                .decode::<Ipld>(&bytes)
+                .map_err(|e| anyhow!("failed to decode state {cid}: {e}"))?;
+
+            ipld_to_json(ipld)
+        }
+    };
+
+    Ok(state)
+}
+
+/// Collects the first `limit` entries of a collection, but counts all of them.
+struct Entries {
+    limit: usize,
+    count: usize,
+    items: Vec<Value>,
+}
+
+impl Entries {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            count: 0,
+            items: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, f: impl FnOnce() -> Value) {
+        if self.count < self.limit {
+            self.items.push(f());
+        }
+        self.count += 1;
+    }
+
+    fn into_json(self) -> Value {
+        json!({ "count": self.count, "entries": self.items })
+    }
+}
+
+fn load<DB, T>(store: &DB, cid: &Cid) -> anyhow::Result<T>
+where
+    DB: Blockstore,
+    T: DeserializeOwned,
+{
+    store
+        .get_cbor(cid)
+        .with_context(|| format!("failed to decode {cid}"))?
+        .ok_or_else(|| anyhow!("{cid} not found"))
+}
+
+/// HAMTs keyed by addresses use the bytes of the address as the key.
+fn key_to_address(k: &BytesKey) -> String {
+    match Address::from_bytes(&k.0) {
+        Ok(a) => a.to_string(),
+        Err(_) => to_hex(&k.0),
+    }
+}
+
+/// The Ethereum address of an actor: the delegated one if it has it, otherwise the masked ID.
+fn eth_address(id: ActorID, delegated_address: Option<&Address>) -> String {
+    let addr = match delegated_address.map(|a| a.payload()) {
+        Some(Payload::Delegated(d))
+            if d.namespace() == EAM_ACTOR_ID && d.subaddress().len() == 20 =>
+        {
+            EthAddress(d.subaddress().try_into().expect("checked length"))
+        }
+        _ => EthAddress::from_id(id),
+    };
+    to_hex(&addr.0)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Convert generic IPLD into JSON, showing bytes as hex and links in the DAG-JSON form.
+fn ipld_to_json(ipld: Ipld) -> Value {
+    match ipld {
+        Ipld::Null => Value::Null,
+        Ipld::Bool(b) => Value::Bool(b),
+        Ipld::Integer(i) => match i64::try_from(i) {
+            Ok(i) => json!(i),
+            Err(_) => json!(i.to_string()),
+        },
+        Ipld::Float(f) => json!(f),
+        Ipld::String(s) => Value::String(s),
+        Ipld::Bytes(b) => Value::String(to_hex(&b)),
+        Ipld::List(l) => Value::Array(l.into_iter().map(ipld_to_json).collect()),
+        Ipld::Map(m) => Value::Object(
+            m.into_iter()
+                .map(|(k, v)| (k, ipld_to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        Ipld::Link(c) => json!({ "/": c.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm::state_tree::ActorState;
+    use fvm_ipld_encoding::{CborStore, RawBytes};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use multihash_codetable::Code;
+    use serde_json::json;
+
+    use super::inspect_actor;
+    use crate::fvm::state::empty_state_tree;
+    use crate::fvm::store::memory::MemoryBlockstore;
+    use fendermint_vm_actor_interface::{account, system};
+
+    #[test]
+    fn decodes_known_and_unknown_actors() {
+        let store = MemoryBlockstore::new();
+
+        let account_code = store.put_cbor(&"account", Code::Blake2b256).unwrap();
+        let unknown_code = store.put_cbor(&"unknown", Code::Blake2b256).unwrap();
+
+        let manifest = store
+            .put_cbor(
+                &vec![("account".to_string(), account_code)],
+                Code::Blake2b256,
+            )
+            .unwrap();
+
+        let actor = |code, state| ActorState {
+            code,
+            state,
+            sequence: 0,
+            balance: TokenAmount::from_atto(10),
+            delegated_address: None,
+        };
+
+        let system_state = store
+            .put_cbor(
+                &system::State {
+                    builtin_actors: manifest,
+                },
+                Code::Blake2b256,
+            )
+            .unwrap();
+        let account_state = store
+            .put_cbor(
+                &account::State {
+                    address: Address::new_id(100),
+                },
+                Code::Blake2b256,
+            )
+            .unwrap();
+        let unknown_state = store
+            .put_cbor(&(1u64, RawBytes::new(vec![0xab, 0xcd])), Code::Blake2b256)
+            .unwrap();
+
+        let mut state_tree = empty_state_tree(store.clone()).unwrap();
+        state_tree.set_actor(system::SYSTEM_ACTOR_ID, actor(unknown_code, system_state));
+        state_tree.set_actor(100, actor(account_code, account_state));
+        state_tree.set_actor(101, actor(unknown_code, unknown_state));
+        let root = state_tree.flush().unwrap();
+
+        let value = inspect_actor(&store, &root, &Address::new_id(100), 10).unwrap();
+        assert_eq!(value["actor"], json!("account"));
+        assert_eq!(value["balance"], json!("10"));
+        assert_eq!(
+            value["state"],
+            json!({ "address": Address::new_id(100).to_string() })
+        );
+
+        let value = inspect_actor(&store, &root, &Address::new_id(101), 10).unwrap();
+        assert_eq!(value["actor"], json!(null));
+        assert_eq!(value["state"], json!([1, "0xabcd"]));
+
+        assert!(inspect_actor(&store, &root, &Address::new_id(102), 10).is_err());
+    }
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::Context;
+use ethers::abi::{Token, Tokenizable};
+use ethers::utils::keccak256;
 
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::econ::TokenAmount;
@@ -17,7 +19,7 @@ use fendermint_vm_message::conv::from_eth;
 use fendermint_vm_topdown::IPCParentFinality;
 
 use super::{
-    fevm::{ContractCaller, MockProvider, NoRevert},
+    fevm::{ContractCaller, ContractResult, MockProvider, NoRevert},
     FvmExecState,
 };
 use crate::fvm::end_block_hook::LightClientCommitments;
@@ -201,6 +203,15 @@ impl<DB: Blockstore + Clone> GatewayCaller<DB> {
             .call(state, |c| c.store_validator_changes(change_requests))
     }
 
+    /// Get the next and the start configuration numbers of the validator changes received from the parent.
+    pub fn tracker_configuration_numbers(
+        &self,
+        state: &mut FvmExecState<DB>,
+    ) -> anyhow::Result<(ConfigurationNumber, ConfigurationNumber)> {
+        self.topdown
+            .call(state, |c| c.get_tracker_configuration_numbers())
+    }
+
     /// Call this function to mint some FIL to the gateway contract
     pub fn mint_to_gateway(
         &self,
@@ -231,6 +242,42 @@ impl<DB: Blockstore + Clone> GatewayCaller<DB> {
         Ok(r.into_return())
     }
 
+    /// Apply a single cross message, returning the failure rather than an error if the
+    /// gateway reverts, so the caller can carry on with the rest of the messages.
+    pub fn try_apply_cross_message(
+        &self,
+        state: &mut FvmExecState<DB>,
+        cross_message: IpcEnvelope,
+    ) -> anyhow::Result<ContractResult<AppliedMessage, NoRevert>> {
+        let message = xnet_messaging_facet::IpcEnvelope::try_from(cross_message)
+            .context("failed to convert cross message")?;
+        let r = self
+            .xnet
+            .try_call_with_ret(state, |c| c.apply_cross_messages(vec![message]))?;
+        Ok(r.map(|r| r.into_return()))
+    }
+
+    /// Skip a top-down message that failed to apply, consuming its nonce and sending a
+    /// `SystemErr` receipt with the revert data back to its sender.
+    pub fn reject_cross_message(
+        &self,
+        state: &mut FvmExecState<DB>,
+        cross_message: IpcEnvelope,
+        reason: Vec<u8>,
+    ) -> anyhow::Result<AppliedMessage> {
+        let message = xnet_messaging_facet::IpcEnvelope::try_from(cross_message)
+            .context("failed to convert cross message")?;
+        let r = self.xnet.call_with_return(state, |c| {
+            c.reject_cross_message(message, ethers::types::Bytes::from(reason))
+        })?;
+        Ok(r.into_return())
+    }
+
+    /// The nonce of the next top-down message the gateway expects to apply.
+    pub fn applied_top_down_nonce(&self, state: &mut FvmExecState<DB>) -> anyhow::Result<u64> {
+        self.getter.call(state, |c| c.applied_top_down_nonce())
+    }
+
     pub fn get_latest_parent_finality(
         &self,
         state: &mut FvmExecState<DB>,
@@ -265,6 +312,20 @@ pub fn tokens_to_mint(msgs: &[ipc_api::cross::IpcEnvelope]) -> TokenAmount {
         })
 }
 
+/// The ID the gateway traces a cross message by, which is the same as `CrossMsgHelper.toTracingId`.
+pub fn cross_msg_id(msg: &ipc_api::cross::IpcEnvelope) -> anyhow::Result<[u8; 32]> {
+    let msg = xnet_messaging_facet::IpcEnvelope::try_from(msg.clone())?;
+    let data = ethers::abi::encode(&[
+        Token::Uint(msg.kind.into()),
+        msg.to.into_token(),
+        msg.from.into_token(),
+        Token::Uint(msg.value),
+        Token::Bytes(msg.message.to_vec()),
+        Token::Uint(msg.original_nonce.into()),
+    ]);
+    Ok(keccak256(data))
+}
+
 /// Total amount of tokens to burn as a result of bottom-up messages leaving the subnet.
 pub fn tokens_to_burn(msgs: &[gateway_getter_facet::IpcEnvelope]) -> TokenAmount {
     msgs.iter()
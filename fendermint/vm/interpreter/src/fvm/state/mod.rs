@@ -1,7 +1,9 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod diff;
 pub mod fevm;
+pub mod inspect;
 pub mod ipc;
 pub mod snapshot;
 
@@ -16,7 +18,7 @@ use std::sync::Arc;
 pub use check::FvmCheckState;
 pub use exec::{BlockHash, FvmExecState, FvmStateParams, FvmUpdatableParams};
 pub use genesis::{empty_state_tree, FvmGenesisState};
-pub use query::FvmQueryState;
+pub use query::{trace_message, FvmQueryState};
 
 use super::store::ReadOnlyBlockstore;
 
@@ -6,7 +6,8 @@ use std::{cell::RefCell, sync::Arc};
 
 use anyhow::{anyhow, Context};
 
-use super::{FvmExecState, FvmStateParams};
+use super::fevm::evm_actor_state;
+use super::{BlockHash, FvmExecState, FvmStateParams};
 use crate::fvm::{state::CheckStateRef, store::ReadOnlyBlockstore, FvmMessage};
 use cid::Cid;
 use fendermint_vm_actor_interface::eam::EAM_ACTOR_ADDR;
@@ -14,14 +15,18 @@ use fendermint_vm_actor_interface::system::{
     is_system_addr, State as SystemState, SYSTEM_ACTOR_ADDR,
 };
 use fendermint_vm_core::chainid::HasChainID;
-use fendermint_vm_message::query::ActorState;
+use fendermint_vm_core::Timestamp;
+use fendermint_vm_message::query::{ActorState, CallTrace, MessageTrace};
 use fil_actor_eam::CreateExternalReturn;
 use fvm::engine::MultiEngine;
 use fvm::executor::ApplyRet;
+use fvm::kernel::SyscallError;
 use fvm::state_tree::StateTree;
+use fvm::trace::ExecutionEvent;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{from_slice, CborStore, RawBytes};
-use fvm_shared::{address::Address, chainid::ChainID, clock::ChainEpoch, ActorID};
+use fvm_shared::error::ExitCode;
+use fvm_shared::{address::Address, chainid::ChainID, clock::ChainEpoch, ActorID, METHOD_SEND};
 use num_traits::Zero;
 
 use crate::fvm::constants::BLOCK_GAS_LIMIT;
@@ -225,6 +230,34 @@ where
         .await
     }
 
+    /// Create an execution state with tracing enabled, to replay the messages of the block at
+    /// `block_height` over the state left by its parent, which is the state we are querying.
+    ///
+    /// Like with [`call`](Self::call), the results are never flushed. The base fee is the one
+    /// the parent left for the block to use.
+    pub fn tracing_exec_state(
+        &self,
+        block_height: ChainEpoch,
+        timestamp: Timestamp,
+        block_hash: Option<BlockHash>,
+    ) -> anyhow::Result<FvmExecState<ReadOnlyBlockstore<DB>>> {
+        let mut state_params = self.state_params.clone();
+        state_params.timestamp = timestamp;
+
+        let exec_state = FvmExecState::new_with_tracing(
+            self.store.clone(),
+            self.multi_engine.as_ref(),
+            block_height,
+            state_params,
+        )
+        .context("error creating execution state")?;
+
+        Ok(match block_hash {
+            Some(block_hash) => exec_state.with_block_hash(block_hash),
+            None => exec_state,
+        })
+    }
+
     pub fn state_params(&self) -> &FvmStateParams {
         &self.state_params
     }
@@ -262,6 +295,45 @@ where
     }
 }
 
+/// Execute a message in a state created with tracing enabled and collect its trace.
+///
+/// Unlike with [`FvmQueryState::call`], the message is executed as it is, without adjusting
+/// its sequence or gas limit, so that it reproduces what happened when it was included in a block.
+pub fn trace_message<DB>(
+    exec_state: &mut FvmExecState<DB>,
+    msg: FvmMessage,
+) -> anyhow::Result<MessageTrace>
+where
+    DB: Blockstore + Clone + 'static,
+{
+    let from = msg.from;
+    let sequence = msg.sequence;
+
+    let (mut ret, _) = if is_system_addr(&msg.from) {
+        exec_state.execute_implicit(msg)?
+    } else {
+        exec_state.execute_explicit(msg)?
+    };
+
+    let mut trace = to_call_trace(std::mem::take(&mut ret.exec_trace));
+
+    if let Some(ref mut trace) = trace {
+        let state_tree = exec_state.state_tree();
+        resolve_addresses(state_tree, trace, &mut HashMap::new())?;
+        if let Some(origin) = state_tree.lookup_id(&from)? {
+            mark_self_destructs(state_tree, trace, origin, sequence)?;
+        }
+    }
+
+    Ok(MessageTrace {
+        exit_code: ret.msg_receipt.exit_code,
+        info: ret.failure_info.map(|f| f.to_string()).unwrap_or_default(),
+        return_data: ret.msg_receipt.return_data,
+        gas_used: ret.msg_receipt.gas_used,
+        trace,
+    })
+}
+
 fn get_actor_state<DB>(
     state_tree: &StateTree<DB>,
     addr: &Address,
@@ -284,3 +356,222 @@ where
         Ok(None)
     }
 }
+
+/// Reconstruct the tree of calls from the flat list of events in the execution trace.
+fn to_call_trace(events: Vec<ExecutionEvent>) -> Option<CallTrace> {
+    let mut stack: Vec<CallTrace> = Vec::new();
+    let mut root = None;
+
+    for event in events {
+        let finished = match event {
+            ExecutionEvent::GasCharge(charge) => {
+                if let Some(call) = stack.last_mut() {
+                    call.gas_used += charge.total().round_up();
+                }
+                None
+            }
+            ExecutionEvent::Call {
+                from,
+                to,
+                method,
+                params,
+                value,
+                gas_limit,
+                read_only,
+                ..
+            } => {
+                stack.push(CallTrace {
+                    from: Address::new_id(from),
+                    to,
+                    method_num: method,
+                    value,
+                    params: params.map(|p| RawBytes::new(p.data)).unwrap_or_default(),
+                    gas_limit,
+                    gas_used: 0,
+                    read_only,
+                    exit_code: ExitCode::OK,
+                    return_data: RawBytes::default(),
+                    error: None,
+                    self_destruct: false,
+                    calls: Vec::new(),
+                });
+                None
+            }
+            ExecutionEvent::CallReturn(exit_code, data) => stack.pop().map(|mut call| {
+                call.exit_code = exit_code;
+                call.return_data = data.map(|d| RawBytes::new(d.data)).unwrap_or_default();
+                call
+            }),
+            ExecutionEvent::CallError(SyscallError(msg, _)) => stack.pop().map(|mut call| {
+                call.error = Some(msg);
+                call
+            }),
+            _ => None,
+        };
+
+        if let Some(call) = finished {
+            match stack.last_mut() {
+                Some(parent) => {
+                    parent.gas_used += call.gas_used;
+                    parent.calls.push(call);
+                }
+                None => root = Some(call),
+            }
+        }
+    }
+
+    root
+}
+
+/// Replace the addresses in the calls with delegated ones where possible,
+/// so they can be presented as Ethereum addresses.
+fn resolve_addresses<DB>(
+    state_tree: &StateTree<DB>,
+    call: &mut CallTrace,
+    cache: &mut HashMap<Address, Address>,
+) -> anyhow::Result<()>
+where
+    DB: Blockstore,
+{
+    call.from = resolve_address(state_tree, &call.from, cache)?;
+    call.to = resolve_address(state_tree, &call.to, cache)?;
+    for call in call.calls.iter_mut() {
+        resolve_addresses(state_tree, call, cache)?;
+    }
+    Ok(())
+}
+
+fn resolve_address<DB>(
+    state_tree: &StateTree<DB>,
+    addr: &Address,
+    cache: &mut HashMap<Address, Address>,
+) -> anyhow::Result<Address>
+where
+    DB: Blockstore,
+{
+    if let Some(resolved) = cache.get(addr) {
+        return Ok(*resolved);
+    }
+    let resolved = match state_tree.lookup_id(addr)? {
+        Some(id) => state_tree
+            .get_actor(id)?
+            .and_then(|st| st.delegated_address)
+            .unwrap_or_else(|| Address::new_id(id)),
+        None => *addr,
+    };
+    cache.insert(*addr, resolved);
+    Ok(resolved)
+}
+
+/// Mark the last value transfer of each EVM actor that self-destructed during the message.
+///
+/// The FVM trace has no dedicated event for it: the EVM actor sends its balance to the
+/// beneficiary and leaves a tombstone with the origin and the nonce of the message.
+fn mark_self_destructs<DB>(
+    state_tree: &StateTree<DB>,
+    call: &mut CallTrace,
+    origin: ActorID,
+    nonce: u64,
+) -> anyhow::Result<()>
+where
+    DB: Blockstore,
+{
+    // Path to the last transfer made by each actor.
+    let mut transfers = HashMap::new();
+    collect_transfers(call, &mut Vec::new(), &mut transfers);
+
+    for (from, path) in transfers {
+        if is_self_destructed(state_tree, &from, origin, nonce)? {
+            let mut call = &mut *call;
+            for i in path {
+                call = &mut call.calls[i];
+            }
+            call.self_destruct = true;
+        }
+    }
+    Ok(())
+}
+
+fn collect_transfers(
+    call: &CallTrace,
+    path: &mut Vec<usize>,
+    transfers: &mut HashMap<Address, Vec<usize>>,
+) {
+    if call.method_num == METHOD_SEND && call.exit_code.is_success() && call.error.is_none() {
+        transfers.insert(call.from, path.clone());
+    }
+    for (i, call) in call.calls.iter().enumerate() {
+        path.push(i);
+        collect_transfers(call, path, transfers);
+        path.pop();
+    }
+}
+
+fn is_self_destructed<DB>(
+    state_tree: &StateTree<DB>,
+    addr: &Address,
+    origin: ActorID,
+    nonce: u64,
+) -> anyhow::Result<bool>
+where
+    DB: Blockstore,
+{
+    let Some(id) = state_tree.lookup_id(addr)? else {
+        return Ok(false);
+    };
+    let Some(actor) = state_tree.get_actor(id)? else {
+        return Ok(false);
+    };
+    let Some(state) = evm_actor_state(state_tree.store(), &actor) else {
+        return Ok(false);
+    };
+    Ok(state
+        .tombstone
+        .is_some_and(|t| t.origin == origin && t.nonce == nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm::trace::ExecutionEvent;
+    use fvm_shared::{address::Address, econ::TokenAmount, error::ExitCode};
+
+    use super::to_call_trace;
+
+    fn call(from: u64, to: u64) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from,
+            to: Address::new_id(to),
+            method: 0,
+            params: None,
+            value: TokenAmount::from_atto(1),
+            gas_limit: 1000,
+            read_only: false,
+        }
+    }
+
+    fn ret(exit_code: ExitCode) -> ExecutionEvent {
+        ExecutionEvent::CallReturn(exit_code, None)
+    }
+
+    #[test]
+    fn builds_call_tree() {
+        let events = vec![
+            call(100, 101),
+            call(101, 102),
+            ret(ExitCode::OK),
+            call(101, 103),
+            call(103, 104),
+            ret(ExitCode::USR_FORBIDDEN),
+            ret(ExitCode::OK),
+            ret(ExitCode::OK),
+        ];
+
+        let root = to_call_trace(events).expect("should have a root");
+
+        assert_eq!(root.from, Address::new_id(100));
+        assert_eq!(root.calls.len(), 2);
+        assert_eq!(root.calls[0].to, Address::new_id(102));
+        assert_eq!(root.calls[1].calls.len(), 1);
+        assert_eq!(root.calls[1].calls[0].exit_code, ExitCode::USR_FORBIDDEN);
+    }
+}
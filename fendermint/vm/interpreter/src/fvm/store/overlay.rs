@@ -0,0 +1,78 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Result;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+use super::memory::MemoryBlockstore;
+
+/// A blockstore which reads through to an underlying store, but keeps everything
+/// written to it in memory, leaving the underlying store untouched.
+///
+/// This is useful to execute messages on top of the database of a node,
+/// e.g. to replay blocks for debugging, without persisting the results.
+#[derive(Clone)]
+pub struct OverlayBlockstore<DB> {
+    base: DB,
+    overlay: MemoryBlockstore,
+}
+
+impl<DB> OverlayBlockstore<DB> {
+    pub fn new(base: DB) -> Self {
+        Self {
+            base,
+            overlay: MemoryBlockstore::new(),
+        }
+    }
+}
+
+impl<DB> Blockstore for OverlayBlockstore<DB>
+where
+    DB: Blockstore,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        match self.overlay.get(k)? {
+            Some(block) => Ok(Some(block)),
+            None => self.base.get(k),
+        }
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        self.overlay.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        Ok(self.overlay.has(k)? || self.base.has(k)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use fvm_ipld_blockstore::Blockstore;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::OverlayBlockstore;
+    use crate::fvm::store::memory::MemoryBlockstore;
+
+    fn block(data: &[u8]) -> (Cid, Vec<u8>) {
+        let cid = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, Code::Blake2b256.digest(data));
+        (cid, data.to_vec())
+    }
+
+    #[test]
+    fn writes_do_not_reach_the_base() {
+        let base = MemoryBlockstore::new();
+        let (k1, v1) = block(b"base");
+        let (k2, v2) = block(b"overlay");
+        base.put_keyed(&k1, &v1).unwrap();
+
+        let store = OverlayBlockstore::new(base.clone());
+        store.put_keyed(&k2, &v2).unwrap();
+
+        assert_eq!(store.get(&k1).unwrap(), Some(v1));
+        assert_eq!(store.get(&k2).unwrap(), Some(v2));
+        assert!(!base.has(&k2).unwrap());
+    }
+}
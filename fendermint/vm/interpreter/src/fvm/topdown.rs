@@ -1,7 +1,8 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use async_stm::atomically;
+use actors_custom_api::gas_market::Gas;
+use async_stm::{atomically, Stm};
 use fendermint_tracing::emit;
 use fendermint_vm_event::ParentFinalityMissingQuorum;
 use fendermint_vm_message::chain::ChainMessage;
@@ -11,7 +12,7 @@ use fendermint_vm_topdown::proxy::IPCProviderProxyWithLatency;
 use fendermint_vm_topdown::voting::ValidatorKey;
 use fendermint_vm_topdown::voting::VoteTally;
 use fendermint_vm_topdown::{
-    BlockHeight, CachedFinalityProvider, IPCParentFinality, ParentFinalityProvider,
+    BlockHash, BlockHeight, CachedFinalityProvider, IPCParentFinality, ParentFinalityProvider,
     ParentViewProvider, Toggle,
 };
 use fvm_shared::clock::ChainEpoch;
@@ -23,12 +24,25 @@ use anyhow::{bail, Context};
 use fvm_ipld_blockstore::Blockstore;
 
 use crate::fvm::end_block_hook::PowerUpdates;
-use crate::fvm::state::ipc::tokens_to_mint;
-use crate::types::AppliedMessage;
+use crate::fvm::state::fevm::ContractError;
+use crate::fvm::state::ipc::{cross_msg_id, tokens_to_mint};
+use crate::types::{AppliedMessage, TopDownReceipt};
+use fvm_ipld_encoding::BytesDe;
 use ipc_api::cross::IpcEnvelope;
 
 type TopDownFinalityProvider = Arc<Toggle<CachedFinalityProvider<IPCProviderProxyWithLatency>>>;
 
+/// The app version from which top-down messages are applied one by one, see [apply_topdown_msgs].
+///
+/// Chains upgrading to this version have to add `rejectCrossMessage` to the `XnetMessagingFacet`
+/// of their gateway as part of the migration; gateways deployed at genesis already have it.
+pub const PER_MESSAGE_TOPDOWN_APP_VERSION: u64 = 1;
+
+/// Gas we assume a top-down message costs when fitting the parent blocks into the gas budget.
+///
+/// This has to be the same on every node, so that proposers and validators agree on the budget.
+const TOPDOWN_MSG_GAS_ESTIMATE: Gas = 10_000_000;
+
 #[derive(Clone)]
 pub struct TopDownManager<DB>
 where
@@ -38,6 +52,8 @@ where
     votes: VoteTally,
     // Gateway caller for IPC gateway interactions
     gateway_caller: GatewayCaller<DB>,
+    /// Maximum estimated gas the top-down messages in our proposals can use.
+    gas_budget: Option<Gas>,
 }
 
 impl<DB> TopDownManager<DB>
@@ -49,15 +65,44 @@ where
             provider,
             votes,
             gateway_caller: GatewayCaller::default(),
+            gas_budget: None,
         }
     }
 
-    pub async fn is_finality_valid(&self, finality: ParentFinality) -> bool {
+    /// Limit the estimated gas of the top-down messages we propose to execute in a single block,
+    /// deferring the rest of the parent blocks to the next proposals.
+    ///
+    /// The budget is capped at the block gas limit. It only applies to our own proposals,
+    /// because other validators may have configured a different one.
+    pub fn with_gas_budget(mut self, gas_budget: Gas) -> Self {
+        self.gas_budget = Some(gas_budget);
+        self
+    }
+
+    /// Check that the proposed finality is known to us and that its top-down messages fit into
+    /// the block gas limit of the chain.
+    pub async fn is_finality_valid(&self, finality: ParentFinality, block_gas_limit: Gas) -> bool {
         let prop = IPCParentFinality {
             height: finality.height as u64,
             block_hash: finality.block_hash,
         };
-        atomically(|| self.provider.check_proposal(&prop)).await
+        atomically(|| {
+            if !self.provider.check_proposal(&prop)? {
+                return Ok(false);
+            }
+            let limited = self.limit_to_gas_budget(prop.clone(), block_gas_limit)?;
+            if limited.height < prop.height {
+                tracing::debug!(
+                    height = prop.height,
+                    limited_height = limited.height,
+                    block_gas_limit,
+                    "rejecting top-down proposal over the gas budget"
+                );
+                return Ok(false);
+            }
+            Ok(true)
+        })
+        .await
     }
 
     /// Prepares a top-down execution message based on the current parent's finality proposal and quorum.
@@ -65,8 +110,12 @@ where
     /// This function first pauses incoming votes to prevent interference during processing. It then atomically retrieves
     /// both the next parent's proposal and the quorum of votes. If either the parent's proposal or the quorum is missing,
     /// the function returns `None`. When both are available, it selects the finality with the lower block height and wraps
-    /// it into a `ChainMessage` for top-down execution.
-    pub async fn chain_message_from_finality_or_quorum(&self) -> Option<ChainMessage> {
+    /// it into a `ChainMessage` for top-down execution, deferring the parent blocks whose messages would not fit
+    /// into the gas budget, which is at most the block gas limit of the chain.
+    pub async fn chain_message_from_finality_or_quorum(
+        &self,
+        block_gas_limit: Gas,
+    ) -> Option<ChainMessage> {
         // Prepare top down proposals.
         // Before we try to find a quorum, pause incoming votes. This is optional but if there are lots of votes coming in it might hold up proposals.
         atomically(|| self.votes.pause_votes_until_find_quorum()).await;
@@ -110,12 +159,50 @@ where
             quorum
         };
 
+        // Defer the parent blocks whose messages would not fit into the gas budget.
+        let gas_budget = self
+            .gas_budget
+            .map_or(block_gas_limit, |b| b.min(block_gas_limit));
+        let finality = atomically(|| self.limit_to_gas_budget(finality.clone(), gas_budget)).await;
+
         Some(ChainMessage::Ipc(IpcMessage::TopDownExec(ParentFinality {
             height: finality.height as ChainEpoch,
             block_hash: finality.block_hash,
         })))
     }
 
+    /// Lower the finality so that the estimated gas of executing its top-down messages fits into
+    /// the budget, see [limit_blocks_to_gas_budget].
+    fn limit_to_gas_budget(
+        &self,
+        finality: IPCParentFinality,
+        gas_budget: Gas,
+    ) -> Stm<IPCParentFinality> {
+        let Some(committed) = self.provider.last_committed_finality()? else {
+            return Ok(finality);
+        };
+
+        let mut blocks = Vec::new();
+        for height in committed.height + 1..=finality.height {
+            let Some(count) = self.provider.top_down_msgs_count(height)? else {
+                break;
+            };
+            blocks.push((height, count, self.provider.block_hash(height)?));
+        }
+
+        let limited = limit_blocks_to_gas_budget(finality.clone(), blocks, gas_budget);
+
+        if limited.height < finality.height {
+            tracing::debug!(
+                height = finality.height,
+                limited_height = limited.height,
+                gas_budget,
+                "deferring top-down messages over the gas budget"
+            );
+        }
+        Ok(limited)
+    }
+
     pub async fn update_voting_power_table(&self, power_updates: &PowerUpdates) {
         let power_updates_mapped: Vec<_> = power_updates
             .0
@@ -131,7 +218,7 @@ where
         &self,
         state: &mut FvmExecState<DB>,
         finality: ParentFinality,
-    ) -> anyhow::Result<AppliedMessage> {
+    ) -> anyhow::Result<(AppliedMessage, Vec<TopDownReceipt>)> {
         if !self.provider.is_enabled() {
             bail!("cannot execute IPC top-down message: parent provider disabled");
         }
@@ -143,8 +230,73 @@ where
             "chain interpreter received topdown exec proposal",
         );
 
+        let ret = self
+            .apply_finality(&self.gateway_caller, state, finality.clone())
+            .await?;
+
+        let local_block_height = state.block_height() as u64;
+        let proposer = state
+            .block_producer()
+            .map(|id| hex::encode(id.serialize_compressed()));
+        let proposer_ref = proposer.as_deref();
+
+        atomically(|| {
+            self.provider.set_new_finality(finality.clone())?;
+
+            self.votes.set_finalized(
+                finality.height,
+                finality.block_hash.clone(),
+                proposer_ref,
+                Some(local_block_height),
+            )?;
+
+            Ok(())
+        })
+        .await;
+
+        tracing::debug!(
+            finality = finality.to_string(),
+            "chain interpreter has set new"
+        );
+
+        Ok(ret)
+    }
+
+    /// Replay the execution of a top-down finality on a state that is never committed, such as
+    /// one used for tracing, without marking it as finalized in the provider and the votes.
+    pub async fn replay_topdown_msg<S>(
+        &self,
+        state: &mut FvmExecState<S>,
+        finality: ParentFinality,
+    ) -> anyhow::Result<(AppliedMessage, Vec<TopDownReceipt>)>
+    where
+        S: Blockstore + Clone + 'static + Send + Sync,
+    {
+        if !self.provider.is_enabled() {
+            bail!("cannot replay IPC top-down message: parent provider disabled");
+        }
+
+        let finality = IPCParentFinality::new(finality.height, finality.block_hash);
+
+        self.apply_finality(&GatewayCaller::default(), state, finality)
+            .await
+    }
+
+    /// Commit the finality in the gateway, then store the validator changes and apply the
+    /// top-down messages from the parent blocks it finalizes.
+    async fn apply_finality<S>(
+        &self,
+        gateway_caller: &GatewayCaller<S>,
+        state: &mut FvmExecState<S>,
+        finality: IPCParentFinality,
+    ) -> anyhow::Result<(AppliedMessage, Vec<TopDownReceipt>)>
+    where
+        S: Blockstore + Clone + 'static + Send + Sync,
+    {
+        let implicit_gas_used = state.block_gas_tracker().implicit_gas_used();
+
         let (prev_height, prev_finality) = self
-            .commit_finality(state, finality.clone())
+            .commit_finality(gateway_caller, state, finality.clone())
             .await
             .context("failed to commit finality")?;
 
@@ -183,7 +335,7 @@ where
             "chain interpreter received total validator changes"
         );
 
-        self.gateway_caller
+        gateway_caller
             .store_validator_changes(state, validator_changes)
             .context("failed to store validator changes")?;
 
@@ -202,55 +354,39 @@ where
         );
 
         let ret = self
-            .execute_topdown_msgs(state, msgs)
+            .execute_topdown_msgs(gateway_caller, state, msgs)
             .await
             .context("failed to execute top down messages")?;
 
         tracing::debug!("chain interpreter applied topdown msgs");
 
-        let local_block_height = state.block_height() as u64;
-        let proposer = state
-            .block_producer()
-            .map(|id| hex::encode(id.serialize_compressed()));
-        let proposer_ref = proposer.as_deref();
-
-        atomically(|| {
-            self.provider.set_new_finality(finality.clone())?;
-
-            self.votes.set_finalized(
-                finality.height,
-                finality.block_hash.clone(),
-                proposer_ref,
-                Some(local_block_height),
-            )?;
-
-            Ok(())
-        })
-        .await;
-
-        tracing::debug!(
-            finality = finality.to_string(),
-            "chain interpreter has set new"
-        );
+        // The top-down execution takes the place of transactions in the block, so it counts towards
+        // the block gas limit, as opposed to other implicit executions.
+        let gas_used = state.block_gas_tracker().implicit_gas_used() - implicit_gas_used;
+        state
+            .block_gas_tracker_mut()
+            .record_topdown_utilization(gas_used);
 
         Ok(ret)
     }
 
     /// Commit the parent finality. Returns the height that the previous parent finality is committed and
     /// the committed finality itself. If there is no parent finality committed, genesis epoch is returned.
-    async fn commit_finality(
+    async fn commit_finality<S>(
         &self,
-        state: &mut FvmExecState<DB>,
+        gateway_caller: &GatewayCaller<S>,
+        state: &mut FvmExecState<S>,
         finality: IPCParentFinality,
-    ) -> anyhow::Result<(BlockHeight, Option<IPCParentFinality>)> {
-        let (prev_height, prev_finality) = if let Some(prev_finality) = self
-            .gateway_caller
-            .commit_parent_finality(state, finality)?
-        {
-            (prev_finality.height, Some(prev_finality))
-        } else {
-            (self.provider.genesis_epoch()?, None)
-        };
+    ) -> anyhow::Result<(BlockHeight, Option<IPCParentFinality>)>
+    where
+        S: Blockstore + Clone + 'static + Send + Sync,
+    {
+        let (prev_height, prev_finality) =
+            if let Some(prev_finality) = gateway_caller.commit_parent_finality(state, finality)? {
+                (prev_finality.height, Some(prev_finality))
+            } else {
+                (self.provider.genesis_epoch()?, None)
+            };
 
         tracing::debug!(
             "commit finality parsed: prev_height {prev_height}, prev_finality: {prev_finality:?}"
@@ -261,16 +397,20 @@ where
 
     /// Execute the top down messages implicitly. Before the execution, mint to the gateway of the funds
     /// transferred in the messages, and increase the circulating supply with the incoming value.
-    async fn execute_topdown_msgs(
+    async fn execute_topdown_msgs<S>(
         &self,
-        state: &mut FvmExecState<DB>,
+        gateway_caller: &GatewayCaller<S>,
+        state: &mut FvmExecState<S>,
         messages: Vec<IpcEnvelope>,
-    ) -> anyhow::Result<AppliedMessage> {
+    ) -> anyhow::Result<(AppliedMessage, Vec<TopDownReceipt>)>
+    where
+        S: Blockstore + Clone + 'static + Send + Sync,
+    {
         let minted_tokens = tokens_to_mint(&messages);
         tracing::debug!(token = minted_tokens.to_string(), "tokens to mint in child");
 
         if !minted_tokens.is_zero() {
-            self.gateway_caller
+            gateway_caller
                 .mint_to_gateway(state, minted_tokens.clone())
                 .context("failed to mint to gateway")?;
 
@@ -279,6 +419,183 @@ where
             });
         }
 
-        self.gateway_caller.apply_cross_messages(state, messages)
+        apply_topdown_msgs(gateway_caller, state, messages)
+    }
+}
+
+/// Lower the finality to the highest of the parent `blocks` (height, number of top-down messages and
+/// hash, or `None` for null rounds, in ascending order of height) whose top-down messages are estimated
+/// to fit into the gas budget. The first block with messages is always included, even if it exceeds the
+/// budget on its own, so that top-down execution keeps making progress.
+fn limit_blocks_to_gas_budget(
+    finality: IPCParentFinality,
+    blocks: Vec<(BlockHeight, usize, Option<BlockHash>)>,
+    gas_budget: Gas,
+) -> IPCParentFinality {
+    let mut gas: Gas = 0;
+    let mut limited = None;
+
+    for (height, count, block_hash) in blocks {
+        gas = gas.saturating_add(TOPDOWN_MSG_GAS_ESTIMATE.saturating_mul(count as Gas));
+
+        if gas > gas_budget && limited.is_some() {
+            break;
+        }
+        // Null rounds cannot be proposed.
+        if let Some(block_hash) = block_hash {
+            limited = Some(IPCParentFinality { height, block_hash });
+        }
+    }
+
+    match limited {
+        Some(limited) if limited.height < finality.height => limited,
+        _ => finality,
+    }
+}
+
+/// Apply the top-down messages in the gateway.
+///
+/// From [PER_MESSAGE_TOPDOWN_APP_VERSION] on, the messages are applied one by one, so that a message
+/// reverting in the gateway does not take the others down with it: the failed message is rejected,
+/// which consumes its nonce and sends a `SystemErr` receipt back to the sender on the parent, and the
+/// returned receipts record the outcome of each message. Before that, the whole batch is applied in a
+/// single call which fails the block if any of the messages reverts, and no receipts are returned.
+pub fn apply_topdown_msgs<DB>(
+    gateway_caller: &GatewayCaller<DB>,
+    state: &mut FvmExecState<DB>,
+    messages: Vec<IpcEnvelope>,
+) -> anyhow::Result<(AppliedMessage, Vec<TopDownReceipt>)>
+where
+    DB: Blockstore + Clone + 'static + Send + Sync,
+{
+    if state.app_version() < PER_MESSAGE_TOPDOWN_APP_VERSION {
+        let applied = gateway_caller.apply_cross_messages(state, messages)?;
+        return Ok((applied, Vec::new()));
+    }
+
+    let mut receipts = Vec::with_capacity(messages.len());
+    let mut applied: Option<AppliedMessage> = None;
+
+    for msg in messages {
+        let nonce = msg.local_nonce;
+        let id = cross_msg_id(&msg).context("failed to compute cross message ID")?;
+
+        let receipt = match gateway_caller.try_apply_cross_message(state, msg.clone())? {
+            Ok(ret) => {
+                let receipt = &ret.apply_ret.msg_receipt;
+                let receipt = TopDownReceipt {
+                    nonce,
+                    id,
+                    exit_code: receipt.exit_code,
+                    gas_used: receipt.gas_used,
+                    return_data: receipt
+                        .return_data
+                        .deserialize::<BytesDe>()
+                        .map(|bz| bz.0)
+                        .unwrap_or_default(),
+                };
+                merge_applied(&mut applied, ret);
+                receipt
+            }
+            Err(e) => {
+                tracing::error!(
+                    nonce,
+                    id = hex::encode(id),
+                    exit_code = e.exit_code.value(),
+                    error = ?e.error,
+                    "failed to apply top-down message"
+                );
+                let reason = match e.error {
+                    ContractError::Raw(bz) => bz,
+                    ContractError::Revert(_) => Vec::new(),
+                };
+                let rejected = gateway_caller
+                    .reject_cross_message(state, msg, reason.clone())
+                    .context("failed to reject top-down message")?;
+                merge_applied(&mut applied, rejected);
+
+                TopDownReceipt {
+                    nonce,
+                    id,
+                    exit_code: e.exit_code,
+                    gas_used: e.gas_used,
+                    return_data: reason,
+                }
+            }
+        };
+        receipts.push(receipt);
+    }
+
+    // Nothing was applied; still give the gateway a chance to propagate its postbox.
+    let applied = match applied {
+        Some(applied) => applied,
+        None => gateway_caller.apply_cross_messages(state, Vec::new())?,
+    };
+
+    Ok((applied, receipts))
+}
+
+/// Fold the result of applying one more message into the combined result of the top-down execution.
+fn merge_applied(acc: &mut Option<AppliedMessage>, ret: AppliedMessage) {
+    match acc {
+        Some(acc) => {
+            acc.apply_ret.msg_receipt.gas_used += ret.apply_ret.msg_receipt.gas_used;
+            acc.apply_ret.events.extend(ret.apply_ret.events);
+            acc.emitters.extend(ret.emitters);
+        }
+        None => *acc = Some(ret),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fendermint_vm_topdown::IPCParentFinality;
+
+    use super::{limit_blocks_to_gas_budget, TOPDOWN_MSG_GAS_ESTIMATE};
+
+    fn finality(height: u64) -> IPCParentFinality {
+        IPCParentFinality {
+            height,
+            block_hash: vec![height as u8],
+        }
+    }
+
+    #[test]
+    fn blocks_within_budget_are_not_limited() {
+        let blocks = vec![(11, 1, Some(vec![11])), (12, 2, Some(vec![12]))];
+        let limited =
+            limit_blocks_to_gas_budget(finality(12), blocks, 3 * TOPDOWN_MSG_GAS_ESTIMATE);
+        assert_eq!(limited, finality(12));
+    }
+
+    #[test]
+    fn blocks_over_budget_are_deferred() {
+        let blocks = vec![
+            (11, 1, Some(vec![11])),
+            (12, 1, Some(vec![12])),
+            (13, 1, Some(vec![13])),
+        ];
+        let limited =
+            limit_blocks_to_gas_budget(finality(13), blocks, 2 * TOPDOWN_MSG_GAS_ESTIMATE);
+        assert_eq!(limited, finality(12));
+    }
+
+    #[test]
+    fn first_block_is_included_even_if_over_budget() {
+        let blocks = vec![(11, 5, Some(vec![11])), (12, 1, Some(vec![12]))];
+        let limited = limit_blocks_to_gas_budget(finality(12), blocks, TOPDOWN_MSG_GAS_ESTIMATE);
+        assert_eq!(limited, finality(11));
+    }
+
+    #[test]
+    fn null_rounds_are_not_proposed() {
+        let blocks = vec![
+            (11, 1, Some(vec![11])),
+            (12, 0, None),
+            (13, 5, Some(vec![13])),
+        ];
+        let limited =
+            limit_blocks_to_gas_budget(finality(13), blocks, 2 * TOPDOWN_MSG_GAS_ESTIMATE);
+        assert_eq!(limited, finality(11));
     }
 }
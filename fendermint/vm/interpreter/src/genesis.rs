@@ -7,10 +7,10 @@ use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use base64::Engine;
 use cid::Cid;
-use ethers::abi::Tokenize;
+use ethers::abi::{Tokenizable, Tokenize};
 use ethers::core::types as et;
 use fendermint_actor_eam::PermissionModeParams;
 use fendermint_eth_deployer::utils as deployer_utils;
@@ -22,24 +22,39 @@ use fendermint_vm_actor_interface::{
     ipc, reward, system, EMPTY_ARR,
 };
 use fendermint_vm_core::Timestamp;
-use fendermint_vm_genesis::{ActorMeta, Collateral, Genesis, Power, PowerScale, Validator};
+use fendermint_vm_genesis::{
+    ActorMeta, Collateral, Genesis, Power, PowerScale, Validator, ValidatorKey,
+};
+use fendermint_vm_message::conv::from_fvm::to_eth_tokens;
 use fvm::engine::MultiEngine;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_car::{load_car, CarHeader};
 use fvm_ipld_encoding::CborStore;
+use fvm_shared::address::Address;
 use fvm_shared::chainid::ChainID;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::version::NetworkVersion;
 use ipc_actors_abis::i_diamond::FacetCut;
+use ipc_api::staking::{PowerChange, PowerChangeRequest, PowerOperation};
+use ipc_api::subnet_id::SubnetID;
 use num_traits::Zero;
 
-use crate::fvm::state::snapshot::{derive_cid, StateTreeStreamer};
+use crate::fvm::state::ipc::GatewayCaller;
+use crate::fvm::state::snapshot::{derive_cid, Snapshot, StateTreeStreamer};
 use crate::fvm::state::{FvmGenesisState, FvmStateParams};
 use crate::fvm::store::memory::MemoryBlockstore;
+use crate::fvm::store::overlay::OverlayBlockstore;
+use crate::fvm::topdown::PER_MESSAGE_TOPDOWN_APP_VERSION;
 use fendermint_vm_genesis::ipc::{GatewayParams, IpcParams};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
+/// The app version new chains start at.
+///
+/// Features gated on the app version of existing chains, like [PER_MESSAGE_TOPDOWN_APP_VERSION],
+/// are on from the first block of chains created with this release.
+pub const GENESIS_APP_VERSION: u64 = PER_MESSAGE_TOPDOWN_APP_VERSION;
+
 /// The sealed genesis state metadata
 #[serde_as]
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -58,7 +73,7 @@ impl GenesisMetadata {
             circ_supply: out.circ_supply,
             chain_id: out.chain_id.into(),
             power_scale: out.power_scale,
-            app_version: 0,
+            app_version: GENESIS_APP_VERSION,
             consensus_params: None,
         };
 
@@ -154,6 +169,76 @@ pub struct GenesisOutput {
     pub validators: Vec<Validator<Power>>,
 }
 
+/// Number of blocks buffered for the CAR writer while the state tree is streamed.
+const CAR_WRITE_BUFFER: usize = 1024;
+
+/// The state of an existing chain, which a new genesis can be forked from, e.g. to start a
+/// staging network with a copy of the production state but with different validators.
+///
+/// The state is read from its source while the genesis is sealed, with only the blocks
+/// written by the fork kept in memory.
+pub struct GenesisFork {
+    store: ForkBlockstore,
+    state_params: FvmStateParams,
+}
+
+impl GenesisFork {
+    /// Fork the state loaded from a snapshot CAR file.
+    ///
+    /// CAR files can't be read by CID, so the snapshot is loaded into memory; to fork a large
+    /// state, use [`GenesisFork::from_store`] with the database of a node instead.
+    pub async fn from_snapshot(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let store = MemoryBlockstore::new();
+        let snapshot = Snapshot::read_car(path, store.clone(), true)
+            .await
+            .context("failed to read snapshot")?;
+
+        let state_params = match snapshot {
+            Snapshot::V1(snapshot) => {
+                tracing::info!(
+                    block_height = snapshot.block_height(),
+                    "forking the state of a snapshot"
+                );
+                snapshot.state_params().state.clone()
+            }
+        };
+
+        Ok(Self::from_store(store, state_params))
+    }
+
+    /// Fork the state with the given parameters from a block store, e.g. the database of a node,
+    /// which is only read from.
+    pub fn from_store<DB>(store: DB, state_params: FvmStateParams) -> Self
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        Self {
+            store: OverlayBlockstore::new(SharedBlockstore(Arc::new(store))),
+            state_params,
+        }
+    }
+}
+
+/// Block store of the chain a genesis is forked from.
+#[derive(Clone)]
+struct SharedBlockstore(Arc<dyn Blockstore + Send + Sync>);
+
+impl Blockstore for SharedBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        self.0.get(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.0.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        self.0.has(k)
+    }
+}
+
+type ForkBlockstore = OverlayBlockstore<SharedBlockstore>;
+
 pub struct GenesisBuilder<'a> {
     /// Hardhat like util to deploy ipc contracts
     hardhat: Hardhat,
@@ -164,6 +249,9 @@ pub struct GenesisBuilder<'a> {
 
     /// Genesis params
     genesis_params: Genesis,
+
+    /// Existing state to start from, instead of an empty one.
+    fork: Option<GenesisFork>,
 }
 
 impl<'a> GenesisBuilder<'a> {
@@ -178,30 +266,62 @@ impl<'a> GenesisBuilder<'a> {
             builtin_actors,
             custom_actors,
             genesis_params,
+            fork: None,
         }
     }
 
+    /// Start from the state of an existing chain instead of creating the actors from scratch.
+    ///
+    /// Only the chain ID, the timestamp and the validators are taken from the Genesis parameters;
+    /// everything else, including the IPC contracts, is what the forked state already contains.
+    ///
+    /// The subnet actor of a forked subnet on its parent isn't changed, so it still has the
+    /// validators and configuration of the original subnet; a fork is meant to run on its own,
+    /// without top-down finality or bottom-up checkpoints.
+    pub fn with_fork(mut self, fork: GenesisFork) -> Self {
+        self.fork = Some(fork);
+        self
+    }
+
     /// Initialize actor states from the Genesis parameters and write the sealed genesis state to
     /// a CAR file specified by `out_path`
     pub async fn write_to(&self, out_path: PathBuf) -> anyhow::Result<()> {
-        let mut state = self.init_state().await?;
-        let genesis_state = self.populate_state(&mut state, self.genesis_params.clone())?;
-        let (state_root, store) = state.finalize()?;
-        self.write_car(state_root, genesis_state, out_path, store)
-            .await
+        match self.fork {
+            None => {
+                let mut state = self.init_state().await?;
+                let genesis_state = self.populate_state(&mut state, self.genesis_params.clone())?;
+                let (state_root, store) = state.finalize()?;
+                let metadata = GenesisMetadata::new(state_root, genesis_state);
+                self.write_car(state_root, metadata, out_path, store).await
+            }
+            Some(ref fork) => {
+                let mut state = self.init_state_in(fork.store.clone()).await?;
+                let (state_params, validators) = self.fork_state(&mut state, fork)?;
+                let (state_root, store) = state.finalize()?;
+                let metadata = GenesisMetadata {
+                    state_params: FvmStateParams {
+                        state_root,
+                        ..state_params
+                    },
+                    validators,
+                };
+                self.write_car(state_root, metadata, out_path, store).await
+            }
+        }
     }
 
-    async fn write_car(
+    async fn write_car<DB>(
         &self,
         state_root: Cid,
-        genesis_state: GenesisOutput,
+        metadata: GenesisMetadata,
         out_path: PathBuf,
-        store: MemoryBlockstore,
-    ) -> anyhow::Result<()> {
+        store: DB,
+    ) -> anyhow::Result<()>
+    where
+        DB: Blockstore + Send + Unpin + 'static,
+    {
         tracing::info!(state_root = state_root.to_string(), "state root");
 
-        let metadata = GenesisMetadata::new(state_root, genesis_state);
-
         let streamer = StateTreeStreamer::new(state_root, store);
         let (metadata_cid, metadata_bytes) = derive_cid(&metadata)?;
         tracing::info!("generated genesis metadata header cid: {}", metadata_cid);
@@ -209,27 +329,32 @@ impl<'a> GenesisBuilder<'a> {
         // create the target car header with the metadata cid as the only root
         let car = CarHeader::new(vec![metadata_cid], 1);
 
-        // In FVM 4.7, CAR API is synchronous, collect stream first
         let mut streamer = tokio_stream::iter(vec![(metadata_cid, metadata_bytes)]).merge(streamer);
 
-        use tokio_stream::StreamExt;
-        let mut blocks = Vec::new();
-        while let Some((cid, data)) = streamer.next().await {
-            blocks.push((cid, data));
-        }
+        // In FVM 4.7, CAR API is synchronous, so the blocks are written in a blocking task
+        // as they are streamed, without holding all of them in memory.
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<(Cid, Vec<u8>)>(CAR_WRITE_BUFFER);
 
-        // Write synchronously in a blocking task
-        let out_path_clone = out_path.clone();
-        tokio::task::spawn_blocking(move || {
+        let writer = tokio::task::spawn_blocking(move || {
             use fvm_ipld_car::{Block, CarWriter};
-            let file_std = std::fs::File::create(out_path_clone)?;
+            let file_std = std::fs::File::create(out_path)?;
             let mut writer = CarWriter::new(car, file_std)?;
-            for (cid, data) in blocks {
+            while let Some((cid, data)) = rx.blocking_recv() {
                 writer.write(Block { cid, data })?;
             }
             Ok::<_, anyhow::Error>(())
-        })
-        .await??;
+        });
+
+        use tokio_stream::StreamExt;
+        while let Some(block) = streamer.next().await {
+            if tx.send(block).await.is_err() {
+                // The writer failed; its error is returned below.
+                break;
+            }
+        }
+        drop(tx);
+
+        writer.await??;
 
         tracing::info!("written sealed genesis state to file");
 
@@ -237,8 +362,13 @@ impl<'a> GenesisBuilder<'a> {
     }
 
     async fn init_state(&self) -> anyhow::Result<FvmGenesisState<MemoryBlockstore>> {
-        let store = MemoryBlockstore::new();
+        self.init_state_in(MemoryBlockstore::new()).await
+    }
 
+    async fn init_state_in<DB>(&self, store: DB) -> anyhow::Result<FvmGenesisState<DB>>
+    where
+        DB: Blockstore + Clone + 'static,
+    {
         FvmGenesisState::new(
             store,
             Arc::new(MultiEngine::new(1)),
@@ -249,6 +379,137 @@ impl<'a> GenesisBuilder<'a> {
         .context("failed to create genesis state")
     }
 
+    /// Continue the forked state with a new chain ID and replace its validators in the gateway.
+    ///
+    /// Returns the state parameters and the validators of the new genesis; the state root
+    /// is only known after the state has been finalized.
+    fn fork_state(
+        &self,
+        state: &mut FvmGenesisState<ForkBlockstore>,
+        fork: &GenesisFork,
+    ) -> anyhow::Result<(FvmStateParams, Vec<Validator<Power>>)> {
+        let genesis = &self.genesis_params;
+        let chain_id = genesis.chain_id()?;
+
+        if genesis.network_version != fork.state_params.network_version {
+            bail!(
+                "the network version of the fork is {}, not {}; the actors cannot be changed by forking",
+                fork.state_params.network_version,
+                genesis.network_version
+            );
+        }
+
+        let state_params = FvmStateParams {
+            timestamp: genesis.timestamp,
+            chain_id: chain_id.into(),
+            consensus_params: None,
+            ..fork.state_params.clone()
+        };
+
+        state
+            .fork_exec_state(state_params.clone())
+            .context("failed to init exec state")?;
+
+        let exec_state = state
+            .exec_state()
+            .ok_or_else(|| anyhow!("exec state not initialized"))?;
+
+        let gateway = GatewayCaller::default();
+
+        // The gateway facets don't allow changing the subnet ID, so a fork stays the same subnet.
+        if let Some(ref ipc) = genesis.ipc {
+            let subnet_id = gateway
+                .subnet_id(exec_state)
+                .context("failed to get the subnet ID of the fork")?;
+            let subnet_id = SubnetID::try_from(subnet_id)?;
+
+            if subnet_id != ipc.gateway.subnet_id {
+                bail!(
+                    "the fork is subnet {subnet_id}, not {}; the subnet ID cannot be changed by forking",
+                    ipc.gateway.subnet_id
+                );
+            }
+        }
+
+        // Apply any pending changes from the parent first, so we can see who we have to remove.
+        gateway
+            .apply_validator_changes(exec_state)
+            .context("failed to apply pending validator changes")?;
+
+        let (_, current) = gateway
+            .current_power_table(exec_state)
+            .context("failed to get the power table of the fork")?;
+
+        let (mut configuration_number, _) = gateway
+            .tracker_configuration_numbers(exec_state)
+            .context("failed to get the configuration numbers of the fork")?;
+
+        let mut changes = Vec::new();
+        let mut change = |validator: &ValidatorKey, op: PowerOperation, payload: Vec<u8>| {
+            let validator = Address::from(EthAddress::new_secp256k1(
+                &validator.public_key().serialize(),
+            )?);
+            changes.push(PowerChangeRequest {
+                configuration_number,
+                change: PowerChange {
+                    op,
+                    payload,
+                    validator,
+                },
+            });
+            configuration_number += 1;
+            Ok::<_, anyhow::Error>(())
+        };
+
+        for v in current.iter() {
+            if !genesis
+                .validators
+                .iter()
+                .any(|g| g.public_key == v.public_key)
+            {
+                change(
+                    &v.public_key,
+                    PowerOperation::SetPower,
+                    ethers::abi::encode(&[et::U256::zero().into_token()]),
+                )?;
+            }
+        }
+
+        for v in genesis.validators.iter() {
+            let collateral = to_eth_tokens(&v.power.0)?;
+            change(
+                &v.public_key,
+                PowerOperation::SetMetadata,
+                v.public_key.public_key().serialize().to_vec(),
+            )?;
+            change(
+                &v.public_key,
+                PowerOperation::SetPower,
+                ethers::abi::encode(&[collateral.into_token()]),
+            )?;
+        }
+
+        gateway
+            .store_validator_changes(exec_state, changes)
+            .context("failed to store validator changes")?;
+
+        gateway
+            .apply_validator_changes(exec_state)
+            .context("failed to apply validator changes")?;
+
+        let (_, validators) = gateway
+            .current_power_table(exec_state)
+            .context("failed to get the new power table")?;
+
+        tracing::info!(
+            chain_id = state_params.chain_id,
+            validators = validators.len(),
+            "forked state"
+        );
+
+        Ok((state_params, validators))
+    }
+
     fn populate_state(
         &self,
         state: &mut FvmGenesisState<MemoryBlockstore>,
@@ -474,6 +735,9 @@ impl<'a> GenesisBuilder<'a> {
         // The reason we aren't using the `init_state.next_id` is because that already accounted for the multisig accounts.
         let mut next_id = init::FIRST_NON_SINGLETON_ADDR + addr_to_id.len() as u64;
 
+        // EVM contracts can only be deployed once the FVM is initialized.
+        let mut contracts = Vec::new();
+
         for a in genesis.accounts {
             let balance = a.balance;
             match a.meta {
@@ -488,6 +752,9 @@ impl<'a> GenesisBuilder<'a> {
                         .context("failed to create multisig actor")?;
                     next_id += 1;
                 }
+                ActorMeta::Contract(contract) => {
+                    contracts.push((contract, balance));
+                }
             }
         }
 
@@ -504,6 +771,17 @@ impl<'a> GenesisBuilder<'a> {
             )
             .context("failed to init exec state")?;
 
+        // STAGE 3b: Deploy the EVM contracts allocated in the genesis file, at their own addresses.
+
+        for (contract, balance) in contracts {
+            let address = contract.address;
+            let id = state
+                .create_genesis_contract_actor(contract, balance, &addr_to_id)
+                .context("failed to create genesis contract actor")?;
+
+            tracing::info!(?address, actor_id = id, "deployed genesis contract");
+        }
+
         // STAGE 4: Deploy the IPC system contracts.
 
         let config = DeployConfig {
@@ -763,7 +1041,7 @@ where
     }
 }
 
-/// Sum of balances in the genesis accounts.
+/// Sum of balances in the genesis accounts, multisigs and contracts, which are all minted into the actors when they are created.
 fn circ_supply(g: &Genesis) -> TokenAmount {
     g.accounts
         .iter()
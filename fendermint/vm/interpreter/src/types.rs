@@ -1,11 +1,12 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::fvm::bottomup::BottomUpBatch;
 use crate::fvm::end_block_hook::{LightClientCommitments, PowerUpdates};
 use crate::fvm::FvmMessage;
 use actors_custom_api::gas_market::Reading;
 use cid::Cid;
-use fendermint_vm_message::query::{ActorState, GasEstimate, StateParams};
+use fendermint_vm_message::query::{ActorState, GasEstimate, MessageTrace, StateParams};
 use fendermint_vm_message::signed::DomainHash;
 use fvm::executor::ApplyRet;
 use fvm_shared::{address::Address, error::ExitCode, event::StampedEvent, ActorID, MethodNum};
@@ -71,12 +72,28 @@ pub struct AppliedMessage {
     pub emitters: Emitters,
 }
 
+/// Outcome of applying a single top-down message.
+#[derive(Debug, Clone)]
+pub struct TopDownReceipt {
+    /// The local nonce of the envelope, which is its sequence number from the parent.
+    pub nonce: u64,
+    /// The cross-message ID the gateway traces the envelope by, also used as the ID
+    /// of the `Result` envelope it sends back to the sender.
+    pub id: [u8; 32],
+    pub exit_code: ExitCode,
+    pub gas_used: u64,
+    /// Data returned by the gateway, e.g. the revert data if the application failed.
+    pub return_data: Vec<u8>,
+}
+
 /// Response from applying a message.
 #[derive(Debug, Clone)]
 pub struct ApplyMessageResponse {
     pub applied_message: AppliedMessage,
     /// Domain-specific transaction hash for EVM compatibility.
     pub domain_hash: Option<DomainHash>,
+    /// The outcome of each message applied during top-down execution.
+    pub topdown_receipts: Vec<TopDownReceipt>,
 }
 
 /// Response from beginning a block.
@@ -91,6 +108,8 @@ pub struct EndBlockResponse {
     pub power_updates: PowerUpdates,
     pub gas_market: Reading,
     pub light_client_commitments: Option<LightClientCommitments>,
+    /// The bottom-up messages of the checkpoint, if any, which the parent will want to resolve.
+    pub bottom_up_batch: Option<BottomUpBatch>,
     pub end_block_events: BlockEndEvents,
 }
 
@@ -128,6 +147,8 @@ pub enum QueryResponse {
     Call(Box<AppliedMessage>),
     /// Estimated gas limit.
     EstimateGas(GasEstimate),
+    /// The traces of replayed messages.
+    Trace(Vec<MessageTrace>),
     /// Current state parameters.
     StateParams(StateParams),
     /// Builtin actors known by the system.
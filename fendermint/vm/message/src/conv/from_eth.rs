@@ -4,7 +4,8 @@
 //! Helper methods to convert between Ethereum and FVM data formats.
 
 use ethers_core::types::{
-    Eip1559TransactionRequest, NameOrAddress, TransactionRequest, H160, U256,
+    Eip1559TransactionRequest, Eip2930TransactionRequest, NameOrAddress, TransactionRequest, H160,
+    U256,
 };
 use fendermint_vm_actor_interface::{
     eam::{self, EthAddress},
@@ -18,6 +19,8 @@ use fvm_shared::{
     message::Message,
 };
 
+use crate::eth::Eip7702TransactionRequest;
+
 fn handle_to_address(to: &Option<NameOrAddress>) -> anyhow::Result<(u64, Address)> {
     // FIP-55 says that we should use `InvokeContract` for transfers instead of `METHOD_SEND`,
     // because if we are sending to some Ethereum actor by ID using `METHOD_SEND`, they will
@@ -117,6 +120,32 @@ pub fn fvm_message_from_eip1559(tx: &Eip1559TransactionRequest) -> anyhow::Resul
     Ok(msg)
 }
 
+/// The access list has no bearing on the execution in the FVM; it is only
+/// carried along with the message so that the signature can be checked.
+pub fn fvm_message_from_eip2930(tx: &Eip2930TransactionRequest) -> anyhow::Result<Message> {
+    fvm_message_from_legacy(&tx.tx)
+}
+
+pub fn fvm_message_from_eip7702(tx: &Eip7702TransactionRequest) -> anyhow::Result<Message> {
+    if tx.to.is_none() {
+        anyhow::bail!("EIP-7702 transactions cannot create contracts");
+    }
+    // Apart from the lists, which travel separately, this is the same as an EIP-1559 transaction.
+    let tx = Eip1559TransactionRequest {
+        from: tx.from,
+        to: tx.to.clone(),
+        gas: tx.gas,
+        value: tx.value,
+        data: tx.data.clone(),
+        nonce: tx.nonce,
+        access_list: Default::default(),
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        chain_id: tx.chain_id,
+    };
+    fvm_message_from_eip1559(&tx)
+}
+
 pub fn to_fvm_address(addr: H160) -> Address {
     Address::from(EthAddress(addr.0))
 }
@@ -179,6 +208,7 @@ mod tests {
             message: fvm_message_from_eip1559(tx0.as_eip1559_ref().unwrap())
                 .expect("to_fvm_message"),
             signature: Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: None,
         };
 
         let domain_hash = msg.domain_hash(&chain_id).expect("domain_hash");
@@ -5,6 +5,7 @@
 
 use std::str::FromStr;
 
+use crate::eth::{Eip7702TransactionRequest, EthExtras, EthTransaction};
 use crate::signed::OriginKind;
 use anyhow::anyhow;
 use anyhow::bail;
@@ -71,19 +72,40 @@ pub fn to_eth_signature(sig: &FvmSignature, normalized: bool) -> anyhow::Result<
     Ok(sig)
 }
 
-pub fn to_eth_typed_transaction(
+/// Reconstruct the Ethereum transaction which the FVM message was created from.
+///
+/// The access and authorization lists of typed transactions are taken from the extras.
+pub fn to_eth_transaction(
     origin_kind: OriginKind,
     message: &Message,
+    eth_extras: Option<&EthExtras>,
     chain_id: &ChainID,
-) -> anyhow::Result<TypedTransaction> {
-    match origin_kind {
-        OriginKind::Fvm => Err(anyhow!("fvm message not allowed")),
-        OriginKind::EthereumLegacy => Ok(TypedTransaction::Legacy(to_eth_legacy_request(
-            message, chain_id,
-        )?)),
-        OriginKind::EthereumEIP1559 => Ok(TypedTransaction::Eip1559(to_eth_eip1559_request(
-            message, chain_id,
-        )?)),
+) -> anyhow::Result<EthTransaction> {
+    match (origin_kind, eth_extras) {
+        (OriginKind::Fvm, _) => Err(anyhow!("fvm message not allowed")),
+        (OriginKind::EthereumLegacy, None) => {
+            Ok(TypedTransaction::Legacy(to_eth_legacy_request(message, chain_id)?).into())
+        }
+        (OriginKind::EthereumEIP1559, None) => {
+            Ok(TypedTransaction::Eip1559(to_eth_eip1559_request(message, chain_id)?).into())
+        }
+        (OriginKind::EthereumLegacy | OriginKind::EthereumEIP1559, Some(_)) => Err(anyhow!(
+            "{origin_kind:?} transactions cannot have access or authorization lists"
+        )),
+        (OriginKind::EthereumEIP2930, extras) => {
+            let extras = extras.cloned().unwrap_or_default();
+            if !extras.authorization_list.is_empty() {
+                bail!("EIP-2930 transactions cannot have an authorization list");
+            }
+            Ok(
+                TypedTransaction::Eip2930(to_eth_eip2930_request(message, &extras, chain_id)?)
+                    .into(),
+            )
+        }
+        (OriginKind::EthereumEIP7702, extras) => {
+            let extras = extras.cloned().unwrap_or_default();
+            Ok(to_eth_eip7702_request(message, &extras, chain_id)?.into())
+        }
     }
 }
 
@@ -175,6 +197,49 @@ pub fn to_eth_eip1559_request(
     Ok(tx)
 }
 
+/// Turn an FVM `Message` back into an Ethereum EIP-2930 transaction request.
+pub fn to_eth_eip2930_request(
+    msg: &Message,
+    eth_extras: &EthExtras,
+    chain_id: &ChainID,
+) -> anyhow::Result<et::Eip2930TransactionRequest> {
+    let tx = to_eth_legacy_request(msg, chain_id)?;
+    Ok(et::Eip2930TransactionRequest::new(
+        tx,
+        eth_extras.eth_access_list(),
+    ))
+}
+
+/// Turn an FVM `Message` back into an Ethereum EIP-7702 transaction request.
+pub fn to_eth_eip7702_request(
+    msg: &Message,
+    eth_extras: &EthExtras,
+    chain_id: &ChainID,
+) -> anyhow::Result<Eip7702TransactionRequest> {
+    let tx = to_eth_eip1559_request(msg, chain_id)?;
+
+    if tx.to.is_none() {
+        bail!("EIP-7702 transactions cannot create contracts");
+    }
+    if eth_extras.authorization_list.is_empty() {
+        bail!("EIP-7702 transactions must have a non-empty authorization list");
+    }
+
+    Ok(Eip7702TransactionRequest {
+        from: tx.from,
+        to: tx.to,
+        gas: tx.gas,
+        value: tx.value,
+        data: tx.data,
+        nonce: tx.nonce,
+        access_list: eth_extras.eth_access_list(),
+        authorization_list: eth_extras.eth_authorization_list(),
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        chain_id: tx.chain_id,
+    })
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -287,6 +352,7 @@ pub mod tests {
             origin_kind: OriginKind::EthereumEIP1559,
             message: msg1,
             signature: Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: None,
         };
 
         signed.verify(&chain_id).expect("signature should be valid")
@@ -0,0 +1,539 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Ethereum transaction types beyond what `ethers` supports, and the parts of
+//! typed transactions which have no equivalent in an FVM [`Message`](fvm_shared::message::Message)
+//! but are covered by the signature, so they have to travel along with it.
+
+use anyhow::{anyhow, bail, Context};
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::types::transaction::eip2930::{self, AccessList};
+use ethers_core::types::{self as et};
+use ethers_core::utils::keccak256;
+use ethers_core::utils::rlp::{Decodable, DecoderError, Rlp, RlpStream};
+use fendermint_vm_actor_interface::eam::EthAddress;
+use fvm_ipld_encoding::strict_bytes;
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use serde::{Deserialize, Serialize};
+
+/// Transaction type of EIP-2930 access list transactions.
+pub const EIP2930_TX_TYPE: u8 = 0x01;
+/// Transaction type of EIP-7702 set code transactions.
+pub const EIP7702_TX_TYPE: u8 = 0x04;
+/// Prefix of the payload signed by EIP-7702 authorities.
+const EIP7702_AUTH_MAGIC: u8 = 0x05;
+
+/// Fields of typed Ethereum transactions that can't be expressed in the FVM message.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Hash, Serialize_tuple, Deserialize_tuple)]
+pub struct EthExtras {
+    pub access_list: Vec<AccessListItem>,
+    pub authorization_list: Vec<Authorization>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize_tuple, Deserialize_tuple)]
+pub struct AccessListItem {
+    pub address: EthAddress,
+    pub storage_keys: Vec<StorageKey>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, Serialize, Deserialize)]
+pub struct StorageKey(#[serde(with = "strict_bytes")] pub [u8; 32]);
+
+/// EIP-7702 authorization tuple, as stored on chain.
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize_tuple, Deserialize_tuple)]
+pub struct Authorization {
+    pub chain_id: u64,
+    pub address: EthAddress,
+    pub nonce: u64,
+    pub y_parity: u8,
+    #[serde(with = "strict_bytes")]
+    pub r: [u8; 32],
+    #[serde(with = "strict_bytes")]
+    pub s: [u8; 32],
+}
+
+impl EthExtras {
+    pub fn new(
+        access_list: &AccessList,
+        authorization_list: &[SignedAuthorization],
+    ) -> anyhow::Result<Self> {
+        let access_list = access_list
+            .0
+            .iter()
+            .map(|item| AccessListItem {
+                address: EthAddress(item.address.0),
+                storage_keys: item.storage_keys.iter().map(|k| StorageKey(k.0)).collect(),
+            })
+            .collect();
+
+        let authorization_list = authorization_list
+            .iter()
+            .map(Authorization::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            access_list,
+            authorization_list,
+        })
+    }
+
+    /// The access list in the format used by `ethers`.
+    pub fn eth_access_list(&self) -> AccessList {
+        AccessList(
+            self.access_list
+                .iter()
+                .map(|item| eip2930::AccessListItem {
+                    address: et::H160(item.address.0),
+                    storage_keys: item.storage_keys.iter().map(|k| et::H256(k.0)).collect(),
+                })
+                .collect(),
+        )
+    }
+
+    /// The authorization list in the format used by the JSON-RPC API.
+    pub fn eth_authorization_list(&self) -> Vec<SignedAuthorization> {
+        self.authorization_list
+            .iter()
+            .map(SignedAuthorization::from)
+            .collect()
+    }
+}
+
+impl TryFrom<&SignedAuthorization> for Authorization {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &SignedAuthorization) -> Result<Self, Self::Error> {
+        if value.chain_id > et::U256::from(u64::MAX) {
+            bail!("authorization chain ID out of range");
+        }
+        let y_parity = u8::try_from(value.y_parity.as_u64())
+            .ok()
+            .filter(|v| *v <= 1)
+            .ok_or_else(|| anyhow!("invalid authorization y-parity"))?;
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        value.r.to_big_endian(&mut r);
+        value.s.to_big_endian(&mut s);
+
+        Ok(Self {
+            chain_id: value.chain_id.as_u64(),
+            address: EthAddress(value.address.0),
+            nonce: value.nonce.as_u64(),
+            y_parity,
+            r,
+            s,
+        })
+    }
+}
+
+impl From<&Authorization> for SignedAuthorization {
+    fn from(value: &Authorization) -> Self {
+        Self {
+            chain_id: et::U256::from(value.chain_id),
+            address: et::H160(value.address.0),
+            nonce: et::U64::from(value.nonce),
+            y_parity: et::U64::from(value.y_parity),
+            r: et::U256::from_big_endian(&value.r),
+            s: et::U256::from_big_endian(&value.s),
+        }
+    }
+}
+
+/// EIP-7702 authorization tuple, signed by the authority delegating its code to `address`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedAuthorization {
+    pub chain_id: et::U256,
+    pub address: et::Address,
+    pub nonce: et::U64,
+    pub y_parity: et::U64,
+    pub r: et::U256,
+    pub s: et::U256,
+}
+
+impl SignedAuthorization {
+    /// Hash signed by the authority: `keccak256(0x05 || rlp([chain_id, address, nonce]))`.
+    pub fn sighash(&self) -> et::H256 {
+        let mut s = RlpStream::new_list(3);
+        s.append(&self.chain_id);
+        s.append(&self.address);
+        s.append(&self.nonce);
+
+        let mut bz = vec![EIP7702_AUTH_MAGIC];
+        bz.extend_from_slice(&s.out());
+        et::H256(keccak256(bz))
+    }
+
+    /// Recover the address of the authority which signed the tuple.
+    pub fn authority(&self) -> anyhow::Result<et::Address> {
+        let sig = et::Signature {
+            r: self.r,
+            s: self.s,
+            v: self.y_parity.as_u64(),
+        };
+        sig.recover(self.sighash())
+            .context("failed to recover authority")
+    }
+
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.chain_id);
+        s.append(&self.address);
+        s.append(&self.nonce);
+        s.append(&self.y_parity);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+}
+
+impl Decodable for SignedAuthorization {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(Self {
+            chain_id: rlp.val_at(0)?,
+            address: rlp.val_at(1)?,
+            nonce: rlp.val_at(2)?,
+            y_parity: rlp.val_at(3)?,
+            r: rlp.val_at(4)?,
+            s: rlp.val_at(5)?,
+        })
+    }
+}
+
+/// EIP-7702 set code transaction, modelled after [`et::Eip1559TransactionRequest`].
+///
+/// Unlike other transaction types it cannot create contracts, so `to` is mandatory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Eip7702TransactionRequest {
+    pub from: Option<et::Address>,
+    pub to: Option<et::NameOrAddress>,
+    pub gas: Option<et::U256>,
+    pub value: Option<et::U256>,
+    pub data: Option<et::Bytes>,
+    pub nonce: Option<et::U256>,
+    pub access_list: AccessList,
+    pub authorization_list: Vec<SignedAuthorization>,
+    pub max_priority_fee_per_gas: Option<et::U256>,
+    pub max_fee_per_gas: Option<et::U256>,
+    pub chain_id: Option<et::U64>,
+}
+
+impl Eip7702TransactionRequest {
+    fn rlp_append_fields(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id.unwrap_or_default());
+        s.append(&self.nonce.unwrap_or_default());
+        s.append(&self.max_priority_fee_per_gas.unwrap_or_default());
+        s.append(&self.max_fee_per_gas.unwrap_or_default());
+        s.append(&self.gas.unwrap_or_default());
+        match self.to.as_ref().and_then(|to| to.as_address()) {
+            Some(to) => s.append(to),
+            None => s.append_empty_data(),
+        };
+        s.append(&self.value.unwrap_or_default());
+        s.append(&self.data.clone().unwrap_or_default().to_vec());
+        s.append(&self.access_list);
+        s.begin_list(self.authorization_list.len());
+        for auth in self.authorization_list.iter() {
+            auth.rlp_append(s);
+        }
+    }
+
+    /// The RLP encoding of the unsigned transaction, without the type prefix.
+    pub fn rlp(&self) -> et::Bytes {
+        let mut s = RlpStream::new_list(10);
+        self.rlp_append_fields(&mut s);
+        s.out().freeze().into()
+    }
+
+    /// The RLP encoding of the signed transaction, without the type prefix.
+    pub fn rlp_signed(&self, sig: &et::Signature) -> et::Bytes {
+        let mut s = RlpStream::new_list(13);
+        self.rlp_append_fields(&mut s);
+        s.append(&normalize_v(sig.v));
+        s.append(&sig.r);
+        s.append(&sig.s);
+        s.out().freeze().into()
+    }
+
+    /// Hash signed by the sender.
+    pub fn sighash(&self) -> et::H256 {
+        et::H256(keccak256(typed(EIP7702_TX_TYPE, &self.rlp())))
+    }
+
+    /// Transaction hash of the signed transaction.
+    pub fn hash(&self, sig: &et::Signature) -> et::H256 {
+        et::H256(keccak256(typed(EIP7702_TX_TYPE, &self.rlp_signed(sig))))
+    }
+
+    /// Decode the RLP payload of a signed transaction, following the type prefix.
+    ///
+    /// The sender is recovered from the signature.
+    pub fn decode_signed(rlp: &Rlp) -> anyhow::Result<(Self, et::Signature)> {
+        if rlp.item_count()? != 13 {
+            bail!("unexpected number of fields in EIP-7702 transaction");
+        }
+        let to: et::Address = rlp
+            .val_at(5)
+            .context("EIP-7702 transactions must have a destination")?;
+
+        let authorization_list = rlp
+            .at(9)?
+            .iter()
+            .map(|r| SignedAuthorization::decode(&r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if authorization_list.is_empty() {
+            bail!("EIP-7702 transactions must have a non-empty authorization list");
+        }
+
+        let data: Vec<u8> = rlp.val_at(7)?;
+
+        let mut tx = Self {
+            from: None,
+            chain_id: Some(rlp.val_at(0)?),
+            nonce: Some(rlp.val_at(1)?),
+            max_priority_fee_per_gas: Some(rlp.val_at(2)?),
+            max_fee_per_gas: Some(rlp.val_at(3)?),
+            gas: Some(rlp.val_at(4)?),
+            to: Some(et::NameOrAddress::Address(to)),
+            value: Some(rlp.val_at(6)?),
+            data: Some(et::Bytes::from(data)),
+            access_list: rlp.val_at(8)?,
+            authorization_list,
+        };
+
+        let sig = et::Signature {
+            v: rlp.val_at(10)?,
+            r: rlp.val_at(11)?,
+            s: rlp.val_at(12)?,
+        };
+
+        tx.from = Some(
+            sig.recover(tx.sighash())
+                .context("failed to recover sender")?,
+        );
+
+        Ok((tx, sig))
+    }
+}
+
+/// An Ethereum transaction of any of the types the API accepts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EthTransaction {
+    /// Legacy, EIP-2930 and EIP-1559 transactions, which `ethers` knows about.
+    Typed(TypedTransaction),
+    Eip7702(Eip7702TransactionRequest),
+}
+
+impl EthTransaction {
+    /// Decode a signed transaction in its canonical (EIP-2718) encoding.
+    pub fn decode_signed(bz: &[u8]) -> anyhow::Result<(Self, et::Signature)> {
+        match bz.first() {
+            Some(&EIP7702_TX_TYPE) => {
+                let rlp = Rlp::new(&bz[1..]);
+                let (tx, sig) = Eip7702TransactionRequest::decode_signed(&rlp)?;
+                Ok((Self::Eip7702(tx), sig))
+            }
+            _ => {
+                let rlp = Rlp::new(bz);
+                let (tx, sig) = TypedTransaction::decode_signed(&rlp)
+                    .context("failed to decode RLP as signed TypedTransaction")?;
+                Ok((Self::Typed(tx), sig))
+            }
+        }
+    }
+
+    pub fn sighash(&self) -> et::H256 {
+        match self {
+            Self::Typed(tx) => tx.sighash(),
+            Self::Eip7702(tx) => tx.sighash(),
+        }
+    }
+
+    pub fn hash(&self, sig: &et::Signature) -> et::H256 {
+        match self {
+            Self::Typed(tx) => tx.hash(sig),
+            Self::Eip7702(tx) => tx.hash(sig),
+        }
+    }
+
+    /// The EIP-2718 transaction type.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            Self::Typed(TypedTransaction::Legacy(_)) => 0x00,
+            Self::Typed(TypedTransaction::Eip2930(_)) => EIP2930_TX_TYPE,
+            Self::Typed(TypedTransaction::Eip1559(_)) => 0x02,
+            Self::Eip7702(_) => EIP7702_TX_TYPE,
+        }
+    }
+
+    pub fn from(&self) -> Option<&et::Address> {
+        match self {
+            Self::Typed(tx) => tx.from(),
+            Self::Eip7702(tx) => tx.from.as_ref(),
+        }
+    }
+}
+
+impl From<TypedTransaction> for EthTransaction {
+    fn from(value: TypedTransaction) -> Self {
+        Self::Typed(value)
+    }
+}
+
+impl From<Eip7702TransactionRequest> for EthTransaction {
+    fn from(value: Eip7702TransactionRequest) -> Self {
+        Self::Eip7702(value)
+    }
+}
+
+/// Typed transactions sign the y-parity rather than a legacy `v`.
+fn normalize_v(v: u64) -> u64 {
+    match v {
+        27 | 28 => v - 27,
+        v => v,
+    }
+}
+
+fn typed(tx_type: u8, rlp: &[u8]) -> Vec<u8> {
+    let mut bz = Vec::with_capacity(rlp.len() + 1);
+    bz.push(tx_type);
+    bz.extend_from_slice(rlp);
+    bz
+}
+
+#[cfg(feature = "arb")]
+mod arb {
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use quickcheck::Arbitrary;
+
+    use super::{AccessListItem, Authorization, EthExtras, StorageKey};
+
+    fn arb_bytes32(g: &mut quickcheck::Gen) -> [u8; 32] {
+        std::array::from_fn(|_| u8::arbitrary(g))
+    }
+
+    fn arb_address(g: &mut quickcheck::Gen) -> EthAddress {
+        EthAddress(std::array::from_fn(|_| u8::arbitrary(g)))
+    }
+
+    impl quickcheck::Arbitrary for EthExtras {
+        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            let access_list = (0..usize::arbitrary(g) % 3)
+                .map(|_| AccessListItem {
+                    address: arb_address(g),
+                    storage_keys: (0..usize::arbitrary(g) % 3)
+                        .map(|_| StorageKey(arb_bytes32(g)))
+                        .collect(),
+                })
+                .collect();
+
+            let authorization_list = (0..usize::arbitrary(g) % 3)
+                .map(|_| Authorization {
+                    chain_id: u64::arbitrary(g),
+                    address: arb_address(g),
+                    nonce: u64::arbitrary(g),
+                    y_parity: u8::arbitrary(g) % 2,
+                    r: arb_bytes32(g),
+                    s: arb_bytes32(g),
+                })
+                .collect();
+
+            Self {
+                access_list,
+                authorization_list,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{Signer, Wallet};
+    use ethers_core::k256::ecdsa::SigningKey;
+    use ethers_core::types::{self as et, transaction::eip2930};
+    use fvm_shared::{chainid::ChainID, crypto::signature::Signature};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::conv::from_eth::fvm_message_from_eip7702;
+    use crate::signed::{DomainHash, OriginKind, SignedMessage};
+
+    use super::{Eip7702TransactionRequest, EthExtras, EthTransaction, SignedAuthorization};
+
+    #[test]
+    fn eip7702_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(7702);
+        let sender = Wallet::new(&mut rng);
+        let authority = Wallet::new(&mut rng);
+
+        let mut auth = SignedAuthorization {
+            chain_id: et::U256::from(314),
+            address: et::Address::repeat_byte(0xaa),
+            nonce: et::U64::from(3),
+            ..Default::default()
+        };
+        let auth_sig = sign(authority.signer(), auth.sighash());
+        auth.y_parity = et::U64::from(auth_sig.v);
+        auth.r = auth_sig.r;
+        auth.s = auth_sig.s;
+
+        let tx = Eip7702TransactionRequest {
+            from: Some(sender.address()),
+            to: Some(et::Address::repeat_byte(0xbb).into()),
+            gas: Some(et::U256::from(100_000)),
+            value: Some(et::U256::from(1)),
+            data: Some(et::Bytes::from(vec![1, 2, 3])),
+            nonce: Some(et::U256::from(5)),
+            access_list: eip2930::AccessList(vec![eip2930::AccessListItem {
+                address: et::Address::repeat_byte(0xcc),
+                storage_keys: vec![et::H256::repeat_byte(0xdd)],
+            }]),
+            authorization_list: vec![auth.clone()],
+            max_priority_fee_per_gas: Some(et::U256::from(10)),
+            max_fee_per_gas: Some(et::U256::from(20)),
+            chain_id: Some(et::U64::from(314)),
+        };
+        let sig = sign(sender.signer(), tx.sighash());
+
+        let mut raw = vec![super::EIP7702_TX_TYPE];
+        raw.extend_from_slice(&tx.rlp_signed(&sig));
+
+        let (decoded, decoded_sig) = EthTransaction::decode_signed(&raw).expect("decode");
+        assert_eq!(decoded_sig, sig);
+        assert_eq!(decoded, EthTransaction::Eip7702(tx.clone()));
+        assert_eq!(decoded.hash(&sig), tx.hash(&sig));
+        assert_eq!(auth.authority().unwrap(), authority.address());
+
+        let extras = EthExtras::new(&tx.access_list, &tx.authorization_list).unwrap();
+        assert_eq!(extras.eth_access_list(), tx.access_list);
+        assert_eq!(extras.eth_authorization_list(), tx.authorization_list);
+
+        // The message can be verified and hashed like any other Ethereum transaction.
+        let signed = SignedMessage {
+            origin_kind: OriginKind::EthereumEIP7702,
+            message: fvm_message_from_eip7702(&tx).unwrap(),
+            signature: Signature::new_secp256k1(sig.to_vec()),
+            eth_extras: Some(extras),
+        };
+        let chain_id = ChainID::from(314);
+        signed.verify(&chain_id).expect("signature should verify");
+
+        match signed.domain_hash(&chain_id).unwrap() {
+            Some(DomainHash::Eth(h)) => assert_eq!(et::H256(h), tx.hash(&sig)),
+            other => panic!("unexpected domain hash: {other:?}"),
+        }
+    }
+
+    fn sign(sk: &SigningKey, hash: et::H256) -> et::Signature {
+        let (sig, recovery_id) = sk.sign_prehash_recoverable(hash.as_ref()).unwrap();
+        let bz = sig.to_bytes();
+        et::Signature {
+            r: et::U256::from_big_endian(&bz[..32]),
+            s: et::U256::from_big_endian(&bz[32..]),
+            v: recovery_id.to_byte() as u64,
+        }
+    }
+}
@@ -1,7 +1,9 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use cid::Cid;
 use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
 
 /// Messages involved in InterPlanetary Consensus.
@@ -11,6 +13,9 @@ pub enum IpcMessage {
     /// A top-down checkpoint parent finality proposal. This proposal should contain the latest parent
     /// state that to be checked and voted by validators.
     TopDownExec(ParentFinality),
+    /// Execute the bottom-up message batch of a child subnet, which the validators have
+    /// resolved from the child subnet through the IPLD Resolver.
+    BottomUpExec(BottomUpBatchRef),
 }
 
 /// A proposal of the parent view that validators will be voting on.
@@ -22,17 +27,45 @@ pub struct ParentFinality {
     pub block_hash: Vec<u8>,
 }
 
+/// Reference to the bottom-up message batch a child subnet committed to at a checkpoint height.
+///
+/// The content is the ABI encoded `IpcEnvelope[]` of the batch, stored as a raw IPLD block,
+/// which the child subnet validators announce to the parent subnet after the checkpoint.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct BottomUpBatchRef {
+    /// The child subnet which created the batch.
+    pub subnet_id: SubnetID,
+    /// The checkpoint height in the child subnet.
+    pub height: ChainEpoch,
+    /// The CID of the ABI encoded messages.
+    pub cid: Cid,
+}
+
 #[cfg(feature = "arb")]
 mod arb {
 
-    use crate::ipc::ParentFinality;
+    use crate::ipc::{BottomUpBatchRef, ParentFinality};
+    use fendermint_testing::arb::{ArbCid, ArbSubnetID};
     use quickcheck::{Arbitrary, Gen};
 
     use super::IpcMessage;
 
     impl Arbitrary for IpcMessage {
         fn arbitrary(g: &mut Gen) -> Self {
-            IpcMessage::TopDownExec(Arbitrary::arbitrary(g))
+            match u8::arbitrary(g) % 2 {
+                0 => IpcMessage::TopDownExec(Arbitrary::arbitrary(g)),
+                _ => IpcMessage::BottomUpExec(Arbitrary::arbitrary(g)),
+            }
+        }
+    }
+
+    impl Arbitrary for BottomUpBatchRef {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                subnet_id: ArbSubnetID::arbitrary(g).0,
+                height: u32::arbitrary(g).into(),
+                cid: ArbCid::arbitrary(g).0,
+            }
         }
     }
 
@@ -3,14 +3,23 @@
 use cid::Cid;
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared::{
-    address::Address, econ::TokenAmount, error::ExitCode, message::Message as FvmMessage,
-    version::NetworkVersion,
+    address::Address, clock::ChainEpoch, econ::TokenAmount, error::ExitCode,
+    message::Message as FvmMessage, version::NetworkVersion, MethodNum,
 };
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
 use fendermint_vm_encoding::IsHumanReadable;
 
+use crate::chain::ChainMessage;
+
+/// ABCI query path for the checkpoint signatures the node collected from the validators.
+///
+/// Unlike [`FvmQuery`], this is not a query over the state: the data is the IPLD encoded
+/// checkpoint height, and the value is the IPLD encoded `ipc_api::checkpoint::CheckpointSignatures`,
+/// or empty if the node has not seen any signatures at that height.
+pub const CHECKPOINT_SIGNATURES_QUERY_PATH: &str = "/checkpoint_signatures";
+
 /// Height at which to run a query.
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Default)]
 pub enum FvmQueryHeight {
@@ -72,12 +81,34 @@ pub enum FvmQuery {
     /// This is effectively a [`Call`], but it's included so that in the future
     /// it can do more sophisticated things with premiums, caps and over estimation.
     EstimateGas(Box<FvmMessage>),
+    /// Replay the messages of a block over the state of its parent with execution tracing enabled,
+    /// without adding them to the blockchain.
+    ///
+    /// The main motivation for this method is to facilitate the `trace_*` methods.
+    ///
+    /// The response is IPLD encoded `Vec<MessageTrace>`, one for each signed message.
+    Trace(Box<TraceBlock>),
     /// Retrieve the slowly changing state parameters that aren't part of the state tree.
     StateParams,
     /// Query the built-in actors known by the System actor.
     BuiltinActors,
 }
 
+/// A block to replay with tracing, as it was executed on chain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TraceBlock {
+    /// Height of the block.
+    pub height: ChainEpoch,
+    /// Time of the block, in seconds since the epoch.
+    pub timestamp: u64,
+    /// Hash of the block, if it is to be pushed to the chain metadata actor.
+    pub block_hash: Option<[u8; 32]>,
+    /// The messages of the block in the order of execution, up to the last one to trace.
+    ///
+    /// The IPC messages are executed to reproduce their effects, but only the signed ones are traced.
+    pub messages: Vec<ChainMessage>,
+}
+
 /// State of all actor implementations.
 ///
 /// This is a copy of `fvm::state_tree::ActorState` so that this crate
@@ -122,6 +153,49 @@ pub struct GasEstimate {
     pub gas_limit: u64,
 }
 
+/// Result of replaying a message with tracing.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct MessageTrace {
+    /// Exit code from the receipt.
+    pub exit_code: ExitCode,
+    /// Any information about failed execution from `ApplyRet::failure_info`.
+    pub info: String,
+    /// Return data from the receipt.
+    pub return_data: RawBytes,
+    /// Gas used by the message.
+    pub gas_used: u64,
+    /// The top level call of the message, if it got as far as being invoked.
+    pub trace: Option<CallTrace>,
+}
+
+/// A call between actors, reconstructed from the FVM execution trace.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct CallTrace {
+    /// The caller, as a delegated address if it has one, otherwise as an ID address.
+    pub from: Address,
+    /// The callee, as a delegated address if it has one, otherwise as an ID address,
+    /// unless it could not be resolved, in which case it's the address the caller used.
+    pub to: Address,
+    pub method_num: MethodNum,
+    pub value: TokenAmount,
+    /// Raw IPLD encoded parameters.
+    pub params: RawBytes,
+    pub gas_limit: u64,
+    /// Gas used by the call including its subcalls.
+    pub gas_used: u64,
+    pub read_only: bool,
+    pub exit_code: ExitCode,
+    /// Raw IPLD encoded return value.
+    pub return_data: RawBytes,
+    /// Syscall error if the call could not be made at all, e.g. due to insufficient funds.
+    pub error: Option<String>,
+    /// Indicate that the call is the transfer of the remaining balance of
+    /// an EVM actor which self-destructed during the message.
+    pub self_destruct: bool,
+    /// Calls made by the callee, in the order they happened.
+    pub calls: Vec<CallTrace>,
+}
+
 /// Slowly changing state parameters outside the state tree.
 #[serde_as]
 #[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
@@ -157,22 +231,35 @@ pub struct BuiltinActors {
 mod arb {
     use fendermint_testing::arb::{ArbAddress, ArbCid, ArbTokenAmount};
 
+    use crate::chain::ChainMessage;
     use crate::signed::SignedMessage;
 
-    use super::{ActorState, FvmQuery};
+    use super::{ActorState, FvmQuery, TraceBlock};
 
     impl quickcheck::Arbitrary for FvmQuery {
         fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-            match u8::arbitrary(g) % 5 {
+            match u8::arbitrary(g) % 6 {
                 0 => FvmQuery::Ipld(ArbCid::arbitrary(g).0),
                 1 => FvmQuery::ActorState(ArbAddress::arbitrary(g).0),
                 2 => FvmQuery::Call(Box::new(SignedMessage::arbitrary(g).into_message())),
                 3 => FvmQuery::EstimateGas(Box::new(SignedMessage::arbitrary(g).into_message())),
+                4 => FvmQuery::Trace(Box::new(TraceBlock::arbitrary(g))),
                 _ => FvmQuery::StateParams,
             }
         }
     }
 
+    impl quickcheck::Arbitrary for TraceBlock {
+        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            Self {
+                height: u32::arbitrary(g).into(),
+                timestamp: u64::arbitrary(g),
+                block_hash: bool::arbitrary(g).then(|| std::array::from_fn(|_| u8::arbitrary(g))),
+                messages: Vec::<ChainMessage>::arbitrary(g),
+            }
+        }
+    }
+
     impl quickcheck::Arbitrary for ActorState {
         fn arbitrary(g: &mut quickcheck::Gen) -> Self {
             Self {
@@ -9,17 +9,19 @@ use ethers_core::types::transaction::eip2718::TypedTransaction;
 use fendermint_crypto::{PublicKey, SecretKey};
 use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_actor_interface::{eam, evm};
-use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
 use fvm_shared::address::{Address, Payload};
 use fvm_shared::chainid::ChainID;
 use fvm_shared::crypto::signature::ops::recover_secp_public_key;
 use fvm_shared::crypto::signature::{Signature, SignatureType, SECP_SIG_LEN};
 use fvm_shared::message::Message;
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use thiserror::Error;
 
 use crate::conv::from_fvm;
+use crate::eth::{EthExtras, EthTransaction};
 
 enum Signable {
     /// Pair of transaction hash and from.
@@ -59,11 +61,17 @@ pub enum DomainHash {
 ///
 /// Tuple serialization is used because it might result in a more compact data structure for storage,
 /// and because the `Message` is already serialized as a tuple.
-#[derive(PartialEq, Clone, Debug, Serialize_tuple, Deserialize_tuple, Hash, Eq)]
+///
+/// The `eth_extras` are appended as a fourth element only if there are any, so all other
+/// messages keep the same 3-tuple encoding they had before the extras were introduced.
+#[derive(PartialEq, Clone, Debug, Hash, Eq)]
 pub struct SignedMessage {
     pub origin_kind: OriginKind,
     pub message: Message,
     pub signature: Signature,
+    /// Access and authorization lists of EIP-2930 and EIP-7702 transactions,
+    /// which are covered by the signature but have no place in the `message`.
+    pub eth_extras: Option<EthExtras>,
 }
 
 /// The original type of the message determines which fields of the message that should be used to
@@ -74,6 +82,13 @@ pub enum OriginKind {
     Fvm = 0,
     EthereumLegacy = 1,
     EthereumEIP1559 = 2,
+    /// Access list transaction; the access list is kept in [`SignedMessage::eth_extras`].
+    EthereumEIP2930 = 3,
+    /// Set code transaction; the authorization list is kept in [`SignedMessage::eth_extras`].
+    ///
+    /// The EVM actor cannot apply code delegations, so these transactions are not accepted
+    /// from clients; the signature can be checked, but the authorizations are not.
+    EthereumEIP7702 = 4,
 }
 
 impl From<u8> for OriginKind {
@@ -81,11 +96,67 @@ impl From<u8> for OriginKind {
         match value {
             0 => Self::Fvm,
             1 => Self::EthereumLegacy,
+            3 => Self::EthereumEIP2930,
+            4 => Self::EthereumEIP7702,
             _ => Self::EthereumEIP1559,
         }
     }
 }
 
+impl Serialize for SignedMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = if self.eth_extras.is_some() { 4 } else { 3 };
+        let mut tuple = serializer.serialize_tuple(len)?;
+        tuple.serialize_element(&self.origin_kind)?;
+        tuple.serialize_element(&self.message)?;
+        tuple.serialize_element(&self.signature)?;
+        if let Some(ref eth_extras) = self.eth_extras {
+            tuple.serialize_element(eth_extras)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SignedMessageVisitor;
+
+        impl<'de> Visitor<'de> for SignedMessageVisitor {
+            type Value = SignedMessage;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a signed message tuple of 3 or 4 elements")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let origin_kind = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let message = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let signature = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let eth_extras = seq.next_element()?;
+
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(5, &self));
+                }
+
+                Ok(SignedMessage {
+                    origin_kind,
+                    message,
+                    signature,
+                    eth_extras,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(SignedMessageVisitor)
+    }
+}
+
 impl SignedMessage {
     /// Generate a new signed message from fields.
     ///
@@ -99,6 +170,7 @@ impl SignedMessage {
             origin_kind,
             message,
             signature,
+            eth_extras: None,
         }
     }
 
@@ -119,6 +191,7 @@ impl SignedMessage {
             origin_kind,
             message,
             signature,
+            eth_extras: None,
         })
     }
 
@@ -173,36 +246,37 @@ impl SignedMessage {
     pub fn verify_signature(
         origin_kind: OriginKind,
         message: &Message,
+        eth_extras: Option<&EthExtras>,
         signature: &Signature,
         chain_id: &ChainID,
     ) -> Result<(), SignedMessageError> {
         match origin_kind {
-            OriginKind::Fvm => Self::verify_fvm_signature(message, chain_id, signature),
-            OriginKind::EthereumLegacy => Self::verify_ethereum_signature(
-                message,
-                chain_id,
-                signature,
-                |message, chain_id| {
-                    let tx = from_fvm::to_eth_legacy_request(message, chain_id)
-                        .map_err(SignedMessageError::Ethereum)?;
-                    Ok(TypedTransaction::Legacy(tx))
-                },
-            ),
-            OriginKind::EthereumEIP1559 => Self::verify_ethereum_signature(
+            OriginKind::Fvm => {
+                if eth_extras.is_some() {
+                    return Err(SignedMessageError::Ethereum(anyhow!(
+                        "FVM messages cannot have Ethereum extras"
+                    )));
+                }
+                Self::verify_fvm_signature(message, chain_id, signature)
+            }
+            OriginKind::EthereumLegacy
+            | OriginKind::EthereumEIP1559
+            | OriginKind::EthereumEIP2930
+            | OriginKind::EthereumEIP7702 => Self::verify_ethereum_signature(
                 message,
+                eth_extras,
                 chain_id,
                 signature,
                 |message, chain_id| {
-                    Ok(from_fvm::to_eth_eip1559_request(message, chain_id)
-                        .map_err(SignedMessageError::Ethereum)?
-                        .into())
+                    from_fvm::to_eth_transaction(origin_kind, message, eth_extras, chain_id)
                 },
             ),
         }
     }
 
-    fn verify_ethereum_signature<F: Fn(&Message, &ChainID) -> anyhow::Result<TypedTransaction>>(
+    fn verify_ethereum_signature<F: Fn(&Message, &ChainID) -> anyhow::Result<EthTransaction>>(
         message: &Message,
+        eth_extras: Option<&EthExtras>,
         chain_id: &ChainID,
         signature: &Signature,
         to_eth_txn: F,
@@ -228,6 +302,13 @@ impl SignedMessage {
         };
 
         if !is_eth_addr_compat(&message.to) {
+            // The extras would not be covered by the signature over the CID.
+            if eth_extras.is_some() {
+                return Err(SignedMessageError::Ethereum(anyhow!(
+                    "Ethereum extras are only allowed when calling Ethereum actors"
+                )));
+            }
+
             let mut data = Self::cid(message)?.to_bytes();
             data.extend(chain_id_bytes(chain_id).iter());
 
@@ -282,8 +363,13 @@ impl SignedMessage {
         chain_id: &ChainID,
     ) -> Result<Option<DomainHash>, SignedMessageError> {
         if is_eth_addr_deleg(&self.message.from) && is_eth_addr_compat(&self.message.to) {
-            let tx = from_fvm::to_eth_typed_transaction(self.origin_kind, self.message(), chain_id)
-                .map_err(SignedMessageError::Ethereum)?;
+            let tx = from_fvm::to_eth_transaction(
+                self.origin_kind,
+                self.message(),
+                self.eth_extras.as_ref(),
+                chain_id,
+            )
+            .map_err(SignedMessageError::Ethereum)?;
 
             let sig = from_fvm::to_eth_signature(self.signature(), true)
                 .map_err(SignedMessageError::Ethereum)?;
@@ -297,7 +383,13 @@ impl SignedMessage {
 
     /// Verifies that the from address of the message generated the signature.
     pub fn verify(&self, chain_id: &ChainID) -> Result<(), SignedMessageError> {
-        Self::verify_signature(self.origin_kind, &self.message, &self.signature, chain_id)
+        Self::verify_signature(
+            self.origin_kind,
+            &self.message,
+            self.eth_extras.as_ref(),
+            &self.signature,
+            chain_id,
+        )
     }
 
     /// Returns reference to the unsigned message.
@@ -446,6 +538,7 @@ fn recover_secp256k1(signature: &Signature, data: &[u8]) -> Result<PublicKey, St
 /// Signed message with an invalid random signature.
 #[cfg(feature = "arb")]
 mod arb {
+    use crate::eth::EthExtras;
     use crate::signed::OriginKind;
     use fendermint_testing::arb::ArbMessage;
     use fvm_shared::crypto::signature::Signature;
@@ -455,10 +548,18 @@ mod arb {
     /// An arbitrary `SignedMessage` that is at least as consistent as required for serialization.
     impl quickcheck::Arbitrary for SignedMessage {
         fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            let origin_kind = OriginKind::from(u8::arbitrary(g) % 5);
+            let eth_extras = match origin_kind {
+                OriginKind::EthereumEIP2930 | OriginKind::EthereumEIP7702 => {
+                    Some(EthExtras::arbitrary(g))
+                }
+                _ => None,
+            };
             Self {
-                origin_kind: OriginKind::from(u8::arbitrary(g) % 3),
+                origin_kind,
                 message: ArbMessage::arbitrary(g).0,
                 signature: Signature::arbitrary(g),
+                eth_extras,
             }
         }
     }
@@ -563,4 +664,17 @@ mod tests {
 
         signed.verify(&chain_id).expect("signature should be valid")
     }
+
+    /// Messages without extras have to be encoded the same way as before the extras existed.
+    #[quickcheck]
+    fn encoding_without_extras_is_a_3_tuple(msg: SignedMessage) {
+        let mut msg = msg;
+        msg.eth_extras = None;
+
+        let repr = fvm_ipld_encoding::to_vec(&msg).expect("failed to encode");
+        let legacy = fvm_ipld_encoding::to_vec(&(&msg.origin_kind, &msg.message, &msg.signature))
+            .expect("failed to encode tuple");
+
+        assert_eq!(repr, legacy);
+    }
 }
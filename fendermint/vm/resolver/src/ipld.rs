@@ -40,13 +40,20 @@ where
     /// Start taking tasks from the resolver pool and resolving them using the IPLD Resolver.
     pub async fn run(self) {
         loop {
-            let (task, use_own_subnet) = atomically(|| {
+            let (task, use_own_subnet, is_dropped) = atomically(|| {
                 let task = self.queue.read()?;
                 let use_own_subnet = task.use_own_subnet()?;
-                Ok((task, use_own_subnet))
+                let is_dropped = task.is_dropped()?;
+                Ok((task, use_own_subnet, is_dropped))
             })
             .await;
 
+            // The items have been evicted from the pool in the meantime.
+            if is_dropped {
+                tracing::debug!(cid = ?task.cid(), "dropping content resolution");
+                continue;
+            }
+
             start_resolve(
                 task,
                 self.client.clone(),
@@ -28,6 +28,8 @@ pub struct ResolveStatus<T> {
     use_own_subnet: TVar<bool>,
     /// The collection of items that all resolve to the same root CID and subnet.
     items: TVar<im::HashSet<T>>,
+    /// Indicate that all items have been removed, so the content is no longer needed.
+    is_dropped: TVar<bool>,
 }
 
 impl<T> ResolveStatus<T>
@@ -41,6 +43,7 @@ where
             is_resolved: TVar::new(false),
             use_own_subnet: TVar::new(use_own_subnet),
             items: TVar::new(items),
+            is_dropped: TVar::new(false),
         }
     }
 
@@ -59,6 +62,8 @@ pub struct ResolveTask {
     /// Flag to flip if consensus reached a state on its own
     /// where the majority of our own peers should have an item.
     use_own_subnet: TVar<bool>,
+    /// Flag flipped when the items were removed from the pool, so there is no need to retry.
+    is_dropped: TVar<bool>,
 }
 
 impl ResolveTask {
@@ -77,6 +82,10 @@ impl ResolveTask {
     pub fn use_own_subnet(&self) -> Stm<bool> {
         self.use_own_subnet.read_clone()
     }
+
+    pub fn is_dropped(&self) -> Stm<bool> {
+        self.is_dropped.read_clone()
+    }
 }
 
 pub type ResolveQueue = TChan<ResolveTask>;
@@ -146,6 +155,7 @@ where
                 key,
                 is_resolved: status.is_resolved.clone(),
                 use_own_subnet: status.use_own_subnet.clone(),
+                is_dropped: status.is_dropped.clone(),
             })?;
             Ok(status)
         }
@@ -172,12 +182,59 @@ where
         Ok(resolved)
     }
 
+    /// Collect all items, whether they have been resolved or not.
+    pub fn collect_all(&self) -> Stm<HashSet<T>> {
+        let mut all = HashSet::new();
+        let items = self.items.read()?;
+        for item in items.values() {
+            all.extend(item.items.read()?.iter().cloned());
+        }
+        Ok(all)
+    }
+
     /// Await the next item to be resolved.
     pub fn next(&self) -> Stm<ResolveTask> {
         self.queue.read()
     }
 
-    // TODO #197: Implement methods to remove executed items.
+    /// Remove an item which has been executed, so it's not collected again.
+    ///
+    /// The resolution status is dropped once none of the items mapping to it remain,
+    /// which also stops the background resolution of the content.
+    pub fn remove(&self, item: &T) -> Stm<()> {
+        let key = ResolveKey::from(item);
+        let mut items = self.items.read_clone()?;
+
+        if let Some(status) = items.get(&key).cloned() {
+            let mut status_items = status.items.read_clone()?;
+            status_items.remove(item);
+            if status_items.is_empty() {
+                items.remove(&key);
+                self.items.write(items)?;
+                status.is_dropped.write(true)?;
+            } else {
+                status.items.write(status_items)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove all items which don't match a predicate, e.g. because they have been
+    /// executed by other means, or turned out to be useless.
+    pub fn retain<F>(&self, f: F) -> Stm<()>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let items = self.items.read()?;
+        for status in items.values() {
+            for item in status.items.read()?.iter() {
+                if !f(item) {
+                    self.remove(item)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -299,4 +356,55 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn remove_executed() {
+        let pool = ResolvePool::new();
+        let item1 = TestItem::dummy(0);
+        let item2 = TestItem::dummy(1);
+
+        atomically(|| {
+            pool.add(item1.clone(), false)?.is_resolved.write(true)?;
+            pool.add(item2.clone(), false)?.is_resolved.write(true)?;
+
+            pool.remove(&item1)?;
+            assert!(pool.get_status(&item1)?.is_none());
+            assert!(pool.get_status(&item2)?.is_some());
+            assert!(!pool.collect_resolved()?.contains(&item1));
+
+            // Removing again is a no-op.
+            pool.remove(&item1)?;
+
+            pool.retain(|item| *item != item2)?;
+            assert!(pool.collect_resolved()?.is_empty());
+            assert!(pool.collect_all()?.is_empty());
+            Ok(())
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn removed_tasks_are_dropped() {
+        let pool = ResolvePool::new();
+        let item1 = TestItem::dummy(0);
+        let item2 = TestItem::dummy(1);
+
+        atomically(|| {
+            pool.add(item1.clone(), false)?;
+            pool.add(item2.clone(), false)?;
+            assert_eq!(pool.collect_all()?.len(), 2);
+            assert!(pool.collect_resolved()?.is_empty());
+
+            let task1 = pool.queue.read()?;
+            let task2 = pool.queue.read()?;
+
+            pool.retain(|item| *item != item1)?;
+
+            assert!(task1.is_dropped()?);
+            assert!(!task2.is_dropped()?);
+            assert!(pool.collect_all()?.contains(&item2));
+            Ok(())
+        })
+        .await;
+    }
 }
@@ -181,7 +181,9 @@ impl<T: ParentQueryProxy + Send + Sync + 'static> CachedFinalityProvider<T> {
 }
 
 impl<T> CachedFinalityProvider<T> {
-    pub(crate) fn new(
+    /// Create a provider starting from a known genesis epoch and committed finality,
+    /// without asking the parent for them, e.g. to feed it parent blocks directly in tests.
+    pub fn new(
         config: Config,
         genesis_epoch: BlockHeight,
         committed_finality: Option<IPCParentFinality>,
@@ -217,6 +219,15 @@ impl<T> CachedFinalityProvider<T> {
         self.inner.reset(finality)
     }
 
+    pub fn top_down_msgs_count(&self, height: BlockHeight) -> Stm<Option<usize>> {
+        self.inner.top_down_msgs_count(height)
+    }
+
+    /// Drop the cached blocks above the height after a reorg on the parent.
+    pub fn rollback(&self, height: BlockHeight) -> Stm<()> {
+        self.inner.rollback(height)
+    }
+
     pub fn new_parent_view(
         &self,
         height: BlockHeight,
@@ -237,106 +248,36 @@ impl<T> CachedFinalityProvider<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::finality::ParentViewPayload;
-    use crate::proxy::ParentQueryProxy;
     use crate::{
         BlockHeight, CachedFinalityProvider, Config, IPCParentFinality, ParentViewProvider,
-        SequentialKeyCache, NULL_ROUND_ERR_MSG,
     };
-    use anyhow::anyhow;
-    use async_trait::async_trait;
     use fvm_shared::address::Address;
+    use fvm_shared::clock::ChainEpoch;
     use fvm_shared::econ::TokenAmount;
     use ipc_api::cross::IpcEnvelope;
-    use ipc_api::staking::{PowerChange, PowerChangeRequest, PowerOperation};
+    use ipc_api::staking::{PowerChange, PowerOperation};
     use ipc_api::subnet_id::SubnetID;
-    use ipc_provider::manager::{GetBlockHashResult, TopDownQueryPayload};
+    use ipc_provider::manager::mock::MockParent;
+    use ipc_provider::manager::TopDownFinalityQuery;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::time::Duration;
 
-    /// Creates a mock of a new parent blockchain view. The key is the height and the value is the
-    /// block hash. If block hash is None, it means the current height is a null block.
-    macro_rules! new_parent_blocks {
-        ($($key:expr => $val:expr),* ,) => (
-            hash_map!($($key => $val),*)
-        );
-        ($($key:expr => $val:expr),*) => ({
-            let mut map = SequentialKeyCache::sequential();
-            $( map.append($key, $val).unwrap(); )*
-            map
-        });
-    }
-
-    struct TestParentProxy {
-        blocks: SequentialKeyCache<BlockHeight, Option<ParentViewPayload>>,
-    }
-
-    #[async_trait]
-    impl ParentQueryProxy for TestParentProxy {
-        async fn get_chain_head_height(&self) -> anyhow::Result<BlockHeight> {
-            Ok(self.blocks.upper_bound().unwrap())
-        }
-
-        async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight> {
-            Ok(self.blocks.lower_bound().unwrap() - 1)
-        }
+    /// The parent height at which the child subnet was created.
+    const GENESIS_EPOCH: BlockHeight = 99;
 
-        async fn get_block_hash(&self, height: BlockHeight) -> anyhow::Result<GetBlockHashResult> {
-            let r = self.blocks.get_value(height).unwrap();
-            if r.is_none() {
-                return Err(anyhow!(NULL_ROUND_ERR_MSG));
-            }
-
-            for h in (self.blocks.lower_bound().unwrap()..height).rev() {
-                let v = self.blocks.get_value(h).unwrap();
-                if v.is_none() {
-                    continue;
-                }
-                return Ok(GetBlockHashResult {
-                    parent_block_hash: v.clone().unwrap().0,
-                    block_hash: r.clone().unwrap().0,
-                });
-            }
-            panic!("invalid testing data")
-        }
+    /// Whether the blocks produced on the parent from height 100 on are null rounds.
+    const NULL_ROUNDS: [bool; 7] = [false, false, false, false, true, true, false];
 
-        async fn get_top_down_msgs(
-            &self,
-            height: BlockHeight,
-        ) -> anyhow::Result<TopDownQueryPayload<Vec<IpcEnvelope>>> {
-            let r = self.blocks.get_value(height).cloned().unwrap();
-            if r.is_none() {
-                return Err(anyhow!(NULL_ROUND_ERR_MSG));
-            }
-            let r = r.unwrap();
-            Ok(TopDownQueryPayload {
-                value: r.2,
-                block_hash: r.0,
-            })
-        }
-
-        async fn get_validator_changes(
-            &self,
-            height: BlockHeight,
-        ) -> anyhow::Result<TopDownQueryPayload<Vec<PowerChangeRequest>>> {
-            let r = self.blocks.get_value(height).cloned().unwrap();
-            if r.is_none() {
-                return Err(anyhow!(NULL_ROUND_ERR_MSG));
-            }
-            let r = r.unwrap();
-            Ok(TopDownQueryPayload {
-                value: r.1,
-                block_hash: r.0,
-            })
-        }
+    fn new_parent() -> MockParent {
+        let child = SubnetID::new(10, vec![Address::new_id(1000)]);
+        MockParent::new(child, GENESIS_EPOCH as ChainEpoch)
     }
 
-    fn new_provider(
-        blocks: SequentialKeyCache<BlockHeight, Option<ParentViewPayload>>,
-    ) -> CachedFinalityProvider<TestParentProxy> {
+    async fn new_provider(parent: &MockParent) -> CachedFinalityProvider<MockParent> {
         let config = Config {
             chain_head_delay: 2,
+            use_finalized_tag: false,
             polling_interval: Default::default(),
             exponential_back_off: Default::default(),
             exponential_retry_limit: 0,
@@ -344,20 +285,26 @@ mod tests {
             max_cache_blocks: None,
             proposal_delay: None,
         };
-        let genesis_epoch = blocks.lower_bound().unwrap();
-        let proxy = Arc::new(TestParentProxy { blocks });
+        let block_hash = TopDownFinalityQuery::get_block_hash(parent, GENESIS_EPOCH as ChainEpoch)
+            .await
+            .unwrap()
+            .block_hash;
         let committed_finality = IPCParentFinality {
-            height: genesis_epoch,
-            block_hash: vec![0; 32],
+            height: GENESIS_EPOCH,
+            block_hash,
         };
 
-        CachedFinalityProvider::new(config, genesis_epoch, Some(committed_finality), proxy)
+        CachedFinalityProvider::new(
+            config,
+            GENESIS_EPOCH,
+            Some(committed_finality),
+            Arc::new(parent.clone()),
+        )
     }
 
-    fn new_cross_msg(nonce: u64) -> IpcEnvelope {
-        let subnet_id = SubnetID::new(10, vec![Address::new_id(1000)]);
+    fn new_cross_msg(subnet_id: &SubnetID, nonce: u64) -> IpcEnvelope {
         let mut msg = IpcEnvelope::new_fund_msg(
-            &subnet_id,
+            subnet_id,
             &Address::new_id(1),
             &Address::new_id(2),
             TokenAmount::from_atto(100),
@@ -367,14 +314,11 @@ mod tests {
         msg
     }
 
-    fn new_validator_changes(configuration_number: u64) -> PowerChangeRequest {
-        PowerChangeRequest {
-            configuration_number,
-            change: PowerChange {
-                op: PowerOperation::SetPower,
-                payload: vec![],
-                validator: Address::new_id(1),
-            },
+    fn new_validator_change() -> PowerChange {
+        PowerChange {
+            op: PowerOperation::SetPower,
+            payload: vec![],
+            validator: Address::new_id(1),
         }
     }
 
@@ -403,44 +347,56 @@ mod tests {
 
     #[tokio::test]
     async fn test_query_topdown_msgs() {
-        let parent_blocks = new_parent_blocks!(
-            100 => Some((vec![0; 32], vec![], vec![new_cross_msg(0)])),   // genesis block
-            101 => Some((vec![1; 32], vec![], vec![new_cross_msg(1)])),
-            102 => Some((vec![2; 32], vec![], vec![new_cross_msg(2)])),
-            103 => Some((vec![3; 32], vec![], vec![new_cross_msg(3)])),
-            104 => None,
-            105 => None,
-            106 => Some((vec![6; 32], vec![], vec![new_cross_msg(6)]))
-        );
-        let provider = new_provider(parent_blocks);
+        let parent = new_parent();
+        let child = parent.child_subnet();
+
+        let mut expected = Vec::new();
+        for null_round in NULL_ROUNDS {
+            if null_round {
+                parent.produce_null_round();
+                continue;
+            }
+            let msg = new_cross_msg(&child, expected.len() as u64);
+            parent.send_top_down_msg(&child, msg.clone());
+            expected.push(msg);
+            parent.produce_block();
+        }
+        assert_eq!(parent.head(), 106);
+
+        let provider = new_provider(&parent).await;
         let messages = provider.top_down_msgs_from(100, 106).await.unwrap();
 
-        assert_eq!(
-            messages,
-            vec![
-                new_cross_msg(0),
-                new_cross_msg(1),
-                new_cross_msg(2),
-                new_cross_msg(3),
-                new_cross_msg(6),
-            ]
-        )
+        assert_eq!(messages, expected);
     }
 
     #[tokio::test]
     async fn test_query_validator_changes() {
-        let parent_blocks = new_parent_blocks!(
-            100 => Some((vec![0; 32], vec![new_validator_changes(0)], vec![])),   // genesis block
-            101 => Some((vec![1; 32], vec![new_validator_changes(1)], vec![])),
-            102 => Some((vec![2; 32], vec![], vec![])),
-            103 => Some((vec![3; 32], vec![new_validator_changes(3)], vec![])),
-            104 => None,
-            105 => None,
-            106 => Some((vec![6; 32], vec![new_validator_changes(6)], vec![]))
-        );
-        let provider = new_provider(parent_blocks);
-        let messages = provider.validator_changes_from(100, 106).await.unwrap();
+        let parent = new_parent();
+        let child = parent.child_subnet();
 
-        assert_eq!(messages.len(), 4)
+        for (i, null_round) in NULL_ROUNDS.into_iter().enumerate() {
+            if null_round {
+                parent.produce_null_round();
+                continue;
+            }
+            // One of the blocks has no changes.
+            if i != 2 {
+                parent.send_validator_change(&child, new_validator_change());
+            }
+            parent.produce_block();
+        }
+        assert_eq!(parent.head(), 106);
+
+        let provider = new_provider(&parent).await;
+        let changes = provider.validator_changes_from(100, 106).await.unwrap();
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|c| c.configuration_number)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
     }
 }
@@ -69,6 +69,24 @@ impl FinalityWithNull {
         self.last_committed_finality.write(Some(finality))
     }
 
+    /// Number of top-down messages at a height in the cache, zero for null rounds,
+    /// or `None` if the height is not cached.
+    pub fn top_down_msgs_count(&self, height: BlockHeight) -> Stm<Option<usize>> {
+        let cache = self.cached_data.read()?;
+        Ok(cache
+            .get_value(height)
+            .map(|v| v.as_ref().map(|p| p.2.len()).unwrap_or_default()))
+    }
+
+    /// Drop the cached blocks above the height, which the parent chain has reorganised,
+    /// keeping the committed finality and everything before the fork.
+    pub fn rollback(&self, height: BlockHeight) -> Stm<()> {
+        self.cached_data.update(|mut cache| {
+            cache.remove_key_above(height);
+            cache
+        })
+    }
+
     pub fn new_parent_view(
         &self,
         height: BlockHeight,
@@ -373,6 +391,7 @@ mod tests {
     ) -> FinalityWithNull {
         let config = Config {
             chain_head_delay: 2,
+            use_finalized_tag: false,
             polling_interval: Default::default(),
             exponential_back_off: Default::default(),
             exponential_retry_limit: 0,
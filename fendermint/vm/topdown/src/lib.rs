@@ -46,6 +46,10 @@ pub struct Config {
     /// conservative and avoid other from rejecting the proposal because they don't see the
     /// height as final yet.
     pub chain_head_delay: BlockHeight,
+    /// Use the block the parent reports as finalized, when it has one, instead of
+    /// only relying on `chain_head_delay`.
+    #[serde(default)]
+    pub use_finalized_tag: bool,
     /// Parent syncing cron period, in seconds
     pub polling_interval: Duration,
     /// Top down exponential back off retry base
@@ -68,6 +72,7 @@ impl Config {
     ) -> Self {
         Self {
             chain_head_delay,
+            use_finalized_tag: false,
             polling_interval,
             exponential_back_off,
             exponential_retry_limit,
@@ -87,6 +92,11 @@ impl Config {
         self
     }
 
+    pub fn with_use_finalized_tag(mut self, use_finalized_tag: bool) -> Self {
+        self.use_finalized_tag = use_finalized_tag;
+        self
+    }
+
     pub fn with_max_cache_blocks(mut self, max_cache_blocks: BlockHeight) -> Self {
         self.max_cache_blocks = Some(max_cache_blocks);
         self
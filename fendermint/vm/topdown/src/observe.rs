@@ -33,6 +33,8 @@ register_metrics! {
         );
     TOPDOWN_PARENT_FINALITY_COMMITTED_HEIGHT: IntGauge
         = register_int_gauge!("topdown_parent_finality_committed_height", "Parent finality committed on chain");
+    TOPDOWN_PARENT_REORG_DEPTH: IntGauge
+        = register_int_gauge!("topdown_parent_reorg_depth", "Number of cached parent blocks rolled back in the latest reorg");
 }
 
 impl_traceables!(
@@ -46,6 +48,8 @@ impl_traceables!(
     ParentFinalityCommitted<'a>
 );
 
+impl_traceables!(TraceLevel::Warn, "Topdown", ParentChainReorged);
+
 #[derive(Debug)]
 pub struct ParentRpcCalled<'a> {
     pub source: &'a str,
@@ -148,6 +152,22 @@ impl Recordable for ParentFinalityCommitted<'_> {
     }
 }
 
+#[derive(Debug)]
+pub struct ParentChainReorged {
+    /// The highest block we had cached.
+    pub tip_height: BlockHeight,
+    /// The highest cached block which is still on the parent chain.
+    pub fork_height: BlockHeight,
+    /// The number of heights rolled back.
+    pub depth: BlockHeight,
+}
+
+impl Recordable for ParentChainReorged {
+    fn record_metrics(&self) {
+        TOPDOWN_PARENT_REORG_DEPTH.set(self.depth as i64);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,5 +256,11 @@ mod tests {
             local_height: Some(0),
             proposer: Some("proposerOption"),
         });
+
+        emit(ParentChainReorged {
+            tip_height: 10,
+            fork_height: 8,
+            depth: 2,
+        });
     }
 }
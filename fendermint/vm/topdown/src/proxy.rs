@@ -11,6 +11,8 @@ use ipc_api::cross::IpcEnvelope;
 use ipc_api::staking::PowerChangeRequest;
 use ipc_api::subnet_id::SubnetID;
 use ipc_observability::emit;
+#[cfg(any(test, feature = "test-util"))]
+use ipc_provider::manager::TopDownFinalityQuery;
 use ipc_provider::manager::{GetBlockHashResult, TopDownQueryPayload};
 use ipc_provider::IpcProvider;
 use std::time::Instant;
@@ -22,6 +24,14 @@ pub trait ParentQueryProxy {
     /// Get the parent chain head block number or block height
     async fn get_chain_head_height(&self) -> anyhow::Result<BlockHeight>;
 
+    /// Get the height of the latest block the parent considers final by itself, e.g. by the
+    /// `finalized` block tag of an Ethereum parent.
+    ///
+    /// Returns `None` if the parent has no such notion, in which case only the chain head is used.
+    async fn get_finalized_chain_head_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(None)
+    }
+
     /// Get the genesis epoch of the child subnet, i.e. the epoch that the subnet was created in
     /// the parent subnet.
     async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight>;
@@ -72,6 +82,14 @@ impl ParentQueryProxy for IPCProviderProxy {
         Ok(height as BlockHeight)
     }
 
+    async fn get_finalized_chain_head_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let height = self
+            .ipc_provider
+            .finalized_chain_head(&self.parent_subnet)
+            .await?;
+        Ok(height.map(|h| h as BlockHeight))
+    }
+
     /// Get the genesis epoch of the child subnet, i.e. the epoch that the subnet was created in
     /// the parent subnet.
     async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight> {
@@ -141,6 +159,16 @@ impl ParentQueryProxy for IPCProviderProxyWithLatency {
         .await
     }
 
+    #[instrument(skip(self))]
+    async fn get_finalized_chain_head_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        emit_event_with_latency(
+            &self.inner.parent_subnet.to_string(),
+            "finalized_chain_head",
+            || async { self.inner.get_finalized_chain_head_height().await },
+        )
+        .await
+    }
+
     #[instrument(skip(self))]
     async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight> {
         emit_event_with_latency(
@@ -211,3 +239,44 @@ where
 
     result
 }
+
+/// Follow the child subnet of an in-memory parent chain.
+#[cfg(any(test, feature = "test-util"))]
+#[async_trait]
+impl ParentQueryProxy for ipc_provider::manager::mock::MockParent {
+    async fn get_chain_head_height(&self) -> anyhow::Result<BlockHeight> {
+        Ok(self.head() as BlockHeight)
+    }
+
+    async fn get_finalized_chain_head_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(self.finalized_head().map(|h| h as BlockHeight))
+    }
+
+    async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight> {
+        Ok(self.child_genesis_epoch() as BlockHeight)
+    }
+
+    async fn get_block_hash(&self, height: BlockHeight) -> anyhow::Result<GetBlockHashResult> {
+        TopDownFinalityQuery::get_block_hash(self, height as ChainEpoch).await
+    }
+
+    async fn get_top_down_msgs(
+        &self,
+        height: BlockHeight,
+    ) -> anyhow::Result<TopDownQueryPayload<Vec<IpcEnvelope>>> {
+        TopDownFinalityQuery::get_top_down_msgs(self, &self.child_subnet(), height as ChainEpoch)
+            .await
+    }
+
+    async fn get_validator_changes(
+        &self,
+        height: BlockHeight,
+    ) -> anyhow::Result<TopDownQueryPayload<Vec<PowerChangeRequest>>> {
+        TopDownFinalityQuery::get_validator_changeset(
+            self,
+            &self.child_subnet(),
+            height as ChainEpoch,
+        )
+        .await
+    }
+}
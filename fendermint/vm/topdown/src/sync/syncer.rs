@@ -16,7 +16,7 @@ use libp2p::futures::TryFutureExt;
 use std::sync::Arc;
 use tracing::instrument;
 
-use crate::observe::ParentFinalityAcquired;
+use crate::observe::{ParentChainReorged, ParentFinalityAcquired};
 use ipc_observability::{emit, serde::HexEncodableBlockHash};
 
 /// Parent syncer that constantly poll parent. This struct handles lotus null blocks and deferred
@@ -77,7 +77,7 @@ where
                 latest_height_fetched,
                 "chain head went backwards, potential reorg detected from height"
             );
-            return self.reset().await;
+            return self.rollback_to_fork_point().await;
         }
 
         if latest_height_fetched == chain_head {
@@ -101,8 +101,10 @@ where
             {
                 Ok(h) => h,
                 Err(Error::ParentChainReorgDetected) => {
-                    tracing::warn!("potential reorg detected, clear cache and retry");
-                    self.reset().await?;
+                    tracing::warn!(
+                        "potential reorg detected, roll back to the fork point and retry"
+                    );
+                    self.rollback_to_fork_point().await?;
                     break;
                 }
                 Err(e) => return Err(anyhow!(e)),
@@ -281,6 +283,16 @@ where
     }
 
     async fn finalized_chain_head(&self) -> anyhow::Result<Option<BlockHeight>> {
+        if self.config.use_finalized_tag {
+            if let Some(h) = self.parent_proxy.get_finalized_chain_head_height().await? {
+                return Ok(Some(h));
+            }
+            tracing::warn!(
+                chain_head_delay = self.config.chain_head_delay,
+                "parent has no finalized block, falling back to the chain head delay"
+            );
+        }
+
         let parent_chain_head_height = self.parent_proxy.get_chain_head_height().await?;
         // sanity check
         if parent_chain_head_height < self.config.chain_head_delay {
@@ -294,6 +306,70 @@ where
         ))
     }
 
+    /// Find the highest cached block which is still part of the parent chain and drop
+    /// everything above it. Falls back to a full reset if the reorg goes deeper than
+    /// the last committed finality.
+    async fn rollback_to_fork_point(&self) -> anyhow::Result<()> {
+        let (tip_height, finality) = atomically(|| {
+            Ok((
+                self.provider.latest_height()?,
+                self.provider.last_committed_finality()?,
+            ))
+        })
+        .await;
+
+        let (Some(tip_height), Some(finality)) = (tip_height, finality) else {
+            return self.reset().await;
+        };
+
+        let mut fork_height = None;
+        for height in (finality.height..=tip_height).rev() {
+            // Null rounds in the cache have nothing to compare against.
+            let Some(cached_hash) = atomically(|| self.provider.block_hash(height)).await else {
+                continue;
+            };
+            match self.parent_proxy.get_block_hash(height).await {
+                Ok(res) if res.block_hash == cached_hash => {
+                    fork_height = Some(height);
+                    break;
+                }
+                Ok(_) => continue,
+                Err(e) if is_null_round_str(&e.to_string()) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let Some(fork_height) = fork_height else {
+            tracing::error!(
+                finality_height = finality.height,
+                "parent reorg goes below the last committed finality, clear cache"
+            );
+            return self.reset().await;
+        };
+
+        if fork_height == tip_height {
+            tracing::debug!(tip_height, "cached blocks are still on the parent chain");
+            return Ok(());
+        }
+
+        atomically(|| {
+            self.provider.rollback(fork_height)?;
+            self.vote_tally.rollback(fork_height)
+        })
+        .await;
+
+        let depth = tip_height - fork_height;
+        tracing::warn!(tip_height, fork_height, depth, "rolled back parent reorg");
+
+        emit(ParentChainReorged {
+            tip_height,
+            fork_height,
+            depth,
+        });
+
+        Ok(())
+    }
+
     /// Reset the cache in the face of a reorg
     async fn reset(&self) -> anyhow::Result<()> {
         let finality = query_starting_finality(&self.query, &self.parent_proxy).await?;
@@ -397,19 +473,26 @@ mod tests {
     use crate::proxy::ParentQueryProxy;
     use crate::sync::syncer::LotusParentSyncer;
     use crate::sync::ParentFinalityStateQuery;
-    use crate::voting::VoteTally;
+    use crate::voting::{ValidatorKey, VoteTally};
     use crate::{
         BlockHash, BlockHeight, CachedFinalityProvider, Config, IPCParentFinality,
         SequentialKeyCache, Toggle, NULL_ROUND_ERR_MSG,
     };
     use anyhow::anyhow;
-    use async_stm::atomically;
+    use async_stm::{atomically, atomically_or_err};
     use async_trait::async_trait;
+    use fendermint_crypto::SecretKey;
     use fendermint_vm_genesis::{Power, Validator};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
     use ipc_api::cross::IpcEnvelope;
     use ipc_api::staking::PowerChangeRequest;
-    use ipc_provider::manager::{GetBlockHashResult, TopDownQueryPayload};
-    use std::sync::Arc;
+    use ipc_api::subnet_id::SubnetID;
+    use ipc_provider::manager::mock::MockParent;
+    use ipc_provider::manager::{
+        GetBlockHashResult, SubnetManager, TopDownFinalityQuery, TopDownQueryPayload,
+    };
+    use std::sync::{Arc, Mutex};
 
     /// How far behind the tip of the chain do we consider blocks final in the tests.
     const FINALITY_DELAY: u64 = 2;
@@ -428,27 +511,28 @@ mod tests {
     }
 
     struct TestParentProxy {
-        blocks: SequentialKeyCache<BlockHeight, Option<BlockHash>>,
+        blocks: Mutex<SequentialKeyCache<BlockHeight, Option<BlockHash>>>,
     }
 
     #[async_trait]
     impl ParentQueryProxy for TestParentProxy {
         async fn get_chain_head_height(&self) -> anyhow::Result<BlockHeight> {
-            Ok(self.blocks.upper_bound().unwrap())
+            Ok(self.blocks.lock().unwrap().upper_bound().unwrap())
         }
 
         async fn get_genesis_epoch(&self) -> anyhow::Result<BlockHeight> {
-            Ok(self.blocks.lower_bound().unwrap() - 1)
+            Ok(self.blocks.lock().unwrap().lower_bound().unwrap() - 1)
         }
 
         async fn get_block_hash(&self, height: BlockHeight) -> anyhow::Result<GetBlockHashResult> {
-            let r = self.blocks.get_value(height).unwrap();
+            let blocks = self.blocks.lock().unwrap();
+            let r = blocks.get_value(height).unwrap();
             if r.is_none() {
                 return Err(anyhow!(NULL_ROUND_ERR_MSG));
             }
 
-            for h in (self.blocks.lower_bound().unwrap()..height).rev() {
-                let v = self.blocks.get_value(h).unwrap();
+            for h in (blocks.lower_bound().unwrap()..height).rev() {
+                let v = blocks.get_value(h).unwrap();
                 if v.is_none() {
                     continue;
                 }
@@ -466,7 +550,14 @@ mod tests {
         ) -> anyhow::Result<TopDownQueryPayload<Vec<IpcEnvelope>>> {
             Ok(TopDownQueryPayload {
                 value: vec![],
-                block_hash: self.blocks.get_value(height).cloned().unwrap().unwrap(),
+                block_hash: self
+                    .blocks
+                    .lock()
+                    .unwrap()
+                    .get_value(height)
+                    .cloned()
+                    .unwrap()
+                    .unwrap(),
             })
         }
 
@@ -476,7 +567,14 @@ mod tests {
         ) -> anyhow::Result<TopDownQueryPayload<Vec<PowerChangeRequest>>> {
             Ok(TopDownQueryPayload {
                 value: vec![],
-                block_hash: self.blocks.get_value(height).cloned().unwrap().unwrap(),
+                block_hash: self
+                    .blocks
+                    .lock()
+                    .unwrap()
+                    .get_value(height)
+                    .cloned()
+                    .unwrap()
+                    .unwrap(),
             })
         }
     }
@@ -485,8 +583,28 @@ mod tests {
         blocks: SequentialKeyCache<BlockHeight, Option<BlockHash>>,
         sync_many: bool,
     ) -> LotusParentSyncer<TestParentFinalityStateQuery, TestParentProxy> {
+        let genesis_epoch = blocks.lower_bound().unwrap();
+        let proxy = Arc::new(TestParentProxy {
+            blocks: Mutex::new(blocks),
+        });
+        let committed_finality = IPCParentFinality {
+            height: genesis_epoch,
+            block_hash: vec![0; 32],
+        };
+        new_syncer_with_proxy(proxy, committed_finality, sync_many).await
+    }
+
+    async fn new_syncer_with_proxy<P>(
+        proxy: Arc<P>,
+        committed_finality: IPCParentFinality,
+        sync_many: bool,
+    ) -> LotusParentSyncer<TestParentFinalityStateQuery, P>
+    where
+        P: ParentQueryProxy + Send + Sync + 'static,
+    {
         let config = Config {
             chain_head_delay: FINALITY_DELAY,
+            use_finalized_tag: false,
             polling_interval: Default::default(),
             exponential_back_off: Default::default(),
             exponential_retry_limit: 0,
@@ -494,12 +612,7 @@ mod tests {
             max_cache_blocks: None,
             proposal_delay: None,
         };
-        let genesis_epoch = blocks.lower_bound().unwrap();
-        let proxy = Arc::new(TestParentProxy { blocks });
-        let committed_finality = IPCParentFinality {
-            height: genesis_epoch,
-            block_hash: vec![0; 32],
-        };
+        let genesis_epoch = committed_finality.height;
 
         let vote_tally = VoteTally::new(
             vec![],
@@ -566,6 +679,29 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn finalized_tag_falls_back_to_chain_head_delay() {
+        let parent_blocks = new_parent_blocks!(
+            100 => Some(vec![0; 32]),   // genesis block
+            101 => Some(vec![1; 32]),
+            102 => Some(vec![2; 32]),
+            103 => Some(vec![3; 32]),
+            104 => Some(vec![4; 32]),   // after chain head delay, we fetch only to here
+            105 => Some(vec![5; 32]),
+            106 => Some(vec![6; 32])    // chain head
+        );
+
+        let mut syncer = new_syncer(parent_blocks, false).await;
+        // The test parent doesn't support the finalized tag.
+        syncer.config.use_finalized_tag = true;
+
+        for h in 101..=104 {
+            syncer.sync().await.unwrap();
+            let p = atomically(|| syncer.provider.latest_height()).await;
+            assert_eq!(p, Some(h));
+        }
+    }
+
     #[tokio::test]
     async fn with_non_null_block() {
         let parent_blocks = new_parent_blocks!(
@@ -593,4 +729,194 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn rollback_to_fork_point() {
+        let parent_blocks = new_parent_blocks!(
+            100 => Some(vec![0; 32]),   // genesis block
+            101 => Some(vec![1; 32]),
+            102 => Some(vec![2; 32]),
+            103 => Some(vec![3; 32]),
+            104 => Some(vec![4; 32]),
+            105 => Some(vec![5; 32]),
+            106 => Some(vec![6; 32])    // chain head
+        );
+
+        let mut syncer = new_syncer(parent_blocks, true).await;
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(104)
+        );
+
+        // The parent replaces everything after 102 with a longer fork.
+        *syncer.parent_proxy.blocks.lock().unwrap() = new_parent_blocks!(
+            100 => Some(vec![0; 32]),
+            101 => Some(vec![1; 32]),
+            102 => Some(vec![2; 32]),   // fork point
+            103 => Some(vec![13; 32]),
+            104 => None,
+            105 => Some(vec![15; 32]),
+            106 => Some(vec![16; 32]),
+            107 => Some(vec![17; 32]),
+            108 => Some(vec![18; 32])   // chain head
+        );
+
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(102)
+        );
+        assert_eq!(atomically(|| syncer.vote_tally.latest_height()).await, 102);
+        assert_eq!(
+            atomically(|| syncer.provider.block_hash(102)).await,
+            Some(vec![2; 32])
+        );
+
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(106)
+        );
+        assert_eq!(
+            atomically(|| syncer.provider.block_hash(103)).await,
+            Some(vec![13; 32])
+        );
+        assert_eq!(atomically(|| syncer.provider.block_hash(104)).await, None);
+    }
+
+    #[tokio::test]
+    async fn sync_from_mock_parent() {
+        let child = SubnetID::new(123, vec![Address::new_id(1001)]);
+        let parent = MockParent::new(child.clone(), 100);
+        let alice = Address::new_id(100);
+        parent.credit(alice, TokenAmount::from_whole(10));
+
+        parent
+            .fund(
+                child.clone(),
+                Address::new_id(64),
+                alice,
+                alice,
+                TokenAmount::from_whole(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(parent.produce_block(), 101);
+        assert_eq!(parent.produce_null_round(), 102);
+        assert_eq!(parent.produce_blocks(4), 106);
+
+        let genesis_hash = TopDownFinalityQuery::get_block_hash(&parent, 100)
+            .await
+            .unwrap()
+            .block_hash;
+        let committed_finality = IPCParentFinality {
+            height: 100,
+            block_hash: genesis_hash,
+        };
+        let mut syncer =
+            new_syncer_with_proxy(Arc::new(parent.clone()), committed_finality, true).await;
+
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(104)
+        );
+        assert_eq!(
+            atomically(|| syncer.provider.top_down_msgs_count(101)).await,
+            Some(1)
+        );
+        assert_eq!(atomically(|| syncer.provider.block_hash(102)).await, None);
+
+        // Everything after 101 is replaced by a longer fork.
+        parent.reorg(5).unwrap();
+        assert_eq!(parent.produce_blocks(6), 107);
+
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(101)
+        );
+
+        syncer.sync().await.unwrap();
+        assert_eq!(
+            atomically(|| syncer.provider.latest_height()).await,
+            Some(105)
+        );
+
+        let expected = TopDownFinalityQuery::get_block_hash(&parent, 102)
+            .await
+            .unwrap()
+            .block_hash;
+        assert_eq!(
+            atomically(|| syncer.provider.block_hash(102)).await,
+            Some(expected)
+        );
+    }
+
+    #[tokio::test]
+    async fn votes_on_blocks_synced_from_mock_parent() {
+        let child = SubnetID::new(123, vec![Address::new_id(1001)]);
+        let parent = MockParent::new(child, 100);
+
+        assert_eq!(parent.produce_blocks(3), 103);
+        assert_eq!(parent.produce_null_round(), 104);
+        assert_eq!(parent.produce_blocks(3), 107);
+
+        let genesis_hash = TopDownFinalityQuery::get_block_hash(&parent, 100)
+            .await
+            .unwrap()
+            .block_hash;
+        let committed_finality = IPCParentFinality {
+            height: 100,
+            block_hash: genesis_hash,
+        };
+        let mut syncer =
+            new_syncer_with_proxy(Arc::new(parent.clone()), committed_finality, true).await;
+
+        let mut rng = rand::thread_rng();
+        let validators = (0..4)
+            .map(|_| ValidatorKey::from(SecretKey::random(&mut rng).public_key()))
+            .collect::<Vec<_>>();
+        let power_table = validators
+            .iter()
+            .map(|v| (v.clone(), 1))
+            .collect::<Vec<_>>();
+        atomically(|| syncer.vote_tally.set_power_table(power_table.clone())).await;
+
+        syncer.sync().await.unwrap();
+        assert_eq!(atomically(|| syncer.vote_tally.latest_height()).await, 105);
+
+        let hash_103 = TopDownFinalityQuery::get_block_hash(&parent, 103)
+            .await
+            .unwrap()
+            .block_hash;
+        let hash_105 = TopDownFinalityQuery::get_block_hash(&parent, 105)
+            .await
+            .unwrap()
+            .block_hash;
+
+        // Two validators have seen the latest final block, one is lagging behind the null round.
+        let votes = [
+            (&validators[0], 105, &hash_105),
+            (&validators[1], 105, &hash_105),
+            (&validators[2], 103, &hash_103),
+        ];
+        for (validator, height, hash) in votes {
+            atomically_or_err(|| {
+                syncer
+                    .vote_tally
+                    .add_vote(validator.clone(), height, hash.clone())
+            })
+            .await
+            .unwrap();
+        }
+
+        // The votes for 105 also count towards its ancestors.
+        assert_eq!(
+            atomically(|| syncer.vote_tally.find_quorum()).await,
+            Some((103, hash_103))
+        );
+    }
 }
@@ -116,6 +116,14 @@ impl<P> Toggle<CachedFinalityProvider<P>> {
         self.perform_or_else(|p| p.reset(finality), ())
     }
 
+    pub fn top_down_msgs_count(&self, height: BlockHeight) -> Stm<Option<usize>> {
+        self.perform_or_else(|p| p.top_down_msgs_count(height), None)
+    }
+
+    pub fn rollback(&self, height: BlockHeight) -> Stm<()> {
+        self.perform_or_else(|p| p.rollback(height), ())
+    }
+
     pub fn cached_blocks(&self) -> Stm<BlockHeight> {
         self.perform_or_else(|p| p.cached_blocks(), BlockHeight::MAX)
     }
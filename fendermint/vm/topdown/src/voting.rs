@@ -177,6 +177,19 @@ where
         Ok(())
     }
 
+    /// Remove the blocks above the given height, after the parent chain has been reorganised.
+    ///
+    /// The votes are kept: they are for block hashes, which will simply not match the new blocks.
+    pub fn rollback(&self, block_height: BlockHeight) -> Stm<()> {
+        self.chain.update(|chain| {
+            let (mut chain, at, _) = chain.split_lookup(&block_height);
+            if let Some(block_hash) = at {
+                chain.insert(block_height, block_hash);
+            }
+            chain
+        })
+    }
+
     /// Add a vote we received.
     ///
     /// Returns `true` if this vote was added, `false` if it was ignored as a
@@ -3,10 +3,12 @@
 //! Cross network messages related struct and utility functions.
 
 use crate::cross::IpcEnvelope;
+use crate::evm::payload_to_evm_address;
 use crate::subnet_id::SubnetID;
 use crate::HumanReadable;
+use anyhow::anyhow;
 use cid::Cid;
-use ethers::abi::AbiEncode;
+use ethers::abi::{AbiEncode, Token};
 use ethers::utils::hex;
 use fvm_ipld_encoding::DAG_CBOR;
 use fvm_shared::clock::ChainEpoch;
@@ -179,6 +181,72 @@ pub mod consensus {
     }
 }
 
+/// A validator's signature over the digest of a bottom-up checkpoint.
+///
+/// Validators gossip these in the subnet, so that a quorum of them can be submitted to the
+/// parent as the checkpoint certificate, instead of a CometBFT signed header.
+#[serde_as]
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointSignature {
+    /// The checkpoint height in the child subnet.
+    pub height: u64,
+    /// The app hash of the subnet after committing the checkpoint height.
+    #[serde_as(as = "HumanReadable")]
+    pub app_hash: Vec<u8>,
+    /// Signature over [`checkpoint_signature_digest`] in `r || s || v` format, where `v` is 27 or 28.
+    #[serde_as(as = "HumanReadable")]
+    pub signature: Vec<u8>,
+}
+
+impl CheckpointSignature {
+    /// Recover the Ethereum address of the validator who signed the checkpoint of a subnet.
+    pub fn recover(&self, subnet_id: &SubnetID) -> anyhow::Result<ethers::types::Address> {
+        let digest = checkpoint_signature_digest(subnet_id, self.height, &self.app_hash)?;
+        let signature = ethers::types::Signature::try_from(self.signature.as_slice())?;
+        Ok(signature.recover(ethers::types::H256::from(digest))?)
+    }
+}
+
+/// The signature of a validator, identified by its Ethereum address.
+#[serde_as]
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct ValidatorSignature {
+    pub validator: ethers::types::Address,
+    #[serde_as(as = "HumanReadable")]
+    pub signature: Vec<u8>,
+}
+
+/// The signatures collected for the app hash of a checkpoint height.
+#[serde_as]
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointSignatures {
+    pub height: u64,
+    #[serde_as(as = "HumanReadable")]
+    pub app_hash: Vec<u8>,
+    /// Signatures ordered by validator address, as expected by the subnet actor.
+    pub signatures: Vec<ValidatorSignature>,
+}
+
+/// The digest validators sign to certify the app hash of a subnet at a checkpoint height,
+/// which is `keccak256(abi.encode(subnetActor, height, appHash))`, the same as
+/// `SubnetActorCheckpointingFacet.checkpointDigest`.
+pub fn checkpoint_signature_digest(
+    subnet_id: &SubnetID,
+    height: u64,
+    app_hash: &[u8],
+) -> anyhow::Result<[u8; 32]> {
+    let subnet_actor = payload_to_evm_address(subnet_id.subnet_actor().payload())?;
+    if app_hash.len() != 32 {
+        return Err(anyhow!("app hash must be 32 bytes, got {}", app_hash.len()));
+    }
+    let encoded = ethers::abi::encode(&[
+        Token::Address(subnet_actor),
+        Token::Uint(height.into()),
+        Token::FixedBytes(app_hash.to_vec()),
+    ]);
+    Ok(ethers::utils::keccak256(encoded))
+}
+
 pub fn serialize_vec_bytes_to_vec_hex<T: AsRef<[u8]>, S>(
     data: &[T],
     s: S,
@@ -196,9 +264,10 @@ where
 #[cfg(test)]
 mod tests {
     use crate::address::IPCAddress;
-    use crate::checkpoint::Signature;
+    use crate::checkpoint::{checkpoint_signature_digest, CheckpointSignature, Signature};
     use crate::subnet_id::SubnetID;
     use crate::HumanReadable;
+    use ethers::signers::{LocalWallet, Signer};
     use fvm_shared::address::Address;
     use serde::{Deserialize, Serialize};
     use serde_with::serde_as;
@@ -236,4 +305,27 @@ mod tests {
 
         assert_eq!(r, t);
     }
+
+    #[test]
+    fn test_checkpoint_signature_recover() {
+        let subnet_id = SubnetID::new(314159, vec![Address::new_delegated(10, &[1; 20]).unwrap()]);
+        let wallet = LocalWallet::from_bytes(&[2; 32]).unwrap();
+
+        let app_hash = vec![3; 32];
+        let digest = checkpoint_signature_digest(&subnet_id, 9, &app_hash).unwrap();
+        let signature = wallet.sign_hash(ethers::types::H256::from(digest)).unwrap();
+
+        let sig = CheckpointSignature {
+            height: 9,
+            app_hash,
+            signature: signature.to_vec(),
+        };
+        assert_eq!(sig.recover(&subnet_id).unwrap(), wallet.address());
+
+        // a different height recovers some other address
+        let sig = CheckpointSignature { height: 18, ..sig };
+        assert_ne!(sig.recover(&subnet_id).unwrap(), wallet.address());
+
+        assert!(checkpoint_signature_digest(&subnet_id, 9, &[3; 20]).is_err());
+    }
 }
@@ -6,21 +6,25 @@ use crate::{require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
 use anyhow::anyhow;
 use anyhow::Context;
 use async_trait::async_trait;
-use clap::Args;
+use clap::{Args, ValueEnum};
+use futures_util::future::join_all;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
-use ipc_provider::checkpoint::BottomUpCheckpointManager;
+use ipc_provider::checkpoint::{BottomUpCheckpointManager, CheckpointCertificate, RelayerStore};
 use ipc_provider::config::Config;
 use ipc_provider::new_evm_keystore_from_arc_config;
 use ipc_provider::observe::register_metrics as register_checkpoint_metrics;
 use ipc_wallet::EvmKeyStore;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::sync::Mutex;
 
 const DEFAULT_POLLING_INTERVAL: u64 = 15;
+const DEFAULT_STATE_DIR: &str = "relayer";
 
 /// The command to run the bottom up relayer in the background.
 pub(crate) struct BottomUpRelayer;
@@ -56,6 +60,7 @@ impl CommandLineHandler for BottomUpRelayer {
 
         let config_path = global.config_path();
         let config = Arc::new(Config::from_file(&config_path)?);
+        let signers = config.remote_signers()?;
         let mut keystore = new_evm_keystore_from_arc_config(config)?;
         let submitter = match (arguments.submitter.as_ref(), keystore.get_default()?) {
             (Some(submitter), _) => require_fil_addr_from_str(submitter)?,
@@ -68,41 +73,94 @@ impl CommandLineHandler for BottomUpRelayer {
             }
         };
 
-        let subnet = SubnetID::from_str(&arguments.subnet)?;
-        let parent = subnet
-            .parent()
-            .ok_or_else(|| anyhow!("root does not have parent"))?;
-
-        let child = get_subnet_config(&config_path, &subnet)?;
-        let parent = get_subnet_config(&config_path, &parent)?;
-
-        let mut manager = BottomUpCheckpointManager::new_evm_manager(
-            parent.clone(),
-            child.clone(),
-            Arc::new(RwLock::new(keystore)),
-        )
-        .await?;
-
-        if let Some(v) = arguments.finalization_blocks {
-            manager = manager.with_finalization_blocks(v as ChainEpoch);
-        }
+        let state_dir = arguments
+            .state_dir
+            .clone()
+            .unwrap_or_else(|| global.config_dir().join(DEFAULT_STATE_DIR));
+        let keystore = Arc::new(RwLock::new(keystore));
+        // All the relayers send from the same submitter, so they must not race for nonces.
+        let send_lock = Arc::new(Mutex::new(()));
 
         let interval = Duration::from_secs(
             arguments
                 .checkpoint_interval_sec
                 .unwrap_or(DEFAULT_POLLING_INTERVAL),
         );
-        manager.run(submitter, interval).await;
+
+        let mut relayers = Vec::new();
+        for subnet in arguments.subnet.iter() {
+            let subnet = SubnetID::from_str(subnet)?;
+            let parent = subnet
+                .parent()
+                .ok_or_else(|| anyhow!("root does not have parent"))?;
+
+            let child = get_subnet_config(&config_path, &subnet)?;
+            let parent = get_subnet_config(&config_path, &parent)?;
+
+            let store_path = state_dir.join(format!(
+                "{}.json",
+                subnet.to_string().trim_start_matches('/').replace('/', "_")
+            ));
+            let store = RelayerStore::open(store_path)?;
+
+            let mut manager = BottomUpCheckpointManager::new_evm_manager(
+                parent.clone(),
+                child.clone(),
+                keystore.clone(),
+                signers.clone(),
+            )
+            .await?
+            .with_store(store)
+            .with_send_lock(send_lock.clone())
+            .with_fee_bumping(
+                Duration::from_secs(arguments.stuck_timeout_sec),
+                arguments.fee_bump_percent,
+            )
+            .with_certificate(arguments.certificate.into());
+
+            if let Some(v) = arguments.finalization_blocks {
+                manager = manager.with_finalization_blocks(v as ChainEpoch);
+            }
+            if arguments.leader_rotation {
+                manager = manager.with_leader_rotation(arguments.leader_grace_blocks as ChainEpoch);
+            }
+
+            relayers.push(manager.run(submitter, interval));
+        }
+
+        join_all(relayers).await;
 
         Ok(())
     }
 }
 
+/// A CLI-friendly wrapper for `CheckpointCertificate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub(crate) enum CliCheckpointCertificate {
+    #[default]
+    SignedHeader,
+    Signatures,
+}
+
+impl From<CliCheckpointCertificate> for CheckpointCertificate {
+    fn from(cli: CliCheckpointCertificate) -> Self {
+        match cli {
+            CliCheckpointCertificate::SignedHeader => CheckpointCertificate::SignedHeader,
+            CliCheckpointCertificate::Signatures => CheckpointCertificate::Signatures,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 #[command(about = "Start the bottom up relayer daemon")]
 pub(crate) struct BottomUpRelayerArgs {
-    #[arg(long, help = "The subnet id of the checkpointing subnet")]
-    pub subnet: String,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        help = "The subnet ids of the checkpointing subnets, comma separated or repeated"
+    )]
+    pub subnet: Vec<String>,
     #[arg(long, help = "The number of seconds to submit checkpoint")]
     pub checkpoint_interval_sec: Option<u64>,
     #[arg(
@@ -113,13 +171,42 @@ pub(crate) struct BottomUpRelayerArgs {
     pub finalization_blocks: Option<u64>,
     #[arg(long, help = "The hex encoded address of the submitter")]
     pub submitter: Option<String>,
+    #[arg(
+        long,
+        help = "Directory to persist the relayer progress in, default to ${HOME}/.ipc/relayer"
+    )]
+    pub state_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value = "300",
+        help = "The number of seconds after which a pending transaction is replaced with higher fees"
+    )]
+    pub stuck_timeout_sec: u64,
+    #[arg(
+        long,
+        default_value = "25",
+        help = "The percentage by which the fees of a stuck transaction are increased"
+    )]
+    pub fee_bump_percent: u64,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CliCheckpointCertificate::SignedHeader,
+        help = "Certify checkpoints with the CometBFT signed header, or with the signatures gossiped by the validators"
+    )]
+    pub certificate: CliCheckpointCertificate,
 
     #[arg(
         long,
-        default_value = "1",
-        help = "The max parallelism for submitting checkpoints"
+        help = "Take turns with the other relayers run by the validators of the subnet to submit checkpoints"
+    )]
+    pub leader_rotation: bool,
+    #[arg(
+        long,
+        default_value = "10",
+        help = "The number of blocks to wait for the relayer in turn before the next one takes over"
     )]
-    pub max_parallelism: usize,
+    pub leader_grace_blocks: u64,
 
     #[arg(
         long,
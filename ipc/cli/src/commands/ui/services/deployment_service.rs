@@ -11,7 +11,7 @@ use ethers::types::Address as EthAddress;
 use fendermint_eth_deployer::DeployedContracts;
 use ipc_api::subnet::{Asset, AssetKind, PermissionMode};
 use ipc_api::subnet_id::SubnetID;
-use ipc_provider::{config::SubnetConfig, new_evm_keystore_from_config};
+use ipc_provider::new_evm_keystore_from_config;
 use ipc_types::EthAddress as IpcEthAddress;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -993,19 +993,15 @@ impl DeploymentService {
                 "Restoring original config for parent subnet: {}",
                 temp_subnet_id
             );
-            match &original.config {
-                SubnetConfig::Fevm(evm_subnet) => {
-                    ipc_config_store
-                        .add_subnet(
-                            temp_subnet_id.clone(),
-                            evm_subnet.provider_http.clone(),
-                            evm_subnet.gateway_addr,
-                            evm_subnet.registry_addr,
-                        )
-                        .await?;
-                    log::info!("Successfully restored original config");
-                }
-            }
+            ipc_config_store
+                .add_subnet(
+                    temp_subnet_id.clone(),
+                    original.rpc_http().clone(),
+                    original.gateway_addr(),
+                    original.registry_addr(),
+                )
+                .await?;
+            log::info!("Successfully restored original config");
         } else {
             // The parent subnet didn't exist before, but we can't easily remove it
             // without affecting other operations. For now, leave it as is.
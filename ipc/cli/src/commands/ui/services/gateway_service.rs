@@ -111,65 +111,61 @@ impl GatewayService {
             }
 
             // Try to get gateway information from the subnet configuration
-            match &subnet_config.config {
-                ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => {
-                    let gateway_addr_str = evm_subnet.gateway_addr.to_string();
-                    let registry_addr_str = evm_subnet.registry_addr.to_string();
-
-                    // Use gateway address as the key for deduplication
-                    let gateway_key = gateway_addr_str.clone();
-
-                    if let Some(existing_gateway) = gateways_map.get_mut(&gateway_key) {
-                        // Gateway already exists, increment subnet count
-                        existing_gateway.subnet_count += 1;
-                        log::info!(
-                            "Found additional subnet {} for existing gateway {}",
-                            subnet_id_str,
-                            gateway_addr_str
-                        );
+            let gateway_addr_str = subnet_config.gateway_addr().to_string();
+            let registry_addr_str = subnet_config.registry_addr().to_string();
+
+            // Use gateway address as the key for deduplication
+            let gateway_key = gateway_addr_str.clone();
+
+            if let Some(existing_gateway) = gateways_map.get_mut(&gateway_key) {
+                // Gateway already exists, increment subnet count
+                existing_gateway.subnet_count += 1;
+                log::info!(
+                    "Found additional subnet {} for existing gateway {}",
+                    subnet_id_str,
+                    gateway_addr_str
+                );
+            } else {
+                // New gateway, create entry
+                let parent_network =
+                    if let Ok(subnet_id) = SubnetID::from_str(&subnet_id_str.to_string()) {
+                        if subnet_id.is_root() {
+                            subnet_id_str.to_string()
+                        } else {
+                            subnet_id
+                                .parent()
+                                .map(|p| p.to_string())
+                                .unwrap_or_else(|| subnet_id_str.to_string())
+                        }
                     } else {
-                        // New gateway, create entry
-                        let parent_network =
-                            if let Ok(subnet_id) = SubnetID::from_str(&subnet_id_str.to_string()) {
-                                if subnet_id.is_root() {
-                                    subnet_id_str.to_string()
-                                } else {
-                                    subnet_id
-                                        .parent()
-                                        .map(|p| p.to_string())
-                                        .unwrap_or_else(|| subnet_id_str.to_string())
-                                }
-                            } else {
-                                subnet_id_str.to_string()
-                            };
-
-                        let gateway_info = GatewayInfo {
-                            id: format!(
-                                "gateway-{}",
-                                &gateway_addr_str[gateway_addr_str.len().saturating_sub(12)..]
-                            ),
-                            address: gateway_addr_str.clone(),
-                            registry_address: registry_addr_str,
-                            deployer_address: "unknown".to_string(), // TODO: Track deployer
-                            parent_network,
-                            name: Some(format!(
-                                "Gateway {}",
-                                &gateway_addr_str[gateway_addr_str.len().saturating_sub(8)..]
-                            )),
-                            subnet_count: 1,
-                            is_active: true, // Assume active if in config
-                            deployed_at: chrono::Utc::now(), // TODO: Track actual deployment time
-                        };
-
-                        log::info!(
-                            "Found new gateway: {} with ID: {} serving subnet: {}",
-                            gateway_addr_str,
-                            gateway_info.id,
-                            subnet_id_str
-                        );
-                        gateways_map.insert(gateway_key, gateway_info);
-                    }
-                }
+                        subnet_id_str.to_string()
+                    };
+
+                let gateway_info = GatewayInfo {
+                    id: format!(
+                        "gateway-{}",
+                        &gateway_addr_str[gateway_addr_str.len().saturating_sub(12)..]
+                    ),
+                    address: gateway_addr_str.clone(),
+                    registry_address: registry_addr_str,
+                    deployer_address: "unknown".to_string(), // TODO: Track deployer
+                    parent_network,
+                    name: Some(format!(
+                        "Gateway {}",
+                        &gateway_addr_str[gateway_addr_str.len().saturating_sub(8)..]
+                    )),
+                    subnet_count: 1,
+                    is_active: true,                 // Assume active if in config
+                    deployed_at: chrono::Utc::now(), // TODO: Track actual deployment time
+                };
+
+                log::info!(
+                    "Found new gateway: {} with ID: {} serving subnet: {}",
+                    gateway_addr_str,
+                    gateway_info.id,
+                    subnet_id_str
+                );
+                gateways_map.insert(gateway_key, gateway_info);
             }
         }
 
@@ -865,12 +865,10 @@ impl SubnetService {
             .get(&parent)
             .ok_or_else(|| anyhow::anyhow!("Parent subnet not found in config"))?;
 
-        let (gateway_addr, rpc_url) = match &parent_config.config {
-            ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => (
-                evm_subnet.gateway_addr.to_string(),
-                &evm_subnet.provider_http,
-            ),
-        };
+        let (gateway_addr, rpc_url) = (
+            parent_config.gateway_addr().to_string(),
+            parent_config.rpc_http(),
+        );
 
         // Convert f410 gateway address to Ethereum format if needed
         let eth_gateway_addr = if gateway_addr.starts_with("t410")
@@ -1077,9 +1075,7 @@ impl SubnetService {
             .get(&parent)
             .ok_or_else(|| anyhow::anyhow!("Parent subnet not found in config"))?;
 
-        let rpc_url = match &parent_config.config {
-            ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => &evm_subnet.provider_http,
-        };
+        let rpc_url = parent_config.rpc_http();
 
         log::info!("Using RPC URL: {}", rpc_url);
 
@@ -1157,14 +1153,8 @@ impl SubnetService {
                 "Raw subnet found: ID='{}', is_root={}, gateway={:?}, registry={:?}",
                 subnet_id_str,
                 subnet_id.is_root(),
-                match &subnet_config.config {
-                    ipc_provider::config::SubnetConfig::Fevm(evm_subnet) =>
-                        evm_subnet.gateway_addr.to_string(),
-                },
-                match &subnet_config.config {
-                    ipc_provider::config::SubnetConfig::Fevm(evm_subnet) =>
-                        evm_subnet.registry_addr.to_string(),
-                }
+                subnet_config.gateway_addr().to_string(),
+                subnet_config.registry_addr().to_string()
             );
         }
 
@@ -1267,12 +1257,8 @@ impl SubnetService {
                 "validators": validators,
                 "config": {
                     "permissionMode": permission_mode,
-                    "gateway_addr": match &subnet_config.config {
-                        ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => evm_subnet.gateway_addr.to_string(),
-                    },
-                    "registry_addr": match &subnet_config.config {
-                        ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => evm_subnet.registry_addr.to_string(),
-                    }
+                    "gateway_addr": subnet_config.gateway_addr().to_string(),
+                    "registry_addr": subnet_config.registry_addr().to_string()
                 },
                 "parent": config_subnet_id.parent().map(|p| p.to_string()),
                 "chain_head": serde_json::Value::Null,
@@ -1416,12 +1402,8 @@ impl SubnetService {
                     "validators": validators,
                     "config": {
                         "permissionMode": permission_mode,
-                        "gateway_addr": match &subnet_config.config {
-                            ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => evm_subnet.gateway_addr.to_string(),
-                        },
-                        "registry_addr": match &subnet_config.config {
-                            ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => evm_subnet.registry_addr.to_string(),
-                        }
+                        "gateway_addr": subnet_config.gateway_addr().to_string(),
+                        "registry_addr": subnet_config.registry_addr().to_string()
                     },
                     "stats": {
                         "block_height": status_info.block_height,
@@ -1963,58 +1945,54 @@ impl SubnetService {
 
         // Find subnets that have this gateway address and check their actual approval status
         for (subnet_id, subnet_config) in &config.subnets {
-            match &subnet_config.config {
-                ipc_provider::config::SubnetConfig::Fevm(evm_subnet) => {
-                    // Convert both addresses to Ethereum hex format for comparison
-                    let config_gateway_eth = evm_subnet.gateway_addr.to_string().to_lowercase();
-                    let target_gateway_eth = gateway_address.to_lowercase();
+            // Convert both addresses to Ethereum hex format for comparison
+            let config_gateway_eth = subnet_config.gateway_addr().to_string().to_lowercase();
+            let target_gateway_eth = gateway_address.to_lowercase();
+
+            log::debug!(
+                "Comparing gateway addresses: config_eth={}, target_eth={}",
+                config_gateway_eth,
+                target_gateway_eth
+            );
 
+            if config_gateway_eth == target_gateway_eth {
+                // Skip root networks - they don't need approval
+                if subnet_id.parent().is_none() {
                     log::debug!(
-                        "Comparing gateway addresses: config_eth={}, target_eth={}",
-                        config_gateway_eth,
-                        target_gateway_eth
+                        "Skipping root network {} - doesn't need approval",
+                        subnet_id
                     );
+                    continue;
+                }
 
-                    if config_gateway_eth == target_gateway_eth {
-                        // Skip root networks - they don't need approval
-                        if subnet_id.parent().is_none() {
-                            log::debug!(
-                                "Skipping root network {} - doesn't need approval",
-                                subnet_id
-                            );
-                            continue;
-                        }
+                let parent_id = subnet_id.parent().unwrap();
+                log::debug!(
+                    "Checking subnet {} that uses gateway {}",
+                    subnet_id,
+                    gateway_address
+                );
 
-                        let parent_id = subnet_id.parent().unwrap();
-                        log::debug!(
-                            "Checking subnet {} that uses gateway {}",
-                            subnet_id,
-                            gateway_address
-                        );
+                // NEW: Actually check if the subnet is approved by querying the gateway contract
+                let is_approved = self
+                    .check_subnet_approval_status(subnet_id, gateway_address)
+                    .await;
 
-                        // NEW: Actually check if the subnet is approved by querying the gateway contract
-                        let is_approved = self
-                            .check_subnet_approval_status(subnet_id, gateway_address)
-                            .await;
-
-                        if !is_approved {
-                            // Only include subnets that are NOT approved
-                            log::info!("Subnet {} is pending approval", subnet_id);
-
-                            let subnet_info = serde_json::json!({
-                                "subnet_id": subnet_id.to_string(),
-                                "gateway_address": evm_subnet.gateway_addr.to_string(),
-                                "registry_address": evm_subnet.registry_addr.to_string(),
-                                "parent_id": parent_id.to_string(),
-                                "status": "pending_approval",
-                                "created_at": chrono::Utc::now().to_rfc3339(),
-                            });
-
-                            pending_subnets.push(subnet_info);
-                        } else {
-                            log::debug!("Subnet {} is already approved, skipping", subnet_id);
-                        }
-                    }
+                if !is_approved {
+                    // Only include subnets that are NOT approved
+                    log::info!("Subnet {} is pending approval", subnet_id);
+
+                    let subnet_info = serde_json::json!({
+                        "subnet_id": subnet_id.to_string(),
+                        "gateway_address": subnet_config.gateway_addr().to_string(),
+                        "registry_address": subnet_config.registry_addr().to_string(),
+                        "parent_id": parent_id.to_string(),
+                        "status": "pending_approval",
+                        "created_at": chrono::Utc::now().to_rfc3339(),
+                    });
+
+                    pending_subnets.push(subnet_info);
+                } else {
+                    log::debug!("Subnet {} is already approved, skipping", subnet_id);
                 }
             }
         }
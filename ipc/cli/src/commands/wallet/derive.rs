@@ -0,0 +1,88 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet derive cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::lotus::message::wallet::WalletKeyType;
+use ipc_wallet::WalletType;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+pub(crate) struct WalletDerive;
+
+#[async_trait]
+impl CommandLineHandler for WalletDerive {
+    type Arguments = WalletDeriveArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!(
+            "derive wallet of type {} at index {}",
+            arguments.wallet_type,
+            arguments.index
+        );
+
+        let provider = get_ipc_provider(global)?;
+        let passphrase = arguments.passphrase.as_deref();
+
+        let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
+        match wallet_type {
+            WalletType::Evm => {
+                println!(
+                    "{:?}",
+                    provider
+                        .derive_evm_key(arguments.index, passphrase)?
+                        .to_string()
+                );
+            }
+            WalletType::Fvm => {
+                let tp = WalletKeyType::from_str(
+                    arguments
+                        .key_type
+                        .as_deref()
+                        .ok_or_else(|| anyhow::anyhow!("fvm key type not specified"))?,
+                )?;
+                // BLS keys would need EIP-2333 rather than BIP-32, and there is no
+                // agreed upon path for Filecoin, so other wallets couldn't recover them.
+                if tp != WalletKeyType::Secp256k1 {
+                    anyhow::bail!(
+                        "{tp} keys cannot be derived from the mnemonic, only secp256k1; create them with `wallet new` instead"
+                    );
+                }
+                println!(
+                    "{:?}",
+                    provider.derive_fvm_key(tp, arguments.index, passphrase)?
+                )
+            }
+        };
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Derive a wallet from the stored mnemonic and add it to the keystore")]
+pub(crate) struct WalletDeriveArgs {
+    #[arg(long, help = "The type of the wallet, i.e. fvm, evm")]
+    pub wallet_type: String,
+    #[arg(
+        long,
+        help = "The fvm key type of the wallet, only for fvm wallet type; only secp256k1 is supported, bls keys are rejected"
+    )]
+    pub key_type: Option<String>,
+    #[arg(
+        long,
+        default_value = "0",
+        help = "The account index in the BIP-44 path, i.e. m/44'/60'/0'/0/<index> for evm and m/44'/461'/0'/0/<index> for fvm"
+    )]
+    pub index: u32,
+    #[arg(
+        long,
+        env = "IPC_MNEMONIC_PASSPHRASE",
+        hide_env_values = true,
+        help = "The optional BIP-39 passphrase of the mnemonic"
+    )]
+    pub passphrase: Option<String>,
+}
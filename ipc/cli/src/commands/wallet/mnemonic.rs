@@ -0,0 +1,87 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet mnemonic cli handlers
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Args;
+use fs_err as fs;
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+pub(crate) struct WalletNewMnemonic;
+
+#[async_trait]
+impl CommandLineHandler for WalletNewMnemonic {
+    type Arguments = WalletNewMnemonicArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("create new mnemonic with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+        let phrase = provider.new_mnemonic(arguments.words)?;
+
+        println!("{phrase}");
+        eprintln!(
+            "Write down the mnemonic and keep it safe; it is the backup of all derived keys."
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Create a new BIP-39 mnemonic to derive keys from")]
+pub(crate) struct WalletNewMnemonicArgs {
+    #[arg(
+        long,
+        default_value = "24",
+        help = "The number of words in the mnemonic (12, 15, 18, 21 or 24)"
+    )]
+    pub words: usize,
+}
+
+pub(crate) struct WalletImportMnemonic;
+
+#[async_trait]
+impl CommandLineHandler for WalletImportMnemonic {
+    type Arguments = WalletImportMnemonicArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        // Don't log the arguments, they contain the phrase.
+        log::debug!("import mnemonic");
+
+        let phrase = match (&arguments.phrase, &arguments.path) {
+            (Some(phrase), None) => phrase.clone(),
+            (None, Some(path)) => fs::read_to_string(path)
+                .with_context(|| format!("failed to read mnemonic file: {path:?}"))?,
+            _ => anyhow::bail!("either --phrase or --path must be provided"),
+        };
+
+        let provider = get_ipc_provider(global)?;
+        provider.import_mnemonic(&phrase)?;
+
+        println!("mnemonic imported");
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+#[command(about = "Import an existing BIP-39 mnemonic to derive keys from")]
+pub(crate) struct WalletImportMnemonicArgs {
+    #[arg(long, conflicts_with = "path", help = "The mnemonic phrase")]
+    pub phrase: Option<String>,
+    #[arg(long, help = "Path to a file containing the mnemonic phrase")]
+    pub path: Option<PathBuf>,
+}
+
+impl Debug for WalletImportMnemonicArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletImportMnemonicArgs")
+            .field("phrase", &self.phrase.as_ref().map(|_| "<redacted>"))
+            .field("path", &self.path)
+            .finish()
+    }
+}
@@ -3,6 +3,10 @@
 use crate::{CommandLineHandler, GlobalArguments};
 
 use crate::commands::wallet::balances::{WalletBalances, WalletBalancesArgs};
+use crate::commands::wallet::derive::{WalletDerive, WalletDeriveArgs};
+use crate::commands::wallet::mnemonic::{
+    WalletImportMnemonic, WalletImportMnemonicArgs, WalletNewMnemonic, WalletNewMnemonicArgs,
+};
 use crate::commands::wallet::new::{WalletNew, WalletNewArgs};
 use clap::{Args, Subcommand};
 
@@ -16,9 +20,11 @@ use self::remove::{WalletRemove, WalletRemoveArgs};
 
 mod balances;
 mod default;
+mod derive;
 mod export;
 pub mod import;
 mod list;
+mod mnemonic;
 mod new;
 mod remove;
 
@@ -42,6 +48,9 @@ impl WalletCommandsArgs {
             Commands::GetDefault(args) => WalletGetDefault::handle(global, args).await,
             Commands::PubKey(args) => WalletPublicKey::handle(global, args).await,
             Commands::List(args) => WalletList::handle(global, args).await,
+            Commands::NewMnemonic(args) => WalletNewMnemonic::handle(global, args).await,
+            Commands::ImportMnemonic(args) => WalletImportMnemonic::handle(global, args).await,
+            Commands::Derive(args) => WalletDerive::handle(global, args).await,
         }
     }
 }
@@ -57,4 +66,7 @@ pub(crate) enum Commands {
     GetDefault(WalletGetDefaultArgs),
     PubKey(WalletPublicKeyArgs),
     List(WalletListArgs),
+    NewMnemonic(WalletNewMnemonicArgs),
+    ImportMnemonic(WalletImportMnemonicArgs),
+    Derive(WalletDeriveArgs),
 }
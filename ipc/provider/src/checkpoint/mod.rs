@@ -0,0 +1,964 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bottom up checkpoint manager
+
+mod rotation;
+mod store;
+
+use crate::config::Subnet;
+use crate::manager::{ContractRevert, EthSubnetManager, SignedHeaderRelayer, TxHash, TxStatus};
+use crate::observe::{RelayerSubmissionSkipped, RelayerTxBumped, RelayerTxReverted};
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::checkpoint::CheckpointSignatures;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::staking::ValidatorInfo;
+use ipc_observability::emit;
+use ipc_wallet::{EthKeyAddress, PersistentKeyStore, Signer};
+use num_traits::Zero;
+use std::cmp::max;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+pub use rotation::LeaderRotation;
+pub use store::{InFlightTx, RelayerState, RelayerStore, TxKind};
+
+/// Default time after which a pending transaction is considered stuck.
+pub const DEFAULT_STUCK_TIMEOUT: Duration = Duration::from_secs(300);
+/// Default gas fee increase of replacement transactions; Lotus requires at least 25%.
+pub const DEFAULT_FEE_BUMP_PERCENT: u64 = 25;
+
+/// How the relayer proves to the parent that the validators agreed on a checkpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckpointCertificate {
+    /// The CometBFT signed header with the commit of the block after the checkpoint.
+    #[default]
+    SignedHeader,
+    /// The signatures over the checkpoint digest gossiped by the validators in the subnet.
+    Signatures,
+}
+
+/// Tracks the config required for bottom up checkpoint submissions
+/// parent/child subnet and checkpoint period.
+pub struct CheckpointConfig {
+    parent: Subnet,
+    child: Subnet,
+    period: ChainEpoch,
+}
+
+pub struct BottomUpCheckpointManager<T> {
+    metadata: CheckpointConfig,
+    parent_handler: Arc<T>,
+    child_handler: T,
+    /// The number of blocks away from the chain head that is considered final
+    finalization_blocks: ChainEpoch,
+    /// The progress of the relayer, persisted across restarts.
+    store: RelayerStore,
+    /// Serializes sending transactions from the submitter, so that the concurrent tasks,
+    /// or relayers of other subnets sharing the lock, don't race for the same nonce.
+    send_lock: Arc<Mutex<()>>,
+    /// The time after which a transaction still pending is replaced with a higher gas price.
+    stuck_timeout: Duration,
+    /// The percentage by which the gas fees of a stuck transaction are increased.
+    fee_bump_percent: u64,
+    /// Takes turns with the validators of the subnet, waiting this many blocks for each, if configured.
+    leader_grace_blocks: Option<ChainEpoch>,
+    /// The kind of certificate submitted with checkpoints.
+    certificate: CheckpointCertificate,
+}
+
+impl<T: SignedHeaderRelayer> BottomUpCheckpointManager<T> {
+    pub async fn new(
+        parent: Subnet,
+        child: Subnet,
+        parent_handler: T,
+        child_handler: T,
+    ) -> Result<Self> {
+        let period = parent_handler
+            .submission_period(&child.id)
+            .await
+            .map_err(|e| anyhow!("cannot get bottom up checkpoint period: {e}"))?;
+        Ok(Self {
+            metadata: CheckpointConfig {
+                parent,
+                child,
+                period,
+            },
+            parent_handler: Arc::new(parent_handler),
+            child_handler,
+            finalization_blocks: 0,
+            store: RelayerStore::in_memory(),
+            send_lock: Default::default(),
+            stuck_timeout: DEFAULT_STUCK_TIMEOUT,
+            fee_bump_percent: DEFAULT_FEE_BUMP_PERCENT,
+            leader_grace_blocks: None,
+            certificate: CheckpointCertificate::default(),
+        })
+    }
+
+    pub fn with_finalization_blocks(mut self, finalization_blocks: ChainEpoch) -> Self {
+        self.finalization_blocks = finalization_blocks;
+        self
+    }
+
+    /// Persist the progress of the relayer in the given store.
+    pub fn with_store(mut self, store: RelayerStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Share the lock serializing transactions with relayers using the same submitter.
+    pub fn with_send_lock(mut self, send_lock: Arc<Mutex<()>>) -> Self {
+        self.send_lock = send_lock;
+        self
+    }
+
+    /// Replace transactions pending for longer than `stuck_timeout` with ones paying
+    /// `fee_bump_percent` more gas fees.
+    pub fn with_fee_bumping(mut self, stuck_timeout: Duration, fee_bump_percent: u64) -> Self {
+        self.stuck_timeout = stuck_timeout;
+        self.fee_bump_percent = fee_bump_percent;
+        self
+    }
+
+    /// Only submit signed headers when it's this relayer's turn in the rotation among the
+    /// validators of the subnet at the checkpoint height, waiting `grace_blocks` for each.
+    pub fn with_leader_rotation(mut self, grace_blocks: ChainEpoch) -> Self {
+        self.leader_grace_blocks = Some(grace_blocks);
+        self
+    }
+
+    /// Choose the kind of certificate submitted with checkpoints.
+    pub fn with_certificate(mut self, certificate: CheckpointCertificate) -> Self {
+        self.certificate = certificate;
+        self
+    }
+}
+
+impl BottomUpCheckpointManager<EthSubnetManager> {
+    /// Create a manager signing with keys from the keystore, or with one of the
+    /// `signers` if it signs for the submitter address.
+    pub async fn new_evm_manager(
+        parent: Subnet,
+        child: Subnet,
+        keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+        signers: Vec<Arc<dyn Signer>>,
+    ) -> Result<Self> {
+        let mut parent_handler =
+            EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?;
+        let mut child_handler =
+            EthSubnetManager::from_subnet_with_wallet_store(&child, Some(keystore))?;
+        for signer in signers {
+            parent_handler = parent_handler.with_signer(signer.clone());
+            child_handler = child_handler.with_signer(signer);
+        }
+        Self::new(parent, child, parent_handler, child_handler).await
+    }
+}
+
+impl<T: SignedHeaderRelayer> Display for BottomUpCheckpointManager<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "light client relayer, target: {:}, source: {:}",
+            self.metadata.parent.id, self.metadata.child.id
+        )
+    }
+}
+
+impl<T: SignedHeaderRelayer + Send + Sync + 'static> BottomUpCheckpointManager<T> {
+    /// Getter for the parent subnet this checkpoint manager is handling
+    pub fn parent_subnet(&self) -> &Subnet {
+        &self.metadata.parent
+    }
+
+    /// Getter for the target subnet this checkpoint manager is handling
+    pub fn child_subnet(&self) -> &Subnet {
+        &self.metadata.child
+    }
+
+    /// The submission period that the current manager is submitting upon
+    pub fn submission_period(&self) -> ChainEpoch {
+        self.metadata.period
+    }
+
+    /// Run the bottom up checkpoint submission daemon in the foreground.
+    ///
+    /// Signed headers, app hash breakdowns and batch executions are relayed by
+    /// independent tasks, so that a slow or stuck transaction of one kind doesn't
+    /// hold up the others.
+    pub async fn run(self, submitter: Address, submission_interval: Duration) {
+        tracing::info!("launching {self} for {submitter}");
+
+        let state = self.store.state();
+        tracing::info!(
+            in_flight = state.in_flight.len(),
+            last_submitted_height = state.last_submitted_height,
+            "loaded relayer state"
+        );
+
+        let this = Arc::new(self);
+
+        let headers = tokio::spawn({
+            let this = this.clone();
+            async move {
+                loop {
+                    if let Err(e) = this.relay_signed_header(submitter).await {
+                        tracing::error!(
+                            "cannot submit checkpoint for submitter: {submitter} due to {e}"
+                        );
+                    }
+                    tokio::time::sleep(submission_interval).await;
+                }
+            }
+        });
+
+        let breakdowns = tokio::spawn({
+            let this = this.clone();
+            async move {
+                loop {
+                    if let Err(e) = this.relay_app_hash_breakdowns(submitter).await {
+                        tracing::error!("cannot submit app hash breakdowns for submitter: {submitter} due to {e}");
+                    }
+                    tokio::time::sleep(submission_interval).await;
+                }
+            }
+        });
+
+        let batches = tokio::spawn({
+            let this = this.clone();
+            async move {
+                loop {
+                    if let Err(e) = this.relay_batch_executions(submitter).await {
+                        tracing::error!("cannot execute pending batch commitments for submitter: {submitter} due to {e}");
+                    }
+                    tokio::time::sleep(submission_interval).await;
+                }
+            }
+        });
+
+        if let Err(e) = tokio::try_join!(headers, breakdowns, batches) {
+            tracing::error!("relayer task terminated: {e}");
+        }
+    }
+
+    /// Submit the next signed header, unless the previous one is still in flight.
+    async fn relay_signed_header(&self, submitter: Address) -> Result<()> {
+        if self
+            .settle_in_flight(submitter, TxKind::SignedHeader)
+            .await?
+        {
+            return Ok(());
+        }
+        self.submit_next_signed_header(submitter).await?;
+        Ok(())
+    }
+
+    /// Record the app hash breakdowns of submitted checkpoints, unless some are still in flight.
+    async fn relay_app_hash_breakdowns(&self, submitter: Address) -> Result<()> {
+        if self
+            .settle_in_flight(submitter, TxKind::AppHashBreakdown)
+            .await?
+        {
+            return Ok(());
+        }
+        let end_height = self
+            .parent_handler
+            .get_last_bottom_up_checkpoint_height(&self.metadata.child.id)
+            .await?;
+        self.submit_missing_app_hash_breakdowns(submitter, end_height as ChainEpoch + 1)
+            .await
+    }
+
+    /// Execute pending batches, unless previous executions are still in flight.
+    async fn relay_batch_executions(&self, submitter: Address) -> Result<()> {
+        if self
+            .settle_in_flight(submitter, TxKind::BatchExecution)
+            .await?
+        {
+            return Ok(());
+        }
+        self.execute_pending_batch_commitments(submitter).await
+    }
+
+    /// Check the in-flight transactions of a kind, forgetting the ones which have been
+    /// included or dropped, and replacing the ones stuck for too long.
+    ///
+    /// Returns whether any transaction of the kind is still pending.
+    async fn settle_in_flight(&self, submitter: Address, kind: TxKind) -> Result<bool> {
+        let in_flight = self
+            .store
+            .state()
+            .in_flight(kind)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut pending = false;
+
+        for tx in in_flight {
+            match self.in_flight_status(&tx).await? {
+                (TxStatus::Included { height, success }, tx_hash) => {
+                    if success {
+                        tracing::info!(
+                            ?kind,
+                            checkpoint = tx.height,
+                            height,
+                            ?tx_hash,
+                            "relayed txn included"
+                        );
+                    } else {
+                        let reason = self
+                            .parent_handler
+                            .revert_reason(&tx_hash)
+                            .await
+                            .unwrap_or_else(|e| {
+                                tracing::debug!("cannot decode revert of {tx_hash:?}: {e}");
+                                None
+                            });
+                        tracing::warn!(
+                            ?kind,
+                            checkpoint = tx.height,
+                            height,
+                            ?tx_hash,
+                            reason = reason.as_ref().map(|r| r.to_string()),
+                            "relayed txn reverted"
+                        );
+                        emit(RelayerTxReverted {
+                            kind: format!("{kind:?}"),
+                            height: tx.height,
+                            error: reason.map(|r| r.name).unwrap_or_else(|| "Unknown".into()),
+                        });
+                    }
+                    self.store.update(|s| {
+                        s.in_flight.retain(|t| t.tx_hash != tx.tx_hash);
+                        if !success {
+                            s.forget_submission(kind, tx.height);
+                        }
+                    })?;
+                }
+                (TxStatus::Dropped, _) => {
+                    tracing::warn!(
+                        ?kind,
+                        checkpoint = tx.height,
+                        "relayed txn dropped, will resubmit"
+                    );
+                    self.store.update(|s| {
+                        s.in_flight.retain(|t| t.tx_hash != tx.tx_hash);
+                        s.forget_submission(kind, tx.height);
+                    })?;
+                }
+                (TxStatus::Pending, tx_hash) if tx.age_secs() >= self.stuck_timeout.as_secs() => {
+                    let replacement = {
+                        let _guard = self.send_lock.lock().await;
+                        self.parent_handler
+                            .bump_transaction(&submitter, &tx_hash, self.fee_bump_percent)
+                            .await?
+                    };
+                    emit(RelayerTxBumped {
+                        kind: format!("{kind:?}"),
+                        height: tx.height,
+                        bumps: tx.bumps + 1,
+                    });
+                    self.store.update(|s| {
+                        if let Some(t) = s.in_flight.iter_mut().find(|t| t.tx_hash == tx.tx_hash) {
+                            *t = tx.clone().bumped(replacement);
+                        }
+                    })?;
+                    pending = true;
+                }
+                (TxStatus::Pending, _) => {
+                    pending = true;
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// Check every hash an in-flight transaction was sent with, because after a fee bump
+    /// the original can still be included instead of its replacement.
+    ///
+    /// The transaction is only dropped if none of them are included or pending.
+    /// Returns the hash the status is about.
+    async fn in_flight_status(&self, tx: &InFlightTx) -> Result<(TxStatus, TxHash)> {
+        let mut status = (TxStatus::Dropped, tx.tx_hash);
+        for tx_hash in tx.hashes() {
+            match self.parent_handler.transaction_status(tx_hash).await? {
+                included @ TxStatus::Included { .. } => return Ok((included, *tx_hash)),
+                TxStatus::Pending if status.0 == TxStatus::Dropped => {
+                    status = (TxStatus::Pending, *tx_hash)
+                }
+                _ => {}
+            }
+        }
+        Ok(status)
+    }
+
+    /// Treat a submission whose dry run reverted as skipped instead of failed.
+    fn skip_on_revert(
+        &self,
+        kind: TxKind,
+        height: ChainEpoch,
+        result: Result<TxHash>,
+    ) -> Result<Option<TxHash>> {
+        match result {
+            Ok(tx_hash) => Ok(Some(tx_hash)),
+            Err(e) => match e.downcast_ref::<ContractRevert>() {
+                Some(revert) => {
+                    tracing::warn!(?kind, height, "skipping submission: {revert}");
+                    emit(RelayerSubmissionSkipped {
+                        kind: format!("{kind:?}"),
+                        height,
+                        reason: "simulated_revert",
+                        error: Some(revert.name.clone()),
+                    });
+                    Ok(None)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    fn skip(&self, kind: TxKind, height: ChainEpoch, reason: &'static str) {
+        emit(RelayerSubmissionSkipped {
+            kind: format!("{kind:?}"),
+            height,
+            reason,
+            error: None,
+        });
+    }
+
+    /// Remember a transaction sent to the parent until it's included.
+    fn track(&self, kind: TxKind, height: ChainEpoch, tx_hash: TxHash) -> Result<()> {
+        self.store.update(|s| {
+            if kind == TxKind::SignedHeader {
+                s.last_submitted_height = Some(height);
+            }
+            s.in_flight.push(InFlightTx::new(kind, height, tx_hash))
+        })
+    }
+
+    /// The bottom up checkpoint submits only the app hash. The breakdown of the app hash, i.e.
+    /// configuration number or bottom up message batch merkle root have to be submitted separately.
+    /// This method does not handle rollup activity as it's not required in ro
+    async fn submit_missing_app_hash_breakdowns(
+        &self,
+        submitter: Address,
+        end_height: ChainEpoch,
+    ) -> Result<()> {
+        let mut next_height = self
+            .parent_handler
+            .get_last_app_commitment_height(&self.metadata.child.id)
+            .await?;
+
+        next_height += self.metadata.period as u64;
+
+        while next_height < end_height as u64 {
+            let Some(mut commitment) = self
+                .child_handler
+                .query_app_hash_breakdown(next_height as ChainEpoch)
+                .await?
+            else {
+                // not published yet, try again in the next round
+                break;
+            };
+
+            let state_root = self
+                .child_handler
+                // the state root from fendermint client is actually in the next block height
+                .get_state_root((next_height + 1) as ChainEpoch)
+                .await?;
+
+            tracing::info!(
+                height = next_height,
+                state_root = hex::encode(state_root.as_slice()),
+                "obtains state root at height"
+            );
+
+            commitment.state_root = ethers::types::Bytes::from(state_root);
+
+            let result = {
+                let _guard = self.send_lock.lock().await;
+                self.parent_handler
+                    .record_app_hash_breakdown(
+                        next_height as ChainEpoch,
+                        &submitter,
+                        &self.metadata.child.id,
+                        commitment,
+                    )
+                    .await
+            };
+            let height = next_height as ChainEpoch;
+            let Some(tx_hash) = self.skip_on_revert(TxKind::AppHashBreakdown, height, result)?
+            else {
+                // the next breakdowns can't be recorded before this one
+                break;
+            };
+            self.track(TxKind::AppHashBreakdown, height, tx_hash)?;
+
+            next_height += self.metadata.period as u64;
+        }
+
+        Ok(())
+    }
+
+    async fn submit_next_signed_header(&self, submitter: Address) -> Result<Option<ChainEpoch>> {
+        let last_checkpoint_epoch = self
+            .parent_handler
+            .get_last_bottom_up_checkpoint_height(&self.metadata.child.id)
+            .await
+            .map_err(|e| {
+                anyhow!("cannot obtain the last bottom up checkpoint height due to: {e:}")
+            })?;
+
+        let next_checkpoint_epoch = last_checkpoint_epoch as ChainEpoch + self.metadata.period;
+
+        tracing::info!(
+            last_checkpoint_epoch,
+            next_checkpoint_epoch,
+            "last and next checkpoint submission heights"
+        );
+
+        let current_height = self.child_handler.current_epoch().await?;
+        let finalized_height = max(1, current_height - self.finalization_blocks);
+
+        tracing::debug!("last submission height: {last_checkpoint_epoch}, current height: {current_height}, finalized_height: {finalized_height}");
+
+        if finalized_height <= next_checkpoint_epoch {
+            return Ok(None);
+        }
+
+        // The parent may not reflect a checkpoint we already got included yet, e.g. right after a restart.
+        if let Some(last) = self.store.state().last_submitted_height {
+            if last >= next_checkpoint_epoch {
+                tracing::debug!(
+                    last_submitted_height = last,
+                    "checkpoint {next_checkpoint_epoch} already submitted"
+                );
+                self.skip(
+                    TxKind::SignedHeader,
+                    next_checkpoint_epoch,
+                    "already_submitted",
+                );
+                return Ok(None);
+            }
+        }
+
+        if let Some(grace_blocks) = self.leader_grace_blocks {
+            let validators = self
+                .child_handler
+                .get_membership_at(next_checkpoint_epoch)
+                .await?;
+            let rotation = LeaderRotation::new(validators, grace_blocks);
+            if !rotation.is_turn(
+                &submitter,
+                next_checkpoint_epoch,
+                self.metadata.period,
+                finalized_height,
+            ) {
+                tracing::debug!(
+                    leader = ?rotation.leader(next_checkpoint_epoch, self.metadata.period),
+                    "not our turn to submit checkpoint {next_checkpoint_epoch}"
+                );
+                self.skip(TxKind::SignedHeader, next_checkpoint_epoch, "not_leader");
+                return Ok(None);
+            }
+        }
+
+        // Our own submissions are settled by now, so anything pending is another relayer's.
+        let pending = self
+            .parent_handler
+            .pending_signed_header_submissions(&self.metadata.child.id)
+            .await?;
+        if !pending.is_empty() {
+            tracing::info!(
+                ?pending,
+                "checkpoint submission of another relayer pending in the mempool"
+            );
+            self.skip(
+                TxKind::SignedHeader,
+                next_checkpoint_epoch,
+                "pending_in_mempool",
+            );
+            return Ok(None);
+        }
+
+        let active_validators = self
+            .parent_handler
+            .list_active_validators(&self.metadata.child.id)
+            .await?;
+        tracing::info!(
+            length = active_validators.len(),
+            "obtained list of active validators"
+        );
+
+        let (height, result) = match self.certificate {
+            CheckpointCertificate::SignedHeader => {
+                self.submit_signed_header(submitter, next_checkpoint_epoch, active_validators)
+                    .await?
+            }
+            CheckpointCertificate::Signatures => {
+                let Some(r) = self
+                    .submit_signatures(submitter, next_checkpoint_epoch, active_validators)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                r
+            }
+        };
+
+        let Some(tx_hash) =
+            self.skip_on_revert(TxKind::SignedHeader, next_checkpoint_epoch, result)?
+        else {
+            return Ok(None);
+        };
+        self.track(TxKind::SignedHeader, next_checkpoint_epoch, tx_hash)?;
+
+        Ok(Some(height))
+    }
+
+    /// Certify the checkpoint with the CometBFT signed header of the block after it,
+    /// which contains the app hash of the checkpoint height.
+    async fn submit_signed_header(
+        &self,
+        submitter: Address,
+        next_checkpoint_epoch: ChainEpoch,
+        active_validators: Vec<(Address, ValidatorInfo)>,
+    ) -> Result<(ChainEpoch, Result<TxHash>)> {
+        let pubkeys = active_validators
+            .iter()
+            .map(|(_, info)| info.staking.metadata.as_slice());
+        tracing::info!("obtained list of active validators public keys");
+
+        let mut header = self
+            .child_handler
+            // we need to query signed header of the next block for the app hash in the checkpoint epoch
+            .get_signed_header(next_checkpoint_epoch as u64 + 1)
+            .await?;
+        tracing::info!("obtained signed header: {header:?}");
+
+        // order validators against the public keys ordered on chain. This is required as contract
+        // requires the exact public keys ordering onchain.
+        let cert = header.generate_validator_cert(pubkeys)?;
+        tracing::info!(cert = ?cert, "obtained certificate");
+
+        let height = header.header.height;
+
+        let result = {
+            let _guard = self.send_lock.lock().await;
+            self.parent_handler
+                .submit_signed_header(&submitter, &self.child_subnet().id, header, cert)
+                .await
+        };
+
+        Ok((height, result))
+    }
+
+    /// Certify the checkpoint with the signatures the validators gossiped in the child subnet,
+    /// if the active ones among them have a quorum.
+    async fn submit_signatures(
+        &self,
+        submitter: Address,
+        next_checkpoint_epoch: ChainEpoch,
+        active_validators: Vec<(Address, ValidatorInfo)>,
+    ) -> Result<Option<(ChainEpoch, Result<TxHash>)>> {
+        let groups = self
+            .child_handler
+            .get_checkpoint_signatures(next_checkpoint_epoch as u64)
+            .await?;
+
+        if groups.is_empty() {
+            tracing::info!("no checkpoint signatures collected yet");
+            self.skip(TxKind::SignedHeader, next_checkpoint_epoch, "no_signatures");
+            return Ok(None);
+        }
+
+        let mut powers = HashMap::new();
+        let mut total_power = TokenAmount::zero();
+        for (addr, info) in active_validators {
+            let addr = payload_to_evm_address(addr.payload())?;
+            total_power += info.staking.current_power.clone();
+            powers.insert(addr, info.staking.current_power);
+        }
+
+        let (signatures, signed_power) = most_powerful_signatures(groups, &powers);
+
+        if signed_power.clone() * 3 <= total_power.clone() * 2 {
+            tracing::info!(
+                signed_power = signed_power.to_string(),
+                total_power = total_power.to_string(),
+                "checkpoint signatures have no quorum yet"
+            );
+            self.skip(TxKind::SignedHeader, next_checkpoint_epoch, "no_quorum");
+            return Ok(None);
+        }
+
+        tracing::info!(
+            signatures = signatures.signatures.len(),
+            "obtained checkpoint signatures"
+        );
+
+        let result = {
+            let _guard = self.send_lock.lock().await;
+            self.parent_handler
+                .submit_checkpoint_signatures(&submitter, &self.child_subnet().id, signatures)
+                .await
+        };
+
+        // Same as the height of the signed header, for consistency.
+        Ok(Some((next_checkpoint_epoch + 1, result)))
+    }
+
+    /// Checks if there are any pending bottom up batch commitments, if so execute them.
+    async fn execute_pending_batch_commitments(&self, submitter: Address) -> Result<()> {
+        let pending_commitments = self
+            .parent_handler
+            .list_pending_bottom_up_batch_commitments(&self.metadata.child.id)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "cannot obtain the list of pending bottom up batch commitments due to: {e:}"
+                )
+            })?;
+        tracing::info!("total pending commitments: {}", pending_commitments.len());
+
+        for commitment in pending_commitments {
+            let inclusions = self
+                .child_handler
+                .make_next_bottom_up_batch_inclusions(&commitment)
+                .await?;
+
+            let height = commitment.height as i64;
+            let result = {
+                let _guard = self.send_lock.lock().await;
+                self.parent_handler
+                    .execute_bottom_up_batch(
+                        &submitter,
+                        &self.metadata.child.id,
+                        height,
+                        inclusions,
+                    )
+                    .await
+            };
+            let Some(tx_hash) = self
+                .skip_on_revert(TxKind::BatchExecution, height, result)
+                .inspect_err(|err| {
+                    tracing::error!("Fail to execute bottom up batch at height {height}: {err}");
+                })?
+            else {
+                continue;
+            };
+            self.track(TxKind::BatchExecution, height, tx_hash)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Pick the app hash signed by the most power among the groups of checkpoint signatures,
+/// after dropping the signatures of inactive validators, which the subnet actor would reject.
+///
+/// The power is only counted after filtering, so signatures from outside the active set
+/// cannot make an app hash win over the one the active validators signed.
+fn most_powerful_signatures(
+    groups: Vec<CheckpointSignatures>,
+    powers: &HashMap<ethers::types::Address, TokenAmount>,
+) -> (CheckpointSignatures, TokenAmount) {
+    groups
+        .into_iter()
+        .map(|mut group| {
+            group
+                .signatures
+                .retain(|s| powers.contains_key(&s.validator));
+            let power = group
+                .signatures
+                .iter()
+                .filter_map(|s| powers.get(&s.validator))
+                .fold(TokenAmount::zero(), |acc, p| acc + p);
+            (group, power)
+        })
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+        .expect("groups are not empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::checkpoint::{CheckpointSignatures, ValidatorSignature};
+    use ipc_api::subnet_id::SubnetID;
+
+    use super::{most_powerful_signatures, BottomUpCheckpointManager, CheckpointCertificate};
+    use crate::config::{EVMSubnet, Subnet, SubnetConfig};
+    use crate::manager::mock::MockParent;
+    use crate::manager::{SignedHeaderRelayer, SubnetManager};
+
+    fn group(app_hash: u8, validators: &[u8]) -> CheckpointSignatures {
+        CheckpointSignatures {
+            height: 10,
+            app_hash: vec![app_hash; 32],
+            signatures: validators
+                .iter()
+                .map(|v| ValidatorSignature {
+                    validator: ethers::types::Address::repeat_byte(*v),
+                    signature: vec![*v; 65],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn inactive_signers_cannot_outvote_active_ones() {
+        let powers = HashMap::from([
+            (
+                ethers::types::Address::repeat_byte(1),
+                TokenAmount::from_whole(10),
+            ),
+            (
+                ethers::types::Address::repeat_byte(2),
+                TokenAmount::from_whole(10),
+            ),
+            (
+                ethers::types::Address::repeat_byte(3),
+                TokenAmount::from_whole(1),
+            ),
+        ]);
+
+        // A Sybil group with more, but unpowered, signatures over a different app hash.
+        let groups = vec![group(0xff, &[3, 7, 8, 9]), group(0xaa, &[1, 2])];
+
+        let (sigs, power) = most_powerful_signatures(groups, &powers);
+
+        assert_eq!(sigs.app_hash, vec![0xaa; 32]);
+        assert_eq!(sigs.signatures.len(), 2);
+        assert_eq!(power, TokenAmount::from_whole(20));
+    }
+
+    #[test]
+    fn inactive_signatures_are_dropped() {
+        let powers = HashMap::from([(
+            ethers::types::Address::repeat_byte(1),
+            TokenAmount::from_whole(1),
+        )]);
+
+        let (sigs, power) = most_powerful_signatures(vec![group(0xaa, &[1, 2])], &powers);
+
+        assert_eq!(sigs.signatures.len(), 1);
+        assert_eq!(
+            sigs.signatures[0].validator,
+            ethers::types::Address::repeat_byte(1)
+        );
+        assert_eq!(power, TokenAmount::from_whole(1));
+    }
+
+    fn validator(v: u8) -> Address {
+        Address::new_delegated(10, &[v; 20]).unwrap()
+    }
+
+    fn subnet(id: SubnetID) -> Subnet {
+        Subnet {
+            id,
+            config: SubnetConfig::Fevm(EVMSubnet {
+                provider_http: "http://127.0.0.1:8545".parse().unwrap(),
+                provider_timeout: None,
+                auth_token: None,
+                registry_addr: Address::new_id(0),
+                gateway_addr: Address::new_id(64),
+            }),
+        }
+    }
+
+    /// A parent chain with a child subnet of three validators with equal power,
+    /// past the first checkpoint height of the child.
+    async fn new_parent() -> MockParent {
+        let child = SubnetID::new(123, vec![Address::new_id(1001)]);
+        let parent = MockParent::new(child.clone(), 0);
+
+        for v in 1..=3 {
+            parent.credit(validator(v), TokenAmount::from_whole(10));
+            parent
+                .join_subnet(
+                    child.clone(),
+                    validator(v),
+                    TokenAmount::from_whole(1),
+                    Vec::new(),
+                )
+                .await
+                .unwrap();
+        }
+
+        parent.produce_blocks(11);
+        parent
+    }
+
+    /// A relayer which reads the child subnet from the same mock it submits to.
+    async fn new_relayer(parent: &MockParent) -> BottomUpCheckpointManager<MockParent> {
+        let child = parent.child_subnet();
+        let root = child.parent().unwrap();
+
+        BottomUpCheckpointManager::new(subnet(root), subnet(child), parent.clone(), parent.clone())
+            .await
+            .unwrap()
+            .with_certificate(CheckpointCertificate::Signatures)
+    }
+
+    #[tokio::test]
+    async fn relays_checkpoint_signatures_with_quorum() {
+        let parent = new_parent().await;
+        let relayer = new_relayer(&parent).await;
+        let child = parent.child_subnet();
+
+        assert_eq!(relayer.submission_period(), 10);
+
+        parent.add_checkpoint_signatures(group(0xaa, &[1, 2, 3]));
+
+        let height = relayer
+            .submit_next_signed_header(validator(1))
+            .await
+            .unwrap();
+        assert_eq!(height, Some(11));
+
+        // The submission is in the mempool until the next block, and is not sent twice.
+        relayer.relay_signed_header(validator(1)).await.unwrap();
+        assert_eq!(
+            parent
+                .pending_signed_header_submissions(&child)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(parent.last_bottom_up_checkpoint_height(&child), 0);
+
+        parent.produce_block();
+
+        assert_eq!(parent.last_bottom_up_checkpoint_height(&child), 10);
+    }
+
+    #[tokio::test]
+    async fn does_not_relay_checkpoint_signatures_without_quorum() {
+        let parent = new_parent().await;
+        let relayer = new_relayer(&parent).await;
+        let child = parent.child_subnet();
+
+        // Two thirds of the power is not enough, and a non-validator adds nothing.
+        parent.add_checkpoint_signatures(group(0xaa, &[1, 2, 4]));
+
+        let height = relayer
+            .submit_next_signed_header(validator(1))
+            .await
+            .unwrap();
+        assert_eq!(height, None);
+
+        parent.produce_block();
+
+        assert_eq!(parent.last_bottom_up_checkpoint_height(&child), 0);
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Deterministic rotation of the relayer expected to submit each checkpoint.
+
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+
+/// Picks which relayer of a submitter set should submit a checkpoint, so that relayers
+/// of the same subnet don't all pay for racing the same submission. The submitters are
+/// the validators of the subnet at the checkpoint height, which every relayer reads from
+/// the chain, so they agree on the set without being configured alike.
+///
+/// The leader of a checkpoint is chosen round robin from the sorted submitter set by
+/// checkpoint number. If the leader hasn't submitted `grace_blocks` blocks after the
+/// checkpoint height, the next relayer in the rotation takes over, and so on. Relayers
+/// outside the set only step in after every member had its turn.
+#[derive(Debug, Clone)]
+pub struct LeaderRotation {
+    submitters: Vec<Address>,
+    grace_blocks: ChainEpoch,
+}
+
+impl LeaderRotation {
+    pub fn new(mut submitters: Vec<Address>, grace_blocks: ChainEpoch) -> Self {
+        // every relayer has to agree on the order, regardless of how it was configured
+        submitters.sort_by_key(|a| a.to_bytes());
+        submitters.dedup();
+        Self {
+            submitters,
+            grace_blocks,
+        }
+    }
+
+    /// The relayer expected to submit the checkpoint at the given height.
+    pub fn leader(&self, checkpoint_height: ChainEpoch, period: ChainEpoch) -> Option<&Address> {
+        if self.submitters.is_empty() {
+            return None;
+        }
+        let round = checkpoint_height / period.max(1);
+        let index = round.rem_euclid(self.submitters.len() as ChainEpoch) as usize;
+        self.submitters.get(index)
+    }
+
+    /// Whether `submitter` may submit the checkpoint at `checkpoint_height`, given the
+    /// latest finalized height of the child subnet.
+    pub fn is_turn(
+        &self,
+        submitter: &Address,
+        checkpoint_height: ChainEpoch,
+        period: ChainEpoch,
+        finalized_height: ChainEpoch,
+    ) -> bool {
+        let n = self.submitters.len();
+        if n == 0 {
+            return true;
+        }
+
+        let round = (checkpoint_height / period.max(1)).rem_euclid(n as ChainEpoch) as usize;
+        let offset = match self.submitters.iter().position(|s| s == submitter) {
+            Some(index) => (index + n - round) % n,
+            None => n,
+        };
+
+        finalized_height >= checkpoint_height + self.grace_blocks * offset as ChainEpoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LeaderRotation;
+    use fvm_shared::address::Address;
+
+    #[test]
+    fn test_leader_rotates_per_checkpoint() {
+        let (a, b, c) = (Address::new_id(3), Address::new_id(1), Address::new_id(2));
+        let rotation = LeaderRotation::new(vec![a, b, c], 10);
+
+        assert_eq!(rotation.leader(0, 100), Some(&b));
+        assert_eq!(rotation.leader(100, 100), Some(&c));
+        assert_eq!(rotation.leader(200, 100), Some(&a));
+        assert_eq!(rotation.leader(300, 100), Some(&b));
+    }
+
+    #[test]
+    fn test_backups_take_over_after_grace() {
+        let (a, b, c) = (Address::new_id(1), Address::new_id(2), Address::new_id(3));
+        let outsider = Address::new_id(4);
+        let rotation = LeaderRotation::new(vec![a, b, c], 10);
+
+        // the leader of checkpoint 100 is `b`
+        assert!(rotation.is_turn(&b, 100, 100, 101));
+        assert!(!rotation.is_turn(&c, 100, 100, 101));
+        assert!(!rotation.is_turn(&a, 100, 100, 101));
+
+        assert!(rotation.is_turn(&c, 100, 100, 110));
+        assert!(!rotation.is_turn(&a, 100, 100, 110));
+
+        assert!(rotation.is_turn(&a, 100, 100, 120));
+        assert!(!rotation.is_turn(&outsider, 100, 100, 120));
+        assert!(rotation.is_turn(&outsider, 100, 100, 130));
+    }
+
+    #[test]
+    fn test_empty_set_always_submits() {
+        let rotation = LeaderRotation::new(vec![], 10);
+        assert!(rotation.is_turn(&Address::new_id(1), 100, 100, 101));
+        assert_eq!(rotation.leader(100, 100), None);
+    }
+}
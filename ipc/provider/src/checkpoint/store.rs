@@ -0,0 +1,242 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Persistent state of the bottom up checkpoint relayer.
+
+use crate::manager::TxHash;
+use anyhow::{Context, Result};
+use fs_err as fs;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The kind of transactions the relayer sends to the parent.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    SignedHeader,
+    AppHashBreakdown,
+    BatchExecution,
+}
+
+/// A transaction sent to the parent which hasn't been seen included yet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InFlightTx {
+    pub kind: TxKind,
+    /// The checkpoint height the transaction is for.
+    pub height: ChainEpoch,
+    /// The hash of the latest replacement of the transaction.
+    pub tx_hash: TxHash,
+    /// The hashes of the earlier transactions with the same nonce, any of which might still
+    /// be included instead of the latest one.
+    #[serde(default)]
+    pub replaced: Vec<TxHash>,
+    /// Unix timestamp in seconds when the transaction, or its latest replacement, was sent.
+    pub sent_at: u64,
+    /// Number of times the transaction has been replaced with a higher gas price.
+    pub bumps: u32,
+}
+
+impl InFlightTx {
+    pub fn new(kind: TxKind, height: ChainEpoch, tx_hash: TxHash) -> Self {
+        Self {
+            kind,
+            height,
+            tx_hash,
+            replaced: Vec::new(),
+            sent_at: now_secs(),
+            bumps: 0,
+        }
+    }
+
+    /// Replace the transaction with one paying more gas, remembering the earlier hash.
+    pub fn bumped(self, tx_hash: TxHash) -> Self {
+        let mut replaced = self.replaced;
+        replaced.push(self.tx_hash);
+        Self {
+            tx_hash,
+            replaced,
+            sent_at: now_secs(),
+            bumps: self.bumps + 1,
+            ..self
+        }
+    }
+
+    /// All the hashes the transaction was sent with, latest first.
+    pub fn hashes(&self) -> impl Iterator<Item = &TxHash> {
+        std::iter::once(&self.tx_hash).chain(self.replaced.iter().rev())
+    }
+
+    /// Number of seconds since the transaction was sent.
+    pub fn age_secs(&self) -> u64 {
+        now_secs().saturating_sub(self.sent_at)
+    }
+}
+
+/// The progress of the relayer, which survives restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayerState {
+    /// The height of the last signed header submitted to the parent, unless it failed.
+    #[serde(default)]
+    pub last_submitted_height: Option<ChainEpoch>,
+    /// Transactions sent to the parent and not yet included.
+    pub in_flight: Vec<InFlightTx>,
+}
+
+impl RelayerState {
+    pub fn in_flight(&self, kind: TxKind) -> impl Iterator<Item = &InFlightTx> {
+        self.in_flight.iter().filter(move |tx| tx.kind == kind)
+    }
+
+    /// Forget a signed header submission that failed, so it's submitted again.
+    pub fn forget_submission(&mut self, kind: TxKind, height: ChainEpoch) {
+        if kind == TxKind::SignedHeader && self.last_submitted_height == Some(height) {
+            self.last_submitted_height = None;
+        }
+    }
+}
+
+/// Keeps the [`RelayerState`] in memory and writes every change through to a JSON file,
+/// or only in memory if no file is given.
+#[derive(Debug)]
+pub struct RelayerStore {
+    path: Option<PathBuf>,
+    state: Mutex<RelayerState>,
+}
+
+impl RelayerStore {
+    /// A store which forgets everything on restart.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            state: Mutex::new(RelayerState::default()),
+        }
+    }
+
+    /// Load the state from the file, or start from scratch if it doesn't exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let state = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("failed to parse relayer state in {path:?}"))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                tracing::info!(?path, "relayer state does not exist, starting from scratch");
+                RelayerState::default()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// A copy of the current state.
+    pub fn state(&self) -> RelayerState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Apply a change to the state and persist it.
+    pub fn update<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut RelayerState) -> T,
+    {
+        let mut state = self.state.lock().unwrap();
+        let value = f(&mut state);
+        if let Some(path) = &self.path {
+            write_atomically(path, &state)?;
+        }
+        Ok(value)
+    }
+}
+
+/// Write to a temporary file first, so a crash can't leave a half written state behind.
+fn write_atomically(path: &Path, state: &RelayerState) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(state)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InFlightTx, RelayerStore, TxKind};
+    use crate::manager::TxHash;
+
+    #[test]
+    fn test_state_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("relayer").join("state.json");
+
+        let store = RelayerStore::open(&path).unwrap();
+        assert_eq!(store.state(), Default::default());
+
+        let tx = InFlightTx::new(TxKind::SignedHeader, 10, TxHash::repeat_byte(1))
+            .bumped(TxHash::repeat_byte(2));
+        store
+            .update(|s| {
+                s.last_submitted_height = Some(10);
+                s.in_flight.push(tx.clone());
+            })
+            .unwrap();
+
+        let store = RelayerStore::open(&path).unwrap();
+        let state = store.state();
+        assert_eq!(state.last_submitted_height, Some(10));
+        assert_eq!(state.in_flight(TxKind::SignedHeader).count(), 1);
+        assert_eq!(state.in_flight(TxKind::BatchExecution).count(), 0);
+        assert_eq!(state.in_flight[0], tx);
+    }
+
+    #[test]
+    fn test_bumped_tx_remembers_earlier_hashes() {
+        let tx = InFlightTx::new(TxKind::BatchExecution, 5, TxHash::repeat_byte(1))
+            .bumped(TxHash::repeat_byte(2))
+            .bumped(TxHash::repeat_byte(3));
+
+        assert_eq!(tx.bumps, 2);
+        assert_eq!(tx.tx_hash, TxHash::repeat_byte(3));
+        assert_eq!(
+            tx.hashes().cloned().collect::<Vec<_>>(),
+            vec![
+                TxHash::repeat_byte(3),
+                TxHash::repeat_byte(2),
+                TxHash::repeat_byte(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_without_replacements_parses() {
+        let json = r#"{"in_flight":[{"kind":"signed_header","height":10,"tx_hash":"0x0101010101010101010101010101010101010101010101010101010101010101","sent_at":0,"bumps":0}]}"#;
+        let state: super::RelayerState = serde_json::from_str(json).unwrap();
+        assert!(state.in_flight[0].replaced.is_empty());
+        assert_eq!(state.last_submitted_height, None);
+    }
+
+    #[test]
+    fn test_failed_submission_is_forgotten() {
+        let mut state = super::RelayerState {
+            last_submitted_height: Some(10),
+            ..Default::default()
+        };
+        state.forget_submission(TxKind::BatchExecution, 10);
+        assert_eq!(state.last_submitted_height, Some(10));
+        state.forget_submission(TxKind::SignedHeader, 5);
+        assert_eq!(state.last_submitted_height, Some(10));
+        state.forget_submission(TxKind::SignedHeader, 10);
+        assert_eq!(state.last_submitted_height, None);
+    }
+}
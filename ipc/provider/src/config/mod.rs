@@ -15,11 +15,13 @@ mod tests;
 use fs_err as fs;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 use crate::config::deserialize::eth_addr_str_to_address;
 use anyhow::{Context, Result};
 use deserialize::deserialize_subnets_from_vec;
 use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{RemoteSigner, RemoteSignerConfig, Signer};
 use serde::{Deserialize, Serialize};
 use serialize::serialize_subnets_to_str;
 pub use subnet::{EVMSubnet, Subnet, SubnetConfig};
@@ -41,6 +43,9 @@ pub struct Config {
     #[serde(deserialize_with = "deserialize_subnets_from_vec", default)]
     #[serde(serialize_with = "serialize_subnets_to_str")]
     pub subnets: HashMap<SubnetID, Subnet>,
+    /// Remote signers holding the keys of some of the accounts, used instead of the keystore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_signers: Vec<RemoteSignerConfig>,
 }
 
 impl Config {
@@ -49,6 +54,7 @@ impl Config {
         Config {
             keystore_path: None,
             subnets: Default::default(),
+            remote_signers: Default::default(),
         }
     }
 
@@ -99,6 +105,14 @@ impl Config {
     pub fn remove_subnet(&mut self, subnet_id: &SubnetID) {
         self.subnets.remove(subnet_id);
     }
+
+    /// Instantiate the configured remote signers.
+    pub fn remote_signers(&self) -> Result<Vec<Arc<dyn Signer>>> {
+        self.remote_signers
+            .iter()
+            .map(|c| Ok(Arc::new(RemoteSigner::from_config(c)?) as Arc<dyn Signer>))
+            .collect()
+    }
 }
 
 impl Default for Config {
@@ -132,6 +146,7 @@ impl Default for Config {
         Config {
             keystore_path: Some(DEFAULT_KEYSTORE.to_string()),
             subnets,
+            remote_signers: Default::default(),
         }
     }
 }
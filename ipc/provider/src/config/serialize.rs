@@ -180,6 +180,7 @@ mod tests {
         let mut config = Config {
             keystore_path: Some(String::from("~/.ipc")),
             subnets: Default::default(),
+            remote_signers: Default::default(),
         };
 
         let eth_addr1 = EthAddress::from_str("0x6BE1Ccf648c74800380d0520D797a170c808b624").unwrap();
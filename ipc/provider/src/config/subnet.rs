@@ -29,60 +29,82 @@ pub struct Subnet {
 pub enum SubnetConfig {
     #[serde(rename = "fevm")]
     Fevm(EVMSubnet),
+    /// A Filecoin network reached through the Lotus JSON-RPC API, sending messages
+    /// from native `f1`/`f3` accounts.
+    #[serde(rename = "fvm")]
+    Fvm(FVMSubnet),
 }
 
 /// A helper enum to differentiate the different network types
 #[derive(PartialEq, Eq)]
 pub enum NetworkType {
     Fevm,
+    Fvm,
 }
 
 impl Subnet {
     pub fn network_type(&self) -> NetworkType {
         match &self.config {
             SubnetConfig::Fevm(_) => NetworkType::Fevm,
+            SubnetConfig::Fvm(_) => NetworkType::Fvm,
         }
     }
 
     pub fn auth_token(&self) -> Option<String> {
         match &self.config {
             SubnetConfig::Fevm(s) => s.auth_token.clone(),
+            SubnetConfig::Fvm(s) => s.auth_token.clone(),
         }
     }
 
     pub fn rpc_http(&self) -> &Url {
         match &self.config {
             SubnetConfig::Fevm(s) => &s.provider_http,
+            SubnetConfig::Fvm(s) => &s.jsonrpc_api_http,
         }
     }
 
     pub fn rpc_timeout(&self) -> Option<Duration> {
         match &self.config {
             SubnetConfig::Fevm(s) => s.provider_timeout,
+            SubnetConfig::Fvm(s) => s.provider_timeout,
         }
     }
 
     pub fn gateway_addr(&self) -> Address {
         match &self.config {
             SubnetConfig::Fevm(s) => s.gateway_addr,
+            SubnetConfig::Fvm(s) => s.gateway_addr,
         }
     }
 
     pub fn registry_addr(&self) -> Address {
         match &self.config {
             SubnetConfig::Fevm(s) => s.registry_addr,
+            SubnetConfig::Fvm(s) => s.registry_addr,
         }
     }
 }
 
 /// The FVM subnet config parameters
+///
+/// The IPC contracts are addressed with their `f410` addresses, and the Lotus endpoint
+/// also has to serve the Ethereum JSON-RPC API, which is used to query them.
+#[serde_as]
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct FVMSubnet {
+    pub jsonrpc_api_http: Url,
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    pub provider_timeout: Option<Duration>,
+    pub auth_token: Option<String>,
+
+    #[serde(deserialize_with = "deserialize_address_from_str")]
+    #[serde(serialize_with = "serialize_address_to_str")]
+    pub registry_addr: Address,
+
     #[serde(deserialize_with = "deserialize_address_from_str")]
     #[serde(serialize_with = "serialize_address_to_str")]
     pub gateway_addr: Address,
-    pub jsonrpc_api_http: Url,
-    pub auth_token: Option<String>,
 }
 
 /// The EVM subnet config parameters
@@ -8,14 +8,17 @@ use ipc_api::subnet_id::SubnetID;
 use ipc_types::EthAddress;
 use url::Url;
 
+use crate::config::subnet::NetworkType;
 use crate::config::Config;
 
 // Arguments for the config's fields
 const REPO_PATH: &str = "~/.ipc";
 const CHILD_ID: &str = "/r123/f0100";
+const FVM_ID: &str = "/r314159";
 const CHILD_AUTH_TOKEN: &str = "CHILD_AUTH_TOKEN";
 const PROVIDER_HTTP: &str = "http://127.0.0.1:3030/rpc/v1";
 const ETH_ADDRESS: &str = "0x6be1ccf648c74800380d0520d797a170c808b624";
+const REMOTE_SIGNER_URL: &str = "http://127.0.0.1:9000/";
 
 #[test]
 fn check_keystore_config() {
@@ -42,7 +45,40 @@ fn check_subnets_config() {
     assert_eq!(child.auth_token().as_ref().unwrap(), CHILD_AUTH_TOKEN);
 }
 
+#[test]
+fn check_fvm_subnet_config() {
+    let config = read_config().subnets;
+
+    let subnet_id = SubnetID::from_str(FVM_ID).unwrap();
+    let subnet = &config[&subnet_id];
+    assert!(subnet.network_type() == NetworkType::Fvm);
+    assert_eq!(
+        subnet.gateway_addr(),
+        Address::from(EthAddress::from_str(ETH_ADDRESS).unwrap())
+    );
+    assert_eq!(*subnet.rpc_http(), Url::from_str(PROVIDER_HTTP).unwrap());
+    assert!(subnet.auth_token().is_none());
+}
+
+#[test]
+fn check_remote_signers_config() {
+    let config = read_config();
+    assert_eq!(config.remote_signers.len(), 1);
+
+    let signer = &config.remote_signers[0];
+    assert_eq!(signer.url, Url::from_str(REMOTE_SIGNER_URL).unwrap());
+    assert_eq!(signer.address, ETH_ADDRESS);
+    assert!(signer.auth_token.is_none());
+
+    let signers = config.remote_signers().unwrap();
+    assert_eq!(
+        signers[0].address(),
+        ethers::types::Address::from_str(ETH_ADDRESS).unwrap()
+    );
+}
+
 fn config_str() -> String {
+    let f410_address = Address::from(EthAddress::from_str(ETH_ADDRESS).unwrap());
     formatdoc!(
         r#"
         keystore_path = "{REPO_PATH}"
@@ -56,6 +92,19 @@ fn config_str() -> String {
         provider_http = "{PROVIDER_HTTP}"
         registry_addr = "{ETH_ADDRESS}"
         gateway_addr = "{ETH_ADDRESS}"
+
+        [[subnets]]
+        id = "{FVM_ID}"
+
+        [subnets.config]
+        network_type = "fvm"
+        jsonrpc_api_http = "{PROVIDER_HTTP}"
+        registry_addr = "{f410_address}"
+        gateway_addr = "{f410_address}"
+
+        [[remote_signers]]
+        url = "{REMOTE_SIGNER_URL}"
+        address = "{ETH_ADDRESS}"
         "#
     )
 }
@@ -19,10 +19,10 @@ use ipc_api::{
     subnet_id::SubnetID,
 };
 use ipc_wallet::{
-    EthKeyAddress, EvmKeyStore, KeyStore, KeyStoreConfig, PersistentKeyStore, Wallet,
+    EthKeyAddress, EvmKeyStore, HdWallet, KeyStore, KeyStoreConfig, PersistentKeyStore, Wallet,
 };
 use lotus::message::wallet::WalletKeyType;
-use manager::{EthSubnetManager, SubnetGenesisInfo, SubnetInfo, SubnetManager};
+use manager::{EthSubnetManager, LotusSubnetManager, SubnetGenesisInfo, SubnetInfo, SubnetManager};
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Borrow,
@@ -136,7 +136,11 @@ impl IpcProvider {
                 config::subnet::SubnetConfig::Fevm(_) => {
                     let wallet = self.evm_keystore.clone();
                     let manager =
-                        match EthSubnetManager::from_subnet_with_wallet_store(subnet, wallet) {
+                        match EthSubnetManager::from_subnet_with_wallet_store(subnet, wallet)
+                            .and_then(|m| {
+                                let signers = self.config.remote_signers()?;
+                                Ok(signers.into_iter().fold(m, |m, s| m.with_signer(s)))
+                            }) {
                             Ok(w) => Some(w),
                             Err(e) => {
                                 tracing::warn!("error initializing evm manager: {e}");
@@ -148,6 +152,27 @@ impl IpcProvider {
                         subnet: subnet.clone(),
                     })
                 }
+                config::subnet::SubnetConfig::Fvm(_) => {
+                    let manager = match LotusSubnetManager::from_subnet_with_wallet_store(
+                        subnet,
+                        self.fvm_wallet.clone(),
+                        self.evm_keystore.clone(),
+                    )
+                    .and_then(|m| {
+                        let signers = self.config.remote_signers()?;
+                        Ok(signers.into_iter().fold(m, |m, s| m.with_signer(s)))
+                    }) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            tracing::warn!("error initializing fvm manager: {e}");
+                            return None;
+                        }
+                    };
+                    Some(Connection {
+                        manager: Box::new(manager),
+                        subnet: subnet.clone(),
+                    })
+                }
             },
             None => None,
         }
@@ -224,6 +249,18 @@ impl IpcProvider {
                     return Ok(addr);
                 }
             }
+            config::subnet::SubnetConfig::Fvm(_) => {
+                if self.sender.is_none() {
+                    let wallet = self.fvm_wallet()?;
+                    let addr = wallet
+                        .read()
+                        .unwrap()
+                        .get_default()
+                        .map_err(|_| anyhow!("no default fvm account configured"))?;
+                    self.sender = Some(addr);
+                    return Ok(addr);
+                }
+            }
         };
 
         Err(anyhow!("error fetching a valid sender"))
@@ -608,6 +645,16 @@ impl IpcProvider {
         conn.manager().chain_head_height().await
     }
 
+    /// Obtain the latest height the subnet considers final by itself, if it has such a notion.
+    pub async fn finalized_chain_head(
+        &self,
+        subnet: &SubnetID,
+    ) -> anyhow::Result<Option<ChainEpoch>> {
+        let conn = self.get_connection(subnet)?;
+
+        conn.manager().finalized_chain_head_height().await
+    }
+
     /// Obtain the genesis epoch of the input subnet.
     pub async fn genesis_epoch(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
@@ -834,6 +881,71 @@ impl IpcProvider {
         out
     }
 
+    /// Path of the mnemonic file in the configured keystore directory.
+    fn mnemonic_path(&self) -> anyhow::Result<PathBuf> {
+        let repo = self
+            .config
+            .keystore_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No keystore repo found in config"))?;
+        Ok(expand_tilde(
+            Path::new(repo).join(ipc_wallet::DEFAULT_MNEMONIC_NAME),
+        ))
+    }
+
+    /// Generate a new mnemonic and store it in the keystore directory.
+    ///
+    /// Returns the phrase, which the user should back up.
+    pub fn new_mnemonic(&self, word_count: usize) -> anyhow::Result<String> {
+        let wallet = HdWallet::generate(word_count)?;
+        wallet.write_to_file(&self.mnemonic_path()?)?;
+        Ok(wallet.phrase())
+    }
+
+    /// Store an existing mnemonic in the keystore directory.
+    pub fn import_mnemonic(&self, phrase: &str) -> anyhow::Result<()> {
+        HdWallet::from_phrase(phrase)?.write_to_file(&self.mnemonic_path()?)
+    }
+
+    /// Load the stored mnemonic, optionally protected by a BIP-39 passphrase.
+    fn hd_wallet(&self, passphrase: Option<&str>) -> anyhow::Result<HdWallet> {
+        let wallet = HdWallet::read_from_file(&self.mnemonic_path()?)?;
+        Ok(match passphrase {
+            Some(p) => wallet.with_passphrase(p),
+            None => wallet,
+        })
+    }
+
+    /// Derive the EVM key at `index` from the stored mnemonic and add it to the keystore.
+    pub fn derive_evm_key(
+        &self,
+        index: u32,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<EthKeyAddress> {
+        let key_info = self.hd_wallet(passphrase)?.derive_evm_key(index)?;
+        let wallet = self.evm_wallet()?;
+
+        let out = wallet.write().unwrap().put(key_info);
+        out
+    }
+
+    /// Derive the FVM key at `index` from the stored mnemonic and add it to the keystore.
+    pub fn derive_fvm_key(
+        &self,
+        tp: WalletKeyType,
+        index: u32,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<Address> {
+        let tp = match tp {
+            WalletKeyType::BLS => SignatureType::BLS,
+            WalletKeyType::Secp256k1 => SignatureType::Secp256k1,
+            WalletKeyType::Secp256k1Ledger => return Err(anyhow!("ledger key type not supported")),
+        };
+        let key_info = self.hd_wallet(passphrase)?.derive_fvm_key(tp, index)?;
+
+        Ok(self.fvm_wallet()?.write().unwrap().import(key_info)?)
+    }
+
     pub fn import_fvm_key(&self, keyinfo: &str) -> anyhow::Result<ImportedKey<Address>> {
         let wallet = self.fvm_wallet()?;
         let mut wallet = wallet.write().unwrap();
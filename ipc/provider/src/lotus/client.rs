@@ -339,13 +339,14 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     async fn get_tipset_by_height(
         &self,
         epoch: ChainEpoch,
-        tip_set: Cid,
+        tip_set: &[Cid],
     ) -> Result<GetTipSetByHeightResponse> {
+        let tip_set = tip_set.iter().map(|c| CIDMap::from(*c)).collect::<Vec<_>>();
         let r = self
             .client
             .request::<GetTipSetByHeightResponse>(
                 methods::GET_TIPSET_BY_HEIGHT,
-                json!([epoch, [CIDMap::from(tip_set)]]),
+                json!([epoch, tip_set]),
             )
             .await?;
         tracing::debug!("received get_tipset_by_height response: {r:?}");
@@ -428,7 +429,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
                 "From": msg.from.to_string(),
                 "Value": msg.value.atto().to_string(),
                 "Method": msg.method,
-                "Params": msg.params,
+                "Params": base64::engine::general_purpose::STANDARD.encode(&msg.params),
                 "Nonce": msg.nonce,
 
                 "GasLimit": 0,
@@ -1,6 +1,9 @@
 use cid::Cid;
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
+use fvm_ipld_encoding::BytesSer;
+use fvm_shared::clock::ChainEpoch;
+use multihash_codetable::{Code, MultihashDigest};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -11,6 +14,8 @@ use crate::lotus::message::CIDMap;
 #[serde(rename_all = "PascalCase")]
 pub struct Block {
     parent_state_root: CIDMap,
+    #[serde(default)]
+    parents: Vec<CIDMap>,
 }
 
 /// A simplified struct representing a `ChainGetTipSetByHeight` response that does not fully
@@ -20,6 +25,9 @@ pub struct Block {
 pub struct GetTipSetByHeightResponse {
     pub cids: Vec<CIDMap>,
     blocks: Vec<Block>,
+    /// The height of the returned tipset, which is lower than the requested one on null rounds.
+    /// Required, because defaulting it would make every height look like a null round.
+    pub height: ChainEpoch,
 }
 
 impl GetTipSetByHeightResponse {
@@ -35,6 +43,25 @@ impl GetTipSetByHeightResponse {
         r
     }
 
+    /// The hash of the tipset as exposed by the Ethereum JSON-RPC API of Lotus.
+    pub fn eth_hash(&self) -> anyhow::Result<Vec<u8>> {
+        tipset_key_eth_hash(&self.tip_set_cids()?)
+    }
+
+    /// The hash of the parent tipset as exposed by the Ethereum JSON-RPC API of Lotus.
+    pub fn parent_eth_hash(&self) -> anyhow::Result<Vec<u8>> {
+        let block = self
+            .blocks
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("tipset has no blocks"))?;
+        let parents = block
+            .parents
+            .iter()
+            .map(Cid::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        tipset_key_eth_hash(&parents)
+    }
+
     pub fn blocks_state_roots(&self) -> anyhow::Result<Vec<Cid>> {
         self.blocks
             .iter()
@@ -43,6 +70,14 @@ impl GetTipSetByHeightResponse {
     }
 }
 
+/// Lotus identifies a tipset on its Ethereum API by the digest of the tipset key CID,
+/// which is the Blake2b-256 hash of the CBOR byte string of the concatenated block CIDs.
+pub fn tipset_key_eth_hash(cids: &[Cid]) -> anyhow::Result<Vec<u8>> {
+    let key = cids.iter().flat_map(|c| c.to_bytes()).collect::<Vec<_>>();
+    let bytes = fvm_ipld_encoding::to_vec(&BytesSer(&key))?;
+    Ok(Code::Blake2b256.digest(&bytes).digest().to_vec())
+}
+
 /// A simplified struct representing a `ChainHead` response that does not decode the `blocks` field.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
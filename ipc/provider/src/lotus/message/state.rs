@@ -14,7 +14,6 @@ use crate::lotus::message::CIDMap;
 pub struct StateWaitMsgResponse {
     #[allow(dead_code)]
     message: CIDMap,
-    #[allow(dead_code)]
     pub(crate) receipt: Receipt,
     #[allow(dead_code)]
     tip_set: Vec<CIDMap>,
@@ -35,7 +34,6 @@ pub struct ReadStateResponse<State> {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Receipt {
-    #[allow(dead_code)]
     exit_code: u32,
     #[serde(rename = "Return")]
     pub result: Option<String>,
@@ -44,6 +42,18 @@ pub struct Receipt {
 }
 
 impl Receipt {
+    pub fn exit_code(&self) -> u32 {
+        self.exit_code
+    }
+
+    /// The raw bytes returned by the message.
+    pub fn return_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match &self.result {
+            None => Ok(vec![]),
+            Some(r) => Ok(base64::engine::general_purpose::STANDARD.decode(r)?),
+        }
+    }
+
     pub fn parse_result_into<T: Default + DeserializeOwned>(self) -> anyhow::Result<T> {
         if self.result.is_none() {
             return Ok(Default::default());
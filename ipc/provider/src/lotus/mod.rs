@@ -81,11 +81,12 @@ pub trait LotusClient {
     /// Returns the heaviest epoch for the chain
     async fn current_epoch(&self) -> Result<ChainEpoch>;
 
-    /// GetTipsetByHeight from the underlying chain
+    /// GetTipsetByHeight from the underlying chain, looking back from the tipset with the
+    /// given key, or from the heaviest tipset if the key is empty.
     async fn get_tipset_by_height(
         &self,
         epoch: ChainEpoch,
-        tip_set: Cid,
+        tip_set: &[Cid],
     ) -> Result<GetTipSetByHeightResponse>;
 
     /// Get the latest F3 certificate
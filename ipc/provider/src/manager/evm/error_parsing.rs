@@ -1,6 +1,7 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 
+use crate::manager::ContractRevert;
 use async_trait::async_trait;
 use ethers::providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError};
 use ipc_actors_abis::error_parser::ContractErrorParser;
@@ -45,6 +46,26 @@ impl JsonRpcClient for ErrorParserHttp {
     }
 }
 
+/// Decode the revert data of a contract call with the error ABIs of the IPC contracts.
+pub(crate) fn decode_revert(data: &[u8]) -> ContractRevert {
+    if let Ok(parsed) = ContractErrorParser::parse_from_bytes(data) {
+        return ContractRevert {
+            name: parsed.name,
+            message: parsed.message,
+        };
+    }
+    match ContractErrorParser::parse_from_bytes_legacy(data) {
+        Ok(name) => ContractRevert {
+            name,
+            message: None,
+        },
+        Err(_) => ContractRevert {
+            name: "Unknown".to_string(),
+            message: Some(format!("0x{}", hex::encode(data))),
+        },
+    }
+}
+
 fn handle_json_rpc_error(e: JsonRpcError) -> HttpClientError {
     let Some(raw_error) = e.data.as_ref() else {
         return HttpClientError::JsonRpcError(e);
@@ -191,6 +212,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_revert() {
+        let revert = decode_revert(&hex::decode("d6bb62dd").unwrap());
+        assert_eq!(revert.name, "BottomUpCheckpointAlreadySubmitted");
+
+        let revert = decode_revert(&[0xde, 0xad]);
+        assert_eq!(revert.name, "Unknown");
+        assert_eq!(revert.message.as_deref(), Some("0xdead"));
+    }
+
     #[test]
     fn test_error_parser_http_creation() {
         // Test that we can create the proxy
@@ -24,7 +24,6 @@ use std::net::{IpAddr, SocketAddr};
 use ipc_api::subnet::{Asset, AssetKind, PermissionMode};
 use ipc_api::{eth_to_fil_amount, ethers_address_to_fil_address};
 
-use crate::config::subnet::SubnetConfig;
 use crate::config::Subnet;
 use crate::lotus::message::ipc::SubnetInfo;
 use crate::manager::subnet::{
@@ -32,22 +31,28 @@ use crate::manager::subnet::{
     ValidatorRewarder,
 };
 
-use crate::manager::{EthManager, SignedHeaderRelayer, SubnetManager};
+use crate::manager::{
+    ContractRevert, EthManager, SignedHeaderRelayer, SubnetManager, TxHash, TxStatus,
+};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use ethers::abi::{AbiDecode, Tokenize};
 use ethers::abi::{Detokenize, Tokenizable};
 use ethers::contract::abigen;
-use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::prelude::{Signer, SignerMiddleware};
-use ethers::providers::{Authorization, Http, Provider};
-use ethers::signers::{LocalWallet, Wallet};
-use ethers::types::{Bytes, Eip1559TransactionRequest, ValueOrArray, H256, U256};
+use ethers::contract::EthCall;
+use ethers::prelude::SignerMiddleware;
+use ethers::providers::{
+    Authorization, FilterKind, Http, JsonRpcClient, Provider, ProviderError, RpcError,
+};
+use ethers::types::{
+    BlockId, BlockNumber, Bytes, Eip1559TransactionRequest, ValueOrArray, H256, U256,
+};
 
 use super::gas_estimator_middleware::Eip1559GasEstimatorMiddleware;
 use crate::manager::cometbft::{CanonicalVoteData, SignedHeader, Timestamp, ValidatorCertificate};
-use crate::manager::evm::error_parsing::ErrorParserHttp;
+use crate::manager::evm::error_parsing::{decode_revert, ErrorParserHttp};
 use ethers::middleware::Middleware;
+use futures_util::{StreamExt, TryStreamExt};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_actors_abis::subnet_actor_activity_facet::ValidatorClaim;
@@ -55,7 +60,7 @@ use ipc_actors_abis::subnet_actor_checkpointing_facet::{Inclusion, SubnetActorCh
 use ipc_actors_abis::subnet_actor_getter_facet::ListPendingCommitmentsEntry;
 use ipc_api::checkpoint::{
     abi_encode_envelope, abi_encode_envelope_fields, consensus::ValidatorData,
-    VALIDATOR_REWARD_FIELDS,
+    CheckpointSignatures, VALIDATOR_REWARD_FIELDS,
 };
 use ipc_api::cross::IpcEnvelope;
 use ipc_api::merkle::MerkleGen;
@@ -63,11 +68,11 @@ use ipc_api::staking::{PowerChangeRequest, ValidatorInfo, ValidatorStakingInfo};
 use ipc_api::subnet::ConstructParams;
 use ipc_api::subnet_id::SubnetID;
 use ipc_observability::lazy_static;
-use ipc_wallet::{EthKeyAddress, EvmKeyStore, PersistentKeyStore};
+use ipc_wallet::{EthKeyAddress, EthersSigner, LocalSigner, PersistentKeyStore, Signer};
 use num_traits::ToPrimitive;
 
 pub type SignerWithFeeEstimatorMiddleware =
-    Eip1559GasEstimatorMiddleware<SignerMiddleware<Provider<ErrorParserHttp>, Wallet<SigningKey>>>;
+    Eip1559GasEstimatorMiddleware<SignerMiddleware<Provider<ErrorParserHttp>, EthersSigner>>;
 
 /// Default polling time used by the Ethers provider to check for pending
 /// transactions and events. Default is 7, and for our child subnets we
@@ -87,7 +92,97 @@ const SUBNET_MAJORITY_PERCENTAGE: u8 = 67;
 
 pub struct EthSubnetManager {
     keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+    /// Signers used instead of the keystore for the addresses they sign for,
+    /// e.g. remote signers fronting keys held in an HSM.
+    signers: HashMap<ethers::types::Address, Arc<dyn Signer>>,
     ipc_contract_info: IPCContractInfo,
+    pending_watch: tokio::sync::Mutex<PendingSubmissionsWatch>,
+}
+
+/// Follows the checkpoint submissions entering the mempool through a pending transaction filter.
+#[derive(Default)]
+struct PendingSubmissionsWatch {
+    filter: Option<U256>,
+    /// Pending checkpoint submissions and the subnet actor they are sent to.
+    submissions: HashMap<TxHash, ethers::types::Address>,
+}
+
+impl PendingSubmissionsWatch {
+    /// Remember the transaction if it submits a checkpoint.
+    fn observe(&mut self, tx: &ethers::types::Transaction) {
+        let selectors = [
+            subnet_actor_checkpointing_facet::SubmitBottomUpCheckpointCall::selector(),
+            subnet_actor_checkpointing_facet::SubmitBottomUpCheckpointSignaturesCall::selector(),
+        ];
+        if let Some(to) = tx.to {
+            if selectors.iter().any(|s| tx.input.as_ref().starts_with(s)) {
+                self.submissions.insert(tx.hash, to);
+            }
+        }
+    }
+}
+
+/// Get the number of the latest finalized block.
+///
+/// Returns `None` if the node doesn't support the `finalized` block tag, e.g. because it
+/// predates the merge, so that the caller can fall back to the chain head delay.
+async fn get_finalized_block_number<P: JsonRpcClient>(
+    provider: &Provider<P>,
+) -> Result<Option<ChainEpoch>> {
+    match provider.get_block(BlockNumber::Finalized).await {
+        Ok(block) => Ok(block
+            .and_then(|b| b.number)
+            .map(|n| n.as_u64() as ChainEpoch)),
+        Err(e) if is_unsupported_block_tag(&e) => {
+            tracing::debug!(
+                error = e.to_string(),
+                "parent does not support the finalized block tag"
+            );
+            Ok(None)
+        }
+        Err(e) => Err(anyhow!(e).context("cannot get finalized evm block")),
+    }
+}
+
+/// Errors of nodes which don't support the `finalized` block tag, or have no finalized block.
+const UNSUPPORTED_BLOCK_TAG_ERRORS: &[&str] = &[
+    // geth before the merge, parsing the tag as a block number
+    "invalid argument 0: hex string without 0x prefix",
+    // anvil and other nodes deserializing the tag into an enum
+    "unknown variant `finalized`",
+    // hardhat
+    "invalid block tag finalized",
+    // geth on chains without finality
+    "finalized block not found",
+];
+
+/// Check whether the node rejected the `finalized` block tag, as opposed to failing the request.
+fn is_unsupported_block_tag(e: &ProviderError) -> bool {
+    let Some(resp) = e.as_error_response() else {
+        return false;
+    };
+    let message = resp.message.to_lowercase();
+    UNSUPPORTED_BLOCK_TAG_ERRORS
+        .iter()
+        .any(|m| message.contains(m))
+}
+
+/// Maximum number of transactions looked up at the same time.
+const MAX_CONCURRENT_TX_LOOKUPS: usize = 16;
+
+/// Look up transactions by hash concurrently; `None` if the node doesn't know about one.
+async fn get_transactions(
+    provider: &Provider<ErrorParserHttp>,
+    hashes: Vec<TxHash>,
+) -> Result<Vec<(TxHash, Option<ethers::types::Transaction>)>> {
+    futures_util::stream::iter(hashes)
+        .map(|hash| async move {
+            let tx = provider.get_transaction(hash).await?;
+            Ok::<_, anyhow::Error>((hash, tx))
+        })
+        .buffer_unordered(MAX_CONCURRENT_TX_LOOKUPS)
+        .try_collect()
+        .await
 }
 
 /// Keep track of the on chain information for the subnet manager
@@ -137,6 +232,10 @@ impl TopDownFinalityQuery for EthSubnetManager {
         Ok(block.as_u64() as ChainEpoch)
     }
 
+    async fn finalized_chain_head_height(&self) -> Result<Option<ChainEpoch>> {
+        get_finalized_block_number(&self.ipc_contract_info.provider).await
+    }
+
     async fn get_top_down_msgs(
         &self,
         subnet_id: &SubnetID,
@@ -262,42 +361,9 @@ impl TopDownFinalityQuery for EthSubnetManager {
 #[async_trait]
 impl SubnetManager for EthSubnetManager {
     async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
-        self.ensure_same_gateway(&params.ipc_gateway_addr)?;
-
-        let min_validator_stake = params
-            .min_validator_stake
-            .atto()
-            .to_u128()
-            .ok_or_else(|| anyhow!("invalid min validator stake"))?;
-
         tracing::debug!("calling create subnet for EVM manager");
 
-        let route = subnet_id_to_evm_addresses(&params.parent)?;
-        tracing::debug!("root SubnetID as Ethereum type: {route:?}");
-
-        let params = register_subnet_facet::ConstructorParams {
-            parent_id: register_subnet_facet::SubnetID {
-                root: params.parent.root_id(),
-                route,
-            },
-            ipc_gateway_addr: self.ipc_contract_info.gateway_addr,
-            consensus: params.consensus as u64 as u8,
-            min_activation_collateral: ethers::types::U256::from(min_validator_stake),
-            min_validators: params.min_validators,
-            bottom_up_check_period: params.bottomup_check_period as u64,
-            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
-            active_validators_limit: params.active_validators_limit,
-            power_scale: 3,
-            permission_mode: params.permission_mode as u8,
-            supply_source: register_subnet_facet::Asset::try_from(params.supply_source)?,
-            collateral_source: register_subnet_facet::Asset::try_from(params.collateral_source)?,
-            validator_gater: payload_to_evm_address(params.validator_gater.payload())?,
-            validator_rewarder: payload_to_evm_address(params.validator_rewarder.payload())?,
-            genesis_subnet_ipc_contracts_owner: params.genesis_subnet_ipc_contracts_owner,
-            chain_id: params.chain_id,
-            genesis_f3_instance_id: params.genesis_f3_instance_id.unwrap_or(0),
-            has_genesis_f3_instance_id: params.genesis_f3_instance_id.is_some(),
-        };
+        let params = self.subnet_constructor_params(params)?;
 
         tracing::info!("creating subnet on evm with params: {params:?}");
 
@@ -1111,12 +1177,14 @@ impl EthSubnetManager {
     ) -> Self {
         Self {
             keystore,
+            signers: HashMap::new(),
             ipc_contract_info: IPCContractInfo {
                 gateway_addr,
                 registry_addr,
                 chain_id,
                 provider,
             },
+            pending_watch: Default::default(),
         }
     }
 
@@ -1145,6 +1213,61 @@ impl EthSubnetManager {
         Ok(txn)
     }
 
+    /// Convert the subnet construction parameters into the ones of the registry contract.
+    pub(crate) fn subnet_constructor_params(
+        &self,
+        params: ConstructParams,
+    ) -> Result<register_subnet_facet::ConstructorParams> {
+        self.ensure_same_gateway(&params.ipc_gateway_addr)?;
+
+        let min_validator_stake = params
+            .min_validator_stake
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid min validator stake"))?;
+
+        let route = subnet_id_to_evm_addresses(&params.parent)?;
+        tracing::debug!("root SubnetID as Ethereum type: {route:?}");
+
+        let params = register_subnet_facet::ConstructorParams {
+            parent_id: register_subnet_facet::SubnetID {
+                root: params.parent.root_id(),
+                route,
+            },
+            ipc_gateway_addr: self.ipc_contract_info.gateway_addr,
+            consensus: params.consensus as u64 as u8,
+            min_activation_collateral: ethers::types::U256::from(min_validator_stake),
+            min_validators: params.min_validators,
+            bottom_up_check_period: params.bottomup_check_period as u64,
+            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
+            active_validators_limit: params.active_validators_limit,
+            power_scale: 3,
+            permission_mode: params.permission_mode as u8,
+            supply_source: register_subnet_facet::Asset::try_from(params.supply_source)?,
+            collateral_source: register_subnet_facet::Asset::try_from(params.collateral_source)?,
+            validator_gater: payload_to_evm_address(params.validator_gater.payload())?,
+            validator_rewarder: payload_to_evm_address(params.validator_rewarder.payload())?,
+            genesis_subnet_ipc_contracts_owner: params.genesis_subnet_ipc_contracts_owner,
+            chain_id: params.chain_id,
+            genesis_f3_instance_id: params.genesis_f3_instance_id.unwrap_or(0),
+            has_genesis_f3_instance_id: params.genesis_f3_instance_id.is_some(),
+        };
+        Ok(params)
+    }
+
+    /// The provider used for read-only calls.
+    pub(crate) fn provider(&self) -> &Provider<ErrorParserHttp> {
+        &self.ipc_contract_info.provider
+    }
+
+    pub(crate) fn gateway_addr(&self) -> ethers::types::Address {
+        self.ipc_contract_info.gateway_addr
+    }
+
+    pub(crate) fn registry_addr(&self) -> ethers::types::Address {
+        self.ipc_contract_info.registry_addr
+    }
+
     pub fn ensure_same_gateway(&self, gateway: &Address) -> Result<()> {
         let evm_gateway_addr = payload_to_evm_address(gateway.payload())?;
         if evm_gateway_addr != self.ipc_contract_info.gateway_addr {
@@ -1160,6 +1283,27 @@ impl EthSubnetManager {
             .ok_or(anyhow!("no evm keystore available"))
     }
 
+    /// Use the signer for transactions sent from its address, instead of looking up
+    /// the private key in the keystore.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signers.insert(signer.address(), signer);
+        self
+    }
+
+    /// Get the signer for an address, preferring explicitly configured signers
+    /// over the keys in the keystore.
+    pub fn signer(&self, addr: &Address) -> Result<Arc<dyn Signer>> {
+        // convert to its underlying eth address
+        let addr = payload_to_evm_address(addr.payload())?;
+        if let Some(signer) = self.signers.get(&addr) {
+            return Ok(signer.clone());
+        }
+        let keystore = self.keystore()?;
+        let keystore = keystore.read().unwrap();
+        let signer = LocalSigner::from_keystore(&*keystore, &addr.into())?;
+        Ok(Arc::new(signer))
+    }
+
     /// Get the ethers singer instance.
     /// We use filecoin addresses throughout our whole code-base
     /// and translate them to evm addresses when relevant.
@@ -1167,19 +1311,8 @@ impl EthSubnetManager {
         &self,
         addr: &Address,
     ) -> Result<SignerWithFeeEstimatorMiddleware> {
-        // convert to its underlying eth address
-        let addr = payload_to_evm_address(addr.payload())?;
-        let keystore = self.keystore()?;
-        let keystore = keystore.read().unwrap();
-        let private_key = keystore
-            .get(&addr.into())?
-            .ok_or_else(|| anyhow!("address {addr:} does not have private key in key store"))?;
-        let wallet = LocalWallet::from_bytes(private_key.private_key())?
-            .with_chain_id(self.ipc_contract_info.chain_id);
-
-        use super::gas_estimator_middleware::Eip1559GasEstimatorMiddleware;
-
-        let signer = SignerMiddleware::new(self.ipc_contract_info.provider.clone(), wallet);
+        let signer = EthersSigner::new(self.signer(addr)?, self.ipc_contract_info.chain_id);
+        let signer = SignerMiddleware::new(self.ipc_contract_info.provider.clone(), signer);
         Ok(Eip1559GasEstimatorMiddleware::new(signer))
     }
 
@@ -1190,8 +1323,6 @@ impl EthSubnetManager {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
 
-        let SubnetConfig::Fevm(config) = &subnet.config;
-
         let mut client = Client::builder();
 
         if let Some(auth_token) = auth_token.as_deref() {
@@ -1219,8 +1350,8 @@ impl EthSubnetManager {
         // TODO: We may want to make it dynamic so it adjusts depending on the type of network
         // so we don't have a too slow or too fast polling for the underlying block times.
         provider.set_interval(ETH_PROVIDER_POLLING_TIME);
-        let gateway_address = payload_to_evm_address(config.gateway_addr.payload())?;
-        let registry_address = payload_to_evm_address(config.registry_addr.payload())?;
+        let gateway_address = payload_to_evm_address(subnet.gateway_addr().payload())?;
+        let registry_address = payload_to_evm_address(subnet.registry_addr().payload())?;
 
         let chain_id = tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async { provider.get_chainid().await })
@@ -1275,6 +1406,7 @@ impl SignedHeaderRelayer for EthSubnetManager {
         );
         let call = contract.submit_bottom_up_checkpoint(Bytes::from(bytes));
         let call = extend_call_with_pending_block(call).await?;
+        dry_run(&call).await?;
 
         let pending_tx = call.send().await?;
         tracing::info!(
@@ -1282,9 +1414,61 @@ impl SignedHeaderRelayer for EthSubnetManager {
             "sent submit bottom up checkpoint with txn"
         );
 
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        Ok(pending_tx.tx_hash())
+    }
 
-        block_number_from_receipt(receipt)
+    async fn get_checkpoint_signatures(&self, height: u64) -> Result<Vec<CheckpointSignatures>> {
+        let signatures = self
+            .ipc_contract_info
+            .provider
+            .request::<_, Vec<CheckpointSignatures>>(
+                "eth_getCheckpointSignatures",
+                [height.to_string()],
+            )
+            .await?;
+        Ok(signatures)
+    }
+
+    async fn submit_checkpoint_signatures(
+        &self,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        signatures: CheckpointSignatures,
+    ) -> Result<TxHash> {
+        let address = contract_address_from_subnet(subnet_id)?;
+
+        let app_hash: [u8; 32] = signatures
+            .app_hash
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("app hash must be 32 bytes"))?;
+        let (signatories, sigs): (Vec<_>, Vec<_>) = signatures
+            .signatures
+            .into_iter()
+            .map(|s| (s.validator, Bytes::from(s.signature)))
+            .unzip();
+
+        let signer = Arc::new(self.get_signer_with_fee_estimator(submitter)?);
+        let contract = subnet_actor_checkpointing_facet::SubnetActorCheckpointingFacet::new(
+            address,
+            signer.clone(),
+        );
+        let call = contract.submit_bottom_up_checkpoint_signatures(
+            signatures.height,
+            app_hash,
+            signatories,
+            sigs,
+        );
+        let call = extend_call_with_pending_block(call).await?;
+        dry_run(&call).await?;
+
+        let pending_tx = call.send().await?;
+        tracing::info!(
+            hash = hex::encode(pending_tx.tx_hash().as_bytes()),
+            "sent submit bottom up checkpoint signatures with txn"
+        );
+
+        Ok(pending_tx.tx_hash())
     }
 
     async fn query_app_hash_breakdown(
@@ -1358,16 +1542,15 @@ impl SignedHeaderRelayer for EthSubnetManager {
         let t = subnet_actor_checkpointing_facet::AppHashBreakdown::from_tokens(tokens)?;
         let call = contract.record_app_hash_breakdown(height as u64, evm_subnet_id, t);
         let call = extend_call_with_pending_block(call).await?;
+        dry_run(&call).await?;
 
         let pending_tx = call.send().await?;
         tracing::info!(
             hash = hex::encode(pending_tx.tx_hash().as_bytes()),
-            "sent confirm validator changet with txn"
+            "sent record app hash breakdown with txn"
         );
 
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-
-        block_number_from_receipt(receipt)
+        Ok(pending_tx.tx_hash())
     }
 
     async fn submission_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
@@ -1390,6 +1573,23 @@ impl SignedHeaderRelayer for EthSubnetManager {
         Ok(epoch as ChainEpoch)
     }
 
+    async fn get_membership_at(&self, height: ChainEpoch) -> Result<Vec<Address>> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let membership = contract
+            .get_current_membership()
+            .block(height as u64)
+            .call()
+            .await?;
+        membership
+            .validators
+            .iter()
+            .map(|v| ethers_address_to_fil_address(&v.addr))
+            .collect()
+    }
+
     async fn list_active_validators(
         &self,
         subnet: &SubnetID,
@@ -1496,6 +1696,7 @@ impl SignedHeaderRelayer for EthSubnetManager {
                 "execute bottom up batch raw call data"
             );
         }
+        dry_run(&call).await?;
 
         let pending_tx = call.send().await?;
         tracing::info!(
@@ -1503,9 +1704,179 @@ impl SignedHeaderRelayer for EthSubnetManager {
             "sent execute bottom up batch with txn"
         );
 
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        Ok(pending_tx.tx_hash())
+    }
 
-        block_number_from_receipt(receipt)
+    async fn transaction_status(&self, tx_hash: &TxHash) -> Result<TxStatus> {
+        let provider = &self.ipc_contract_info.provider;
+
+        if let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? {
+            let height = receipt
+                .block_number
+                .ok_or_else(|| anyhow!("receipt of {tx_hash:?} has no block number"))?;
+            return Ok(TxStatus::Included {
+                height: height.as_u64() as ChainEpoch,
+                success: receipt.status == Some(1u64.into()),
+            });
+        }
+
+        match provider.get_transaction(*tx_hash).await? {
+            Some(_) => Ok(TxStatus::Pending),
+            None => Ok(TxStatus::Dropped),
+        }
+    }
+
+    async fn revert_reason(&self, tx_hash: &TxHash) -> Result<Option<ContractRevert>> {
+        let provider = &self.ipc_contract_info.provider;
+
+        let Some(tx) = provider.get_transaction(*tx_hash).await? else {
+            return Ok(None);
+        };
+        let Some(height) = tx.block_number else {
+            return Ok(None);
+        };
+
+        let mut call = Eip1559TransactionRequest::new()
+            .from(tx.from)
+            .data(tx.input)
+            .value(tx.value)
+            .gas(tx.gas);
+        if let Some(to) = tx.to {
+            call = call.to(to);
+        }
+
+        // Replay on top of the previous block, without the transactions before it in the same block.
+        // If one of those made it revert, e.g. another relayer submitting the same checkpoint first,
+        // the replay succeeds and the reason stays unknown.
+        let block = BlockId::Number(BlockNumber::Number(height.saturating_sub(1.into())));
+        match provider.call(&call.into(), Some(block)).await {
+            Ok(_) => Ok(None),
+            Err(e) => Ok(e
+                .as_error_response()
+                .and_then(|e| e.as_revert_data())
+                .map(|data| decode_revert(&data))),
+        }
+    }
+
+    async fn pending_signed_header_submissions(&self, subnet_id: &SubnetID) -> Result<Vec<TxHash>> {
+        let address = contract_address_from_subnet(subnet_id)?;
+        let provider = &self.ipc_contract_info.provider;
+
+        let mut watch = self.pending_watch.lock().await;
+
+        let new_hashes = match watch.filter {
+            Some(id) => match provider.get_filter_changes::<_, TxHash>(id).await {
+                Ok(hashes) => hashes,
+                Err(e) => {
+                    // filters expire if not polled for a while
+                    tracing::debug!("pending transaction filter lost, reinstalling: {e}");
+                    watch.filter = None;
+                    vec![]
+                }
+            },
+            None => vec![],
+        };
+        if watch.filter.is_none() {
+            watch.filter = Some(provider.new_filter(FilterKind::PendingTransactions).await?);
+
+            // The filter only reports transactions arriving from now on, so pick up the ones
+            // already waiting from the pending block, on nodes which build one.
+            match provider.get_block_with_txs(BlockNumber::Pending).await {
+                Ok(Some(block)) => {
+                    for tx in block.transactions {
+                        watch.observe(&tx);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::debug!("cannot get the pending block: {e}"),
+            }
+        }
+
+        let new_txs = get_transactions(provider, new_hashes).await?;
+        for tx in new_txs.into_iter().filter_map(|(_, tx)| tx) {
+            watch.observe(&tx);
+        }
+
+        // forget the submissions which have been included or dropped since
+        let known = watch.submissions.keys().cloned().collect();
+        let mut pending = vec![];
+        for (hash, tx) in get_transactions(provider, known).await? {
+            match tx {
+                Some(tx) if tx.block_number.is_none() => {
+                    if watch.submissions.get(&hash) == Some(&address) {
+                        pending.push(hash);
+                    }
+                }
+                _ => {
+                    watch.submissions.remove(&hash);
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+
+    async fn bump_transaction(
+        &self,
+        submitter: &Address,
+        tx_hash: &TxHash,
+        bump_percent: u64,
+    ) -> Result<TxHash> {
+        let tx = self
+            .ipc_contract_info
+            .provider
+            .get_transaction(*tx_hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {tx_hash:?} not found"))?;
+
+        if tx.block_number.is_some() {
+            return Err(anyhow!("transaction {tx_hash:?} is already included"));
+        }
+        if tx.from != payload_to_evm_address(submitter.payload())? {
+            return Err(anyhow!(
+                "transaction {tx_hash:?} was not sent by submitter {submitter}"
+            ));
+        }
+
+        let signer = self.get_signer_with_fee_estimator(submitter)?;
+
+        // Bump the original fees, but never go below what the network currently asks for.
+        let estimate = signer
+            .estimate_gas_fees()
+            .await
+            .map_err(|e| anyhow!("cannot estimate gas fees: {e}"))?;
+        let bump = |fee: U256| fee + fee * bump_percent / 100;
+        let max_priority_fee_per_gas = bump(tx.max_priority_fee_per_gas.unwrap_or_default())
+            .max(estimate.max_priority_fee_per_gas);
+        let max_fee_per_gas = bump(tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default())
+            .max(estimate.max_fee_per_gas)
+            .max(max_priority_fee_per_gas);
+
+        let mut replacement = Eip1559TransactionRequest::new()
+            .from(tx.from)
+            .data(tx.input)
+            .value(tx.value)
+            .gas(tx.gas)
+            .nonce(tx.nonce)
+            .access_list(tx.access_list.unwrap_or_default())
+            .max_priority_fee_per_gas(max_priority_fee_per_gas)
+            .max_fee_per_gas(max_fee_per_gas)
+            .chain_id(self.ipc_contract_info.chain_id);
+        if let Some(to) = tx.to {
+            replacement = replacement.to(to);
+        }
+
+        let pending_tx = signer
+            .send_transaction(replacement, None)
+            .await
+            .map_err(|e| anyhow!("cannot send replacement of {tx_hash:?}: {e}"))?;
+        tracing::info!(
+            replaced = hex::encode(tx_hash.as_bytes()),
+            hash = hex::encode(pending_tx.tx_hash().as_bytes()),
+            "sent replacement txn with bumped gas fees"
+        );
+
+        Ok(pending_tx.tx_hash())
     }
 }
 
@@ -1712,6 +2083,27 @@ where
     Ok(call.block(ethers::types::BlockNumber::Pending))
 }
 
+/// Simulate the call with `eth_call`, so that a transaction which would revert is
+/// detected before paying for it.
+async fn dry_run<B, M, D>(call: &ethers_contract::FunctionCall<B, M, D>) -> Result<()>
+where
+    B: std::borrow::Borrow<M>,
+    M: Middleware,
+    D: Detokenize,
+{
+    match call.call().await {
+        Ok(_) => Ok(()),
+        Err(e) => match e.as_revert() {
+            Some(data) => {
+                let revert = decode_revert(data);
+                tracing::warn!("dry run of the call reverted: {revert}");
+                Err(revert.into())
+            }
+            None => Err(anyhow!("failed to simulate the call: {e}")),
+        },
+    }
+}
+
 /// Get the block number from the transaction receipt
 fn block_number_from_receipt(
     receipt: Option<ethers::types::TransactionReceipt>,
@@ -1729,7 +2121,7 @@ fn block_number_from_receipt(
     }
 }
 
-fn is_valid_bootstrap_addr(input: &str) -> Option<(String, IpAddr, u16)> {
+pub(crate) fn is_valid_bootstrap_addr(input: &str) -> Option<(String, IpAddr, u16)> {
     let parts: Vec<&str> = input.split('@').collect();
 
     if parts.len() == 2 {
@@ -1848,9 +2240,11 @@ fn generate_bottom_up_checkpoint_bytes(
 
 #[cfg(test)]
 mod tests {
-    use crate::manager::evm::manager::contract_address_from_subnet;
+    use crate::manager::evm::manager::{contract_address_from_subnet, get_finalized_block_number};
     use ethers::core::rand::prelude::SliceRandom;
     use ethers::core::rand::{random, thread_rng};
+    use ethers::providers::{JsonRpcError, MockResponse, Provider};
+    use ethers::types::{Block, TxHash, U64};
     use fvm_shared::address::Address;
     use ipc_actors_abis::checkpointing_facet::{checkpointing_facet, ValidatorData};
     use ipc_api::checkpoint::VALIDATOR_REWARD_FIELDS;
@@ -1972,4 +2366,59 @@ mod tests {
         .root();
         assert_eq!(new_root, root);
     }
+
+    #[tokio::test]
+    async fn test_finalized_block_number() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> {
+            number: Some(U64::from(10u64)),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            get_finalized_block_number(&provider).await.unwrap(),
+            Some(10)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalized_block_tag_unsupported() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32602,
+            message: "invalid argument 0: hex string without 0x prefix".into(),
+            data: None,
+        }));
+        assert_eq!(get_finalized_block_number(&provider).await.unwrap(), None);
+
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32602,
+            message: "unknown variant `finalized`, expected one of `latest`, `earliest`, `pending`"
+                .into(),
+            data: None,
+        }));
+        assert_eq!(get_finalized_block_number(&provider).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_finalized_block_other_errors() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "upstream unavailable".into(),
+            data: None,
+        }));
+        assert!(get_finalized_block_number(&provider).await.is_err());
+
+        // invalid params, but not because of the tag
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32602,
+            message: "invalid argument 1: json: cannot unmarshal string into Go value of type bool"
+                .into(),
+            data: None,
+        }));
+        assert!(get_finalized_block_number(&provider).await.is_err());
+    }
 }
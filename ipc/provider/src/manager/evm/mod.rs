@@ -10,7 +10,11 @@ use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
 
 use super::subnet::SubnetManager;
+pub(crate) use error_parsing::{decode_revert, ErrorParserHttp};
 pub use manager::EthSubnetManager;
+pub(crate) use manager::{
+    contract_address_from_subnet, fil_amount_to_eth_amount, is_valid_bootstrap_addr, IERC20,
+};
 
 #[async_trait]
 pub trait EthManager: SubnetManager {
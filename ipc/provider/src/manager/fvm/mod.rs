@@ -0,0 +1,915 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Subnet manager for Filecoin parents reached through the native Lotus JSON-RPC API.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use ethers::abi::{Detokenize, ParamType, Token};
+use ethers::middleware::Middleware;
+use ethers::providers::Provider;
+use fvm_ipld_encoding::{BytesDe, BytesSer, RawBytes};
+use fvm_shared::address::{Address, Protocol};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::{MethodNum, METHOD_SEND};
+use ipc_actors_abis::checkpointing_facet::AppHashBreakdown;
+use ipc_actors_abis::subnet_actor_activity_facet::ValidatorClaim;
+use ipc_actors_abis::subnet_actor_checkpointing_facet::Inclusion;
+use ipc_actors_abis::subnet_actor_getter_facet::ListPendingCommitmentsEntry;
+use ipc_actors_abis::{
+    gateway_manager_facet, register_subnet_facet, subnet_actor_activity_facet,
+    subnet_actor_manager_facet, subnet_actor_reward_facet,
+};
+use ipc_api::checkpoint::consensus::ValidatorData;
+use ipc_api::checkpoint::CheckpointSignatures;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::staking::{PowerChangeRequest, ValidatorInfo};
+use ipc_api::subnet::{Asset, AssetKind, ConstructParams};
+use ipc_api::subnet_id::SubnetID;
+use ipc_api::{eth_to_fil_amount, ethers_address_to_fil_address};
+use ipc_wallet::{EthKeyAddress, LocalSigner, PersistentKeyStore, Signer, Wallet};
+use num_traits::ToPrimitive;
+
+use crate::config::Subnet;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::message::ipc::SubnetInfo;
+use crate::lotus::message::mpool::MpoolPushMessage;
+use crate::lotus::message::state::StateWaitMsgResponse;
+use crate::lotus::LotusClient;
+use crate::manager::cometbft::{SignedHeader, ValidatorCertificate};
+use crate::manager::evm::{
+    contract_address_from_subnet, decode_revert, fil_amount_to_eth_amount, is_valid_bootstrap_addr,
+    ErrorParserHttp, IERC20,
+};
+use crate::manager::subnet::{
+    GetBlockHashResult, SubnetGenesisInfo, TopDownFinalityQuery, TopDownQueryPayload,
+    ValidatorRewarder,
+};
+use crate::manager::{
+    ContractRevert, EthSubnetManager, SignedHeaderRelayer, SubnetManager, TxHash, TxStatus,
+    NULL_ROUND_ERR_MSG,
+};
+
+/// The `InvokeEVM` method of the EVM actor, see FRC-0042.
+const METHOD_INVOKE_CONTRACT: MethodNum = 3844450837;
+/// The exit code of messages reverted by an EVM contract.
+const EVM_CONTRACT_REVERTED: u32 = 33;
+
+/// The delegated (f410) address of an uncompressed secp256k1 public key.
+fn delegated_address(public_key: &[u8]) -> Result<Address> {
+    if public_key.len() != 65 {
+        return Err(anyhow!(
+            "expected an uncompressed 65 byte secp256k1 public key"
+        ));
+    }
+    let hash = ethers::utils::keccak256(&public_key[1..]);
+    ethers_address_to_fil_address(&ethers::types::Address::from_slice(&hash[12..]))
+}
+
+/// Fail early if the sender of a validator operation can't own the validator key: validator keys
+/// are secp256k1, so it has to be an `f1` or `f410` address, of the public key if one is given.
+fn require_validator_sender(from: &Address, public_key: Option<&[u8]>) -> Result<()> {
+    let owner = match (from.protocol(), public_key) {
+        (Protocol::Delegated | Protocol::Secp256k1, None) => return Ok(()),
+        (Protocol::Delegated, Some(pk)) => delegated_address(pk)?,
+        (Protocol::Secp256k1, Some(pk)) => Address::new_secp256k1(pk)?,
+        _ => {
+            return Err(anyhow!(
+                "validator keys are secp256k1, so validator operations must be sent \
+                 from an f1 or f410 address, not {from}"
+            ))
+        }
+    };
+    if owner != *from {
+        return Err(anyhow!(
+            "the validator public key does not belong to {from}"
+        ));
+    }
+    Ok(())
+}
+
+/// A subnet manager which sends native Filecoin messages through the Lotus JSON-RPC API,
+/// so that `f1` and `f3` wallets can manage subnets deployed on a Filecoin parent.
+///
+/// The IPC contracts are invoked through the EVM actor with Lotus estimating the gas,
+/// while read-only calls go to the Ethereum JSON-RPC API the same Lotus node exposes.
+/// Validator operations, like `join_subnet`, have to come from the delegated `f410` address
+/// of the validator key, because the contracts check the caller against the public key and
+/// would see an `f1` sender through its masked ID address. So an `f1` validator, whose key
+/// has to be in the FVM wallet, sends them as Ethereum transactions from the `f410` address
+/// of the same key instead. `f3` senders are rejected, validator keys being secp256k1.
+///
+/// Checkpoint relaying and the other operations returning Ethereum transaction hashes
+/// are sent with the keys in the EVM keystore.
+pub struct LotusSubnetManager<T: JsonRpcClient = JsonRpcClientImpl> {
+    lotus: LotusJsonRPCClient<T>,
+    eth: EthSubnetManager,
+    /// The `f410` addresses of the `f1` keys in the FVM wallet, signing through `eth`.
+    delegated: HashMap<Address, Address>,
+}
+
+impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
+    pub fn new(lotus: LotusJsonRPCClient<T>, eth: EthSubnetManager) -> Self {
+        Self {
+            lotus,
+            eth,
+            delegated: HashMap::new(),
+        }
+    }
+
+    /// Send validator operations of the `f1` addresses in the wallet from the `f410` address
+    /// of the same key.
+    pub fn with_validator_keys(mut self, wallet: &mut Wallet) -> Result<Self> {
+        for addr in wallet.list_addrs()? {
+            if addr.protocol() != Protocol::Secp256k1 {
+                continue;
+            }
+            let info = wallet.export(&addr)?;
+            let signer = LocalSigner::from_fvm_key_info(&info)?;
+            let delegated = ethers_address_to_fil_address(&signer.address())?;
+            self.eth = self.eth.with_signer(Arc::new(signer));
+            self.delegated.insert(addr, delegated);
+        }
+        Ok(self)
+    }
+
+    /// The `f410` address sending the validator operations of an `f1` address.
+    fn delegated_sender(&self, from: &Address) -> Result<Address> {
+        self.delegated.get(from).copied().ok_or_else(|| {
+            anyhow!("the key of {from} has to be in the FVM wallet to send validator operations")
+        })
+    }
+
+    /// Build the native message invoking the contract call from `from`.
+    fn invoke_message<B, M, D>(
+        &self,
+        from: &Address,
+        call: &ethers_contract::FunctionCall<B, M, D>,
+    ) -> Result<MpoolPushMessage>
+    where
+        B: Borrow<M>,
+        M: Middleware,
+        D: Detokenize,
+    {
+        let to = call
+            .tx
+            .to_addr()
+            .ok_or_else(|| anyhow!("contract call has no target"))?;
+        let calldata = call.tx.data().map(|d| d.to_vec()).unwrap_or_default();
+        let params = if calldata.is_empty() {
+            vec![]
+        } else {
+            RawBytes::serialize(BytesSer(&calldata))?.to_vec()
+        };
+
+        let mut msg = MpoolPushMessage::new(
+            ethers_address_to_fil_address(to)?,
+            *from,
+            METHOD_INVOKE_CONTRACT,
+            params,
+        );
+        if let Some(value) = call.tx.value() {
+            msg.value = eth_to_fil_amount(value)?;
+        }
+        Ok(msg)
+    }
+
+    /// Sign and push the message, then wait for its execution, decoding the revert
+    /// reason if the contract reverted.
+    async fn push(&self, msg: MpoolPushMessage) -> Result<StateWaitMsgResponse> {
+        let (from, to, method) = (msg.from, msg.to, msg.method);
+
+        let cid = self.lotus.mpool_push(msg).await?;
+        tracing::info!("pushed message {cid} from {from} to {to} calling method {method}");
+
+        let r = self.lotus.state_wait_msg(cid).await?;
+        let exit_code = r.receipt.exit_code();
+        if exit_code == 0 {
+            return Ok(r);
+        }
+
+        let ret = r.receipt.return_bytes()?;
+        if exit_code == EVM_CONTRACT_REVERTED && !ret.is_empty() {
+            let BytesDe(data) = fvm_ipld_encoding::from_slice(&ret)?;
+            let revert: ContractRevert = decode_revert(&data);
+            return Err(anyhow::Error::new(revert)
+                .context(format!("message {cid} failed with exit code {exit_code}")));
+        }
+        Err(anyhow!("message {cid} failed with exit code {exit_code}"))
+    }
+
+    /// Invoke the contract call with a native message from `from`.
+    async fn invoke<B, M, D>(
+        &self,
+        from: &Address,
+        call: ethers_contract::FunctionCall<B, M, D>,
+    ) -> Result<StateWaitMsgResponse>
+    where
+        B: Borrow<M>,
+        M: Middleware,
+        D: Detokenize,
+    {
+        let msg = self.invoke_message(from, &call)?;
+        self.push(msg).await
+    }
+
+    /// Contracts are only used to encode calls, so they are bound to the read-only provider.
+    fn client(&self) -> Arc<Provider<ErrorParserHttp>> {
+        Arc::new(self.eth.provider().clone())
+    }
+}
+
+impl LotusSubnetManager<JsonRpcClientImpl> {
+    pub fn from_subnet_with_wallet_store(
+        subnet: &Subnet,
+        fvm_wallet: Option<Arc<RwLock<Wallet>>>,
+        evm_keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+    ) -> Result<Self> {
+        let eth = EthSubnetManager::from_subnet_with_wallet_store(subnet, evm_keystore)?;
+        match fvm_wallet {
+            Some(wallet) => {
+                let lotus =
+                    LotusJsonRPCClient::from_subnet_with_wallet_store(subnet, wallet.clone());
+                let mut wallet = wallet.write().unwrap();
+                Self::new(lotus, eth).with_validator_keys(&mut wallet)
+            }
+            None => Ok(Self::new(LotusJsonRPCClient::from_subnet(subnet), eth)),
+        }
+    }
+
+    /// Use the signer for the Ethereum transactions sent from its address.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.eth = self.eth.with_signer(signer);
+        self
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> TopDownFinalityQuery for LotusSubnetManager<T> {
+    async fn genesis_epoch(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        self.eth.genesis_epoch(subnet_id).await
+    }
+
+    async fn chain_head_height(&self) -> Result<ChainEpoch> {
+        self.lotus.current_epoch().await
+    }
+
+    async fn finalized_chain_head_height(&self) -> Result<Option<ChainEpoch>> {
+        self.eth.finalized_chain_head_height().await
+    }
+
+    async fn get_top_down_msgs(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<IpcEnvelope>>> {
+        self.eth.get_top_down_msgs(subnet_id, epoch).await
+    }
+
+    async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult> {
+        let tipset = self.lotus.get_tipset_by_height(height, &[]).await?;
+        if tipset.height != height {
+            tracing::debug!(
+                height,
+                closest = tipset.height,
+                "no tipset at height, null round"
+            );
+            return Err(anyhow!(NULL_ROUND_ERR_MSG));
+        }
+
+        Ok(GetBlockHashResult {
+            parent_block_hash: tipset.parent_eth_hash()?,
+            block_hash: tipset.eth_hash()?,
+        })
+    }
+
+    async fn get_validator_changeset(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<PowerChangeRequest>>> {
+        self.eth.get_validator_changeset(subnet_id, epoch).await
+    }
+
+    async fn latest_parent_finality(&self) -> Result<ChainEpoch> {
+        self.eth.latest_parent_finality().await
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
+    async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
+        let params = self.eth.subnet_constructor_params(params)?;
+        tracing::info!("creating subnet on fvm with params: {params:?}");
+
+        let registry = register_subnet_facet::RegisterSubnetFacet::new(
+            self.eth.registry_addr(),
+            self.client(),
+        );
+        let r = self
+            .invoke(&from, registry.new_subnet_actor(params))
+            .await?;
+
+        let BytesDe(ret) = fvm_ipld_encoding::from_slice(&r.receipt.return_bytes()?)?;
+        match ethers::abi::decode(&[ParamType::Address], &ret)?.pop() {
+            Some(Token::Address(subnet_addr)) => {
+                tracing::debug!("subnet deployed at {subnet_addr:?}");
+                ethers_address_to_fil_address(&subnet_addr)
+            }
+            _ => Err(anyhow!("unexpected return from subnet creation")),
+        }
+    }
+
+    async fn join_subnet(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+        pub_key: Vec<u8>,
+    ) -> Result<ChainEpoch> {
+        require_validator_sender(&from, Some(&pub_key))?;
+        if from.protocol() == Protocol::Secp256k1 {
+            let from = self.delegated_sender(&from)?;
+            return self
+                .eth
+                .join_subnet(subnet, from, collateral, pub_key)
+                .await;
+        }
+
+        let collateral = collateral
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid min validator stake"))?;
+
+        let address = contract_address_from_subnet(&subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        let txn = contract.join(ethers::types::Bytes::from(pub_key), collateral.into());
+        let txn = self
+            .eth
+            .handle_txn_token(&subnet, txn, collateral, 0)
+            .await?;
+
+        let r = self.invoke(&from, txn).await?;
+        Ok(r.height as ChainEpoch)
+    }
+
+    async fn approve_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        let contract =
+            gateway_manager_facet::GatewayManagerFacet::new(self.eth.gateway_addr(), self.client());
+        let subnet_address = contract_address_from_subnet(&subnet)?;
+
+        self.invoke(&from, contract.approve_subnet(subnet_address))
+            .await?;
+        Ok(())
+    }
+
+    async fn reject_approved_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        let contract =
+            gateway_manager_facet::GatewayManagerFacet::new(self.eth.gateway_addr(), self.client());
+        let subnet_address = contract_address_from_subnet(&subnet)?;
+
+        self.invoke(&from, contract.reject_approved_subnet(subnet_address))
+            .await?;
+        Ok(())
+    }
+
+    async fn pre_fund(&self, subnet: SubnetID, from: Address, balance: TokenAmount) -> Result<()> {
+        let balance = balance
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid initial balance"))?;
+
+        let address = contract_address_from_subnet(&subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        let txn = contract.pre_fund(balance.into());
+        let txn = self.eth.handle_txn_token(&subnet, txn, 0, balance).await?;
+
+        self.invoke(&from, txn).await?;
+        Ok(())
+    }
+
+    async fn pre_release(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<()> {
+        let amount = amount
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid pre-release amount"))?;
+
+        let address = contract_address_from_subnet(&subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        self.invoke(&from, contract.pre_release(amount.into()))
+            .await?;
+        Ok(())
+    }
+
+    async fn stake(&self, subnet: SubnetID, from: Address, collateral: TokenAmount) -> Result<()> {
+        require_validator_sender(&from, None)?;
+        if from.protocol() == Protocol::Secp256k1 {
+            let from = self.delegated_sender(&from)?;
+            return self.eth.stake(subnet, from, collateral).await;
+        }
+
+        let collateral = collateral
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid collateral amount"))?;
+
+        let address = contract_address_from_subnet(&subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        let txn = contract.stake(collateral.into());
+        let txn = self
+            .eth
+            .handle_txn_token(&subnet, txn, collateral, 0)
+            .await?;
+
+        self.invoke(&from, txn).await?;
+        Ok(())
+    }
+
+    async fn unstake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<()> {
+        require_validator_sender(&from, None)?;
+        if from.protocol() == Protocol::Secp256k1 {
+            let from = self.delegated_sender(&from)?;
+            return self.eth.unstake(subnet, from, collateral).await;
+        }
+
+        let collateral = collateral
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid collateral amount"))?;
+
+        let address = contract_address_from_subnet(&subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        self.invoke(&from, contract.unstake(collateral.into()))
+            .await?;
+        Ok(())
+    }
+
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        require_validator_sender(&from, None)?;
+        if from.protocol() == Protocol::Secp256k1 {
+            let from = self.delegated_sender(&from)?;
+            return self.eth.leave_subnet(subnet, from).await;
+        }
+
+        let address = contract_address_from_subnet(&subnet)?;
+        tracing::info!("leaving fvm subnet: {subnet:} at contract: {address:}");
+
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+        self.invoke(&from, contract.leave()).await?;
+        Ok(())
+    }
+
+    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        let address = contract_address_from_subnet(&subnet)?;
+        tracing::info!("kill fvm subnet: {subnet:} at contract: {address:}");
+
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+        self.invoke(&from, contract.kill()).await?;
+        Ok(())
+    }
+
+    async fn list_child_subnets(
+        &self,
+        gateway_addr: Address,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
+        self.eth.list_child_subnets(gateway_addr).await
+    }
+
+    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        require_validator_sender(&from, None)?;
+        if from.protocol() == Protocol::Secp256k1 {
+            let from = self.delegated_sender(&from)?;
+            return self.eth.claim_collateral(subnet, from).await;
+        }
+
+        let address = contract_address_from_subnet(&subnet)?;
+        tracing::info!("claim collateral fvm subnet: {subnet:} at contract: {address:}");
+
+        let contract =
+            subnet_actor_reward_facet::SubnetActorRewardFacet::new(address, self.client());
+        self.invoke(&from, contract.claim()).await?;
+        Ok(())
+    }
+
+    async fn fund(
+        &self,
+        subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        self.eth.ensure_same_gateway(&gateway_addr)?;
+
+        let value = amount
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid value to fund"))?;
+
+        let contract =
+            gateway_manager_facet::GatewayManagerFacet::new(self.eth.gateway_addr(), self.client());
+        let mut txn = contract.fund(
+            gateway_manager_facet::SubnetID::try_from(&subnet)?,
+            gateway_manager_facet::FvmAddress::try_from(to)?,
+        );
+        txn.tx.set_value(value);
+
+        let r = self.invoke(&from, txn).await?;
+        Ok(r.height as ChainEpoch)
+    }
+
+    async fn fund_with_token(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        let contract =
+            gateway_manager_facet::GatewayManagerFacet::new(self.eth.gateway_addr(), self.client());
+        let txn = contract.fund_with_token(
+            gateway_manager_facet::SubnetID::try_from(&subnet)?,
+            gateway_manager_facet::FvmAddress::try_from(to)?,
+            fil_amount_to_eth_amount(&amount)?,
+        );
+
+        let r = self.invoke(&from, txn).await?;
+        Ok(r.height as ChainEpoch)
+    }
+
+    async fn approve_token(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        let supply_source = self.eth.get_subnet_supply_source(&subnet).await?;
+        if supply_source.kind != AssetKind::ERC20 {
+            return Err(anyhow!("Invalid operation: Expected the subnet's supply source to be ERC20, but found a different kind."));
+        }
+
+        let token_address = payload_to_evm_address(
+            supply_source
+                .token_address
+                .ok_or_else(|| anyhow!("zero adress not erc20"))?
+                .payload(),
+        )?;
+        let token = IERC20::new(token_address, self.client());
+        let txn = token.approve(self.eth.gateway_addr(), fil_amount_to_eth_amount(&amount)?);
+
+        let r = self.invoke(&from, txn).await?;
+        Ok(r.height as ChainEpoch)
+    }
+
+    async fn release(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        self.eth.ensure_same_gateway(&gateway_addr)?;
+
+        let value = amount
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid value to release"))?;
+
+        let contract =
+            gateway_manager_facet::GatewayManagerFacet::new(self.eth.gateway_addr(), self.client());
+        let mut txn = contract.release(gateway_manager_facet::FvmAddress::try_from(to)?);
+        txn.tx.set_value(value);
+
+        let r = self.invoke(&from, txn).await?;
+        Ok(r.height as ChainEpoch)
+    }
+
+    async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
+        // FIP-55: transfers to Ethereum accounts have to go through `InvokeContract`
+        let method = match to.protocol() {
+            Protocol::Delegated => METHOD_INVOKE_CONTRACT,
+            _ => METHOD_SEND,
+        };
+        let mut msg = MpoolPushMessage::new(to, from, method, vec![]);
+        msg.value = amount;
+
+        self.push(msg).await?;
+        Ok(())
+    }
+
+    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
+        self.lotus.wallet_balance(address).await
+    }
+
+    async fn get_chain_id(&self) -> Result<String> {
+        self.eth.get_chain_id().await
+    }
+
+    async fn get_commit_sha(&self) -> Result<[u8; 32]> {
+        self.eth.get_commit_sha().await
+    }
+
+    async fn get_subnet_supply_source(&self, subnet: &SubnetID) -> Result<Asset> {
+        self.eth.get_subnet_supply_source(subnet).await
+    }
+
+    async fn get_subnet_collateral_source(&self, subnet: &SubnetID) -> Result<Asset> {
+        self.eth.get_subnet_collateral_source(subnet).await
+    }
+
+    async fn get_genesis_info(&self, subnet: &SubnetID) -> Result<SubnetGenesisInfo> {
+        self.eth.get_genesis_info(subnet).await
+    }
+
+    async fn add_bootstrap(
+        &self,
+        subnet: &SubnetID,
+        from: &Address,
+        endpoint: String,
+    ) -> Result<()> {
+        if is_valid_bootstrap_addr(&endpoint).is_none() {
+            return Err(anyhow!("wrong format for bootstrap endpoint"));
+        }
+
+        let address = contract_address_from_subnet(subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+        self.invoke(from, contract.add_bootstrap_node(endpoint))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
+        self.eth.list_bootstrap_nodes(subnet).await
+    }
+
+    async fn get_validator_info(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<ValidatorInfo> {
+        self.eth.get_validator_info(subnet, validator).await
+    }
+
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<(Address, ValidatorInfo)>> {
+        self.eth.list_validators(subnet).await
+    }
+
+    async fn list_subnet_active_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        self.eth.list_subnet_active_validators(subnet).await
+    }
+
+    async fn list_waiting_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        self.eth.list_waiting_validators(subnet).await
+    }
+
+    async fn set_federated_power(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        validators: &[Address],
+        public_keys: &[Vec<u8>],
+        federated_power: &[u128],
+    ) -> Result<ChainEpoch> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, self.client());
+
+        let addresses = validators
+            .iter()
+            .map(|a| payload_to_evm_address(a.payload()))
+            .collect::<Result<Vec<_>>>()?;
+        let pubkeys = public_keys
+            .iter()
+            .map(|k| ethers::types::Bytes::from(k.clone()))
+            .collect();
+        let power = federated_power
+            .iter()
+            .map(|p| ethers::types::U256::from(*p))
+            .collect();
+
+        let r = self
+            .invoke(
+                from,
+                contract.set_federated_power(addresses, pubkeys, power),
+            )
+            .await?;
+        Ok(r.height as ChainEpoch)
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> SignedHeaderRelayer for LotusSubnetManager<T> {
+    async fn get_signed_header(&self, height: u64) -> Result<SignedHeader> {
+        self.eth.get_signed_header(height).await
+    }
+
+    async fn submit_signed_header(
+        &self,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        header: SignedHeader,
+        cert: ValidatorCertificate,
+    ) -> Result<TxHash> {
+        self.eth
+            .submit_signed_header(submitter, subnet_id, header, cert)
+            .await
+    }
+
+    async fn get_checkpoint_signatures(&self, height: u64) -> Result<Vec<CheckpointSignatures>> {
+        self.eth.get_checkpoint_signatures(height).await
+    }
+
+    async fn submit_checkpoint_signatures(
+        &self,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        signatures: CheckpointSignatures,
+    ) -> Result<TxHash> {
+        self.eth
+            .submit_checkpoint_signatures(submitter, subnet_id, signatures)
+            .await
+    }
+
+    async fn query_app_hash_breakdown(
+        &self,
+        height: ChainEpoch,
+    ) -> Result<Option<AppHashBreakdown>> {
+        self.eth.query_app_hash_breakdown(height).await
+    }
+
+    async fn get_state_root(&self, height: ChainEpoch) -> Result<Vec<u8>> {
+        self.eth.get_state_root(height).await
+    }
+
+    async fn get_last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<u64> {
+        self.eth
+            .get_last_bottom_up_checkpoint_height(subnet_id)
+            .await
+    }
+
+    async fn get_last_app_commitment_height(&self, subnet_id: &SubnetID) -> Result<u64> {
+        self.eth.get_last_app_commitment_height(subnet_id).await
+    }
+
+    async fn record_app_hash_breakdown(
+        &self,
+        height: ChainEpoch,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        breakdown: AppHashBreakdown,
+    ) -> Result<TxHash> {
+        self.eth
+            .record_app_hash_breakdown(height, submitter, subnet_id, breakdown)
+            .await
+    }
+
+    async fn submission_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        self.eth.submission_period(subnet_id).await
+    }
+
+    async fn current_epoch(&self) -> Result<ChainEpoch> {
+        self.lotus.current_epoch().await
+    }
+
+    async fn list_active_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        SignedHeaderRelayer::list_active_validators(&self.eth, subnet).await
+    }
+
+    async fn list_pending_bottom_up_batch_commitments(
+        &self,
+        subnet_id: &SubnetID,
+    ) -> Result<Vec<ListPendingCommitmentsEntry>> {
+        self.eth
+            .list_pending_bottom_up_batch_commitments(subnet_id)
+            .await
+    }
+
+    async fn make_next_bottom_up_batch_inclusions(
+        &self,
+        current: &ListPendingCommitmentsEntry,
+    ) -> Result<Vec<Inclusion>> {
+        self.eth.make_next_bottom_up_batch_inclusions(current).await
+    }
+
+    async fn execute_bottom_up_batch(
+        &self,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        height: ChainEpoch,
+        inclusions: Vec<Inclusion>,
+    ) -> Result<TxHash> {
+        self.eth
+            .execute_bottom_up_batch(submitter, subnet_id, height, inclusions)
+            .await
+    }
+
+    async fn transaction_status(&self, tx_hash: &TxHash) -> Result<TxStatus> {
+        self.eth.transaction_status(tx_hash).await
+    }
+
+    async fn revert_reason(&self, tx_hash: &TxHash) -> Result<Option<ContractRevert>> {
+        self.eth.revert_reason(tx_hash).await
+    }
+
+    async fn pending_signed_header_submissions(&self, subnet_id: &SubnetID) -> Result<Vec<TxHash>> {
+        self.eth.pending_signed_header_submissions(subnet_id).await
+    }
+
+    async fn bump_transaction(
+        &self,
+        submitter: &Address,
+        tx_hash: &TxHash,
+        bump_percent: u64,
+    ) -> Result<TxHash> {
+        self.eth
+            .bump_transaction(submitter, tx_hash, bump_percent)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> ValidatorRewarder for LotusSubnetManager<T> {
+    async fn query_reward_claims(
+        &self,
+        validator_addr: &Address,
+        from_checkpoint: ChainEpoch,
+        to_checkpoint: ChainEpoch,
+    ) -> Result<Vec<(u64, ValidatorClaim)>> {
+        self.eth
+            .query_reward_claims(validator_addr, from_checkpoint, to_checkpoint)
+            .await
+    }
+
+    async fn query_validator_rewards(
+        &self,
+        validator: &Address,
+        from_checkpoint: ChainEpoch,
+        to_checkpoint: ChainEpoch,
+    ) -> Result<Vec<(u64, ValidatorData)>> {
+        self.eth
+            .query_validator_rewards(validator, from_checkpoint, to_checkpoint)
+            .await
+    }
+
+    async fn batch_subnet_claim(
+        &self,
+        submitter: &Address,
+        reward_claim_subnet: &SubnetID,
+        reward_origin_subnet: &SubnetID,
+        claims: Vec<(u64, ValidatorClaim)>,
+    ) -> Result<()> {
+        let contract = subnet_actor_activity_facet::SubnetActorActivityFacet::new(
+            contract_address_from_subnet(reward_claim_subnet)?,
+            self.client(),
+        );
+
+        let (heights, claims): (Vec<u64>, Vec<ValidatorClaim>) = claims.into_iter().unzip();
+        let call = contract.batch_subnet_claim(reward_origin_subnet.try_into()?, heights, claims);
+
+        self.invoke(submitter, call)
+            .await
+            .context("failed to claim validator rewards")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
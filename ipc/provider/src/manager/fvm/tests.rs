@@ -0,0 +1,331 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex, RwLock};
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::Engine;
+use cid::Cid;
+use fvm_ipld_encoding::BytesSer;
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::SignatureType;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{KeyStore, KeyStoreConfig, Wallet};
+use multihash_codetable::{Code, MultihashDigest};
+use serde_json::{json, Value};
+
+use super::{
+    delegated_address, require_validator_sender, LotusSubnetManager, METHOD_INVOKE_CONTRACT,
+};
+use crate::config::subnet::{FVMSubnet, SubnetConfig};
+use crate::config::Subnet;
+use crate::lotus::message::chain::tipset_key_eth_hash;
+use crate::manager::{SubnetManager, TopDownFinalityQuery, NULL_ROUND_ERR_MSG};
+
+/// Stand-in for a Lotus node, answering the methods it knows with canned results
+/// and recording every request it receives.
+#[derive(Default)]
+struct MockLotus {
+    results: HashMap<&'static str, Value>,
+    requests: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockLotus {
+    fn requests(&self, method: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(m, _)| m == method)
+            .map(|(_, p)| p.clone())
+            .collect()
+    }
+}
+
+async fn handle(State(mock): State<Arc<MockLotus>>, Json(request): Json<Value>) -> Json<Value> {
+    let id = request["id"].clone();
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+
+    mock.requests
+        .lock()
+        .unwrap()
+        .push((method.clone(), request["params"].clone()));
+
+    match mock.results.get(method.as_str()) {
+        Some(result) => Json(json!({"jsonrpc": "2.0", "id": id, "result": result})),
+        None => Json(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32601, "message": format!("method not found: {method}")}
+        })),
+    }
+}
+
+fn start_mock_lotus(mock: Arc<MockLotus>) -> url::Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = Router::new()
+        .route("/rpc/v1", post(handle))
+        .with_state(mock);
+
+    tokio::spawn(
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service()),
+    );
+
+    format!("http://{addr}/rpc/v1").parse().unwrap()
+}
+
+fn cid(data: &[u8]) -> Cid {
+    Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, Code::Blake2b256.digest(data))
+}
+
+fn cid_map(cid: &Cid) -> Value {
+    json!({"/": cid.to_string()})
+}
+
+/// Canned results for the calls made when pushing a message and waiting for it.
+fn message_results(exit_code: u32, ret: Option<Vec<u8>>) -> HashMap<&'static str, Value> {
+    let msg_cid = cid(b"message");
+    let ret = ret.map(|r| base64::engine::general_purpose::STANDARD.encode(r));
+
+    HashMap::from([
+        ("eth_chainId", json!("0x4cb2f")),
+        ("Filecoin.MpoolGetNonce", json!(7)),
+        (
+            "Filecoin.GasEstimateMessageGas",
+            json!({"GasLimit": 1_000_000, "GasFeeCap": "100000", "GasPremium": "10000"}),
+        ),
+        ("Filecoin.MpoolPush", cid_map(&msg_cid)),
+        (
+            "Filecoin.StateWaitMsg",
+            json!({
+                "Message": cid_map(&msg_cid),
+                "Receipt": {"ExitCode": exit_code, "Return": ret, "GasUsed": 500_000},
+                "TipSet": [cid_map(&cid(b"tipset"))],
+                "Height": 42
+            }),
+        ),
+    ])
+}
+
+fn new_manager(url: url::Url) -> (LotusSubnetManager, Address) {
+    let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+    let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+    (new_manager_with_wallet(url, wallet), sender)
+}
+
+fn new_manager_with_wallet(url: url::Url, wallet: Wallet) -> LotusSubnetManager {
+    let subnet = Subnet {
+        id: SubnetID::new_root(314159),
+        config: SubnetConfig::Fvm(FVMSubnet {
+            jsonrpc_api_http: url,
+            provider_timeout: None,
+            auth_token: None,
+            registry_addr: Address::new_delegated(10, &[1; 20]).unwrap(),
+            gateway_addr: Address::new_delegated(10, &[2; 20]).unwrap(),
+        }),
+    };
+
+    LotusSubnetManager::from_subnet_with_wallet_store(
+        &subnet,
+        Some(Arc::new(RwLock::new(wallet))),
+        None,
+    )
+    .unwrap()
+}
+
+/// The uncompressed public key of a key in the wallet.
+fn public_key(wallet: &mut Wallet, addr: &Address) -> Vec<u8> {
+    let info = wallet.export(addr).unwrap();
+    let sk = libsecp256k1::SecretKey::parse_slice(info.private_key()).unwrap();
+    libsecp256k1::PublicKey::from_secret_key(&sk)
+        .serialize()
+        .to_vec()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_block_hash_is_tipset_key_hash() {
+    let (parent, block) = (cid(b"parent"), cid(b"block"));
+
+    let mock = Arc::new(MockLotus {
+        results: HashMap::from([
+            ("eth_chainId", json!("0x4cb2f")),
+            (
+                "Filecoin.ChainHead",
+                json!({"Cids": [cid_map(&block)], "Blocks": [], "Height": 12}),
+            ),
+            (
+                "Filecoin.ChainGetTipSetByHeight",
+                json!({
+                    "Cids": [cid_map(&block)],
+                    "Blocks": [{"ParentStateRoot": cid_map(&cid(b"state")), "Parents": [cid_map(&parent)]}],
+                    "Height": 10
+                }),
+            ),
+        ]),
+        ..Default::default()
+    });
+    let (manager, _) = new_manager(start_mock_lotus(mock.clone()));
+
+    assert_eq!(manager.chain_head_height().await.unwrap(), 12);
+
+    let r = manager.get_block_hash(10).await.unwrap();
+    assert_eq!(r.block_hash, tipset_key_eth_hash(&[block]).unwrap());
+    assert_eq!(r.parent_block_hash, tipset_key_eth_hash(&[parent]).unwrap());
+    // looked up from the heaviest tipset
+    assert_eq!(
+        mock.requests("Filecoin.ChainGetTipSetByHeight"),
+        vec![json!([10, []])]
+    );
+
+    // epoch 11 was a null round, which has no block hash; the syncer skips it by the message
+    let err = manager.get_block_hash(11).await.unwrap_err();
+    assert!(err.to_string().contains(NULL_ROUND_ERR_MSG), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tipset_without_height_is_not_a_null_round() {
+    let mock = Arc::new(MockLotus {
+        results: HashMap::from([
+            ("eth_chainId", json!("0x4cb2f")),
+            (
+                "Filecoin.ChainGetTipSetByHeight",
+                json!({"Cids": [cid_map(&cid(b"block"))], "Blocks": []}),
+            ),
+        ]),
+        ..Default::default()
+    });
+    let (manager, _) = new_manager(start_mock_lotus(mock));
+
+    let err = manager.get_block_hash(10).await.unwrap_err();
+    assert!(!err.to_string().contains(NULL_ROUND_ERR_MSG), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_value_pushes_signed_message() {
+    let mock = Arc::new(MockLotus {
+        results: message_results(0, None),
+        ..Default::default()
+    });
+    let (manager, sender) = new_manager(start_mock_lotus(mock.clone()));
+
+    let to = Address::new_id(1001);
+    let eth_to = Address::new_delegated(10, &[3; 20]).unwrap();
+    let amount = TokenAmount::from_whole(1);
+
+    manager
+        .send_value(sender, to, amount.clone())
+        .await
+        .unwrap();
+    manager.send_value(sender, eth_to, amount).await.unwrap();
+
+    let pushed = mock.requests("Filecoin.MpoolPush");
+    assert_eq!(pushed.len(), 2);
+
+    let msg = &pushed[0][0]["Message"];
+    assert_eq!(msg["From"], json!(sender.to_string()));
+    assert_eq!(msg["To"], json!(to.to_string()));
+    assert_eq!(msg["Value"], json!("1000000000000000000"));
+    assert_eq!(msg["Method"], json!(0));
+    assert_eq!(msg["Nonce"], json!(7));
+    assert_eq!(msg["GasLimit"], json!(1_000_000));
+    assert_eq!(
+        pushed[0][0]["Signature"]["Type"],
+        json!(SignatureType::Secp256k1 as u8)
+    );
+
+    // transfers to Ethereum accounts invoke the contract
+    let msg = &pushed[1][0]["Message"];
+    assert_eq!(msg["Method"], json!(METHOD_INVOKE_CONTRACT));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reverted_invocation_is_decoded() {
+    // `NotOwnerOfPublicKey()` wrapped as the CBOR byte string returned by the EVM actor
+    let revert = hex::decode("97d24a3a").unwrap();
+    let ret = fvm_ipld_encoding::to_vec(&BytesSer(&revert)).unwrap();
+
+    let mock = Arc::new(MockLotus {
+        results: message_results(33, Some(ret)),
+        ..Default::default()
+    });
+    let (manager, sender) = new_manager(start_mock_lotus(mock.clone()));
+
+    let subnet_addr = Address::new_delegated(10, &[4; 20]).unwrap();
+    let subnet = SubnetID::new(314159, vec![subnet_addr]);
+
+    let err = manager.kill_subnet(subnet, sender).await.unwrap_err();
+    assert!(
+        format!("{err:#}").contains("NotOwnerOfPublicKey"),
+        "{err:#}"
+    );
+
+    let pushed = mock.requests("Filecoin.MpoolPush");
+    let msg = &pushed[0][0]["Message"];
+    assert_eq!(msg["To"], json!(subnet_addr.to_string()));
+    assert_eq!(msg["Method"], json!(METHOD_INVOKE_CONTRACT));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_validator_operations_check_the_sender_key() {
+    let mock = Arc::new(MockLotus {
+        results: message_results(0, None),
+        ..Default::default()
+    });
+    let (manager, sender) = new_manager(start_mock_lotus(mock.clone()));
+
+    let subnet_addr = Address::new_delegated(10, &[4; 20]).unwrap();
+    let subnet = SubnetID::new(314159, vec![subnet_addr]);
+
+    // the public key of another validator
+    let err = manager
+        .join_subnet(
+            subnet.clone(),
+            sender,
+            TokenAmount::from_whole(1),
+            vec![4; 65],
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not belong"), "{err}");
+
+    // validator keys can't be BLS
+    let bls = Address::new_bls(&[1; 48]).unwrap();
+    let err = manager.leave_subnet(subnet.clone(), bls).await.unwrap_err();
+    assert!(err.to_string().contains("secp256k1"), "{err}");
+
+    // an f1 validator needs its key to send from its f410 address
+    let unknown = Address::new_secp256k1(&[4; 65]).unwrap();
+    let err = manager.leave_subnet(subnet, unknown).await.unwrap_err();
+    assert!(err.to_string().contains("FVM wallet"), "{err}");
+
+    // rejected before anything is sent
+    assert!(mock.requests("Filecoin.MpoolPush").is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_f1_validators_send_from_their_delegated_address() {
+    let mock = Arc::new(MockLotus {
+        results: message_results(0, None),
+        ..Default::default()
+    });
+
+    let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+    let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+    let pk = public_key(&mut wallet, &sender);
+
+    require_validator_sender(&sender, Some(&pk)).unwrap();
+
+    let delegated = delegated_address(&pk).unwrap();
+    require_validator_sender(&delegated, Some(&pk)).unwrap();
+
+    let manager = new_manager_with_wallet(start_mock_lotus(mock), wallet);
+    assert_eq!(manager.delegated_sender(&sender).unwrap(), delegated);
+}
@@ -0,0 +1,1436 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! An in-memory parent chain for testing the top-down syncer, the voting,
+//! the relayer and the CLI flows without running a Lotus or Anvil node.
+//!
+//! The chain only moves when the test tells it to: transactions and calls
+//! such as `join_subnet` or `fund` take effect immediately in the state of the
+//! mock, but the top-down messages and validator changes they emit, as well as
+//! the inclusion of relayed checkpoints, wait for the next call to
+//! [MockParent::produce_block]. Null rounds and reorgs can be scripted too.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use ethers::abi::Tokenizable;
+use ethers::utils::keccak256;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_actors_abis::checkpointing_facet::AppHashBreakdown;
+use ipc_actors_abis::subnet_actor_activity_facet::ValidatorClaim;
+use ipc_actors_abis::subnet_actor_checkpointing_facet::Inclusion;
+use ipc_actors_abis::subnet_actor_getter_facet::ListPendingCommitmentsEntry;
+use ipc_api::checkpoint::consensus::ValidatorData;
+use ipc_api::checkpoint::CheckpointSignatures;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::evm::fil_to_eth_amount;
+use ipc_api::staking::{
+    PowerChange, PowerChangeRequest, PowerOperation, ValidatorInfo, ValidatorStakingInfo,
+};
+use ipc_api::subnet::{Asset, ConstructParams, PermissionMode};
+use ipc_api::subnet_id::SubnetID;
+use ipc_api::validator::Validator;
+use num_traits::Zero;
+
+use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::cometbft::{SignedHeader, ValidatorCertificate};
+use crate::manager::subnet::{
+    GetBlockHashResult, SubnetGenesisInfo, TopDownFinalityQuery, TopDownQueryPayload,
+    ValidatorRewarder,
+};
+use crate::manager::{
+    ContractRevert, SignedHeaderRelayer, SubnetManager, TxHash, TxStatus, NULL_ROUND_ERR_MSG,
+};
+
+/// Chain ID reported by the mock unless configured otherwise.
+const DEFAULT_CHAIN_ID: u64 = 31415926;
+/// Bottom-up checkpoint period of subnets not created through the mock.
+const DEFAULT_SUBMISSION_PERIOD: ChainEpoch = 10;
+
+/// A scriptable parent chain, keeping all its state in memory.
+///
+/// Clones share the same chain, so one copy can be handed to the component
+/// under test while the test itself produces blocks with another.
+#[derive(Clone)]
+pub struct MockParent {
+    state: Arc<Mutex<MockParentState>>,
+}
+
+/// A block of the mock chain, with the events emitted in it.
+#[derive(Clone, Debug)]
+struct MockBlock {
+    hash: Vec<u8>,
+    top_down_msgs: HashMap<SubnetID, Vec<IpcEnvelope>>,
+    validator_changes: HashMap<SubnetID, Vec<PowerChangeRequest>>,
+    txs: Vec<TxHash>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct MockValidator {
+    collateral: TokenAmount,
+    metadata: Vec<u8>,
+}
+
+struct MockSubnet {
+    params: Option<ConstructParams>,
+    genesis_epoch: ChainEpoch,
+    approved: bool,
+    killed: bool,
+    validators: BTreeMap<Address, MockValidator>,
+    genesis_balances: BTreeMap<Address, TokenAmount>,
+    bootstrap_nodes: Vec<String>,
+    circ_supply: TokenAmount,
+    configuration_number: u64,
+    top_down_nonce: u64,
+    last_bottom_up_checkpoint_height: u64,
+    last_app_commitment_height: u64,
+    pending_commitments: Vec<ListPendingCommitmentsEntry>,
+}
+
+impl MockSubnet {
+    fn new(params: Option<ConstructParams>, genesis_epoch: ChainEpoch) -> Self {
+        Self {
+            params,
+            genesis_epoch,
+            approved: false,
+            killed: false,
+            validators: Default::default(),
+            genesis_balances: Default::default(),
+            bootstrap_nodes: Default::default(),
+            circ_supply: TokenAmount::zero(),
+            configuration_number: 0,
+            top_down_nonce: 0,
+            last_bottom_up_checkpoint_height: 0,
+            last_app_commitment_height: 0,
+            pending_commitments: Default::default(),
+        }
+    }
+
+    fn active_validators_limit(&self) -> usize {
+        self.params
+            .as_ref()
+            .map(|p| p.active_validators_limit as usize)
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Validators ordered by collateral, highest first, with ties broken by address.
+    fn ranked_validators(&self) -> Vec<(Address, ValidatorInfo)> {
+        let mut validators = self
+            .validators
+            .iter()
+            .filter(|(_, v)| !v.collateral.is_zero())
+            .collect::<Vec<_>>();
+
+        validators.sort_by(|(a1, v1), (a2, v2)| {
+            v2.collateral.cmp(&v1.collateral).then_with(|| a1.cmp(a2))
+        });
+
+        let limit = self.active_validators_limit();
+
+        validators
+            .into_iter()
+            .enumerate()
+            .map(|(i, (addr, v))| {
+                let info = ValidatorInfo {
+                    staking: ValidatorStakingInfo {
+                        current_power: v.collateral.clone(),
+                        next_power: v.collateral.clone(),
+                        metadata: v.metadata.clone(),
+                    },
+                    is_active: i < limit,
+                    is_waiting: i >= limit,
+                };
+                (*addr, info)
+            })
+            .collect()
+    }
+}
+
+/// What the transaction does once it's included.
+#[derive(Clone, Debug)]
+enum MockTxKind {
+    SignedHeader { subnet_id: SubnetID, height: u64 },
+    CheckpointSignatures { subnet_id: SubnetID, height: u64 },
+    AppHashBreakdown { subnet_id: SubnetID, height: u64 },
+    BottomUpBatch,
+}
+
+#[derive(Clone, Debug)]
+struct MockTx {
+    kind: MockTxKind,
+    status: TxStatus,
+}
+
+struct MockParentState {
+    chain_id: u64,
+    /// The subnet following this chain as its parent.
+    child_subnet: SubnetID,
+    /// Blocks by height, with `None` standing for null rounds.
+    blocks: BTreeMap<ChainEpoch, Option<MockBlock>>,
+    /// Incremented on every reorg, so that the new blocks get different hashes.
+    fork: u64,
+    /// How far behind the head blocks are considered final, if the chain reports finality.
+    finality_delay: Option<ChainEpoch>,
+    /// Events waiting for the next block.
+    pending_msgs: HashMap<SubnetID, Vec<IpcEnvelope>>,
+    pending_changes: HashMap<SubnetID, Vec<PowerChangeRequest>>,
+    subnets: HashMap<SubnetID, MockSubnet>,
+    balances: HashMap<Address, TokenAmount>,
+    latest_parent_finality: ChainEpoch,
+    /// Child side data served to the relayer.
+    signed_headers: BTreeMap<u64, SignedHeader>,
+    checkpoint_signatures: BTreeMap<u64, Vec<CheckpointSignatures>>,
+    state_roots: BTreeMap<ChainEpoch, Vec<u8>>,
+    app_hash_breakdowns: BTreeMap<ChainEpoch, AppHashBreakdown>,
+    /// Validator set of the child subnet, by the height from which it applies.
+    memberships: BTreeMap<ChainEpoch, Vec<Address>>,
+    txs: HashMap<TxHash, MockTx>,
+    /// Transactions waiting for the next block, in order.
+    mempool: Vec<TxHash>,
+    /// The error the next transaction fails with in simulation.
+    next_revert: Option<ContractRevert>,
+    nonce: u64,
+}
+
+impl MockParent {
+    /// Create a chain whose first blocks, up to and including `genesis_epoch`,
+    /// precede the creation of the child subnet.
+    pub fn new(child_subnet: SubnetID, genesis_epoch: ChainEpoch) -> Self {
+        let mut subnets = HashMap::new();
+        subnets.insert(child_subnet.clone(), MockSubnet::new(None, genesis_epoch));
+
+        let mut state = MockParentState {
+            chain_id: DEFAULT_CHAIN_ID,
+            child_subnet,
+            blocks: Default::default(),
+            fork: 0,
+            finality_delay: None,
+            pending_msgs: Default::default(),
+            pending_changes: Default::default(),
+            subnets,
+            balances: Default::default(),
+            latest_parent_finality: genesis_epoch,
+            signed_headers: Default::default(),
+            checkpoint_signatures: Default::default(),
+            state_roots: Default::default(),
+            app_hash_breakdowns: Default::default(),
+            memberships: Default::default(),
+            txs: Default::default(),
+            mempool: Default::default(),
+            next_revert: None,
+            nonce: 0,
+        };
+
+        for _ in 0..=genesis_epoch {
+            state.seal_block();
+        }
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn with_chain_id(self, chain_id: u64) -> Self {
+        self.lock().chain_id = chain_id;
+        self
+    }
+
+    /// Report blocks `delay` epochs behind the head as final, like the `finalized` tag of Ethereum.
+    pub fn with_finality_delay(self, delay: ChainEpoch) -> Self {
+        self.lock().finality_delay = Some(delay);
+        self
+    }
+
+    /// The subnet following this chain.
+    pub fn child_subnet(&self) -> SubnetID {
+        self.lock().child_subnet.clone()
+    }
+
+    /// Genesis epoch of the followed subnet.
+    pub fn child_genesis_epoch(&self) -> ChainEpoch {
+        let state = self.lock();
+        state
+            .subnet(&state.child_subnet)
+            .map(|s| s.genesis_epoch)
+            .unwrap_or_default()
+    }
+
+    /// Height of the latest block or null round.
+    pub fn head(&self) -> ChainEpoch {
+        self.lock().head()
+    }
+
+    /// Height of the latest block considered final, if the chain reports finality.
+    pub fn finalized_head(&self) -> Option<ChainEpoch> {
+        self.lock().finalized_head()
+    }
+
+    /// Include the pending events and transactions in a new block, returning its height.
+    pub fn produce_block(&self) -> ChainEpoch {
+        self.lock().seal_block()
+    }
+
+    /// Produce a number of blocks, returning the height of the last one.
+    pub fn produce_blocks(&self, count: usize) -> ChainEpoch {
+        let mut state = self.lock();
+        for _ in 0..count {
+            state.seal_block();
+        }
+        state.head()
+    }
+
+    /// Skip an epoch without producing a block; pending events wait for the next block.
+    pub fn produce_null_round(&self) -> ChainEpoch {
+        let mut state = self.lock();
+        let height = state.head() + 1;
+        state.blocks.insert(height, None);
+        height
+    }
+
+    /// Remove the last `depth` epochs from the chain, returning the events and
+    /// transactions of their blocks to the pending queues.
+    ///
+    /// Blocks produced after a reorg get different hashes than the ones they replace.
+    pub fn reorg(&self, depth: usize) -> Result<()> {
+        let mut state = self.lock();
+        let genesis_epoch = state
+            .subnet(&state.child_subnet)
+            .map(|s| s.genesis_epoch)
+            .unwrap_or_default();
+
+        if state.head() - (depth as ChainEpoch) < genesis_epoch {
+            bail!("cannot reorg beyond the genesis epoch of the child subnet");
+        }
+
+        let mut removed = Vec::new();
+        for _ in 0..depth {
+            if let Some((_, block)) = state.blocks.pop_last() {
+                removed.push(block);
+            }
+        }
+
+        // Starting from the latest block, put back the events ahead of what is pending,
+        // so that in the end they are in their original order.
+        for block in removed.into_iter().flatten() {
+            for (subnet_id, mut msgs) in block.top_down_msgs {
+                let pending = state.pending_msgs.entry(subnet_id).or_default();
+                msgs.append(pending);
+                *pending = msgs;
+            }
+            for (subnet_id, mut changes) in block.validator_changes {
+                let pending = state.pending_changes.entry(subnet_id).or_default();
+                changes.append(pending);
+                *pending = changes;
+            }
+            for tx_hash in block.txs.iter() {
+                if let Some(tx) = state.txs.get_mut(tx_hash) {
+                    tx.status = TxStatus::Pending;
+                }
+            }
+            let mut txs = block.txs;
+            txs.append(&mut state.mempool);
+            state.mempool = txs;
+        }
+
+        state.fork += 1;
+
+        Ok(())
+    }
+
+    /// Emit a top-down message to a subnet in the next block, assigning it the next nonce.
+    pub fn send_top_down_msg(&self, subnet_id: &SubnetID, msg: IpcEnvelope) {
+        self.lock().push_top_down_msg(subnet_id, msg);
+    }
+
+    /// Emit a validator change of a subnet in the next block, assigning it the next configuration number.
+    pub fn send_validator_change(&self, subnet_id: &SubnetID, change: PowerChange) -> u64 {
+        self.lock().push_validator_change(subnet_id, change)
+    }
+
+    /// Give funds to an account on the parent.
+    pub fn credit(&self, addr: Address, amount: TokenAmount) {
+        self.lock().credit(&addr, &amount);
+    }
+
+    /// Set the parent finality the child subnet has committed, as returned by `latest_parent_finality`.
+    pub fn set_latest_parent_finality(&self, height: ChainEpoch) {
+        self.lock().latest_parent_finality = height;
+    }
+
+    /// Make the next transaction fail in simulation with a contract revert.
+    pub fn revert_next(&self, revert: ContractRevert) {
+        self.lock().next_revert = Some(revert);
+    }
+
+    /// Serve a signed header of the child subnet to the relayer.
+    pub fn set_signed_header(&self, height: u64, header: SignedHeader) {
+        self.lock().signed_headers.insert(height, header);
+    }
+
+    /// Serve the checkpoint signatures of the child subnet over an app hash to the relayer.
+    pub fn add_checkpoint_signatures(&self, signatures: CheckpointSignatures) {
+        self.lock()
+            .checkpoint_signatures
+            .entry(signatures.height)
+            .or_default()
+            .push(signatures);
+    }
+
+    /// Serve the state root of the child subnet at a height to the relayer.
+    pub fn set_state_root(&self, height: ChainEpoch, state_root: Vec<u8>) {
+        self.lock().state_roots.insert(height, state_root);
+    }
+
+    /// Serve the app hash breakdown of the child subnet at a height to the relayer.
+    pub fn set_app_hash_breakdown(&self, height: ChainEpoch, breakdown: AppHashBreakdown) {
+        self.lock().app_hash_breakdowns.insert(height, breakdown);
+    }
+
+    /// Serve the validator set of the child subnet from a height on to the relayer.
+    pub fn set_membership(&self, height: ChainEpoch, validators: Vec<Address>) {
+        self.lock().memberships.insert(height, validators);
+    }
+
+    /// Set the bottom-up batch commitments of a subnet waiting for execution.
+    pub fn set_pending_commitments(
+        &self,
+        subnet_id: &SubnetID,
+        commitments: Vec<ListPendingCommitmentsEntry>,
+    ) {
+        self.lock()
+            .subnet_mut_or_default(subnet_id)
+            .pending_commitments = commitments;
+    }
+
+    /// Height of the last bottom-up checkpoint of a subnet included in a block.
+    pub fn last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> u64 {
+        self.lock()
+            .subnet(subnet_id)
+            .map(|s| s.last_bottom_up_checkpoint_height)
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockParentState> {
+        self.state.lock().expect("mock parent lock poisoned")
+    }
+}
+
+impl MockParentState {
+    fn head(&self) -> ChainEpoch {
+        self.blocks
+            .last_key_value()
+            .map(|(h, _)| *h)
+            .unwrap_or_default()
+    }
+
+    fn finalized_head(&self) -> Option<ChainEpoch> {
+        self.finality_delay
+            .map(|delay| (self.head() - delay).max(0))
+    }
+
+    fn last_block_hash(&self) -> Vec<u8> {
+        self.blocks
+            .values()
+            .rev()
+            .flatten()
+            .next()
+            .map(|b| b.hash.clone())
+            .unwrap_or_default()
+    }
+
+    /// Find a block, failing with the same error as Lotus for null rounds.
+    fn block(&self, height: ChainEpoch) -> Result<&MockBlock> {
+        match self.blocks.get(&height) {
+            None => bail!("height {height} does not exist"),
+            Some(None) => bail!(NULL_ROUND_ERR_MSG),
+            Some(Some(b)) => Ok(b),
+        }
+    }
+
+    fn subnet(&self, subnet_id: &SubnetID) -> Result<&MockSubnet> {
+        self.subnets
+            .get(subnet_id)
+            .ok_or_else(|| anyhow!("subnet: {subnet_id} does not exists"))
+    }
+
+    fn subnet_mut(&mut self, subnet_id: &SubnetID) -> Result<&mut MockSubnet> {
+        self.subnets
+            .get_mut(subnet_id)
+            .ok_or_else(|| anyhow!("subnet: {subnet_id} does not exists"))
+    }
+
+    fn subnet_mut_or_default(&mut self, subnet_id: &SubnetID) -> &mut MockSubnet {
+        let genesis_epoch = self.head();
+        self.subnets
+            .entry(subnet_id.clone())
+            .or_insert_with(|| MockSubnet::new(None, genesis_epoch))
+    }
+
+    fn seal_block(&mut self) -> ChainEpoch {
+        let height = if self.blocks.is_empty() {
+            0
+        } else {
+            self.head() + 1
+        };
+
+        let parent_hash = self.last_block_hash();
+        let mut preimage = (height as u64).to_be_bytes().to_vec();
+        preimage.extend(parent_hash);
+        preimage.extend(self.fork.to_be_bytes());
+        let hash = keccak256(preimage).to_vec();
+
+        let txs = std::mem::take(&mut self.mempool);
+        for tx_hash in txs.iter() {
+            self.include_tx(tx_hash, height);
+        }
+
+        let block = MockBlock {
+            hash,
+            top_down_msgs: std::mem::take(&mut self.pending_msgs),
+            validator_changes: std::mem::take(&mut self.pending_changes),
+            txs,
+        };
+
+        self.blocks.insert(height, Some(block));
+
+        height
+    }
+
+    fn include_tx(&mut self, tx_hash: &TxHash, height: ChainEpoch) {
+        let Some(tx) = self.txs.get_mut(tx_hash) else {
+            return;
+        };
+        if tx.status != TxStatus::Pending {
+            return;
+        }
+        tx.status = TxStatus::Included {
+            height,
+            success: true,
+        };
+
+        let kind = tx.kind.clone();
+        match kind {
+            MockTxKind::SignedHeader { subnet_id, height }
+            | MockTxKind::CheckpointSignatures { subnet_id, height } => {
+                let subnet = self.subnet_mut_or_default(&subnet_id);
+                subnet.last_bottom_up_checkpoint_height =
+                    subnet.last_bottom_up_checkpoint_height.max(height);
+            }
+            MockTxKind::AppHashBreakdown { subnet_id, height } => {
+                let subnet = self.subnet_mut_or_default(&subnet_id);
+                subnet.last_app_commitment_height = subnet.last_app_commitment_height.max(height);
+            }
+            MockTxKind::BottomUpBatch => {}
+        }
+    }
+
+    /// Send a transaction to the mempool, unless it has been scripted to revert.
+    fn submit_tx(&mut self, kind: MockTxKind) -> Result<TxHash> {
+        if let Some(revert) = self.next_revert.take() {
+            return Err(revert.into());
+        }
+        let tx_hash = self.next_hash();
+        self.txs.insert(
+            tx_hash,
+            MockTx {
+                kind,
+                status: TxStatus::Pending,
+            },
+        );
+        self.mempool.push(tx_hash);
+        Ok(tx_hash)
+    }
+
+    fn next_hash(&mut self) -> TxHash {
+        self.nonce += 1;
+        TxHash::from(keccak256(self.nonce.to_be_bytes()))
+    }
+
+    fn push_top_down_msg(&mut self, subnet_id: &SubnetID, mut msg: IpcEnvelope) {
+        let subnet = self.subnet_mut_or_default(subnet_id);
+        msg.local_nonce = subnet.top_down_nonce;
+        subnet.top_down_nonce += 1;
+        self.pending_msgs
+            .entry(subnet_id.clone())
+            .or_default()
+            .push(msg);
+    }
+
+    fn push_validator_change(&mut self, subnet_id: &SubnetID, change: PowerChange) -> u64 {
+        let subnet = self.subnet_mut_or_default(subnet_id);
+        subnet.configuration_number += 1;
+        let configuration_number = subnet.configuration_number;
+        self.pending_changes
+            .entry(subnet_id.clone())
+            .or_default()
+            .push(PowerChangeRequest {
+                configuration_number,
+                change,
+            });
+        configuration_number
+    }
+
+    /// Set the power and metadata of a validator, emitting the corresponding changes.
+    fn set_validator(
+        &mut self,
+        subnet_id: &SubnetID,
+        validator: &Address,
+        collateral: TokenAmount,
+        metadata: Option<Vec<u8>>,
+    ) -> Result<()> {
+        let subnet = self.subnet_mut(subnet_id)?;
+        let v = subnet.validators.entry(*validator).or_default();
+        v.collateral = collateral.clone();
+
+        if let Some(metadata) = metadata {
+            v.metadata = metadata.clone();
+            self.push_validator_change(
+                subnet_id,
+                PowerChange {
+                    op: PowerOperation::SetMetadata,
+                    payload: metadata,
+                    validator: *validator,
+                },
+            );
+        }
+
+        let power = fil_to_eth_amount(&collateral)?;
+        self.push_validator_change(
+            subnet_id,
+            PowerChange {
+                op: PowerOperation::SetPower,
+                payload: ethers::abi::encode(&[power.into_token()]),
+                validator: *validator,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn credit(&mut self, addr: &Address, amount: &TokenAmount) {
+        *self.balances.entry(*addr).or_default() += amount.clone();
+    }
+
+    fn debit(&mut self, addr: &Address, amount: &TokenAmount) -> Result<()> {
+        let balance = self.balances.entry(*addr).or_default();
+        if *balance < *amount {
+            bail!("insufficient funds: {addr} has {balance}, needs {amount}");
+        }
+        *balance -= amount.clone();
+        Ok(())
+    }
+
+    fn collateral(&self, subnet_id: &SubnetID, validator: &Address) -> Result<TokenAmount> {
+        Ok(self
+            .subnet(subnet_id)?
+            .validators
+            .get(validator)
+            .map(|v| v.collateral.clone())
+            .unwrap_or_default())
+    }
+
+    fn fund(
+        &mut self,
+        subnet_id: &SubnetID,
+        from: &Address,
+        to: &Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        self.debit(from, &amount)?;
+        let msg = IpcEnvelope::new_fund_msg(subnet_id, from, to, amount.clone())?;
+        self.subnet_mut(subnet_id)?.circ_supply += amount.clone();
+        self.push_top_down_msg(subnet_id, msg);
+        Ok(self.head() + 1)
+    }
+}
+
+#[async_trait]
+impl TopDownFinalityQuery for MockParent {
+    async fn genesis_epoch(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        Ok(self.lock().subnet(subnet_id)?.genesis_epoch)
+    }
+
+    async fn chain_head_height(&self) -> Result<ChainEpoch> {
+        Ok(self.head())
+    }
+
+    async fn finalized_chain_head_height(&self) -> Result<Option<ChainEpoch>> {
+        Ok(self.finalized_head())
+    }
+
+    async fn get_top_down_msgs(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<IpcEnvelope>>> {
+        let state = self.lock();
+        let block = state.block(epoch)?;
+        Ok(TopDownQueryPayload {
+            value: block
+                .top_down_msgs
+                .get(subnet_id)
+                .cloned()
+                .unwrap_or_default(),
+            block_hash: block.hash.clone(),
+        })
+    }
+
+    async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult> {
+        let state = self.lock();
+        let block = state.block(height)?;
+        let parent_block_hash = state
+            .blocks
+            .range(..height)
+            .rev()
+            .find_map(|(_, b)| b.as_ref())
+            .map(|b| b.hash.clone())
+            .unwrap_or_default();
+
+        Ok(GetBlockHashResult {
+            parent_block_hash,
+            block_hash: block.hash.clone(),
+        })
+    }
+
+    async fn get_validator_changeset(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<PowerChangeRequest>>> {
+        let state = self.lock();
+        let block = state.block(epoch)?;
+        Ok(TopDownQueryPayload {
+            value: block
+                .validator_changes
+                .get(subnet_id)
+                .cloned()
+                .unwrap_or_default(),
+            block_hash: block.hash.clone(),
+        })
+    }
+
+    async fn latest_parent_finality(&self) -> Result<ChainEpoch> {
+        Ok(self.lock().latest_parent_finality)
+    }
+}
+
+#[async_trait]
+impl SubnetManager for MockParent {
+    async fn create_subnet(&self, _from: Address, params: ConstructParams) -> Result<Address> {
+        let mut state = self.lock();
+        let hash = state.next_hash();
+        let addr = ethers_address_to_fil_address(&ethers::types::Address::from_slice(
+            &hash.as_bytes()[12..],
+        ))?;
+        let subnet_id = SubnetID::new_from_parent(&params.parent, addr);
+        let genesis_epoch = state.head();
+        state
+            .subnets
+            .insert(subnet_id, MockSubnet::new(Some(params), genesis_epoch));
+        Ok(addr)
+    }
+
+    async fn join_subnet(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+        metadata: Vec<u8>,
+    ) -> Result<ChainEpoch> {
+        let mut state = self.lock();
+        if state.subnet(&subnet)?.killed {
+            bail!("subnet {subnet} has been killed");
+        }
+        state.debit(&from, &collateral)?;
+        let collateral = state.collateral(&subnet, &from)? + collateral;
+        state.set_validator(&subnet, &from, collateral, Some(metadata))?;
+        Ok(state.head() + 1)
+    }
+
+    async fn approve_subnet(&self, subnet: SubnetID, _from: Address) -> Result<()> {
+        self.lock().subnet_mut(&subnet)?.approved = true;
+        Ok(())
+    }
+
+    async fn reject_approved_subnet(&self, subnet: SubnetID, _from: Address) -> Result<()> {
+        self.lock().subnet_mut(&subnet)?.approved = false;
+        Ok(())
+    }
+
+    async fn pre_fund(&self, subnet: SubnetID, from: Address, balance: TokenAmount) -> Result<()> {
+        let mut state = self.lock();
+        state.debit(&from, &balance)?;
+        *state
+            .subnet_mut(&subnet)?
+            .genesis_balances
+            .entry(from)
+            .or_default() += balance;
+        Ok(())
+    }
+
+    async fn pre_release(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let balance = state
+            .subnet_mut(&subnet)?
+            .genesis_balances
+            .entry(from)
+            .or_default();
+        if *balance < amount {
+            bail!("insufficient genesis balance: {from} has {balance}");
+        }
+        *balance -= amount.clone();
+        state.credit(&from, &amount);
+        Ok(())
+    }
+
+    async fn stake(&self, subnet: SubnetID, from: Address, collateral: TokenAmount) -> Result<()> {
+        let mut state = self.lock();
+        let current = state.collateral(&subnet, &from)?;
+        if current.is_zero() {
+            bail!("{from} is not a validator of {subnet}");
+        }
+        state.debit(&from, &collateral)?;
+        state.set_validator(&subnet, &from, current + collateral, None)
+    }
+
+    async fn unstake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let current = state.collateral(&subnet, &from)?;
+        if current <= collateral {
+            bail!("cannot unstake all collateral of {from}; leave the subnet instead");
+        }
+        state.credit(&from, &collateral);
+        state.set_validator(&subnet, &from, current - collateral, None)
+    }
+
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+        let mut state = self.lock();
+        let current = state.collateral(&subnet, &from)?;
+        if current.is_zero() {
+            bail!("{from} is not a validator of {subnet}");
+        }
+        state.credit(&from, &current);
+        state.set_validator(&subnet, &from, TokenAmount::zero(), None)
+    }
+
+    async fn kill_subnet(&self, subnet: SubnetID, _from: Address) -> Result<()> {
+        let mut state = self.lock();
+        let s = state.subnet_mut(&subnet)?;
+        if s.validators.values().any(|v| !v.collateral.is_zero()) {
+            bail!("subnet {subnet} still has validators");
+        }
+        s.killed = true;
+        Ok(())
+    }
+
+    async fn list_child_subnets(
+        &self,
+        _gateway_addr: Address,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
+        let state = self.lock();
+        Ok(state
+            .subnets
+            .iter()
+            .filter(|(_, s)| !s.killed)
+            .map(|(id, s)| {
+                let info = SubnetInfo {
+                    id: id.clone(),
+                    stake: s
+                        .validators
+                        .values()
+                        .fold(TokenAmount::zero(), |acc, v| acc + v.collateral.clone()),
+                    circ_supply: s.circ_supply.clone(),
+                    genesis_epoch: s.genesis_epoch,
+                };
+                (id.clone(), info)
+            })
+            .collect())
+    }
+
+    /// Collateral is returned as soon as a validator leaves, so there is nothing to claim.
+    async fn claim_collateral(&self, subnet: SubnetID, _from: Address) -> Result<()> {
+        self.lock().subnet(&subnet)?;
+        Ok(())
+    }
+
+    async fn fund(
+        &self,
+        subnet: SubnetID,
+        _gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        self.lock().fund(&subnet, &from, &to, amount)
+    }
+
+    /// Tokens are not modelled; funds are taken from the native balance.
+    async fn fund_with_token(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        self.lock().fund(&subnet, &from, &to, amount)
+    }
+
+    async fn approve_token(
+        &self,
+        subnet: SubnetID,
+        _from: Address,
+        _amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        let state = self.lock();
+        state.subnet(&subnet)?;
+        Ok(state.head() + 1)
+    }
+
+    async fn release(
+        &self,
+        _gateway_addr: Address,
+        _from: Address,
+        _to: Address,
+        _amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        bail!("release is sent on the child subnet, not the parent")
+    }
+
+    async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
+        let mut state = self.lock();
+        state.debit(&from, &amount)?;
+        state.credit(&to, &amount);
+        Ok(())
+    }
+
+    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
+        Ok(self
+            .lock()
+            .balances
+            .get(address)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_chain_id(&self) -> Result<String> {
+        Ok(self.lock().chain_id.to_string())
+    }
+
+    async fn get_commit_sha(&self) -> Result<[u8; 32]> {
+        Ok([0; 32])
+    }
+
+    async fn get_subnet_supply_source(&self, subnet: &SubnetID) -> Result<Asset> {
+        let state = self.lock();
+        Ok(state
+            .subnet(subnet)?
+            .params
+            .as_ref()
+            .map(|p| p.supply_source.clone())
+            .unwrap_or_default())
+    }
+
+    async fn get_subnet_collateral_source(&self, subnet: &SubnetID) -> Result<Asset> {
+        let state = self.lock();
+        Ok(state
+            .subnet(subnet)?
+            .params
+            .as_ref()
+            .map(|p| p.collateral_source.clone())
+            .unwrap_or_default())
+    }
+
+    async fn get_genesis_info(&self, subnet: &SubnetID) -> Result<SubnetGenesisInfo> {
+        let state = self.lock();
+        let s = state.subnet(subnet)?;
+        let params = s
+            .params
+            .as_ref()
+            .ok_or_else(|| anyhow!("subnet {subnet} was not created through the mock"))?;
+
+        let validators = s
+            .ranked_validators()
+            .into_iter()
+            .filter(|(_, v)| v.is_active)
+            .map(|(addr, v)| Validator {
+                addr,
+                metadata: v.staking.metadata,
+                weight: v.staking.current_power,
+            })
+            .collect();
+
+        Ok(SubnetGenesisInfo {
+            chain_id: params.chain_id,
+            bottom_up_checkpoint_period: params.bottomup_check_period as u64,
+            majority_percentage: 67,
+            active_validators_limit: params.active_validators_limit,
+            min_collateral: params.min_validator_stake.clone(),
+            genesis_epoch: s.genesis_epoch,
+            validators,
+            genesis_balances: s.genesis_balances.clone(),
+            permission_mode: params.permission_mode,
+            supply_source: params.supply_source.clone(),
+            genesis_subnet_ipc_contracts_owner: params.genesis_subnet_ipc_contracts_owner,
+            f3_instance_id: params.genesis_f3_instance_id,
+        })
+    }
+
+    async fn add_bootstrap(
+        &self,
+        subnet: &SubnetID,
+        _from: &Address,
+        endpoint: String,
+    ) -> Result<()> {
+        self.lock()
+            .subnet_mut(subnet)?
+            .bootstrap_nodes
+            .push(endpoint);
+        Ok(())
+    }
+
+    async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
+        Ok(self.lock().subnet(subnet)?.bootstrap_nodes.clone())
+    }
+
+    async fn get_validator_info(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<ValidatorInfo> {
+        let state = self.lock();
+        let ranked = state.subnet(subnet)?.ranked_validators();
+        Ok(ranked
+            .into_iter()
+            .find(|(addr, _)| addr == validator)
+            .map(|(_, v)| v)
+            .unwrap_or_else(|| ValidatorInfo {
+                staking: ValidatorStakingInfo {
+                    current_power: TokenAmount::zero(),
+                    next_power: TokenAmount::zero(),
+                    metadata: Vec::new(),
+                },
+                is_active: false,
+                is_waiting: false,
+            }))
+    }
+
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<(Address, ValidatorInfo)>> {
+        Ok(self.lock().subnet(subnet)?.ranked_validators())
+    }
+
+    async fn list_subnet_active_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        let mut validators = self.list_validators(subnet).await?;
+        validators.retain(|(_, v)| v.is_active);
+        Ok(validators)
+    }
+
+    async fn list_waiting_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        let mut validators = self.list_validators(subnet).await?;
+        validators.retain(|(_, v)| v.is_waiting);
+        Ok(validators)
+    }
+
+    async fn set_federated_power(
+        &self,
+        _from: &Address,
+        subnet: &SubnetID,
+        validators: &[Address],
+        public_keys: &[Vec<u8>],
+        federated_power: &[u128],
+    ) -> Result<ChainEpoch> {
+        if validators.len() != public_keys.len() || validators.len() != federated_power.len() {
+            bail!("validators, public keys and powers must have the same length");
+        }
+
+        let mut state = self.lock();
+
+        if let Some(params) = state.subnet(subnet)?.params.as_ref() {
+            if params.permission_mode != PermissionMode::Federated {
+                bail!("subnet {subnet} is not federated");
+            }
+        }
+
+        for ((addr, pk), power) in validators.iter().zip(public_keys).zip(federated_power) {
+            state.set_validator(
+                subnet,
+                addr,
+                TokenAmount::from_atto(*power),
+                Some(pk.clone()),
+            )?;
+        }
+
+        Ok(state.head() + 1)
+    }
+}
+
+#[async_trait]
+impl SignedHeaderRelayer for MockParent {
+    async fn get_signed_header(&self, height: u64) -> Result<SignedHeader> {
+        self.lock()
+            .signed_headers
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| anyhow!("no signed header at height {height}"))
+    }
+
+    async fn submit_signed_header(
+        &self,
+        _submitter: &Address,
+        subnet_id: &SubnetID,
+        header: SignedHeader,
+        _cert: ValidatorCertificate,
+    ) -> Result<TxHash> {
+        let height = u64::try_from(header.header.height)?;
+        self.lock().submit_tx(MockTxKind::SignedHeader {
+            subnet_id: subnet_id.clone(),
+            height,
+        })
+    }
+
+    async fn get_checkpoint_signatures(&self, height: u64) -> Result<Vec<CheckpointSignatures>> {
+        Ok(self
+            .lock()
+            .checkpoint_signatures
+            .get(&height)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn submit_checkpoint_signatures(
+        &self,
+        _submitter: &Address,
+        subnet_id: &SubnetID,
+        signatures: CheckpointSignatures,
+    ) -> Result<TxHash> {
+        self.lock().submit_tx(MockTxKind::CheckpointSignatures {
+            subnet_id: subnet_id.clone(),
+            height: signatures.height,
+        })
+    }
+
+    async fn query_app_hash_breakdown(
+        &self,
+        height: ChainEpoch,
+    ) -> Result<Option<AppHashBreakdown>> {
+        Ok(self.lock().app_hash_breakdowns.get(&height).cloned())
+    }
+
+    async fn get_state_root(&self, height: ChainEpoch) -> Result<Vec<u8>> {
+        self.lock()
+            .state_roots
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| anyhow!("no state root at height {height}"))
+    }
+
+    async fn get_last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<u64> {
+        Ok(self.last_bottom_up_checkpoint_height(subnet_id))
+    }
+
+    async fn get_last_app_commitment_height(&self, subnet_id: &SubnetID) -> Result<u64> {
+        Ok(self
+            .lock()
+            .subnet(subnet_id)
+            .map(|s| s.last_app_commitment_height)
+            .unwrap_or_default())
+    }
+
+    async fn record_app_hash_breakdown(
+        &self,
+        height: ChainEpoch,
+        _submitter: &Address,
+        subnet_id: &SubnetID,
+        _breakdown: AppHashBreakdown,
+    ) -> Result<TxHash> {
+        self.lock().submit_tx(MockTxKind::AppHashBreakdown {
+            subnet_id: subnet_id.clone(),
+            height: u64::try_from(height)?,
+        })
+    }
+
+    async fn submission_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.lock();
+        Ok(state
+            .subnet(subnet_id)
+            .ok()
+            .and_then(|s| s.params.as_ref())
+            .map(|p| p.bottomup_check_period)
+            .unwrap_or(DEFAULT_SUBMISSION_PERIOD))
+    }
+
+    async fn current_epoch(&self) -> Result<ChainEpoch> {
+        Ok(self.head())
+    }
+
+    async fn get_membership_at(&self, height: ChainEpoch) -> Result<Vec<Address>> {
+        Ok(self
+            .lock()
+            .memberships
+            .range(..=height)
+            .next_back()
+            .map(|(_, validators)| validators.clone())
+            .unwrap_or_default())
+    }
+
+    async fn list_active_validators(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<Vec<(Address, ValidatorInfo)>> {
+        self.list_subnet_active_validators(subnet).await
+    }
+
+    async fn list_pending_bottom_up_batch_commitments(
+        &self,
+        subnet_id: &SubnetID,
+    ) -> Result<Vec<ListPendingCommitmentsEntry>> {
+        Ok(self
+            .lock()
+            .subnet(subnet_id)
+            .map(|s| s.pending_commitments.clone())
+            .unwrap_or_default())
+    }
+
+    /// Message inclusion proofs are not modelled.
+    async fn make_next_bottom_up_batch_inclusions(
+        &self,
+        _current: &ListPendingCommitmentsEntry,
+    ) -> Result<Vec<Inclusion>> {
+        Ok(Vec::new())
+    }
+
+    async fn execute_bottom_up_batch(
+        &self,
+        _submitter: &Address,
+        _subnet_id: &SubnetID,
+        _height: ChainEpoch,
+        _inclusions: Vec<Inclusion>,
+    ) -> Result<TxHash> {
+        self.lock().submit_tx(MockTxKind::BottomUpBatch)
+    }
+
+    async fn transaction_status(&self, tx_hash: &TxHash) -> Result<TxStatus> {
+        Ok(self
+            .lock()
+            .txs
+            .get(tx_hash)
+            .map(|tx| tx.status)
+            .unwrap_or(TxStatus::Dropped))
+    }
+
+    /// Transactions which made it to the mempool always succeed.
+    async fn revert_reason(&self, _tx_hash: &TxHash) -> Result<Option<ContractRevert>> {
+        Ok(None)
+    }
+
+    async fn pending_signed_header_submissions(&self, subnet_id: &SubnetID) -> Result<Vec<TxHash>> {
+        let state = self.lock();
+        Ok(state
+            .mempool
+            .iter()
+            .filter(|h| {
+                state.txs.get(h).is_some_and(|tx| match tx.kind {
+                    MockTxKind::SignedHeader {
+                        subnet_id: ref s, ..
+                    }
+                    | MockTxKind::CheckpointSignatures {
+                        subnet_id: ref s, ..
+                    } => s == subnet_id,
+                    _ => false,
+                })
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn bump_transaction(
+        &self,
+        _submitter: &Address,
+        tx_hash: &TxHash,
+        _bump_percent: u64,
+    ) -> Result<TxHash> {
+        let mut state = self.lock();
+        let tx = state
+            .txs
+            .get_mut(tx_hash)
+            .filter(|tx| tx.status == TxStatus::Pending)
+            .ok_or_else(|| anyhow!("transaction {tx_hash:?} is not pending"))?;
+
+        tx.status = TxStatus::Dropped;
+        let kind = tx.kind.clone();
+
+        state.mempool.retain(|h| h != tx_hash);
+        state.submit_tx(kind)
+    }
+}
+
+/// Activity rollups are not modelled, so there are never any rewards to claim.
+#[async_trait]
+impl ValidatorRewarder for MockParent {
+    async fn query_reward_claims(
+        &self,
+        _validator_addr: &Address,
+        _from_checkpoint: ChainEpoch,
+        _to_checkpoint: ChainEpoch,
+    ) -> Result<Vec<(u64, ValidatorClaim)>> {
+        Ok(Vec::new())
+    }
+
+    async fn query_validator_rewards(
+        &self,
+        _validator: &Address,
+        _from_checkpoint: ChainEpoch,
+        _to_checkpoint: ChainEpoch,
+    ) -> Result<Vec<(u64, ValidatorData)>> {
+        Ok(Vec::new())
+    }
+
+    async fn batch_subnet_claim(
+        &self,
+        _submitter: &Address,
+        _reward_claim_subnet: &SubnetID,
+        _reward_origin_subnet: &SubnetID,
+        _claims: Vec<(u64, ValidatorClaim)>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::staking::PowerOperation;
+    use ipc_api::subnet_id::SubnetID;
+
+    use super::{MockParent, NULL_ROUND_ERR_MSG};
+    use crate::manager::{SubnetManager, TopDownFinalityQuery};
+
+    fn child() -> SubnetID {
+        SubnetID::new(123, vec![Address::new_id(1001)])
+    }
+
+    #[tokio::test]
+    async fn test_events_are_included_in_next_block() {
+        let parent = MockParent::new(child(), 10);
+        let alice = Address::new_id(100);
+        parent.credit(alice, TokenAmount::from_whole(10));
+
+        let epoch = parent
+            .fund(
+                child(),
+                Address::new_id(64),
+                alice,
+                alice,
+                TokenAmount::from_whole(1),
+            )
+            .await
+            .unwrap();
+        let joined = parent
+            .join_subnet(child(), alice, TokenAmount::from_whole(5), vec![1, 2, 3])
+            .await
+            .unwrap();
+
+        assert_eq!(epoch, 11);
+        assert_eq!(joined, 11);
+        assert_eq!(parent.produce_null_round(), 11);
+        assert_eq!(parent.produce_block(), 12);
+
+        let err = parent.get_block_hash(11).await.unwrap_err();
+        assert!(err.to_string().contains(NULL_ROUND_ERR_MSG));
+
+        let msgs = parent.get_top_down_msgs(&child(), 12).await.unwrap();
+        assert_eq!(msgs.value.len(), 1);
+        assert_eq!(msgs.value[0].local_nonce, 0);
+
+        let changes = parent.get_validator_changeset(&child(), 12).await.unwrap();
+        assert_eq!(changes.value.len(), 2);
+        assert!(matches!(
+            changes.value[0].change.op,
+            PowerOperation::SetMetadata
+        ));
+        assert!(matches!(
+            changes.value[1].change.op,
+            PowerOperation::SetPower
+        ));
+        assert_eq!(changes.value[1].configuration_number, 2);
+
+        let hash = parent.get_block_hash(12).await.unwrap();
+        let prev = parent.get_block_hash(10).await.unwrap();
+        assert_eq!(hash.parent_block_hash, prev.block_hash);
+        assert_eq!(msgs.block_hash, hash.block_hash);
+
+        assert_eq!(
+            parent.wallet_balance(&alice).await.unwrap(),
+            TokenAmount::from_whole(4)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reorg_replaces_blocks() {
+        let parent = MockParent::new(child(), 0);
+        let alice = Address::new_id(100);
+        parent.credit(alice, TokenAmount::from_whole(10));
+
+        parent
+            .fund(
+                child(),
+                Address::new_id(64),
+                alice,
+                alice,
+                TokenAmount::from_whole(1),
+            )
+            .await
+            .unwrap();
+
+        let height = parent.produce_blocks(3);
+        assert_eq!(height, 3);
+
+        let before = parent.get_block_hash(1).await.unwrap().block_hash;
+        assert_eq!(
+            parent
+                .get_top_down_msgs(&child(), 1)
+                .await
+                .unwrap()
+                .value
+                .len(),
+            1
+        );
+
+        parent.reorg(3).unwrap();
+        assert_eq!(parent.head(), 0);
+        assert!(parent.get_block_hash(1).await.is_err());
+
+        parent.produce_null_round();
+        parent.produce_block();
+
+        let after = parent.get_block_hash(2).await.unwrap().block_hash;
+        assert_ne!(before, after);
+
+        // The message was re-included in the new fork.
+        let msgs = parent.get_top_down_msgs(&child(), 2).await.unwrap();
+        assert_eq!(msgs.value.len(), 1);
+        assert_eq!(msgs.value[0].local_nonce, 0);
+
+        assert!(parent.reorg(3).is_err());
+    }
+}
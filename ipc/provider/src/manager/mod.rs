@@ -2,11 +2,15 @@
 // SPDX-License-Identifier: MIT
 pub use crate::lotus::message::ipc::SubnetInfo;
 pub use evm::{EthManager, EthSubnetManager};
+pub use fvm::LotusSubnetManager;
 pub use subnet::{
-    GetBlockHashResult, SignedHeaderRelayer, SubnetGenesisInfo, SubnetManager,
-    TopDownFinalityQuery, TopDownQueryPayload,
+    ContractRevert, GetBlockHashResult, SignedHeaderRelayer, SubnetGenesisInfo, SubnetManager,
+    TopDownFinalityQuery, TopDownQueryPayload, TxHash, TxStatus, NULL_ROUND_ERR_MSG,
 };
 
 pub mod cometbft;
 pub mod evm;
+pub mod fvm;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
 mod subnet;
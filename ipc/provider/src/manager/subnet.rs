@@ -12,6 +12,7 @@ use ipc_actors_abis::subnet_actor_activity_facet::ValidatorClaim;
 use ipc_actors_abis::subnet_actor_checkpointing_facet::Inclusion;
 use ipc_actors_abis::subnet_actor_getter_facet::ListPendingCommitmentsEntry;
 use ipc_api::checkpoint::consensus::ValidatorData;
+use ipc_api::checkpoint::CheckpointSignatures;
 use ipc_api::cross::IpcEnvelope;
 use ipc_api::staking::{PowerChangeRequest, ValidatorInfo};
 use ipc_api::subnet::{Asset, ConstructParams, PermissionMode};
@@ -248,6 +249,9 @@ pub trait TopDownFinalityQuery: Send + Sync {
     async fn genesis_epoch(&self, subnet_id: &SubnetID) -> Result<ChainEpoch>;
     /// Returns the chain head height
     async fn chain_head_height(&self) -> Result<ChainEpoch>;
+    /// Returns the height of the latest block the chain itself considers final, i.e. the
+    /// `finalized` block tag, or `None` if the chain doesn't report one.
+    async fn finalized_chain_head_height(&self) -> Result<Option<ChainEpoch>>;
     /// Returns the list of top down messages
     async fn get_top_down_msgs(
         &self,
@@ -266,6 +270,50 @@ pub trait TopDownFinalityQuery: Send + Sync {
     async fn latest_parent_finality(&self) -> Result<ChainEpoch>;
 }
 
+/// Hash of a transaction sent to a subnet.
+pub type TxHash = ethers::types::H256;
+
+/// The error returned by [`TopDownFinalityQuery::get_block_hash`] for epochs without a block,
+/// which the top-down syncer recognises to skip null rounds; it is the same as Lotus returns.
+pub const NULL_ROUND_ERR_MSG: &str = "requested epoch was a null round";
+
+/// The inclusion status of a transaction sent to a subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Known to the node, but not included in a block yet.
+    Pending,
+    /// Included in a block, successfully or reverted.
+    Included { height: ChainEpoch, success: bool },
+    /// Not known to the node, e.g. it has been evicted from the mempool or replaced.
+    Dropped,
+}
+
+/// A contract call which reverted, or would revert, decoded with the error ABIs of the
+/// IPC contracts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractRevert {
+    /// The name of the custom error, or `RevertString`, `Panic` for the standard ones.
+    pub name: String,
+    pub message: Option<String>,
+}
+
+impl std::fmt::Display for ContractRevert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "contract reverted with {}: {message}", self.name),
+            None => write!(f, "contract reverted with {}", self.name),
+        }
+    }
+}
+
+impl std::error::Error for ContractRevert {}
+
+/// Relays bottom-up checkpoints from a child subnet to its parent.
+///
+/// The methods submitting transactions return as soon as the transaction has been
+/// accepted into the mempool; use [`SignedHeaderRelayer::transaction_status`] to
+/// follow up on its inclusion. Before sending, they simulate the call and fail with
+/// a [`ContractRevert`] error, without paying for the transaction, if it would revert.
 #[async_trait]
 pub trait SignedHeaderRelayer: Send + Sync {
     async fn get_signed_header(&self, height: u64) -> Result<SignedHeader>;
@@ -276,7 +324,21 @@ pub trait SignedHeaderRelayer: Send + Sync {
         subnet_id: &SubnetID,
         header: SignedHeader,
         cert: ValidatorCertificate,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxHash>;
+
+    /// Gets the checkpoint signatures the validators gossiped at a checkpoint height,
+    /// one entry for each app hash that was signed, or nothing if the subnet node has not
+    /// collected any. The signers are not filtered by power; that is up to the caller.
+    async fn get_checkpoint_signatures(&self, height: u64) -> Result<Vec<CheckpointSignatures>>;
+
+    /// Submits a checkpoint certified by a quorum of validator signatures,
+    /// as an alternative to [`SignedHeaderRelayer::submit_signed_header`].
+    async fn submit_checkpoint_signatures(
+        &self,
+        submitter: &Address,
+        subnet_id: &SubnetID,
+        signatures: CheckpointSignatures,
+    ) -> Result<TxHash>;
 
     async fn query_app_hash_breakdown(
         &self,
@@ -295,12 +357,15 @@ pub trait SignedHeaderRelayer: Send + Sync {
         submitter: &Address,
         subnet_id: &SubnetID,
         breakdown: AppHashBreakdown,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxHash>;
 
     async fn submission_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch>;
 
     async fn current_epoch(&self) -> Result<ChainEpoch>;
 
+    /// The addresses of the validators of this subnet, as recorded by its gateway at the given height.
+    async fn get_membership_at(&self, height: ChainEpoch) -> Result<Vec<Address>>;
+
     async fn list_active_validators(
         &self,
         subnet: &SubnetID,
@@ -326,7 +391,26 @@ pub trait SignedHeaderRelayer: Send + Sync {
         subnet_id: &SubnetID,
         height: ChainEpoch,
         inclusions: Vec<Inclusion>,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxHash>;
+
+    /// Checks whether a previously sent transaction has been included.
+    async fn transaction_status(&self, tx_hash: &TxHash) -> Result<TxStatus>;
+
+    /// Decodes the reason an included transaction reverted, if it did.
+    async fn revert_reason(&self, tx_hash: &TxHash) -> Result<Option<ContractRevert>>;
+
+    /// Lists the checkpoint submissions for the subnet waiting in the mempool,
+    /// by any submitter.
+    async fn pending_signed_header_submissions(&self, subnet_id: &SubnetID) -> Result<Vec<TxHash>>;
+
+    /// Replaces a pending transaction with one using the same nonce and gas fees
+    /// increased by at least `bump_percent`, returning the hash of the replacement.
+    async fn bump_transaction(
+        &self,
+        submitter: &Address,
+        tx_hash: &TxHash,
+        bump_percent: u64,
+    ) -> Result<TxHash>;
 }
 
 /// The validator reward related functions, such as check reward and claim reward for mining blocks
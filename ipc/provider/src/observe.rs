@@ -5,14 +5,27 @@ use ipc_observability::{
     impl_traceable, impl_traceables, lazy_static, register_metrics, serde::HexEncodableBlockHash,
     Recordable, TraceLevel, Traceable,
 };
-use prometheus::{register_int_gauge, IntGauge, Registry};
+use prometheus::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge, Registry};
 
 register_metrics! {
     BOTTOMUP_CHECKPOINT_FINALIZED_HEIGHT: IntGauge
         = register_int_gauge!("bottomup_checkpoint_finalized_height", "Height of the checkpoint finalized");
+    RELAYER_TX_BUMPED: IntCounterVec
+        = register_int_counter_vec!("relayer_tx_bumped", "Number of stuck relayer transactions replaced with higher fees", &["kind"]);
+    RELAYER_SUBMISSION_SKIPPED: IntCounterVec
+        = register_int_counter_vec!("relayer_submission_skipped", "Number of relayer submissions skipped to avoid competing or reverting", &["kind", "reason"]);
+    RELAYER_TX_REVERTED: IntCounterVec
+        = register_int_counter_vec!("relayer_tx_reverted", "Number of relayer transactions reverted on the parent, by decoded error", &["kind", "error"]);
 }
 
 impl_traceables!(TraceLevel::Info, "Bottomup", CheckpointSubmitted);
+impl_traceables!(
+    TraceLevel::Warn,
+    "Bottomup",
+    RelayerTxBumped,
+    RelayerTxReverted
+);
+impl_traceables!(TraceLevel::Info, "Bottomup", RelayerSubmissionSkipped);
 
 #[derive(Debug)]
 pub struct CheckpointSubmitted {
@@ -26,6 +39,59 @@ impl Recordable for CheckpointSubmitted {
     }
 }
 
+#[derive(Debug)]
+pub struct RelayerTxBumped {
+    pub kind: String,
+    pub height: i64,
+    pub bumps: u32,
+}
+
+impl Recordable for RelayerTxBumped {
+    fn record_metrics(&self) {
+        RELAYER_TX_BUMPED
+            .with_label_values(&[self.kind.as_str()])
+            .inc();
+    }
+}
+
+#[derive(Debug)]
+pub struct RelayerSubmissionSkipped {
+    pub kind: String,
+    pub height: i64,
+    /// Why the submission was skipped:
+    /// * `not_leader`: another relayer's turn in the rotation
+    /// * `pending_in_mempool`: another relayer's submission is waiting in the mempool
+    /// * `simulated_revert`: the dry run of the transaction reverted
+    /// * `no_signatures`: the subnet has not collected any checkpoint signatures yet
+    /// * `no_quorum`: the signatures of the active validators don't have a quorum yet
+    pub reason: &'static str,
+    /// The decoded error, if the dry run reverted.
+    pub error: Option<String>,
+}
+
+impl Recordable for RelayerSubmissionSkipped {
+    fn record_metrics(&self) {
+        RELAYER_SUBMISSION_SKIPPED
+            .with_label_values(&[self.kind.as_str(), self.reason])
+            .inc();
+    }
+}
+
+#[derive(Debug)]
+pub struct RelayerTxReverted {
+    pub kind: String,
+    pub height: i64,
+    pub error: String,
+}
+
+impl Recordable for RelayerTxReverted {
+    fn record_metrics(&self) {
+        RELAYER_TX_REVERTED
+            .with_label_values(&[self.kind.as_str(), self.error.as_str()])
+            .inc();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,5 +111,24 @@ mod tests {
             height: 1,
             hash: HexEncodableBlockHash(hash.clone()),
         });
+
+        emit(RelayerTxBumped {
+            kind: "SignedHeader".to_string(),
+            height: 1,
+            bumps: 1,
+        });
+
+        emit(RelayerSubmissionSkipped {
+            kind: "SignedHeader".to_string(),
+            height: 1,
+            reason: "simulated_revert",
+            error: Some("BottomUpCheckpointAlreadySubmitted".to_string()),
+        });
+
+        emit(RelayerTxReverted {
+            kind: "SignedHeader".to_string(),
+            height: 1,
+            error: "BottomUpCheckpointAlreadySubmitted".to_string(),
+        });
     }
 }
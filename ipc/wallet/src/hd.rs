@@ -0,0 +1,311 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Hierarchical deterministic (BIP-39/BIP-32/BIP-44) key derivation.
+//!
+//! A single mnemonic can back up any number of EVM and FVM accounts, which are derived
+//! along the BIP-44 paths of their coin type: `m/44'/60'/0'/0/{index}` for Ethereum and
+//! `m/44'/461'/0'/0/{index}` for Filecoin.
+//!
+//! The mnemonic is stored in plaintext, like the keys in the EVM keystore, so anyone who can
+//! read the file can derive every key. Setting a BIP-39 passphrase, which is never stored,
+//! means the file alone is not enough.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use coins_bip39::{English, Mnemonic};
+use fs_err as fs;
+use fvm_shared::crypto::signature::SignatureType;
+use zeroize::Zeroize;
+
+use crate::evm::KeyInfo as EvmKeyInfo;
+use crate::KeyInfo;
+
+/// SLIP-44 coin type of Ethereum.
+pub const EVM_COIN_TYPE: u32 = 60;
+/// SLIP-44 coin type of Filecoin.
+pub const FIL_COIN_TYPE: u32 = 461;
+
+/// Name of the file in the keystore directory holding the mnemonic.
+pub const DEFAULT_MNEMONIC_NAME: &str = "mnemonic";
+
+/// Word counts accepted by BIP-39.
+pub const MNEMONIC_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// The BIP-44 derivation path of the account at `index` for a coin type.
+pub fn derivation_path(coin_type: u32, index: u32) -> String {
+    format!("m/44'/{coin_type}'/0'/0/{index}")
+}
+
+/// A wallet deriving all its keys from a BIP-39 mnemonic.
+pub struct HdWallet {
+    mnemonic: Mnemonic<English>,
+    passphrase: Option<String>,
+}
+
+impl HdWallet {
+    /// Generate a new random mnemonic with the given number of words.
+    pub fn generate(word_count: usize) -> Result<Self> {
+        if !MNEMONIC_WORD_COUNTS.contains(&word_count) {
+            return Err(anyhow!(
+                "invalid mnemonic word count {word_count}, expected one of {MNEMONIC_WORD_COUNTS:?}"
+            ));
+        }
+        let mnemonic = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), word_count)
+            .map_err(|e| anyhow!("failed to generate mnemonic: {e}"))?;
+        Ok(Self {
+            mnemonic,
+            passphrase: None,
+        })
+    }
+
+    /// Import an existing mnemonic phrase, checking its words and checksum.
+    pub fn from_phrase(phrase: &str) -> Result<Self> {
+        let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mnemonic = Mnemonic::<English>::new_from_phrase(&phrase)
+            .map_err(|e| anyhow!("invalid mnemonic: {e}"))?;
+        Ok(Self {
+            mnemonic,
+            passphrase: None,
+        })
+    }
+
+    /// Read the mnemonic phrase from a file.
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let mut phrase = fs::read_to_string(path).context("failed to read mnemonic")?;
+        let wallet = Self::from_phrase(&phrase);
+        phrase.zeroize();
+        wallet
+    }
+
+    /// Write the mnemonic phrase to a file readable only by the user.
+    ///
+    /// An existing mnemonic is never overwritten, as that would lose access to the keys
+    /// derived from it. The phrase is not encrypted; see the module docs.
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+
+        // Create the file with the right permissions, so it's never readable by others.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            const USER_ONLY_MODE: u32 = 0o600;
+            options.mode(USER_ONLY_MODE);
+        }
+
+        let mut file = match options.open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                bail!("mnemonic already exists at {path:?}")
+            }
+            Err(e) => return Err(e).with_context(|| format!("failed to create {path:?}")),
+        };
+
+        let mut phrase = self.phrase();
+        let written = file.write_all(phrase.as_bytes());
+        phrase.zeroize();
+        Ok(written?)
+    }
+
+    /// Set the optional BIP-39 passphrase, which is mixed into the seed.
+    pub fn with_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// The mnemonic phrase, to be backed up by the user.
+    pub fn phrase(&self) -> String {
+        self.mnemonic.to_phrase()
+    }
+
+    /// Derive the secp256k1 private key at an arbitrary BIP-32 path.
+    pub fn derive_secp256k1(&self, path: &str) -> Result<Vec<u8>> {
+        let xpriv = self
+            .mnemonic
+            .derive_key(path, self.passphrase.as_deref())
+            .map_err(|e| anyhow!("failed to derive key at {path}: {e}"))?;
+        let key: &coins_bip32::prelude::SigningKey = xpriv.as_ref();
+        Ok(key.to_bytes().to_vec())
+    }
+
+    /// Derive the EVM key of the account at `index`.
+    pub fn derive_evm_key(&self, index: u32) -> Result<EvmKeyInfo> {
+        let private_key = self.derive_secp256k1(&derivation_path(EVM_COIN_TYPE, index))?;
+        Ok(EvmKeyInfo::new(private_key))
+    }
+
+    /// Derive the FVM key of the account at `index`.
+    ///
+    /// Only secp256k1 keys can be derived: BLS keys need EIP-2333 rather than BIP-32,
+    /// which we don't support, and a homegrown scheme would not be recoverable elsewhere.
+    pub fn derive_fvm_key(&self, key_type: SignatureType, index: u32) -> Result<KeyInfo> {
+        if key_type != SignatureType::Secp256k1 {
+            bail!("only secp256k1 keys can be derived from a mnemonic, not {key_type:?}");
+        }
+        let private_key = self.derive_secp256k1(&derivation_path(FIL_COIN_TYPE, index))?;
+        Ok(KeyInfo::new(key_type, private_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use base64::Engine;
+    use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::SignatureType;
+
+    use crate::hd::{derivation_path, HdWallet, EVM_COIN_TYPE, FIL_COIN_TYPE};
+    use crate::{Key, KeyInfo};
+
+    /// The well known development mnemonic used by Hardhat and Anvil.
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    /// Private keys of the first accounts derived by Hardhat and Anvil from [`TEST_MNEMONIC`].
+    const TEST_EVM_KEYS: [&str; 3] = [
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        "5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    ];
+
+    #[test]
+    fn derive_evm_test_vectors() {
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        for (i, expected) in TEST_EVM_KEYS.iter().enumerate() {
+            let key = wallet.derive_evm_key(i as u32).unwrap();
+            assert_eq!(hex::encode(key.private_key()), *expected, "account {i}");
+        }
+    }
+
+    #[cfg(feature = "with-ethers")]
+    #[test]
+    fn derive_evm_address() {
+        use crate::EthKeyAddress;
+        use std::str::FromStr;
+
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        let addr = EthKeyAddress::try_from(wallet.derive_evm_key(0).unwrap()).unwrap();
+        assert_eq!(
+            addr,
+            EthKeyAddress::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap()
+        );
+    }
+
+    /// The example mnemonic of Zondax's `filecoin-signing-tools`, which the Glif wallet builds on.
+    const ZONDAX_MNEMONIC: &str =
+        "equip will roof matter pink blind book anxiety banner elbow sun young";
+
+    /// The key and address `filecoin-signing-tools` derives from [`ZONDAX_MNEMONIC`] in its
+    /// `key_derive` example; note that its path has non-hardened account and change levels.
+    const ZONDAX_PATH: &str = "m/44'/461'/0/0/0";
+    const ZONDAX_PRIVATE_KEY_BASE64: &str = "8VcW07ADswS4BV2cxi5rnIadVsyTDDhY1NfDH19T8Uo=";
+    const ZONDAX_ADDRESS: &str = "f1d2xrzcslx7xlbbylc5c3d5lvandqw4iwl6epxba";
+
+    #[test]
+    fn derive_filecoin_test_vector() {
+        let wallet = HdWallet::from_phrase(ZONDAX_MNEMONIC).unwrap();
+        let private_key = wallet.derive_secp256k1(ZONDAX_PATH).unwrap();
+
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD.encode(&private_key),
+            ZONDAX_PRIVATE_KEY_BASE64
+        );
+
+        let key = Key::try_from(KeyInfo::new(SignatureType::Secp256k1, private_key)).unwrap();
+        assert_eq!(key.address, Address::from_str(ZONDAX_ADDRESS).unwrap());
+    }
+
+    #[test]
+    fn derive_fvm_keys_along_the_filecoin_path() {
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        for i in 0..3 {
+            let key_info = wallet.derive_fvm_key(SignatureType::Secp256k1, i).unwrap();
+            assert_eq!(
+                key_info.private_key(),
+                &wallet
+                    .derive_secp256k1(&derivation_path(FIL_COIN_TYPE, i))
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn derive_fvm_keys_deterministically() {
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        let again = HdWallet::from_phrase(&format!("  {TEST_MNEMONIC}\n")).unwrap();
+
+        let k0 = wallet.derive_fvm_key(SignatureType::Secp256k1, 0).unwrap();
+        let k0_again = again.derive_fvm_key(SignatureType::Secp256k1, 0).unwrap();
+        assert_eq!(k0.private_key(), k0_again.private_key());
+
+        // The Filecoin coin type yields different keys than the Ethereum one.
+        let evm = wallet.derive_evm_key(0).unwrap();
+        assert_ne!(k0.private_key().as_slice(), evm.private_key());
+
+        assert!(wallet.derive_fvm_key(SignatureType::BLS, 0).is_err());
+    }
+
+    #[test]
+    fn passphrase_changes_keys() {
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        let protected = HdWallet::from_phrase(TEST_MNEMONIC)
+            .unwrap()
+            .with_passphrase("secret");
+        let path = derivation_path(EVM_COIN_TYPE, 0);
+        assert_ne!(
+            wallet.derive_secp256k1(&path).unwrap(),
+            protected.derive_secp256k1(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn mnemonic_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::hd::DEFAULT_MNEMONIC_NAME);
+
+        let wallet = HdWallet::from_phrase(TEST_MNEMONIC).unwrap();
+        wallet.write_to_file(&path).unwrap();
+
+        let read = HdWallet::read_from_file(&path).unwrap();
+        assert_eq!(read.phrase(), TEST_MNEMONIC);
+
+        // Don't overwrite an existing mnemonic.
+        let other = HdWallet::generate(12).unwrap();
+        assert!(other.write_to_file(&path).is_err());
+        assert_eq!(
+            HdWallet::read_from_file(&path).unwrap().phrase(),
+            TEST_MNEMONIC
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn generated_mnemonic_roundtrip() {
+        let wallet = HdWallet::generate(24).unwrap();
+        let phrase = wallet.phrase();
+        assert_eq!(phrase.split(' ').count(), 24);
+
+        let imported = HdWallet::from_phrase(&phrase).unwrap();
+        assert_eq!(
+            wallet.derive_evm_key(0).unwrap(),
+            imported.derive_evm_key(0).unwrap()
+        );
+
+        assert!(HdWallet::generate(13).is_err());
+        assert!(HdWallet::from_phrase("test test test").is_err());
+    }
+}
@@ -8,6 +8,9 @@ use serde::{Deserialize, Serialize};
 
 mod evm;
 mod fvm;
+mod hd;
+#[cfg(feature = "with-ethers")]
+mod signer;
 
 #[cfg(feature = "with-ethers")]
 pub use crate::evm::{random_eth_key_info, EthKeyAddress};
@@ -16,6 +19,14 @@ pub use crate::evm::{
     DEFAULT_KEYSTORE_NAME,
 };
 pub use crate::fvm::*;
+pub use crate::hd::{
+    derivation_path, HdWallet, DEFAULT_MNEMONIC_NAME, EVM_COIN_TYPE, FIL_COIN_TYPE,
+    MNEMONIC_WORD_COUNTS,
+};
+#[cfg(feature = "with-ethers")]
+pub use crate::signer::{
+    y_parity, EthersSigner, LocalSigner, RemoteSigner, RemoteSignerConfig, Signer, SignerError,
+};
 
 /// WalletType determines the kind of keys and wallets
 /// supported in the keystore
@@ -0,0 +1,71 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Signer backed by a private key from one of the local key stores.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::signers::{LocalWallet, Signer as _};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Signature};
+use fvm_shared::crypto::signature::SignatureType;
+
+use crate::evm::{KeyInfo as EvmKeyInfo, KeyStore as EvmKeyStore};
+use crate::signer::Signer;
+use crate::EthKeyAddress;
+
+/// Signs with a private key held in memory.
+#[derive(Clone, Debug)]
+pub struct LocalSigner {
+    wallet: LocalWallet,
+}
+
+impl LocalSigner {
+    /// Create a signer from raw secp256k1 private key bytes.
+    pub fn new(private_key: &[u8]) -> Result<Self> {
+        let wallet = LocalWallet::from_bytes(private_key)?;
+        Ok(Self { wallet })
+    }
+
+    /// Create a signer from a key in the EVM key store.
+    pub fn from_key_info(info: &EvmKeyInfo) -> Result<Self> {
+        Self::new(info.private_key())
+    }
+
+    /// Look up the key of an address in the EVM key store.
+    pub fn from_keystore<S>(keystore: &S, addr: &EthKeyAddress) -> Result<Self>
+    where
+        S: EvmKeyStore<Key = EthKeyAddress>,
+    {
+        let info = keystore
+            .get(addr)?
+            .ok_or_else(|| anyhow!("address {addr} does not have private key in key store"))?;
+        Self::from_key_info(&info)
+    }
+
+    /// Create a signer from a key in the FVM key store; only secp256k1 keys are supported.
+    pub fn from_fvm_key_info(info: &crate::KeyInfo) -> Result<Self> {
+        if *info.key_type() != SignatureType::Secp256k1 {
+            return Err(anyhow!(
+                "only secp256k1 keys can be used as signers, got {:?}",
+                info.key_type()
+            ));
+        }
+        Self::new(info.private_key())
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        Ok(self.wallet.sign_transaction_sync(tx)?)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.wallet.sign_message(message).await?)
+    }
+}
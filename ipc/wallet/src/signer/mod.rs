@@ -0,0 +1,148 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Pluggable transaction signers.
+//!
+//! The [`Signer`] trait abstracts over where a key lives: in one of the local key stores,
+//! or behind a remote signing service (e.g. web3signer fronting an HSM or a KMS), so that
+//! the relayer, the CLI and validators don't have to hold private keys in process.
+
+mod local;
+mod remote;
+
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Signature};
+
+pub use local::LocalSigner;
+pub use remote::{RemoteSigner, RemoteSignerConfig};
+
+/// A signer holding the secp256k1 key of a single account.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// The Ethereum address of the account the signer signs for.
+    fn address(&self) -> Address;
+
+    /// Sign a transaction. The chain ID is expected to be already set on the transaction.
+    ///
+    /// The `v` of the signature is in the EIP-155 format, `chain_id * 2 + 35 + y_parity`,
+    /// the same as what `ethers` wallets return; see [`y_parity`] to get the recovery ID back.
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature>;
+
+    /// Sign a message with the EIP-191 personal message prefix.
+    ///
+    /// The `v` of the signature is `27 + y_parity`.
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature>;
+}
+
+/// Recover the y-parity (the recovery ID) of a signature returned by [`Signer::sign_transaction`].
+pub fn y_parity(signature: &Signature, chain_id: u64) -> Result<u8> {
+    let offset = chain_id
+        .checked_mul(2)
+        .and_then(|v| v.checked_add(35))
+        .ok_or_else(|| anyhow!("chain ID {chain_id} is too large for EIP-155"))?;
+
+    match signature.v.checked_sub(offset) {
+        Some(y @ (0 | 1)) => Ok(y as u8),
+        _ => bail!(
+            "signature v {} is not an EIP-155 value for chain ID {chain_id}",
+            signature.v
+        ),
+    }
+}
+
+/// Convert the `v` of a signature from the y-parity or the legacy `27 + y_parity` format to EIP-155.
+pub(crate) fn to_eip155_signature(mut signature: Signature, chain_id: u64) -> Result<Signature> {
+    let y = match signature.v {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        _ => {
+            // Already EIP-155; check that it's for the right chain.
+            y_parity(&signature, chain_id)?;
+            return Ok(signature);
+        }
+    };
+    signature.v = ethers::signers::to_eip155_v(y as u8, chain_id);
+    Ok(signature)
+}
+
+/// The error returned by [`EthersSigner`], which `ethers` requires to be a `std::error::Error`.
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct SignerError(#[from] anyhow::Error);
+
+/// Adapter to use any [`Signer`] with the `ethers` `SignerMiddleware`.
+#[derive(Clone)]
+pub struct EthersSigner {
+    inner: Arc<dyn Signer>,
+    chain_id: u64,
+}
+
+impl EthersSigner {
+    pub fn new(inner: Arc<dyn Signer>, chain_id: u64) -> Self {
+        Self { inner, chain_id }
+    }
+
+    pub fn inner(&self) -> &Arc<dyn Signer> {
+        &self.inner
+    }
+}
+
+impl Debug for EthersSigner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EthersSigner")
+            .field("address", &self.inner.address())
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ethers::signers::Signer for EthersSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> std::result::Result<Signature, Self::Error> {
+        Ok(self.inner.sign_message(message.as_ref()).await?)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> std::result::Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        Ok(self.inner.sign_transaction(&tx).await?)
+    }
+
+    /// Typed data is not supported: remote signers only sign transactions and messages
+    /// they can show to their operators, so we don't ask any backend for raw digests.
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> std::result::Result<Signature, Self::Error> {
+        Err(anyhow!("signing EIP-712 typed data is not supported").into())
+    }
+
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Signer delegating to a remote signing service over HTTP.
+//!
+//! The service is expected to speak the Ethereum JSON-RPC signing methods the way
+//! web3signer exposes them: `eth_signTransaction` returns the RLP encoded signed transaction,
+//! and `eth_sign` returns the signature of an EIP-191 prefixed message.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, Signature};
+use ethers::utils::rlp::Rlp;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+
+use crate::signer::{to_eip155_signature, Signer};
+
+/// Connection details of a remote signer for one account.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RemoteSignerConfig {
+    /// The JSON-RPC endpoint of the signing service.
+    pub url: Url,
+    /// The hex encoded Ethereum address of the account the service signs for.
+    pub address: String,
+    /// Optional bearer token sent with every request.
+    pub auth_token: Option<String>,
+}
+
+/// Signs by sending requests to a remote signing service; the key never leaves the service.
+#[derive(Debug)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: Url,
+    address: Address,
+    next_id: AtomicU64,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+impl RemoteSigner {
+    pub fn new(url: Url, address: Address, auth_token: Option<&str>) -> Result<Self> {
+        let mut client = reqwest::Client::builder();
+
+        if let Some(token) = auth_token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))?;
+            value.set_sensitive(true);
+
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, value);
+
+            client = client.default_headers(headers);
+        }
+
+        Ok(Self {
+            client: client.build()?,
+            url,
+            address,
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    pub fn from_config(config: &RemoteSignerConfig) -> Result<Self> {
+        let address = Address::from_str(&config.address)
+            .with_context(|| format!("invalid remote signer address: {}", config.address))?;
+        Self::new(config.url.clone(), address, config.auth_token.as_deref())
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("failed to send {method} to remote signer"))?
+            .error_for_status()?
+            .json::<JsonRpcResponse<T>>()
+            .await
+            .with_context(|| format!("failed to parse {method} response of remote signer"))?;
+
+        match (response.result, response.error) {
+            (_, Some(e)) => Err(anyhow!(
+                "remote signer rejected {method}: {} (code {})",
+                e.message,
+                e.code
+            )),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(anyhow!("remote signer returned no result for {method}")),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        let chain_id = tx
+            .chain_id()
+            .ok_or_else(|| anyhow!("transaction has no chain ID"))?
+            .as_u64();
+
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+
+        let raw: Bytes = self.request("eth_signTransaction", json!([tx])).await?;
+        let (_, signature) = TypedTransaction::decode_signed(&Rlp::new(raw.as_ref()))
+            .context("failed to decode transaction signed by remote signer")?;
+
+        // Don't trust the service to have signed what we asked for.
+        signature
+            .verify(tx.sighash(), self.address)
+            .context("remote signer returned a signature over a different transaction")?;
+
+        // Typed transactions carry the y-parity in the RLP; return EIP-155 like local wallets.
+        to_eip155_signature(signature, chain_id)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let data = Bytes::from(message.to_vec());
+        let raw: Bytes = self
+            .request("eth_sign", json!([self.address, data]))
+            .await?;
+        let mut signature = Signature::try_from(raw.as_ref())?;
+
+        // Some services return the y-parity rather than `27 + y_parity`.
+        if signature.v < 27 {
+            signature.v += 27;
+        }
+
+        signature
+            .verify(message, self.address)
+            .context("remote signer returned a signature over a different message")?;
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::extract::State;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use ethers::signers::{LocalWallet, Signer as _};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, Bytes, Eip1559TransactionRequest};
+    use serde_json::{json, Value};
+
+    use crate::signer::{LocalSigner, RemoteSigner, Signer};
+
+    const PRIVATE_KEY: &str = "a1e0f7c1d7f5aa4c0a6bba7b6e0b6a4ad9df5e6d32d7d3c4c2b1a0f9e8d7c6b5";
+
+    /// Minimal stand-in for web3signer which signs with a local wallet.
+    async fn handle(State(wallet): State<LocalWallet>, Json(request): Json<Value>) -> Json<Value> {
+        let id = request["id"].clone();
+        let params = &request["params"];
+
+        let result = match request["method"].as_str() {
+            Some("eth_signTransaction") => {
+                let tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+                let sig = wallet.sign_transaction_sync(&tx).unwrap();
+                json!(tx.rlp_signed(&sig))
+            }
+            Some("eth_sign") => {
+                let data: Bytes = serde_json::from_value(params[1].clone()).unwrap();
+                let sig = wallet.sign_message(data.as_ref()).await.unwrap();
+                json!(Bytes::from(sig.to_vec()))
+            }
+            _ => {
+                return Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": -32601, "message": "method not found"}
+                }))
+            }
+        };
+
+        Json(json!({"jsonrpc": "2.0", "id": id, "result": result}))
+    }
+
+    fn start_mock_signer(wallet: LocalWallet) -> url::Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", post(handle)).with_state(wallet);
+
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        format!("http://{addr}").parse().unwrap()
+    }
+
+    fn sample_tx() -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .value(1_000_000u64)
+            .nonce(7u64)
+            .gas(21_000u64)
+            .max_fee_per_gas(2_000_000_000u64)
+            .max_priority_fee_per_gas(1_000_000u64)
+            .chain_id(314159u64)
+            .into()
+    }
+
+    #[tokio::test]
+    async fn remote_signature_matches_local() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let local = LocalSigner::new(wallet.signer().to_bytes().as_slice()).unwrap();
+        let url = start_mock_signer(wallet.clone());
+        let remote = RemoteSigner::new(url, wallet.address(), Some("secret")).unwrap();
+
+        assert_eq!(remote.address(), local.address());
+
+        let tx = sample_tx();
+        assert_eq!(
+            remote.sign_transaction(&tx).await.unwrap(),
+            local.sign_transaction(&tx).await.unwrap()
+        );
+
+        assert_eq!(
+            remote.sign_message(b"hello ipc").await.unwrap(),
+            local.sign_message(b"hello ipc").await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn remote_signature_for_wrong_account_is_rejected() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let url = start_mock_signer(wallet);
+        let remote = RemoteSigner::new(url, Address::repeat_byte(0x22), None).unwrap();
+
+        assert!(remote.sign_transaction(&sample_tx()).await.is_err());
+    }
+}
@@ -59,8 +59,6 @@ pub enum ConfigError {
     InvalidNetwork(String),
     #[error("invalid bootstrap address: {0}")]
     InvalidBootstrapAddress(Multiaddr),
-    #[error("no bootstrap address")]
-    NoBootstrapAddress,
 }
 
 /// Discovery behaviour, periodically running a random lookup with Kademlia to find new peers.
@@ -85,6 +83,12 @@ pub struct Behaviour {
     lookup_interval: Interval,
     /// Buffer incoming identify requests until we have finished the bootstrap.
     bootstrap_buffer: Option<Vec<(PeerId, Info)>>,
+    /// Addresses remembered from a previous run, waiting for the bootstrap from the
+    /// static addresses to finish, so they can't crowd out the peers we learn from them.
+    remembered_buffer: Vec<(PeerId, Multiaddr)>,
+    /// Bootstrapping is started on the first poll, so that if there are no static addresses,
+    /// we can bootstrap from the peers remembered from a previous run instead.
+    pending_bootstrap: bool,
     /// Events to return when polled.
     outbox: VecDeque<Event>,
 }
@@ -119,6 +123,7 @@ impl Behaviour {
             StreamProtocol::try_from_owned(protocol_name).expect("valid protocol name");
 
         let mut bootstrap_buffer = None;
+        let mut pending_bootstrap = false;
 
         let kademlia_opt = if dc.enable_kademlia {
             let mut kad_config = kad::Config::default();
@@ -142,10 +147,7 @@ impl Behaviour {
                 for (peer_id, addr) in static_addresses.iter() {
                     kademlia.add_address(peer_id, addr.clone());
                 }
-                kademlia
-                    .bootstrap()
-                    .map_err(|_| ConfigError::NoBootstrapAddress)?;
-
+                pending_bootstrap = true;
                 bootstrap_buffer = Some(Vec::new());
             }
 
@@ -168,10 +170,47 @@ impl Behaviour {
             outbox,
             num_connections: 0,
             bootstrap_buffer,
+            remembered_buffer: Vec::new(),
+            pending_bootstrap,
             target_connections: dc.target_connections,
         })
     }
 
+    /// Add addresses remembered from a previous run to Kademlia.
+    ///
+    /// If we are bootstrapping from the static addresses, they are only added once that has
+    /// finished, like the self-identified ones; otherwise we bootstrap from them.
+    ///
+    /// Has no effect if Kademlia is disabled, in which case we only use the static addresses.
+    pub fn add_known_addresses(&mut self, addresses: Vec<(PeerId, Multiaddr)>) {
+        if addresses.is_empty() || !self.inner.is_enabled() {
+            return;
+        }
+        let addresses = addresses.into_iter().filter(|(id, _)| *id != self.peer_id);
+        if self.bootstrap_buffer.is_some() {
+            self.remembered_buffer.extend(addresses);
+        } else {
+            for (peer_id, addr) in addresses {
+                self.add_address(&peer_id, addr);
+            }
+            self.pending_bootstrap = true;
+            self.bootstrap_buffer = Some(Vec::new());
+        }
+    }
+
+    /// Add the peers buffered while bootstrapping to the routing table.
+    fn finish_bootstrap(&mut self) {
+        for (peer_id, addr) in std::mem::take(&mut self.remembered_buffer) {
+            self.add_address(&peer_id, addr);
+        }
+        if let Some(buffer) = self.bootstrap_buffer.take() {
+            debug!("Adding {} self-identified peers.", buffer.len());
+            for (peer_id, info) in buffer {
+                self.add_identified(&peer_id, info);
+            }
+        }
+    }
+
     /// Lookup a peer, unless we already know their address, so that we have a chance to connect to them later.
     pub fn background_lookup(&mut self, peer_id: PeerId) {
         if self.addresses_of_peer(peer_id).is_empty() {
@@ -335,6 +374,17 @@ impl NetworkBehaviour for Behaviour {
             return Poll::Ready(ToSwarm::GenerateEvent(ev));
         }
 
+        // Bootstrap from the static peers, or the remembered ones if there are none.
+        if self.pending_bootstrap {
+            self.pending_bootstrap = false;
+            if let Some(k) = self.inner.as_mut() {
+                if k.bootstrap().is_err() {
+                    warn!("no peers to bootstrap from");
+                    self.finish_bootstrap();
+                }
+            }
+        }
+
         // Trigger periodic queries.
         if self.lookup_interval.poll_tick(cx).is_ready() {
             if self.num_connections < self.target_connections {
@@ -375,12 +425,7 @@ impl NetworkBehaviour for Behaviour {
                         kad::Event::OutboundQueryProgressed { result, step, .. } => match result {
                             kad::QueryResult::Bootstrap(result) if step.last => {
                                 debug!("Bootstrapping finished with {result:?}");
-                                if let Some(buffer) = self.bootstrap_buffer.take() {
-                                    debug!("Adding {} self-identified peers.", buffer.len());
-                                    for (peer_id, info) in buffer {
-                                        self.add_identified(&peer_id, info)
-                                    }
-                                }
+                                self.finish_bootstrap();
                             }
                             _ => {}
                         },
@@ -10,7 +10,7 @@ use crate::hash::blake2b_256;
 use crate::observe;
 use crate::provider_cache::{ProviderDelta, SubnetProviderCache};
 use crate::provider_record::{ProviderRecord, SignedProviderRecord};
-use crate::vote_record::{SignedVoteRecord, VoteRecord};
+use crate::vote_record::SignedVoteRecord;
 use crate::Timestamp;
 use anyhow::anyhow;
 use ipc_api::subnet_id::SubnetID;
@@ -53,11 +53,18 @@ pub enum Event<V> {
     /// to trigger a lookup by the discovery module to learn the address.
     Skipped(PeerId),
 
-    /// We received a [`VoteRecord`] in one of the subnets we are providing data for.
-    ReceivedVote(Box<VoteRecord<V>>),
+    /// We received a [`SignedVoteRecord`] in one of the subnets we are providing data for.
+    ///
+    /// The signed envelope is kept so the vote can be presented as evidence later.
+    ReceivedVote(Box<SignedVoteRecord<V>>),
 
     /// We received preemptive data published in a subnet we were interested in.
     ReceivedPreemptive(SubnetID, Vec<u8>),
+
+    /// We accepted a more recent provider record from a routable peer.
+    ///
+    /// The signed envelope is kept so the record can be persisted and checked again when reloaded.
+    Accepted(Box<SignedProviderRecord>),
 }
 
 /// Configuration for [`membership::Behaviour`].
@@ -411,8 +418,8 @@ where
     /// to answer future queries about the topic.
     fn handle_message(&mut self, msg: gossipsub::Message) {
         if msg.topic == self.membership_topic.hash() {
-            match SignedProviderRecord::from_bytes(&msg.data).map(|r| r.into_record()) {
-                Ok(record) => self.handle_provider_record(record),
+            match SignedProviderRecord::from_bytes(&msg.data) {
+                Ok(signed) => self.handle_provider_record(signed),
                 Err(e) => emit(
                     observe::MembershipFailureEvent::GossipInvalidProviderRecord(
                         msg.source,
@@ -421,7 +428,7 @@ where
                 ),
             }
         } else if self.voting_topics.contains(&msg.topic) {
-            match SignedVoteRecord::from_bytes(&msg.data).map(|r| r.into_record()) {
+            match SignedVoteRecord::from_bytes(&msg.data) {
                 Ok(record) => self.handle_vote_record(record),
                 Err(e) => emit(observe::MembershipFailureEvent::GossipInvalidVoteRecord(
                     msg.source,
@@ -437,13 +444,26 @@ where
         }
     }
 
+    /// Add a provider record which didn't arrive through gossip, e.g. one remembered from a previous run.
+    ///
+    /// Call this method after the peer has been marked as routable, otherwise it is skipped.
+    pub fn add_provider_record(&mut self, signed: SignedProviderRecord) {
+        self.handle_provider_record(signed)
+    }
+
     /// Try to add a provider record to the cache.
     ///
     /// If this is the first time we receive a record from the peer,
     /// reciprocate by publishing our own.
-    fn handle_provider_record(&mut self, record: ProviderRecord) {
+    fn handle_provider_record(&mut self, signed: SignedProviderRecord) {
+        let record = signed.record();
         debug!("received provider record: {record:?}");
-        let (event, publish) = match self.provider_cache.add_provider(&record) {
+        let is_newer = match self.provider_cache.timestamp(&record.peer_id) {
+            Some(timestamp) => timestamp < record.timestamp,
+            None => true,
+        };
+
+        let (event, publish) = match self.provider_cache.add_provider(record) {
             None => {
                 emit(observe::MembershipEvent::Skipped(record.peer_id));
                 (Some(Event::Skipped(record.peer_id)), false)
@@ -455,18 +475,25 @@ where
             }
         };
 
+        let peer_id = record.peer_id;
+        let accepted = is_newer && self.provider_cache.is_routable(&peer_id);
+
         if let Some(event) = event {
             self.outbox.push_back(event);
         }
 
+        if accepted {
+            self.outbox.push_back(Event::Accepted(Box::new(signed)));
+        }
+
         if publish {
-            emit(observe::MembershipEvent::Added(record.peer_id));
-            self.publish_for_new_peer(record.peer_id)
+            emit(observe::MembershipEvent::Added(peer_id));
+            self.publish_for_new_peer(peer_id)
         }
     }
 
     /// Raise an event to tell we received a new vote.
-    fn handle_vote_record(&mut self, record: VoteRecord<V>) {
+    fn handle_vote_record(&mut self, record: SignedVoteRecord<V>) {
         self.outbox.push_back(Event::ReceivedVote(Box::new(record)))
     }
 
@@ -5,6 +5,7 @@ mod client;
 mod hash;
 mod limiter;
 mod observe;
+mod peer_store;
 mod service;
 mod timestamp;
 
@@ -21,6 +22,7 @@ pub mod missing_blocks;
 
 pub use behaviour::{ContentConfig, DiscoveryConfig, MembershipConfig, NetworkConfig};
 pub use client::{Client, Resolver};
-pub use service::{Config, ConnectionConfig, Event, NoKnownPeers, Service};
+pub use peer_store::{MemoryPeerStore, PeerBook, PeerEntry, PeerStore};
+pub use service::{build_transport, Config, ConnectionConfig, Event, NoKnownPeers, Service};
 pub use timestamp::Timestamp;
-pub use vote_record::{ValidatorKey, VoteRecord};
+pub use vote_record::{SignedVoteRecord, ValidatorKey, VoteRecord};
@@ -0,0 +1,350 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::provider_record::SignedProviderRecord;
+use crate::Timestamp;
+
+/// Maximum number of addresses we remember about a single peer.
+const MAX_ADDRESSES_PER_PEER: usize = 8;
+
+/// Everything we remember about a peer between restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerEntry {
+    /// The addresses we managed to dial the peer on, most recent first.
+    ///
+    /// Addresses peers report about themselves aren't stored, so a peer can't make us
+    /// dial arbitrary addresses after a restart.
+    pub addresses: Vec<Multiaddr>,
+    /// The last time we heard from the peer, either its addresses or its provider record.
+    pub last_seen: Timestamp,
+    /// The last provider record of the peer, as the protobuf encoding of the signed envelope,
+    /// so the signature can be checked again when it's loaded.
+    #[serde(with = "serde_bytes")]
+    pub provider_record: Option<Vec<u8>>,
+}
+
+/// Persistent storage for [`PeerEntry`] records, e.g. a database namespace.
+///
+/// The [`Service`](crate::Service) only calls it from its event loop, periodically flushing
+/// the changes of the [`PeerBook`], so implementations don't have to worry about concurrent writes.
+pub trait PeerStore: Send + 'static {
+    /// Load all the stored entries.
+    fn list(&self) -> anyhow::Result<Vec<(PeerId, PeerEntry)>>;
+    /// Insert or overwrite the entry of a peer.
+    fn put(&self, peer_id: &PeerId, entry: &PeerEntry) -> anyhow::Result<()>;
+    /// Remove the entry of a peer.
+    fn delete(&self, peer_id: &PeerId) -> anyhow::Result<()>;
+}
+
+/// [`PeerStore`] that only lives as long as the process, for testing.
+#[derive(Clone, Default)]
+pub struct MemoryPeerStore {
+    entries: Arc<Mutex<HashMap<PeerId, PeerEntry>>>,
+}
+
+impl PeerStore for MemoryPeerStore {
+    fn list(&self) -> anyhow::Result<Vec<(PeerId, PeerEntry)>> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries.iter().map(|(k, v)| (*k, v.clone())).collect())
+    }
+
+    fn put(&self, peer_id: &PeerId, entry: &PeerEntry) -> anyhow::Result<()> {
+        self.entries.lock().unwrap().insert(*peer_id, entry.clone());
+        Ok(())
+    }
+
+    fn delete(&self, peer_id: &PeerId) -> anyhow::Result<()> {
+        self.entries.lock().unwrap().remove(peer_id);
+        Ok(())
+    }
+}
+
+/// Address book of the peers we have seen, persisted to a [`PeerStore`],
+/// so that after a restart we can reconnect to the network even if the bootstrap
+/// nodes are gone, and know which peers to ask for subnet content straight away.
+///
+/// Changes are only written to the store when the book is flushed, so that a busy
+/// network doesn't turn into a write on every event.
+///
+/// Entries we haven't heard about for longer than the TTL are dropped when loaded,
+/// and if the book is full, the least recently seen peer makes room for the new one.
+pub struct PeerBook {
+    store: Box<dyn PeerStore>,
+    ttl: Duration,
+    max_peers: usize,
+    entries: HashMap<PeerId, PeerEntry>,
+    /// Peers which have changed since the last flush.
+    dirty: HashSet<PeerId>,
+    /// Peers which have been evicted since the last flush.
+    evicted: HashSet<PeerId>,
+}
+
+impl PeerBook {
+    /// Load the entries from the store, removing the ones which have expired,
+    /// and the least recently seen ones above `max_peers`.
+    pub fn load<S: PeerStore>(store: S, ttl: Duration, max_peers: usize) -> anyhow::Result<Self> {
+        let cutoff = Timestamp::now() - ttl;
+        let mut entries = HashMap::new();
+
+        for (peer_id, entry) in store.list()? {
+            if entry.last_seen < cutoff {
+                store.delete(&peer_id)?;
+            } else {
+                entries.insert(peer_id, entry);
+            }
+        }
+
+        let mut book = Self {
+            store: Box::new(store),
+            ttl,
+            max_peers,
+            entries,
+            dirty: Default::default(),
+            evicted: Default::default(),
+        };
+
+        book.evict_least_recent();
+        book.flush()?;
+
+        Ok(book)
+    }
+
+    /// Number of peers in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All the addresses we know about, to seed the discovery with.
+    pub fn addresses(&self) -> Vec<(PeerId, Multiaddr)> {
+        self.entries
+            .iter()
+            .flat_map(|(peer_id, entry)| entry.addresses.iter().map(|a| (*peer_id, a.clone())))
+            .collect()
+    }
+
+    /// The provider records which are still valid, to seed the membership cache with.
+    pub fn provider_records(&self) -> Vec<SignedProviderRecord> {
+        let cutoff = Timestamp::now() - self.ttl;
+        let mut records = Vec::new();
+        for (peer_id, entry) in self.entries.iter() {
+            let Some(bytes) = entry.provider_record.as_ref() else {
+                continue;
+            };
+            match SignedProviderRecord::from_bytes(bytes) {
+                Ok(signed)
+                    if signed.record().peer_id == *peer_id
+                        && signed.record().timestamp >= cutoff =>
+                {
+                    records.push(signed)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("invalid provider record stored for {peer_id}: {e}"),
+            }
+        }
+        records
+    }
+
+    /// Remember an address we have successfully dialled a peer on.
+    pub fn add_dialled_address(&mut self, peer_id: PeerId, mut address: Multiaddr) {
+        if let Some(Protocol::P2p(_)) = address.iter().last() {
+            address.pop();
+        }
+        self.update(peer_id, Timestamp::now(), |entry| {
+            entry.addresses.retain(|a| *a != address);
+            entry.addresses.insert(0, address);
+            entry.addresses.truncate(MAX_ADDRESSES_PER_PEER);
+        })
+    }
+
+    /// Remember the latest provider record of a peer.
+    ///
+    /// The peer is only considered seen as of the timestamp of the record, so that
+    /// reloading the same record after a restart doesn't prolong its life.
+    pub fn set_provider_record(&mut self, signed: &SignedProviderRecord) {
+        let record = signed.record();
+        let bytes = signed.envelope().clone().into_protobuf_encoding();
+        self.update(record.peer_id, record.timestamp, |entry| {
+            entry.provider_record = Some(bytes)
+        })
+    }
+
+    /// Write the changes since the last flush to the store.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        for peer_id in self.evicted.iter() {
+            self.store.delete(peer_id)?;
+        }
+        self.evicted.clear();
+
+        for peer_id in self.dirty.iter() {
+            if let Some(entry) = self.entries.get(peer_id) {
+                self.store.put(peer_id, entry)?;
+            }
+        }
+        self.dirty.clear();
+
+        Ok(())
+    }
+
+    fn update<F>(&mut self, peer_id: PeerId, seen: Timestamp, f: F)
+    where
+        F: FnOnce(&mut PeerEntry),
+    {
+        let entry = self.entries.entry(peer_id).or_insert_with(|| PeerEntry {
+            addresses: Vec::new(),
+            last_seen: Timestamp::default(),
+            provider_record: None,
+        });
+        f(entry);
+        entry.last_seen = entry.last_seen.max(seen);
+
+        self.evicted.remove(&peer_id);
+        self.dirty.insert(peer_id);
+        self.evict_least_recent();
+    }
+
+    /// Remove the least recently seen peers while the book is over capacity.
+    fn evict_least_recent(&mut self) {
+        while self.entries.len() > self.max_peers {
+            let Some(peer_id) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_seen)
+                .map(|(peer_id, _)| *peer_id)
+            else {
+                break;
+            };
+            self.entries.remove(&peer_id);
+            self.dirty.remove(&peer_id);
+            self.evicted.insert(peer_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use libp2p::{identity::Keypair, Multiaddr, PeerId};
+
+    use super::{MemoryPeerStore, PeerBook, PeerEntry, PeerStore};
+    use crate::provider_record::ProviderRecord;
+    use crate::Timestamp;
+
+    const TTL: Duration = Duration::from_secs(60);
+    const MAX_PEERS: usize = 10;
+
+    #[test]
+    fn expired_entries_are_removed_on_load() {
+        let store = MemoryPeerStore::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+
+        let fresh = PeerId::random();
+        let stale = PeerId::random();
+
+        for (peer_id, last_seen) in [
+            (fresh, Timestamp::now()),
+            (stale, Timestamp::now() - TTL * 2),
+        ] {
+            let entry = PeerEntry {
+                addresses: vec![addr.clone()],
+                last_seen,
+                provider_record: None,
+            };
+            store.put(&peer_id, &entry).unwrap();
+        }
+
+        let book = PeerBook::load(store.clone(), TTL, MAX_PEERS).unwrap();
+
+        assert_eq!(book.addresses(), vec![(fresh, addr)]);
+
+        let stored = store.list().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].0, fresh);
+    }
+
+    #[test]
+    fn provider_records_survive_reload() {
+        let store = MemoryPeerStore::default();
+        let key = Keypair::generate_ed25519();
+        let signed = ProviderRecord::signed(&key, vec![]).unwrap();
+
+        let mut book = PeerBook::load(store.clone(), TTL, MAX_PEERS).unwrap();
+        book.set_provider_record(&signed);
+        book.flush().unwrap();
+
+        let book = PeerBook::load(store, TTL, MAX_PEERS).unwrap();
+        let records = book.provider_records();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record(), signed.record());
+    }
+
+    #[test]
+    fn changes_are_written_on_flush() {
+        let store = MemoryPeerStore::default();
+        let peer_id = PeerId::random();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+
+        let mut book = PeerBook::load(store.clone(), TTL, MAX_PEERS).unwrap();
+        book.add_dialled_address(peer_id, addr.clone().with_p2p(peer_id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+
+        book.flush().unwrap();
+
+        let stored = store.list().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].0, peer_id);
+        // The peer ID is not part of the stored address.
+        assert_eq!(stored[0].1.addresses, vec![addr]);
+    }
+
+    #[test]
+    fn least_recently_seen_peers_are_evicted() {
+        let store = MemoryPeerStore::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+
+        let oldest = PeerId::random();
+        store
+            .put(
+                &oldest,
+                &PeerEntry {
+                    addresses: vec![addr.clone()],
+                    last_seen: Timestamp::now() - TTL / 2,
+                    provider_record: None,
+                },
+            )
+            .unwrap();
+
+        let mut book = PeerBook::load(store.clone(), TTL, 2).unwrap();
+        let newer = [PeerId::random(), PeerId::random()];
+        for peer_id in newer {
+            book.add_dialled_address(peer_id, addr.clone());
+        }
+        book.flush().unwrap();
+
+        assert_eq!(book.len(), 2);
+
+        let mut stored = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|(peer_id, _)| peer_id)
+            .collect::<Vec<_>>();
+        stored.sort();
+        let mut expected = newer.to_vec();
+        expected.sort();
+        assert_eq!(stored, expected);
+    }
+}
@@ -94,6 +94,11 @@ impl SubnetProviderCache {
         self.peer_timestamps.contains_key(peer_id)
     }
 
+    /// Timestamp of the last record received about a peer, if it's routable.
+    pub fn timestamp(&self, peer_id: &PeerId) -> Option<Timestamp> {
+        self.peer_timestamps.get(peer_id).copied()
+    }
+
     /// Try to add a provider to the cache.
     ///
     /// Returns `None` if the peer is not routable and nothing could be added.
@@ -9,7 +9,9 @@ use crate::behaviour::{
 };
 use crate::client::Client;
 use crate::observe;
-use crate::vote_record::{SignedVoteRecord, VoteRecord};
+use crate::peer_store::{PeerBook, PeerStore};
+use crate::provider_record::SignedProviderRecord;
+use crate::vote_record::SignedVoteRecord;
 use anyhow::anyhow;
 use bloom::{BloomFilter, ASMS};
 use ipc_api::subnet_id::SubnetID;
@@ -20,9 +22,15 @@ use libp2p::connection_limits::ConnectionLimits;
 use libp2p::futures::StreamExt;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{
-    core::{muxing::StreamMuxerBox, transport::Boxed},
+    core::{
+        muxing::StreamMuxerBox,
+        transport::{Boxed, ListenerId},
+        upgrade::SelectUpgrade,
+        ConnectedPoint,
+    },
     identity::Keypair,
-    noise, Multiaddr, PeerId, Swarm, Transport,
+    multiaddr::Protocol,
+    noise, yamux, Multiaddr, PeerId, Swarm, Transport,
 };
 use libp2p::{identify, ping};
 use libp2p_bitswap::{BitswapResponse, BitswapStore};
@@ -37,6 +45,9 @@ use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot::{self, Sender};
 
+/// How often the changes to the [`PeerBook`] are written to the [`PeerStore`].
+const PEER_BOOK_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Result of attempting to resolve a CID.
 pub type ResolveResult = anyhow::Result<()>;
 
@@ -76,6 +87,23 @@ pub struct ConnectionConfig {
     /// Maximum number of events in the push-based broadcast channel before a slow
     /// consumer gets an error because it's falling behind.
     pub event_buffer_capacity: u32,
+    /// Offer mplex as a fallback to yamux on TCP connections, for peers which don't support yamux.
+    pub mplex_fallback: bool,
+    /// Dial and accept QUIC connections besides TCP.
+    ///
+    /// Always enabled when listening on a QUIC address.
+    pub enable_quic: bool,
+}
+
+impl ConnectionConfig {
+    /// Whether the QUIC transport is needed, either explicitly or to listen on a `quic-v1` address.
+    pub fn use_quic(&self) -> bool {
+        self.enable_quic
+            || self
+                .listen_addr
+                .iter()
+                .any(|p| matches!(p, Protocol::QuicV1))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -106,7 +134,7 @@ pub(crate) enum Request<V> {
 #[derive(Clone, Debug)]
 pub enum Event<V> {
     /// Received a vote about in a subnet about a CID.
-    ReceivedVote(Box<VoteRecord<V>>),
+    ReceivedVote(Box<SignedVoteRecord<V>>),
     /// Received raw pre-emptive data published to a pinned subnet.
     ReceivedPreemptive(SubnetID, Vec<u8>),
 }
@@ -119,6 +147,8 @@ where
 {
     peer_id: PeerId,
     listen_addr: Multiaddr,
+    /// Set once the swarm started listening on the `listen_addr`.
+    listener_id: Option<ListenerId>,
     swarm: Swarm<Behaviour<P, V>>,
     /// To match finished queries to response channels.
     queries: QueryMap,
@@ -132,6 +162,10 @@ where
     background_lookup_filter: BloomFilter,
     /// To limit the number of peers contacted in a Bitswap resolution attempt.
     max_peers_per_query: usize,
+    /// Peers remembered across restarts, if persistence is enabled.
+    peer_book: Option<PeerBook>,
+    /// Provider records loaded from the [`PeerBook`], waiting for their peers to become routable.
+    pending_provider_records: HashMap<PeerId, SignedProviderRecord>,
 }
 
 impl<P, V> Service<P, V>
@@ -144,7 +178,10 @@ where
     where
         S: BitswapStore<Params = P>,
     {
-        Self::new_with_transport(config, store, build_transport)
+        let connection = config.connection.clone();
+        Self::new_with_transport(config, store, |local_key| {
+            build_transport(local_key, &connection)
+        })
     }
 
     /// Build a [`Service`] and a [`Client`] by passing in a transport factory function.
@@ -195,6 +232,7 @@ where
         let service = Self {
             peer_id,
             listen_addr: config.connection.listen_addr,
+            listener_id: None,
             swarm,
             queries: Default::default(),
             request_rx,
@@ -205,11 +243,43 @@ where
                 config.connection.expected_peer_count,
             ),
             max_peers_per_query: config.connection.max_peers_per_query as usize,
+            peer_book: None,
+            pending_provider_records: Default::default(),
         };
 
         Ok(service)
     }
 
+    /// Remember the addresses we dialled peers on and their provider records in a [`PeerStore`],
+    /// expiring them if we haven't heard from a peer for longer than the `ttl`, and keeping
+    /// at most `max_peers` of them.
+    ///
+    /// The addresses remembered from a previous run are only added to the routing table once
+    /// bootstrapping from the static addresses has finished, or used to bootstrap from if there
+    /// are no static addresses.
+    pub fn with_peer_store<S: PeerStore>(
+        mut self,
+        store: S,
+        ttl: Duration,
+        max_peers: usize,
+    ) -> anyhow::Result<Self> {
+        let book = PeerBook::load(store, ttl, max_peers)?;
+
+        info!("loaded {} peers from the peer store", book.len());
+
+        self.discovery_mut().add_known_addresses(book.addresses());
+
+        self.pending_provider_records = book
+            .provider_records()
+            .into_iter()
+            .map(|signed| (signed.record().peer_id, signed))
+            .collect();
+
+        self.peer_book = Some(book);
+
+        Ok(self)
+    }
+
     /// Create a new [`Client`] instance bound to this `Service`.
     ///
     /// The [`Client`] is geared towards request-response interactions,
@@ -258,11 +328,58 @@ where
         Ok(())
     }
 
+    /// Start the swarm listening for incoming connections and wait until the address is bound.
+    ///
+    /// Returns the actual address the swarm is listening on, which is useful when
+    /// the configured address leaves the port for the OS to pick, e.g. `/tcp/0`.
+    ///
+    /// Calling this is optional; [`Service::run`] starts listening if it hasn't been done yet.
+    pub async fn listen(&mut self) -> anyhow::Result<Multiaddr> {
+        if self.listener_id.is_some() {
+            return Ok(self.listen_addr.clone());
+        }
+        let listener_id = Swarm::listen_on(&mut self.swarm, self.listen_addr.clone())?;
+        self.listener_id = Some(listener_id);
+
+        loop {
+            match self.swarm.next().await {
+                Some(SwarmEvent::NewListenAddr {
+                    listener_id: id,
+                    address,
+                }) if id == listener_id => {
+                    self.listen_addr = address.clone();
+                    return Ok(address);
+                }
+                Some(SwarmEvent::ListenerClosed {
+                    listener_id: id,
+                    reason,
+                    ..
+                }) if id == listener_id => {
+                    return Err(anyhow!("listener closed: {reason:?}"));
+                }
+                Some(SwarmEvent::ListenerError {
+                    listener_id: id,
+                    error,
+                }) if id == listener_id => {
+                    return Err(anyhow!("listener error: {error}"));
+                }
+                Some(SwarmEvent::Behaviour(event)) => self.handle_behaviour_event(event),
+                Some(_) => {}
+                None => return Err(anyhow!("swarm closed before listening")),
+            }
+        }
+    }
+
     /// Start the swarm listening for incoming connections and drive the events forward.
     pub async fn run(mut self) -> anyhow::Result<()> {
         // Start the swarm.
         info!("running service on {}", self.listen_addr);
-        Swarm::listen_on(&mut self.swarm, self.listen_addr.clone())?;
+        if self.listener_id.is_none() {
+            let listener_id = Swarm::listen_on(&mut self.swarm, self.listen_addr.clone())?;
+            self.listener_id = Some(listener_id);
+        }
+
+        let mut flush_interval = tokio::time::interval(PEER_BOOK_FLUSH_INTERVAL);
 
         loop {
             select! {
@@ -271,6 +388,16 @@ where
                     Some(SwarmEvent::Behaviour(event)) => {
                         self.handle_behaviour_event(event)
                     },
+                    // Only remember addresses we could actually reach the peer on.
+                    Some(SwarmEvent::ConnectionEstablished {
+                        peer_id,
+                        endpoint: ConnectedPoint::Dialer { address, .. },
+                        ..
+                    }) => {
+                        if let Some(book) = self.peer_book.as_mut() {
+                            book.add_dialled_address(peer_id, address);
+                        }
+                    },
                     // Connection events are handled by the behaviours, passed directly from the Swarm.
                     Some(_) => { },
                     // The connection is closed.
@@ -282,12 +409,22 @@ where
                     // This shouldn't happen because the service has a copy of the sender.
                     // All Client instances have been dropped.
                     None => { break; }
-                }
+                },
+                _ = flush_interval.tick() => self.flush_peer_book(),
             };
         }
+        self.flush_peer_book();
         Ok(())
     }
 
+    fn flush_peer_book(&mut self) {
+        if let Some(book) = self.peer_book.as_mut() {
+            if let Err(e) = book.flush() {
+                warn!("failed to write the peer book to the store: {e}")
+            }
+        }
+    }
+
     /// Handle events that the [`NetworkBehaviour`] macro generated for our [`Behaviour`], one for each field.
     fn handle_behaviour_event(&mut self, event: BehaviourEvent<P, V>) {
         match event {
@@ -337,7 +474,10 @@ where
         match event {
             discovery::Event::Added(peer_id) => {
                 debug!("adding routable peer {peer_id} to {}", self.peer_id);
-                self.membership_mut().set_routable(peer_id)
+                self.membership_mut().set_routable(peer_id);
+                if let Some(signed) = self.pending_provider_records.remove(&peer_id) {
+                    self.membership_mut().add_provider_record(signed)
+                }
             }
             discovery::Event::Removed(peer_id) => {
                 debug!("removing unroutable peer {peer_id} from {}", self.peer_id);
@@ -377,6 +517,11 @@ where
                     debug!("dropped received preemptive data because there are no subscribers")
                 }
             }
+            membership::Event::Accepted(signed) => {
+                if let Some(book) = self.peer_book.as_mut() {
+                    book.set_provider_record(&signed);
+                }
+            }
         }
     }
 
@@ -560,23 +705,50 @@ fn send_resolve_result(tx: Sender<ResolveResult>, res: ResolveResult) {
 
 /// Builds the transport stack that libp2p will communicate over.
 ///
+/// Connections over TCP are multiplexed with yamux, falling back to mplex if enabled.
+/// QUIC is added on top if the config calls for it.
+///
 /// Based on the equivalent in Forest.
-pub fn build_transport(local_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)> {
+pub fn build_transport(
+    local_key: Keypair,
+    config: &ConnectionConfig,
+) -> Boxed<(PeerId, StreamMuxerBox)> {
     let tcp_transport =
         || libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::new().nodelay(true));
     let transport = libp2p::dns::tokio::Transport::system(tcp_transport()).unwrap();
     let auth_config = noise::Config::new(&local_key).expect("Noise key generation failed");
+    let yamux_config = yamux::Config::default();
 
-    let mplex_config = {
-        let mut mplex_config = MplexConfig::new();
-        mplex_config.set_max_buffer_size(usize::MAX);
-        mplex_config
+    let transport = transport
+        .upgrade(libp2p::core::upgrade::Version::V1)
+        .authenticate(auth_config);
+
+    let transport = if config.mplex_fallback {
+        let mplex_config = {
+            let mut mplex_config = MplexConfig::new();
+            mplex_config.set_max_buffer_size(usize::MAX);
+            mplex_config
+        };
+        // Yamux is listed first, so it is preferred by peers supporting both.
+        transport
+            .multiplex(SelectUpgrade::new(yamux_config, mplex_config))
+            .timeout(Duration::from_secs(20))
+            .boxed()
+    } else {
+        transport
+            .multiplex(yamux_config)
+            .timeout(Duration::from_secs(20))
+            .boxed()
     };
 
-    transport
-        .upgrade(libp2p::core::upgrade::Version::V1)
-        .authenticate(auth_config)
-        .multiplex(mplex_config)
-        .timeout(Duration::from_secs(20))
+    if !config.use_quic() {
+        return transport;
+    }
+
+    // QUIC comes with its own encryption and multiplexing.
+    libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(&local_key))
+        .map(|(peer_id, conn), _| (peer_id, StreamMuxerBox::new(conn)))
+        .or_transport(transport)
+        .map(|either, _| either.into_inner())
         .boxed()
 }
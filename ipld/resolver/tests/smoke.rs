@@ -18,7 +18,10 @@
 // (although these might be orthogonal).
 
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
@@ -37,10 +40,14 @@ use libp2p::{
     core::{
         muxing::StreamMuxerBox,
         transport::{Boxed, MemoryTransport},
+        upgrade::SelectUpgrade,
     },
+    futures::{future::Either, StreamExt},
     identity::Keypair,
     multiaddr::Protocol,
-    plaintext, yamux, Multiaddr, PeerId, Transport,
+    noise, plaintext,
+    swarm::{dummy, SwarmEvent},
+    yamux, Multiaddr, PeerId, Swarm, Transport,
 };
 use multihash_codetable::{Code, MultihashDigest};
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -57,6 +64,8 @@ struct TestVote(Cid);
 
 struct Agent {
     config: Config,
+    /// The address the service actually listens on.
+    listen_addr: Multiaddr,
     client: Client<TestVote>,
     events: broadcast::Receiver<Event<TestVote>>,
     store: TestBlockstore,
@@ -66,6 +75,17 @@ struct Cluster {
     agents: Vec<Agent>,
 }
 
+/// The transport the agents of a cluster connect over.
+#[derive(Clone, Copy, Debug)]
+enum TestTransport {
+    /// In-memory transport, for speed.
+    Memory,
+    /// The production TCP stack on the loopback interface.
+    Tcp { mplex_fallback: bool },
+    /// The production stack listening on QUIC on the loopback interface.
+    Quic,
+}
+
 impl Cluster {
     pub fn size(&self) -> usize {
         self.agents.len()
@@ -82,23 +102,25 @@ impl Cluster {
 
 struct ClusterBuilder {
     size: u32,
+    transport: TestTransport,
     rng: StdRng,
     services: Vec<Service<TestStoreParams, TestVote>>,
     agents: Vec<Agent>,
 }
 
 impl ClusterBuilder {
-    fn new(size: u32) -> Self {
+    fn new(size: u32, transport: TestTransport) -> Self {
         // Each port has to be unique, so each test must use a different seed.
         // This is shared between all instances.
         static COUNTER: AtomicU64 = AtomicU64::new(0);
         let seed = COUNTER.fetch_add(1, Ordering::Relaxed);
-        Self::new_with_seed(size, seed)
+        Self::new_with_seed(size, transport, seed)
     }
 
-    fn new_with_seed(size: u32, seed: u64) -> Self {
+    fn new_with_seed(size: u32, transport: TestTransport, seed: u64) -> Self {
         Self {
             size,
+            transport,
             rng: rand::rngs::StdRng::seed_from_u64(seed),
             services: Default::default(),
             agents: Default::default(),
@@ -106,21 +128,21 @@ impl ClusterBuilder {
     }
 
     /// Add a node with randomized address, optionally bootstrapping from an existing node.
-    fn add_node(&mut self, bootstrap: Option<usize>) {
+    async fn add_node(&mut self, bootstrap: Option<usize>) {
         let bootstrap_addr = bootstrap.map(|i| {
-            let config = &self.agents[i].config;
-            let peer_id = config.network.local_peer_id();
-            let mut addr = config.connection.listen_addr.clone();
-            addr.push(Protocol::P2p(peer_id));
-            addr
+            let agent = &self.agents[i];
+            let peer_id = agent.config.network.local_peer_id();
+            agent.listen_addr.clone().with(Protocol::P2p(peer_id))
         });
-        let config = make_config(&mut self.rng, self.size, bootstrap_addr);
-        let (service, store) = make_service(config.clone());
+        let config = make_config(&mut self.rng, self.size, self.transport, bootstrap_addr);
+        let (mut service, store) = make_service(config.clone(), self.transport);
+        let listen_addr = service.listen().await.expect("failed to listen");
         let client = service.client();
         let events = service.subscribe();
         self.services.push(service);
         self.agents.push(Agent {
             config,
+            listen_addr,
             client,
             events,
             store,
@@ -210,8 +232,253 @@ async fn single_bootstrap_single_provider_resolve_one() {
 async fn single_bootstrap_publish_receive_vote() {
     init_log();
 
-    let mut cluster = make_cluster_with_bootstrap(2, 0).await;
+    let cluster = make_cluster_with_bootstrap(2, 0).await;
+    publish_receive_vote(cluster).await;
+}
+
+/// Same as [single_bootstrap_publish_receive_vote] over TCP, negotiating yamux.
+#[tokio::test]
+async fn single_bootstrap_publish_receive_vote_over_tcp() {
+    init_log();
+
+    let transport = TestTransport::Tcp {
+        mplex_fallback: false,
+    };
+    let cluster = make_cluster_with_transport(2, 0, transport).await;
+    publish_receive_vote(cluster).await;
+}
+
+/// Same as [single_bootstrap_publish_receive_vote] over TCP, offering mplex besides yamux.
+#[tokio::test]
+async fn single_bootstrap_publish_receive_vote_over_tcp_with_mplex_fallback() {
+    init_log();
+
+    let transport = TestTransport::Tcp {
+        mplex_fallback: true,
+    };
+    let cluster = make_cluster_with_transport(2, 0, transport).await;
+    publish_receive_vote(cluster).await;
+}
+
+/// Same as [single_bootstrap_publish_receive_vote] over QUIC, selected by the listen address.
+#[tokio::test]
+async fn single_bootstrap_publish_receive_vote_over_quic() {
+    init_log();
+
+    let cluster = make_cluster_with_transport(2, 0, TestTransport::Quic).await;
+    publish_receive_vote(cluster).await;
+}
+
+/// A peer offering only yamux negotiates it, whichever side dials.
+#[tokio::test]
+async fn tcp_negotiates_yamux_without_mplex_fallback() {
+    init_log();
+
+    for service_dials in [false, true] {
+        let muxer = negotiate_muxer(&[TestMuxer::Yamux], false, service_dials).await;
+        assert_eq!(
+            muxer,
+            Some(TestMuxer::Yamux),
+            "service_dials={service_dials}"
+        );
+    }
+}
+
+/// A peer offering both muxers negotiates yamux, even if the service falls back to mplex.
+#[tokio::test]
+async fn tcp_prefers_yamux_over_mplex_fallback() {
+    init_log();
+
+    for service_dials in [false, true] {
+        let muxer =
+            negotiate_muxer(&[TestMuxer::Yamux, TestMuxer::Mplex], true, service_dials).await;
+        assert_eq!(
+            muxer,
+            Some(TestMuxer::Yamux),
+            "service_dials={service_dials}"
+        );
+    }
+}
+
+/// A peer offering only mplex negotiates it if the service has the fallback enabled.
+#[tokio::test]
+async fn tcp_negotiates_mplex_fallback() {
+    init_log();
+
+    for service_dials in [false, true] {
+        let muxer = negotiate_muxer(&[TestMuxer::Mplex], true, service_dials).await;
+        assert_eq!(
+            muxer,
+            Some(TestMuxer::Mplex),
+            "service_dials={service_dials}"
+        );
+    }
+}
+
+/// A peer offering only mplex cannot connect if the service has the fallback disabled.
+#[tokio::test]
+async fn tcp_rejects_mplex_without_fallback() {
+    init_log();
+
+    for service_dials in [false, true] {
+        let muxer = negotiate_muxer(&[TestMuxer::Mplex], false, service_dials).await;
+        assert_eq!(muxer, None, "service_dials={service_dials}");
+    }
+}
+
+/// Connect a [TestPeer] offering the given muxers with a [Service] running the production TCP stack,
+/// returning the muxer they negotiated, or `None` if they failed to connect.
+async fn negotiate_muxer(
+    offered: &[TestMuxer],
+    mplex_fallback: bool,
+    service_dials: bool,
+) -> Option<TestMuxer> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let transport = TestTransport::Tcp { mplex_fallback };
+    let mut peer = TestPeer::new(offered);
+
+    let bootstrap_addr = if service_dials {
+        Some(peer.listen().await)
+    } else {
+        None
+    };
+
+    let config = make_config(&mut rng, 2, transport, bootstrap_addr);
+    let service_id = config.network.local_peer_id();
+    let (mut service, _) = make_service(config, transport);
+    let service_addr = service.listen().await.expect("failed to listen");
+
+    tokio::task::spawn(async move { service.run().await.expect("error running service") });
+
+    if !service_dials {
+        peer.dial(service_addr.with(Protocol::P2p(service_id)));
+    }
+
+    peer.await_connection(service_id).await
+}
+
+/// The stream multiplexers a [TestPeer] can offer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TestMuxer {
+    Yamux,
+    Mplex,
+}
+
+/// A bare swarm over TCP offering a fixed set of muxers,
+/// recording which one got negotiated with each peer it connects to.
+struct TestPeer {
+    swarm: Swarm<dummy::Behaviour>,
+    negotiated: Arc<Mutex<Vec<(PeerId, TestMuxer)>>>,
+}
+
+impl TestPeer {
+    fn new(offered: &[TestMuxer]) -> Self {
+        let local_key = Keypair::generate_secp256k1();
+        let peer_id = local_key.public().to_peer_id();
+        let negotiated = Arc::new(Mutex::new(Vec::new()));
+
+        let record = {
+            let negotiated = negotiated.clone();
+            move |peer_id: PeerId, muxer: TestMuxer| {
+                negotiated.lock().unwrap().push((peer_id, muxer))
+            }
+        };
+
+        let transport = libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default())
+            .upgrade(libp2p::core::upgrade::Version::V1)
+            .authenticate(noise::Config::new(&local_key).expect("Noise key generation failed"));
+
+        let transport = match offered {
+            [TestMuxer::Yamux] => transport
+                .multiplex(yamux::Config::default())
+                .map(move |(peer_id, muxer), _| {
+                    record(peer_id, TestMuxer::Yamux);
+                    (peer_id, StreamMuxerBox::new(muxer))
+                })
+                .boxed(),
+            [TestMuxer::Mplex] => transport
+                .multiplex(libp2p_mplex::MplexConfig::new())
+                .map(move |(peer_id, muxer), _| {
+                    record(peer_id, TestMuxer::Mplex);
+                    (peer_id, StreamMuxerBox::new(muxer))
+                })
+                .boxed(),
+            [TestMuxer::Yamux, TestMuxer::Mplex] => transport
+                .multiplex(SelectUpgrade::new(
+                    yamux::Config::default(),
+                    libp2p_mplex::MplexConfig::new(),
+                ))
+                .map(move |(peer_id, muxer), _| {
+                    let negotiated = match muxer {
+                        Either::Left(_) => TestMuxer::Yamux,
+                        Either::Right(_) => TestMuxer::Mplex,
+                    };
+                    record(peer_id, negotiated);
+                    (peer_id, StreamMuxerBox::new(muxer))
+                })
+                .boxed(),
+            other => panic!("unexpected muxer offer: {other:?}"),
+        };
+
+        let swarm = Swarm::new(
+            transport,
+            dummy::Behaviour,
+            peer_id,
+            libp2p::swarm::Config::with_tokio_executor(),
+        );
+
+        Self { swarm, negotiated }
+    }
 
+    /// Listen on a port picked by the OS and return the full address of the peer.
+    async fn listen(&mut self) -> Multiaddr {
+        let addr = Multiaddr::from(Protocol::Ip4([127, 0, 0, 1].into())).with(Protocol::Tcp(0));
+        self.swarm.listen_on(addr).expect("failed to listen");
+        loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = self.swarm.select_next_some().await {
+                return address.with(Protocol::P2p(*self.swarm.local_peer_id()));
+            }
+        }
+    }
+
+    fn dial(&mut self, addr: Multiaddr) {
+        self.swarm.dial(addr).expect("failed to dial");
+    }
+
+    /// Drive the swarm until the first connection attempt with the peer succeeds or fails,
+    /// returning the muxer negotiated with it, if any.
+    async fn await_connection(&mut self, peer_id: PeerId) -> Option<TestMuxer> {
+        let connected = timeout(Duration::from_secs(5), async {
+            loop {
+                match self.swarm.select_next_some().await {
+                    SwarmEvent::ConnectionEstablished { peer_id: id, .. } if id == peer_id => {
+                        return true
+                    }
+                    SwarmEvent::IncomingConnectionError { .. }
+                    | SwarmEvent::OutgoingConnectionError { .. } => return false,
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .expect("timeout connecting");
+
+        if !connected {
+            return None;
+        }
+
+        let negotiated = self.negotiated.lock().unwrap();
+        let muxer = negotiated
+            .iter()
+            .find(|(id, _)| *id == peer_id)
+            .map(|(_, muxer)| *muxer);
+
+        assert!(muxer.is_some(), "connection established without a muxer");
+        muxer
+    }
+}
+
+async fn publish_receive_vote(mut cluster: Cluster) {
     // Announce the support of some subnet.
     let subnet_id = make_subnet_id(1001);
 
@@ -245,7 +512,7 @@ async fn single_bootstrap_publish_receive_vote() {
         .expect("error receiving vote");
 
     if let Event::ReceivedVote(v) = event {
-        assert_eq!(&*v, vote.record());
+        assert_eq!(v.record(), vote.record());
     } else {
         panic!("unexpected {event:?}")
     }
@@ -293,19 +560,29 @@ async fn single_bootstrap_publish_receive_preemptive() {
 #[tokio::test]
 async fn can_register_metrics() {
     let mut rng = rand::rngs::StdRng::seed_from_u64(0);
-    let config = make_config(&mut rng, 1, None);
-    let (mut service, _) = make_service(config);
+    let config = make_config(&mut rng, 1, TestTransport::Memory, None);
+    let (mut service, _) = make_service(config, TestTransport::Memory);
     let registry = prometheus::Registry::new();
     service.register_metrics(&registry).unwrap();
 }
 
 async fn make_cluster_with_bootstrap(cluster_size: u32, bootstrap_idx: usize) -> Cluster {
+    make_cluster_with_transport(cluster_size, bootstrap_idx, TestTransport::Memory).await
+}
+
+async fn make_cluster_with_transport(
+    cluster_size: u32,
+    bootstrap_idx: usize,
+    transport: TestTransport,
+) -> Cluster {
     // TODO: Get the seed from QuickCheck
-    let mut builder = ClusterBuilder::new(cluster_size);
+    let mut builder = ClusterBuilder::new(cluster_size, transport);
 
     // Build a cluster of nodes.
     for i in 0..builder.size {
-        builder.add_node(if i == 0 { None } else { Some(bootstrap_idx) });
+        builder
+            .add_node(if i == 0 { None } else { Some(bootstrap_idx) })
+            .await;
     }
 
     // Start the swarms.
@@ -314,21 +591,49 @@ async fn make_cluster_with_bootstrap(cluster_size: u32, bootstrap_idx: usize) ->
     cluster
 }
 
-fn make_service(config: Config) -> (Service<TestStoreParams, TestVote>, TestBlockstore) {
+fn make_service(
+    config: Config,
+    transport: TestTransport,
+) -> (Service<TestStoreParams, TestVote>, TestBlockstore) {
     let store = TestBlockstore::default();
-    let svc = Service::new_with_transport(config, store.clone(), build_transport).unwrap();
-    (svc, store)
+    let svc = match transport {
+        TestTransport::Memory => {
+            Service::new_with_transport(config, store.clone(), build_memory_transport)
+        }
+        TestTransport::Tcp { .. } | TestTransport::Quic => Service::new(config, store.clone()),
+    };
+    (svc.unwrap(), store)
 }
 
-fn make_config(rng: &mut StdRng, cluster_size: u32, bootstrap_addr: Option<Multiaddr>) -> Config {
+fn make_config(
+    rng: &mut StdRng,
+    cluster_size: u32,
+    transport: TestTransport,
+    bootstrap_addr: Option<Multiaddr>,
+) -> Config {
+    let localhost = Multiaddr::from(Protocol::Ip4([127, 0, 0, 1].into()));
+
+    let (listen_addr, mplex_fallback) = match transport {
+        TestTransport::Memory => (Multiaddr::from(Protocol::Memory(rng.gen::<u64>())), true),
+        // Let the OS pick the port; the actual address is read back after listening.
+        TestTransport::Tcp { mplex_fallback } => (localhost.with(Protocol::Tcp(0)), mplex_fallback),
+        TestTransport::Quic => {
+            let addr = localhost.with(Protocol::Udp(0)).with(Protocol::QuicV1);
+            (addr, false)
+        }
+    };
+
     let config = Config {
         connection: ConnectionConfig {
-            listen_addr: Multiaddr::from(Protocol::Memory(rng.gen::<u64>())),
+            listen_addr,
             external_addresses: vec![],
             expected_peer_count: cluster_size,
             max_incoming: cluster_size,
             max_peers_per_query: cluster_size,
             event_buffer_capacity: cluster_size,
+            mplex_fallback,
+            // Enabled by the listen address.
+            enable_quic: false,
         },
         network: NetworkConfig {
             local_key: Keypair::generate_secp256k1(),
@@ -356,7 +661,7 @@ fn make_config(rng: &mut StdRng, cluster_size: u32, bootstrap_addr: Option<Multi
 }
 
 /// Builds an in-memory transport for libp2p to communicate over.
-fn build_transport(local_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)> {
+fn build_memory_transport(local_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)> {
     let auth_config = plaintext::Config::new(&local_key);
 
     let mplex_config = {